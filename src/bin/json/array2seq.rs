@@ -0,0 +1,65 @@
+use crate::{located, read_bytes, Source};
+use anyhow::{bail, Result};
+use grammar::json::{for_each_element_from_reader, JsonError};
+use std::{
+    ffi::OsString,
+    io::{self, BufWriter, Write},
+    ops::ControlFlow,
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json array2seq [FILE]
+
+Read a document whose top level is an array from FILE, or standard input
+when FILE is `-` or missing, and print each element as compact JSON on a
+line of its own: newline-delimited JSON.
+
+options:
+  -h, --help  show this help";
+
+pub struct Options {
+    input: Source,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut input = None;
+        for arg in args {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    // kept whole only to put errors at a line and column
+    let (name, input) = read_bytes(&options.input)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut written = Ok(());
+    let read = for_each_element_from_reader(&input[..], |_, value| {
+        written = value
+            .to_writer(&mut out)
+            .and_then(|()| out.write_all(b"\n"));
+        match written {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    });
+    written?;
+    out.flush()?;
+    read.map_err(|e| match e {
+        JsonError::Element { index, source } => {
+            located(*source, &name, &input).context(format!("element {}", index))
+        }
+        e => located(e, &name, &input),
+    })?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,111 @@
+use crate::{read_value, Source};
+use anyhow::{anyhow, bail, Result};
+use grammar::json::{diff, patch_to_json, FormatOptions, JsonValue, PatchOp};
+use std::{
+    ffi::OsString,
+    io::{self, BufWriter, IsTerminal, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json diff [OPTIONS] A B
+
+Compare the documents in files A and B, either of them `-` for standard
+input, ignoring formatting, key order and number spelling, and list the
+paths added (+), removed (-) and changed (~) going from A to B. The report
+is colored when standard output is a terminal.
+
+options:
+  --patch      print an RFC 6902 JSON Patch turning A into B instead
+  --exit-code  exit with 1 when the documents differ, 0 when they do not
+  -h, --help   show this help";
+
+pub struct Options {
+    a: Source,
+    b: Source,
+    patch: bool,
+    exit_code: bool,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut inputs = Vec::new();
+        let mut patch = false;
+        let mut exit_code = false;
+        for arg in args {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--patch") => patch = true,
+                Some("--exit-code") => exit_code = true,
+                Some("-") => inputs.push(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ => inputs.push(Source::File(arg.into())),
+            }
+        }
+        let [a, b]: [Source; 2] = inputs
+            .try_into()
+            .map_err(|_| anyhow!("diff needs two inputs"))?;
+        if matches!((&a, &b), (Source::Stdin, Source::Stdin)) {
+            bail!("only one input can be standard input");
+        }
+        Ok(Some(Options {
+            a,
+            b,
+            patch,
+            exit_code,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let a = read_value(&options.a)?;
+    let b = read_value(&options.b)?;
+    let patch = diff(&a, &b);
+    let mut out = BufWriter::new(io::stdout().lock());
+    if options.patch {
+        let format = FormatOptions::pretty()
+            .sort_keys(true)
+            .trailing_newline(true);
+        patch_to_json(&patch).to_writer_with(&mut out, &format)?;
+    } else {
+        let color = io::stdout().is_terminal();
+        for op in &patch {
+            writeln!(out, "{}", report_line(op, &a, color))?;
+        }
+    }
+    out.flush()?;
+    Ok(if options.exit_code && !patch.is_empty() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// One line of the report: `+ PATH: NEW`, `- PATH: OLD` or
+/// `~ PATH: OLD -> NEW`, values as compact JSON and the whole document
+/// named `(root)`.
+fn report_line(op: &PatchOp, a: &JsonValue, color: bool) -> String {
+    let old = |path: &str| {
+        a.pointer(path)
+            .map_or_else(String::new, JsonValue::to_json_string)
+    };
+    let path = match op.path() {
+        "" => "(root)",
+        path => path,
+    };
+    let (sign, ansi, text) = match op {
+        PatchOp::Add { value, .. } => ('+', "32", format!("{}: {}", path, value.to_json_string())),
+        PatchOp::Remove { path: at } => ('-', "31", format!("{}: {}", path, old(at))),
+        PatchOp::Replace { path: at, value } => (
+            '~',
+            "33",
+            format!("{}: {} -> {}", path, old(at), value.to_json_string()),
+        ),
+        // diff only produces the three above
+        _ => ('?', "0", path.to_string()),
+    };
+    if color {
+        format!("\x1b[{}m{} {}\x1b[0m", ansi, sign, text)
+    } else {
+        format!("{} {}", sign, text)
+    }
+}
@@ -0,0 +1,384 @@
+use crate::{no_color, read_value, replace, value, write_value, Color, FormatFlags, Source};
+use anyhow::{anyhow, bail, Context, Result};
+use grammar::json::{
+    apply_patch, parse_json, parse_json_spanned, ArrayWriter, FormatOptions, JsonError, JsonObject,
+    JsonValue, JsonWriter, MaybeGzip, Member, ObjectWriter, PatchOp, Pointer, Spanned,
+    SpannedValue,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+pub const SET_USAGE: &str = "usage: json set [OPTIONS] FILE POINTER VALUE
+
+Put VALUE, parsed as JSON, at the JSON Pointer POINTER in the document in
+FILE, standard input when FILE is `-`, and print the result. POINTER may
+name a new member of an object, or `-` to append to an array. With --write,
+members keep the order FILE has them in, and new ones come after, sorted,
+unless --sort-keys is given.
+
+options:
+  --string          take VALUE as a string rather than as JSON
+  --create-parents  make missing objects on the way to POINTER
+  --write           rewrite FILE in place instead of printing
+  --minify, --indent N, --tab, --sort-keys, --color WHEN
+                    format the result as the json command does
+  -h, --help        show this help";
+
+pub const DELETE_USAGE: &str = "usage: json delete [OPTIONS] FILE POINTER
+
+Remove the member or element at the JSON Pointer POINTER from the document
+in FILE, standard input when FILE is `-`, and print the result. With
+--write, the other members keep the order FILE has them in, unless
+--sort-keys is given.
+
+options:
+  --write     rewrite FILE in place instead of printing
+  --minify, --indent N, --tab, --sort-keys, --color WHEN
+              format the result as the json command does
+  -h, --help  show this help";
+
+pub struct Options {
+    input: Source,
+    pointer: Pointer,
+    edit: Edit,
+    write: bool,
+    format: FormatOptions,
+    color: Color,
+}
+
+enum Edit {
+    Set {
+        value: JsonValue,
+        create_parents: bool,
+    },
+    Delete,
+}
+
+impl Options {
+    pub fn parse_set(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        Self::parse(args, true)
+    }
+
+    pub fn parse_delete(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        Self::parse(args, false)
+    }
+
+    /// The options of `set`, or of `delete` when `set` is false.
+    fn parse(args: impl Iterator<Item = OsString>, set: bool) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut positional = Vec::new();
+        let mut string = false;
+        let mut create_parents = false;
+        let mut write = false;
+        let mut format = FormatFlags::default();
+        let mut color = Color::default();
+        while let Some(arg) = args.next() {
+            if format.take(&arg, &mut args)? {
+                continue;
+            }
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some(flag @ "--color") => color = Color::parse(&value(flag, &mut args)?)?,
+                Some("--write") => write = true,
+                Some("--string") if set => string = true,
+                Some("--create-parents") if set => create_parents = true,
+                // a pointer starts with `/`, so anything else is a flag
+                Some(flag) if flag.starts_with('-') && flag != "-" => {
+                    bail!("unknown option {}", flag)
+                }
+                _ => positional.push(arg),
+            }
+        }
+        let wanted = if set { 3 } else { 2 };
+        if positional.len() != wanted {
+            bail!(
+                "{} needs FILE, POINTER{}",
+                if set { "set" } else { "delete" },
+                if set { " and VALUE" } else { "" }
+            );
+        }
+        let mut positional = positional.into_iter();
+        let input = match positional.next() {
+            Some(arg) if arg == "-" => Source::Stdin,
+            Some(arg) => Source::File(arg.into()),
+            None => unreachable!("counted above"),
+        };
+        if write && matches!(input, Source::Stdin) {
+            bail!("--write needs a FILE, not standard input");
+        }
+        let text = utf8(positional.next(), "POINTER")?;
+        let pointer =
+            Pointer::parse(&text).map_err(|e| anyhow!("invalid pointer {:?}: {}", text, e))?;
+        let edit = if set {
+            let text = utf8(positional.next(), "VALUE")?;
+            let value = if string {
+                JsonValue::from(text)
+            } else {
+                parse_json(&text)
+                    .map_err(|e| anyhow!("VALUE is not JSON ({}); use --string for a string", e))?
+            };
+            Edit::Set {
+                value,
+                create_parents,
+            }
+        } else {
+            if pointer.is_root() {
+                bail!("cannot delete the whole document");
+            }
+            Edit::Delete
+        };
+        Ok(Some(Options {
+            input,
+            pointer,
+            edit,
+            write,
+            format: format.options()?,
+            color,
+        }))
+    }
+}
+
+fn utf8(arg: Option<OsString>, what: &str) -> Result<String> {
+    let arg = arg.expect("counted when parsing");
+    arg.into_string()
+        .map_err(|_| anyhow!("{} needs to be UTF-8", what))
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    match &options.input {
+        // rewriting would replace the compressed file with plain JSON
+        Source::File(path) if options.write && is_gzip(path) => {
+            bail!("cannot --write {}: it is gzip-compressed", path.display())
+        }
+        _ => {}
+    }
+    let mut value = read_value(&options.input)?;
+    match &options.edit {
+        Edit::Set {
+            value: new,
+            create_parents,
+        } => set(&mut value, &options.pointer, new.clone(), *create_parents)?,
+        Edit::Delete => {
+            let op = PatchOp::Remove {
+                path: options.pointer.to_string(),
+            };
+            apply_patch(&mut value, &[op])
+                .map_err(|_| anyhow!("cannot delete {}: nothing there", options.pointer))?;
+        }
+    }
+    match &options.input {
+        Source::File(path) if options.write => {
+            // the tree has lost the order of the members, which the file still has
+            let original = fs::read_to_string(path)
+                .ok()
+                .and_then(|text| parse_json_spanned(text.trim_start_matches('\u{feff}')).ok());
+            let formatted = match original {
+                Some(mut original) if !options.format.sort_keys => {
+                    forget(&mut original.value, &options.pointer, &options.edit);
+                    in_order(&value, &original.value, &options.format)?
+                }
+                _ => value.try_to_vec_with(&options.format)?,
+            };
+            replace(path, &formatted)
+                .with_context(|| format!("cannot write {}", path.display()))?;
+        }
+        _ => {
+            let mut out = BufWriter::new(io::stdout().lock());
+            let color = options
+                .color
+                .enabled(io::stdout().is_terminal(), no_color());
+            write_value(&mut out, &value, &options.format, color)?;
+            out.flush()?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Put `new` at `pointer`, explaining what is in the way if there is no
+/// such place.
+fn set(value: &mut JsonValue, pointer: &Pointer, new: JsonValue, create: bool) -> Result<()> {
+    let parent = pointer.parent().unwrap_or_default();
+    if create {
+        value
+            .ensure_path(&parent)
+            .map_err(|e| anyhow!("cannot set {}: {}", pointer, e))?;
+    }
+    if value.set_pointer(pointer, new) {
+        return Ok(());
+    }
+    let at = if parent.is_root() {
+        "the document".to_string()
+    } else {
+        parent.to_string()
+    };
+    let why = match value.pointer(&parent) {
+        None => format!("{} does not exist; --create-parents makes it", at),
+        Some(JsonValue::Array(arr)) => format!(
+            "{} is an array of {}, with no index {:?}",
+            at,
+            arr.len(),
+            pointer.tokens().last().map_or("", String::as_str)
+        ),
+        Some(other) => format!("{} is {}, not a container", at, kind(other)),
+    };
+    Err(anyhow!("cannot set {}: {}", pointer, why))
+}
+
+/// Make `original`, the document before the edit, match it where it made
+/// `pointer` something new: a removed member or element goes, and the one
+/// put there is forgotten.
+fn forget(original: &mut SpannedValue, pointer: &Pointer, edit: &Edit) {
+    let Some((last, parents)) = pointer.tokens().split_last() else {
+        *original = SpannedValue::Null;
+        return;
+    };
+    let mut at = original;
+    for token in parents {
+        let next = match at {
+            SpannedValue::Object(members) => {
+                // the last of duplicate members is the one parsed
+                members.iter_mut().rev().find(|m| m.key.value == *token)
+            }
+            .map(|m| &mut m.value.value),
+            SpannedValue::Array(items) => token
+                .parse()
+                .ok()
+                .and_then(|i: usize| items.get_mut(i))
+                .map(|item| &mut item.value),
+            _ => None,
+        };
+        match next {
+            Some(next) => at = next,
+            // made by --create-parents, so there is nothing to forget
+            None => return,
+        }
+    }
+    let remove = matches!(edit, Edit::Delete);
+    match at {
+        SpannedValue::Object(members) if remove => members.retain(|m| m.key.value != *last),
+        SpannedValue::Object(members) => members
+            .iter_mut()
+            .filter(|m| m.key.value == *last)
+            .for_each(|m| m.value.value = SpannedValue::Null),
+        SpannedValue::Array(items) => match last.parse() {
+            Ok(i) if i < items.len() && remove => {
+                items.remove(i);
+            }
+            Ok(i) if i < items.len() => items[i].value = SpannedValue::Null,
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// `value` laid out as `options` say, but with the members of each object
+/// in the order `original` has them, and any it lacks after those, sorted.
+fn in_order(
+    value: &JsonValue,
+    original: &SpannedValue,
+    options: &FormatOptions,
+) -> Result<Vec<u8>, JsonError> {
+    // sorting only reaches the values written whole, which `original` lacks
+    let options = FormatOptions {
+        sort_keys: true,
+        ..options.clone()
+    };
+    let mut w = JsonWriter::new(Vec::new(), &options);
+    match (value, original) {
+        (JsonValue::Object(obj), SpannedValue::Object(members)) => {
+            object_in_order(w.begin_object()?, obj, members)?
+        }
+        (JsonValue::Array(arr), SpannedValue::Array(items)) => {
+            array_in_order(w.begin_array()?, arr, items)?
+        }
+        _ => w.value_ref(value)?,
+    }
+    w.finish()
+}
+
+fn object_in_order(
+    mut w: ObjectWriter<'_, Vec<u8>>,
+    obj: &JsonObject,
+    members: &[Member],
+) -> Result<(), JsonError> {
+    let originals: HashMap<&str, &SpannedValue> = members
+        .iter()
+        .map(|m| (m.key.value.as_str(), &m.value.value))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut keys: Vec<&str> = members
+        .iter()
+        .map(|m| m.key.value.as_str())
+        .filter(|key| obj.contains_key(*key) && seen.insert(*key))
+        .collect();
+    let mut added: Vec<&str> = obj
+        .keys()
+        .map(|key| &**key)
+        .filter(|key| !originals.contains_key(key))
+        .collect();
+    added.sort_unstable();
+    keys.extend(added);
+    for key in keys {
+        let value = &obj[key];
+        w.key(key)?;
+        match (value, originals.get(key)) {
+            (JsonValue::Object(obj), Some(SpannedValue::Object(members))) => {
+                object_in_order(w.begin_object()?, obj, members)?
+            }
+            (JsonValue::Array(arr), Some(SpannedValue::Array(items))) => {
+                array_in_order(w.begin_array()?, arr, items)?
+            }
+            _ => {
+                w.value_ref(value)?;
+            }
+        }
+    }
+    w.end()
+}
+
+fn array_in_order(
+    mut w: ArrayWriter<'_, Vec<u8>>,
+    arr: &[JsonValue],
+    items: &[Spanned<SpannedValue>],
+) -> Result<(), JsonError> {
+    for (i, value) in arr.iter().enumerate() {
+        match (value, items.get(i).map(|item| &item.value)) {
+            (JsonValue::Object(obj), Some(SpannedValue::Object(members))) => {
+                object_in_order(w.begin_object()?, obj, members)?
+            }
+            (JsonValue::Array(arr), Some(SpannedValue::Array(items))) => {
+                array_in_order(w.begin_array()?, arr, items)?
+            }
+            _ => {
+                w.value_ref(value)?;
+            }
+        }
+    }
+    w.end()
+}
+
+/// Whether the file at `path` starts like a gzip stream; a file that
+/// cannot be read is left for reading it to report.
+fn is_gzip(path: &Path) -> bool {
+    File::open(path)
+        .and_then(MaybeGzip::new)
+        .is_ok_and(|r| r.is_gzip())
+}
+
+fn kind(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "a boolean",
+        JsonValue::Number(_) => "a number",
+        JsonValue::String(_) => "a string",
+        JsonValue::Array(_) => "an array",
+        JsonValue::Object(_) => "an object",
+        _ => "a raw value",
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{no_color, read_value, value, write_value, Color, FormatFlags, Source};
+use anyhow::{bail, Result};
+use grammar::json::{flatten, unflatten, FlattenOptions, FormatOptions};
+use std::{
+    ffi::OsString,
+    io::{self, BufWriter, IsTerminal, Write},
+    process::ExitCode,
+};
+
+pub const FLATTEN_USAGE: &str = "usage: json flatten [OPTIONS] [FILE]
+
+Print the document in FILE, or standard input when FILE is `-` or missing,
+as one object with a member for each scalar, named by the path to it:
+{\"a\": {\"b\": [1]}} prints as {\"a.b.0\": 1}. Empty objects and arrays are
+kept as values.
+
+options:
+  --sep S           put S between the keys of a path instead of `.`
+  --bracket-arrays  name array elements like a.b[0]
+  --minify, --indent N, --tab, --sort-keys, --color WHEN
+                    format the result as the json command does
+  -h, --help        show this help";
+
+pub const UNFLATTEN_USAGE: &str = "usage: json unflatten [OPTIONS] [FILE]
+
+Rebuild the nested document that `json flatten` made the object in FILE, or
+standard input when FILE is `-` or missing, from. Names that clash, like
+a.b next to a.b.c, fail with the path where they meet.
+
+options:
+  --sep S           split names at S instead of at `.`
+  --bracket-arrays  read a.b[0] as an array element, and a.b.0 as a member;
+                    without it, members named 0, 1, ... become arrays
+  --minify, --indent N, --tab, --sort-keys, --color WHEN
+                    format the result as the json command does
+  -h, --help        show this help";
+
+pub struct Options {
+    input: Source,
+    unflatten: bool,
+    flatten: FlattenOptions,
+    format: FormatOptions,
+    color: Color,
+}
+
+impl Options {
+    pub fn parse_flatten(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        Self::parse(args, false)
+    }
+
+    pub fn parse_unflatten(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        Self::parse(args, true)
+    }
+
+    fn parse(args: impl Iterator<Item = OsString>, unflatten: bool) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut input = None;
+        let mut flatten = FlattenOptions::default();
+        let mut format = FormatFlags::default();
+        let mut color = Color::default();
+        while let Some(arg) = args.next() {
+            if format.take(&arg, &mut args)? {
+                continue;
+            }
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some(flag @ "--color") => color = Color::parse(&value(flag, &mut args)?)?,
+                Some(flag @ "--sep") => {
+                    let Ok(sep) = value(flag, &mut args)?.into_string() else {
+                        bail!("--sep needs to be UTF-8");
+                    };
+                    if sep.is_empty() {
+                        bail!("--sep cannot be empty");
+                    }
+                    flatten.separator = sep;
+                }
+                Some("--bracket-arrays") => flatten.bracket_arrays = true,
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+            unflatten,
+            flatten,
+            format: format.options()?,
+            color,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let value = read_value(&options.input)?;
+    let value = if options.unflatten {
+        unflatten(&value, &options.flatten)?
+    } else {
+        flatten(&value, &options.flatten)
+    };
+    let mut out = BufWriter::new(io::stdout().lock());
+    let color = options
+        .color
+        .enabled(io::stdout().is_terminal(), no_color());
+    write_value(&mut out, &value, &options.format, color)?;
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,140 @@
+use crate::{located, replace, FormatFlags};
+use anyhow::{bail, Context, Result};
+use grammar::json::{parse_json_bytes, transcode, FormatOptions, MaybeGzip};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json fmt [OPTIONS] FILE...
+
+Format each FILE and print the result. Keys stay in the order the file has
+them unless --sort-keys is given. A trailing newline is kept only if the
+file has one. A gzip-compressed file is decompressed first. A file
+that does not parse, or a compressed one given to --write, is reported and
+left alone, and makes the exit status 1.
+
+options:
+  --write      rewrite the files in place instead of printing them
+  --check      print only the names of the files that are not formatted,
+               and exit with 1 if there are any
+  --minify, --indent N, --tab, --sort-keys
+               the style, as for the json command
+  -h, --help   show this help";
+
+pub struct Options {
+    files: Vec<PathBuf>,
+    mode: Mode,
+    format: FormatOptions,
+}
+
+#[derive(PartialEq)]
+enum Mode {
+    Print,
+    Write,
+    Check,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut files = Vec::new();
+        let mut mode = Mode::Print;
+        let mut format = FormatFlags::default();
+        while let Some(arg) = args.next() {
+            if format.take(&arg, &mut args)? {
+                continue;
+            }
+            let asked = match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--write") => Mode::Write,
+                Some("--check") => Mode::Check,
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ => {
+                    files.push(arg.into());
+                    continue;
+                }
+            };
+            if mode != Mode::Print && mode != asked {
+                bail!("--write and --check cannot be combined");
+            }
+            mode = asked;
+        }
+        if files.is_empty() {
+            bail!("fmt needs at least one file");
+        }
+        Ok(Some(Options {
+            files,
+            mode,
+            format: format.options()?,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut code = ExitCode::SUCCESS;
+    for path in &options.files {
+        let cannot_read = || format!("cannot read {}", path.display());
+        let mut file = File::open(path)
+            .and_then(MaybeGzip::new)
+            .with_context(cannot_read)?;
+        // formatted text written over it would no longer be gzip
+        if file.is_gzip() && options.mode == Mode::Write {
+            out.flush()?;
+            eprintln!(
+                "json: {}: cannot rewrite gzip-compressed input in place",
+                path.display()
+            );
+            code = ExitCode::FAILURE;
+            continue;
+        }
+        let mut original = Vec::new();
+        file.read_to_end(&mut original).with_context(cannot_read)?;
+        // parsed first so that an error is reported with its line and column
+        let value = match parse_json_bytes(&original) {
+            Ok(value) => value,
+            Err(e) => {
+                out.flush()?;
+                eprintln!(
+                    "json: {}",
+                    located(e, &path.display().to_string(), &original)
+                );
+                code = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        let format = FormatOptions {
+            trailing_newline: original.ends_with(b"\n"),
+            ..options.format.clone()
+        };
+        // the parsed tree has lost the key order, which transcoding keeps
+        let formatted = if format.sort_keys {
+            value.try_to_vec_with(&format)?
+        } else {
+            let mut formatted = Vec::new();
+            transcode(&original[..], &mut formatted, &format)?;
+            formatted
+        };
+        match options.mode {
+            Mode::Print => {
+                out.write_all(&formatted)?;
+                if !format.trailing_newline {
+                    out.write_all(b"\n")?;
+                }
+            }
+            Mode::Check if formatted != original => {
+                writeln!(out, "{}", path.display())?;
+                code = ExitCode::FAILURE;
+            }
+            Mode::Write if formatted != original => replace(path, &formatted)
+                .with_context(|| format!("cannot write {}", path.display()))?,
+            Mode::Check | Mode::Write => {}
+        }
+    }
+    out.flush()?;
+    Ok(code)
+}
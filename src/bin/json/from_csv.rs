@@ -0,0 +1,70 @@
+use crate::{no_color, read_bytes, value, write_value, Color, FormatFlags, Source};
+use anyhow::{anyhow, bail, Result};
+use grammar::json::{from_csv, FormatOptions};
+use std::{
+    ffi::OsString,
+    io::{self, BufWriter, IsTerminal, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json from-csv [OPTIONS] [FILE]
+
+Read CSV with a header row from FILE, or standard input when FILE is `-` or
+missing, and print it as an array of objects. Cells that spell numbers or
+booleans become them; empty cells are left out.
+
+options:
+  --minify, --indent N, --tab, --sort-keys, --color WHEN
+              format the array as the json command does
+  -h, --help  show this help";
+
+pub struct Options {
+    input: Source,
+    format: FormatOptions,
+    color: Color,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut input = None;
+        let mut format = FormatFlags::default();
+        let mut color = Color::default();
+        while let Some(arg) = args.next() {
+            if format.take(&arg, &mut args)? {
+                continue;
+            }
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some(flag @ "--color") => color = Color::parse(&value(flag, &mut args)?)?,
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+            format: format.options()?,
+            color,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let (name, input) = read_bytes(&options.input)?;
+    let input = String::from_utf8(input).map_err(|e| {
+        anyhow!(
+            "{}: invalid UTF-8 at offset {}",
+            name,
+            e.utf8_error().valid_up_to()
+        )
+    })?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    let color = options
+        .color
+        .enabled(io::stdout().is_terminal(), no_color());
+    write_value(&mut out, &from_csv(&input), &options.format, color)?;
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
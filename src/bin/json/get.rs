@@ -0,0 +1,172 @@
+use crate::{read_value, Source};
+use anyhow::{anyhow, bail, Result};
+use grammar::json::{parse_json, JsonValue};
+use std::{
+    ffi::OsString,
+    io::{self, BufWriter, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json get [OPTIONS] POINTER... [FILE]
+
+Print the value at each JSON Pointer, one per line, from FILE or from
+standard input when FILE is `-` or missing. FILE is the last argument once
+more than one is given. Strings print as their contents, other values as
+compact JSON. An address that matches nothing is reported and makes the
+exit status 1.
+
+options:
+  --path P    address a value by the dotted path P, as in users[0].name
+  --json      print strings as JSON too, quoted and escaped
+  -h, --help  show this help";
+
+pub struct Options {
+    queries: Vec<Query>,
+    input: Source,
+    json: bool,
+}
+
+/// An address as given, and the pointer it stands for.
+struct Query {
+    text: String,
+    pointer: String,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut queries = Vec::new();
+        let mut positional = Vec::new();
+        let mut paths = false;
+        let mut json = false;
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--json") => json = true,
+                Some("--path") => {
+                    let text = args.next().ok_or_else(|| anyhow!("--path needs a value"))?;
+                    let text = text
+                        .into_string()
+                        .map_err(|_| anyhow!("--path needs a UTF-8 value"))?;
+                    let pointer = path_to_pointer(&text)?;
+                    queries.push((queries.len() + positional.len(), Query { text, pointer }));
+                    paths = true;
+                }
+                Some(flag) if flag.starts_with('-') && flag != "-" => {
+                    bail!("unknown option {}", flag)
+                }
+                _ => positional.push((queries.len() + positional.len(), arg)),
+            }
+        }
+        // with a --path, no positional argument needs to be a pointer
+        let input = if paths || positional.len() > 1 {
+            match positional.pop() {
+                Some((_, arg)) if arg == "-" => Source::Stdin,
+                Some((_, arg)) => Source::File(arg.into()),
+                None => Source::Stdin,
+            }
+        } else {
+            Source::Stdin
+        };
+        for (at, arg) in positional {
+            let text = arg
+                .into_string()
+                .map_err(|arg| anyhow!("{} is not a JSON Pointer", arg.to_string_lossy()))?;
+            if !text.is_empty() && !text.starts_with('/') {
+                bail!("{} is not a JSON Pointer, which starts with /", text);
+            }
+            let pointer = text.clone();
+            queries.push((at, Query { text, pointer }));
+        }
+        if queries.is_empty() {
+            bail!("no pointer or path given");
+        }
+        // print in the order given, pointers and paths mixed
+        queries.sort_by_key(|&(at, _)| at);
+        Ok(Some(Options {
+            queries: queries.into_iter().map(|(_, query)| query).collect(),
+            input,
+            json,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let value = read_value(&options.input)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut code = ExitCode::SUCCESS;
+    for query in &options.queries {
+        match value.pointer(&query.pointer) {
+            Some(found) => match found.as_display() {
+                // null stays `null`, to tell it from an empty string
+                Some(text) if !options.json => writeln!(out, "{}", text)?,
+                _ => writeln!(out, "{}", found.to_json_string())?,
+            },
+            None => {
+                out.flush()?;
+                eprintln!("json: {}: not found", query.text);
+                code = ExitCode::FAILURE;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(code)
+}
+
+/// The JSON Pointer for a dotted path: names split by `.`, indices as
+/// `[N]`, and keys holding `.` or `[` as quoted JSON strings, `["a.b"]`.
+/// An empty path, or `.`, is the whole document.
+fn path_to_pointer(path: &str) -> Result<String> {
+    let invalid = |why: &str| anyhow!("invalid path {}: {}", path, why);
+    let mut pointer = String::new();
+    let mut rest = path.strip_prefix('.').unwrap_or(path);
+    let mut first = true;
+    while !rest.is_empty() {
+        let token = if let Some(inner) = rest.strip_prefix('[') {
+            let end = if let Some(quoted) = inner.strip_prefix('"') {
+                // the closing quote is the first one not escaped
+                let mut escaped = false;
+                let close = quoted.find(|c| {
+                    let quote = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    quote
+                });
+                close.map(|i| i + 2)
+            } else {
+                inner.find(']')
+            };
+            let end = end.ok_or_else(|| invalid("unclosed ["))?;
+            let (inside, after) = inner.split_at(end);
+            rest = after
+                .strip_prefix(']')
+                .ok_or_else(|| invalid("expected ] after a quoted key"))?;
+            if inside.starts_with('"') {
+                match parse_json(inside) {
+                    Ok(JsonValue::String(key)) => key.to_string(),
+                    _ => return Err(invalid("bad quoted key")),
+                }
+            } else if !inside.is_empty() && inside.bytes().all(|b| b.is_ascii_digit()) {
+                inside.to_string()
+            } else {
+                return Err(invalid("expected an index or a quoted key in []"));
+            }
+        } else {
+            if !first {
+                rest = rest
+                    .strip_prefix('.')
+                    .ok_or_else(|| invalid("expected . or ["))?;
+            }
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(invalid("empty name"));
+            }
+            let (name, after) = rest.split_at(end);
+            rest = after;
+            name.to_string()
+        };
+        pointer.push('/');
+        pointer.push_str(&token.replace('~', "~0").replace('/', "~1"));
+        first = false;
+    }
+    Ok(pointer)
+}
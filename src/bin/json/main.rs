@@ -0,0 +1,471 @@
+use anyhow::{anyhow, bail, Context, Result};
+use grammar::json::{
+    from_reader_auto, offset_to_line_col, parse_json_bytes, ColorScheme, ColoredFormatter,
+    FormatOptions, Indent, JsonError, JsonValue, MaybeGzip,
+};
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+mod array2seq;
+mod diff;
+mod edit;
+mod flatten;
+mod fmt;
+mod from_csv;
+mod get;
+mod merge;
+mod ndjson;
+mod paths;
+mod schema;
+mod seq2array;
+mod stats;
+mod strip_comments;
+mod to_csv;
+mod validate;
+
+const USAGE: &str = "usage: json [OPTIONS] [FILE]
+       json COMMAND [ARGS]
+
+Parse FILE, or standard input when FILE is `-` or missing, and print it as
+JSON, indented by two spaces unless told otherwise. Input compressed with
+gzip is decompressed as it is read, here and in the commands that read a
+whole document.
+
+options:
+  --minify        print compact JSON on one line
+  --indent N      indent by N spaces
+  --tab           indent by tabs
+  --sort-keys     sort object members by key at every level
+  --color WHEN    color indented output: auto (the default) when writing
+                  to a terminal and NO_COLOR is unset, always or never
+  -o, --output F  write to file F instead of standard output
+  -h, --help      show this help
+
+commands:
+  array2seq       print the elements of an array as newline-delimited JSON
+  delete          remove the value at a JSON Pointer
+  diff            compare two documents, as a report or a JSON Patch
+  flatten         print a document as one object of dotted paths
+  fmt             format files, printing them or in place
+  from-csv        read CSV with a header row as an array of objects
+  get             print the values at JSON Pointers or dotted paths
+  merge           layer documents over one another
+  ndjson          filter newline-delimited JSON records as they arrive
+  paths           list the JSON Pointer of every leaf with its value
+  schema          infer a JSON Schema from examples or check files against one
+  seq2array       collect newline-delimited JSON into an array
+  set             put a value at a JSON Pointer
+  stats           count what a document holds, without building it
+  strip-comments  turn JSONC into strict JSON, keeping the layout
+  to-csv          print an array of objects as CSV
+  unflatten       rebuild the document a flattened object came from
+  validate        check files and report where each one goes wrong";
+
+fn main() -> ExitCode {
+    let mut args = env::args_os().skip(1).peekable();
+    match args.peek().and_then(|arg| arg.to_str()) {
+        Some("array2seq") => {
+            args.next();
+            command(
+                array2seq::USAGE,
+                array2seq::Options::parse(args),
+                array2seq::run,
+            )
+        }
+        Some("delete") => {
+            args.next();
+            command(
+                edit::DELETE_USAGE,
+                edit::Options::parse_delete(args),
+                edit::run,
+            )
+        }
+        Some("diff") => {
+            args.next();
+            command(diff::USAGE, diff::Options::parse(args), diff::run)
+        }
+        Some("flatten") => {
+            args.next();
+            command(
+                flatten::FLATTEN_USAGE,
+                flatten::Options::parse_flatten(args),
+                flatten::run,
+            )
+        }
+        Some("fmt") => {
+            args.next();
+            command(fmt::USAGE, fmt::Options::parse(args), fmt::run)
+        }
+        Some("from-csv") => {
+            args.next();
+            command(
+                from_csv::USAGE,
+                from_csv::Options::parse(args),
+                from_csv::run,
+            )
+        }
+        Some("get") => {
+            args.next();
+            command(get::USAGE, get::Options::parse(args), get::run)
+        }
+        Some("merge") => {
+            args.next();
+            command(merge::USAGE, merge::Options::parse(args), merge::run)
+        }
+        Some("ndjson") => {
+            args.next();
+            command(ndjson::USAGE, ndjson::Options::parse(args), ndjson::run)
+        }
+        Some("paths") => {
+            args.next();
+            command(paths::USAGE, paths::Options::parse(args), paths::run)
+        }
+        Some("schema") => {
+            args.next();
+            command(schema::USAGE, schema::Options::parse(args), schema::run)
+        }
+        Some("seq2array") => {
+            args.next();
+            command(
+                seq2array::USAGE,
+                seq2array::Options::parse(args),
+                seq2array::run,
+            )
+        }
+        Some("set") => {
+            args.next();
+            command(edit::SET_USAGE, edit::Options::parse_set(args), edit::run)
+        }
+        Some("stats") => {
+            args.next();
+            command(stats::USAGE, stats::Options::parse(args), stats::run)
+        }
+        Some("strip-comments") => {
+            args.next();
+            command(
+                strip_comments::USAGE,
+                strip_comments::Options::parse(args),
+                strip_comments::run,
+            )
+        }
+        Some("to-csv") => {
+            args.next();
+            command(to_csv::USAGE, to_csv::Options::parse(args), to_csv::run)
+        }
+        Some("unflatten") => {
+            args.next();
+            command(
+                flatten::UNFLATTEN_USAGE,
+                flatten::Options::parse_unflatten(args),
+                flatten::run,
+            )
+        }
+        Some("validate") => {
+            args.next();
+            command(
+                validate::USAGE,
+                validate::Options::parse(args),
+                validate::run,
+            )
+        }
+        _ => command(USAGE, Options::parse(args), |options| {
+            run(options).map(|()| ExitCode::SUCCESS)
+        }),
+    }
+}
+
+/// Run a command on its parsed `options`: help or a usage error print
+/// `usage`, the latter exiting with 2, and a failed run exits with 1.
+fn command<T>(
+    usage: &str,
+    options: Result<Option<T>>,
+    run: impl FnOnce(&T) -> Result<ExitCode>,
+) -> ExitCode {
+    let options = match options {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", usage);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("json: {}\n\n{}", e, usage);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&options) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("json: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// What the command line asks for.
+struct Options {
+    input: Source,
+    output: Option<PathBuf>,
+    format: FormatOptions,
+    color: Color,
+}
+
+enum Source {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Options {
+    /// `None` when help was asked for. Conflicting flags are an error here,
+    /// before any input is read.
+    fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut input = None;
+        let mut output = None;
+        let mut format = FormatFlags::default();
+        let mut color = Color::default();
+        while let Some(arg) = args.next() {
+            if format.take(&arg, &mut args)? {
+                continue;
+            }
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some(flag @ "--color") => color = Color::parse(&value(flag, &mut args)?)?,
+                Some(flag @ ("-o" | "--output")) => output = Some(value(flag, &mut args)?.into()),
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+            output,
+            format: format.options()?,
+            color,
+        }))
+    }
+}
+
+/// The value of `flag`, the argument after it.
+fn value(flag: &str, args: &mut impl Iterator<Item = OsString>) -> Result<OsString> {
+    args.next().ok_or_else(|| anyhow!("{} needs a value", flag))
+}
+
+/// The formatting flags of every command that prints a document.
+#[derive(Default)]
+struct FormatFlags {
+    minify: bool,
+    indent: Option<Indent>,
+    sort_keys: bool,
+}
+
+impl FormatFlags {
+    /// Take `arg` if it is a formatting flag, with its value from `args`.
+    fn take(&mut self, arg: &OsString, args: &mut impl Iterator<Item = OsString>) -> Result<bool> {
+        match arg.to_str() {
+            Some("--minify") => self.minify = true,
+            Some("--sort-keys") => self.sort_keys = true,
+            Some(flag @ "--indent") => {
+                let n = value(flag, args)?;
+                let n = n.to_str().and_then(|n| n.parse().ok());
+                let n = n.ok_or_else(|| anyhow!("--indent needs a number of spaces"))?;
+                if self.indent.replace(Indent::Spaces(n)).is_some() {
+                    bail!("--indent and --tab cannot be combined");
+                }
+            }
+            Some("--tab") => {
+                if self.indent.replace(Indent::tab()).is_some() {
+                    bail!("--indent and --tab cannot be combined");
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// The options the flags ask for, or an error if they conflict.
+    fn options(self) -> Result<FormatOptions> {
+        if self.minify && self.indent.is_some() {
+            bail!("--minify cannot be combined with --indent or --tab");
+        }
+        Ok(FormatOptions {
+            indent: if self.minify {
+                None
+            } else {
+                Some(self.indent.unwrap_or_default())
+            },
+            trailing_newline: true,
+            sort_keys: self.sort_keys,
+            ..FormatOptions::default()
+        })
+    }
+}
+
+fn run(options: &Options) -> Result<()> {
+    let value = read_value(&options.input)?;
+    // created only once the input has parsed, so a failure leaves no file
+    let (out, terminal): (Box<dyn Write>, _) = match &options.output {
+        Some(path) => (
+            Box::new(
+                File::create(path).with_context(|| format!("cannot create {}", path.display()))?,
+            ),
+            false,
+        ),
+        None => (Box::new(io::stdout().lock()), io::stdout().is_terminal()),
+    };
+    let mut out = BufWriter::new(out);
+    let color = options.color.enabled(terminal, no_color());
+    write_value(&mut out, &value, &options.format, color)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Parse the input, decompressing it first if it is gzip. Files go through
+/// the streaming reader, so they are never held in memory whole.
+fn read_value(source: &Source) -> Result<JsonValue> {
+    match source {
+        Source::Stdin => {
+            let mut input = Vec::new();
+            MaybeGzip::new(io::stdin().lock())
+                .and_then(|mut stdin| stdin.read_to_end(&mut input))
+                .context("cannot read standard input")?;
+            parse_json_bytes(&input).map_err(|e| located(e, "<stdin>", &input))
+        }
+        Source::File(path) => File::open(path)
+            .map_err(JsonError::from)
+            .and_then(from_reader_auto)
+            .map_err(|e| match e {
+                JsonError::Parse { offset, .. } => {
+                    let before = read_prefix(path, offset).unwrap_or_default();
+                    located(e, &path.display().to_string(), &before)
+                }
+                e => anyhow!("{}: {}", path.display(), e),
+            }),
+    }
+}
+
+/// When to color output.
+#[derive(Clone, Copy, Default)]
+enum Color {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    fn parse(when: &OsStr) -> Result<Self> {
+        match when.to_str() {
+            Some("auto") => Ok(Color::Auto),
+            Some("always") => Ok(Color::Always),
+            Some("never") => Ok(Color::Never),
+            _ => bail!("--color is auto, always or never"),
+        }
+    }
+
+    /// Whether to color output that goes to a terminal or not, NO_COLOR
+    /// being set or not. Only `auto` asks either question.
+    fn enabled(self, terminal: bool, no_color: bool) -> bool {
+        match self {
+            Color::Auto => terminal && !no_color,
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+/// Whether NO_COLOR is set to anything but the empty string, see
+/// <https://no-color.org>.
+fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// Write `value` as `format` says, in the default colors if `color` is set
+/// and the output is indented; compact output is never colored.
+fn write_value(
+    out: &mut impl Write,
+    value: &JsonValue,
+    format: &FormatOptions,
+    color: bool,
+) -> io::Result<()> {
+    match &format.indent {
+        Some(indent) if color => {
+            let unit = indent.unit();
+            let newline = format.newline.as_str().as_bytes();
+            let scheme = ColorScheme::default();
+            let mut f = ColoredFormatter::new(unit.as_bytes(), &scheme).with_newline(newline);
+            value.to_writer_with_formatter(out, &mut f, format)?;
+            if format.trailing_newline {
+                out.write_all(newline)?;
+            }
+            Ok(())
+        }
+        _ => value.to_writer_with(out, format),
+    }
+}
+
+/// The name of the input in messages, and all its bytes, decompressed if
+/// it is gzip.
+fn read_bytes(source: &Source) -> Result<(String, Vec<u8>)> {
+    let mut input = Vec::new();
+    match source {
+        Source::Stdin => {
+            MaybeGzip::new(io::stdin().lock())
+                .and_then(|mut stdin| stdin.read_to_end(&mut input))
+                .context("cannot read standard input")?;
+            Ok(("<stdin>".to_string(), input))
+        }
+        Source::File(path) => {
+            File::open(path)
+                .and_then(MaybeGzip::new)
+                .and_then(|mut file| file.read_to_end(&mut input))
+                .with_context(|| format!("cannot read {}", path.display()))?;
+            Ok((path.display().to_string(), input))
+        }
+    }
+}
+
+/// A parse error as `NAME:LINE:COLUMN: message`, the position worked out
+/// from `before`, the bytes of the input up to the error.
+fn located(err: JsonError, name: &str, before: &[u8]) -> anyhow::Error {
+    match err {
+        JsonError::Parse {
+            offset, message, ..
+        } => {
+            let before = String::from_utf8_lossy(&before[..offset.min(before.len())]);
+            let (line, column) = offset_to_line_col(&before, before.len());
+            anyhow!("{}:{}:{}: {}", name, line, column, message)
+        }
+        e => anyhow!("{}: {}", name, e),
+    }
+}
+
+/// The first `len` bytes of the file at `path`, decompressed if it is gzip.
+fn read_prefix(path: &Path, len: usize) -> io::Result<Vec<u8>> {
+    let mut before = Vec::new();
+    MaybeGzip::new(File::open(path)?)?
+        .take(len as u64)
+        .read_to_end(&mut before)?;
+    Ok(before)
+}
+
+/// Replace the contents of the file at `path` in one step: write a
+/// temporary file beside it, with the same permissions, and rename that
+/// over it, so a failure never leaves the file half written.
+fn replace(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let written = fs::write(&temp, contents)
+        .and_then(|()| fs::set_permissions(&temp, fs::metadata(path)?.permissions()))
+        .and_then(|()| fs::rename(&temp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
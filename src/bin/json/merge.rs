@@ -0,0 +1,99 @@
+use crate::{no_color, read_value, value, write_value, Color, FormatFlags, Source};
+use anyhow::{bail, Result};
+use grammar::json::{ArrayStrategy, FormatOptions};
+use std::{
+    ffi::OsString,
+    io::{self, BufWriter, IsTerminal, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json merge [OPTIONS] FILE...
+
+Merge the documents in the FILEs left to right, later ones winning, and
+print the result. One FILE can be `-` for standard input. Objects merge
+member by member; anything else is replaced.
+
+options:
+  --array-strategy S  replace (the default) or concat arrays found on both sides
+  --merge-patch       apply each later FILE as an RFC 7386 merge patch, where
+                      null removes a member
+  --minify, --indent N, --tab, --sort-keys, --color WHEN
+                      format the result as the json command does
+  -h, --help          show this help";
+
+pub struct Options {
+    inputs: Vec<Source>,
+    arrays: ArrayStrategy,
+    merge_patch: bool,
+    format: FormatOptions,
+    color: Color,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut inputs = Vec::new();
+        let mut arrays = None;
+        let mut merge_patch = false;
+        let mut format = FormatFlags::default();
+        let mut color = Color::default();
+        while let Some(arg) = args.next() {
+            if format.take(&arg, &mut args)? {
+                continue;
+            }
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some(flag @ "--color") => color = Color::parse(&value(flag, &mut args)?)?,
+                Some(flag @ "--array-strategy") => {
+                    arrays = match value(flag, &mut args)?.to_str() {
+                        Some("replace") => Some(ArrayStrategy::Replace),
+                        Some("concat") => Some(ArrayStrategy::Concat),
+                        _ => bail!("--array-strategy is replace or concat"),
+                    };
+                }
+                Some("--merge-patch") => merge_patch = true,
+                Some("-") => {
+                    if inputs.iter().any(|input| matches!(input, Source::Stdin)) {
+                        bail!("only one input can be standard input");
+                    }
+                    inputs.push(Source::Stdin);
+                }
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ => inputs.push(Source::File(arg.into())),
+            }
+        }
+        if merge_patch && arrays.is_some() {
+            bail!("--merge-patch cannot be combined with --array-strategy");
+        }
+        if inputs.is_empty() {
+            bail!("merge needs at least one input");
+        }
+        Ok(Some(Options {
+            inputs,
+            arrays: arrays.unwrap_or_default(),
+            merge_patch,
+            format: format.options()?,
+            color,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let mut inputs = options.inputs.iter();
+    let mut merged = read_value(inputs.next().expect("at least one input"))?;
+    for input in inputs {
+        let value = read_value(input)?;
+        if options.merge_patch {
+            merged.merge_patch(value);
+        } else {
+            merged.deep_merge(value, options.arrays);
+        }
+    }
+    let mut out = BufWriter::new(io::stdout().lock());
+    let color = options
+        .color
+        .enabled(io::stdout().is_terminal(), no_color());
+    write_value(&mut out, &merged, &options.format, color)?;
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
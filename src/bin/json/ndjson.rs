@@ -0,0 +1,149 @@
+use crate::{seq2array::line_error, value, Source};
+use anyhow::{anyhow, bail, Context, Result};
+use grammar::json::{parse_json, parse_json_bytes, FormatOptions, JsonValue, MaybeGzip, Pointer};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json ndjson [OPTIONS] [FILE]
+
+Read newline-delimited JSON from FILE, or standard input when FILE is `-` or
+missing, and print the records asked for as newline-delimited JSON. Each
+record is printed as soon as its line is read, so the command can follow
+`tail -f`. Blank lines are ignored.
+
+options:
+  --get POINTER   keep only records with a value at the JSON Pointer POINTER
+  --match VALUE   keep only records whose value at POINTER is VALUE, read as
+                  JSON, or as a string when it is not JSON
+  --values        print the value at POINTER instead of the record
+  --skip-invalid  report lines that are not JSON and go on, instead of
+                  stopping at the first
+  --sort-keys     sort object members by key
+  -h, --help      show this help";
+
+pub struct Options {
+    input: Source,
+    get: Option<Pointer>,
+    wanted: Option<JsonValue>,
+    values: bool,
+    skip_invalid: bool,
+    format: FormatOptions,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut input = None;
+        let mut get = None;
+        let mut wanted = None;
+        let mut values = false;
+        let mut skip_invalid = false;
+        let mut format = FormatOptions::default().trailing_newline(true);
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some(flag @ "--get") => {
+                    let text = utf8(value(flag, &mut args)?, flag)?;
+                    let pointer = Pointer::parse(&text)
+                        .map_err(|e| anyhow!("invalid pointer {:?}: {}", text, e))?;
+                    get = Some(pointer);
+                }
+                Some(flag @ "--match") => {
+                    let text = utf8(value(flag, &mut args)?, flag)?;
+                    wanted = Some(parse_json(&text).unwrap_or(JsonValue::from(text)));
+                }
+                Some("--values") => values = true,
+                Some("--skip-invalid") => skip_invalid = true,
+                Some("--sort-keys") => format = format.sort_keys(true),
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        if get.is_none() && (wanted.is_some() || values) {
+            bail!(
+                "{} needs --get POINTER",
+                if values { "--values" } else { "--match" }
+            );
+        }
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+            get,
+            wanted,
+            values,
+            skip_invalid,
+            format,
+        }))
+    }
+}
+
+fn utf8(arg: OsString, flag: &str) -> Result<String> {
+    arg.into_string()
+        .map_err(|_| anyhow!("{} needs a UTF-8 value", flag))
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let (name, reader): (_, Box<dyn BufRead>) = match &options.input {
+        Source::Stdin => (
+            "<stdin>".to_string(),
+            Box::new(BufReader::new(
+                MaybeGzip::new(io::stdin().lock()).context("cannot read standard input")?,
+            )),
+        ),
+        Source::File(path) => (
+            path.display().to_string(),
+            Box::new(BufReader::new(
+                File::open(path)
+                    .and_then(MaybeGzip::new)
+                    .with_context(|| format!("cannot open {}", path.display()))?,
+            )),
+        ),
+    };
+    // standard output is flushed at each newline, so every record goes out
+    // as soon as it is written
+    let mut out = io::stdout().lock();
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let line = line.with_context(|| format!("cannot read {}", name))?;
+        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let record = match parse_json_bytes(line) {
+            Ok(record) => record,
+            Err(e) => {
+                let e = line_error(e, &name, i + 1, line);
+                if !options.skip_invalid {
+                    return Err(e);
+                }
+                eprintln!("json: {}", e);
+                continue;
+            }
+        };
+        let picked = match &options.get {
+            Some(pointer) => match record.pointer(pointer) {
+                Some(found) => Some(found),
+                None => continue,
+            },
+            None => None,
+        };
+        if options
+            .wanted
+            .as_ref()
+            .is_some_and(|wanted| picked != Some(wanted))
+        {
+            continue;
+        }
+        let shown = match picked {
+            Some(found) if options.values => found,
+            _ => &record,
+        };
+        shown.to_writer_with(&mut out, &options.format)?;
+    }
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,121 @@
+use crate::{read_value, value, Source};
+use anyhow::{anyhow, bail, Result};
+use grammar::json::JsonValue;
+use std::{
+    ffi::OsString,
+    io::{self, BufWriter, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json paths [OPTIONS] [FILE]
+
+Print every leaf of the document in FILE, or standard input when FILE is
+`-` or missing, one per line: its JSON Pointer, a tab and the value as
+compact JSON. Leaves are scalars and empty arrays and objects. Members are
+listed in key order and elements in index order, so the same document
+always gives the same lines.
+
+options:
+  --types          print the type of each value instead of the value
+  --max-depth N    stop N levels below the document, printing each array
+                   or object there as a count of what it holds
+  -h, --help       show this help";
+
+pub struct Options {
+    input: Source,
+    types: bool,
+    max_depth: Option<usize>,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut input = None;
+        let mut types = false;
+        let mut max_depth = None;
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--types") => types = true,
+                Some(flag @ "--max-depth") => {
+                    let n = value(flag, &mut args)?;
+                    let n = n.to_str().and_then(|n| n.parse().ok());
+                    max_depth = Some(n.ok_or_else(|| anyhow!("--max-depth needs a number"))?);
+                }
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+            types,
+            max_depth,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let value = read_value(&options.input)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    leaves(&mut out, options, &mut String::new(), &value, 0)?;
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Print the leaves of `value`, which is at `pointer`, `depth` levels down.
+fn leaves(
+    out: &mut impl Write,
+    options: &Options,
+    pointer: &mut String,
+    value: &JsonValue,
+    depth: usize,
+) -> Result<()> {
+    let stop = options.max_depth.is_some_and(|max| depth >= max);
+    let len = pointer.len();
+    match value {
+        JsonValue::Array(arr) if !arr.is_empty() && !stop => {
+            for (i, v) in arr.iter().enumerate() {
+                pointer.push_str(&format!("/{}", i));
+                leaves(out, options, pointer, v, depth + 1)?;
+                pointer.truncate(len);
+            }
+        }
+        JsonValue::Object(obj) if !obj.is_empty() && !stop => {
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_by(|a, b| a.0.cmp(b.0));
+            for (k, v) in members {
+                pointer.push('/');
+                pointer.push_str(&k.replace('~', "~0").replace('/', "~1"));
+                leaves(out, options, pointer, v, depth + 1)?;
+                pointer.truncate(len);
+            }
+        }
+        _ if options.types => writeln!(out, "{}\t{}", pointer, type_name(value))?,
+        JsonValue::Array(arr) if !arr.is_empty() => {
+            writeln!(out, "{}\t[{}]", pointer, count(arr.len(), "element"))?
+        }
+        JsonValue::Object(obj) if !obj.is_empty() => {
+            writeln!(out, "{}\t{{{}}}", pointer, count(obj.len(), "member"))?
+        }
+        _ => writeln!(out, "{}\t{}", pointer, value.to_json_string())?,
+    }
+    Ok(())
+}
+
+fn count(n: usize, what: &str) -> String {
+    format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+        JsonValue::Raw(_) => "raw",
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{no_color, read_value, value, write_value, Color, FormatFlags, Source};
+use anyhow::{anyhow, bail, Result};
+use grammar::json::{infer_schema_many, FormatOptions, Schema};
+use std::{
+    ffi::OsString,
+    io::{self, BufWriter, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json schema infer [OPTIONS] FILE...
+       json schema check --schema SCHEMA FILE...
+
+infer prints a JSON Schema describing the example documents in the FILEs,
+one of which can be `-` for standard input. Keys missing from some examples
+become optional and values of different types get a type union.
+
+check validates each FILE against the schema in SCHEMA and prints every
+violation as FILE: POINTER: message. The exit status is 0 only if all of
+them conform.
+
+options:
+  --schema SCHEMA     the schema to check against
+  --minify, --indent N, --tab, --sort-keys, --color WHEN
+                      format the inferred schema as the json command does,
+                      its keys always sorted
+  -h, --help          show this help";
+
+pub struct Options {
+    inputs: Vec<Source>,
+    action: Action,
+}
+
+enum Action {
+    Infer { format: FormatOptions, color: Color },
+    Check { schema: PathBuf },
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let check = match args.next().as_deref().and_then(|arg| arg.to_str()) {
+            Some("infer") => false,
+            Some("check") => true,
+            Some("-h" | "--help") => return Ok(None),
+            Some(other) => bail!("unknown schema command {}", other),
+            None => bail!("schema needs a command, infer or check"),
+        };
+        let mut inputs = Vec::new();
+        let mut schema = None;
+        let mut format = FormatFlags::default();
+        let mut color = Color::default();
+        while let Some(arg) = args.next() {
+            if !check && format.take(&arg, &mut args)? {
+                continue;
+            }
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some(flag @ "--color") if !check => color = Color::parse(&value(flag, &mut args)?)?,
+                Some(flag @ "--schema") if check => {
+                    schema = Some(PathBuf::from(value(flag, &mut args)?))
+                }
+                Some("-") => {
+                    if inputs.iter().any(|input| matches!(input, Source::Stdin)) {
+                        bail!("only one input can be standard input");
+                    }
+                    inputs.push(Source::Stdin);
+                }
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ => inputs.push(Source::File(arg.into())),
+            }
+        }
+        if inputs.is_empty() {
+            bail!("schema needs at least one input");
+        }
+        let action = if check {
+            Action::Check {
+                schema: schema.ok_or_else(|| anyhow!("check needs --schema"))?,
+            }
+        } else {
+            // sorted, so the same examples always give the same text
+            let mut format = format.options()?;
+            format.sort_keys = true;
+            Action::Infer { format, color }
+        };
+        Ok(Some(Options { inputs, action }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    let code = match &options.action {
+        Action::Infer { format, color } => {
+            let examples = options
+                .inputs
+                .iter()
+                .map(read_value)
+                .collect::<Result<Vec<_>>>()?;
+            let color = color.enabled(io::stdout().is_terminal(), no_color());
+            write_value(&mut out, &infer_schema_many(&examples), format, color)?;
+            ExitCode::SUCCESS
+        }
+        Action::Check { schema } => {
+            let document = read_value(&Source::File(schema.clone()))?;
+            let schema = Schema::from_value(&document)
+                .map_err(|e| anyhow!("{}: {}", schema.display(), e))?;
+            let mut code = ExitCode::SUCCESS;
+            for input in &options.inputs {
+                let Err(errors) = schema.validate(&read_value(input)?) else {
+                    continue;
+                };
+                code = ExitCode::FAILURE;
+                let name = match input {
+                    Source::Stdin => "<stdin>".to_string(),
+                    Source::File(path) => path.display().to_string(),
+                };
+                for error in errors {
+                    let pointer = match error.pointer.as_str() {
+                        "" => "(root)",
+                        pointer => pointer,
+                    };
+                    writeln!(out, "{}: {}: {}", name, pointer, error.message)?;
+                }
+            }
+            code
+        }
+    };
+    out.flush()?;
+    Ok(code)
+}
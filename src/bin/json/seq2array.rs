@@ -0,0 +1,129 @@
+use crate::{FormatFlags, Source};
+use anyhow::{anyhow, bail, Context, Result};
+use grammar::json::{parse_json_bytes, FormatOptions, JsonError, JsonValue};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json seq2array [OPTIONS] [FILE]
+
+Read newline-delimited JSON from FILE, or standard input when FILE is `-` or
+missing, and print its values as one JSON array. Blank lines are ignored.
+Nothing is printed until every line has been read, so an invalid line or a
+failed read leaves no partial array behind.
+
+options:
+  --skip-invalid        report lines that are not JSON and leave them out,
+                        instead of stopping at the first
+  --indent N, --tab, --sort-keys
+                        format the array as the json command does
+  -h, --help            show this help";
+
+pub struct Options {
+    input: Source,
+    skip_invalid: bool,
+    format: FormatOptions,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut input = None;
+        let mut skip_invalid = false;
+        let mut format = FormatFlags::default();
+        while let Some(arg) = args.next() {
+            if format.take(&arg, &mut args)? {
+                continue;
+            }
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--skip-invalid") => skip_invalid = true,
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        // compact unless an indent is asked for
+        format.minify |= format.indent.is_none();
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+            skip_invalid,
+            format: format.options()?,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let (name, reader): (_, Box<dyn BufRead>) = match &options.input {
+        Source::Stdin => ("<stdin>".to_string(), Box::new(io::stdin().lock())),
+        Source::File(path) => (
+            path.display().to_string(),
+            Box::new(BufReader::new(
+                File::open(path).with_context(|| format!("cannot open {}", path.display()))?,
+            )),
+        ),
+    };
+    let compact = options.format.indent.is_none();
+    let element = FormatOptions {
+        trailing_newline: false,
+        ..options.format.clone()
+    };
+    // compact output is kept as text, which is smaller than the values
+    let mut values = Vec::new();
+    let mut array = b"[".to_vec();
+    let mut first = true;
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let line = line.with_context(|| format!("cannot read {}", name))?;
+        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let value = match parse_json_bytes(line) {
+            Ok(value) => value,
+            Err(e) => {
+                let e = line_error(e, &name, i + 1, line);
+                if !options.skip_invalid {
+                    return Err(e);
+                }
+                eprintln!("json: {}", e);
+                continue;
+            }
+        };
+        if !compact {
+            values.push(value);
+            continue;
+        }
+        if !first {
+            array.push(b',');
+        }
+        first = false;
+        value.to_writer_with(&mut array, &element)?;
+    }
+    let mut out = BufWriter::new(io::stdout().lock());
+    if compact {
+        array.extend_from_slice(b"]\n");
+        out.write_all(&array)?;
+    } else {
+        JsonValue::Array(values).to_writer_with(&mut out, &options.format)?;
+    }
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// An error on line `number` of the input as `NAME:LINE:COLUMN: message`.
+pub fn line_error(err: JsonError, name: &str, number: usize, line: &[u8]) -> anyhow::Error {
+    match err {
+        JsonError::Parse {
+            offset, message, ..
+        } => {
+            let before = String::from_utf8_lossy(&line[..offset.min(line.len())]);
+            let column = before.chars().count() + 1;
+            anyhow!("{}:{}:{}: {}", name, number, column, message)
+        }
+        e => anyhow!("{}:{}: {}", name, number, e),
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{located, read_prefix, Source};
+use anyhow::{anyhow, bail, Context, Result};
+use grammar::json::{
+    stats_from_reader, FormatOptions, JsonError, JsonObject, JsonStats, JsonValue, MaybeGzip,
+};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json stats [OPTIONS] [FILE]
+
+Count what the document in FILE, or standard input when FILE is `-` or
+missing, holds: values of each type, members and elements, distinct keys,
+the deepest nesting and the string sizes. The document is read in one pass
+and never built, so any size works.
+
+options:
+  --json      print the counts as a JSON object
+  -h, --help  show this help";
+
+pub struct Options {
+    input: Source,
+    json: bool,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut input = None;
+        let mut json = false;
+        for arg in args {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--json") => json = true,
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+            json,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let (stats, total) = match &options.input {
+        Source::Stdin => {
+            // nothing is kept to show a line and column in, so errors
+            // give the offset
+            let stdin = MaybeGzip::new(io::stdin().lock()).context("cannot read standard input")?;
+            let mut input = Counted {
+                inner: stdin,
+                bytes: 0,
+            };
+            let stats = stats_from_reader(&mut input).map_err(|e| match e {
+                JsonError::Io(e) => anyhow::Error::new(e).context("cannot read standard input"),
+                e => anyhow!("<stdin>: {}", e),
+            })?;
+            (stats, input.bytes)
+        }
+        Source::File(path) => {
+            let file = File::open(path)
+                .and_then(MaybeGzip::new)
+                .with_context(|| format!("cannot open {}", path.display()))?;
+            let mut input = Counted {
+                inner: file,
+                bytes: 0,
+            };
+            let stats = stats_from_reader(&mut input).map_err(|e| {
+                let before = match &e {
+                    JsonError::Parse { offset, .. } => {
+                        read_prefix(path, *offset).unwrap_or_default()
+                    }
+                    _ => Vec::new(),
+                };
+                match e {
+                    JsonError::Io(e) => {
+                        anyhow::Error::new(e).context(format!("cannot read {}", path.display()))
+                    }
+                    e => located(e, &path.display().to_string(), &before),
+                }
+            })?;
+            (stats, input.bytes)
+        }
+    };
+    let rows = rows(&stats, total);
+    let mut out = io::stdout().lock();
+    if options.json {
+        let obj: JsonObject = rows
+            .iter()
+            .map(|(key, _, n)| ((*key).into(), JsonValue::from(*n as i64)))
+            .collect();
+        let format = FormatOptions::pretty()
+            .sort_keys(true)
+            .trailing_newline(true);
+        JsonValue::Object(obj).to_writer_with(&mut out, &format)?;
+    } else {
+        for (_, label, n) in rows {
+            writeln!(out, "{:<16}{:>12}", label, n)?;
+        }
+    }
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The counts under their JSON key and their label in the table.
+fn rows(stats: &JsonStats, total: u64) -> Vec<(&'static str, &'static str, u64)> {
+    let n = |n: usize| n as u64;
+    vec![
+        ("values", "values", n(stats.values())),
+        ("nulls", "nulls", n(stats.nulls)),
+        ("bools", "booleans", n(stats.bools)),
+        ("numbers", "numbers", n(stats.numbers)),
+        ("strings", "strings", n(stats.strings)),
+        ("arrays", "arrays", n(stats.arrays)),
+        ("objects", "objects", n(stats.objects)),
+        ("elements", "elements", n(stats.elements)),
+        ("members", "members", n(stats.members)),
+        ("distinct_keys", "distinct keys", n(stats.distinct_keys)),
+        ("max_depth", "max depth", n(stats.max_depth)),
+        ("longest_string", "longest string", n(stats.longest_string)),
+        ("string_bytes", "string bytes", n(stats.string_bytes)),
+        ("total_bytes", "total bytes", total),
+    ]
+}
+
+/// A reader counting the bytes read through it.
+struct Counted<R> {
+    inner: R,
+    bytes: u64,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{located, read_bytes, replace, Source};
+use anyhow::{anyhow, bail, Context, Result};
+use grammar::json::{minify, strip_comments, validate};
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json strip-comments [OPTIONS] [FILE]
+
+Print the JSONC in FILE, or standard input when FILE is `-` or missing, as
+strict JSON: each comment is blanked out with spaces, keeping its line
+breaks, so the layout stays as it was. Input that is not JSON once the
+comments are gone is refused.
+
+options:
+  --minify    drop all insignificant whitespace too
+  --write     rewrite FILE in place instead of printing it
+  -h, --help  show this help";
+
+pub struct Options {
+    input: Source,
+    minify: bool,
+    write: bool,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut input = None;
+        let mut minify = false;
+        let mut write = false;
+        for arg in args {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--minify") => minify = true,
+                Some("--write") => write = true,
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        let input = input.unwrap_or(Source::Stdin);
+        if write && matches!(input, Source::Stdin) {
+            bail!("--write needs a file");
+        }
+        Ok(Some(Options {
+            input,
+            minify,
+            write,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let (name, original) = read_bytes(&options.input)?;
+    let text = std::str::from_utf8(&original)
+        .map_err(|e| anyhow!("{}: invalid UTF-8 at offset {}", name, e.valid_up_to()))?;
+    // offsets still match the original, comments being blanked in place
+    let mut stripped = strip_comments(text)
+        .and_then(|stripped| validate(&stripped).map(|()| stripped))
+        .map_err(|e| located(e, &name, &original))?;
+    if options.minify {
+        stripped = minify(&stripped)?;
+        if original.ends_with(b"\n") {
+            stripped.push('\n');
+        }
+    }
+    match &options.input {
+        Source::File(path) if options.write => {
+            if stripped.as_bytes() != original {
+                replace(path, stripped.as_bytes())
+                    .with_context(|| format!("cannot write {}", path.display()))?;
+            }
+        }
+        _ => {
+            let mut out = io::stdout().lock();
+            out.write_all(stripped.as_bytes())?;
+            if !stripped.ends_with('\n') {
+                out.write_all(b"\n")?;
+            }
+            out.flush()?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,62 @@
+use crate::{read_value, value, Source};
+use anyhow::{bail, Result};
+use grammar::json::{to_csv_with, CsvOptions};
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json to-csv [OPTIONS] [FILE]
+
+Print the array of objects in FILE, or standard input when FILE is `-` or
+missing, as RFC 4180 CSV: a header row of the keys, sorted, then a row per
+object, with empty cells for missing keys and nulls.
+
+options:
+  --columns A,B,C  write just these columns, in this order
+  --flatten        spread nested values over columns named like address.city
+  -h, --help       show this help";
+
+pub struct Options {
+    input: Source,
+    csv: CsvOptions,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut input = None;
+        let mut csv = CsvOptions::default();
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--flatten") => csv.flatten = true,
+                Some(flag @ "--columns") => {
+                    let columns = value(flag, &mut args)?;
+                    let Some(columns) = columns.to_str() else {
+                        bail!("--columns needs UTF-8 names");
+                    };
+                    csv.columns = Some(columns.split(',').map(str::to_string).collect());
+                }
+                Some("-") => input = Some(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ if input.is_some() => bail!("more than one input given"),
+                _ => input = Some(Source::File(arg.into())),
+            }
+        }
+        Ok(Some(Options {
+            input: input.unwrap_or(Source::Stdin),
+            csv,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let value = read_value(&options.input)?;
+    let csv = to_csv_with(&value, &options.csv)?;
+    let mut out = io::stdout().lock();
+    out.write_all(csv.as_bytes())?;
+    out.flush()?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,158 @@
+use anyhow::{anyhow, bail, Context, Result};
+use grammar::json::{
+    offset_to_line_col, validate_with, FormatOptions, JsonError, JsonObject, JsonValue, MaybeGzip,
+    ParseOptions,
+};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+pub const USAGE: &str = "usage: json validate [OPTIONS] [FILE...]
+
+Check that each FILE, or standard input when FILE is `-` or none is given,
+is JSON. Valid input prints nothing; each invalid one prints
+FILE:LINE:COLUMN: message. The exit status is 0 only if all of them are
+valid.
+
+options:
+  --format F               text (the default) or json, one object per line
+                           with file, offset, line, column and message
+  --json5                  accept everything JSON5 adds
+  --allow-comments         accept // and /* */ comments
+  --allow-trailing-commas  accept a comma before ] or }
+  --allow-single-quotes    accept 'single quoted' strings
+  --allow-unquoted-keys    accept identifier object keys
+  --allow-nonfinite        accept Infinity, -Infinity and NaN
+  -h, --help               show this help";
+
+pub struct Options {
+    inputs: Vec<Option<PathBuf>>,
+    parse: ParseOptions,
+    json: bool,
+}
+
+/// Why one input is not valid.
+struct Diagnostic {
+    offset: usize,
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = OsString>) -> Result<Option<Self>> {
+        let mut args = args;
+        let mut inputs = Vec::new();
+        let mut parse = ParseOptions::strict();
+        let mut json = false;
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("--format") => {
+                    let format = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--format needs a value"))?;
+                    json = match format.to_str() {
+                        Some("text") => false,
+                        Some("json") => true,
+                        _ => bail!("--format is text or json"),
+                    };
+                }
+                Some("--json5") => parse = ParseOptions::json5(),
+                Some("--allow-comments") => parse.allow_comments = true,
+                Some("--allow-trailing-commas") => parse.allow_trailing_commas = true,
+                Some("--allow-single-quotes") => parse.allow_single_quotes = true,
+                Some("--allow-unquoted-keys") => parse.allow_unquoted_keys = true,
+                Some("--allow-nonfinite") => parse.allow_nonfinite = true,
+                Some("-") => inputs.push(None),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {}", flag),
+                _ => inputs.push(Some(arg.into())),
+            }
+        }
+        if inputs.is_empty() {
+            inputs.push(None);
+        }
+        Ok(Some(Options {
+            inputs,
+            parse,
+            json,
+        }))
+    }
+}
+
+pub fn run(options: &Options) -> Result<ExitCode> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut code = ExitCode::SUCCESS;
+    for input in &options.inputs {
+        let mut bytes = Vec::new();
+        let name = match input {
+            Some(path) => {
+                File::open(path)
+                    .and_then(MaybeGzip::new)
+                    .and_then(|mut file| file.read_to_end(&mut bytes))
+                    .with_context(|| format!("cannot read {}", path.display()))?;
+                path.display().to_string()
+            }
+            None => {
+                MaybeGzip::new(io::stdin().lock())
+                    .and_then(|mut stdin| stdin.read_to_end(&mut bytes))
+                    .context("cannot read standard input")?;
+                "<stdin>".to_string()
+            }
+        };
+        let Some(diagnostic) = check(&bytes, &options.parse) else {
+            continue;
+        };
+        code = ExitCode::FAILURE;
+        if options.json {
+            writeln!(out, "{}", diagnostic.to_json(&name))?;
+        } else {
+            writeln!(
+                out,
+                "{}:{}:{}: {}",
+                name, diagnostic.line, diagnostic.column, diagnostic.message
+            )?;
+        }
+    }
+    out.flush()?;
+    Ok(code)
+}
+
+/// What is wrong with `bytes`, if anything.
+fn check(bytes: &[u8], options: &ParseOptions) -> Option<Diagnostic> {
+    let (offset, message) = match std::str::from_utf8(bytes) {
+        Ok(text) => match validate_with(text, options) {
+            Ok(()) => return None,
+            Err(JsonError::Parse {
+                offset, message, ..
+            }) => (offset, message),
+            Err(e) => (0, e.to_string()),
+        },
+        Err(e) => (e.valid_up_to(), "invalid UTF-8".to_string()),
+    };
+    let before = String::from_utf8_lossy(&bytes[..offset.min(bytes.len())]);
+    let (line, column) = offset_to_line_col(&before, before.len());
+    Some(Diagnostic {
+        offset,
+        line,
+        column,
+        message,
+    })
+}
+
+impl Diagnostic {
+    /// The diagnostic as one compact JSON object, its keys sorted.
+    fn to_json(&self, file: &str) -> String {
+        let mut object = JsonObject::default();
+        object.insert("file".into(), file.into());
+        object.insert("offset".into(), (self.offset as i64).into());
+        object.insert("line".into(), (self.line as i64).into());
+        object.insert("column".into(), (self.column as i64).into());
+        object.insert("message".into(), self.message.as_str().into());
+        JsonValue::Object(object).to_string_with(&FormatOptions::compact().sort_keys(true))
+    }
+}
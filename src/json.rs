@@ -1,52 +1,666 @@
-use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use std::{borrow::Cow, fmt, sync::Arc};
+use trace::rule;
 use winnow::{
-    ascii::{digit1, multispace0},
-    combinator::{alt, delimited, opt, separated, separated_pair, trace},
-    error::{ContextError, ErrMode, ParserError},
+    ascii::{digit0, digit1, multispace0},
+    combinator::{alt, cut_err, fail, opt, peek, preceded, separated_pair, trace},
+    error::{ContextError, ErrMode, ErrorKind, FromExternalError, ParserError, StrContext},
     prelude::*,
-    stream::{AsChar, Stream, StreamIsPartial},
-    token::take_until,
+    stream::{AsChar, Stateful, Stream, StreamIsPartial},
+    token::{any, one_of, take_till, take_while},
 };
 
+mod arena;
+#[cfg(feature = "async")]
+mod async_reader;
+mod base64;
+mod borrowed;
+mod canonical;
+#[cfg(feature = "cbor")]
+mod cbor;
+mod codegen;
+mod coerce;
+mod columns;
+mod compare;
+mod cst;
+mod csv;
+#[cfg(feature = "time")]
+mod datetime;
+#[cfg(feature = "decimal")]
+mod decimal;
+mod dedup;
+mod diagnostic;
+mod error;
+mod events;
+mod expand;
+mod extract;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod flatten;
+mod form;
+mod formatter;
+mod fuzz;
+mod fxhash;
+#[cfg(feature = "gzip")]
+mod gzip;
+mod handler;
+mod incremental;
+mod infer;
+mod intern;
+mod jsonc;
+mod keys;
+mod lazy;
+mod lint;
+mod merge;
+mod minify;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod num;
+mod ordered;
+#[cfg(feature = "parallel")]
+mod parallel;
+pub mod parsers;
+mod patch;
+mod pointer;
+mod preview;
+mod raw;
+mod reader;
+mod redact;
+mod relative;
+mod report;
+mod reuse;
+mod schema;
+mod ser;
+#[cfg(feature = "serde")]
+mod serde_de;
+#[cfg(feature = "serde-json")]
+mod serde_json_value;
+#[cfg(feature = "serde")]
+mod serde_ser;
+#[cfg(feature = "serde")]
+mod serde_value;
+mod shared;
+mod size;
+mod skip;
+mod smallstr;
+mod snippet;
+mod spanned;
+mod stats;
+mod stream;
+mod tokenizer;
+mod trace;
+mod transcode;
+#[cfg(feature = "uuid")]
+mod uuid;
+mod warning;
+mod writer;
+mod yaml;
+
+pub use arena::{parse_in, ArenaValue, JsonArena};
+#[cfg(feature = "async")]
+pub use async_reader::{from_async_reader, AsyncNdjson, AsyncRead};
+pub use borrowed::{parse_json_borrowed, JsonValueRef};
+#[cfg(feature = "cbor")]
+pub use cbor::{from_cbor, to_cbor};
+pub use codegen::{generate_rust_types, generate_rust_types_with, RustTypesOptions};
+pub use coerce::ScalarDisplay;
+pub use columns::{from_columns, to_columns};
+pub use compare::{
+    json_assert_eq, json_assert_eq_with, json_eq, json_eq_with, semantic_eq, semantic_eq_with,
+    CompareOptions, Difference,
+};
+pub use cst::{parse_cst, Container, Cst, Entry, Key, Node, Token, TokenKind};
+pub use csv::{from_csv, to_csv, to_csv_with, CsvError, CsvOptions};
+#[cfg(feature = "time")]
+pub use datetime::EpochUnit;
+#[cfg(feature = "decimal")]
+pub use decimal::{Decimal, DecimalError};
+pub use diagnostic::JsonReport;
+pub use error::{ErrorCode, JsonError};
+pub use events::{JsonEvent, JsonReader};
+pub use expand::{expand_vars, ExpandError};
+pub use extract::{extract_all_json, extract_json};
+pub use flatten::{flatten, unflatten, FlattenOptions};
+pub use form::{
+    from_form_urlencoded, from_form_urlencoded_with, to_form_urlencoded, to_form_urlencoded_with,
+    FormOptions,
+};
+pub use formatter::{
+    CharEscape, ColorScheme, ColoredFormatter, CompactFormatter, Formatter, Json5Formatter,
+    PrettyFormatter,
+};
+pub use fuzz::fuzz_one;
+pub use fxhash::FxHasher;
+#[cfg(feature = "gzip")]
+pub use gzip::GzDecoder;
+pub use handler::{
+    parse_with_handler, BuildError, JsonHandler, ParseOutcome, TreeBuilder, ValueBuilder,
+};
+pub use incremental::{partial_value, StreamingParser};
+pub use infer::{infer_schema, infer_schema_many};
+pub use intern::{parse_json_interned, InternedValue};
+pub use jsonc::strip_comments;
+pub use keys::{key_tree, top_level_keys};
+pub use lazy::{parse_json_lazy, parse_json_lazy_with, LazyValue};
+pub use lint::{lint, lint_with, LintOptions, LintRule, LintWarning};
+pub use merge::{ArrayStrategy, MergeDecision};
+pub use minify::minify;
+#[cfg(feature = "msgpack")]
+pub use msgpack::{from_msgpack, to_msgpack};
+pub use ordered::OrderedMap;
+#[cfg(feature = "parallel")]
+pub use parallel::parse_large_array_parallel;
+pub use patch::{
+    apply_patch, diff, diff_with, patch_from_json, patch_to_json, DiffOptions, PatchError, PatchOp,
+};
+pub use pointer::{
+    get_pointer_streaming, AsPointer, PathSeg, Pointer, PointerError, PointerTokens,
+};
+pub use preview::PreviewLimits;
+pub use raw::{parse_json_with_raw, RawValue};
+pub use reader::{
+    for_each_element_from_reader, from_file, from_reader, from_reader_auto, from_reader_with,
+    parse_json_bytes, parse_json_bytes_lossy, MaybeGzip,
+};
+pub use redact::REDACTED;
+pub use relative::RelResult;
+pub use report::{diff_report, DiffReport, ReportChange, ReportEntry, ReportResize};
+pub use reuse::{parse_json_into, Scratch};
+pub use schema::{Schema, SchemaError, SchemaType, ValidationError};
+pub use ser::{
+    escape_str, EscapeOptions, FormatOptions, Indent, KeyOrder, Newline, NonFiniteBehavior,
+};
+#[cfg(feature = "serde")]
+pub use serde_de::{from_str, from_value, Deserializer};
+#[cfg(feature = "serde")]
+pub use serde_ser::{to_string_via_serde, to_value, to_value_with, BytesFormat, ToValueOptions};
+pub use shared::{SharedJson, SharedObject};
+pub use skip::{is_valid_json, skip_value, validate, validate_with};
+pub use smallstr::SmallString;
+pub use spanned::{offset_to_line_col, parse_json_spanned, Member, Spanned, SpannedValue};
+pub use stats::{stats, stats_from_reader, JsonStats};
+pub use stream::{
+    for_each_element, iter_array, iter_values, parse_json_seq, parse_ndjson, write_json_seq,
+    ArrayIter,
+};
+pub use tokenizer::Tokenizer;
+pub use trace::{parse_json_traced, parse_json_traced_with, ParseTrace, TraceEvent};
+pub use transcode::transcode;
+#[cfg(feature = "uuid")]
+pub use uuid::{Uuid, UuidError};
+pub use warning::{parse_json_with_warnings, JsonWarning, WarningCode};
+pub use writer::{ArrayWriter, JsonWriter, ObjectWriter};
+pub use yaml::to_yaml_string;
+
 #[derive(Debug, Clone, PartialEq)]
-enum Num {
+pub enum Num {
     Int(i64),
     Float(f64),
+    /// The number exactly as spelled in the input (`1.000`, `1e+5`), kept
+    /// when parsing with [`ParseOptions::preserve_number_text`] and written
+    /// back verbatim. It must be a valid JSON number token. Compares by text,
+    /// so `Raw("1.0")` is not equal to `Float(1.0)`.
+    Raw(String),
+    /// An exact decimal, parsed from a number with a fraction or exponent
+    /// under [`ParseOptions::parse_decimals`] and written back digit for
+    /// digit.
+    #[cfg(feature = "decimal")]
+    Decimal(Decimal),
 }
 
-#[allow(unused)]
-#[derive(Debug, Clone, PartialEq)]
-enum JsonValue {
+/// The map behind [`JsonValue::Object`]. Create one with
+/// `JsonObject::default()` or by collecting pairs, so code works with every
+/// backing. By default it is a `HashMap` with [`ObjectHasher`]. The
+/// `preserve-order` feature makes it an [`OrderedMap`], whose members iterate
+/// and serialize in the order they were parsed or inserted; failing that the
+/// `sorted-keys` feature makes it a `BTreeMap`, which keeps them sorted.
+#[cfg(not(any(feature = "preserve-order", feature = "sorted-keys")))]
+pub type JsonObject = std::collections::HashMap<JsonString, JsonValue, ObjectHasher>;
+
+#[cfg(feature = "preserve-order")]
+pub type JsonObject = OrderedMap<JsonValue>;
+
+#[cfg(all(feature = "sorted-keys", not(feature = "preserve-order")))]
+pub type JsonObject = std::collections::BTreeMap<JsonString, JsonValue>;
+
+/// The hasher of [`JsonObject`]: the standard DoS-resistant SipHash.
+#[cfg(not(feature = "fast-hash"))]
+pub type ObjectHasher = std::collections::hash_map::RandomState;
+
+/// The hasher of [`JsonObject`]: [`FxHasher`], much cheaper than SipHash
+/// but open to flooding by adversarial keys.
+#[cfg(feature = "fast-hash")]
+pub type ObjectHasher = std::hash::BuildHasherDefault<FxHasher>;
+
+/// The text of [`JsonValue::String`] and of object keys. It is `String`
+/// by default; the `compact-str` feature makes it a [`SmallString`], which
+/// keeps short text inline. Either derefs to `str`. Code meant for both
+/// builds one with [`json_string`] or `.into()` from `&str`.
+#[cfg(not(feature = "compact-str"))]
+pub type JsonString = String;
+
+#[cfg(feature = "compact-str")]
+pub type JsonString = SmallString;
+
+/// Turn anything a [`JsonString`] is built from, such as `&str`, `String`
+/// or `char`, into one, whichever type it is.
+pub fn json_string(s: impl Into<JsonString>) -> JsonString {
+    s.into()
+}
+
+/// A [`JsonString`] as a `String`, free when it already is one.
+pub(crate) fn into_std_string(s: JsonString) -> String {
+    #[allow(clippy::useless_conversion)]
+    s.into()
+}
+
+#[derive(Clone, PartialEq)]
+pub enum JsonValue {
     Null,
     Bool(bool),
     Number(Num),
-    String(String),
+    String(JsonString),
     Array(Vec<JsonValue>),
-    Object(HashMap<String, JsonValue>),
+    Object(JsonObject),
+    /// Unparsed text of a value, written back verbatim.
+    Raw(RawValue),
 }
 
-fn main() -> Result<()> {
-    let s = r#"{
-        "name": "John Doe",
-        "age": 30,
-        "is_student": false,
-        "marks": [90.0, -80.0, 85.1],
-        "address": {
-            "city": "New York",
-            "zip": 10001
+/// As derived, but with the members of an object in key order, so the
+/// output is the same whatever order the map behind [`JsonObject`] keeps.
+impl fmt::Debug for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Members<'a>(&'a JsonObject);
+
+        impl fmt::Debug for Members<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut members: Vec<_> = self.0.iter().collect();
+                members.sort_by(|a, b| a.0.cmp(b.0));
+                f.debug_map().entries(members).finish()
+            }
         }
-    }"#;
 
-    let input = &mut (&*s);
-    let v = parse_json(input)?;
-    println!("{:#?}", v);
-    Ok(())
+        match self {
+            JsonValue::Null => f.write_str("Null"),
+            JsonValue::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            JsonValue::Number(n) => f.debug_tuple("Number").field(n).finish(),
+            JsonValue::String(s) => f.debug_tuple("String").field(s).finish(),
+            JsonValue::Array(arr) => f.debug_tuple("Array").field(arr).finish(),
+            JsonValue::Object(obj) => f.debug_tuple("Object").field(&Members(obj)).finish(),
+            JsonValue::Raw(raw) => f.debug_tuple("Raw").field(raw).finish(),
+        }
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self {
+        JsonValue::Bool(b)
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(i: i64) -> Self {
+        JsonValue::Number(Num::Int(i))
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(f: f64) -> Self {
+        JsonValue::Number(Num::Float(f))
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self {
+        JsonValue::String(json_string(s))
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self {
+        JsonValue::String(json_string(s))
+    }
+}
+
+/// Switches for the parser. The default is strict RFC 8259 JSON; start from
+/// [`ParseOptions::strict`] or [`ParseOptions::json5`] and chain setters:
+///
+/// ```
+/// use grammar::json::{parse_json_with, ParseOptions};
+///
+/// let jsonc = ParseOptions::strict().allow_comments(true).allow_trailing_commas(true);
+/// assert!(parse_json_with("[1, /* two */ 2,]", &jsonc).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Keep every number as [`Num::Raw`] with its original text instead of
+    /// converting it, so serializing reproduces the input spelling.
+    pub preserve_number_text: bool,
+    /// Treat `//` line comments and non-nesting `/* */` block comments as
+    /// whitespace, as JSONC (tsconfig.json, VS Code settings) does.
+    pub allow_comments: bool,
+    /// Accept one trailing comma before the closing bracket of a non-empty
+    /// array or object, as in `[1, 2,]`.
+    pub allow_trailing_commas: bool,
+    /// Accept `'single quoted'` strings for values and keys. Inside them `"`
+    /// needs no escape and `\'` is a valid one.
+    pub allow_single_quotes: bool,
+    /// Accept identifier object keys such as `{foo: 1, $bar_2: true}`: ASCII
+    /// letters, `_` and `$`, plus digits after the first character.
+    pub allow_unquoted_keys: bool,
+    /// Accept JSON5 number spellings: hexadecimal integers (`0xFF`, `-0X1f`),
+    /// a missing integer or fraction part (`.5`, `5.`) and a leading `+`.
+    pub allow_json5_numbers: bool,
+    /// Accept the `Infinity`, `-Infinity` and `NaN` literals as floats.
+    pub allow_nonfinite: bool,
+    /// Let a backslash directly followed by a line terminator (`\n`, `\r` or
+    /// `\r\n`) continue a string on the next line without adding a newline.
+    pub allow_line_continuations: bool,
+    /// Fail on arrays and objects nested more than this many levels deep,
+    /// so hostile input cannot overflow the stack of a recursive parser or
+    /// of whatever walks the value later, down to dropping it. The default
+    /// is [`ParseOptions::DEFAULT_MAX_DEPTH`]; `None` sets no limit, which
+    /// only trusted input should get.
+    pub max_depth: Option<usize>,
+    /// Fail on an array with more elements than this, as soon as the one
+    /// past the limit starts, so a huge array is never held. `None`, the
+    /// default, sets no limit.
+    pub max_array_len: Option<usize>,
+    /// [`max_array_len`](Self::max_array_len) for the members of each
+    /// object, repeated keys included.
+    pub max_object_members: Option<usize>,
+    /// Fail once the value being built would take more than this many
+    /// bytes, as [`JsonValue::deep_size_of`] counts them. The parser keeps a
+    /// running total, adding the slot of each value as its element or member
+    /// starts and the text of each string, key and raw number before it is
+    /// copied, so the copy that would cross the budget is never made. The
+    /// error has [`ErrorCode::LimitExceeded`] and the offset of the value
+    /// that did not fit. `None`, the default, sets no limit.
+    pub max_total_allocated: Option<usize>,
+    /// Parse a number written with an exponent but no fraction, such as
+    /// `1e3` or `2E+6`, as the [`Num::Int`] it stands for when it fits,
+    /// instead of as a float. A negative exponent keeps it a float.
+    pub integerize_exponents: bool,
+    /// Parse an integer too large for [`Num::Int`] as the nearest float,
+    /// as most parsers do, rather than failing with "integer out of range".
+    /// On by default.
+    pub big_int_as_float: bool,
+    /// Parse every number as a [`Num::Float`], integers included, as
+    /// JavaScript does, so values compare like those of a JS service: one
+    /// past 2^53, such as `9007199254740993`, rounds to the nearest double
+    /// without notice and is written back in the shortest form that reads
+    /// as that double. Takes precedence over `integerize_exponents` and
+    /// `parse_decimals` but not `preserve_number_text`.
+    pub numbers_as_f64: bool,
+    /// Parse numbers with a fraction or an exponent as an exact
+    /// [`Num::Decimal`] instead of an `f64`. Integers stay [`Num::Int`] and
+    /// `preserve_number_text` takes precedence.
+    #[cfg(feature = "decimal")]
+    pub parse_decimals: bool,
+    /// Fail with [`JsonError::PrecisionLoss`] instead of rounding a number
+    /// that would become an `f64` other than the decimal it spells, like
+    /// `0.1`, or an integer too large for [`Num::Int`]. Numbers kept as
+    /// [`Num::Raw`] or [`Num::Decimal`] are exact and never fail.
+    ///
+    /// The check takes the token apart as `d × 10^e`: for `e >= 0` the value
+    /// is exact when `d × 5^e` is a 53-bit significand times a power of two,
+    /// and for `e < 0` when `5^-e` divides `d` and the quotient is. It works
+    /// in `u128`, so it is never fooled into passing a rounded number, but
+    /// one with more than 38 significant digits fails even when, like a
+    /// written-out power of two, it happens to be exact.
+    pub error_on_precision_loss: bool,
+    /// Asked every [`ParseOptions::CANCEL_INTERVAL`] bytes of input, between
+    /// the entries of arrays and objects, whether to give up; once it says
+    /// yes the parse fails with [`JsonError::Cancelled`]. It is also asked at
+    /// the first entry, so a document is not started once cancelled. The
+    /// probe may capture state, such as an `Arc<AtomicBool>` set by another
+    /// thread, so each parse can be cancelled on its own.
+    pub cancel: Option<CancelProbe>,
+    /// Fail with [`JsonError::ScalarRoot`] when the document is a scalar
+    /// rather than an object or array, as RFC 4627 had it. Whitespace and
+    /// comments around the root are skipped first.
+    pub require_container: bool,
+}
+
+/// The function behind [`ParseOptions::cancel`], shared by the clones of
+/// the options. Probes are equal when they are clones of one another.
+#[derive(Clone)]
+pub struct CancelProbe(Arc<dyn Fn() -> bool + Send + Sync>);
+
+impl CancelProbe {
+    pub fn new(probe: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        CancelProbe(Arc::new(probe))
+    }
+
+    /// Whether to give up.
+    pub fn cancelled(&self) -> bool {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for CancelProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CancelProbe(..)")
+    }
+}
+
+impl PartialEq for CancelProbe {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancelProbe {}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            preserve_number_text: false,
+            allow_comments: false,
+            allow_trailing_commas: false,
+            allow_single_quotes: false,
+            allow_unquoted_keys: false,
+            allow_json5_numbers: false,
+            allow_nonfinite: false,
+            allow_line_continuations: false,
+            max_depth: Some(Self::DEFAULT_MAX_DEPTH),
+            max_array_len: None,
+            max_object_members: None,
+            max_total_allocated: None,
+            integerize_exponents: false,
+            big_int_as_float: true,
+            numbers_as_f64: false,
+            #[cfg(feature = "decimal")]
+            parse_decimals: false,
+            error_on_precision_loss: false,
+            cancel: None,
+            require_container: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// The nesting every parser accepts unless told otherwise.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// The bytes parsed between two calls of [`cancel`](Self::cancel):
+    /// about a tenth of a millisecond of parsing.
+    pub const CANCEL_INTERVAL: usize = 64 * 1024;
+
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    /// Everything JSON5 adds to JSON. Raw number text stays off.
+    pub fn json5() -> Self {
+        Self {
+            preserve_number_text: false,
+            allow_comments: true,
+            allow_trailing_commas: true,
+            allow_single_quotes: true,
+            allow_unquoted_keys: true,
+            allow_json5_numbers: true,
+            allow_nonfinite: true,
+            allow_line_continuations: true,
+            max_depth: Some(Self::DEFAULT_MAX_DEPTH),
+            max_array_len: None,
+            max_object_members: None,
+            max_total_allocated: None,
+            integerize_exponents: false,
+            big_int_as_float: true,
+            numbers_as_f64: false,
+            #[cfg(feature = "decimal")]
+            parse_decimals: false,
+            error_on_precision_loss: false,
+            cancel: None,
+            require_container: false,
+        }
+    }
+
+    #[cfg(feature = "decimal")]
+    pub fn parse_decimals(mut self, yes: bool) -> Self {
+        self.parse_decimals = yes;
+        self
+    }
+
+    pub fn cancel(mut self, probe: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        self.cancel = Some(CancelProbe::new(probe));
+        self
+    }
+
+    pub fn error_on_precision_loss(mut self, yes: bool) -> Self {
+        self.error_on_precision_loss = yes;
+        self
+    }
+
+    pub fn big_int_as_float(mut self, yes: bool) -> Self {
+        self.big_int_as_float = yes;
+        self
+    }
+
+    pub fn numbers_as_f64(mut self, yes: bool) -> Self {
+        self.numbers_as_f64 = yes;
+        self
+    }
+
+    pub fn integerize_exponents(mut self, yes: bool) -> Self {
+        self.integerize_exponents = yes;
+        self
+    }
+
+    pub fn preserve_number_text(mut self, yes: bool) -> Self {
+        self.preserve_number_text = yes;
+        self
+    }
+
+    pub fn allow_comments(mut self, yes: bool) -> Self {
+        self.allow_comments = yes;
+        self
+    }
+
+    pub fn allow_trailing_commas(mut self, yes: bool) -> Self {
+        self.allow_trailing_commas = yes;
+        self
+    }
+
+    pub fn allow_single_quotes(mut self, yes: bool) -> Self {
+        self.allow_single_quotes = yes;
+        self
+    }
+
+    pub fn allow_unquoted_keys(mut self, yes: bool) -> Self {
+        self.allow_unquoted_keys = yes;
+        self
+    }
+
+    pub fn allow_json5_numbers(mut self, yes: bool) -> Self {
+        self.allow_json5_numbers = yes;
+        self
+    }
+
+    pub fn allow_nonfinite(mut self, yes: bool) -> Self {
+        self.allow_nonfinite = yes;
+        self
+    }
+
+    pub fn allow_line_continuations(mut self, yes: bool) -> Self {
+        self.allow_line_continuations = yes;
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    pub fn max_array_len(mut self, len: usize) -> Self {
+        self.max_array_len = Some(len);
+        self
+    }
+
+    pub fn max_object_members(mut self, members: usize) -> Self {
+        self.max_object_members = Some(members);
+        self
+    }
+
+    pub fn max_total_allocated(mut self, bytes: usize) -> Self {
+        self.max_total_allocated = Some(bytes);
+        self
+    }
+
+    pub fn require_container(mut self, yes: bool) -> Self {
+        self.require_container = yes;
+        self
+    }
+}
+
+/// What the parsers carry along besides the text: the options every one of
+/// them consults and how deep in containers they are.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ParseState {
+    options: ParseOptions,
+    /// Arrays and objects open around the current position.
+    depth: usize,
+    /// The length of the input from the last opening bracket on, for
+    /// errors about that container.
+    opened: usize,
+    /// The length of the input when `cancel` was last asked.
+    asked: usize,
+    /// Bytes counted against `max_total_allocated` so far.
+    allocated: usize,
+    /// Record rules into the [`ParseTrace`].
+    tracing: bool,
+}
+
+/// Parser input: the remaining text plus the [`ParseState`].
+type Input<'a> = Stateful<&'a str, ParseState>;
+
+fn stateful<'a>(input: &'a str, options: &ParseOptions) -> Input<'a> {
+    Input {
+        input,
+        state: ParseState {
+            options: options.clone(),
+            depth: 0,
+            opened: 0,
+            // so that the first entry asks
+            asked: usize::MAX,
+            allocated: 0,
+            tracing: false,
+        },
+    }
+}
+
+fn strict(input: &str) -> Input<'_> {
+    stateful(input, &ParseOptions::default())
 }
 
-fn parse_json(input: &str) -> Result<JsonValue> {
-    let input = &mut (&*input);
-    parse_value(input).map_err(|e: ErrMode<ContextError>| anyhow!("Failed to parse JSON: {:?}", e))
+/// Parse strict JSON.
+pub fn parse_json(input: &str) -> Result<JsonValue, JsonError> {
+    parse_json_with(input, &ParseOptions::default())
+}
+
+/// Parse with the leniency switches in `options`.
+pub fn parse_json_with(input: &str, options: &ParseOptions) -> Result<JsonValue, JsonError> {
+    JsonReader::with_options(input, options).into_value()
 }
 
 pub fn sep_with_space<Input, Output, Error, ParseNext>(
@@ -66,237 +680,1975 @@ where
     })
 }
 
-fn parse_null(input: &mut &str) -> PResult<()> {
-    "null".value(()).parse_next(input)
+/// The punctuation `c` with the whitespace around it, skipping comments too
+/// when they are enabled. Each whitespace run is scanned once: the run after
+/// a token belongs to it, so the leading [`ws`] only ever sees a run that
+/// follows a scalar.
+fn sep_with_ws<'a>(mut c: char) -> impl Parser<Input<'a>, (), ContextError> {
+    move |input: &mut Input<'a>| {
+        ws(input)?;
+        c.parse_next(input)?;
+        ws(input)
+    }
 }
 
-fn parse_bool(input: &mut &str) -> PResult<bool> {
-    alt(("true", "false")).parse_to().parse_next(input)
+/// The opening bracket `c` with the whitespace around it, one container
+/// deeper.
+fn open<'a>(mut c: char) -> impl Parser<Input<'a>, (), ContextError> {
+    move |input: &mut Input<'a>| {
+        ws(input)?;
+        peek(c).parse_next(input)?;
+        input.state.opened = input.input.len();
+        descend(input)?;
+        c.parse_next(input)?;
+        ws(input)
+    }
 }
 
-// FIXME: num parse doesn't work with scientific notation, fix it
-fn parse_num(input: &mut &str) -> PResult<Num> {
-    // Process the sign
-    let sign = opt("-").map(|s| s.is_some()).parse_next(input)?;
-
-    // Parse the integer part
-    let num = digit1.parse_to::<i64>().parse_next(input)?;
-
-    // Check if the input is a simple integer
-    if let Ok(_) = <&str as winnow::Parser<&str, &str, ErrMode<ContextError>>>::value::<()>(".", ())
-        .parse_next(input)
+/// Count one more open container, at an opening bracket. Going past
+/// `max_depth` fails there, for good.
+fn descend(input: &mut Input<'_>) -> PResult<()> {
+    let state = &input.state;
+    if state
+        .options
+        .max_depth
+        .is_some_and(|max| state.depth >= max)
     {
-        // If we have a decimal point, parse the fractional part
-        let mut v = num as f64;
-        let frac = digit1.parse_to::<i64>().parse_next(input)?;
-        let frac_length = frac.to_string().len() as f64;
-        v += frac as f64 / 10f64.powf(frac_length);
-
-        // Check for scientific notation
-        let exponent = if let Ok(_) =
-            ("e".value(())
-                .parse_next(input)
-                .or_else(|_: ErrMode<ContextError>| {
-                    <&str as winnow::Parser<&str, &str, ErrMode<ContextError>>>::value::<()>(
-                        "E",
-                        (),
-                    )
-                    .parse_next(input)
-                })) {
-            // Parse the exponent sign
-            let exp_sign = opt("-").map(|s| s.is_some()).parse_next(input)?;
-
-            // Parse the exponent value
-            let exp_value = digit1.parse_to::<i64>().parse_next(input)?;
-
-            // Determine the final exponent value
-            if exp_sign {
-                -exp_value
-            } else {
-                exp_value
-            }
-        } else {
-            // If parsing the exponent fails, assume no exponent
-            0
-        };
+        return cut_err(fail)
+            .context(StrContext::Label(error::DEPTH_EXCEEDED))
+            .parse_next(input);
+    }
+    input.state.depth += 1;
+    Ok(())
+}
 
-        // Apply the exponent
-        v *= 10f64.powi(exponent.try_into().expect("Invalid exponent value"));
+/// Fail for good if the container whose bracket starts where `opened` bytes
+/// of input are left already has `entries`, as many as `max_array_len` or
+/// `max_object_members` allow.
+fn entry_limit(input: &mut Input<'_>, opened: usize, entries: usize, object: bool) -> PResult<()> {
+    let options = &input.state.options;
+    let limit = match object {
+        true => options.max_object_members,
+        false => options.max_array_len,
+    };
+    match limit {
+        Some(limit) if entries == limit => {
+            let hit = error::EntryLimit {
+                left: opened,
+                limit,
+                object,
+            };
+            let err = ContextError::from_external_error(input, ErrorKind::Verify, hit);
+            Err(ErrMode::Cut(err))
+        }
+        _ => Ok(()),
+    }
+}
 
-        // Return the final result with the correct sign
-        Ok(if sign { Num::Float(-v) } else { Num::Float(v) })
-    } else {
-        // If there's no decimal point, return an integer value
-        Ok(if sign { Num::Int(-num) } else { Num::Int(num) })
+/// Count `bytes` more against `max_total_allocated`, failing for good at
+/// `start`, where the value they are for began, if that is too many.
+fn allocate<'a>(
+    input: &mut Input<'a>,
+    start: &<Input<'a> as Stream>::Checkpoint,
+    bytes: usize,
+) -> PResult<()> {
+    let Some(max) = input.state.options.max_total_allocated else {
+        return Ok(());
+    };
+    let total = input.state.allocated.saturating_add(bytes);
+    if total > max {
+        input.reset(start);
+        return cut_err(fail)
+            .context(StrContext::Label(error::ALLOCATION_BUDGET))
+            .parse_next(input);
     }
+    input.state.allocated = total;
+    Ok(())
 }
-// json allows quoted strings to have escaped characters, we won't handle that here
-fn parse_string(input: &mut &str) -> PResult<String> {
-    let ret = delimited('"', take_until(0.., '"'), '"').parse_next(input)?;
-    Ok(ret.to_string())
+
+/// [`allocate`] for a value that starts where `input` stands.
+fn allocate_here(input: &mut Input<'_>, bytes: usize) -> PResult<()> {
+    let start = input.checkpoint();
+    allocate(input, &start, bytes)
 }
 
-fn parse_array(input: &mut &str) -> PResult<Vec<JsonValue>> {
-    let sep1 = sep_with_space('[');
-    let sep2 = sep_with_space(']');
-    let sep_comma = sep_with_space(',');
-    let parse_values = separated(0.., parse_value, sep_comma);
-    delimited(sep1, parse_values, sep2).parse_next(input)
+/// Fail for good if `cancel` says to, asking it once every
+/// [`ParseOptions::CANCEL_INTERVAL`] bytes.
+fn cancelled(input: &mut Input<'_>) -> PResult<()> {
+    let Some(probe) = &input.state.options.cancel else {
+        return Ok(());
+    };
+    let left = input.input.len();
+    if input.state.asked.saturating_sub(left) < ParseOptions::CANCEL_INTERVAL {
+        return Ok(());
+    }
+    let cancel = probe.cancelled();
+    input.state.asked = left;
+    if !cancel {
+        return Ok(());
+    }
+    let err = ContextError::from_external_error(input, ErrorKind::Verify, error::Cancel);
+    Err(ErrMode::Cut(err))
 }
 
-fn parse_object(input: &mut &str) -> PResult<HashMap<String, JsonValue>> {
-    let sep1 = sep_with_space('{');
-    let sep2 = sep_with_space('}');
-    let sep_comma = sep_with_space(',');
-    let sep_colon = sep_with_space(':');
+/// Insignificant whitespace, and comments under `allow_comments`.
+fn ws(input: &mut Input<'_>) -> PResult<()> {
+    // most calls sit between two tokens with nothing to skip
+    if !input.input.starts_with([' ', '\t', '\n', '\r', '/']) {
+        return Ok(());
+    }
+    loop {
+        multispace0.parse_next(input)?;
+        if opt(comment).parse_next(input)?.is_none() {
+            return Ok(());
+        }
+    }
+}
 
-    let parse_kv_pair = separated_pair(parse_string, sep_colon, parse_value);
-    let parse_kv = separated(1.., parse_kv_pair, sep_comma);
-    delimited(sep1, parse_kv, sep2).parse_next(input)
+/// One `//` or `/* */` comment, only recognized under `allow_comments`.
+fn comment<'a>(input: &mut Input<'a>) -> PResult<&'a str> {
+    if !input.state.options.allow_comments {
+        return fail(input);
+    }
+    rule("comment", |input: &mut Input<'a>| {
+        let rest = input.input;
+        if rest.starts_with("//") {
+            take_till(0.., '\n').recognize().parse_next(input)
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            match body.find("*/") {
+                Some(end) => Ok(input.next_slice(end + 4)),
+                None => cut_err(fail)
+                    .context(StrContext::Label(error::UNTERMINATED_COMMENT))
+                    .parse_next(input),
+            }
+        } else {
+            fail(input)
+        }
+    })
+    .parse_next(input)
 }
 
-fn parse_value(input: &mut &str) -> PResult<JsonValue> {
-    alt((
-        parse_null.value(JsonValue::Null),
-        parse_bool.map(JsonValue::Bool),
-        parse_num.map(JsonValue::Number),
-        parse_string.map(JsonValue::String),
-        parse_array.map(JsonValue::Array),
-        parse_object.map(JsonValue::Object),
-    ))
+fn parse_null(input: &mut Input<'_>) -> PResult<()> {
+    rule("null", |input: &mut Input<'_>| {
+        "null".value(()).parse_next(input)
+    })
     .parse_next(input)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn parse_bool(input: &mut Input<'_>) -> PResult<bool> {
+    rule("bool", |input: &mut Input<'_>| {
+        alt(("true", "false")).parse_to().parse_next(input)
+    })
+    .parse_next(input)
+}
 
-    #[test]
-    fn test_parse_null() -> PResult<(), ContextError> {
-        let input = "null";
-        parse_null(&mut (&*input))?;
+fn parse_num(input: &mut Input<'_>) -> PResult<Num> {
+    rule("number", |input: &mut Input<'_>| {
+        let json5 = input.state.options.allow_json5_numbers;
+        let floats = input.state.options.numbers_as_f64;
+        let start = input.checkpoint();
+        if json5 {
+            if let Some(num) = opt(parse_hex).parse_next(input)? {
+                return Ok(match num {
+                    Num::Int(i) if floats => Num::Float(i as f64),
+                    num => num,
+                });
+            }
+        }
+        // recognize the whole token first and let the standard library do the
+        // conversion, which is exact for floats and accepts the JSON5 forms
+        let token = if json5 {
+            json5_number_token(input)?
+        } else {
+            number_token(input)?
+        };
 
-        Ok(())
+        // JSON5 spellings are not valid JSON, so they are never kept raw
+        let raw = input.state.options.preserve_number_text
+            && (!json5 || number_token.parse(strict(token)).is_ok());
+        let integral = input.state.options.integerize_exponents && !floats;
+        let exact = input.state.options.error_on_precision_loss;
+        let num = if raw {
+            allocate(input, &start, token.len())?;
+            Some(Num::Raw(token.to_string()))
+        } else if let Some(i) = integral_exponent(token).filter(|_| integral) {
+            Some(Num::Int(i))
+        } else if decimal(input) && !floats && token.contains(['.', 'e', 'E']) {
+            decimal_num(token)
+        } else if floats || token.contains(['.', 'e', 'E']) {
+            // past f64 range it would be infinite, which JSON cannot write back
+            let float = token.parse().ok().filter(|f: &f64| f.is_finite());
+            if float.is_some() && exact && !exact_float(token) {
+                return precision_loss(input, &start, token);
+            }
+            float.map(Num::Float)
+        } else if let Ok(i) = token.parse() {
+            Some(Num::Int(i))
+        } else if exact {
+            return precision_loss(input, &start, token);
+        } else if input.state.options.big_int_as_float {
+            // from every digit, so the float is as close as it can be
+            token
+                .parse()
+                .ok()
+                .filter(|f: &f64| f.is_finite())
+                .map(Num::Float)
+        } else {
+            return out_of_range(input, &start, error::INTEGER_OUT_OF_RANGE);
+        };
+        match num {
+            Some(num) => Ok(num),
+            None => out_of_range(input, &start, error::NUMBER_OUT_OF_RANGE),
+        }
+    })
+    .parse_next(input)
+}
+
+/// The integer `token` stands for when it has an exponent, no fraction,
+/// and a value that fits, such as `12e3`.
+fn integral_exponent(token: &str) -> Option<i64> {
+    let (digits, exponent) = token.split_once(['e', 'E'])?;
+    if digits.contains('.') {
+        return None;
     }
+    let exponent: u32 = exponent
+        .strip_prefix('+')
+        .unwrap_or(exponent)
+        .parse()
+        .ok()?;
+    let digits: i64 = digits.parse().ok()?;
+    digits.checked_mul(10i64.checked_pow(exponent)?)
+}
 
-    #[test]
-    fn test_parse_bool() -> PResult<(), ContextError> {
-        let input = "true";
-        let result = parse_bool(&mut (&*input))?;
-        assert!(result);
+/// Whether the decimal number `token` is exactly the `f64` it parses to, as
+/// described at [`ParseOptions::error_on_precision_loss`].
+fn exact_float(token: &str) -> bool {
+    let (mantissa, exponent) = token.split_once(['e', 'E']).unwrap_or((token, "0"));
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", int.trim_start_matches(['-', '+']), frac);
+    let significant = digits.trim_end_matches('0');
+    if significant.trim_start_matches('0').is_empty() {
+        return true;
+    }
+    let (Ok(d), Ok(exponent)) = (significant.parse::<u128>(), exponent.parse::<i64>()) else {
+        return false;
+    };
+    // `d × 10^e`, with the trailing zeros taken out of `d`
+    let e = exponent - frac.len() as i64 + (digits.len() - significant.len()) as i64;
+    let five = |e: i64| 5u128.checked_pow(u32::try_from(e.unsigned_abs()).ok()?);
+    let significand = if e >= 0 {
+        match five(e).and_then(|p| (d >> d.trailing_zeros()).checked_mul(p)) {
+            Some(m) => m,
+            None => return false,
+        }
+    } else {
+        match five(e) {
+            Some(p) if d % p == 0 => d / p,
+            _ => return false,
+        }
+    };
+    significand >> significand.trailing_zeros() < 1 << f64::MANTISSA_DIGITS
+}
 
-        let input = "false";
-        let result = parse_bool(&mut (&*input))?;
-        assert!(!result);
+/// Whether numbers with a fraction become [`Num::Decimal`].
+#[cfg(feature = "decimal")]
+fn decimal(input: &Input<'_>) -> bool {
+    input.state.options.parse_decimals
+}
 
-        Ok(())
-    }
+#[cfg(not(feature = "decimal"))]
+fn decimal(_: &Input<'_>) -> bool {
+    false
+}
 
-    #[test]
-    fn test_parse_num() -> PResult<(), ContextError> {
-        let input = "123";
-        let result = parse_num(&mut (&*input))?;
-        assert_eq!(result, Num::Int(123));
+#[cfg(feature = "decimal")]
+fn decimal_num(token: &str) -> Option<Num> {
+    token.parse().ok().map(Num::Decimal)
+}
 
-        let input = "-123";
-        let result = parse_num(&mut (&*input))?;
-        assert_eq!(result, Num::Int(-123));
+#[cfg(not(feature = "decimal"))]
+fn decimal_num(_: &str) -> Option<Num> {
+    None
+}
 
-        let input = "123.456";
-        let result = parse_num(&mut (&*input))?;
-        assert_eq!(result, Num::Float(123.456));
+/// Fail for good at the number that began at `start`, which has no
+/// [`Num`] to hold it.
+fn out_of_range<'a, O>(
+    input: &mut Input<'a>,
+    start: &<Input<'a> as Stream>::Checkpoint,
+    message: &'static str,
+) -> PResult<O> {
+    input.reset(start);
+    cut_err(fail)
+        .context(StrContext::Label(message))
+        .parse_next(input)
+}
 
-        let input = "-123.456";
-        let result = parse_num(&mut (&*input))?;
-        assert_eq!(result, Num::Float(-123.456));
+/// Fail for good at the number that began at `start`, which would not be
+/// held exactly.
+fn precision_loss<'a, O>(
+    input: &mut Input<'a>,
+    start: &<Input<'a> as Stream>::Checkpoint,
+    token: &str,
+) -> PResult<O> {
+    input.reset(start);
+    let hit = error::Inexact {
+        left: input.input.len(),
+        len: token.len(),
+    };
+    let err = ContextError::from_external_error(input, ErrorKind::Verify, hit);
+    Err(ErrMode::Cut(err))
+}
 
-        Ok(())
-    }
+fn number_token<'a>(input: &mut Input<'a>) -> PResult<&'a str> {
+    (
+        opt('-'),
+        // no leading zeros: `012` is the number `0` and then a stray `1`
+        alt(("0", (one_of('1'..='9'), digit0).recognize())),
+        opt(('.', digit1)),
+        opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+    )
+        .recognize()
+        .parse_next(input)
+}
 
-    #[test]
-    fn test_parse_string() -> PResult<(), ContextError> {
-        let input = r#""hello""#;
-        let result = parse_string(&mut (&*input))?;
-        assert_eq!(result, "hello");
+fn json5_number_token<'a>(input: &mut Input<'a>) -> PResult<&'a str> {
+    (
+        opt(one_of(['+', '-'])),
+        alt(((digit1, opt(('.', digit0))).void(), ('.', digit1).void())),
+        opt((one_of(['e', 'E']), opt(one_of(['+', '-'])), digit1)),
+    )
+        .recognize()
+        .parse_next(input)
+}
 
-        Ok(())
+/// A JSON5 hexadecimal integer. Only `-` may precede it.
+fn parse_hex(input: &mut Input<'_>) -> PResult<Num> {
+    let start = input.checkpoint();
+    let (sign, digits) = (
+        opt('-'),
+        preceded(alt(("0x", "0X")), take_while(1.., AsChar::is_hex_digit)),
+    )
+        .parse_next(input)?;
+    let int = u64::from_str_radix(digits, 16)
+        .ok()
+        .and_then(|m| match sign {
+            Some(_) => 0i64.checked_sub_unsigned(m),
+            None => i64::try_from(m).ok(),
+        });
+    match int {
+        Some(i) => Ok(Num::Int(i)),
+        None => out_of_range(input, &start, error::NUMBER_OUT_OF_RANGE),
     }
+}
 
-    #[test]
-    fn test_parse_array() -> PResult<(), ContextError> {
-        let input = r#"[1, 2, 3]"#;
-        let result = parse_array(&mut (&*input))?;
+/// A string as a [`JsonString`]. One without escapes is built straight
+/// from the input, never going through a `String`.
+fn parse_string(input: &mut Input<'_>) -> PResult<JsonString> {
+    rule("string", |input: &mut Input<'_>| {
+        let start = input.checkpoint();
+        let quote = alt(('"', single_quote)).parse_next(input)?;
+        let plain = plain_span(input, quote);
+        allocate(input, &start, plain.len())?;
+        if input.input.starts_with(quote) {
+            input.next_token();
+            return Ok(json_string(plain));
+        }
+        let mut ret = plain.to_string();
+        string_rest(input, &start, quote, &mut ret)?;
+        Ok(json_string(ret))
+    })
+    .parse_next(input)
+}
 
-        assert_eq!(
-            result,
-            vec![
-                JsonValue::Number(Num::Int(1)),
-                JsonValue::Number(Num::Int(2)),
-                JsonValue::Number(Num::Int(3))
-            ]
-        );
+/// [`parse_string`] appending to `ret`, so its buffer can be reused.
+fn parse_string_into(input: &mut Input<'_>, ret: &mut String) -> PResult<()> {
+    let start = input.checkpoint();
+    let quote = alt(('"', single_quote)).parse_next(input)?;
+    string_rest(input, &start, quote, ret)
+}
 
-        let input = r#"["a", "b", "c"]"#;
-        let result = parse_array(&mut (&*input))?;
-        assert_eq!(
-            result,
-            vec![
-                JsonValue::String("a".to_string()),
-                JsonValue::String("b".to_string()),
-                JsonValue::String("c".to_string())
-            ]
-        );
-        Ok(())
+/// The rest of a string that began at `start` after its opening `quote`,
+/// unescaped onto `ret`, each piece counted before it is copied.
+fn string_rest<'a>(
+    input: &mut Input<'a>,
+    start: &<Input<'a> as Stream>::Checkpoint,
+    quote: char,
+    ret: &mut String,
+) -> PResult<()> {
+    loop {
+        let span = plain_span(input, quote);
+        allocate(input, start, span.len())?;
+        ret.push_str(span);
+        if !span_end(input)? {
+            return Ok(());
+        }
+        let continued = input.state.options.allow_line_continuations
+            && opt(alt(("\r\n", "\n", "\r"))).parse_next(input)?.is_some();
+        if !continued {
+            let c = parse_escape(input, quote)?;
+            allocate(input, start, c.len_utf8())?;
+            ret.push(c);
+        }
     }
+}
 
-    #[test]
-    fn test_parse_object() -> PResult<(), ContextError> {
-        let input = r#"{"a": 1, "b": 2}"#;
-        let result = parse_object(&mut (&*input))?;
-        let mut expected = HashMap::new();
-        expected.insert("a".to_string(), JsonValue::Number(Num::Int(1)));
-        expected.insert("b".to_string(), JsonValue::Number(Num::Int(2)));
-        assert_eq!(result, expected);
+/// Decode the escapes in `s`, the contents of a JSON string without its
+/// quotes, exactly as the parser does, surrogate pairs included. Text
+/// without a backslash comes back borrowed. Offsets of errors are into `s`,
+/// and a bare `"` is one, since it would have ended the string, as is a
+/// control character, which must be escaped.
+///
+/// ```
+/// use grammar::json::unescape_str;
+/// use std::borrow::Cow;
+///
+/// assert_eq!(unescape_str(r"tab\there \ud83d\ude00").unwrap(), "tab\there 😀");
+/// assert!(matches!(unescape_str("plain").unwrap(), Cow::Borrowed("plain")));
+/// ```
+pub fn unescape_str(s: &str) -> Result<Cow<'_, str>, JsonError> {
+    let mut input = stateful(s, &ParseOptions::strict());
+    let plain = plain_span(&mut input, '"');
+    if input.input.is_empty() {
+        return Ok(Cow::Borrowed(plain));
+    }
+    let mut ret = plain.to_string();
+    loop {
+        match input.next_token() {
+            None => return Ok(Cow::Owned(ret)),
+            Some('\\') => {
+                let c = parse_escape(&mut input, '"')
+                    .map_err(|e| JsonError::from_parser(s, input.input, e))?;
+                ret.push(c);
+            }
+            Some(c) => return Err(error::unexpected(s.len() - input.input.len() - 1, c)),
+        }
+        ret.push_str(plain_span(&mut input, '"'));
+    }
+}
 
-        let input = r#"{"a": 1, "b": [1, 2, 3]}"#;
-        let result = parse_object(&mut (&*input))?;
-        let mut expected = HashMap::new();
-        expected.insert("a".to_string(), JsonValue::Number(Num::Int(1)));
-        expected.insert(
-            "b".to_string(),
-            JsonValue::Array(vec![
-                JsonValue::Number(Num::Int(1)),
+/// Take the characters up to the next `quote`, backslash or control
+/// character in one byte search. All are ASCII, and every byte of a
+/// multibyte UTF-8 sequence is 0x80 or above, so a match is never inside a
+/// character.
+pub(super) fn plain_span<'a>(input: &mut Input<'a>, quote: char) -> &'a str {
+    let bytes = input.input.as_bytes();
+    let stop = |b: &u8| *b == quote as u8 || *b == b'\\' || *b < 0x20;
+    let end = bytes.iter().position(stop).unwrap_or(bytes.len());
+    input.next_slice(end)
+}
+
+/// Step past what stopped [`plain_span`]: `true` for a backslash, which an
+/// escape follows, `false` for the closing quote. A control character
+/// fails for good, since JSON only allows it escaped.
+pub(super) fn span_end(input: &mut Input<'_>) -> PResult<bool> {
+    if input.input.starts_with(|c: char| c < ' ') {
+        return cut_err(fail)
+            .context(StrContext::Label(error::CONTROL_CHARACTER))
+            .parse_next(input);
+    }
+    Ok(any.parse_next(input)? == '\\')
+}
+
+fn single_quote(input: &mut Input<'_>) -> PResult<char> {
+    if input.input.starts_with('\'') && !input.state.options.allow_single_quotes {
+        return cut_err(fail)
+            .context(StrContext::Label(error::SINGLE_QUOTES))
+            .parse_next(input);
+    }
+    '\''.parse_next(input)
+}
+
+// the leading backslash has already been consumed
+fn parse_escape(input: &mut Input<'_>, quote: char) -> PResult<char> {
+    let start = input.checkpoint();
+    let c = match any.parse_next(input)? {
+        '\'' if quote == '\'' => '\'',
+        '"' => '"',
+        '\\' => '\\',
+        '/' => '/',
+        'b' => '\u{08}',
+        'f' => '\u{0c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => {
+            let high = parse_hex4(input)?;
+            if (0xD800..0xDC00).contains(&high) {
+                // a high surrogate must be followed by an escaped low surrogate
+                let low = opt(preceded("\\u", parse_hex4)).parse_next(input)?;
+                let Some(low) = low.filter(|low| (0xDC00..0xE000).contains(low)) else {
+                    return lone_surrogate(input, &start);
+                };
+                let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                char::from_u32(c).expect("surrogate pair is always a valid char")
+            } else {
+                match char::from_u32(high) {
+                    Some(c) => c,
+                    None => return lone_surrogate(input, &start),
+                }
+            }
+        }
+        _ => {
+            return cut_err(fail)
+                .context(StrContext::Label(error::INVALID_ESCAPE))
+                .parse_next(input)
+        }
+    };
+    Ok(c)
+}
+
+/// Four hex digits, as the escape that started with `\u` must go on.
+fn parse_hex4(input: &mut Input<'_>) -> PResult<u32> {
+    cut_err(take_while(4, AsChar::is_hex_digit))
+        .try_map(|s| u32::from_str_radix(s, 16))
+        .context(StrContext::Label(error::INVALID_ESCAPE))
+        .parse_next(input)
+}
+
+/// Fail for good at the `\u` escape that began at `start`, a surrogate
+/// without its other half, which no string can hold.
+fn lone_surrogate<'a, O>(
+    input: &mut Input<'a>,
+    start: &<Input<'a> as Stream>::Checkpoint,
+) -> PResult<O> {
+    input.reset(start);
+    cut_err(fail)
+        .context(StrContext::Label(error::LONE_SURROGATE))
+        .parse_next(input)
+}
+
+fn parse_array(input: &mut Input<'_>) -> PResult<Vec<JsonValue>> {
+    rule("array", |input: &mut Input<'_>| {
+        open('[').parse_next(input)?;
+        entries(input, ']', parse_value)
+    })
+    .parse_next(input)
+}
+
+fn parse_object(input: &mut Input<'_>) -> PResult<JsonObject> {
+    rule("object", |input: &mut Input<'_>| {
+        open('{').parse_next(input)?;
+        let parse_kv_pair = separated_pair(parse_key, sep_with_ws(':'), parse_value);
+        Ok(entries(input, '}', parse_kv_pair)?.into_iter().collect())
+    })
+    .parse_next(input)
+}
+
+/// The comma-separated entries of a container [`open`] has entered, up to
+/// and including `close`, leaving it again. Once the bracket is open
+/// failures are final, so errors point inside it, except for an entry past
+/// `max_array_len` or `max_object_members`, which fails at the bracket.
+fn entries<'a, O>(
+    input: &mut Input<'a>,
+    close: char,
+    mut entry: impl Parser<Input<'a>, O, ContextError>,
+) -> PResult<Vec<O>> {
+    let opened = input.state.opened;
+    let mut out = Vec::new();
+    if opt(close).parse_next(input)?.is_some() {
+        ws(input)?;
+    } else {
+        loop {
+            entry_limit(input, opened, out.len(), close == '}')?;
+            cancelled(input)?;
+            allocate_here(input, size::entry_size(close == '}'))?;
+            out.push(cut_err(entry.by_ref()).parse_next(input)?);
+            if entry_end(input, close)? {
+                break;
+            }
+        }
+    }
+    input.state.depth -= 1;
+    Ok(out)
+}
+
+/// What follows an entry: the comma before the next one, or `close`, with
+/// the whitespace after it. Returns whether the container closed. A comma
+/// directly before `close` is skipped, or fails in strict mode.
+fn entry_end(input: &mut Input<'_>, mut close: char) -> PResult<bool> {
+    ws(input)?;
+    if opt(close).parse_next(input)?.is_none() {
+        let comma = input.checkpoint();
+        cut_err(',').parse_next(input)?;
+        ws(input)?;
+        if !input.input.starts_with(close) {
+            return Ok(false);
+        }
+        if !input.state.options.allow_trailing_commas {
+            input.reset(&comma);
+            return cut_err(fail)
+                .context(StrContext::Label(error::TRAILING_COMMA))
+                .parse_next(input);
+        }
+        close.parse_next(input)?;
+    }
+    ws(input)?;
+    Ok(true)
+}
+
+fn parse_nonfinite(input: &mut Input<'_>) -> PResult<f64> {
+    rule("nonfinite", |input: &mut Input<'_>| {
+        let mut literal = alt((
+            "Infinity".value(f64::INFINITY),
+            "-Infinity".value(f64::NEG_INFINITY),
+            "NaN".value(f64::NAN),
+        ));
+        if input.state.options.allow_nonfinite {
+            return literal.parse_next(input);
+        }
+        if peek(literal).parse_next(input).is_ok() {
+            return cut_err(fail)
+                .context(StrContext::Label(error::NONFINITE))
+                .parse_next(input);
+        }
+        fail(input)
+    })
+    .parse_next(input)
+}
+
+fn parse_key(input: &mut Input<'_>) -> PResult<JsonString> {
+    rule("key", |input: &mut Input<'_>| {
+        if input.state.options.allow_unquoted_keys {
+            alt((parse_string, parse_identifier)).parse_next(input)
+        } else {
+            parse_string(input)
+        }
+    })
+    .parse_next(input)
+}
+
+fn parse_identifier(input: &mut Input<'_>) -> PResult<JsonString> {
+    counted_identifier.map(json_string).parse_next(input)
+}
+
+/// An [`identifier`] key, counted against `max_total_allocated`.
+fn counted_identifier<'a>(input: &mut Input<'a>) -> PResult<&'a str> {
+    let start = input.checkpoint();
+    let key = identifier(input)?;
+    allocate(input, &start, key.len())?;
+    Ok(key)
+}
+
+fn identifier<'a>(input: &mut Input<'a>) -> PResult<&'a str> {
+    take_while(1.., |c: char| {
+        c.is_ascii_alphanumeric() || c == '_' || c == '$'
+    })
+    .verify(|s: &str| formatter::is_identifier(s))
+    .parse_next(input)
+}
+
+fn parse_value(input: &mut Input<'_>) -> PResult<JsonValue> {
+    rule("value", |input: &mut Input<'_>| {
+        alt((
+            parse_null.value(JsonValue::Null),
+            parse_bool.map(JsonValue::Bool),
+            parse_nonfinite.map(|f| JsonValue::Number(Num::Float(f))),
+            parse_num.map(JsonValue::Number),
+            parse_string.map(JsonValue::String),
+            parse_array.map(JsonValue::Array),
+            parse_object.map(JsonValue::Object),
+        ))
+        .parse_next(input)
+    })
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the allocations made by the current thread, so tests running
+    /// in parallel do not see each other's.
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    // SAFETY: forwards to the system allocator unchanged
+    unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            ALLOCATIONS.with(|n| n.set(n.get() + 1));
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            std::alloc::System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATIONS.with(|n| n.get());
+        let result = f();
+        (result, ALLOCATIONS.with(|n| n.get()) - before)
+    }
+
+    #[test]
+    fn test_parse_null() -> PResult<(), ContextError> {
+        let input = "null";
+        parse_null(&mut strict(input))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_bool() -> PResult<(), ContextError> {
+        let input = "true";
+        let result = parse_bool(&mut strict(input))?;
+        assert!(result);
+
+        let input = "false";
+        let result = parse_bool(&mut strict(input))?;
+        assert!(!result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_num() -> PResult<(), ContextError> {
+        let input = "123";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Int(123));
+
+        let input = "-123";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Int(-123));
+
+        let input = "123.456";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(123.456));
+
+        let input = "-123.456";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(-123.456));
+
+        let input = "0.05";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(0.05));
+
+        let input = "1e300";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(1e300));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_num_does_not_allocate() {
+        for input in [
+            "0",
+            "-12",
+            "3.25",
+            "-0.000125e-7",
+            "1E300",
+            "9223372036854775807",
+        ] {
+            let (result, count) = allocations(|| parse_num(&mut strict(input)));
+            assert!(result.is_ok(), "{}", input);
+            assert_eq!(count, 0, "{}", input);
+        }
+
+        let floats: Vec<f64> = (0..20_000).map(|i| i as f64 * -1.125e-3).collect();
+        let input = format!("{:?}", floats);
+        let (result, count) = allocations(|| parse_json(&input));
+        assert_eq!(
+            result.unwrap(),
+            JsonValue::Array(
+                floats
+                    .into_iter()
+                    .map(|f| JsonValue::Number(Num::Float(f)))
+                    .collect()
+            )
+        );
+        // only the growth of the array itself
+        assert!(count < 40, "{} allocations", count);
+    }
+
+    #[test]
+    fn test_parse_short_keys_allocate_only_with_std_strings() {
+        // eight members whose values are short strings, keyed by `prefix`
+        let doc = |prefix: &str| {
+            let members: Vec<_> = (0..8)
+                .map(|i| format!("\"{}{}\": \"v{}\"", prefix, i, i))
+                .collect();
+            format!("{{{}}}", members.join(", "))
+        };
+        let (short, long) = (doc("id_"), doc("a_key_well_past_the_inline_limit_"));
+        let (result, short_count) = allocations(|| parse_json(&short));
+        assert!(result.is_ok());
+        let (result, long_count) = allocations(|| parse_json(&long));
+        assert!(result.is_ok());
+        // with inline strings only the long keys allocate, once per copy
+        // kept: an `OrderedMap` indexes its keys by a second copy
+        let copies = if cfg!(feature = "preserve-order") {
+            2
+        } else {
+            1
+        };
+        let expected = if cfg!(feature = "compact-str") {
+            8 * copies
+        } else {
+            0
+        };
+        assert_eq!(long_count - short_count, expected);
+
+        let (key, count) = allocations(|| parse_key(&mut strict("\"created_at\"")));
+        assert_eq!(key.unwrap(), "created_at");
+        assert_eq!(count, usize::from(!cfg!(feature = "compact-str")));
+    }
+
+    #[test]
+    fn test_parse_string() -> PResult<(), ContextError> {
+        let input = r#""hello""#;
+        let result = parse_string(&mut strict(input))?;
+        assert_eq!(result, "hello");
+
+        let input = r#""a\"b\\c\/\n\u00e9\ud83d\ude00""#;
+        let result = parse_string(&mut strict(input))?;
+        assert_eq!(result, "a\"b\\c/\né😀");
+
+        let input = r#""\ud83d""#;
+        assert!(parse_string(&mut strict(input)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_long_strings() {
+        let blob = "QUJD".repeat(100_000);
+        let input = format!("\"{}\"", blob);
+        assert_eq!(
+            parse_string(&mut strict(&input)),
+            Ok(json_string(blob.clone()))
+        );
+
+        let input = format!("[\"{}\\n\\u00e9\", \"{}\\\"\"]", blob, blob);
+        assert_eq!(
+            parse_json(&input).unwrap(),
+            JsonValue::Array(vec![
+                JsonValue::from(format!("{}\né", blob)),
+                JsonValue::from(format!("{}\"", blob)),
+            ])
+        );
+        assert!(skip_value(&mut input.as_str()).is_ok());
+
+        // the error lands where it does in a short string, shifted by the blob
+        let short = parse_json("\"\\x\"").unwrap_err();
+        let input = format!("\"{}\\x\"", blob);
+        let (JsonError::Parse { offset, .. }, JsonError::Parse { offset: short, .. }) =
+            (parse_json(&input).unwrap_err(), short)
+        else {
+            panic!("not parse errors")
+        };
+        assert_eq!(offset, short + blob.len());
+    }
+
+    #[test]
+    fn test_parse_string_scan_is_utf8_safe() -> PResult<(), ContextError> {
+        // U+0122 is C4 A2 and U+015C is C5 9C, continuation bytes whose low
+        // bits spell `"` and `\`. The byte scan cannot stop inside them, or
+        // inside any multibyte character, because all of their bytes have the
+        // high bit set while a quote and a backslash do not
+        let input = "\"\u{122}\u{15c}\u{5c22}\u{1F022}\\\"\u{122}\"";
+        let result = parse_string(&mut strict(input))?;
+        assert_eq!(result, "\u{122}\u{15c}\u{5c22}\u{1F022}\"\u{122}");
+        assert!("\u{122}\u{15c}\u{5c22}\u{1F022}".bytes().all(|b| b >= 0x80));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_array() -> PResult<(), ContextError> {
+        let input = r#"[1, 2, 3]"#;
+        let result = parse_array(&mut strict(input))?;
+
+        assert_eq!(
+            result,
+            vec![
+                JsonValue::Number(Num::Int(1)),
+                JsonValue::Number(Num::Int(2)),
+                JsonValue::Number(Num::Int(3))
+            ]
+        );
+
+        let input = r#"["a", "b", "c"]"#;
+        let result = parse_array(&mut strict(input))?;
+        assert_eq!(
+            result,
+            vec![
+                JsonValue::String("a".into()),
+                JsonValue::String("b".into()),
+                JsonValue::String("c".into())
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_object() -> PResult<(), ContextError> {
+        let input = r#"{"a": 1, "b": 2}"#;
+        let result = parse_object(&mut strict(input))?;
+        let mut expected = JsonObject::default();
+        expected.insert("a".into(), JsonValue::Number(Num::Int(1)));
+        expected.insert("b".into(), JsonValue::Number(Num::Int(2)));
+        assert_eq!(result, expected);
+
+        let input = r#"{"a": 1, "b": [1, 2, 3]}"#;
+        let result = parse_object(&mut strict(input))?;
+        let mut expected = JsonObject::default();
+        expected.insert("a".into(), JsonValue::Number(Num::Int(1)));
+        expected.insert(
+            "b".into(),
+            JsonValue::Array(vec![
+                JsonValue::Number(Num::Int(1)),
                 JsonValue::Number(Num::Int(2)),
                 JsonValue::Number(Num::Int(3)),
             ]),
         );
         assert_eq!(result, expected);
 
+        let input = "{ }";
+        let result = parse_object(&mut strict(input))?;
+        assert_eq!(result, JsonObject::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_json_rejects_trailing_data() {
+        assert_eq!(parse_json(" [1] \n").unwrap(), parse_json("[1]").unwrap());
+        assert_eq!(
+            parse_json("[1] x").unwrap_err(),
+            JsonError::Parse {
+                offset: 4,
+                message: "unexpected character 'x'".to_string(),
+                code: ErrorCode::TrailingData,
+            }
+        );
+        assert_eq!(
+            parse_json("[1, x]").unwrap_err(),
+            JsonError::Parse {
+                offset: 4,
+                message: "unexpected character 'x'".to_string(),
+                code: ErrorCode::UnexpectedToken,
+            }
+        );
+        assert_eq!(
+            parse_json("[1").unwrap_err(),
+            JsonError::Parse {
+                offset: 2,
+                message: "unexpected end of input".to_string(),
+                code: ErrorCode::UnexpectedEof,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_scientific_num() -> PResult<(), ContextError> {
+        let input = "1.23e3";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(1230.0));
+
+        let input = "1.23e-3";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(0.00123));
+
+        let input = "-1.23e-4";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(-0.000123));
+
+        let input = "1.23e4";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(12300.0));
+
+        let input = "-1.23e9";
+        let result = parse_num(&mut strict(input))?;
+        assert_eq!(result, Num::Float(-1230000000.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_integerize_exponents() -> PResult<(), ContextError> {
+        let options = ParseOptions::strict().integerize_exponents(true);
+        for (input, expected) in [
+            ("1e3", Num::Int(1000)),
+            ("2E+6", Num::Int(2_000_000)),
+            ("5e0", Num::Int(5)),
+            ("-7e2", Num::Int(-700)),
+            ("0e400", Num::Float(0.0)),
+            ("9e18", Num::Int(9_000_000_000_000_000_000)),
+            ("9e19", Num::Float(9e19)),
+            ("99999999999999999999e0", Num::Float(1e20)),
+            ("1e-3", Num::Float(0.001)),
+            ("10e-1", Num::Float(1.0)),
+            ("1.5e3", Num::Float(1500.0)),
+            ("1.0", Num::Float(1.0)),
+            ("42", Num::Int(42)),
+        ] {
+            assert_eq!(
+                parse_num(&mut stateful(input, &options))?,
+                expected,
+                "{}",
+                input
+            );
+        }
+        // off by default
+        assert_eq!(parse_num(&mut strict("1e3"))?, Num::Float(1000.0));
+        // exact where a float is not
+        let near = parse_json_with("9007199254740993e0", &options).unwrap();
+        assert_eq!(near, JsonValue::from(9_007_199_254_740_993));
+
+        let doc = r#"{"count": 1e3, "big": 9e19, "small": 25e-1}"#;
+        let value = parse_json_with(doc, &options).unwrap();
+        let written = value.to_json_string();
+        assert!(written.contains(r#""count":1000"#), "{}", written);
+        assert_eq!(parse_json_with(&written, &options).unwrap(), value);
+        assert_eq!(parse_json(&written).unwrap(), value);
         Ok(())
     }
+
+    #[test]
+    fn test_parse_big_integers() {
+        let twenty = "12345678901234567890";
+        let forty = "1234567890123456789012345678901234567890";
+        let exact = ParseOptions::strict().big_int_as_float(false);
+        for digits in [twenty, forty] {
+            for text in [digits.to_string(), format!("-{}", digits)] {
+                let nearest: f64 = text.parse().unwrap();
+                assert_eq!(parse_json(&text).unwrap(), JsonValue::from(nearest));
+                let doc = format!("[1, {}]", text);
+                assert_eq!(
+                    parse_json_with(&doc, &exact).unwrap_err(),
+                    JsonError::Parse {
+                        offset: 4,
+                        message: "integer out of range; enable big_int_as_float".to_string(),
+                        code: ErrorCode::InvalidNumber,
+                    }
+                );
+                // raw text keeps every digit either way
+                for options in [ParseOptions::strict(), exact.clone()] {
+                    let raw = options.preserve_number_text(true);
+                    assert_eq!(
+                        parse_json_with(&text, &raw).unwrap(),
+                        JsonValue::Number(Num::Raw(text.clone()))
+                    );
+                }
+            }
+        }
+        // the float comes from all the digits, not a prefix that fits
+        let value = parse_json("9223372036854775808123").unwrap();
+        assert_eq!(
+            value,
+            JsonValue::from("9223372036854775808123".parse::<f64>().unwrap())
+        );
+        assert_ne!(value, JsonValue::from(9.223372036854775e21));
+        // what fits stays an integer
+        assert_eq!(
+            parse_json_with("-9223372036854775808", &exact).unwrap(),
+            JsonValue::from(i64::MIN)
+        );
+        assert!(ParseOptions::default().big_int_as_float);
+        assert!(ParseOptions::json5().big_int_as_float);
+    }
+
+    #[test]
+    fn test_parse_numbers_as_f64() {
+        let js = ParseOptions::strict().numbers_as_f64(true);
+        // what `JSON.parse("9007199254740993")` gives
+        assert_eq!(
+            parse_json_with("9007199254740993", &js).unwrap(),
+            JsonValue::Number(Num::Float(9007199254740992.0))
+        );
+        for (text, int, float) in [
+            ("0", 0, 0.0),
+            ("-7", -7, -7.0),
+            ("1e3", 1000, 1000.0),
+            ("9007199254740992", 9007199254740992, 9007199254740992.0),
+        ] {
+            let exponents = ParseOptions::strict().integerize_exponents(true);
+            assert_eq!(
+                parse_json_with(text, &exponents).unwrap(),
+                JsonValue::Number(Num::Int(int))
+            );
+            let js_exponents = exponents.numbers_as_f64(true);
+            assert_eq!(
+                parse_json_with(text, &js_exponents).unwrap(),
+                JsonValue::Number(Num::Float(float))
+            );
+        }
+        assert_eq!(
+            parse_json("9007199254740993").unwrap(),
+            JsonValue::Number(Num::Int(9007199254740993))
+        );
+        match parse_json_with("-0", &js).unwrap() {
+            JsonValue::Number(Num::Float(f)) => assert_eq!(f.to_bits(), (-0.0f64).to_bits()),
+            v => panic!("-0 parsed as {:?}", v),
+        }
+        let hex = ParseOptions::json5().numbers_as_f64(true);
+        assert_eq!(
+            parse_json_with("-0x10", &hex).unwrap(),
+            JsonValue::Number(Num::Float(-16.0))
+        );
+        let raw = js.clone().preserve_number_text(true);
+        assert_eq!(
+            parse_json_with("10", &raw).unwrap(),
+            JsonValue::Number(Num::Raw("10".to_string()))
+        );
+        assert!(!ParseOptions::default().numbers_as_f64);
+
+        let doc = "[1, 9007199254740993, 12345678901234567890, 0.1, 2e-7, 100]";
+        let value = parse_json_with(doc, &js).unwrap();
+        assert_eq!(
+            value.to_json_string(),
+            "[1.0,9007199254740992.0,1.2345678901234567e19,0.1,2e-7,100.0]"
+        );
+        // as `JSON.stringify` writes them
+        assert_eq!(
+            value.to_canonical_json().unwrap(),
+            "[1,9007199254740992,12345678901234567000,0.1,2e-7,100]"
+        );
+        assert_eq!(
+            parse_json_with(&value.to_json_string(), &js).unwrap(),
+            value
+        );
+    }
+
+    fn jsonc() -> ParseOptions {
+        ParseOptions {
+            allow_comments: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let input = r#"// leading
+            /* before */ {
+                "a": [1, // after an element
+                      2 /* between */, /**/ 3],
+                "b" /* before colon */ : "x // not a comment /* nor this */"
+            } // trailing
+            /* and a block at the end */"#;
+        let result = parse_json_with(input, &jsonc()).unwrap();
+        assert_eq!(
+            result,
+            parse_json(r#"{"a": [1, 2, 3], "b": "x // not a comment /* nor this */"}"#).unwrap()
+        );
+        assert!(parse_json(input).is_err());
+        assert!(parse_json_with("[1] // no newline at end", &jsonc()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_unterminated_comment() {
+        assert_eq!(
+            parse_json_with("[1, /* 2 ]", &jsonc()).unwrap_err(),
+            JsonError::Parse {
+                offset: 4,
+                message: "unterminated block comment".to_string(),
+                code: ErrorCode::UnexpectedEof,
+            }
+        );
+        assert_eq!(
+            parse_json("[1 /* 2 */]").unwrap_err(),
+            JsonError::Parse {
+                offset: 3,
+                message: "unexpected character '/'".to_string(),
+                code: ErrorCode::UnexpectedToken,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_whitespace_heavy_document() {
+        // with 200,000 of these records, a release build went from about
+        // 560ms to 505ms per parse once each whitespace run was skipped once
+        // and `ws` returned early between adjacent tokens
+        let record = r#"{ "id" : 12 , "tags" : [ "a" , "b" , [ 1 , 2 , { } ] ] , "ok" : true }"#;
+        let spaced = format!("[\n  {}\n]", vec![record; 5_000].join(" ,\n  "));
+        let dense: String = spaced.split_whitespace().collect();
+        assert_eq!(parse_json(&spaced).unwrap(), parse_json(&dense).unwrap());
+
+        let jsonc = ParseOptions::strict()
+            .allow_comments(true)
+            .allow_trailing_commas(true);
+        let commented = spaced.replace(" ,", " /* , */ , // ]\n");
+        let commented = format!("{},\n]", commented.trim_end_matches(']'));
+        assert_eq!(
+            parse_json_with(&commented, &jsonc).unwrap(),
+            parse_json(&dense).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_trailing_commas() {
+        let lenient = ParseOptions {
+            allow_trailing_commas: true,
+            ..Default::default()
+        };
+        for (input, expected) in [
+            ("[1, 2, 3,]", "[1, 2, 3]"),
+            (r#"{"a": 1,}"#, r#"{"a": 1}"#),
+            ("[[1,] , {\"b\": [],\n}\n,\n]", r#"[[1], {"b": []}]"#),
+        ] {
+            assert_eq!(
+                parse_json_with(input, &lenient).unwrap(),
+                parse_json(expected).unwrap()
+            );
+        }
+        for input in ["[,]", "[1,,2]", "{,}", "[1,,]", r#"{"a": 1,,}"#] {
+            assert!(parse_json_with(input, &lenient).is_err(), "{:?}", input);
+        }
+
+        let message = "trailing commas are not allowed; enable allow_trailing_commas";
+        assert_eq!(
+            parse_json("[1, 2 ,]").unwrap_err(),
+            JsonError::Parse {
+                offset: 6,
+                message: message.to_string(),
+                code: ErrorCode::TrailingComma,
+            }
+        );
+        assert_eq!(
+            parse_json(r#"{"a": [1,]}"#).unwrap_err(),
+            JsonError::Parse {
+                offset: 8,
+                message: message.to_string(),
+                code: ErrorCode::TrailingComma,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_single_quotes() {
+        let lenient = ParseOptions {
+            allow_single_quotes: true,
+            ..Default::default()
+        };
+        let result = parse_json_with(
+            r#"{'a': 'it\'s "quoted"', "b": ['x', "y\"z", 'tab\t\u00e9']}"#,
+            &lenient,
+        )
+        .unwrap();
+        let expected =
+            parse_json(r#"{"a": "it's \"quoted\"", "b": ["x", "y\"z", "tab\t\u00e9"]}"#).unwrap();
+        assert_eq!(result, expected);
+        // \' is only an escape inside single quotes, and quotes must match
+        assert!(parse_json_with(r#""it\'s""#, &lenient).is_err());
+        assert!(parse_json_with(r#"'a""#, &lenient).is_err());
+
+        assert_eq!(
+            parse_json(r#"{"a": 'b'}"#).unwrap_err(),
+            JsonError::Parse {
+                offset: 6,
+                message: "single-quoted strings are not allowed; enable allow_single_quotes"
+                    .to_string(),
+                code: ErrorCode::NotAllowed,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_unquoted_keys() {
+        let lenient = ParseOptions {
+            allow_unquoted_keys: true,
+            ..Default::default()
+        };
+        let result = parse_json_with(
+            r#"{foo: 1, "quoted key": 2, $bar_2: true, _: [], null: null}"#,
+            &lenient,
+        )
+        .unwrap();
+        let expected =
+            parse_json(r#"{"foo": 1, "quoted key": 2, "$bar_2": true, "_": [], "null": null}"#)
+                .unwrap();
+        assert_eq!(result, expected);
+
+        for input in ["{2x: 1}", "{a-b: 1}", r#"{"a": foo}"#, "foo"] {
+            assert!(parse_json_with(input, &lenient).is_err(), "{:?}", input);
+        }
+        assert!(parse_json("{foo: 1}").is_err());
+    }
+
+    #[test]
+    fn test_parse_json5_numbers() {
+        let json5 = ParseOptions {
+            allow_json5_numbers: true,
+            ..Default::default()
+        };
+        for (input, expected) in [
+            ("0xdeadBEEF", Num::Int(0xdeadbeef)),
+            ("0X10", Num::Int(16)),
+            ("-0x10", Num::Int(-16)),
+            ("-0x8000000000000000", Num::Int(i64::MIN)),
+            ("-.25", Num::Float(-0.25)),
+            ("+1.", Num::Float(1.0)),
+            ("+.5e-3", Num::Float(0.0005)),
+            ("5.E2", Num::Float(500.0)),
+            ("+7", Num::Int(7)),
+            ("-12.5e1", Num::Float(-125.0)),
+        ] {
+            assert_eq!(
+                parse_json_with(input, &json5).unwrap(),
+                JsonValue::Number(expected),
+                "{:?}",
+                input
+            );
+        }
+        for input in ["0xdeadBEEF", "-.25", "+1.", "+7", "5.E2"] {
+            assert!(parse_json(input).is_err(), "{:?}", input);
+        }
+        for input in ["+0x10", "0x", ".", "+", "0x8000000000000000", "1.e"] {
+            assert!(parse_json_with(input, &json5).is_err(), "{:?}", input);
+            assert!(parse_json(input).is_err(), "{:?}", input);
+        }
+
+        let raw = ParseOptions {
+            preserve_number_text: true,
+            ..json5
+        };
+        assert_eq!(
+            parse_json_with("[1.50, .5, 0x1f]", &raw).unwrap(),
+            JsonValue::Array(vec![
+                JsonValue::Number(Num::Raw("1.50".to_string())),
+                JsonValue::Number(Num::Float(0.5)),
+                JsonValue::Number(Num::Int(31)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_nonfinite() {
+        let lenient = ParseOptions {
+            allow_nonfinite: true,
+            ..Default::default()
+        };
+        let floats = |v: &JsonValue| match v {
+            JsonValue::Array(arr) => arr
+                .iter()
+                .map(|v| match v {
+                    JsonValue::Number(Num::Float(f)) => *f,
+                    _ => panic!("not a float: {:?}", v),
+                })
+                .collect::<Vec<_>>(),
+            _ => panic!("not an array: {:?}", v),
+        };
+
+        let top: Vec<_> = ["Infinity", "-Infinity", "NaN"]
+            .iter()
+            .map(|s| match parse_json_with(s, &lenient).unwrap() {
+                JsonValue::Number(Num::Float(f)) => f,
+                v => panic!("not a float: {:?}", v),
+            })
+            .collect();
+        assert_eq!(top[..2], [f64::INFINITY, f64::NEG_INFINITY]);
+        assert!(top[2].is_nan());
+
+        let arr = floats(&parse_json_with("[Infinity, -Infinity, NaN, 1.5]", &lenient).unwrap());
+        assert_eq!(arr[..2], [f64::INFINITY, f64::NEG_INFINITY]);
+        assert!(arr[2].is_nan());
+        assert_eq!(arr[3], 1.5);
+
+        let obj = parse_json_with(r#"{"a": -Infinity}"#, &lenient).unwrap();
+        assert_eq!(
+            obj,
+            JsonValue::Object(JsonObject::from_iter([(
+                "a".into(),
+                JsonValue::from(f64::NEG_INFINITY)
+            )]))
+        );
+
+        for input in ["Infinityx", "[NaNa]", "infinity", "nan", "+Infinity"] {
+            assert!(parse_json_with(input, &lenient).is_err(), "{:?}", input);
+        }
+        assert_eq!(
+            parse_json("[1, -Infinity]").unwrap_err(),
+            JsonError::Parse {
+                offset: 4,
+                message: "NaN and Infinity are not allowed; enable allow_nonfinite".to_string(),
+                code: ErrorCode::NotAllowed,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_line_continuations() {
+        let lenient = ParseOptions {
+            allow_line_continuations: true,
+            ..Default::default()
+        };
+        let input = "\"one \\\ntwo \\\r\nthree\\\r!\"";
+        assert_eq!(
+            parse_json_with(input, &lenient).unwrap(),
+            JsonValue::String("one two three!".into())
+        );
+        assert_eq!(
+            parse_json("\"one \\\ntwo\"").unwrap_err(),
+            JsonError::Parse {
+                offset: 7,
+                message: "invalid escape sequence".to_string(),
+                code: ErrorCode::InvalidEscape,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_json5_preset() {
+        // the example from the front page of json5.org
+        let input = r#"// This file is written in JSON5 syntax, naturally, but npm needs a regular
+// JSON file, so compile via `npm run build`. Be sure to keep both in sync!
+
+{
+  // comments
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON",
 }
+"#;
+        let expected = parse_json(
+            r#"{
+                "unquoted": "and you can quote me on that",
+                "singleQuotes": "I can use \"double quotes\" here",
+                "lineBreaks": "Look, Mom! No \\n's!",
+                "hexadecimal": 912559,
+                "leadingDecimalPoint": 0.8675309,
+                "andTrailing": 8675309.0,
+                "positiveSign": 1,
+                "trailingComma": "in objects",
+                "andIn": ["arrays"],
+                "backwardsCompatible": "with JSON"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            parse_json_with(input, &ParseOptions::json5()).unwrap(),
+            expected
+        );
+        assert!(parse_json_with(input, &ParseOptions::strict()).is_err());
+        assert!(parse_json(input).is_err());
+    }
+
+    #[test]
+    fn test_parse_options_builder() {
+        let options = ParseOptions::strict()
+            .allow_comments(true)
+            .allow_unquoted_keys(true);
+        assert_eq!(
+            options,
+            ParseOptions {
+                allow_comments: true,
+                allow_unquoted_keys: true,
+                ..Default::default()
+            }
+        );
+        assert!(parse_json_with("{a: 1} // ok", &options).is_ok());
+        assert!(parse_json_with("{a: 1,}", &options).is_err());
+        assert!(!ParseOptions::json5().allow_nonfinite(false).allow_nonfinite);
+    }
+
+    #[test]
+    fn test_parse_state_options_switch_the_same_parser() {
+        let strict = ParseOptions::strict();
+        let lenient = ParseOptions::json5();
+        // whitespace, strings and containers all read the state they are given
+        for text in ["[1, /* c */ 2]", "'single'", "{a: [1,],}"] {
+            assert!(
+                parse_value(&mut stateful(text, &strict)).is_err(),
+                "{}",
+                text
+            );
+            let mut input = stateful(text, &lenient);
+            assert!(parse_value(&mut input).is_ok(), "{}", text);
+            assert_eq!(input.input, "", "{}", text);
+            assert_eq!(input.state.depth, 0, "{}", text);
+        }
 
-#[test]
-fn test_parse_scientific_num() -> PResult<(), ContextError> {
-    let input = "1.23e3";
-    let result = parse_num(&mut (&*input))?;
-    assert_eq!(result, Num::Float(1230.0));
+        let shallow = ParseOptions::strict().max_depth(1);
+        assert!(parse_value(&mut stateful("[[1]]", &strict)).is_ok());
+        assert!(parse_value(&mut stateful("[[1]]", &shallow)).is_err());
+        assert!(parse_value(&mut stateful("[1, 2]", &shallow)).is_ok());
+    }
 
-    let input = "1.23e-3";
-    let result = parse_num(&mut (&*input))?;
-    assert_eq!(result, Num::Float(0.00123));
+    #[test]
+    fn test_parse_max_depth() {
+        let options = ParseOptions::strict().max_depth(3);
+        let at_limit = r#"[{"a": [1]}, [], {"b": {}}]"#;
+        assert!(parse_json_with(at_limit, &options).is_ok());
+        assert!(skip::validate_with(at_limit, &options).is_ok());
+        assert!(parse_cst(at_limit, &options).is_ok());
 
-    let input = "-1.23e-4";
-    let result = parse_num(&mut (&*input))?;
-    assert_eq!(result, Num::Float(-0.000123));
+        let over = r#"[{"a": [[1]]}]"#;
+        let err = JsonError::Parse {
+            offset: 8,
+            message: "nesting is too deep; raise max_depth".to_string(),
+            code: ErrorCode::DepthExceeded,
+        };
+        assert_eq!(parse_json_with(over, &options).unwrap_err(), err);
+        assert_eq!(skip::validate_with(over, &options).unwrap_err(), err);
+        assert_eq!(parse_cst(over, &options).unwrap_err(), err);
+        assert!(parse_json(over).is_ok());
+    }
 
-    let input = "1.23e4";
-    let result = parse_num(&mut (&*input))?;
-    assert_eq!(result, Num::Float(12300.0));
+    #[test]
+    fn test_parse_max_total_allocated() {
+        type Parse = fn(&str, &ParseOptions) -> Result<(), JsonError>;
+        let parsers: [(&str, Parse); 5] = [
+            ("parse_json_with", |i, o| parse_json_with(i, o).map(drop)),
+            ("validate_with", skip::validate_with),
+            ("parse_json_lazy_with", |i, o| {
+                parse_json_lazy_with(i, o).map(|lazy| drop(lazy.to_value()))
+            }),
+            ("from_reader_with", |i, o| {
+                from_reader_with(i.as_bytes(), o).map(drop)
+            }),
+            ("JsonReader", |i, o| {
+                let mut reader = JsonReader::with_options(i, o);
+                while reader.next_event()?.is_some() {}
+                Ok(())
+            }),
+        ];
+        for input in [
+            "null",
+            " \"text\" ",
+            "[1, \"two\", [3.5, []]]",
+            r#"{"name": "caf\u00e9 \ud83d\ude00", "tags": ["a", "b\n"], "nested": {"": {}}}"#,
+        ] {
+            // the budget the tree takes is just enough
+            let budget = parse_json(input).unwrap().deep_size_of();
+            let enough = ParseOptions::strict().max_total_allocated(budget);
+            let short = ParseOptions::strict().max_total_allocated(budget - 1);
+            let expected = parse_json_with(input, &short).unwrap_err();
+            assert_eq!(expected.code(), ErrorCode::LimitExceeded, "{}", input);
+            for (name, parse) in parsers {
+                assert_eq!(parse(input, &enough), Ok(()), "{}: {}", name, input);
+                assert_eq!(
+                    parse(input, &short).unwrap_err(),
+                    expected,
+                    "{}: {}",
+                    name,
+                    input
+                );
+            }
+        }
+        let raw = ParseOptions::strict()
+            .preserve_number_text(true)
+            .max_total_allocated(size::VALUE_SIZE + 3);
+        assert!(parse_json_with("1.5", &raw).is_ok());
+        assert!(parse_json_with("1.50", &raw).is_err());
 
-    let input = "-1.23e9";
-    let result = parse_num(&mut (&*input))?;
-    assert_eq!(result, Num::Float(-1230000000.0));
+        // a document far larger than the budget fails before most of it is read
+        let record = r#""a medium-sized string of filler","#;
+        let input = format!("[{}0]", record.repeat(20_000));
+        let budget = ParseOptions::strict().max_total_allocated(1000);
+        for (name, parse) in parsers {
+            match parse(&input, &budget) {
+                Err(JsonError::Parse { offset, code, .. }) => {
+                    assert_eq!(code, ErrorCode::LimitExceeded, "{}", name);
+                    assert!(offset < 2 * 1000, "{}: at {}", name, offset);
+                }
+                other => panic!("{}: {:?}", name, other),
+            }
+        }
+        assert_eq!(
+            parse_json_with("[\"abc\"]", &ParseOptions::strict().max_total_allocated(10))
+                .unwrap_err()
+                .to_string(),
+            "the value would take more memory than max_total_allocated allows at offset 0"
+        );
+    }
 
-    Ok(())
+    #[test]
+    fn test_parse_max_entries() {
+        let options = ParseOptions::strict()
+            .max_array_len(2)
+            .max_object_members(2);
+        type Parse = fn(&str, &ParseOptions) -> Result<(), JsonError>;
+        let parsers: [(&str, Parse); 6] = [
+            ("parse_json_with", |i, o| parse_json_with(i, o).map(drop)),
+            ("validate_with", skip::validate_with),
+            ("parse_cst", |i, o| parse_cst(i, o).map(drop)),
+            ("parse_json_lazy_with", |i, o| {
+                parse_json_lazy_with(i, o).map(drop)
+            }),
+            ("from_reader_with", |i, o| {
+                from_reader_with(i.as_bytes(), o).map(drop)
+            }),
+            ("JsonReader", |i, o| {
+                let mut reader = JsonReader::with_options(i, o);
+                while reader.next_event()?.is_some() {}
+                Ok(())
+            }),
+        ];
+        for input in [
+            "[]",
+            "[1, 2]",
+            "{\"a\": 1, \"b\": 2}",
+            // each container counts its own entries
+            "[[1, 2], {\"a\": [3, 4], \"b\": {}}]",
+            "{\"a\": {\"x\": 1, \"y\": 2}, \"b\": [[5, 6], []]}",
+        ] {
+            for (name, parse) in parsers {
+                assert_eq!(parse(input, &options), Ok(()), "{}: {}", name, input);
+            }
+        }
+        for (input, at, object) in [
+            ("[1, 2, 3]", 0, false),
+            (" {\"a\": 1, \"b\": 2, \"a\": 3}", 1, true),
+            ("[[1, 2], [3, 4, []]]", 9, false),
+            (
+                "{\"a\": [], \"b\": {\"x\": 1, \"y\": 2, \"z\": 3}}",
+                15,
+                true,
+            ),
+        ] {
+            for (name, parse) in parsers {
+                let err = parse(input, &options).unwrap_err();
+                assert_eq!(
+                    err,
+                    JsonError::TooManyEntries {
+                        span: at..at + 1,
+                        limit: 2,
+                        object,
+                    },
+                    "{}: {}",
+                    name,
+                    input
+                );
+                assert_eq!(err.code(), ErrorCode::LimitExceeded);
+            }
+            assert!(parse_json(input).is_ok(), "{}", input);
+        }
+        assert_eq!(
+            parse_json_with("[0, 0, 0]", &options)
+                .unwrap_err()
+                .to_string(),
+            "array has more than 2 elements; raise max_array_len at offset 0"
+        );
+        let empty = ParseOptions::strict().max_object_members(0);
+        assert!(parse_json_with("[{}, {}]", &empty).is_ok());
+        assert_eq!(
+            parse_json_with("[{}, {\"a\": 1}]", &empty)
+                .unwrap_err()
+                .to_string(),
+            "object has more than 0 members; raise max_object_members at offset 5"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_on_precision_loss() {
+        let options = ParseOptions::strict().error_on_precision_loss(true);
+        for input in [
+            "0.5",
+            "123",
+            "-0.0",
+            "1e3",
+            "2.5E-1",
+            "1.25",
+            "9223372036854775807",
+            "9007199254740992.0",
+            "1180591620717411303424.0",
+            "0.0009765625",
+        ] {
+            let strict = parse_json(input).unwrap();
+            assert_eq!(parse_json_with(input, &options), Ok(strict), "{}", input);
+        }
+        for input in [
+            "0.1",
+            "1e-1",
+            // 19 digits, one past i64::MAX
+            "9223372036854775808",
+            "9007199254740993.0",
+            "1.7976931348623157e308",
+            "1e-400",
+            "3.14159",
+        ] {
+            assert!(parse_json(input).is_ok(), "{}", input);
+            let err = JsonError::PrecisionLoss {
+                span: 0..input.len(),
+                token: input.to_string(),
+            };
+            assert_eq!(parse_json_with(input, &options), Err(err), "{}", input);
+        }
+        let input = "{\"price\": [19.5, 0.3]}";
+        let err = parse_json_with(input, &options).unwrap_err();
+        assert_eq!(
+            err,
+            JsonError::PrecisionLoss {
+                span: 17..20,
+                token: "0.3".to_string(),
+            }
+        );
+        assert_eq!(err.code(), ErrorCode::PrecisionLoss);
+        assert_eq!(
+            err.to_string(),
+            "0.3 cannot be held exactly; enable preserve_number_text or parse_decimals, at offset 17"
+        );
+        assert_eq!(skip::validate_with(input, &options), Err(err));
+        assert_eq!(
+            from_reader_with(input.as_bytes(), &options).unwrap_err(),
+            parse_json_with(input, &options).unwrap_err()
+        );
+        // text kept as written is exact
+        assert!(parse_json_with("0.1", &options.clone().preserve_number_text(true)).is_ok());
+        assert!(parse_json_with("1e3", &options.integerize_exponents(true)).is_ok());
+    }
+
+    #[test]
+    fn test_parse_cancel() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        let input = format!(
+            "[{}]",
+            vec!["{\"id\": 12345, \"tags\": [\"a\", \"b\"], \"ok\": true}"; 20_000].join(", ")
+        );
+        let never = ParseOptions::strict().cancel(|| false);
+        assert_eq!(parse_json_with(&input, &never), parse_json(&input));
+
+        let now = ParseOptions::strict().cancel(|| true);
+        let cancelled = Err(JsonError::Cancelled { offset: 1 });
+        assert_eq!(parse_json_with(&input, &now).map(drop), cancelled);
+        assert_eq!(skip::validate_with(&input, &now), cancelled);
+        assert_eq!(parse_cst(&input, &now).map(drop), cancelled);
+        assert_eq!(
+            from_reader_with(input.as_bytes(), &now).map(drop),
+            cancelled
+        );
+        assert_eq!(parse_json_with("[]", &now), Ok(JsonValue::Array(vec![])));
+        assert_eq!(
+            JsonError::Cancelled { offset: 1 }.code(),
+            ErrorCode::Cancelled
+        );
+
+        static ASKED: AtomicUsize = AtomicUsize::new(0);
+        static STOP: AtomicBool = AtomicBool::new(false);
+        let probe = ParseOptions::strict().cancel(|| {
+            ASKED.fetch_add(1, Ordering::SeqCst);
+            STOP.load(Ordering::SeqCst)
+        });
+        let stopper = std::thread::spawn(|| {
+            while ASKED.load(Ordering::SeqCst) < 2 {
+                std::thread::yield_now();
+            }
+            STOP.store(true, Ordering::SeqCst);
+        });
+        let err = parse_json_with(&input, &probe).unwrap_err();
+        stopper.join().unwrap();
+        let JsonError::Cancelled { offset } = err else {
+            panic!("not cancelled: {}", err);
+        };
+        assert!(offset < input.len() / 2, "{} of {}", offset, input.len());
+    }
+
+    #[test]
+    fn test_parse_cancel_one_of_two_concurrent_parses() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        let input = format!(
+            "[{}]",
+            vec!["{\"id\": 12345, \"ok\": true}"; 20_000].join(", ")
+        );
+        let stop = Arc::new(AtomicBool::new(false));
+        let asked = Arc::new(AtomicUsize::new(0));
+        let probe = {
+            let (stop, asked) = (stop.clone(), asked.clone());
+            ParseOptions::strict().cancel(move || {
+                asked.fetch_add(1, Ordering::SeqCst);
+                stop.load(Ordering::SeqCst)
+            })
+        };
+        let never = ParseOptions::strict().cancel(|| false);
+        assert_ne!(probe, never);
+        assert_eq!(probe.clone(), probe);
+        let (cancelled, finished) = std::thread::scope(|scope| {
+            let cancelled = scope.spawn(|| parse_json_with(&input, &probe));
+            let finished = scope.spawn(|| parse_json_with(&input, &never));
+            while asked.load(Ordering::SeqCst) < 2 {
+                std::thread::yield_now();
+            }
+            stop.store(true, Ordering::SeqCst);
+            (cancelled.join().unwrap(), finished.join().unwrap())
+        });
+        assert!(
+            matches!(cancelled, Err(JsonError::Cancelled { offset }) if offset < input.len()),
+            "{:?}",
+            cancelled.map(drop)
+        );
+        assert_eq!(finished, parse_json(&input));
+    }
+
+    #[test]
+    fn test_parse_require_container() {
+        let options = ParseOptions::strict().require_container(true);
+        for (input, span, found) in [
+            ("42", 0..2, "a number"),
+            (" -1.5e3 \n", 1..7, "a number"),
+            ("\"hi\"", 0..4, "a string"),
+            ("\ttrue", 1..5, "a boolean"),
+            ("false", 0..5, "a boolean"),
+            ("null ", 0..4, "null"),
+        ] {
+            assert!(parse_json(input).is_ok(), "{}", input);
+            let err = JsonError::ScalarRoot {
+                span: span.clone(),
+                found,
+            };
+            assert_eq!(
+                parse_json_with(input, &options).unwrap_err(),
+                err,
+                "{}",
+                input
+            );
+            assert_eq!(
+                skip::validate_with(input, &options).unwrap_err(),
+                err,
+                "{}",
+                input
+            );
+            assert_eq!(parse_cst(input, &options).unwrap_err(), err, "{}", input);
+            assert_eq!(err.code(), ErrorCode::ScalarRoot);
+        }
+        let commented = options
+            .clone()
+            .allow_comments(true)
+            .allow_single_quotes(true);
+        assert_eq!(
+            parse_json_with("/* a */ 'hi' // b", &commented).unwrap_err(),
+            JsonError::ScalarRoot {
+                span: 8..12,
+                found: "a string"
+            }
+        );
+        assert_eq!(
+            parse_json_with("  7", &options).unwrap_err().to_string(),
+            "the document must be an object or array, not a number, at offset 2"
+        );
+        assert_eq!(
+            parse_json_with("  7", &options)
+                .unwrap_err()
+                .display_with_source("  7"),
+            "error: the document must be an object or array, not a number\n --> 1:3\n  |\n1 |   7\n  |   ^"
+        );
+        // containers, scalars inside them and broken scalars are as before
+        for input in ["[]", " {} ", "[1, \"a\", null]", r#"{"a": true}"#] {
+            assert_eq!(
+                parse_json_with(input, &options),
+                parse_json(input),
+                "{}",
+                input
+            );
+            assert!(skip::validate_with(input, &options).is_ok(), "{}", input);
+            assert!(parse_cst(input, &options).is_ok(), "{}", input);
+        }
+        for input in ["tru", "", "[1", "\"open"] {
+            assert_eq!(
+                parse_json_with(input, &options),
+                parse_json(input),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[cfg(all(feature = "sorted-keys", not(feature = "preserve-order")))]
+    #[test]
+    fn test_sorted_keys() {
+        let input = r#"{"zeta": 1, "alpha": {"y": [], "b": null, "x": true}, "mid": 2, "zeta": 3}"#;
+        let value = parse_json(input).unwrap();
+        assert_eq!(
+            value.to_json_string(),
+            r#"{"alpha":{"b":null,"x":true,"y":[]},"mid":2,"zeta":3}"#
+        );
+        assert_eq!(
+            format!("{:?}", parse_json(r#"{"b": 1, "a": 2}"#).unwrap()),
+            r#"Object({"a": Number(Int(2)), "b": Number(Int(1))})"#
+        );
+    }
+
+    #[cfg(feature = "preserve-order")]
+    #[test]
+    fn test_preserve_order() {
+        let input = r#"{"zeta": 1, "alpha": {"y": [], "b": null, "x": true}, "mid": 2, "zeta": 3}"#;
+        let value = parse_json(input).unwrap();
+        assert_eq!(
+            value.to_json_string(),
+            r#"{"zeta":3,"alpha":{"y":[],"b":null,"x":true},"mid":2}"#
+        );
+        let JsonValue::Object(obj) = value else {
+            panic!("not an object")
+        };
+        assert_eq!(obj.keys().collect::<Vec<_>>(), ["zeta", "alpha", "mid"]);
+    }
+
+    #[test]
+    fn test_debug_is_deterministic() {
+        let keys = [
+            "zeta", "alpha", "mid", "q\"uote", "beta", "é", "k1", "k10", "k2",
+        ];
+        let build = |keys: &mut dyn Iterator<Item = &&str>| {
+            let mut obj = JsonObject::default();
+            for key in keys {
+                let mut inner = JsonObject::default();
+                inner.insert("y".into(), JsonValue::from(key.len() as f64));
+                inner.insert("x".into(), JsonValue::from(1i64));
+                inner.insert("w".into(), JsonValue::from("a\nb"));
+                obj.insert(json_string(*key), JsonValue::Object(inner));
+            }
+            JsonValue::Object(obj)
+        };
+        let forward = build(&mut keys.iter());
+        for _ in 0..5 {
+            let reverse = build(&mut keys.iter().rev());
+            assert_eq!(format!("{:?}", forward), format!("{:?}", reverse));
+            assert_eq!(format!("{:#?}", forward), format!("{:#?}", reverse));
+        }
+        let small = parse_json(r#"{"b": [1, 2.0, "s\t"], "a": null, "c": true}"#).unwrap();
+        assert_eq!(
+            format!("{:?}", small),
+            r#"Object({"a": Null, "b": Array([Number(Int(1)), Number(Float(2.0)), String("s\t")]), "c": Bool(true)})"#
+        );
+    }
+
+    #[test]
+    fn test_unescape_str() {
+        let escaped = r#"q\"b\\ \/ \b\f\n\r\t \u00e9 \ud83d\ude00"#;
+        let text = "q\"b\\ / \u{8}\u{c}\n\r\t é 😀";
+        assert_eq!(unescape_str(escaped).unwrap(), text);
+        let quoted = format!("\"{}\"", escaped);
+        assert_eq!(parse_json(&quoted).unwrap(), JsonValue::from(text));
+        // the borrowed fast path
+        assert!(matches!(
+            unescape_str("plain é"),
+            Ok(Cow::Borrowed("plain é"))
+        ));
+        assert!(matches!(unescape_str(""), Ok(Cow::Borrowed(""))));
+        assert!(matches!(unescape_str(r"\n"), Ok(Cow::Owned(_))));
+        // escapes then a plain tail
+        assert_eq!(unescape_str(r"\ttail").unwrap(), "\ttail");
+    }
+
+    #[test]
+    fn test_unescape_str_errors() {
+        let err = |s: &str| {
+            let e = unescape_str(s).unwrap_err();
+            let JsonError::Parse { offset, .. } = e else {
+                panic!("{:?}", e)
+            };
+            (e.code(), offset)
+        };
+        assert_eq!(err(r"ab\x"), (ErrorCode::InvalidEscape, 4));
+        assert_eq!(err(r"ab\u12zz"), (ErrorCode::InvalidEscape, 4));
+        assert_eq!(err(r"\ud83dx"), (ErrorCode::InvalidEscape, 1));
+        assert_eq!(err("a\tb\nc"), (ErrorCode::UnexpectedToken, 1));
+        assert_eq!(err(r"ab\"), (ErrorCode::UnexpectedEof, 3));
+        assert_eq!(err("a\"b"), (ErrorCode::UnexpectedToken, 1));
+    }
 }
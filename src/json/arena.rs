@@ -0,0 +1,405 @@
+use super::{
+    borrowed::string, entries, json_string, open, parse_bool, parse_nonfinite, parse_null,
+    parse_num, sep_with_ws, strict, ws, Input, JsonError, JsonValue, Num,
+};
+use std::{cell::RefCell, collections::HashSet};
+use winnow::{combinator::alt, error::ErrMode, prelude::*, stream::Stream};
+
+/// Storage for documents parsed with [`parse_in`]. Strings and containers
+/// are bump-allocated into a few large buffers, so parsing does no
+/// allocation per value and dropping or [resetting](JsonArena::reset) the
+/// arena frees every document in it at once.
+#[derive(Default)]
+pub struct JsonArena {
+    bytes: RefCell<Chunks<u8>>,
+    values: RefCell<Chunks<ArenaValue<'static>>>,
+    members: RefCell<Chunks<(&'static str, ArenaValue<'static>)>>,
+}
+
+/// A document parsed into a [`JsonArena`]. It is `Copy`: containers are
+/// slices in the arena, and an object keeps its members in input order with
+/// duplicate keys resolved as [`parse_json`](super::parse_json) does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaValue<'a> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(&'a str),
+    Array(&'a [ArenaValue<'a>]),
+    Object(&'a [(&'a str, ArenaValue<'a>)]),
+}
+
+impl JsonArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop every document, keeping the largest buffer of each kind for the
+    /// next parses.
+    pub fn reset(&mut self) {
+        self.bytes.get_mut().reset();
+        self.values.get_mut().reset();
+        self.members.get_mut().reset();
+    }
+
+    fn alloc_str(&self, s: &str) -> &str {
+        // SAFETY: the slice is only handed out for the borrow of `self`, and
+        // the bytes are a copy of a whole `str`
+        unsafe { std::str::from_utf8_unchecked(self.bytes.borrow_mut().alloc(s.as_bytes())) }
+    }
+
+    fn alloc_values<'a>(&'a self, values: &[ArenaValue<'a>]) -> &'a [ArenaValue<'a>] {
+        // SAFETY: `ArenaValue` is `Copy` and the arena never reads what it
+        // stores, so keeping the values under a longer lifetime is sound as
+        // long as they are only handed back for `'a`, the borrow of the arena
+        // they point into
+        unsafe {
+            let values: &[ArenaValue<'static>] = std::mem::transmute(values);
+            std::mem::transmute::<&[ArenaValue<'static>], _>(self.values.borrow_mut().alloc(values))
+        }
+    }
+
+    fn alloc_members<'a>(
+        &'a self,
+        members: &[(&'a str, ArenaValue<'a>)],
+    ) -> &'a [(&'a str, ArenaValue<'a>)] {
+        // SAFETY: as for `alloc_values`
+        unsafe {
+            let members: &[(&'static str, ArenaValue<'static>)] = std::mem::transmute(members);
+            std::mem::transmute::<&[(&'static str, ArenaValue<'static>)], _>(
+                self.members.borrow_mut().alloc(members),
+            )
+        }
+    }
+}
+
+/// Buffers that are never reallocated once written to, so slices into them
+/// stay valid while more is appended.
+struct Chunks<T> {
+    chunks: Vec<Vec<T>>,
+}
+
+impl<T> Default for Chunks<T> {
+    fn default() -> Self {
+        Chunks { chunks: Vec::new() }
+    }
+}
+
+impl<T: Copy> Chunks<T> {
+    /// Copy `items` in and return where they now are.
+    ///
+    /// # Safety
+    ///
+    /// The slice must not be used after `self` is reset or dropped.
+    unsafe fn alloc<'b>(&mut self, items: &[T]) -> &'b [T] {
+        let fits = self
+            .chunks
+            .last()
+            .is_some_and(|c| c.capacity() - c.len() >= items.len());
+        if !fits {
+            let last = self.chunks.last().map_or(0, Vec::capacity);
+            let size = (last * 2).max(items.len()).max(1024);
+            self.chunks.push(Vec::with_capacity(size));
+        }
+        let chunk = self.chunks.last_mut().expect("a chunk was just ensured");
+        let start = chunk.len();
+        // never grows past the capacity, so earlier slices stay put
+        chunk.extend_from_slice(items);
+        let ptr = chunk[start..].as_ptr();
+        // SAFETY: the chunk's buffer is neither freed nor moved until `reset`
+        // or drop, and the caller has stopped using the slice by then
+        unsafe { std::slice::from_raw_parts(ptr, items.len()) }
+    }
+
+    fn reset(&mut self) {
+        let largest = self.chunks.pop();
+        self.chunks.clear();
+        if let Some(mut chunk) = largest {
+            chunk.clear();
+            self.chunks.push(chunk);
+        }
+    }
+}
+
+impl<'a> ArenaValue<'a> {
+    /// The member `key` of an object. Objects are searched linearly.
+    pub fn get(&self, key: &str) -> Option<&'a ArenaValue<'a>> {
+        match self {
+            ArenaValue::Object(members) => members.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// The element `index` of an array.
+    pub fn at(&self, index: usize) -> Option<&'a ArenaValue<'a>> {
+        match self {
+            ArenaValue::Array(values) => values.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            ArenaValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ArenaValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ArenaValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Any number as a float.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ArenaValue::Int(i) => Some(*i as f64),
+            ArenaValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&'a [ArenaValue<'a>]> {
+        match self {
+            ArenaValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&'a [(&'a str, ArenaValue<'a>)]> {
+        match self {
+            ArenaValue::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, ArenaValue::Null)
+    }
+
+    /// Copy the document out of the arena.
+    pub fn to_value(&self) -> JsonValue {
+        match *self {
+            ArenaValue::Null => JsonValue::Null,
+            ArenaValue::Bool(b) => JsonValue::Bool(b),
+            ArenaValue::Int(i) => JsonValue::Number(Num::Int(i)),
+            ArenaValue::Float(f) => JsonValue::Number(Num::Float(f)),
+            ArenaValue::String(s) => JsonValue::String(json_string(s)),
+            ArenaValue::Array(values) => {
+                JsonValue::Array(values.iter().map(ArenaValue::to_value).collect())
+            }
+            ArenaValue::Object(members) => JsonValue::Object(
+                members
+                    .iter()
+                    .map(|(k, v)| (json_string(*k), v.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Parse strict JSON like [`parse_json`](super::parse_json) into `arena`.
+/// The document lives as long as the borrow of the arena; any number of
+/// them can share one.
+pub fn parse_in<'a>(arena: &'a JsonArena, input: &str) -> Result<ArenaValue<'a>, JsonError> {
+    let mut scratch = Scratch {
+        arena,
+        values: Vec::new(),
+        members: Vec::new(),
+    };
+    let mut rest = strict(input);
+    let value = (ws, |i: &mut Input<'_>| scratch.value(i), ws)
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?
+        .1;
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    Ok(value)
+}
+
+/// Stacks the entries of the open containers until each is complete and
+/// can be copied into the arena in one piece.
+struct Scratch<'a> {
+    arena: &'a JsonArena,
+    values: Vec<ArenaValue<'a>>,
+    members: Vec<(&'a str, ArenaValue<'a>)>,
+}
+
+impl<'a> Scratch<'a> {
+    /// [`parse_value`](super::parse_value) into the arena. The alternatives
+    /// are tried by hand because the containers need `self`.
+    fn value(&mut self, input: &mut Input<'_>) -> PResult<ArenaValue<'a>> {
+        let start = input.checkpoint();
+        let arena = self.arena;
+        let scalar = alt((
+            parse_null.value(ArenaValue::Null),
+            parse_bool.map(ArenaValue::Bool),
+            parse_nonfinite.map(ArenaValue::Float),
+            parse_num.map(|n| match n {
+                Num::Int(i) => ArenaValue::Int(i),
+                Num::Float(f) => ArenaValue::Float(f),
+                Num::Raw(_) => unreachable!("strict parsing keeps no number text"),
+                #[cfg(feature = "decimal")]
+                Num::Decimal(_) => unreachable!("strict parsing makes no decimals"),
+            }),
+            string.map(|s| ArenaValue::String(arena.alloc_str(&s))),
+        ))
+        .parse_next(input);
+        match scalar {
+            Err(ErrMode::Backtrack(_)) => input.reset(&start),
+            scalar => return scalar,
+        }
+        match self.array(input) {
+            Err(ErrMode::Backtrack(_)) => input.reset(&start),
+            arr => return arr,
+        }
+        self.object(input)
+    }
+
+    fn array(&mut self, input: &mut Input<'_>) -> PResult<ArenaValue<'a>> {
+        open('[').parse_next(input)?;
+        let start = self.values.len();
+        let parsed = entries(input, ']', |i: &mut Input<'_>| {
+            let value = self.value(i)?;
+            self.values.push(value);
+            Ok(())
+        });
+        let values = self.arena.alloc_values(&self.values[start..]);
+        self.values.truncate(start);
+        parsed.map(|_| ArenaValue::Array(values))
+    }
+
+    fn object(&mut self, input: &mut Input<'_>) -> PResult<ArenaValue<'a>> {
+        open('{').parse_next(input)?;
+        let start = self.members.len();
+        let arena = self.arena;
+        let parsed = entries(input, '}', |i: &mut Input<'_>| {
+            let key = arena.alloc_str(&string.parse_next(i)?);
+            sep_with_ws(':').parse_next(i)?;
+            let value = self.value(i)?;
+            self.members.push((key, value));
+            Ok(())
+        });
+        dedup_keys(&mut self.members, start);
+        let members = self.arena.alloc_members(&self.members[start..]);
+        self.members.truncate(start);
+        parsed.map(|_| ArenaValue::Object(members))
+    }
+}
+
+/// Keep only the last of each key in `members[start..]`, at the position of
+/// that last occurrence.
+fn dedup_keys(members: &mut Vec<(&str, ArenaValue<'_>)>, start: usize) {
+    let object = &members[start..];
+    let unique = if object.len() <= 16 {
+        (1..object.len()).all(|i| object[..i].iter().all(|(k, _)| *k != object[i].0))
+    } else {
+        let mut seen = HashSet::new();
+        object.iter().all(|(k, _)| seen.insert(*k))
+    };
+    if unique {
+        return;
+    }
+    let mut seen = HashSet::new();
+    let mut kept: Vec<_> = members[start..]
+        .iter()
+        .rev()
+        .filter(|(k, _)| seen.insert(*k))
+        .copied()
+        .collect();
+    kept.reverse();
+    members.truncate(start);
+    members.extend(kept);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const DOC: &str = r#"{"name": "Zoë", "age": 30, "marks": [90.5, -80, 1e3],
+        "address": {"city": "New York", "zip": null}, "ok": true, "esc": "a\"b\u00e9",
+        "dup": 1, "dup": [2]}"#;
+
+    #[test]
+    fn parse_in_should_read_fields() {
+        let arena = JsonArena::new();
+        let doc = parse_in(&arena, DOC).unwrap();
+        assert_eq!(doc.get("name").and_then(ArenaValue::as_str), Some("Zoë"));
+        assert_eq!(doc.get("age").and_then(ArenaValue::as_i64), Some(30));
+        let marks = doc.get("marks").and_then(ArenaValue::as_array).unwrap();
+        assert_eq!(
+            marks.iter().filter_map(ArenaValue::as_f64).sum::<f64>(),
+            1010.5
+        );
+        let city = doc.get("address").and_then(|a| a.get("city"));
+        assert_eq!(city.and_then(ArenaValue::as_str), Some("New York"));
+        assert!(doc
+            .get("address")
+            .and_then(|a| a.get("zip"))
+            .unwrap()
+            .is_null());
+        assert_eq!(doc.get("ok").and_then(ArenaValue::as_bool), Some(true));
+        assert_eq!(doc.get("esc").and_then(ArenaValue::as_str), Some("a\"bé"));
+        assert_eq!(
+            doc.get("dup").and_then(|d| d.at(0)),
+            Some(&ArenaValue::Int(2))
+        );
+        let keys: Vec<_> = doc.as_object().unwrap().iter().map(|(k, _)| *k).collect();
+        assert_eq!(
+            keys,
+            ["name", "age", "marks", "address", "ok", "esc", "dup"]
+        );
+    }
+
+    #[test]
+    fn parse_in_should_match_parse_json() {
+        let arena = JsonArena::new();
+        let many: String = format!(
+            "{{{}}}",
+            (0..40)
+                .map(|i| format!("\"k{}\": {}", i % 20, i))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        for input in [DOC, "[]", "{}", "\"x\"", "[[[], {}], [1, [2]]]", &many] {
+            assert_eq!(
+                parse_in(&arena, input).unwrap().to_value(),
+                parse_json(input).unwrap(),
+                "{}",
+                input
+            );
+        }
+        for input in ["[1,]", "{\"a\" 1}", "[1] 2", "[\"\\q\"]", ""] {
+            assert_eq!(
+                parse_in(&arena, input),
+                Err(parse_json(input).unwrap_err()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn arena_should_be_reusable_after_reset() {
+        let mut arena = JsonArena::new();
+        let expected = parse_json(DOC).unwrap();
+        for _ in 0..100 {
+            let docs: Vec<_> = (0..50).map(|_| parse_in(&arena, DOC).unwrap()).collect();
+            assert!(docs.iter().all(|d| d.to_value() == expected));
+            arena.reset();
+        }
+        assert_eq!(arena.bytes.get_mut().chunks.len(), 1);
+    }
+}
@@ -0,0 +1,319 @@
+use super::{
+    error::{self, invalid_utf8},
+    parse_json, JsonError, JsonValue, StreamingParser,
+};
+use std::{
+    future::poll_fn,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Bytes requested from the reader per read.
+const CHUNK: usize = 8 * 1024;
+
+/// A source of bytes that may not be ready yet. This is the shape of
+/// `futures::io::AsyncRead`, so a reader from any runtime can be adapted
+/// with a one-line impl forwarding `poll_read`.
+pub trait AsyncRead {
+    /// Read into `buf`, returning how many bytes were read, `0` at the end
+    /// of the stream, or `Pending` after arranging for `cx` to be woken.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+impl AsyncRead for &[u8] {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(io::Read::read(&mut *self, buf))
+    }
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> AsyncRead for &mut R {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_read(cx, buf)
+    }
+}
+
+async fn read_chunk<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, buf)).await
+}
+
+/// Parse one strict JSON document from `reader` as its bytes arrive, like
+/// [`from_reader`](super::from_reader) but waiting for each chunk instead
+/// of blocking on it. The document is recognized with a
+/// [`StreamingParser`], so only the bytes of the document are buffered.
+/// Values and errors are those of [`parse_json`], with offsets counted from
+/// the start of the stream; read failures are [`JsonError::Io`].
+pub async fn from_async_reader<R: AsyncRead + Unpin>(
+    mut reader: R,
+) -> Result<JsonValue, JsonError> {
+    let mut parser = StreamingParser::new();
+    let mut chunk = vec![0; CHUNK];
+    let mut read = 0;
+    let value = loop {
+        let n = read_chunk(&mut reader, &mut chunk).await?;
+        read += n;
+        if n == 0 {
+            parser.finish();
+        } else {
+            parser.push(&chunk[..n]);
+        }
+        if let Some(value) = parser.next_value()? {
+            break value;
+        }
+        if n == 0 {
+            return Err(error::eof(read));
+        }
+    };
+
+    // only whitespace may follow the document
+    let (mut offset, rest) = parser.buffered();
+    let mut tail = rest.to_vec();
+    let mut eof = false;
+    loop {
+        if let Some(start) = tail.iter().position(|b| !b" \t\n\r".contains(b)) {
+            let mut chars = tail[start..].utf8_chunks();
+            let err = match chars.next().and_then(|c| c.valid().chars().next()) {
+                Some(c) => error::unexpected(offset + start, c).trailing(),
+                // the character may continue in the next chunk
+                None if !eof && tail.len() - start < 4 => {
+                    let n = read_chunk(&mut reader, &mut chunk).await?;
+                    tail.extend_from_slice(&chunk[..n]);
+                    eof = n == 0;
+                    continue;
+                }
+                None => invalid_utf8(offset + start),
+            };
+            return Err(err);
+        }
+        if eof {
+            return Ok(value);
+        }
+        offset += tail.len();
+        let n = read_chunk(&mut reader, &mut chunk).await?;
+        tail.clear();
+        tail.extend_from_slice(&chunk[..n]);
+        eof = n == 0;
+    }
+}
+
+/// Newline-delimited JSON read from `reader` as it arrives, parsed like
+/// [`parse_ndjson`](super::parse_ndjson): one result per non-blank line,
+/// with a broken line yielding an error carrying its line number.
+/// [`next`](AsyncNdjson::next) is what `futures::StreamExt::next` would be,
+/// so a `Stream` is one `unfold` over it.
+pub struct AsyncNdjson<R> {
+    reader: R,
+    buf: Vec<u8>,
+    /// How much of `buf` is known to hold no newline.
+    scanned: usize,
+    line: usize,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncNdjson<R> {
+    pub fn new(reader: R) -> Self {
+        AsyncNdjson {
+            reader,
+            buf: Vec::new(),
+            scanned: 0,
+            line: 0,
+            eof: false,
+        }
+    }
+
+    /// The next record, or `None` at the end of the stream. A read failure
+    /// is yielded as [`JsonError::Io`] and ends the stream.
+    pub async fn next(&mut self) -> Option<Result<JsonValue, JsonError>> {
+        let mut chunk = vec![0; CHUNK];
+        loop {
+            let end = match memchr::memchr(b'\n', &self.buf[self.scanned..]) {
+                Some(i) => Some(self.scanned + i + 1),
+                None if self.eof && !self.buf.is_empty() => Some(self.buf.len()),
+                None if self.eof => return None,
+                None => None,
+            };
+            if let Some(end) = end {
+                let line: Vec<u8> = self.buf.drain(..end).collect();
+                self.scanned = 0;
+                self.line += 1;
+                if let Some(record) = self.record(&line) {
+                    return Some(record);
+                }
+                continue;
+            }
+            self.scanned = self.buf.len();
+            match read_chunk(&mut self.reader, &mut chunk).await {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(e) => {
+                    self.eof = true;
+                    self.buf.clear();
+                    return Some(Err(e.into()));
+                }
+            }
+        }
+    }
+
+    /// Parse one line, `None` if it is blank.
+    fn record(&self, line: &[u8]) -> Option<Result<JsonValue, JsonError>> {
+        let parsed = match std::str::from_utf8(line) {
+            Ok(text) if text.trim().is_empty() => return None,
+            Ok(text) => parse_json(text.strip_suffix('\n').unwrap_or(text)),
+            Err(e) => Err(invalid_utf8(e.valid_up_to())),
+        };
+        Some(parsed.map_err(|e| JsonError::Line {
+            line: self.line,
+            source: Box::new(e),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_ndjson;
+    use std::{
+        future::Future,
+        sync::Arc,
+        task::{Wake, Waker},
+        thread::{self, Thread},
+    };
+
+    /// Run a future to completion on this thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct Unpark(Thread);
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// Hands out `input` a few bytes at a time, not ready on every other
+    /// poll, and wakes its task from another thread after a short delay.
+    struct Delayed<'a> {
+        input: &'a [u8],
+        chunk: usize,
+        ready: bool,
+    }
+
+    impl<'a> Delayed<'a> {
+        fn new(input: &'a str, chunk: usize) -> Self {
+            Delayed {
+                input: input.as_bytes(),
+                chunk,
+                ready: false,
+            }
+        }
+    }
+
+    impl AsyncRead for Delayed<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                let waker = cx.waker().clone();
+                thread::spawn(move || {
+                    thread::sleep(std::time::Duration::from_micros(50));
+                    waker.wake();
+                });
+                return Poll::Pending;
+            }
+            let n = self.chunk.min(buf.len()).min(self.input.len());
+            buf[..n].copy_from_slice(&self.input[..n]);
+            self.input = &self.input[n..];
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    struct Failing;
+
+    impl AsyncRead for Failing {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Err(io::Error::other("connection reset")))
+        }
+    }
+
+    #[test]
+    fn from_async_reader_should_match_parse_json() {
+        let doc =
+            r#" {"name": "Zoë 😀", "marks": [90.5, -80, 1e3], "nested": {"a": [true, null]}} "#;
+        for input in [doc, "12", " \"a\\\"b\" ", "[]"] {
+            for chunk in [1, 3, 4096] {
+                assert_eq!(
+                    block_on(from_async_reader(Delayed::new(input, chunk))),
+                    parse_json(input),
+                    "{} in chunks of {}",
+                    input,
+                    chunk
+                );
+            }
+        }
+        for input in ["", "  ", "[1,]", "{\"a\" 1}", "1 2", "[1] é", "[1", "nul"] {
+            for chunk in [1, 2, 4096] {
+                assert_eq!(
+                    block_on(from_async_reader(Delayed::new(input, chunk))),
+                    parse_json(input),
+                    "{:?} in chunks of {}",
+                    input,
+                    chunk
+                );
+            }
+        }
+        assert!(matches!(
+            block_on(from_async_reader(Failing)),
+            Err(JsonError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn async_ndjson_should_match_parse_ndjson() {
+        let input = "{\"a\": 1}\n\n[1, 2]\r\n{\"bad\"}\n  \n\"last é\"";
+        let expected: Vec<_> = parse_ndjson(input).collect();
+        for chunk in [1, 5, 4096] {
+            let mut stream = AsyncNdjson::new(Delayed::new(input, chunk));
+            let records = block_on(async {
+                let mut records = Vec::new();
+                while let Some(record) = stream.next().await {
+                    records.push(record);
+                }
+                records
+            });
+            assert_eq!(records, expected, "chunks of {}", chunk);
+        }
+        let mut stream = AsyncNdjson::new(Failing);
+        assert!(matches!(
+            block_on(stream.next()),
+            Some(Err(JsonError::Io(_)))
+        ));
+        assert!(block_on(stream.next()).is_none());
+    }
+}
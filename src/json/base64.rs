@@ -0,0 +1,183 @@
+use super::JsonValue;
+
+pub(crate) const STD_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+pub(crate) const URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// `bytes` as base64 (RFC 4648) in `alphabet`, padded with `=` if `pad`.
+pub(crate) fn encode(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, b)| acc | u32::from(*b) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(char::from(alphabet[(n >> (18 - 6 * i)) as usize & 0x3f]));
+        }
+        if pad {
+            out.push_str(&"=="[..2 - (chunk.len() - 1)]);
+        }
+    }
+    out
+}
+
+/// The bytes of base64 `text` in `alphabet`, or `None` unless it is exactly
+/// that: no whitespace or other characters, `=` padding only where it
+/// belongs, and zero bits past the last byte. Padding may be left off
+/// unless `require_padding`.
+pub(crate) fn decode(text: &str, alphabet: &[u8; 64], require_padding: bool) -> Option<Vec<u8>> {
+    let data = text.trim_end_matches('=');
+    let pads = text.len() - data.len();
+    if pads > 2
+        || (pads > 0 || require_padding) && !text.len().is_multiple_of(4)
+        || data.len() % 4 == 1
+    {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len() / 4 * 3 + 2);
+    for chunk in data.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let sextet = alphabet.iter().position(|a| a == c)? as u32;
+            n |= sextet << (18 - 6 * i);
+        }
+        let len = chunk.len() - 1;
+        // a short chunk leaves bits over, which an encoder sets to zero
+        if n & ((1 << (24 - 8 * len)) - 1) != 0 {
+            return None;
+        }
+        out.extend((0..len).map(|i| (n >> (16 - 8 * i)) as u8));
+    }
+    Some(out)
+}
+
+impl JsonValue {
+    /// A string of `bytes` in padded standard base64, the usual way to carry
+    /// binary data in JSON.
+    pub fn from_bytes_base64(bytes: &[u8]) -> JsonValue {
+        JsonValue::from(encode(bytes, STD_ALPHABET, true))
+    }
+
+    /// A string of `bytes` in unpadded URL-safe base64, as JWTs and URLs
+    /// use.
+    pub fn from_bytes_base64_url(bytes: &[u8]) -> JsonValue {
+        JsonValue::from(encode(bytes, URL_ALPHABET, false))
+    }
+
+    /// The bytes of a string in padded standard base64. `None` for other
+    /// values and for strings that are not exactly that, whitespace inside
+    /// included.
+    pub fn as_base64(&self) -> Option<Vec<u8>> {
+        self.as_base64_with(false, true)
+    }
+
+    /// Like [`as_base64`](Self::as_base64), with `-` and `_` in place of `+`
+    /// and `/` if `url_safe`, and taking strings without their `=` padding
+    /// unless `require_padding`. Padding that is there must be right.
+    pub fn as_base64_with(&self, url_safe: bool, require_padding: bool) -> Option<Vec<u8>> {
+        let alphabet = if url_safe { URL_ALPHABET } else { STD_ALPHABET };
+        match self {
+            JsonValue::String(s) => decode(s, alphabet, require_padding),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_should_follow_rfc_4648() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(encode(input.as_bytes(), STD_ALPHABET, true), expected);
+        }
+        assert_eq!(encode(&[0xfb, 0xff], URL_ALPHABET, false), "-_8");
+        for (expected, input) in cases {
+            assert_eq!(
+                decode(input, STD_ALPHABET, true).unwrap(),
+                expected.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn base64_should_round_trip_random_bytes() {
+        // xorshift64, deterministic so failures are reproducible
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        for len in 0..200 {
+            let bytes: Vec<u8> = (0..len)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect();
+            let value = JsonValue::from_bytes_base64(&bytes);
+            assert_eq!(value.as_base64().unwrap(), bytes);
+            let url = JsonValue::from_bytes_base64_url(&bytes);
+            assert_eq!(url.as_base64_with(true, false).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn base64_url_and_padding_should_be_opt_in() {
+        let url = JsonValue::from("-_8");
+        assert_eq!(url.as_base64(), None);
+        assert_eq!(url.as_base64_with(true, true), None);
+        assert_eq!(url.as_base64_with(true, false).unwrap(), [0xfb, 0xff]);
+        assert_eq!(
+            JsonValue::from("-_8=").as_base64_with(true, true).unwrap(),
+            [0xfb, 0xff]
+        );
+        assert_eq!(JsonValue::from("+/8=").as_base64().unwrap(), [0xfb, 0xff]);
+        assert_eq!(JsonValue::from("+/8=").as_base64_with(true, false), None);
+        assert_eq!(JsonValue::from("Zg").as_base64(), None);
+        assert_eq!(
+            JsonValue::from("Zg").as_base64_with(false, false).unwrap(),
+            b"f"
+        );
+    }
+
+    #[test]
+    fn base64_should_reject_what_is_not_base64() {
+        for text in [
+            "Zm9v YmFy",
+            "Zm9v\nYmFy",
+            " Zm9v",
+            "Zm9v!",
+            "Zg=",
+            "Zg===",
+            "Z===",
+            "Zm9vY",
+            "=Zm9",
+            "Zh==",
+            "hello, world",
+        ] {
+            let value = JsonValue::from(text);
+            assert_eq!(value.as_base64(), None, "{:?}", text);
+            assert_eq!(value.as_base64_with(false, false), None, "{:?}", text);
+        }
+        assert_eq!(
+            JsonValue::from_bytes_base64(b"foo").as_base64(),
+            Some(b"foo".to_vec())
+        );
+        for value in [
+            JsonValue::Null,
+            JsonValue::from(1),
+            JsonValue::Array(vec![]),
+        ] {
+            assert_eq!(value.as_base64(), None);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use super::{
+    entries, into_std_string, json_string, open, parse_bool, parse_nonfinite, parse_null,
+    parse_num, parse_string, sep_with_ws, skip::skip_string, strict, ws, Input, JsonError,
+    JsonValue, Num,
+};
+use std::{borrow::Cow, collections::HashMap};
+use winnow::{
+    combinator::{alt, separated_pair},
+    prelude::*,
+};
+
+/// A [`JsonValue`] whose strings and keys borrow from the parsed text where
+/// they can. Only strings containing escapes are copied out, since their
+/// text differs from the input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValueRef<'a> {
+    Null,
+    Bool(bool),
+    Number(Num),
+    String(Cow<'a, str>),
+    Array(Vec<JsonValueRef<'a>>),
+    Object(HashMap<Cow<'a, str>, JsonValueRef<'a>>),
+}
+
+impl JsonValueRef<'_> {
+    /// Copy everything still borrowed into an owned [`JsonValue`].
+    pub fn into_owned(self) -> JsonValue {
+        match self {
+            JsonValueRef::Null => JsonValue::Null,
+            JsonValueRef::Bool(b) => JsonValue::Bool(b),
+            JsonValueRef::Number(n) => JsonValue::Number(n),
+            JsonValueRef::String(s) => JsonValue::String(json_string(s)),
+            JsonValueRef::Array(arr) => {
+                JsonValue::Array(arr.into_iter().map(JsonValueRef::into_owned).collect())
+            }
+            JsonValueRef::Object(obj) => JsonValue::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (json_string(k), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Parse strict JSON like [`parse_json`](super::parse_json), borrowing
+/// every string and key without escapes from `input`.
+pub fn parse_json_borrowed(input: &str) -> Result<JsonValueRef<'_>, JsonError> {
+    let mut rest = strict(input);
+    let value = (ws, value, ws)
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?
+        .1;
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    Ok(value)
+}
+
+fn value<'a>(input: &mut Input<'a>) -> PResult<JsonValueRef<'a>> {
+    alt((
+        parse_null.value(JsonValueRef::Null),
+        parse_bool.map(JsonValueRef::Bool),
+        parse_nonfinite.map(|f| JsonValueRef::Number(Num::Float(f))),
+        parse_num.map(JsonValueRef::Number),
+        string.map(JsonValueRef::String),
+        array.map(JsonValueRef::Array),
+        object.map(JsonValueRef::Object),
+    ))
+    .parse_next(input)
+}
+
+/// A string literal, borrowed from the input unless it has escapes.
+pub(super) fn string<'a>(input: &mut Input<'a>) -> PResult<Cow<'a, str>> {
+    let raw = skip_string.recognize().parse_next(input)?;
+    let inner = &raw[1..raw.len() - 1];
+    if !inner.contains('\\') {
+        return Ok(Cow::Borrowed(inner));
+    }
+    let mut escaped = Input {
+        input: raw,
+        state: input.state.clone(),
+    };
+    parse_string(&mut escaped).map(|s| Cow::Owned(into_std_string(s)))
+}
+
+fn array<'a>(input: &mut Input<'a>) -> PResult<Vec<JsonValueRef<'a>>> {
+    open('[').parse_next(input)?;
+    entries(input, ']', value)
+}
+
+fn object<'a>(input: &mut Input<'a>) -> PResult<HashMap<Cow<'a, str>, JsonValueRef<'a>>> {
+    open('{').parse_next(input)?;
+    let member = separated_pair(string, sep_with_ws(':'), value);
+    Ok(entries(input, '}', member)?.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    fn borrowed_from(s: &str, input: &str) -> bool {
+        input.as_bytes().as_ptr_range().contains(&s.as_ptr())
+    }
+
+    #[test]
+    fn parse_json_borrowed_should_point_into_the_input() {
+        let input = r#"{"plain": "value", "esc\"aped": "a\nb", "list": ["x", "\u00e9", "é"]}"#;
+        let JsonValueRef::Object(obj) = parse_json_borrowed(input).unwrap() else {
+            panic!("not an object")
+        };
+        let (key, value) = obj.get_key_value("plain").unwrap();
+        assert!(matches!(key, Cow::Borrowed(k) if borrowed_from(k, input)));
+        assert!(matches!(value, JsonValueRef::String(Cow::Borrowed(v)) if borrowed_from(v, input)));
+        let (key, value) = obj.get_key_value("esc\"aped").unwrap();
+        assert!(matches!(key, Cow::Owned(_)));
+        assert_eq!(value, &JsonValueRef::String(Cow::Owned("a\nb".to_string())));
+        let JsonValueRef::Array(list) = &obj["list"] else {
+            panic!("not an array")
+        };
+        let kinds: Vec<_> = list
+            .iter()
+            .map(|v| matches!(v, JsonValueRef::String(Cow::Borrowed(s)) if borrowed_from(s, input)))
+            .collect();
+        assert_eq!(kinds, [true, false, true]);
+    }
+
+    #[test]
+    fn into_owned_should_match_parse_json() {
+        for input in [
+            r#"{"a": [1, -2.5, {"b": null}], "c\td": "e\u0041", "f": true, "f": false}"#,
+            " [] ",
+            "\"\"",
+            "12",
+        ] {
+            assert_eq!(
+                parse_json_borrowed(input).unwrap().into_owned(),
+                parse_json(input).unwrap(),
+                "{}",
+                input
+            );
+        }
+        for input in ["[1,]", "{\"a\" 1}", "\"\\q\"", "[1] 2", ""] {
+            assert_eq!(
+                parse_json_borrowed(input),
+                Err(parse_json(input).unwrap_err()),
+                "{}",
+                input
+            );
+        }
+    }
+}
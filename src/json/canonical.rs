@@ -0,0 +1,256 @@
+use super::{ser::write_string, CompactFormatter, EscapeOptions, JsonError, JsonValue, Num};
+
+const JCS_ESCAPE: EscapeOptions = EscapeOptions {
+    ascii_only: false,
+    escape_html: false,
+    escape_line_separators: false,
+    escape_solidus: false,
+};
+
+impl JsonValue {
+    /// Serialize per RFC 8785 (JSON Canonicalization Scheme): members sorted by
+    /// UTF-16 code units, no whitespace, minimal string escaping and numbers in
+    /// ECMAScript `Number.prototype.toString` form. Integers are treated as
+    /// IEEE doubles like every other number, so values beyond 2^53 round.
+    /// NaN and infinities are rejected.
+    pub fn to_canonical_json(&self) -> Result<String, JsonError> {
+        let mut out = Vec::new();
+        write_canonical(&mut out, self)?;
+        Ok(String::from_utf8(out).expect("serializer only emits UTF-8"))
+    }
+}
+
+fn write_canonical(out: &mut Vec<u8>, value: &JsonValue) -> Result<(), JsonError> {
+    match value {
+        JsonValue::Null => out.extend_from_slice(b"null"),
+        JsonValue::Bool(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+        JsonValue::Number(n) => {
+            // raw number text is deliberately ignored: JCS fixes the spelling
+            let f = match n {
+                Num::Int(i) => *i as f64,
+                Num::Float(f) => *f,
+                Num::Raw(s) => s
+                    .parse()
+                    .map_err(|_| JsonError::serialize(format!("invalid number {:?}", s)))?,
+                #[cfg(feature = "decimal")]
+                Num::Decimal(d) => d.to_f64(),
+            };
+            out.extend_from_slice(es_number_to_string(f)?.as_bytes());
+        }
+        JsonValue::String(s) => write_str(out, s),
+        JsonValue::Raw(raw) => write_canonical(out, &raw.parse()?)?,
+        JsonValue::Array(arr) => {
+            out.push(b'[');
+            for (i, v) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_canonical(out, v).map_err(|e| e.within(&i.to_string()))?;
+            }
+            out.push(b']');
+        }
+        JsonValue::Object(obj) => {
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push(b'{');
+            for (i, (k, v)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_str(out, k);
+                out.push(b':');
+                write_canonical(out, v).map_err(|e| e.within(k))?;
+            }
+            out.push(b'}');
+        }
+    }
+    Ok(())
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_string(out, &mut CompactFormatter, s, &JCS_ESCAPE).expect("writing to a Vec never fails");
+}
+
+/// Format a double the way ECMAScript's Number-to-String does (ECMA-262
+/// 7.1.12.1), which is what RFC 8785 mandates.
+fn es_number_to_string(f: f64) -> Result<String, JsonError> {
+    if !f.is_finite() {
+        return Err(JsonError::serialize(format!(
+            "{} is not allowed in canonical JSON",
+            f
+        )));
+    }
+    if f == 0.0 {
+        return Ok("0".to_string());
+    }
+
+    let (digits, n) = shortest_digits(f.abs());
+    let k = digits.len() as i32;
+
+    let mut ret = String::new();
+    if f < 0.0 {
+        ret.push('-');
+    }
+    if k <= n && n <= 21 {
+        ret.push_str(&digits);
+        ret.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        ret.push_str(&digits[..n as usize]);
+        ret.push('.');
+        ret.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        ret.push_str("0.");
+        ret.extend(std::iter::repeat_n('0', -n as usize));
+        ret.push_str(&digits);
+    } else {
+        ret.push_str(&digits[..1]);
+        if k > 1 {
+            ret.push('.');
+            ret.push_str(&digits[1..]);
+        }
+        ret.push('e');
+        ret.push(if n > 0 { '+' } else { '-' });
+        ret.push_str(&(n - 1).abs().to_string());
+    }
+    Ok(ret)
+}
+
+/// Shortest round-trip significant digits of a positive finite `f` and the
+/// decimal exponent `n` such that `f = 0.digits * 10^n`.
+fn shortest_digits(f: f64) -> (String, i32) {
+    // `{:e}` yields the shortest round-trip digits, e.g. "1.2345e-7"
+    let (digits, n) = split_sci(&format!("{:e}", f));
+
+    // When the value lies exactly halfway between two equally short candidates,
+    // Rust picks the upper one while ECMAScript picks the even one.
+    let (exact, exact_n) = split_sci(&format!("{:.767e}", f));
+    let exact = exact.trim_end_matches('0');
+    if exact.len() == digits.len() + 1 && exact.ends_with('5') {
+        let lower = &exact[..digits.len()];
+        let last = lower.as_bytes()[lower.len() - 1];
+        if last % 2 == 0 && lower != digits {
+            let candidate = format!("0.{}e{}", lower, exact_n);
+            if candidate.parse::<f64>() == Ok(f) {
+                return (lower.to_string(), exact_n);
+            }
+        }
+    }
+    (digits, n)
+}
+
+fn split_sci(sci: &str) -> (String, i32) {
+    let (mantissa, exp) = sci.split_once('e').expect("{:e} always has an exponent");
+    let digits = mantissa.chars().filter(|c| *c != '.').collect();
+    (
+        digits,
+        exp.parse::<i32>().expect("exponent is an integer") + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonObject;
+
+    #[test]
+    fn rfc8785_number_vectors_should_match() {
+        // RFC 8785 Appendix B
+        let vectors = [
+            (0x0000000000000000u64, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, expected) in vectors {
+            let v = JsonValue::Number(Num::Float(f64::from_bits(bits)));
+            assert_eq!(v.to_canonical_json().unwrap(), expected, "{:#018x}", bits);
+        }
+    }
+
+    #[test]
+    fn non_finite_numbers_should_be_rejected() {
+        for bits in [0x7fffffffffffffffu64, 0x7ff0000000000000] {
+            let mut obj = JsonObject::default();
+            obj.insert(
+                "a".into(),
+                JsonValue::Array(vec![
+                    JsonValue::Null,
+                    JsonValue::Number(Num::Float(f64::from_bits(bits))),
+                ]),
+            );
+            let err = JsonValue::Object(obj).to_canonical_json().unwrap_err();
+            assert!(matches!(err, JsonError::Serialize { ref pointer, .. } if pointer == "/a/1"));
+        }
+    }
+
+    #[test]
+    fn rfc8785_sample_should_match() {
+        // RFC 8785 section 3.2.2
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        let v = crate::json::parse_json(input).unwrap();
+        let expected = r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#;
+        assert_eq!(v.to_canonical_json().unwrap(), expected);
+    }
+
+    #[test]
+    fn keys_should_sort_by_utf16_code_units() {
+        // RFC 8785 section 3.2.3
+        let keys = [
+            "\u{20ac}",
+            "\r",
+            "\u{fb33}",
+            "1",
+            "\u{1f600}",
+            "\u{80}",
+            "\u{f6}",
+        ];
+        let obj: JsonObject = keys
+            .iter()
+            .map(|k| ((*k).into(), JsonValue::Null))
+            .collect();
+        let s = JsonValue::Object(obj).to_canonical_json().unwrap();
+        let expected = "{\"\\r\":null,\"1\":null,\"\u{80}\":null,\"\u{f6}\":null,\"\u{20ac}\":null,\"\u{1f600}\":null,\"\u{fb33}\":null}";
+        assert_eq!(s, expected);
+    }
+
+    #[test]
+    fn equal_values_should_canonicalize_identically() {
+        let a = crate::json::parse_json(r#"{"b": [1, 2.5], "a": {"y": null, "x": true}}"#).unwrap();
+        let b = crate::json::parse_json(r#"{"a":{"x":true,"y":null},"b":[1,2.5]}"#).unwrap();
+        assert_eq!(a.to_canonical_json(), b.to_canonical_json());
+    }
+
+    #[test]
+    fn raw_number_text_should_be_ignored() {
+        let options = crate::json::ParseOptions {
+            preserve_number_text: true,
+            ..Default::default()
+        };
+        let v = crate::json::parse_json_with("[1.000, 1e+5, -0.0]", &options).unwrap();
+        assert_eq!(v.to_canonical_json().unwrap(), "[1,100000,0]");
+    }
+}
@@ -0,0 +1,745 @@
+use super::{
+    base64::{self, STD_ALPHABET, URL_ALPHABET},
+    json_string, ErrorCode, JsonError, JsonObject, JsonString, JsonValue, Num, ParseOptions,
+};
+
+/// Encode `value` as CBOR (RFC 8949) in preferred serialization: integers
+/// and lengths take the shortest head, and floats the shortest of half,
+/// single or double precision that holds the value exactly. Map keys are
+/// sorted by their encoded bytes, as deterministic encoding asks. Raw
+/// integers beyond 64 bits become bignums (tags 2 and 3); other raw numbers
+/// are encoded as floats.
+pub fn to_cbor(value: &JsonValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode(&mut out, value);
+    out
+}
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+fn encode(out: &mut Vec<u8>, value: &JsonValue) {
+    match value {
+        JsonValue::Null => out.push(0xf6),
+        JsonValue::Bool(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+        JsonValue::Number(Num::Int(i)) => encode_int(out, i128::from(*i)),
+        JsonValue::Number(Num::Float(f)) => encode_float(out, *f),
+        JsonValue::Number(Num::Raw(s)) => encode_raw_number(out, s),
+        #[cfg(feature = "decimal")]
+        JsonValue::Number(Num::Decimal(d)) => encode_raw_number(out, &d.to_string()),
+        JsonValue::String(s) => encode_text(out, s),
+        JsonValue::Array(arr) => {
+            head(out, ARRAY, arr.len() as u64);
+            for v in arr {
+                encode(out, v);
+            }
+        }
+        JsonValue::Object(obj) => {
+            head(out, MAP, obj.len() as u64);
+            let mut members: Vec<_> = obj
+                .iter()
+                .map(|(k, v)| {
+                    let mut key = Vec::new();
+                    encode_text(&mut key, k);
+                    (key, v)
+                })
+                .collect();
+            members.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+            for (k, v) in members {
+                out.extend_from_slice(&k);
+                encode(out, v);
+            }
+        }
+        JsonValue::Raw(raw) => encode(out, &raw.parse().expect("raw values are valid JSON")),
+    }
+}
+
+/// Write the initial byte of major type `major` with argument `n`.
+fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    match n {
+        0..=23 => out.push(major | n as u8),
+        24..=0xff => out.extend_from_slice(&[major | 24, n as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
+
+/// Integers in -2^64..2^64, the range of major types 0 and 1.
+fn encode_int(out: &mut Vec<u8>, i: i128) {
+    if i >= 0 {
+        head(out, UNSIGNED, i as u64);
+    } else {
+        head(out, NEGATIVE, (-1 - i) as u64);
+    }
+}
+
+fn encode_text(out: &mut Vec<u8>, s: &str) {
+    head(out, TEXT, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn encode_raw_number(out: &mut Vec<u8>, s: &str) {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return encode_float(out, s.parse().unwrap_or(f64::NAN));
+    }
+    let magnitude = decimal_to_bytes(digits);
+    let n = magnitude.iter().fold(0u128, |acc, b| {
+        acc.saturating_mul(256).saturating_add(u128::from(*b))
+    });
+    if !negative && n <= u128::from(u64::MAX) {
+        return head(out, UNSIGNED, n as u64);
+    }
+    if negative && n != 0 && n - 1 <= u128::from(u64::MAX) {
+        return head(out, NEGATIVE, (n - 1) as u64);
+    }
+    // a bignum holds the magnitude, or magnitude - 1 when negative
+    let bytes = if negative {
+        decrement(magnitude)
+    } else {
+        magnitude
+    };
+    head(out, TAG, if negative { 3 } else { 2 });
+    head(out, BYTES, bytes.len() as u64);
+    out.extend_from_slice(&bytes);
+}
+
+/// Big-endian bytes of a decimal digit string, without leading zeros.
+fn decimal_to_bytes(digits: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for d in digits.bytes() {
+        let mut carry = u32::from(d - b'0');
+        for b in bytes.iter_mut().rev() {
+            let v = u32::from(*b) * 10 + carry;
+            *b = v as u8;
+            carry = v >> 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    bytes
+}
+
+fn decrement(mut bytes: Vec<u8>) -> Vec<u8> {
+    for b in bytes.iter_mut().rev() {
+        if *b > 0 {
+            *b -= 1;
+            break;
+        }
+        *b = 0xff;
+    }
+    while bytes.first() == Some(&0) {
+        bytes.remove(0);
+    }
+    bytes
+}
+
+/// Decimal digits of a big-endian unsigned integer.
+fn bytes_to_decimal(bytes: &[u8]) -> String {
+    let mut digits: Vec<u8> = Vec::new();
+    for b in bytes {
+        let mut carry = u32::from(*b);
+        for d in digits.iter_mut() {
+            let v = u32::from(*d) * 256 + carry;
+            *d = (v % 10) as u8;
+            carry = v / 10;
+        }
+        while carry > 0 {
+            digits.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+    digits.iter().rev().map(|d| char::from(b'0' + d)).collect()
+}
+
+fn encode_float(out: &mut Vec<u8>, f: f64) {
+    if let Some(half) = f64_to_f16(f) {
+        out.push(0xf9);
+        out.extend_from_slice(&half.to_be_bytes());
+    } else if f64::from(f as f32) == f {
+        out.push(0xfa);
+        out.extend_from_slice(&(f as f32).to_be_bytes());
+    } else {
+        out.push(0xfb);
+        out.extend_from_slice(&f.to_be_bytes());
+    }
+}
+
+/// The half-precision bits of `f`, if it has an exact half-precision form.
+/// All NaNs map to the canonical quiet NaN.
+fn f64_to_f16(f: f64) -> Option<u16> {
+    if f.is_nan() {
+        return Some(0x7e00);
+    }
+    let sign = if f.is_sign_negative() { 0x8000 } else { 0 };
+    let a = f.abs();
+    if a == 0.0 {
+        return Some(sign);
+    }
+    if a.is_infinite() {
+        return Some(sign | 0x7c00);
+    }
+    // subnormals are multiples of 2^-24 below 2^-14
+    if a < f64::powi(2.0, -14) {
+        let m = a * f64::powi(2.0, 24);
+        return (m.fract() == 0.0).then_some(sign | m as u16);
+    }
+    let exp = ((a.to_bits() >> 52) & 0x7ff) as i32 - 1023;
+    if exp > 15 {
+        return None;
+    }
+    let m = a / f64::powi(2.0, exp) * 1024.0;
+    if m.fract() != 0.0 {
+        return None;
+    }
+    Some(sign | ((exp + 15) as u16) << 10 | (m as u16 - 1024))
+}
+
+fn f16_to_f64(half: u16) -> f64 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = i32::from((half >> 10) & 0x1f);
+    let mant = f64::from(half & 0x3ff);
+    sign * match exp {
+        0 => mant * f64::powi(2.0, -24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1024.0 + mant) * f64::powi(2.0, exp - 25),
+    }
+}
+
+/// Decode one CBOR data item that makes up all of `bytes`. Definite and
+/// indefinite lengths are accepted. Following RFC 8949 section 6.1:
+///
+/// - integers outside i64 and bignums (tags 2 and 3) become [`Num::Raw`];
+/// - byte strings become base64url text, or base64 or hex under tags 22
+///   and 23; other tags are dropped and their content kept;
+/// - `undefined` becomes null; other simple values are rejected.
+///
+/// Map keys that are integers, floats, booleans or null are stringified to
+/// their JSON text, so `{1: 2}` decodes to `{"1": 2}`; container keys are
+/// rejected. Arrays, maps and tags nested deeper than
+/// [`ParseOptions::DEFAULT_MAX_DEPTH`] are rejected too, as the text parser
+/// rejects them, rather than overflowing the stack. Error offsets are byte
+/// offsets into `bytes`.
+pub fn from_cbor(bytes: &[u8]) -> Result<JsonValue, JsonError> {
+    let mut decoder = Decoder {
+        bytes,
+        pos: 0,
+        encoding: ByteEncoding::Base64Url,
+        depth: 0,
+    };
+    let value = decoder.item()?;
+    if decoder.pos != bytes.len() {
+        return Err(decoder.error_at(
+            decoder.pos,
+            ErrorCode::TrailingData,
+            "trailing bytes after data item",
+        ));
+    }
+    Ok(value)
+}
+
+#[derive(Clone, Copy)]
+enum ByteEncoding {
+    Base64Url,
+    Base64,
+    Base16,
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    encoding: ByteEncoding,
+    /// Arrays, maps and tags open around the item being decoded.
+    depth: usize,
+}
+
+/// What the head of a data item announces.
+enum Head {
+    Definite(u64),
+    Indefinite,
+    Break,
+}
+
+impl<'a> Decoder<'a> {
+    fn error_at(&self, offset: usize, code: ErrorCode, message: &str) -> JsonError {
+        JsonError::parse(code, offset, message)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], JsonError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| {
+                self.error_at(
+                    self.bytes.len(),
+                    ErrorCode::UnexpectedEof,
+                    "unexpected end of input",
+                )
+            })?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn head(&mut self) -> Result<(u8, Head), JsonError> {
+        let start = self.pos;
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let arg = match info {
+            0..=23 => u64::from(info),
+            24..=27 => {
+                let n = 1 << (info - 24);
+                self.take(n)?
+                    .iter()
+                    .fold(0, |acc, b| (acc << 8) | u64::from(*b))
+            }
+            31 if major == SIMPLE => return Ok((major, Head::Break)),
+            31 if matches!(major, BYTES | TEXT | ARRAY | MAP) => {
+                return Ok((major, Head::Indefinite))
+            }
+            _ => {
+                return Err(self.error_at(
+                    start,
+                    ErrorCode::UnexpectedToken,
+                    "malformed initial byte",
+                ))
+            }
+        };
+        Ok((major, Head::Definite(arg)))
+    }
+
+    fn item(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.pos;
+        let (major, head) = self.head()?;
+        let n = match head {
+            Head::Definite(n) => n,
+            Head::Break => {
+                return Err(self.error_at(start, ErrorCode::UnexpectedToken, "unexpected break"))
+            }
+            Head::Indefinite if matches!(major, ARRAY | MAP) => {
+                return self.nested(start, |d| d.indefinite(major, start))
+            }
+            Head::Indefinite => return self.indefinite(major, start),
+        };
+        Ok(match major {
+            UNSIGNED => int(i128::from(n)),
+            NEGATIVE => int(-1 - i128::from(n)),
+            BYTES => {
+                let bytes = self.take_len(n)?;
+                JsonValue::String(json_string(self.bytes_to_text(bytes)))
+            }
+            TEXT => {
+                let bytes = self.take_len(n)?;
+                JsonValue::String(utf8(bytes, start, self)?)
+            }
+            ARRAY => self.nested(start, |d| {
+                let mut arr = Vec::with_capacity(d.capacity(n));
+                for _ in 0..n {
+                    arr.push(d.item()?);
+                }
+                Ok(JsonValue::Array(arr))
+            })?,
+            MAP => self.nested(start, |d| {
+                let mut obj = JsonObject::default();
+                for _ in 0..n {
+                    let key = d.key()?;
+                    obj.insert(key, d.item()?);
+                }
+                Ok(JsonValue::Object(obj))
+            })?,
+            TAG => return self.nested(start, |d| d.tagged(n)),
+            _ => match (self.bytes[start] & 0x1f, n) {
+                (_, 20) => JsonValue::Bool(false),
+                (_, 21) => JsonValue::Bool(true),
+                (_, 22) | (_, 23) => JsonValue::Null,
+                (25, half) => JsonValue::Number(Num::Float(f16_to_f64(half as u16))),
+                (26, single) => {
+                    JsonValue::Number(Num::Float(f64::from(f32::from_bits(single as u32))))
+                }
+                (27, double) => JsonValue::Number(Num::Float(f64::from_bits(double))),
+                _ => {
+                    return Err(self.error_at(
+                        start,
+                        ErrorCode::Unsupported,
+                        "simple values have no JSON equivalent",
+                    ))
+                }
+            },
+        })
+    }
+
+    /// `decode` run one level deeper, or an error at `start` when that
+    /// would pass the limit.
+    fn nested(
+        &mut self,
+        start: usize,
+        decode: impl FnOnce(&mut Self) -> Result<JsonValue, JsonError>,
+    ) -> Result<JsonValue, JsonError> {
+        if self.depth == ParseOptions::DEFAULT_MAX_DEPTH {
+            return Err(self.error_at(start, ErrorCode::DepthExceeded, "nesting is too deep"));
+        }
+        self.depth += 1;
+        let value = decode(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn take_len(&mut self, n: u64) -> Result<&'a [u8], JsonError> {
+        let n = usize::try_from(n)
+            .map_err(|_| self.error_at(self.pos, ErrorCode::LimitExceeded, "length too large"))?;
+        self.take(n)
+    }
+
+    /// A preallocation size that a bogus length cannot blow up.
+    fn capacity(&self, n: u64) -> usize {
+        (n as usize).min(self.bytes.len() - self.pos)
+    }
+
+    fn indefinite(&mut self, major: u8, start: usize) -> Result<JsonValue, JsonError> {
+        let mut arr = Vec::new();
+        let mut obj = JsonObject::default();
+        let mut chunks = Vec::new();
+        loop {
+            if self.bytes.get(self.pos) == Some(&0xff) {
+                self.pos += 1;
+                break;
+            }
+            match major {
+                ARRAY => arr.push(self.item()?),
+                MAP => {
+                    let key = self.key()?;
+                    obj.insert(key, self.item()?);
+                }
+                _ => {
+                    // string chunks must be definite strings of the same type
+                    let chunk_start = self.pos;
+                    match self.head()? {
+                        (m, Head::Definite(n)) if m == major => {
+                            chunks.extend_from_slice(self.take_len(n)?)
+                        }
+                        _ => {
+                            return Err(self.error_at(
+                                chunk_start,
+                                ErrorCode::UnexpectedToken,
+                                "invalid string chunk",
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+        Ok(match major {
+            ARRAY => JsonValue::Array(arr),
+            MAP => JsonValue::Object(obj),
+            BYTES => JsonValue::String(json_string(self.bytes_to_text(&chunks))),
+            _ => JsonValue::String(utf8(&chunks, start, self)?),
+        })
+    }
+
+    fn key(&mut self) -> Result<JsonString, JsonError> {
+        let start = self.pos;
+        match self.item()? {
+            JsonValue::String(s) => Ok(s),
+            JsonValue::Array(_) | JsonValue::Object(_) => Err(self.error_at(
+                start,
+                ErrorCode::Unsupported,
+                "map keys must not be arrays or maps",
+            )),
+            key => Ok(json_string(key.to_json_string())),
+        }
+    }
+
+    fn tagged(&mut self, tag: u64) -> Result<JsonValue, JsonError> {
+        let start = self.pos;
+        let hint = match tag {
+            21 => Some(ByteEncoding::Base64Url),
+            22 => Some(ByteEncoding::Base64),
+            23 => Some(ByteEncoding::Base16),
+            _ => None,
+        };
+        if tag == 2 || tag == 3 {
+            if let (BYTES, Head::Definite(n)) = self.head()? {
+                let digits = bytes_to_decimal(self.take_len(n)?);
+                let text = if tag == 2 {
+                    digits
+                } else {
+                    // -1 - n: add one to the magnitude
+                    let mut bytes = decimal_to_bytes(&digits);
+                    let mut carry = true;
+                    for b in bytes.iter_mut().rev() {
+                        let (v, c) = b.overflowing_add(u8::from(carry));
+                        *b = v;
+                        carry = c;
+                    }
+                    if carry || bytes.is_empty() {
+                        bytes.insert(0, 1);
+                    }
+                    format!("-{}", bytes_to_decimal(&bytes))
+                };
+                return Ok(number_from_text(text));
+            }
+            return Err(self.error_at(
+                start,
+                ErrorCode::UnexpectedToken,
+                "bignum content must be a byte string",
+            ));
+        }
+        let saved = self.encoding;
+        if let Some(hint) = hint {
+            self.encoding = hint;
+        }
+        let value = self.item();
+        self.encoding = saved;
+        value
+    }
+
+    fn bytes_to_text(&self, bytes: &[u8]) -> String {
+        match self.encoding {
+            ByteEncoding::Base64Url => base64::encode(bytes, URL_ALPHABET, false),
+            ByteEncoding::Base64 => base64::encode(bytes, STD_ALPHABET, true),
+            ByteEncoding::Base16 => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+}
+
+fn utf8(bytes: &[u8], start: usize, decoder: &Decoder<'_>) -> Result<JsonString, JsonError> {
+    std::str::from_utf8(bytes).map(json_string).map_err(|_| {
+        decoder.error_at(
+            start,
+            ErrorCode::InvalidUtf8,
+            "invalid UTF-8 in text string",
+        )
+    })
+}
+
+fn int(i: i128) -> JsonValue {
+    match i64::try_from(i) {
+        Ok(i) => JsonValue::Number(Num::Int(i)),
+        Err(_) => JsonValue::Number(Num::Raw(i.to_string())),
+    }
+}
+
+fn number_from_text(text: String) -> JsonValue {
+    match text.parse::<i64>() {
+        Ok(i) => JsonValue::Number(Num::Int(i)),
+        Err(_) => JsonValue::Number(Num::Raw(text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, parse_json_with, ParseOptions};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// RFC 8949 Appendix A vectors that have a JSON equivalent, with
+    /// whether our encoder produces exactly these bytes again.
+    const VECTORS: &[(&str, &str, bool)] = &[
+        ("00", "0", true),
+        ("01", "1", true),
+        ("0a", "10", true),
+        ("17", "23", true),
+        ("1818", "24", true),
+        ("1819", "25", true),
+        ("1864", "100", true),
+        ("1903e8", "1000", true),
+        ("1a000f4240", "1000000", true),
+        ("1b000000e8d4a51000", "1000000000000", true),
+        ("1bffffffffffffffff", "18446744073709551615", true),
+        ("c249010000000000000000", "18446744073709551616", true),
+        ("3bffffffffffffffff", "-18446744073709551616", true),
+        ("c349010000000000000000", "-18446744073709551617", true),
+        ("20", "-1", true),
+        ("29", "-10", true),
+        ("3863", "-100", true),
+        ("3903e7", "-1000", true),
+        ("f90000", "0.0", true),
+        ("f98000", "-0.0", true),
+        ("f93c00", "1.0", true),
+        ("fb3ff199999999999a", "1.1", true),
+        ("f93e00", "1.5", true),
+        ("f97bff", "65504.0", true),
+        ("fa47c35000", "100000.0", true),
+        ("fa7f7fffff", "3.4028234663852886e+38", true),
+        ("fb7e37e43c8800759c", "1.0e+300", true),
+        ("f90001", "5.960464477539063e-8", true),
+        ("f90400", "0.00006103515625", true),
+        ("f9c400", "-4.0", true),
+        ("fbc010666666666666", "-4.1", true),
+        ("f4", "false", true),
+        ("f5", "true", true),
+        ("f6", "null", true),
+        ("f7", "null", false),
+        ("c074323031332d30332d32315432303a30343a30305a", r#""2013-03-21T20:04:00Z""#, false),
+        ("c11a514b67b0", "1363896240", false),
+        ("c1fb41d452d9ec200000", "1363896240.5", false),
+        ("d74401020304", r#""01020304""#, false),
+        ("d818456449455446", r#""ZElFVEY""#, false),
+        ("d82076687474703a2f2f7777772e6578616d706c652e636f6d", r#""http://www.example.com""#, false),
+        ("40", r#""""#, false),
+        ("4401020304", r#""AQIDBA""#, false),
+        ("60", r#""""#, true),
+        ("6161", r#""a""#, true),
+        ("6449455446", r#""IETF""#, true),
+        ("62225c", r#""\"\\""#, true),
+        ("62c3bc", r#""ü""#, true),
+        ("63e6b0b4", r#""水""#, true),
+        ("64f0908591", r#""𐅑""#, true),
+        ("80", "[]", true),
+        ("83010203", "[1, 2, 3]", true),
+        ("8301820203820405", "[1, [2, 3], [4, 5]]", true),
+        ("98190102030405060708090a0b0c0d0e0f101112131415161718181819", "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25]", true),
+        ("a0", "{}", true),
+        ("a201020304", r#"{"1": 2, "3": 4}"#, false),
+        ("a26161016162820203", r#"{"a": 1, "b": [2, 3]}"#, true),
+        ("826161a161626163", r#"["a", {"b": "c"}]"#, true),
+        ("a56161614161626142616361436164614461656145", r#"{"a": "A", "b": "B", "c": "C", "d": "D", "e": "E"}"#, true),
+        ("5f42010243030405ff", r#""AQIDBAU""#, false),
+        ("7f657374726561646d696e67ff", r#""streaming""#, false),
+        ("9fff", "[]", false),
+        ("9f018202039f0405ffff", "[1, [2, 3], [4, 5]]", false),
+        ("9f01820203820405ff", "[1, [2, 3], [4, 5]]", false),
+        ("83018202039f0405ff", "[1, [2, 3], [4, 5]]", false),
+        ("83019f0203ff820405", "[1, [2, 3], [4, 5]]", false),
+        ("9f0102030405060708090a0b0c0d0e0f101112131415161718181819ff", "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25]", false),
+        ("bf61610161629f0203ffff", r#"{"a": 1, "b": [2, 3]}"#, false),
+        ("826161bf61626163ff", r#"["a", {"b": "c"}]"#, false),
+        ("bf6346756ef563416d7421ff", r#"{"Fun": true, "Amt": -2}"#, false),
+    ];
+
+    #[test]
+    fn cbor_should_decode_and_reencode_rfc_vectors() {
+        let exact_ints = ParseOptions::strict().big_int_as_float(false);
+        for &(bytes, json, exact) in VECTORS {
+            let bytes = hex(bytes);
+            // CBOR keeps integers past i64 exact, so they are spelled out raw
+            let expected = parse_json_with(json, &exact_ints)
+                .unwrap_or_else(|_| JsonValue::Number(Num::Raw(json.to_string())));
+            let decoded = from_cbor(&bytes).unwrap();
+            assert_eq!(decoded, expected, "{}", json);
+            if exact {
+                assert_eq!(to_cbor(&decoded), bytes, "{}", json);
+            }
+        }
+    }
+
+    #[test]
+    fn cbor_should_handle_non_finite_floats() {
+        for (bytes, f) in [("f97c00", f64::INFINITY), ("f9fc00", f64::NEG_INFINITY)] {
+            assert_eq!(from_cbor(&hex(bytes)).unwrap(), JsonValue::from(f));
+            assert_eq!(to_cbor(&JsonValue::from(f)), hex(bytes));
+        }
+        for bytes in ["f97e00", "fa7fc00000", "fb7ff8000000000000"] {
+            match from_cbor(&hex(bytes)).unwrap() {
+                JsonValue::Number(Num::Float(f)) => assert!(f.is_nan()),
+                v => panic!("{:?}", v),
+            }
+        }
+        assert_eq!(to_cbor(&JsonValue::from(f64::NAN)), hex("f97e00"));
+        // non-preferred encodings still decode
+        assert_eq!(
+            from_cbor(&hex("fb3ff8000000000000")).unwrap(),
+            JsonValue::from(1.5)
+        );
+    }
+
+    #[test]
+    fn cbor_should_round_trip_documents() {
+        for text in [
+            r#"{"name": "John Doe", "age": 30, "marks": [90.0, -80.0, 85.1], "address": {"zip": 10001}}"#,
+            "[9223372036854775807, -9223372036854775808, 0.1, 1e-310, 65536.5]",
+            r#"["", "café 😀", {}, [[]], null, true]"#,
+        ] {
+            let v = parse_json(text).unwrap();
+            assert_eq!(from_cbor(&to_cbor(&v)).unwrap(), v, "{}", text);
+        }
+        let long = JsonValue::from("x".repeat(70_000).as_str());
+        assert_eq!(from_cbor(&to_cbor(&long)).unwrap(), long);
+    }
+
+    #[test]
+    fn cbor_should_reject_malformed_input() {
+        let err = |bytes: &str| match from_cbor(&hex(bytes)).unwrap_err() {
+            JsonError::Parse {
+                offset, message, ..
+            } => (offset, message),
+            e => panic!("{:?}", e),
+        };
+        assert_eq!(err("8201"), (2, "unexpected end of input".to_string()));
+        assert_eq!(
+            err("0101"),
+            (1, "trailing bytes after data item".to_string())
+        );
+        assert_eq!(
+            err("a18001"),
+            (1, "map keys must not be arrays or maps".to_string())
+        );
+        assert_eq!(
+            err("f0"),
+            (0, "simple values have no JSON equivalent".to_string())
+        );
+        assert_eq!(err("1c"), (0, "malformed initial byte".to_string()));
+        assert_eq!(err("ff"), (0, "unexpected break".to_string()));
+        assert_eq!(err("7f01ff").1, "invalid string chunk");
+        assert_eq!(err("62c328").1, "invalid UTF-8 in text string");
+        assert_eq!(err("9bffffffffffffffff").1, "unexpected end of input");
+        let code = |bytes: &str| from_cbor(&hex(bytes)).unwrap_err().code();
+        assert_eq!(code("8201"), ErrorCode::UnexpectedEof);
+        assert_eq!(code("0101"), ErrorCode::TrailingData);
+        assert_eq!(code("f0"), ErrorCode::Unsupported);
+        assert_eq!(code("62c328"), ErrorCode::InvalidUtf8);
+    }
+
+    #[test]
+    fn cbor_should_reject_nesting_deeper_than_the_text_parser_takes() {
+        let limit = ParseOptions::DEFAULT_MAX_DEPTH;
+        // indefinite arrays, one-element arrays and tags, all unclosed
+        for byte in [0x9f, 0x81, 0xc6] {
+            let err = from_cbor(&vec![byte; 100_000]).unwrap_err();
+            assert_eq!(err.code(), ErrorCode::DepthExceeded, "{:02x}", byte);
+            assert!(matches!(err, JsonError::Parse { offset, .. } if offset == limit));
+        }
+        let mut deepest = vec![0x81; limit];
+        deepest.push(0x00);
+        let value = from_cbor(&deepest).unwrap();
+        assert_eq!(to_cbor(&value), deepest);
+        deepest.insert(0, 0x81);
+        assert_eq!(
+            from_cbor(&deepest).unwrap_err().code(),
+            ErrorCode::DepthExceeded
+        );
+    }
+}
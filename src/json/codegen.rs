@@ -0,0 +1,323 @@
+use super::{infer::Shape, JsonValue};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RustTypesOptions {
+    /// Derive serde's `Serialize` and `Deserialize`, with `#[serde(rename)]`
+    /// on renamed fields and `serde_json::Value` for values of mixed type.
+    /// Without it the original key is noted in a doc comment and mixed
+    /// values are [`JsonValue`].
+    pub serde: bool,
+}
+
+/// Rust struct definitions for data shaped like `sample`, with the root
+/// struct named `root_name`. See [`generate_rust_types_with`].
+pub fn generate_rust_types(sample: &JsonValue, root_name: &str) -> String {
+    generate_rust_types_with(sample, root_name, &RustTypesOptions::default())
+}
+
+/// Field types are inferred from the sample: `bool`, `i64`, `f64` (also for
+/// a mix of integers and floats), `String`, `Vec<T>`, `Option<T>` for fields
+/// that are null or missing somewhere, and a nested struct named after its
+/// key for objects. Elements of an array of objects are merged into one
+/// struct. Keys that are not valid Rust identifiers are converted to snake
+/// case and annotated with the original.
+pub fn generate_rust_types_with(
+    sample: &JsonValue,
+    root_name: &str,
+    options: &RustTypesOptions,
+) -> String {
+    let mut shape = Shape::default();
+    shape.add(sample);
+    let mut gen = Generator {
+        options,
+        structs: Vec::new(),
+        names: Vec::new(),
+        uses_value: false,
+    };
+    let root = pascal_case(root_name);
+    let alias = if shape.objects > 0 && !mixed(&shape) {
+        gen.define(&shape, &root);
+        None
+    } else {
+        Some(gen.type_of(&shape, &root))
+    };
+
+    let mut out = String::new();
+    if options.serde {
+        out.push_str("use serde::{Deserialize, Serialize};\n\n");
+    } else if gen.uses_value {
+        out.push_str("use grammar::json::JsonValue;\n\n");
+    }
+    if let Some(ty) = alias {
+        writeln!(out, "pub type {} = {};\n", root, ty).unwrap();
+    }
+    out.push_str(&gen.structs.join("\n"));
+    out
+}
+
+struct Generator<'o> {
+    options: &'o RustTypesOptions,
+    /// Finished definitions, root first.
+    structs: Vec<String>,
+    names: Vec<String>,
+    uses_value: bool,
+}
+
+impl Generator<'_> {
+    /// Emit a struct for an object shape and return its (deduplicated) name.
+    fn define(&mut self, shape: &Shape, name: &str) -> String {
+        let mut unique = name.to_string();
+        let mut n = 2;
+        while self.names.contains(&unique) {
+            unique = format!("{}{}", name, n);
+            n += 1;
+        }
+        self.names.push(unique.clone());
+        let slot = self.structs.len();
+        self.structs.push(String::new());
+
+        let derives = if self.options.serde {
+            "Debug, Clone, PartialEq, Serialize, Deserialize"
+        } else {
+            "Debug, Clone, PartialEq"
+        };
+        let mut def = format!("#[derive({})]\npub struct {} {{\n", derives, unique);
+        for (key, field, seen) in &shape.properties {
+            let mut ty = self.type_of(field, &pascal_case(key));
+            if *seen < shape.objects && !field.null {
+                ty = format!("Option<{}>", ty);
+            }
+            let ident = field_name(key);
+            if ident != *key {
+                if self.options.serde {
+                    writeln!(def, "    #[serde(rename = {:?})]", key).unwrap();
+                } else {
+                    writeln!(def, "    /// JSON key: `{}`", key).unwrap();
+                }
+            }
+            writeln!(def, "    pub {}: {},", ident, ty).unwrap();
+        }
+        def.push_str("}\n");
+        self.structs[slot] = def;
+        unique
+    }
+
+    fn type_of(&mut self, shape: &Shape, name: &str) -> String {
+        let ty = if mixed(shape) {
+            self.value_type()
+        } else if shape.boolean {
+            "bool".to_string()
+        } else if shape.number {
+            "f64".to_string()
+        } else if shape.integer {
+            "i64".to_string()
+        } else if shape.string {
+            "String".to_string()
+        } else if shape.array {
+            match &shape.items {
+                Some(items) => format!("Vec<{}>", self.type_of(items, &singular(name))),
+                None => format!("Vec<{}>", self.value_type()),
+            }
+        } else if shape.objects > 0 {
+            self.define(shape, name)
+        } else {
+            // only ever null
+            self.value_type()
+        };
+        if shape.null {
+            format!("Option<{}>", ty)
+        } else {
+            ty
+        }
+    }
+
+    fn value_type(&mut self) -> String {
+        if self.options.serde {
+            "serde_json::Value".to_string()
+        } else {
+            self.uses_value = true;
+            "JsonValue".to_string()
+        }
+    }
+}
+
+/// Whether values of more than one kind (other than null) were seen.
+fn mixed(shape: &Shape) -> bool {
+    let kinds = [
+        shape.boolean,
+        shape.integer || shape.number,
+        shape.string,
+        shape.array,
+        shape.objects > 0,
+    ];
+    kinds.iter().filter(|k| **k).count() > 1
+}
+
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Split `key` into lowercase words at separators and case changes.
+fn words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lower = false;
+    for c in key.chars() {
+        if !c.is_ascii_alphanumeric() {
+            prev_lower = false;
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_ascii_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut word));
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        word.push(c.to_ascii_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn field_name(key: &str) -> String {
+    let mut name = words(key).join("_");
+    if name.is_empty() {
+        name = "field".to_string();
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+fn pascal_case(key: &str) -> String {
+    let mut name: String = words(key)
+        .iter()
+        .map(|w| {
+            let mut cs = w.chars();
+            cs.next()
+                .map(|c| c.to_ascii_uppercase().to_string() + cs.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert_str(0, "Type");
+    }
+    name
+}
+
+/// A name for the elements of an array called `name`: `Servers` gives
+/// `Server`, `Entries` gives `Entry`, anything else gets an `Item` suffix.
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies").filter(|s| !s.is_empty()) {
+        format!("{}y", stem)
+    } else if let Some(stem) = name
+        .strip_suffix('s')
+        .filter(|s| !s.is_empty() && !s.ends_with('s'))
+    {
+        stem.to_string()
+    } else {
+        format!("{}Item", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    /// Cheap well-formedness check: brackets balance and every field line
+    /// has the shape `pub name: Type,`.
+    fn assert_tokenizes(code: &str) {
+        let mut depth = 0i32;
+        for c in code.chars() {
+            match c {
+                '{' | '<' | '(' | '[' => depth += 1,
+                '}' | '>' | ')' | ']' => depth -= 1,
+                _ => {}
+            }
+            assert!(depth >= 0, "{}", code);
+        }
+        assert_eq!(depth, 0, "{}", code);
+        for line in code.lines().filter(|l| l.starts_with("    pub ")) {
+            let (name, ty) = line["    pub ".len()..].split_once(": ").unwrap();
+            assert!(
+                name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+                "{}",
+                line
+            );
+            assert!(ty.ends_with(','), "{}", line);
+        }
+    }
+
+    #[test]
+    fn generate_rust_types_should_match_golden_output() {
+        let sample = parse_json(
+            r#"{
+                "name": "John Doe",
+                "age": 30,
+                "is-student": false,
+                "marks": [90.0, -80.0, 85, null],
+                "address": {"city": "New York", "zip": 10001, "type": "home"},
+                "nickname": null
+            }"#,
+        )
+        .unwrap();
+        let code = generate_rust_types(&sample, "person");
+        assert_eq!(code, include_str!("../../tests/golden/person.rs.txt"));
+        assert_tokenizes(&code);
+    }
+
+    #[test]
+    fn generate_rust_types_should_unify_array_elements() {
+        let sample = parse_json(
+            r#"{
+                "servers": [
+                    {"host": "a", "port": 80, "tags": []},
+                    {"host": "b", "port": 8080, "tls": {"cert": "x"}, "tags": ["edge", 1]},
+                    {"host": "c", "port": 443.5, "2fa": true}
+                ],
+                "entries": [[1, 2], [3]],
+                "userID": "u1"
+            }"#,
+        )
+        .unwrap();
+        let options = RustTypesOptions { serde: true };
+        let code = generate_rust_types_with(&sample, "Config", &options);
+        assert_eq!(code, include_str!("../../tests/golden/config.rs.txt"));
+        assert_tokenizes(&code);
+    }
+
+    #[test]
+    fn generate_rust_types_should_alias_non_object_roots() {
+        let sample = parse_json(r#"[{"id": 1}, {"id": 2, "Type": "x"}]"#).unwrap();
+        let code = generate_rust_types(&sample, "Users");
+        assert_eq!(
+            code,
+            "pub type Users = Vec<User>;\n\n#[derive(Debug, Clone, PartialEq)]\npub struct User {\n    pub id: i64,\n    /// JSON key: `Type`\n    pub type_: Option<String>,\n}\n"
+        );
+        assert_tokenizes(&code);
+    }
+
+    #[test]
+    fn identifiers_should_be_sanitized() {
+        assert_eq!(field_name("first-name"), "first_name");
+        assert_eq!(field_name("userID"), "user_id");
+        assert_eq!(field_name("2fa"), "_2fa");
+        assert_eq!(field_name("$"), "field");
+        assert_eq!(field_name("match"), "match_");
+        assert_eq!(pascal_case("http_server"), "HttpServer");
+        assert_eq!(singular("Entries"), "Entry");
+        assert_eq!(singular("Address"), "AddressItem");
+    }
+}
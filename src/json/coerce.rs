@@ -0,0 +1,240 @@
+use super::{number_token, strict, JsonValue, Num};
+use std::fmt;
+use winnow::Parser;
+
+/// A scalar as text, from [`JsonValue::as_display`].
+#[derive(Debug, Clone, Copy)]
+pub struct ScalarDisplay<'a>(&'a JsonValue);
+
+impl fmt::Display for ScalarDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            JsonValue::String(s) => f.write_str(s),
+            JsonValue::Bool(b) => write!(f, "{}", b),
+            JsonValue::Number(Num::Int(i)) => write!(f, "{}", i),
+            JsonValue::Number(Num::Raw(s)) => f.write_str(s),
+            number => f.write_str(&number.to_json_string()),
+        }
+    }
+}
+
+impl JsonValue {
+    /// A scalar as text to put in a template or a cell: a string as it is,
+    /// without quotes, a number as the serializer writes it, and `true` or
+    /// `false`. `None` for null and for arrays and objects; see
+    /// [`to_display_string_with`](Self::to_display_string_with).
+    pub fn to_display_string(&self) -> Option<String> {
+        self.to_display_string_with(false)
+    }
+
+    /// [`to_display_string`](Self::to_display_string), rendering null as
+    /// the empty string when `null_as_empty` is set.
+    pub fn to_display_string_with(&self, null_as_empty: bool) -> Option<String> {
+        match self {
+            JsonValue::Null if null_as_empty => Some(String::new()),
+            value => value.as_display().map(|d| d.to_string()),
+        }
+    }
+
+    /// [`to_display_string`](Self::to_display_string) without building a
+    /// `String`, for `write!`.
+    pub fn as_display(&self) -> Option<ScalarDisplay<'_>> {
+        match self {
+            JsonValue::String(_) | JsonValue::Number(_) | JsonValue::Bool(_) => {
+                Some(ScalarDisplay(self))
+            }
+            _ => None,
+        }
+    }
+
+    /// The value as an `f64`, taking any number and also a string holding
+    /// one, such as `"30"` or `"1e3"`, written as the parser reads numbers.
+    /// Large integers round. Numbers past `f64` range are `None`.
+    pub fn as_f64_lossy(&self) -> Option<f64> {
+        self.as_f64_lossy_with(false)
+    }
+
+    /// [`as_f64_lossy`](Self::as_f64_lossy), also taking `false` and
+    /// `true` as 0 and 1 when `bools` is set.
+    pub fn as_f64_lossy_with(&self, bools: bool) -> Option<f64> {
+        let f = match self {
+            JsonValue::Number(Num::Int(i)) => *i as f64,
+            JsonValue::Number(Num::Float(f)) => *f,
+            JsonValue::Number(Num::Raw(s)) => s.parse().ok()?,
+            #[cfg(feature = "decimal")]
+            JsonValue::Number(Num::Decimal(d)) => d.to_f64(),
+            JsonValue::String(s) => numeric(s)?.parse().ok()?,
+            JsonValue::Bool(b) if bools => f64::from(u8::from(*b)),
+            _ => return None,
+        };
+        f.is_finite().then_some(f)
+    }
+
+    /// The value as an `i64`, taking integers, floats with an integral
+    /// value in range such as `30.0`, and strings holding an integer such
+    /// as `"30"`. A string with a fraction or an exponent is `None`, as is
+    /// anything out of range.
+    pub fn as_i64_lossy(&self) -> Option<i64> {
+        self.as_i64_lossy_with(false)
+    }
+
+    /// [`as_i64_lossy`](Self::as_i64_lossy), also taking `false` and
+    /// `true` as 0 and 1 when `bools` is set.
+    pub fn as_i64_lossy_with(&self, bools: bool) -> Option<i64> {
+        match self {
+            JsonValue::Number(Num::Int(i)) => Some(*i),
+            JsonValue::Number(Num::Float(f)) => integral(*f),
+            JsonValue::Number(Num::Raw(s)) if s.contains(['.', 'e', 'E']) => {
+                integral(s.parse().ok()?)
+            }
+            JsonValue::Number(Num::Raw(s)) => s.parse().ok(),
+            #[cfg(feature = "decimal")]
+            JsonValue::Number(Num::Decimal(d)) => d.to_i64(),
+            JsonValue::String(s) => {
+                let s = numeric(s)?;
+                if s.contains(['.', 'e', 'E']) {
+                    return None;
+                }
+                s.parse().ok()
+            }
+            JsonValue::Bool(b) if bools => Some(i64::from(*b)),
+            _ => None,
+        }
+    }
+}
+
+/// `s` when it is one whole number token.
+fn numeric(s: &str) -> Option<&str> {
+    number_token.parse(strict(s)).ok()
+}
+
+/// `f` as an `i64` when it has no fraction and is within range.
+fn integral(f: f64) -> Option<i64> {
+    // 2^63 itself is just out of range
+    let in_range = (-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&f);
+    (f.fract() == 0.0 && in_range).then_some(f as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{parse_json, parse_json_with, JsonValue, Num, ParseOptions};
+
+    fn raw(s: &str) -> JsonValue {
+        JsonValue::Number(Num::Raw(s.to_string()))
+    }
+
+    #[test]
+    fn to_display_string_should_render_scalars() {
+        for (json, expected) in [
+            (r#""plain""#, "plain"),
+            (r#""with \"quotes\" and \n""#, "with \"quotes\" and \n"),
+            (r#""""#, ""),
+            ("30", "30"),
+            ("-7", "-7"),
+            ("2.5", "2.5"),
+            ("1e3", "1000.0"),
+            ("true", "true"),
+            ("false", "false"),
+        ] {
+            let value = parse_json(json).unwrap();
+            assert_eq!(
+                value.to_display_string().as_deref(),
+                Some(expected),
+                "{}",
+                json
+            );
+            assert_eq!(
+                value.to_display_string_with(true).as_deref(),
+                Some(expected)
+            );
+            let shown = format!("<{}>", value.as_display().unwrap());
+            assert_eq!(shown, format!("<{}>", expected));
+            if !matches!(value, JsonValue::String(_)) {
+                assert_eq!(expected, value.to_json_string(), "{}", json);
+            }
+        }
+        let raw = parse_json_with("1.50", &ParseOptions::strict().preserve_number_text(true));
+        assert_eq!(raw.unwrap().to_display_string().as_deref(), Some("1.50"));
+    }
+
+    #[test]
+    fn to_display_string_should_refuse_null_and_containers() {
+        assert_eq!(JsonValue::Null.to_display_string(), None);
+        assert_eq!(
+            JsonValue::Null.to_display_string_with(true).as_deref(),
+            Some("")
+        );
+        assert!(JsonValue::Null.as_display().is_none());
+        for json in ["[]", "[1]", "{}", r#"{"a": "b"}"#] {
+            let value = parse_json(json).unwrap();
+            assert_eq!(value.to_display_string(), None, "{}", json);
+            assert_eq!(value.to_display_string_with(true), None, "{}", json);
+            assert!(value.as_display().is_none(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn as_f64_lossy_should_take_numbers_and_numeric_strings() {
+        for (value, expected) in [
+            (JsonValue::from(30), Some(30.0)),
+            (JsonValue::from(30.5), Some(30.5)),
+            (raw("1.000"), Some(1.0)),
+            (JsonValue::from("30"), Some(30.0)),
+            (JsonValue::from("-12.5"), Some(-12.5)),
+            (JsonValue::from("1e3"), Some(1000.0)),
+            (JsonValue::from("99999999999999999999"), Some(1e20)),
+            (JsonValue::from("1e400"), None),
+            (JsonValue::from(" 30"), None),
+            (JsonValue::from("30px"), None),
+            (JsonValue::from("+1"), None),
+            (JsonValue::from(".5"), None),
+            (JsonValue::from("0x1F"), None),
+            (JsonValue::from("NaN"), None),
+            (JsonValue::from("Infinity"), None),
+            (JsonValue::from(""), None),
+            (JsonValue::from(f64::INFINITY), None),
+            (JsonValue::from(true), None),
+            (JsonValue::Null, None),
+            (JsonValue::Array(vec![JsonValue::from(1)]), None),
+        ] {
+            assert_eq!(value.as_f64_lossy(), expected, "{:?}", value);
+        }
+        assert_eq!(JsonValue::from(true).as_f64_lossy_with(true), Some(1.0));
+        assert_eq!(JsonValue::from(false).as_f64_lossy_with(true), Some(0.0));
+        assert_eq!(JsonValue::Null.as_f64_lossy_with(true), None);
+    }
+
+    #[test]
+    fn as_i64_lossy_should_take_integral_values_only() {
+        for (value, expected) in [
+            (JsonValue::from(30), Some(30)),
+            (JsonValue::from(i64::MIN), Some(i64::MIN)),
+            (JsonValue::from(30.0), Some(30)),
+            (JsonValue::from(-2.0), Some(-2)),
+            (JsonValue::from(30.5), None),
+            (JsonValue::from(9.3e18), None),
+            (JsonValue::from(i64::MIN as f64), Some(i64::MIN)),
+            (JsonValue::from(f64::NAN), None),
+            (raw("7"), Some(7)),
+            (raw("1e3"), Some(1000)),
+            (raw("1.5"), None),
+            (JsonValue::from("30"), Some(30)),
+            (JsonValue::from("-30"), Some(-30)),
+            (JsonValue::from("9223372036854775807"), Some(i64::MAX)),
+            (JsonValue::from("9223372036854775808"), None),
+            (JsonValue::from("-99999999999999999999"), None),
+            (JsonValue::from("1e3"), None),
+            (JsonValue::from("12.5"), None),
+            (JsonValue::from("12.0"), None),
+            (JsonValue::from("12 "), None),
+            (JsonValue::from("twelve"), None),
+            (JsonValue::from(true), None),
+            (JsonValue::Null, None),
+        ] {
+            assert_eq!(value.as_i64_lossy(), expected, "{:?}", value);
+        }
+        assert_eq!(JsonValue::from(true).as_i64_lossy_with(true), Some(1));
+        assert_eq!(JsonValue::from(false).as_i64_lossy_with(true), Some(0));
+        assert_eq!(JsonValue::from("x").as_i64_lossy_with(true), None);
+    }
+}
@@ -0,0 +1,154 @@
+use super::{schema::type_name, JsonError, JsonObject, JsonValue, Pointer};
+
+/// Turn an array of objects into an object of arrays, one per key, so
+/// `[{"a": 1, "b": "x"}, {"a": 2}]` becomes `{"a": [1, 2], "b": ["x",
+/// null]}`: each column has an entry for every row, `null` where the row
+/// lacks the key. An empty array has no columns. Fails with
+/// [`JsonError::Decode`] on anything but an array, or at the first element
+/// that is not an object.
+pub fn to_columns(rows: &JsonValue) -> Result<JsonValue, JsonError> {
+    let JsonValue::Array(rows) = rows else {
+        return Err(shape(Pointer::default(), rows, "an array of objects"));
+    };
+    let mut columns = JsonObject::default();
+    for (i, row) in rows.iter().enumerate() {
+        let JsonValue::Object(row) = row else {
+            return Err(shape(Pointer::from_iter([i.to_string()]), row, "an object"));
+        };
+        for (key, value) in row {
+            match columns.get_mut(key) {
+                Some(JsonValue::Array(column)) => column.push(value.clone()),
+                Some(_) => unreachable!("columns are arrays"),
+                None => {
+                    let mut column = vec![JsonValue::Null; i];
+                    column.push(value.clone());
+                    columns.insert(key.clone(), JsonValue::Array(column));
+                }
+            }
+        }
+        for column in columns.values_mut() {
+            let JsonValue::Array(column) = column else {
+                unreachable!("columns are arrays")
+            };
+            column.resize(i + 1, JsonValue::Null);
+        }
+    }
+    Ok(JsonValue::Object(columns))
+}
+
+/// The reverse of [`to_columns`]: an object of equal-length arrays becomes
+/// an array of objects, row `i` holding element `i` of every column. A
+/// `null` that `to_columns` filled in comes back as a member that is
+/// `null`. An object with no columns has no rows. Fails with
+/// [`JsonError::Decode`] on anything but an object, or naming a column
+/// that is not an array or whose length differs from that of the first
+/// column in key order.
+pub fn from_columns(columns: &JsonValue) -> Result<JsonValue, JsonError> {
+    let JsonValue::Object(columns) = columns else {
+        return Err(shape(Pointer::default(), columns, "an object of arrays"));
+    };
+    let mut sorted: Vec<_> = columns.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    let mut len = None;
+    for (key, column) in &sorted {
+        let at = Pointer::from_iter([key.to_string()]);
+        let JsonValue::Array(column) = column else {
+            return Err(shape(at, column, "an array"));
+        };
+        match len {
+            None => len = Some((key, column.len())),
+            Some((first, n)) if n != column.len() => {
+                return Err(JsonError::Decode {
+                    path: at,
+                    message: format!(
+                        "column has length {}, but {:?} has length {}",
+                        column.len(),
+                        first.to_string(),
+                        n
+                    ),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    let len = len.map_or(0, |(_, n)| n);
+    let rows = (0..len)
+        .map(|i| {
+            let row = sorted.iter().map(|(key, column)| {
+                let JsonValue::Array(column) = column else {
+                    unreachable!("checked above")
+                };
+                ((*key).clone(), column[i].clone())
+            });
+            JsonValue::Object(row.collect())
+        })
+        .collect();
+    Ok(JsonValue::Array(rows))
+}
+
+fn shape(path: Pointer, found: &JsonValue, expected: &str) -> JsonError {
+    JsonError::Decode {
+        path,
+        message: format!("expected {}, found {}", expected, type_name(found)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn columns_should_round_trip() {
+        let rows = json(r#"[{"a": 1, "b": "x"}, {"a": 2, "b": "y"}, {"b": "z", "a": 3}]"#);
+        let columns = to_columns(&rows).unwrap();
+        assert_eq!(columns, json(r#"{"a": [1, 2, 3], "b": ["x", "y", "z"]}"#));
+        assert_eq!(from_columns(&columns).unwrap(), rows);
+        assert_eq!(to_columns(&json("[]")).unwrap(), json("{}"));
+        assert_eq!(from_columns(&json("{}")).unwrap(), json("[]"));
+        assert_eq!(from_columns(&json(r#"{"a": []}"#)).unwrap(), json("[]"));
+    }
+
+    #[test]
+    fn to_columns_should_fill_missing_keys_with_null() {
+        let rows = json(r#"[{"a": 1}, {}, {"b": true}, {"a": 4, "c": [0]}]"#);
+        let columns = to_columns(&rows).unwrap();
+        assert_eq!(
+            columns,
+            json(
+                r#"{"a": [1, null, null, 4], "b": [null, null, true, null], "c": [null, null, null, [0]]}"#
+            )
+        );
+        assert_eq!(
+            from_columns(&columns).unwrap(),
+            json(
+                r#"[{"a": 1, "b": null, "c": null}, {"a": null, "b": null, "c": null},
+                    {"a": null, "b": true, "c": null}, {"a": 4, "b": null, "c": [0]}]"#
+            )
+        );
+    }
+
+    #[test]
+    fn columns_should_reject_the_wrong_shape() {
+        let err = to_columns(&json(r#"[{"a": 1}, 2]"#)).unwrap_err();
+        assert_eq!(err.to_string(), "at /1: expected an object, found number");
+        let err = to_columns(&json(r#"{"a": 1}"#)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected an array of objects, found object"
+        );
+
+        let err = from_columns(&json(r#"{"b": [1], "a": [1, 2], "c": [3, 4]}"#)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"at /b: column has length 1, but "a" has length 2"#
+        );
+        let err = from_columns(&json(r#"{"a": [1], "b": "x"}"#)).unwrap_err();
+        assert!(matches!(&err, JsonError::Decode { path, .. } if path.to_string() == "/b"));
+        assert!(from_columns(&json("[]")).is_err());
+    }
+}
@@ -0,0 +1,505 @@
+use super::{
+    error::escape_pointer_token,
+    parse_json,
+    patch::same_number,
+    pointer::array_index,
+    redact::{Patterns, States},
+    JsonError, JsonValue,
+};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// How [`json_eq_with`], [`json_assert_eq_with`] and [`semantic_eq_with`]
+/// compare documents. Whitespace, key order and number spelling never
+/// matter.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompareOptions {
+    /// Take two numbers as equal when they differ by at most this much;
+    /// 0 by default, which compares them exactly.
+    pub float_tolerance: f64,
+    /// Take a missing member as equal to a member that is `null`.
+    pub missing_is_null: bool,
+    /// Compare every array as a multiset, for producers whose arrays are
+    /// sets in all but name: equal when each element of one can be paired
+    /// with an equal element of the other, each used once, so `[1, 2, 1]`
+    /// equals `[2, 1, 1]` but not `[1, 2, 2]`. Elements are bucketed by a
+    /// hash first, so this stays fast for large arrays. Under a
+    /// `float_tolerance` numbers share a bucket and are paired first come,
+    /// first served, which can miss a pairing when tolerances overlap.
+    pub unordered_arrays: bool,
+    /// [`unordered_arrays`](Self::unordered_arrays) for just the arrays at
+    /// these patterns, written as for [`JsonValue::redact`]: `/tags`,
+    /// `/users/*/roles`, `/**/permissions`. The elements of an unordered
+    /// array have no index to match, so below one only `*` and `**` do.
+    pub unordered_at: Vec<String>,
+}
+
+impl CompareOptions {
+    pub fn float_tolerance(mut self, tolerance: f64) -> Self {
+        self.float_tolerance = tolerance;
+        self
+    }
+
+    pub fn missing_is_null(mut self, yes: bool) -> Self {
+        self.missing_is_null = yes;
+        self
+    }
+
+    pub fn unordered_arrays(mut self, yes: bool) -> Self {
+        self.unordered_arrays = yes;
+        self
+    }
+
+    /// Add a pattern to [`unordered_at`](Self::unordered_at).
+    pub fn unordered_at(mut self, pattern: impl Into<String>) -> Self {
+        self.unordered_at.push(pattern.into());
+        self
+    }
+}
+
+/// Where two documents first differ, with what each holds there written
+/// compactly, or `None` for a member one of them lacks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// The JSON Pointer of the mismatch, `""` for the documents themselves.
+    pub pointer: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |s: &Option<String>| s.clone().unwrap_or_else(|| "nothing".to_string());
+        write!(
+            f,
+            "at \"{}\": {} != {}",
+            self.pointer,
+            side(&self.left),
+            side(&self.right)
+        )
+    }
+}
+
+/// Whether the JSON texts `a` and `b` hold the same value, ignoring
+/// whitespace, key order and number spelling, so `{"n": 1e2}` equals
+/// `{ "n": 100.0 }`. Fails with the error of the first text that does not
+/// parse.
+pub fn json_eq(a: &str, b: &str) -> Result<bool, JsonError> {
+    json_eq_with(a, b, &CompareOptions::default())
+}
+
+/// [`json_eq`], comparing as `options` asks.
+pub fn json_eq_with(a: &str, b: &str, options: &CompareOptions) -> Result<bool, JsonError> {
+    Ok(json_assert_eq_with(a, b, options)?.is_none())
+}
+
+/// [`json_eq`], saying where the texts first differ: `None` when they are
+/// equal. Object members are visited in key order, so the same pair of
+/// documents always reports the same difference. Unordered arrays that
+/// differ are reported as a whole.
+pub fn json_assert_eq(a: &str, b: &str) -> Result<Option<Difference>, JsonError> {
+    json_assert_eq_with(a, b, &CompareOptions::default())
+}
+
+/// [`json_assert_eq`], comparing as `options` asks.
+pub fn json_assert_eq_with(
+    a: &str,
+    b: &str,
+    options: &CompareOptions,
+) -> Result<Option<Difference>, JsonError> {
+    let a = parse_json(a)?;
+    let b = parse_json(b)?;
+    let compare = Comparer::new(options);
+    Ok(compare.first_difference(&mut String::new(), Some(&a), Some(&b), &compare.start()))
+}
+
+/// [`json_eq`] for values already parsed.
+pub fn semantic_eq(a: &JsonValue, b: &JsonValue) -> bool {
+    semantic_eq_with(a, b, &CompareOptions::default())
+}
+
+/// [`semantic_eq`], comparing as `options` asks.
+pub fn semantic_eq_with(a: &JsonValue, b: &JsonValue, options: &CompareOptions) -> bool {
+    let compare = Comparer::new(options);
+    compare.same(a, b, &compare.start())
+}
+
+/// Compares values as its options ask, following where arrays are
+/// unordered with a state for each level, as [`Patterns`] matches them.
+pub(crate) struct Comparer<'o> {
+    options: &'o CompareOptions,
+    unordered: Patterns<'o>,
+}
+
+impl<'o> Comparer<'o> {
+    pub(crate) fn new(options: &'o CompareOptions) -> Self {
+        Comparer {
+            options,
+            unordered: Patterns::new(options.unordered_at.iter().map(String::as_str)),
+        }
+    }
+
+    /// The states of the document itself.
+    pub(crate) fn start(&self) -> States {
+        self.unordered.start()
+    }
+
+    pub(crate) fn member(&self, states: &States, key: &str) -> States {
+        self.unordered.step(states, |token| token == key)
+    }
+
+    /// The states of element `i` of an array that is not unordered.
+    pub(crate) fn element(&self, states: &States, i: usize) -> States {
+        self.unordered
+            .step(states, |token| array_index(token) == Some(i))
+    }
+
+    /// The states of any element of an unordered array.
+    fn unordered_element(&self, states: &States) -> States {
+        self.unordered.step(states, |_| false)
+    }
+
+    pub(crate) fn is_unordered(&self, states: &States) -> bool {
+        self.options.unordered_arrays || self.unordered.matched(states)
+    }
+
+    pub(crate) fn same(&self, a: &JsonValue, b: &JsonValue, states: &States) -> bool {
+        self.first_difference(&mut String::new(), Some(a), Some(b), states)
+            .is_none()
+    }
+
+    /// Whether `x` and `y` are equal as multisets. Equal values hash alike,
+    /// so each element is only tried against those in its bucket.
+    pub(crate) fn same_elements(&self, x: &[JsonValue], y: &[JsonValue], states: &States) -> bool {
+        if x.len() != y.len() {
+            return false;
+        }
+        let states = self.unordered_element(states);
+        let mut buckets: HashMap<u64, Vec<&JsonValue>> = HashMap::new();
+        for w in y {
+            buckets.entry(self.hash(w, &states)).or_default().push(w);
+        }
+        x.iter().all(|v| {
+            let Some(bucket) = buckets.get_mut(&self.hash(v, &states)) else {
+                return false;
+            };
+            match bucket.iter().position(|w| self.same(v, w, &states)) {
+                Some(i) => {
+                    bucket.swap_remove(i);
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// A hash that values this compares as equal share: numbers hash by
+    /// their `f64`, or all alike under a tolerance, members by key with
+    /// `null`s left out under `missing_is_null`, and the elements of an
+    /// unordered array whatever their order.
+    fn hash(&self, value: &JsonValue, states: &States) -> u64 {
+        let mut h = DefaultHasher::new();
+        match value {
+            JsonValue::Null => 0u8.hash(&mut h),
+            JsonValue::Bool(b) => (1u8, b).hash(&mut h),
+            JsonValue::Number(_) => {
+                2u8.hash(&mut h);
+                if self.options.float_tolerance <= 0.0 {
+                    // `+ 0.0` makes -0 hash as 0, which it equals
+                    let f = value.as_f64_lossy().map(|f| (f + 0.0).to_bits());
+                    f.hash(&mut h);
+                }
+            }
+            JsonValue::String(s) => (3u8, &**s).hash(&mut h),
+            JsonValue::Raw(raw) => (4u8, raw.get()).hash(&mut h),
+            JsonValue::Array(arr) if self.is_unordered(states) => {
+                let states = self.unordered_element(states);
+                let mut hashes: Vec<_> = arr.iter().map(|v| self.hash(v, &states)).collect();
+                hashes.sort_unstable();
+                (5u8, hashes).hash(&mut h);
+            }
+            JsonValue::Array(arr) => {
+                6u8.hash(&mut h);
+                for (i, v) in arr.iter().enumerate() {
+                    self.hash(v, &self.element(states, i)).hash(&mut h);
+                }
+            }
+            JsonValue::Object(obj) => {
+                let null = self.options.missing_is_null;
+                let mut members: Vec<_> = obj
+                    .iter()
+                    .filter(|(_, v)| !(null && **v == JsonValue::Null))
+                    .collect();
+                members.sort_by(|a, b| a.0.cmp(b.0));
+                7u8.hash(&mut h);
+                for (key, v) in members {
+                    (&**key, self.hash(v, &self.member(states, key))).hash(&mut h);
+                }
+            }
+        }
+        h.finish()
+    }
+
+    fn first_difference(
+        &self,
+        path: &mut String,
+        a: Option<&JsonValue>,
+        b: Option<&JsonValue>,
+        states: &States,
+    ) -> Option<Difference> {
+        let options = self.options;
+        let differ = |path: &str| {
+            Some(Difference {
+                pointer: path.to_string(),
+                left: a.map(JsonValue::to_json_string),
+                right: b.map(JsonValue::to_json_string),
+            })
+        };
+        match (a?, b?) {
+            (JsonValue::Object(x), JsonValue::Object(y)) => {
+                let mut keys: Vec<_> = x
+                    .keys()
+                    .chain(y.keys().filter(|k| !x.contains_key(*k)))
+                    .collect();
+                keys.sort();
+                keys.into_iter().find_map(|key| {
+                    let (v, w) = (x.get(key), y.get(key));
+                    let null = Some(&JsonValue::Null);
+                    if options.missing_is_null && (v.or(null) == w.or(null)) {
+                        return None;
+                    }
+                    let len = path.len();
+                    path.push('/');
+                    path.push_str(&escape_pointer_token(key));
+                    let found = match (v, w) {
+                        (Some(_), Some(_)) => {
+                            self.first_difference(path, v, w, &self.member(states, key))
+                        }
+                        _ => Some(Difference {
+                            pointer: path.clone(),
+                            left: v.map(JsonValue::to_json_string),
+                            right: w.map(JsonValue::to_json_string),
+                        }),
+                    };
+                    path.truncate(len);
+                    found
+                })
+            }
+            (JsonValue::Array(x), JsonValue::Array(y)) if self.is_unordered(states) => {
+                if self.same_elements(x, y, states) {
+                    None
+                } else {
+                    differ(path)
+                }
+            }
+            (JsonValue::Array(x), JsonValue::Array(y)) if x.len() == y.len() => {
+                x.iter().zip(y).enumerate().find_map(|(i, (v, w))| {
+                    let len = path.len();
+                    path.push('/');
+                    path.push_str(&i.to_string());
+                    let states = self.element(states, i);
+                    let found = self.first_difference(path, Some(v), Some(w), &states);
+                    path.truncate(len);
+                    found
+                })
+            }
+            (JsonValue::Number(x), JsonValue::Number(y)) => {
+                let close = match (a?.as_f64_lossy(), b?.as_f64_lossy()) {
+                    (Some(x), Some(y)) => (x - y).abs() <= options.float_tolerance,
+                    _ => false,
+                };
+                if same_number(x, y) || close {
+                    None
+                } else {
+                    differ(path)
+                }
+            }
+            (x, y) if x == y => None,
+            _ => differ(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_eq_should_ignore_formatting_key_order_and_number_spelling() {
+        let a = r#"{"id": 7, "tags": ["x", "y"], "size": {"w": 1e2, "h": 0.5}}"#;
+        let b = "{\n  \"size\": {\"h\": 5e-1, \"w\": 100.0},\n  \"tags\": [\"x\",\"y\"],\n  \"id\": 7.0\n}";
+        assert_eq!(json_eq(a, b), Ok(true));
+        assert_eq!(json_assert_eq(a, b), Ok(None));
+        assert_eq!(json_eq("[1, 2]", "[2, 1]"), Ok(false));
+        assert_eq!(json_eq(r#"{"a": 1}"#, r#"{"a": 1, "b": 2}"#), Ok(false));
+    }
+
+    #[test]
+    fn json_assert_eq_should_report_the_pointer_of_the_first_difference() {
+        let a = r#"{"users": [{"name": "ann", "roles": ["admin"]}, {"a/b": {"x": 1}}]}"#;
+        let b = r#"{"users": [{"name": "ann", "roles": ["admin"]}, {"a/b": {"x": [1, 2]}}]}"#;
+        let diff = json_assert_eq(a, b).unwrap().unwrap();
+        assert_eq!(
+            diff,
+            Difference {
+                pointer: "/users/1/a~1b/x".to_string(),
+                left: Some("1".to_string()),
+                right: Some("[1,2]".to_string()),
+            }
+        );
+        assert_eq!(diff.to_string(), r#"at "/users/1/a~1b/x": 1 != [1,2]"#);
+        let diff = json_assert_eq(r#"{"a": 1, "b": null}"#, r#"{"a": 1}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.to_string(), r#"at "/b": null != nothing"#);
+        let diff = json_assert_eq("[1]", "[1, 2]").unwrap().unwrap();
+        assert_eq!(diff.pointer, "");
+    }
+
+    #[test]
+    fn json_eq_with_should_apply_tolerance_and_missing_is_null() {
+        let (a, b) = (r#"{"t": 0.1, "n": 3}"#, r#"{"t": 0.1000001, "n": 3}"#);
+        assert_eq!(json_eq(a, b), Ok(false));
+        let loose = CompareOptions::default().float_tolerance(1e-3);
+        assert_eq!(json_eq_with(a, b, &loose), Ok(true));
+        assert_eq!(json_eq_with("[1]", "[1.01]", &loose), Ok(false));
+
+        let (a, b) = (r#"{"a": 1, "b": null}"#, r#"{"a": 1}"#);
+        let nulls = CompareOptions::default().missing_is_null(true);
+        assert_eq!(json_eq_with(a, b, &nulls), Ok(true));
+        assert_eq!(json_eq_with(b, a, &nulls), Ok(true));
+        assert_eq!(json_eq_with(r#"{"b": 0}"#, "{}", &nulls), Ok(false));
+    }
+
+    #[test]
+    fn unordered_arrays_should_compare_as_multisets() {
+        let (a, b) = (r#"{"tags": ["x", "y", 1]}"#, r#"{"tags": [1.0, "y", "x"]}"#);
+        assert_eq!(json_eq(a, b), Ok(false));
+        let sets = CompareOptions::default().unordered_arrays(true);
+        assert_eq!(json_eq_with(a, b, &sets), Ok(true));
+        let (a, b) = (parse_json(a).unwrap(), parse_json(b).unwrap());
+        assert!(!semantic_eq(&a, &b));
+        assert!(semantic_eq_with(&a, &b, &sets));
+
+        // each element pairs with one other, so duplicates count
+        assert_eq!(json_eq_with("[1, 2, 1]", "[2, 1, 1]", &sets), Ok(true));
+        assert_eq!(json_eq_with("[1, 2, 1]", "[1, 2, 2]", &sets), Ok(false));
+        assert_eq!(json_eq_with("[1, 1]", "[1]", &sets), Ok(false));
+        let diff = json_assert_eq_with(r#"{"a": [1, 2]}"#, r#"{"a": [2, 3]}"#, &sets)
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.to_string(), r#"at "/a": [1,2] != [2,3]"#);
+    }
+
+    #[test]
+    fn unordered_arrays_should_apply_at_every_level() {
+        let sets = CompareOptions::default().unordered_arrays(true);
+        let a = r#"[{"id": 1, "roles": ["r", "w"]}, [[1, 2], [3]], {"id": 2, "roles": []}]"#;
+        let b = r#"[[[3], [2, 1]], {"roles": [], "id": 2}, {"roles": ["w", "r"], "id": 1e0}]"#;
+        assert_eq!(json_eq_with(a, b, &sets), Ok(true));
+        assert_eq!(json_eq_with(a, b, &CompareOptions::default()), Ok(false));
+        let c = r#"[[[3], [2, 1]], {"roles": [], "id": 2}, {"roles": ["w", "w"], "id": 1}]"#;
+        assert_eq!(json_eq_with(a, c, &sets), Ok(false));
+
+        let nulls = sets.clone().missing_is_null(true);
+        let (a, b) = (r#"[{"a": 1, "b": null}, {}]"#, r#"[{"c": null}, {"a": 1}]"#);
+        assert_eq!(json_eq_with(a, b, &nulls), Ok(true));
+        let loose = sets.float_tolerance(0.01);
+        assert_eq!(json_eq_with("[1, 2.001]", "[2, 1.005]", &loose), Ok(true));
+    }
+
+    #[test]
+    fn unordered_at_should_only_reach_the_arrays_it_names() {
+        let options = CompareOptions::default()
+            .unordered_at("/tags")
+            .unordered_at("/users/*/roles");
+        let a = r#"{"tags": [1, 2], "users": [{"roles": ["a", "b"]}, {"roles": ["c"]}]}"#;
+        let b = r#"{"tags": [2, 1], "users": [{"roles": ["b", "a"]}, {"roles": ["c"]}]}"#;
+        assert_eq!(json_eq_with(a, b, &options), Ok(true));
+        // the users themselves keep their order
+        let swapped = r#"{"tags": [1, 2], "users": [{"roles": ["c"]}, {"roles": ["a", "b"]}]}"#;
+        let diff = json_assert_eq_with(a, swapped, &options).unwrap().unwrap();
+        assert_eq!(diff.pointer, "/users/0/roles");
+        assert_eq!(
+            json_eq_with(r#"{"other": [1, 2]}"#, r#"{"other": [2, 1]}"#, &options),
+            Ok(false)
+        );
+
+        // below an unordered array only wildcards match an element
+        let deep = CompareOptions::default().unordered_at("/**/ids");
+        let a = r#"{"groups": [{"ids": [1, 2]}, {"ids": [3, 4]}], "ids": [5, 6]}"#;
+        let b = r#"{"groups": [{"ids": [2, 1]}, {"ids": [4, 3]}], "ids": [6, 5]}"#;
+        assert_eq!(json_eq_with(a, b, &deep), Ok(true));
+        let nested = CompareOptions::default()
+            .unordered_at("/sets")
+            .unordered_at("/sets/*");
+        assert_eq!(
+            json_eq_with(
+                r#"{"sets": [[1, 2], [3]]}"#,
+                r#"{"sets": [[3], [2, 1]]}"#,
+                &nested
+            ),
+            Ok(true)
+        );
+        let indexed = CompareOptions::default()
+            .unordered_at("/sets")
+            .unordered_at("/sets/0");
+        assert_eq!(
+            json_eq_with(
+                r#"{"sets": [[1, 2], [3]]}"#,
+                r#"{"sets": [[3], [2, 1]]}"#,
+                &indexed
+            ),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn unordered_arrays_should_stay_fast_on_large_arrays() {
+        let n = 5000;
+        let rows = |order: &mut dyn Iterator<Item = usize>| {
+            let rows: Vec<String> = order
+                .map(|i| {
+                    format!(
+                        r#"{{"id": {}, "tags": ["t{}", "t{}"]}}"#,
+                        i / 2,
+                        i % 7,
+                        i % 3
+                    )
+                })
+                .collect();
+            parse_json(&format!("[{}]", rows.join(","))).unwrap()
+        };
+        let a = rows(&mut (0..n));
+        let b = rows(&mut (0..n).rev());
+        let sets = CompareOptions::default().unordered_arrays(true);
+        assert!(semantic_eq_with(&a, &b, &sets));
+        assert!(!semantic_eq(&a, &b));
+        let mut c = b.clone();
+        assert!(c.set_pointer("/7/id", JsonValue::from(-1i64)));
+        assert!(!semantic_eq_with(&a, &c, &sets));
+        let numbers = |order: &mut dyn Iterator<Item = usize>| {
+            JsonValue::Array(order.map(|i| JsonValue::from((i % 1000) as i64)).collect())
+        };
+        assert!(semantic_eq_with(
+            &numbers(&mut (0..n)),
+            &numbers(&mut (0..n).rev()),
+            &sets
+        ));
+    }
+
+    #[test]
+    fn json_eq_should_propagate_the_right_parse_error() {
+        assert_eq!(
+            json_eq("[1, 2]", "[1, 2"),
+            Err(parse_json("[1, 2").unwrap_err())
+        );
+        assert_eq!(
+            json_assert_eq("{}", r#"{"a" 1}"#),
+            Err(parse_json(r#"{"a" 1}"#).unwrap_err())
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use super::{
+    cancelled, comment, descend, entry_limit, error, parse_key, parse_value, stateful,
+    tokenizer::scalar, Input, JsonError, JsonString, JsonValue, ParseOptions,
+};
+use std::{fmt, ops::Range};
+use winnow::{
+    ascii::multispace1,
+    combinator::{cut_err, fail, opt},
+    error::{ContextError, StrContext},
+    prelude::*,
+    stream::Stream,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Colon,
+    Comma,
+    Null,
+    True,
+    False,
+    Number,
+    String,
+    /// An unquoted object key under `allow_unquoted_keys`.
+    Identifier,
+}
+
+/// A slice of the input with its byte span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Range<usize>,
+}
+
+/// A lossless parse: every byte of the input, including whitespace and
+/// comments, belongs to exactly one token, so [`Cst::to_string`] gives the
+/// input back byte for byte. Tokens are laid out in a tree mirroring the
+/// value so edits can be made without disturbing the rest of the text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst<'a> {
+    pub before: Vec<Token<'a>>,
+    pub root: Node<'a>,
+    pub after: Vec<Token<'a>>,
+    options: ParseOptions,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node<'a> {
+    /// A null, boolean, number or string token in its original spelling.
+    Scalar(Token<'a>),
+    Array(Container<'a>),
+    Object(Container<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container<'a> {
+    pub open: Token<'a>,
+    pub entries: Vec<Entry<'a>>,
+    /// Trivia after the last comma, or everything inside an empty container.
+    pub before_close: Vec<Token<'a>>,
+    pub close: Token<'a>,
+}
+
+/// An array element or object member with the trivia around it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry<'a> {
+    /// Object members only.
+    pub key: Option<Key<'a>>,
+    /// Trivia before the value: after the `[` or comma for array elements,
+    /// after the colon for members.
+    pub before: Vec<Token<'a>>,
+    pub value: Node<'a>,
+    pub after: Vec<Token<'a>>,
+    pub comma: Option<Token<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key<'a> {
+    pub before: Vec<Token<'a>>,
+    pub token: Token<'a>,
+    pub after: Vec<Token<'a>>,
+    pub colon: Token<'a>,
+}
+
+/// Parse `input` into a lossless syntax tree, accepting whatever `options`
+/// allow. Comments only become tokens under `allow_comments`.
+pub fn parse_cst<'a>(input: &'a str, options: &ParseOptions) -> Result<Cst<'a>, JsonError> {
+    let mut rest = stateful(input, options);
+    let mut lexer = Lexer { source: input };
+    let cst = (|rest: &mut Input<'a>| {
+        let before = lexer.trivia(rest)?;
+        let root = lexer.node(rest)?;
+        let after = lexer.trivia(rest)?;
+        Ok(Cst {
+            before,
+            root,
+            after,
+            options: options.clone(),
+        })
+    })(&mut rest)
+    .map_err(|e| JsonError::from_parser(input, rest.input, e))?;
+    if let Node::Scalar(token) = &cst.root {
+        if options.require_container {
+            return Err(error::scalar_root(input, token.span.clone()));
+        }
+    }
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    Ok(cst)
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+}
+
+impl<'a> Lexer<'a> {
+    fn token(&self, kind: TokenKind, text: &'a str, input: &Input<'a>) -> Token<'a> {
+        let end = self.source.len() - input.input.len();
+        Token {
+            kind,
+            text,
+            span: end - text.len()..end,
+        }
+    }
+
+    fn trivia(&mut self, input: &mut Input<'a>) -> PResult<Vec<Token<'a>>> {
+        let mut tokens = Vec::new();
+        loop {
+            if let Some(text) = opt(multispace1).parse_next(input)? {
+                tokens.push(self.token(TokenKind::Whitespace, text, input));
+            } else if let Some(text) = opt(comment).parse_next(input)? {
+                let kind = if text.starts_with("//") {
+                    TokenKind::LineComment
+                } else {
+                    TokenKind::BlockComment
+                };
+                tokens.push(self.token(kind, text, input));
+            } else {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    fn punct(&mut self, input: &mut Input<'a>, c: char, kind: TokenKind) -> PResult<Token<'a>> {
+        let text = cut_err(c).recognize().parse_next(input)?;
+        Ok(self.token(kind, text, input))
+    }
+
+    fn node(&mut self, input: &mut Input<'a>) -> PResult<Node<'a>> {
+        match input.input.chars().next() {
+            Some('[') => Ok(Node::Array(self.container(input, false)?)),
+            Some('{') => Ok(Node::Object(self.container(input, true)?)),
+            _ => {
+                let (kind, text) = scalar(input)?;
+                Ok(Node::Scalar(self.token(kind, text, input)))
+            }
+        }
+    }
+
+    fn container(&mut self, input: &mut Input<'a>, object: bool) -> PResult<Container<'a>> {
+        let (open, close, close_kind) = if object {
+            ('{', '}', TokenKind::RBrace)
+        } else {
+            ('[', ']', TokenKind::RBracket)
+        };
+        let open_kind = if object {
+            TokenKind::LBrace
+        } else {
+            TokenKind::LBracket
+        };
+        descend(input)?;
+        let opened = input.input.len();
+        let open = self.punct(input, open, open_kind)?;
+        let mut entries: Vec<Entry<'a>> = Vec::new();
+        let mut pending = self.trivia(input)?;
+        loop {
+            if input.input.starts_with(close) {
+                let close = self.punct(input, close, close_kind)?;
+                input.state.depth -= 1;
+                return Ok(Container {
+                    open,
+                    entries,
+                    before_close: pending,
+                    close,
+                });
+            }
+            if entries.last().is_some_and(|e| e.comma.is_none()) {
+                // neither a comma nor the closing bracket
+                return cut_err(fail).parse_next(input);
+            }
+
+            entry_limit(input, opened, entries.len(), object)?;
+            cancelled(input)?;
+            let mut before = std::mem::take(&mut pending);
+            let key = if object {
+                let text = cut_err(parse_key.recognize()).parse_next(input)?;
+                let kind = if text.starts_with(['"', '\'']) {
+                    TokenKind::String
+                } else {
+                    TokenKind::Identifier
+                };
+                let token = self.token(kind, text, input);
+                let after = self.trivia(input)?;
+                let colon = self.punct(input, ':', TokenKind::Colon)?;
+                let key_before = std::mem::replace(&mut before, self.trivia(input)?);
+                Some(Key {
+                    before: key_before,
+                    token,
+                    after,
+                    colon,
+                })
+            } else {
+                None
+            };
+            let value = cut_err(|input: &mut Input<'a>| self.node(input)).parse_next(input)?;
+            let after = self.trivia(input)?;
+            let at_comma = input.checkpoint();
+            let comma = match opt(',').recognize().parse_next(input)? {
+                "" => None,
+                text => {
+                    let comma = self.token(TokenKind::Comma, text, input);
+                    pending = self.trivia(input)?;
+                    if input.input.starts_with(close) && !input.state.options.allow_trailing_commas
+                    {
+                        input.reset(&at_comma);
+                        return cut_err(fail)
+                            .context(StrContext::Label(error::TRAILING_COMMA))
+                            .parse_next(input);
+                    }
+                    Some(comma)
+                }
+            };
+            entries.push(Entry {
+                key,
+                before,
+                value,
+                after,
+                comma,
+            });
+        }
+    }
+}
+
+impl Cst<'_> {
+    /// Lower to a [`JsonValue`], as parsing the same text with the same
+    /// options would produce.
+    pub fn to_value(&self) -> JsonValue {
+        self.root.to_value(&self.options)
+    }
+
+    /// Every token in document order.
+    pub fn tokens(&self) -> Vec<&Token<'_>> {
+        let mut out = Vec::new();
+        out.extend(&self.before);
+        self.root.collect_tokens(&mut out);
+        out.extend(&self.after);
+        out
+    }
+}
+
+impl<'a> Node<'a> {
+    /// The bytes from the first to the last token of this node, including
+    /// brackets and quotes.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Node::Scalar(token) => token.span.clone(),
+            Node::Array(c) | Node::Object(c) => c.open.span.start..c.close.span.end,
+        }
+    }
+
+    pub(super) fn to_value(&self, options: &ParseOptions) -> JsonValue {
+        match self {
+            Node::Scalar(token) => reparse(token.text, options, parse_value),
+            Node::Array(c) => JsonValue::Array(
+                c.entries
+                    .iter()
+                    .map(|e| e.value.to_value(options))
+                    .collect(),
+            ),
+            Node::Object(c) => JsonValue::Object(
+                c.entries
+                    .iter()
+                    .map(|e| {
+                        let key = e.key.as_ref().expect("object entries have keys");
+                        (key.to_string(options), e.value.to_value(options))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn collect_tokens<'t>(&'t self, out: &mut Vec<&'t Token<'a>>) {
+        match self {
+            Node::Scalar(token) => out.push(token),
+            Node::Array(c) | Node::Object(c) => {
+                out.push(&c.open);
+                for e in &c.entries {
+                    if let Some(key) = &e.key {
+                        out.extend(&key.before);
+                        out.push(&key.token);
+                        out.extend(&key.after);
+                        out.push(&key.colon);
+                    }
+                    out.extend(&e.before);
+                    e.value.collect_tokens(out);
+                    out.extend(&e.after);
+                    out.extend(&e.comma);
+                }
+                out.extend(&c.before_close);
+                out.push(&c.close);
+            }
+        }
+    }
+}
+
+impl Key<'_> {
+    /// The decoded key, with quotes and escapes resolved.
+    pub(super) fn to_string(&self, options: &ParseOptions) -> JsonString {
+        reparse(self.token.text, options, parse_key)
+    }
+}
+
+/// Run `parser` over the text of a token that was already validated with
+/// the same options.
+fn reparse<'t, O>(
+    text: &'t str,
+    options: &ParseOptions,
+    mut parser: impl Parser<Input<'t>, O, ContextError>,
+) -> O {
+    parser
+        .parse(stateful(text, options))
+        .expect("tokens were validated while building the tree")
+}
+
+/// Reproduces the parsed input byte for byte.
+impl fmt::Display for Cst<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens().iter().try_for_each(|t| f.write_str(t.text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, parse_json_with};
+
+    const CORPUS: [&str; 6] = [
+        "null",
+        "  [ 1 ,2,\t3.50e+1 ]\n",
+        "{}",
+        "[ ]",
+        "{\"a\" : {\"b\":[true,false , \"x\\u00e9\\n\"]},\r\n \"c\":-0}",
+        "\n\n{ \"deep\": [[[ [] ], {}]] }  ",
+    ];
+
+    #[test]
+    fn cst_should_round_trip_and_lower_like_the_parser() {
+        for input in CORPUS {
+            let cst = parse_cst(input, &ParseOptions::strict()).unwrap();
+            assert_eq!(cst.to_string(), input);
+            assert_eq!(cst.to_value(), parse_json(input).unwrap(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn cst_should_keep_comments_and_lenient_syntax() {
+        let input = "// config\n{\n  unquoted: 'single', /* inline */ \"hex\": 0x1F,\n  \"list\": [1, 2, /* last */ ],  // trailing\n}\n/* end */";
+        let options = ParseOptions::json5();
+        let cst = parse_cst(input, &options).unwrap();
+        assert_eq!(cst.to_string(), input);
+        assert_eq!(cst.to_value(), parse_json_with(input, &options).unwrap());
+
+        let kinds: Vec<_> = cst
+            .tokens()
+            .iter()
+            .filter(|t| t.kind != TokenKind::Whitespace)
+            .map(|t| t.kind)
+            .collect();
+        use TokenKind::*;
+        assert_eq!(
+            kinds,
+            [
+                LineComment,
+                LBrace,
+                Identifier,
+                Colon,
+                String,
+                Comma,
+                BlockComment,
+                String,
+                Colon,
+                Number,
+                Comma,
+                String,
+                Colon,
+                LBracket,
+                Number,
+                Comma,
+                Number,
+                Comma,
+                BlockComment,
+                RBracket,
+                Comma,
+                LineComment,
+                RBrace,
+                BlockComment
+            ]
+        );
+        // spans index the input
+        for t in cst.tokens() {
+            assert_eq!(&input[t.span.clone()], t.text);
+        }
+    }
+
+    #[test]
+    fn cst_should_reject_what_the_parser_rejects() {
+        for input in ["[1,]", "[1 2]", "{\"a\" 1}", "[", "// c\n1", "1 x"] {
+            assert!(
+                parse_cst(input, &ParseOptions::strict()).is_err(),
+                "{:?}",
+                input
+            );
+            assert!(parse_json(input).is_err(), "{:?}", input);
+        }
+        assert_eq!(
+            parse_cst("[1, 2 ,]", &ParseOptions::strict()).unwrap_err(),
+            parse_json("[1, 2 ,]").unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use super::{json_string, parse_json, JsonValue};
+use std::collections::{BTreeSet, HashMap};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CsvError {
+    #[error("CSV conversion needs an array of objects")]
+    NotAnArray,
+    #[error("row {row} is not an object")]
+    NotAnObject { row: usize },
+    /// A cell would hold an array or object and `flatten` is off.
+    #[error("row {row} has a nested value at {key:?}; enable flatten")]
+    Nested { row: usize, key: String },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Spread nested containers over several columns named by their path,
+    /// `address.city` or `tags.0`, instead of failing. Empty containers
+    /// become empty cells.
+    pub flatten: bool,
+    /// Write just these columns, in this order, instead of the union of
+    /// all keys. Members outside them are ignored, nested or not.
+    pub columns: Option<Vec<String>>,
+}
+
+/// Render an array of flat objects as RFC 4180 CSV. The header is the union
+/// of all keys in sorted order; a row missing a key, or holding null for it,
+/// gets an empty cell. Cells with commas, quotes or line breaks are quoted
+/// and embedded quotes doubled. Lines end in CRLF.
+pub fn to_csv(value: &JsonValue) -> Result<String, CsvError> {
+    to_csv_with(value, &CsvOptions::default())
+}
+
+pub fn to_csv_with(value: &JsonValue, options: &CsvOptions) -> Result<String, CsvError> {
+    let JsonValue::Array(rows) = value else {
+        return Err(CsvError::NotAnArray);
+    };
+    let mut flat_rows = Vec::with_capacity(rows.len());
+    for (row, v) in rows.iter().enumerate() {
+        let JsonValue::Object(obj) = v else {
+            return Err(CsvError::NotAnObject { row });
+        };
+        let mut cells = HashMap::new();
+        // sorted so the first nested value reported is always the same
+        let mut members: Vec<_> = obj.iter().collect();
+        members.sort_unstable_by_key(|(k, _)| *k);
+        for (k, v) in members {
+            if options.columns.as_ref().is_some_and(|c| !selects(c, k)) {
+                continue;
+            }
+            flatten_into(&mut cells, k.to_string(), v, options, row)?;
+        }
+        flat_rows.push(cells);
+    }
+    let header: Vec<&String> = match &options.columns {
+        Some(columns) => columns.iter().collect(),
+        None => {
+            let keys: BTreeSet<&String> = flat_rows.iter().flat_map(|r| r.keys()).collect();
+            keys.into_iter().collect()
+        }
+    };
+
+    let mut out = String::new();
+    write_record(&mut out, header.iter().map(|k| k.as_str()));
+    for cells in &flat_rows {
+        write_record(
+            &mut out,
+            header
+                .iter()
+                .map(|k| cells.get(*k).map_or("", String::as_str)),
+        );
+    }
+    Ok(out)
+}
+
+/// Whether one of `columns` is the member `key` or, flattened, inside it.
+fn selects(columns: &[String], key: &str) -> bool {
+    columns.iter().any(|c| {
+        c.strip_prefix(key)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+fn flatten_into(
+    cells: &mut HashMap<String, String>,
+    key: String,
+    value: &JsonValue,
+    options: &CsvOptions,
+    row: usize,
+) -> Result<(), CsvError> {
+    let children: Vec<(String, &JsonValue)> = match value {
+        JsonValue::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        JsonValue::Object(obj) => obj.iter().map(|(k, v)| (k.to_string(), v)).collect(),
+        scalar => {
+            let cell = scalar
+                .to_display_string_with(true)
+                .unwrap_or_else(|| scalar.to_json_string());
+            cells.insert(key, cell);
+            return Ok(());
+        }
+    };
+    if !options.flatten {
+        return Err(CsvError::Nested { row, key });
+    }
+    if children.is_empty() {
+        cells.insert(key, String::new());
+        return Ok(());
+    }
+    for (k, v) in children {
+        flatten_into(cells, format!("{}.{}", key, k), v, options, row)?;
+    }
+    Ok(())
+}
+
+fn write_record<'a>(out: &mut String, cells: impl Iterator<Item = &'a str>) {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if cell.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&cell.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(cell);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+/// Read CSV with a header row into an array of objects. Cells that spell a
+/// JSON number or `true`/`false` become numbers and booleans, everything else
+/// a string. Empty cells are left out of their object. Both CRLF and LF line
+/// endings are accepted, and a quoted cell left open runs to the end.
+pub fn from_csv(input: &str) -> JsonValue {
+    let mut records = parse_records(input).into_iter();
+    let Some(header) = records.next() else {
+        return JsonValue::Array(Vec::new());
+    };
+    let rows = records
+        .map(|record| {
+            let obj = header
+                .iter()
+                .zip(record)
+                .filter(|(_, cell)| !cell.is_empty())
+                .map(|(k, cell)| (json_string(k), typed(cell)))
+                .collect();
+            JsonValue::Object(obj)
+        })
+        .collect();
+    JsonValue::Array(rows)
+}
+
+/// `cell` as a number or boolean when it spells one, else as a string.
+pub(super) fn typed(cell: String) -> JsonValue {
+    let digits = cell.strip_prefix('-').unwrap_or(&cell);
+    // zero-padded cells such as ZIP codes and IDs stay strings
+    let padded = digits.starts_with('0') && digits[1..].starts_with(|c: char| c.is_ascii_digit());
+    let looks_scalar = (digits.starts_with(|c: char| c.is_ascii_digit()) && !padded)
+        || cell == "true"
+        || cell == "false";
+    match parse_json(&cell) {
+        Ok(v @ (JsonValue::Number(_) | JsonValue::Bool(_)))
+            if looks_scalar && cell.trim() == cell =>
+        {
+            v
+        }
+        _ => JsonValue::String(json_string(cell)),
+    }
+}
+
+fn parse_records(input: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    let mut chars = input.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::take(&mut cell)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut cell));
+                records.push(std::mem::take(&mut record));
+            }
+            c => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !record.is_empty() {
+        record.push(cell);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_should_round_trip() {
+        let v = parse_json(
+            r#"[{"name": "a", "age": 1, "ok": true}, {"name": "b", "age": 2.5, "ok": false}]"#,
+        )
+        .unwrap();
+        let csv = to_csv(&v).unwrap();
+        assert_eq!(csv, "age,name,ok\r\n1,a,true\r\n2.5,b,false\r\n");
+        assert_eq!(from_csv(&csv), v);
+    }
+
+    #[test]
+    fn csv_should_leave_missing_keys_empty() {
+        let v = parse_json(r#"[{"a": 1}, {"b": "x"}, {"a": null, "b": "y"}]"#).unwrap();
+        let csv = to_csv(&v).unwrap();
+        assert_eq!(csv, "a,b\r\n1,\r\n,x\r\n,y\r\n");
+        assert_eq!(
+            from_csv(&csv),
+            parse_json(r#"[{"a": 1}, {"b": "x"}, {"b": "y"}]"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn csv_should_quote_special_cells() {
+        let v = parse_json(r#"[{"text": "a, \"b\"\nc", "n": "007", "s": " 1"}]"#).unwrap();
+        let csv = to_csv(&v).unwrap();
+        assert_eq!(csv, "n,s,text\r\n007, 1,\"a, \"\"b\"\"\nc\"\r\n");
+        assert_eq!(from_csv(&csv), v);
+        assert_eq!(
+            from_csv("a,b\n\"x\"\"y\",\"1,2\"\n"),
+            parse_json(r#"[{"a": "x\"y", "b": "1,2"}]"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn csv_should_flatten_nested_values_on_request() {
+        let v =
+            parse_json(r#"[{"id": 1, "address": {"city": "NY"}, "tags": ["x", "y"], "e": []}]"#)
+                .unwrap();
+        assert_eq!(
+            to_csv(&v),
+            Err(CsvError::Nested {
+                row: 0,
+                key: "address".to_string()
+            })
+        );
+        let flatten = CsvOptions {
+            flatten: true,
+            ..CsvOptions::default()
+        };
+        let csv = to_csv_with(&v, &flatten).unwrap();
+        assert_eq!(csv, "address.city,e,id,tags.0,tags.1\r\nNY,,1,x,y\r\n");
+        assert_eq!(to_csv(&JsonValue::from(1)), Err(CsvError::NotAnArray));
+        assert_eq!(
+            to_csv(&parse_json("[{}, 2]").unwrap()),
+            Err(CsvError::NotAnObject { row: 1 })
+        );
+    }
+
+    #[test]
+    fn csv_should_write_only_the_columns_asked_for() {
+        let v = parse_json(r#"[{"id": 1, "name": "a", "meta": {"x": 1}}, {"name": "b"}]"#).unwrap();
+        let columns = |names: &[&str], flatten| CsvOptions {
+            flatten,
+            columns: Some(names.iter().map(|c| c.to_string()).collect()),
+        };
+        // the nested member is not asked for, so it is no error
+        let csv = to_csv_with(&v, &columns(&["name", "id", "missing"], false)).unwrap();
+        assert_eq!(csv, "name,id,missing\r\na,1,\r\nb,,\r\n");
+        let csv = to_csv_with(&v, &columns(&["meta.x", "id"], true)).unwrap();
+        assert_eq!(csv, "meta.x,id\r\n1,1\r\n,\r\n");
+        assert_eq!(
+            to_csv_with(&v, &columns(&["meta"], false)),
+            Err(CsvError::Nested {
+                row: 0,
+                key: "meta".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use super::{JsonValue, Num};
+use chrono::{DateTime, FixedOffset, SecondsFormat, TimeZone, Utc};
+use std::fmt;
+
+/// What a number counts from the Unix epoch, for
+/// [`JsonValue::as_datetime_with`]. There is no guessing from the size of
+/// the number: the caller knows what the field holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpochUnit {
+    Seconds,
+    Milliseconds,
+}
+
+impl JsonValue {
+    /// The timestamp in an RFC 3339 string such as `2024-05-01T12:30:00Z`
+    /// or `2024-05-01T14:30:00.250+02:00`, keeping its offset. `None` for
+    /// other values and for strings that are not one, such as a bare date.
+    pub fn as_datetime(&self) -> Option<DateTime<FixedOffset>> {
+        self.as_datetime_with(None)
+    }
+
+    /// [`as_datetime`](Self::as_datetime), also taking a number as that
+    /// many `epoch` units since 1970-01-01T00:00:00Z, at offset zero. A
+    /// float keeps its fraction to the nanosecond, within the years 1677 to
+    /// 2262; an integer may reach as far as `chrono` does.
+    pub fn as_datetime_with(&self, epoch: Option<EpochUnit>) -> Option<DateTime<FixedOffset>> {
+        match self {
+            JsonValue::String(s) => DateTime::parse_from_rfc3339(s).ok(),
+            JsonValue::Number(n) => Some(from_epoch(self, n, epoch?)?.fixed_offset()),
+            _ => None,
+        }
+    }
+
+    /// `datetime` as an RFC 3339 string, with `Z` for UTC and as many
+    /// digits of fraction as it needs in groups of three: `2024-05-01T12:30:00Z`,
+    /// `2024-05-01T14:30:00.250+02:00`.
+    pub fn from_datetime<Tz: TimeZone>(datetime: &DateTime<Tz>) -> JsonValue
+    where
+        Tz::Offset: fmt::Display,
+    {
+        JsonValue::from(datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+    }
+}
+
+/// The instant `number`, held by `value`, stands for in `unit`s since the
+/// epoch.
+fn from_epoch(value: &JsonValue, number: &Num, unit: EpochUnit) -> Option<DateTime<Utc>> {
+    match (number, unit) {
+        (Num::Int(i), EpochUnit::Seconds) => DateTime::from_timestamp(*i, 0),
+        (Num::Int(i), EpochUnit::Milliseconds) => DateTime::from_timestamp_millis(*i),
+        _ => {
+            let per_unit: i64 = match unit {
+                EpochUnit::Seconds => 1_000_000_000,
+                EpochUnit::Milliseconds => 1_000_000,
+            };
+            // whole units and the fraction apart, which a product in
+            // nanoseconds would round far more coarsely
+            let f = value.as_f64_lossy()?;
+            let whole = f.trunc();
+            if !(i64::MIN as f64..i64::MAX as f64).contains(&whole) {
+                return None;
+            }
+            let fraction = ((f - whole) * per_unit as f64).round() as i64;
+            let nanos = (whole as i64)
+                .checked_mul(per_unit)?
+                .checked_add(fraction)?;
+            Some(DateTime::from_timestamp_nanos(nanos))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, parse_json_with, ParseOptions};
+
+    fn at(text: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    #[test]
+    fn as_datetime_should_read_rfc_3339_strings() {
+        let noon = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap();
+        let value = JsonValue::from("2024-05-01T12:30:00Z");
+        assert_eq!(value.as_datetime().unwrap(), noon);
+        let shifted = JsonValue::from("2024-05-01T14:30:00.250+02:00")
+            .as_datetime()
+            .unwrap();
+        assert_eq!(shifted.offset().local_minus_utc(), 7200);
+        assert_eq!(shifted, noon + chrono::Duration::milliseconds(250));
+        assert_eq!(
+            JsonValue::from("1990-12-31T15:59:60.123456789-08:00")
+                .as_datetime()
+                .unwrap()
+                .timestamp_subsec_nanos(),
+            1_123_456_789
+        );
+        assert!(JsonValue::from("2024-05-01t12:30:00z")
+            .as_datetime()
+            .is_some());
+        for text in [
+            "",
+            "2024-05-01",
+            "12:30:00Z",
+            "2024-05-01T12:30:00",
+            "2024-13-01T12:30:00Z",
+            "2024-05-01T12:30:00+25:00",
+            "yesterday",
+            " 2024-05-01T12:30:00Z",
+        ] {
+            assert_eq!(JsonValue::from(text).as_datetime(), None, "{:?}", text);
+        }
+        for value in [
+            JsonValue::Null,
+            JsonValue::from(1714566600),
+            JsonValue::Bool(true),
+        ] {
+            assert_eq!(value.as_datetime(), None);
+        }
+    }
+
+    #[test]
+    fn as_datetime_with_should_take_epoch_numbers_in_the_given_unit() {
+        let noon = at("2024-05-01T12:30:00Z");
+        let seconds = Some(EpochUnit::Seconds);
+        let millis = Some(EpochUnit::Milliseconds);
+        let doc = parse_json("[1714566600, 1714566600000, 1714566600.5, -1]").unwrap();
+        let JsonValue::Array(items) = &doc else {
+            unreachable!()
+        };
+        assert_eq!(items[0].as_datetime_with(seconds).unwrap(), noon);
+        assert_eq!(items[1].as_datetime_with(millis).unwrap(), noon);
+        assert_eq!(
+            items[2].as_datetime_with(seconds).unwrap(),
+            at("2024-05-01T12:30:00.5Z")
+        );
+        assert_eq!(
+            items[3].as_datetime_with(seconds).unwrap(),
+            at("1969-12-31T23:59:59Z")
+        );
+        assert_eq!(
+            JsonValue::from(1714566600000.25)
+                .as_datetime_with(millis)
+                .unwrap(),
+            at("2024-05-01T12:30:00.00025Z")
+        );
+        let raw = ParseOptions::strict().preserve_number_text(true);
+        assert_eq!(
+            parse_json_with("1714566600", &raw)
+                .unwrap()
+                .as_datetime_with(seconds)
+                .unwrap(),
+            noon
+        );
+        // strings are still RFC 3339, numbers are not read without a unit
+        assert_eq!(
+            JsonValue::from("2024-05-01T12:30:00Z")
+                .as_datetime_with(seconds)
+                .unwrap(),
+            noon
+        );
+        assert_eq!(
+            JsonValue::from("1714566600").as_datetime_with(seconds),
+            None
+        );
+        assert_eq!(items[0].as_datetime_with(None), None);
+        // out of range
+        assert_eq!(JsonValue::from(i64::MAX).as_datetime_with(seconds), None);
+        assert_eq!(JsonValue::from(1e300).as_datetime_with(seconds), None);
+        assert_eq!(JsonValue::from(f64::NAN).as_datetime_with(seconds), None);
+    }
+
+    #[test]
+    fn from_datetime_should_write_rfc_3339() {
+        for text in [
+            "2024-05-01T12:30:00Z",
+            "2024-05-01T14:30:00.250+02:00",
+            "2024-05-01T12:30:00.000001-05:30",
+            "2024-05-01T12:30:00.123456789Z",
+        ] {
+            let value = JsonValue::from_datetime(&at(text));
+            assert_eq!(value, JsonValue::from(text));
+            assert_eq!(value.as_datetime().unwrap(), at(text));
+        }
+        let utc = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap();
+        assert_eq!(
+            JsonValue::from_datetime(&utc),
+            JsonValue::from("2024-05-01T12:30:00Z")
+        );
+    }
+}
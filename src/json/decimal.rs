@@ -0,0 +1,289 @@
+use super::{JsonValue, Num};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// An exact decimal number, `mantissa × 10^-scale`, as parsed with
+/// [`ParseOptions::parse_decimals`](super::ParseOptions::parse_decimals).
+/// It keeps the digits it was written with, so `1.50` stays `1.50` and
+/// compares unequal to `1.5`; [`diff`](super::diff) and JSON Patch `test`
+/// compare by value. It does no arithmetic: take the mantissa and scale
+/// out, or convert it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+/// Text that is not a number a [`Decimal`] can hold.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("invalid decimal {0:?}")]
+pub struct DecimalError(String);
+
+impl Decimal {
+    /// The most digits after the point a decimal can have.
+    pub const MAX_SCALE: u32 = 38;
+
+    /// `mantissa × 10^-scale`, or `None` when `scale` is over
+    /// [`MAX_SCALE`](Self::MAX_SCALE).
+    pub fn new(mantissa: i128, scale: u32) -> Option<Self> {
+        (scale <= Self::MAX_SCALE).then_some(Decimal { mantissa, scale })
+    }
+
+    /// The digits as an integer: `-150` for `-1.50`.
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// How many of the digits come after the point: `2` for `-1.50`.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// The nearest `f64`.
+    pub fn to_f64(&self) -> f64 {
+        // through the text, which the standard library rounds correctly
+        self.to_string().parse().expect("decimal text is a float")
+    }
+
+    /// Whether the two are the same number, whatever their digits: `1.0`
+    /// and `1.00` are.
+    pub(crate) fn same_value(&self, other: &Decimal) -> bool {
+        let scale = self.scale.max(other.scale);
+        let widen = |d: &Decimal| d.mantissa.checked_mul(10i128.pow(scale - d.scale));
+        match (widen(self), widen(other)) {
+            (Some(a), Some(b)) => a == b,
+            // one side overflowing means it has more significant digits
+            _ => false,
+        }
+    }
+
+    /// The value as an integer, when it has no fraction and fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        let unit = 10i128.pow(self.scale);
+        if self.mantissa % unit != 0 {
+            return None;
+        }
+        i64::try_from(self.mantissa / unit).ok()
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(i: i64) -> Self {
+        Decimal {
+            mantissa: i.into(),
+            scale: 0,
+        }
+    }
+}
+
+/// Reads JSON number text, and also JSON5's `+1`, `.5` and `5.`. An
+/// exponent moves the point, so `1.5e2` is `150` and `15e-1` is `1.5`.
+impl FromStr for Decimal {
+    type Err = DecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DecimalError(s.to_string());
+        let (negative, rest) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (number, exponent) = match rest.split_once(['e', 'E']) {
+            Some((number, exponent)) => {
+                let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid());
+                }
+                (number, exponent.parse::<i64>().map_err(|_| invalid())?)
+            }
+            None => (rest, 0),
+        };
+        let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+        let digits = || int.bytes().chain(frac.bytes());
+        if int.len() + frac.len() == 0 || !digits().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let mut mantissa: i128 = 0;
+        for b in digits() {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(i128::from(b - b'0')))
+                .ok_or_else(invalid)?;
+        }
+        let mut scale = frac.len() as i64 - exponent;
+        // a negative scale is made up with trailing zeros
+        while scale < 0 {
+            mantissa = mantissa.checked_mul(10).ok_or_else(invalid)?;
+            scale += 1;
+        }
+        let scale = u32::try_from(scale).map_err(|_| invalid())?;
+        let mantissa = if negative { -mantissa } else { mantissa };
+        Decimal::new(mantissa, scale).ok_or_else(invalid)
+    }
+}
+
+/// The digits with the point in place, such as `-0.050`: valid JSON number
+/// text.
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if self.mantissa < 0 {
+            f.write_str("-")?;
+        }
+        if scale == 0 {
+            return f.write_str(&digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{}.{}", int, frac)
+    }
+}
+
+impl From<Decimal> for JsonValue {
+    fn from(d: Decimal) -> Self {
+        JsonValue::Number(Num::Decimal(d))
+    }
+}
+
+impl Num {
+    /// The number as a [`Decimal`]: exactly for decimals, integers and raw
+    /// number text that fits, and for floats through their shortest text,
+    /// so `0.1` gives `0.1`.
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Num::Decimal(d) => Some(*d),
+            Num::Int(i) => Some(Decimal::from(*i)),
+            Num::Float(f) if f.is_finite() => format!("{:?}", f).parse().ok(),
+            Num::Float(_) => None,
+            Num::Raw(s) => s.parse().ok(),
+        }
+    }
+}
+
+impl JsonValue {
+    /// A number as a [`Decimal`], as [`Num::as_decimal`] converts it.
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            JsonValue::Number(n) => n.as_decimal(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{diff, parse_json, parse_json_with, ParseOptions};
+
+    fn decimals() -> ParseOptions {
+        ParseOptions::strict().parse_decimals(true)
+    }
+
+    #[test]
+    fn decimals_should_parse_and_serialize_exactly() {
+        let value = parse_json_with("0.1", &decimals()).unwrap();
+        assert_eq!(value, JsonValue::from(Decimal::new(1, 1).unwrap()));
+        assert_eq!(value.to_json_string(), "0.1");
+        for (text, written) in [
+            (
+                "[0.1, -12.50, 3, 1e2, 1.5e-3, 0.000, -0.05, 1E+1]",
+                "[0.1,-12.50,3,100,0.0015,0.000,-0.05,10]",
+            ),
+            (
+                "123456789012345678901234.567890123456789",
+                "123456789012345678901234.567890123456789",
+            ),
+        ] {
+            let value = parse_json_with(text, &decimals()).unwrap();
+            assert_eq!(value.to_json_string(), written);
+            let reparsed = parse_json_with(written, &decimals()).unwrap();
+            assert_eq!(reparsed.to_json_string(), written);
+        }
+        // too many digits for the mantissa
+        assert!(parse_json_with("1e40", &decimals()).is_err());
+        let json5 = ParseOptions::json5().parse_decimals(true);
+        assert_eq!(
+            parse_json_with("[.5, +2.]", &json5)
+                .unwrap()
+                .to_json_string(),
+            "[0.5,2]"
+        );
+    }
+
+    #[test]
+    fn decimals_should_sum_exactly() {
+        let parsed = parse_json_with("[0.1, 0.2, 19.99, 0.001, 0.009]", &decimals()).unwrap();
+        let JsonValue::Array(items) = &parsed else {
+            unreachable!()
+        };
+        let parts: Vec<Decimal> = items.iter().map(|v| v.as_decimal().unwrap()).collect();
+        let scale = parts.iter().map(Decimal::scale).max().unwrap();
+        let mantissa: i128 = parts
+            .iter()
+            .map(|d| d.mantissa() * 10i128.pow(scale - d.scale()))
+            .sum();
+        let sum = JsonValue::from(Decimal::new(mantissa, scale).unwrap());
+        assert_eq!(sum.to_json_string(), "20.300");
+        assert_eq!(parse_json_with("20.300", &decimals()).unwrap(), sum);
+        // what binary floats give instead
+        assert_ne!(0.1 + 0.2, 0.3);
+    }
+
+    #[test]
+    fn decimals_should_be_off_unless_asked_for() {
+        for text in ["0.1", "[1.50, 2]", "1e2"] {
+            assert_eq!(
+                parse_json_with(text, &ParseOptions::strict()),
+                parse_json(text)
+            );
+        }
+        assert_eq!(parse_json("0.1").unwrap(), JsonValue::from(0.1));
+        // integers stay integers, and raw text wins
+        assert_eq!(
+            parse_json_with("7", &decimals()).unwrap(),
+            JsonValue::from(7)
+        );
+        let raw = decimals().preserve_number_text(true);
+        assert_eq!(
+            parse_json_with("1.50", &raw).unwrap(),
+            JsonValue::Number(Num::Raw("1.50".into()))
+        );
+    }
+
+    #[test]
+    fn decimals_should_equal_other_numbers_by_value() {
+        let one = parse_json_with("1.0", &decimals()).unwrap();
+        assert_ne!(one, JsonValue::from(1));
+        assert_eq!(diff(&one, &JsonValue::from(1)), []);
+        assert_eq!(diff(&one, &JsonValue::from(1.0)), []);
+        assert_eq!(
+            diff(&one, &parse_json_with("1.000", &decimals()).unwrap()),
+            []
+        );
+        assert_eq!(diff(&one, &JsonValue::from(1.5)).len(), 1);
+        assert_eq!(one.as_decimal().unwrap().to_i64(), Some(1));
+        assert_eq!(one.as_decimal().unwrap().to_f64(), 1.0);
+        assert_eq!(JsonValue::from(0.1).as_decimal(), Decimal::new(1, 1));
+        assert_eq!(JsonValue::from(-3).as_decimal(), Decimal::new(-3, 0));
+        assert_eq!(JsonValue::from("1").as_decimal(), None);
+        assert_eq!(Decimal::new(5, 1).unwrap().to_i64(), None);
+    }
+
+    #[test]
+    fn decimal_text_should_round_trip() {
+        for text in [
+            "0",
+            "-0.5",
+            "12.340",
+            "0.001",
+            "170141183460469231731687303715884105727",
+        ] {
+            assert_eq!(text.parse::<Decimal>().unwrap().to_string(), text);
+        }
+        for text in [
+            "", "-", ".", "1.2.3", "1e", "e5", "1x", "--1", "1e+", "0x10",
+        ] {
+            assert!(text.parse::<Decimal>().is_err(), "{}", text);
+        }
+    }
+}
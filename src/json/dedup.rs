@@ -0,0 +1,125 @@
+use super::{patch::same_value, AsPointer, JsonValue};
+
+impl JsonValue {
+    /// Drop the elements of an array equal to an earlier one, keeping the
+    /// first of each in place. Equality is structural, so `1` and `1.0`
+    /// differ; see [`dedup_members_with`](Self::dedup_members_with).
+    /// Returns whether anything was dropped; anything but an array is left
+    /// alone.
+    pub fn dedup_members(&mut self) -> bool {
+        self.dedup_members_with(false)
+    }
+
+    /// [`dedup_members`](Self::dedup_members), comparing numbers by value
+    /// when `semantic_numbers` is set, so `1`, `1.0` and raw `1e0` are
+    /// duplicates, also inside containers.
+    pub fn dedup_members_with(&mut self, semantic_numbers: bool) -> bool {
+        if semantic_numbers {
+            dedup_by(self, |value| Some(value), same_value)
+        } else {
+            dedup_by(self, |value| Some(value), |a, b| a == b)
+        }
+    }
+
+    /// Drop the elements of an array whose value at `ptr` equals that of an
+    /// earlier element, as in "unique by id", keeping the first of each.
+    /// Elements without a value at `ptr` are never duplicates and are all
+    /// kept. Returns whether anything was dropped; anything but an array is
+    /// left alone.
+    pub fn dedup_by_key<P: AsPointer + ?Sized>(&mut self, ptr: &P) -> bool {
+        dedup_by(self, |value| value.pointer(ptr), |a, b| a == b)
+    }
+}
+
+/// Keep the elements of `value` whose `key` is not `eq` to that of an
+/// earlier element kept. An element without a key is always kept.
+fn dedup_by(
+    value: &mut JsonValue,
+    key: impl Fn(&JsonValue) -> Option<&JsonValue>,
+    eq: impl Fn(&JsonValue, &JsonValue) -> bool,
+) -> bool {
+    let JsonValue::Array(arr) = value else {
+        return false;
+    };
+    let len = arr.len();
+    let mut kept: Vec<JsonValue> = Vec::with_capacity(len);
+    for element in arr.drain(..) {
+        let duplicate = key(&element)
+            .is_some_and(|k| kept.iter().filter_map(&key).any(|earlier| eq(earlier, k)));
+        if !duplicate {
+            kept.push(element);
+        }
+    }
+    *arr = kept;
+    arr.len() < len
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{parse_json, JsonValue, Pointer};
+
+    #[test]
+    fn dedup_members_should_keep_first_occurrences_in_order() {
+        let mut doc = parse_json(
+            r#"[3, "a", {"x": [1, 2]}, 3, null, [1, {"y": true}], "a", {"x": [1, 2]},
+                [1, {"y": true}], {"x": [2, 1]}, null, 1, 1.0]"#,
+        )
+        .unwrap();
+        assert!(doc.dedup_members());
+        assert_eq!(
+            doc,
+            parse_json(r#"[3, "a", {"x": [1, 2]}, null, [1, {"y": true}], {"x": [2, 1]}, 1, 1.0]"#)
+                .unwrap()
+        );
+        assert!(!doc.dedup_members());
+        assert!(doc.dedup_members_with(true));
+        assert_eq!(
+            doc,
+            parse_json(r#"[3, "a", {"x": [1, 2]}, null, [1, {"y": true}], {"x": [2, 1]}, 1]"#)
+                .unwrap()
+        );
+
+        let mut nested = parse_json(r#"[{"n": 1}, {"n": 1.0}, [2], [2e0]]"#).unwrap();
+        assert!(!nested.dedup_members());
+        assert!(nested.dedup_members_with(true));
+        assert_eq!(nested, parse_json(r#"[{"n": 1}, [2]]"#).unwrap());
+    }
+
+    #[test]
+    fn dedup_should_leave_non_arrays_alone() {
+        for json in [r#"{"a": [1, 1]}"#, "1", "null", r#""aa""#] {
+            let mut doc = parse_json(json).unwrap();
+            assert!(!doc.dedup_members());
+            assert!(!doc.dedup_members_with(true));
+            assert!(!doc.dedup_by_key("/a"));
+            assert_eq!(doc, parse_json(json).unwrap());
+        }
+        let mut empty = JsonValue::Array(vec![]);
+        assert!(!empty.dedup_members());
+    }
+
+    #[test]
+    fn dedup_by_key_should_keep_elements_without_the_key() {
+        let mut doc = parse_json(
+            r#"[{"id": 1, "v": "a"}, {"v": "no id"}, {"id": 2}, {"id": 1, "v": "b"},
+                {"v": "no id"}, 5, {"id": {"n": 1}}, {"id": {"n": 1}, "v": "c"}, {"id": null}]"#,
+        )
+        .unwrap();
+        assert!(doc.dedup_by_key("/id"));
+        assert_eq!(
+            doc,
+            parse_json(
+                r#"[{"id": 1, "v": "a"}, {"v": "no id"}, {"id": 2}, {"v": "no id"}, 5,
+                    {"id": {"n": 1}}, {"id": null}]"#
+            )
+            .unwrap()
+        );
+        let mut deep =
+            parse_json(r#"[{"m": {"k": 1}}, {"m": {"k": 2}}, {"m": {"k": 1}}]"#).unwrap();
+        assert!(deep.dedup_by_key(&Pointer::parse("/m/k").unwrap()));
+        assert_eq!(
+            deep,
+            parse_json(r#"[{"m": {"k": 1}}, {"m": {"k": 2}}]"#).unwrap()
+        );
+    }
+}
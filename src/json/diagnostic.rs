@@ -0,0 +1,202 @@
+use super::{snippet::line_start, JsonError};
+use std::{error::Error, fmt, ops::Range};
+
+impl JsonError {
+    /// The code a diagnostic shows for the error: `json::` and the name of
+    /// its [`code`](Self::code), such as `json::trailing_comma`.
+    pub fn diagnostic_code(&self) -> String {
+        format!("json::{}", self.code())
+    }
+
+    /// What would get the input past the error, when a parse option would,
+    /// such as "enable allow_trailing_commas to accept this".
+    pub fn help(&self) -> Option<String> {
+        let hint = match self {
+            JsonError::Parse { message, .. } => message.split_once("; ")?.1,
+            JsonError::ScalarRoot { .. } => "turn off require_container",
+            JsonError::TooManyEntries { object: false, .. } => "raise max_array_len",
+            JsonError::TooManyEntries { object: true, .. } => "raise max_object_members",
+            JsonError::PrecisionLoss { .. } => "enable preserve_number_text or parse_decimals",
+            JsonError::Line { source, .. }
+            | JsonError::Element { source, .. }
+            | JsonError::File { source, .. } => return source.help(),
+            _ => return None,
+        };
+        Some(format!("{} to accept this", hint))
+    }
+
+    /// The bytes of `input`, the text that was parsed, that the error is
+    /// about: the token of a scalar root or a rounded number, the bracket
+    /// of a container with too many entries, and the character where
+    /// parsing stopped otherwise, which is empty at the end of the input.
+    /// Errors for lines and elements of `input`, and for a file holding it,
+    /// point into it too; errors with no position have no span.
+    pub fn label_span(&self, input: &str) -> Option<Range<usize>> {
+        let span = match self {
+            JsonError::Parse { offset, .. } | JsonError::Cancelled { offset } => {
+                let offset = (*offset).min(input.len());
+                let len = input.get(offset..).and_then(|rest| rest.chars().next());
+                offset..offset + len.map_or(0, char::len_utf8)
+            }
+            JsonError::ScalarRoot { span, .. }
+            | JsonError::TooManyEntries { span, .. }
+            | JsonError::PrecisionLoss { span, .. } => span.clone(),
+            JsonError::Element { source, .. } | JsonError::File { source, .. } => {
+                return source.label_span(input)
+            }
+            JsonError::Line { line, source } => {
+                let start = line_start(input, *line)?;
+                let span = source.label_span(&input[start..])?;
+                start + span.start..start + span.end
+            }
+            _ => return None,
+        };
+        Some(span)
+    }
+
+    /// The error together with `input`, the text it was found in, to show
+    /// the two as one report.
+    pub fn with_source(self, input: impl Into<String>) -> JsonReport {
+        JsonReport {
+            error: self,
+            input: input.into(),
+        }
+    }
+}
+
+/// A [`JsonError`] with the text it was found in, from
+/// [`JsonError::with_source`]. It displays as
+/// [`display_with_source`](JsonError::display_with_source) does, with a
+/// line of help after when the error has some, and gives diagnostic
+/// renderers the code, span and help they label the source with.
+#[derive(Debug)]
+pub struct JsonReport {
+    error: JsonError,
+    input: String,
+}
+
+impl JsonReport {
+    pub fn error(&self) -> &JsonError {
+        &self.error
+    }
+
+    /// The text the error was found in.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn code(&self) -> String {
+        self.error.diagnostic_code()
+    }
+
+    pub fn help(&self) -> Option<String> {
+        self.error.help()
+    }
+
+    /// [`label_span`](JsonError::label_span) in the text.
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.error.label_span(&self.input)
+    }
+
+    pub fn into_error(self) -> JsonError {
+        self.error
+    }
+}
+
+impl fmt::Display for JsonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.error.display_with_source(&self.input))?;
+        match self.help() {
+            Some(help) => write!(f, "\n  = help: {}", help),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Error for JsonReport {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{parse_json, parse_json_with, parse_ndjson, ParseOptions};
+
+    #[test]
+    fn errors_should_carry_a_code_span_and_help() {
+        let strict = ParseOptions::strict();
+        for (input, options, code, span, help) in [
+            (
+                "[1, 2,]",
+                &strict,
+                "json::trailing_comma",
+                5..6,
+                Some("enable allow_trailing_commas to accept this"),
+            ),
+            ("{\"a\" 1}", &strict, "json::unexpected_token", 5..6, None),
+            ("[1, 2", &strict, "json::unexpected_eof", 5..5, None),
+            (
+                "['é']",
+                &strict,
+                "json::not_allowed",
+                1..2,
+                Some("enable allow_single_quotes to accept this"),
+            ),
+            (
+                "  \"hi\" ",
+                &strict.clone().require_container(true),
+                "json::scalar_root",
+                2..6,
+                Some("turn off require_container to accept this"),
+            ),
+            (
+                "[0.1]",
+                &strict.clone().error_on_precision_loss(true),
+                "json::precision_loss",
+                1..4,
+                Some("enable preserve_number_text or parse_decimals to accept this"),
+            ),
+        ] {
+            let err = parse_json_with(input, options).unwrap_err();
+            assert_eq!(err.diagnostic_code(), code, "{}", input);
+            assert_eq!(err.label_span(input), Some(span), "{}", input);
+            assert_eq!(err.help().as_deref(), help, "{}", input);
+        }
+        let deep = "[".repeat(200);
+        let err = parse_json(&deep).unwrap_err();
+        assert_eq!(err.diagnostic_code(), "json::depth_exceeded");
+        assert_eq!(err.help().unwrap(), "raise max_depth to accept this");
+    }
+
+    #[test]
+    fn errors_of_a_line_should_point_into_the_whole_input() {
+        let input = "{\"a\": 1}\n[1 2]\n";
+        let err = parse_ndjson(input).find_map(Result::err).unwrap();
+        let span = err.label_span(input).unwrap();
+        assert_eq!(&input[span], "2");
+        let no_position = crate::json::JsonError::serialize("NaN is not a valid JSON number");
+        assert_eq!(no_position.label_span("[1]"), None);
+        assert_eq!(no_position.diagnostic_code(), "json::serialize");
+        assert_eq!(no_position.help(), None);
+    }
+
+    #[test]
+    fn reports_should_show_the_source_and_help() {
+        let input = "[1,\n 2,]";
+        let report = parse_json(input).unwrap_err().with_source(input);
+        assert_eq!(report.code(), "json::trailing_comma");
+        assert_eq!(report.span(), Some(6..7));
+        assert_eq!(report.input(), input);
+        assert_eq!(
+            report.to_string(),
+            "error: trailing commas are not allowed; enable allow_trailing_commas\n --> 2:3\n  |\n1 | [1,\n2 |  2,]\n  |   ^\n  = help: enable allow_trailing_commas to accept this"
+        );
+        let source = std::error::Error::source(&report).unwrap();
+        assert_eq!(source.to_string(), report.error().to_string());
+        assert_eq!(
+            report.into_error().code(),
+            crate::json::ErrorCode::TrailingComma
+        );
+    }
+}
@@ -0,0 +1,627 @@
+use super::Pointer;
+use std::{fmt, io, ops::Range, path::PathBuf};
+use thiserror::Error;
+use winnow::error::{ContextError, ErrMode, StrContext};
+
+#[derive(Debug, Error)]
+pub enum JsonError {
+    /// The input is not valid JSON. `offset` is the byte offset into the
+    /// input where parsing stopped, and `code` says what went wrong.
+    #[error("{message} at offset {offset}")]
+    Parse {
+        offset: usize,
+        message: String,
+        code: ErrorCode,
+    },
+    /// The document is a scalar, and
+    /// [`require_container`](super::ParseOptions::require_container) asks
+    /// for an object or array. `span` covers the scalar's token.
+    #[error("the document must be an object or array, not {found}, at offset {}", span.start)]
+    ScalarRoot {
+        span: Range<usize>,
+        found: &'static str,
+    },
+    /// An array has more elements than `max_array_len` allows, or an
+    /// object more members than `max_object_members`. Parsing stops at the
+    /// first entry past the limit; `span` covers the opening bracket.
+    #[error("{} at offset {}", too_many_message(*object, *limit), span.start)]
+    TooManyEntries {
+        span: Range<usize>,
+        limit: usize,
+        object: bool,
+    },
+    /// Under [`error_on_precision_loss`], a number that would be rounded.
+    /// `token` is its text and `span` covers it.
+    ///
+    /// [`error_on_precision_loss`]: super::ParseOptions::error_on_precision_loss
+    #[error(
+        "{token} cannot be held exactly; enable preserve_number_text or parse_decimals, at offset {}",
+        span.start
+    )]
+    PrecisionLoss { span: Range<usize>, token: String },
+    /// [`cancel`](super::ParseOptions::cancel) asked to give up. `offset`
+    /// is how far parsing got.
+    #[error("parsing was cancelled at offset {offset}")]
+    Cancelled { offset: usize },
+    /// The value cannot be represented in the requested output. `pointer` is
+    /// the JSON Pointer of the offending value.
+    #[error("cannot serialize value at \"{pointer}\": {message}")]
+    Serialize { pointer: String, message: String },
+    /// The value does not have the shape a typed decode asked for, such as
+    /// a string where a number belongs. `path` leads to the offending value,
+    /// built up frame by frame as the error leaves each array, map and
+    /// struct it was in: `at /servers/2/port: invalid type: ...`.
+    #[error("{}", decode_message(path, message))]
+    Decode { path: Pointer, message: String },
+    /// A path cannot be made through the document: what is at `pointer`
+    /// cannot hold the next reference token.
+    #[error("cannot make a path through \"{pointer}\": {message}")]
+    Path { pointer: String, message: String },
+    /// A record of a line-oriented format such as NDJSON failed to parse.
+    /// `line` is 1-based and offsets in `source` are relative to the line.
+    #[error("line {line}: {source}")]
+    Line { line: usize, source: Box<JsonError> },
+    /// An element of a top-level array failed to parse. `index` is 0-based
+    /// and offsets in `source` are into the whole input.
+    #[error("element {index}: {source}")]
+    Element {
+        index: usize,
+        source: Box<JsonError>,
+    },
+    /// Reading the input failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Reading or parsing the file at `path` failed.
+    #[error("{}: {source}", path.display())]
+    File {
+        path: PathBuf,
+        source: Box<JsonError>,
+    },
+}
+
+/// I/O errors compare by kind and message, since `io::Error` itself has no
+/// equality.
+impl PartialEq for JsonError {
+    fn eq(&self, other: &Self) -> bool {
+        use JsonError::*;
+        match (self, other) {
+            (
+                Parse {
+                    offset,
+                    message,
+                    code,
+                },
+                Parse {
+                    offset: o,
+                    message: m,
+                    code: c,
+                },
+            ) => offset == o && message == m && code == c,
+            (ScalarRoot { span, found }, ScalarRoot { span: s, found: f }) => {
+                span == s && found == f
+            }
+            (
+                TooManyEntries {
+                    span,
+                    limit,
+                    object,
+                },
+                TooManyEntries {
+                    span: s,
+                    limit: l,
+                    object: o,
+                },
+            ) => span == s && limit == l && object == o,
+            (Cancelled { offset }, Cancelled { offset: o }) => offset == o,
+            (PrecisionLoss { span, token }, PrecisionLoss { span: s, token: t }) => {
+                span == s && token == t
+            }
+            (
+                Serialize { pointer, message },
+                Serialize {
+                    pointer: p,
+                    message: m,
+                },
+            ) => pointer == p && message == m,
+            (
+                Decode { path, message },
+                Decode {
+                    path: p,
+                    message: m,
+                },
+            ) => path == p && message == m,
+            (
+                Path { pointer, message },
+                Path {
+                    pointer: p,
+                    message: m,
+                },
+            ) => pointer == p && message == m,
+            (Line { line, source }, Line { line: l, source: s }) => line == l && source == s,
+            (
+                Element { index, source },
+                Element {
+                    index: i,
+                    source: s,
+                },
+            ) => index == i && source == s,
+            (Io(a), Io(b)) => a.kind() == b.kind() && a.to_string() == b.to_string(),
+            (File { path, source }, File { path: p, source: s }) => path == p && source == s,
+            _ => false,
+        }
+    }
+}
+
+/// What kind of failure a [`JsonError`] is, for code that branches on it:
+/// retrying on [`UnexpectedEof`](Self::UnexpectedEof) when more input may
+/// come, say. Codes and their [`as_str`](Self::as_str) names are a
+/// compatibility surface and stay put across releases, while messages may
+/// be reworded at any time. More codes may be added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// The input stopped inside a value or comment.
+    UnexpectedEof,
+    /// A character or byte that cannot come where it is.
+    UnexpectedToken,
+    /// More input after a complete value.
+    TrailingData,
+    /// A backslash escape that is not one JSON has.
+    InvalidEscape,
+    /// A number that cannot be held, being too large or, without
+    /// `big_int_as_float`, an integer too large to keep exactly.
+    InvalidNumber,
+    /// Bytes that are not UTF-8.
+    InvalidUtf8,
+    /// Nesting deeper than `max_depth`, or in CBOR or MessagePack deeper than
+    /// [`ParseOptions::DEFAULT_MAX_DEPTH`](super::ParseOptions::DEFAULT_MAX_DEPTH).
+    DepthExceeded,
+    /// A comma before a closing bracket, without `allow_trailing_commas`.
+    TrailingComma,
+    /// Syntax a parse option would accept, such as single quotes or `NaN`.
+    NotAllowed,
+    /// A scalar document under `require_container`:
+    /// [`JsonError::ScalarRoot`].
+    ScalarRoot,
+    /// A length or count past what can be held.
+    LimitExceeded,
+    /// A number that would be rounded, under `error_on_precision_loss`:
+    /// [`JsonError::PrecisionLoss`].
+    PrecisionLoss,
+    /// Parsing was given up: [`JsonError::Cancelled`].
+    Cancelled,
+    /// A CBOR or MessagePack item with no JSON equivalent.
+    Unsupported,
+    /// Serializing failed: [`JsonError::Serialize`].
+    Serialize,
+    /// Decoding into a type failed: [`JsonError::Decode`].
+    Decode,
+    /// A path could not be made: [`JsonError::Path`].
+    Path,
+    /// Reading failed: [`JsonError::Io`].
+    Io,
+}
+
+impl ErrorCode {
+    /// The code as `snake_case` text, such as `"unexpected_eof"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedEof => "unexpected_eof",
+            ErrorCode::UnexpectedToken => "unexpected_token",
+            ErrorCode::TrailingData => "trailing_data",
+            ErrorCode::InvalidEscape => "invalid_escape",
+            ErrorCode::InvalidNumber => "invalid_number",
+            ErrorCode::InvalidUtf8 => "invalid_utf8",
+            ErrorCode::DepthExceeded => "depth_exceeded",
+            ErrorCode::TrailingComma => "trailing_comma",
+            ErrorCode::NotAllowed => "not_allowed",
+            ErrorCode::ScalarRoot => "scalar_root",
+            ErrorCode::LimitExceeded => "limit_exceeded",
+            ErrorCode::PrecisionLoss => "precision_loss",
+            ErrorCode::Cancelled => "cancelled",
+            ErrorCode::Unsupported => "unsupported",
+            ErrorCode::Serialize => "serialize",
+            ErrorCode::Decode => "decode",
+            ErrorCode::Path => "path",
+            ErrorCode::Io => "io",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The labels the grammar fails with, each with its own code.
+pub(crate) const DEPTH_EXCEEDED: &str = "nesting is too deep; raise max_depth";
+pub(crate) const ALLOCATION_BUDGET: &str =
+    "the value would take more memory than max_total_allocated allows";
+pub(crate) const TRAILING_COMMA: &str =
+    "trailing commas are not allowed; enable allow_trailing_commas";
+pub(crate) const UNTERMINATED_COMMENT: &str = "unterminated block comment";
+pub(crate) const INVALID_ESCAPE: &str = "invalid escape sequence";
+pub(crate) const LONE_SURROGATE: &str = "unpaired surrogate in a \\u escape";
+pub(crate) const CONTROL_CHARACTER: &str = "control characters in strings must be escaped";
+pub(crate) const SINGLE_QUOTES: &str =
+    "single-quoted strings are not allowed; enable allow_single_quotes";
+pub(crate) const NONFINITE: &str = "NaN and Infinity are not allowed; enable allow_nonfinite";
+pub(crate) const NUMBER_OUT_OF_RANGE: &str = "number out of range";
+pub(crate) const INTEGER_OUT_OF_RANGE: &str = "integer out of range; enable big_int_as_float";
+
+fn label_code(label: &str) -> ErrorCode {
+    match label {
+        DEPTH_EXCEEDED => ErrorCode::DepthExceeded,
+        ALLOCATION_BUDGET => ErrorCode::LimitExceeded,
+        TRAILING_COMMA => ErrorCode::TrailingComma,
+        UNTERMINATED_COMMENT => ErrorCode::UnexpectedEof,
+        INVALID_ESCAPE | LONE_SURROGATE => ErrorCode::InvalidEscape,
+        SINGLE_QUOTES | NONFINITE => ErrorCode::NotAllowed,
+        NUMBER_OUT_OF_RANGE | INTEGER_OUT_OF_RANGE => ErrorCode::InvalidNumber,
+        _ => ErrorCode::UnexpectedToken,
+    }
+}
+
+impl JsonError {
+    /// What kind of failure this is; for a wrapped error, that of the one
+    /// inside.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            JsonError::Parse { code, .. } => *code,
+            JsonError::ScalarRoot { .. } => ErrorCode::ScalarRoot,
+            JsonError::TooManyEntries { .. } => ErrorCode::LimitExceeded,
+            JsonError::PrecisionLoss { .. } => ErrorCode::PrecisionLoss,
+            JsonError::Cancelled { .. } => ErrorCode::Cancelled,
+            JsonError::Serialize { .. } => ErrorCode::Serialize,
+            JsonError::Decode { .. } => ErrorCode::Decode,
+            JsonError::Path { .. } => ErrorCode::Path,
+            JsonError::Line { source, .. }
+            | JsonError::Element { source, .. }
+            | JsonError::File { source, .. } => source.code(),
+            JsonError::Io(_) => ErrorCode::Io,
+        }
+    }
+
+    /// Where in the document a typed decode failed, for a
+    /// [`Decode`](JsonError::Decode) error or one wrapping it.
+    pub fn path(&self) -> Option<&Pointer> {
+        match self {
+            JsonError::Decode { path, .. } => Some(path),
+            JsonError::Line { source, .. }
+            | JsonError::Element { source, .. }
+            | JsonError::File { source, .. } => source.path(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn parse(code: ErrorCode, offset: usize, message: impl Into<String>) -> Self {
+        JsonError::Parse {
+            offset,
+            message: message.into(),
+            code,
+        }
+    }
+
+    /// A parse error at the position `rest` points to, where `rest` is a
+    /// suffix of `input`.
+    pub(crate) fn parse_at(input: &str, rest: &str) -> Self {
+        let offset = input.len() - rest.len();
+        match rest.chars().next() {
+            Some(c) => unexpected(offset, c),
+            None => eof(offset),
+        }
+    }
+
+    /// This error with the offset of a parse error moved `by` bytes on, for
+    /// input that was parsed out of a larger whole.
+    pub(crate) fn shifted(self, by: usize) -> Self {
+        match self {
+            JsonError::Parse {
+                offset,
+                message,
+                code,
+            } => JsonError::Parse {
+                offset: by + offset,
+                message,
+                code,
+            },
+            JsonError::ScalarRoot { span, found } => JsonError::ScalarRoot {
+                span: by + span.start..by + span.end,
+                found,
+            },
+            JsonError::TooManyEntries {
+                span,
+                limit,
+                object,
+            } => JsonError::TooManyEntries {
+                span: by + span.start..by + span.end,
+                limit,
+                object,
+            },
+            JsonError::PrecisionLoss { span, token } => JsonError::PrecisionLoss {
+                span: by + span.start..by + span.end,
+                token,
+            },
+            JsonError::Cancelled { offset } => JsonError::Cancelled {
+                offset: by + offset,
+            },
+            e => e,
+        }
+    }
+
+    /// This error for what follows a complete value, where an unexpected
+    /// character is [`ErrorCode::TrailingData`].
+    pub(crate) fn trailing(self) -> Self {
+        match self {
+            JsonError::Parse {
+                offset,
+                message,
+                code: ErrorCode::UnexpectedToken,
+            } => JsonError::parse(ErrorCode::TrailingData, offset, message),
+            e => e,
+        }
+    }
+
+    /// A parse error from a failed parser, using its label as the message
+    /// when it has one.
+    pub(crate) fn from_parser(input: &str, rest: &str, err: ErrMode<ContextError>) -> Self {
+        let err = err.into_inner();
+        let cause = err.as_ref().and_then(ContextError::cause);
+        if let Some(hit) = cause.and_then(|c| c.downcast_ref::<EntryLimit>()) {
+            let at = input.len() - hit.left;
+            return too_many_entries(at, hit.limit, hit.object);
+        }
+        if cause.is_some_and(|c| c.is::<Cancel>()) {
+            return JsonError::Cancelled {
+                offset: input.len() - rest.len(),
+            };
+        }
+        if let Some(hit) = cause.and_then(|c| c.downcast_ref::<Inexact>()) {
+            let at = input.len() - hit.left;
+            return JsonError::PrecisionLoss {
+                span: at..at + hit.len,
+                token: input[at..at + hit.len].to_string(),
+            };
+        }
+        let label = err.and_then(|e| {
+            e.context().find_map(|c| match c {
+                StrContext::Label(label) => Some(*label),
+                _ => None,
+            })
+        });
+        match label {
+            Some(message) => {
+                JsonError::parse(label_code(message), input.len() - rest.len(), message)
+            }
+            None if truncated(rest) => eof(input.len()),
+            None => JsonError::parse_at(input, rest),
+        }
+    }
+
+    pub(crate) fn serialize(message: impl Into<String>) -> Self {
+        JsonError::Serialize {
+            pointer: String::new(),
+            message: message.into(),
+        }
+    }
+
+    /// Prepend a path segment while the error bubbles up out of a container.
+    pub(crate) fn within(self, segment: &str) -> Self {
+        match self {
+            JsonError::Serialize { pointer, message } => JsonError::Serialize {
+                pointer: format!("/{}{}", escape_pointer_token(segment), pointer),
+                message,
+            },
+            JsonError::Decode { mut path, message } => {
+                path.prepend(segment);
+                JsonError::Decode { path, message }
+            }
+            e => e,
+        }
+    }
+}
+
+/// A decode failure with where it happened in front, unless that is the
+/// whole document.
+fn decode_message(path: &Pointer, message: &str) -> String {
+    match path.is_root() {
+        true => message.to_string(),
+        false => format!("at {}: {}", path, message),
+    }
+}
+
+/// `c` where it cannot be.
+pub(crate) fn unexpected(offset: usize, c: char) -> JsonError {
+    JsonError::parse(
+        ErrorCode::UnexpectedToken,
+        offset,
+        format!("unexpected character {:?}", c),
+    )
+}
+
+/// The input ending at `offset` before it should.
+pub(crate) fn eof(offset: usize) -> JsonError {
+    JsonError::parse(ErrorCode::UnexpectedEof, offset, "unexpected end of input")
+}
+
+/// [`JsonError::TooManyEntries`] on its way out of the grammar, as the
+/// cause of its failure. `left` is the length of the input from the
+/// opening bracket on.
+#[derive(Debug, Error)]
+#[error("too many entries")]
+pub(crate) struct EntryLimit {
+    pub(crate) left: usize,
+    pub(crate) limit: usize,
+    pub(crate) object: bool,
+}
+
+/// [`JsonError::PrecisionLoss`] on its way out of the grammar, for the
+/// `len` bytes of number text `left` bytes before the end of the input.
+#[derive(Debug, Error)]
+#[error("inexact number")]
+pub(crate) struct Inexact {
+    pub(crate) left: usize,
+    pub(crate) len: usize,
+}
+
+/// [`JsonError::Cancelled`] on its way out of the grammar.
+#[derive(Debug, Error)]
+#[error("cancelled")]
+pub(crate) struct Cancel;
+
+/// The container opening at `at` going past `limit` entries.
+pub(crate) fn too_many_entries(at: usize, limit: usize, object: bool) -> JsonError {
+    JsonError::TooManyEntries {
+        span: at..at + 1,
+        limit,
+        object,
+    }
+}
+
+/// What [`JsonError::TooManyEntries`] says, before the offset.
+pub(crate) fn too_many_message(object: bool, limit: usize) -> String {
+    match object {
+        true => format!(
+            "object has more than {} members; raise max_object_members",
+            limit
+        ),
+        false => format!(
+            "array has more than {} elements; raise max_array_len",
+            limit
+        ),
+    }
+}
+
+/// The scalar at `span` of `input` standing as the whole document.
+pub(crate) fn scalar_root(input: &str, span: Range<usize>) -> JsonError {
+    let found = match input[span.start..].chars().next() {
+        Some('n') => "null",
+        Some('t' | 'f') => "a boolean",
+        Some('"' | '\'') => "a string",
+        _ => "a number",
+    };
+    JsonError::ScalarRoot { span, found }
+}
+
+pub(crate) fn invalid_utf8(offset: usize) -> JsonError {
+    JsonError::parse(ErrorCode::InvalidUtf8, offset, "invalid UTF-8")
+}
+
+/// Whether `rest`, all that is left of the input where a value failed to
+/// parse, is a string, literal or number the input ended in the middle of,
+/// which more input could finish.
+pub(crate) fn truncated(rest: &str) -> bool {
+    let mut chars = rest.chars();
+    match chars.next() {
+        Some(quote @ ('"' | '\'')) => {
+            let mut escaped = false;
+            for c in chars {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    c if c == quote => return false,
+                    _ => {}
+                }
+            }
+            true
+        }
+        Some(_) if rest.bytes().all(|b| b"0123456789+-.eE".contains(&b)) => {
+            rest.ends_with(['+', '-', '.', 'e', 'E'])
+        }
+        Some(_) => ["true", "false", "null", "NaN", "Infinity", "-Infinity"]
+            .iter()
+            .any(|word| word.len() > rest.len() && word.starts_with(rest)),
+        None => false,
+    }
+}
+
+pub(crate) fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{
+        from_reader, parse_json, parse_json_bytes, parse_json_with, parse_ndjson, skip,
+        FormatOptions, JsonValue, ParseOptions,
+    };
+
+    fn code(input: &str) -> ErrorCode {
+        parse_json(input).unwrap_err().code()
+    }
+
+    #[test]
+    fn malformed_input_should_have_stable_codes() {
+        for (input, expected) in [
+            ("", ErrorCode::UnexpectedEof),
+            ("[1", ErrorCode::UnexpectedEof),
+            ("{\"a\": \"b", ErrorCode::UnexpectedEof),
+            ("[\"a\\", ErrorCode::UnexpectedEof),
+            ("[tru", ErrorCode::UnexpectedEof),
+            ("[-", ErrorCode::UnexpectedEof),
+            ("[1.", ErrorCode::UnexpectedEof),
+            ("[1e", ErrorCode::UnexpectedEof),
+            ("[1 x", ErrorCode::UnexpectedToken),
+            ("{1: 2}", ErrorCode::UnexpectedToken),
+            ("[tru]", ErrorCode::UnexpectedToken),
+            ("[1.]", ErrorCode::UnexpectedToken),
+            ("[1e, 2", ErrorCode::UnexpectedToken),
+            ("[1] x", ErrorCode::TrailingData),
+            ("{} {}", ErrorCode::TrailingData),
+            ("\"\\q\"", ErrorCode::InvalidEscape),
+            ("1e400", ErrorCode::InvalidNumber),
+            ("[1,]", ErrorCode::TrailingComma),
+            ("'a'", ErrorCode::NotAllowed),
+            ("[NaN]", ErrorCode::NotAllowed),
+        ] {
+            assert_eq!(code(input), expected, "{:?}", input);
+            // the other front ends agree
+            let err = skip::validate(input).unwrap_err();
+            assert_eq!(err.code(), expected, "{:?}", input);
+            // the byte reader is strict only and names no options
+            if expected != ErrorCode::NotAllowed {
+                let err = from_reader(input.as_bytes()).unwrap_err();
+                assert_eq!(err.code(), expected, "{:?}", input);
+            }
+        }
+        let deep = format!("{}{}", "[".repeat(1000), "]".repeat(1000));
+        assert_eq!(code(&deep), ErrorCode::DepthExceeded);
+        let shallow = ParseOptions::strict().max_depth(1);
+        let err = parse_json_with("[[1]]", &shallow).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::DepthExceeded);
+        let exact = ParseOptions::strict().big_int_as_float(false);
+        let err = parse_json_with("99999999999999999999", &exact).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidNumber);
+        let comments = ParseOptions::strict().allow_comments(true);
+        let err = parse_json_with("[1 /* 2", &comments).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::UnexpectedEof);
+        let err = parse_json_bytes(b"[\"\xff\"]").unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidUtf8);
+    }
+
+    #[test]
+    fn wrapped_errors_should_have_the_code_inside() {
+        let line = parse_ndjson("1\n[1,]\n").find_map(Result::err).unwrap();
+        assert!(matches!(line, JsonError::Line { line: 2, .. }));
+        assert_eq!(line.code(), ErrorCode::TrailingComma);
+        let file = JsonError::File {
+            path: "data.json".into(),
+            source: Box::new(line),
+        };
+        assert_eq!(file.code(), ErrorCode::TrailingComma);
+        let io = JsonError::from(io::Error::other("gone"));
+        assert_eq!(io.code(), ErrorCode::Io);
+        let nan = JsonValue::from(f64::NAN).try_to_string_with(&FormatOptions::default());
+        assert_eq!(nan.unwrap_err().code(), ErrorCode::Serialize);
+    }
+
+    #[test]
+    fn codes_should_have_snake_case_names() {
+        assert_eq!(ErrorCode::UnexpectedEof.as_str(), "unexpected_eof");
+        assert_eq!(ErrorCode::TrailingData.to_string(), "trailing_data");
+        assert_eq!(ErrorCode::InvalidUtf8.as_str(), "invalid_utf8");
+        assert_eq!(ErrorCode::InvalidNumber.to_string(), "invalid_number");
+    }
+}
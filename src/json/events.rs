@@ -0,0 +1,390 @@
+use super::{
+    allocate_here, cancelled, entry_end, error, open, parse_bool, parse_key, parse_nonfinite,
+    parse_null, parse_num, parse_string, sep_with_ws, size, stateful, trace, ws, Input, JsonError,
+    JsonString, JsonValue, Num, ParseOptions, TreeBuilder,
+};
+use winnow::{
+    combinator::{alt, cut_err, opt, peek},
+    error::ContextError,
+    prelude::*,
+};
+
+/// One step of a document, in the order [`JsonReader`] meets it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent {
+    ObjectStart,
+    ObjectEnd,
+    /// An object key; the member's value follows as the next event(s).
+    Key(JsonString),
+    ArrayStart,
+    ArrayEnd,
+    String(JsonString),
+    Number(Num),
+    Bool(bool),
+    Null,
+}
+
+/// A pull parser that hands out a document as [`JsonEvent`]s without
+/// building a tree. Containers are checked to open and close in matching
+/// pairs, and input after the root value is an error. [`parse_json`] is the
+/// tree built from these events, so both accept exactly the same input.
+///
+/// [`parse_json`]: super::parse_json
+pub struct JsonReader<'a> {
+    source: &'a str,
+    input: Input<'a>,
+    /// Open containers, innermost last.
+    stack: Vec<Frame>,
+    state: State,
+}
+
+/// An open container, counted against `max_array_len` or
+/// `max_object_members`.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    object: bool,
+    entries: usize,
+    /// The input left at its opening bracket.
+    left: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Root,
+    /// Just inside an opening bracket.
+    FirstEntry,
+    /// After a comma.
+    Entry,
+    /// After a key and its colon.
+    MemberValue,
+    AfterEntry,
+    AfterRoot,
+    Done,
+}
+
+impl<'a> JsonReader<'a> {
+    /// A reader for strict JSON.
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, &ParseOptions::default())
+    }
+
+    /// A reader with the leniency switches in `options`.
+    pub fn with_options(input: &'a str, options: &ParseOptions) -> Self {
+        JsonReader {
+            source: input,
+            input: stateful(input, options),
+            stack: Vec::new(),
+            state: State::Root,
+        }
+    }
+
+    /// Record the rules this reader goes through for
+    /// [`parse_json_traced`](super::parse_json_traced).
+    pub(super) fn trace(&mut self) {
+        self.input.state.tracing = true;
+    }
+
+    /// The next event, or `None` once the root value has ended. After an
+    /// error the reader is exhausted.
+    pub fn next_event(&mut self) -> Result<Option<JsonEvent>, JsonError> {
+        let event = self.step();
+        if event.is_err() {
+            self.state = State::Done;
+        }
+        event
+    }
+
+    fn step(&mut self) -> Result<Option<JsonEvent>, JsonError> {
+        loop {
+            let event = match self.state {
+                State::Root => self.root()?,
+                State::FirstEntry => match self.close()? {
+                    Some(end) => end,
+                    None => self.entry()?,
+                },
+                State::Entry => self.entry()?,
+                State::MemberValue => self.wrap(cut_err(value))?,
+                State::AfterEntry => {
+                    let close = self.closing();
+                    if !self.wrap(|i: &mut Input<'a>| entry_end(i, close))? {
+                        self.state = State::Entry;
+                        continue;
+                    }
+                    self.end_event(close)
+                }
+                State::AfterRoot => {
+                    self.wrap(ws)?;
+                    self.state = State::Done;
+                    if !self.input.input.is_empty() {
+                        return Err(JsonError::parse_at(self.source, self.input.input).trailing());
+                    }
+                    return Ok(None);
+                }
+                State::Done => return Ok(None),
+            };
+            self.after(&event);
+            return Ok(Some(event));
+        }
+    }
+
+    /// The first event, checked against `require_container`.
+    fn root(&mut self) -> Result<JsonEvent, JsonError> {
+        self.wrap(ws)?;
+        let start = self.source.len() - self.input.input.len();
+        self.wrap(|i: &mut Input<'a>| allocate_here(i, size::VALUE_SIZE))?;
+        let event = self.wrap(value)?;
+        let scalar = !matches!(event, JsonEvent::ArrayStart | JsonEvent::ObjectStart);
+        if scalar && self.input.state.options.require_container {
+            let end = self.source.len() - self.input.input.len();
+            return Err(error::scalar_root(self.source, start..end));
+        }
+        Ok(event)
+    }
+
+    /// Run `parser` on the input, turning its failure into a [`JsonError`].
+    fn wrap<O>(
+        &mut self,
+        mut parser: impl Parser<Input<'a>, O, ContextError>,
+    ) -> Result<O, JsonError> {
+        parser
+            .parse_next(&mut self.input)
+            .map_err(|e| JsonError::from_parser(self.source, self.input.input, e))
+    }
+
+    fn in_object(&self) -> bool {
+        self.stack.last().is_some_and(|frame| frame.object)
+    }
+
+    fn closing(&self) -> char {
+        if self.in_object() {
+            '}'
+        } else {
+            ']'
+        }
+    }
+
+    /// The end event if the innermost container closes here.
+    fn close(&mut self) -> Result<Option<JsonEvent>, JsonError> {
+        let close = self.closing();
+        let closed = self.wrap(opt(sep_with_ws(close)))?.is_some();
+        Ok(closed.then(|| self.end_event(close)))
+    }
+
+    fn end_event(&self, close: char) -> JsonEvent {
+        if close == '}' {
+            JsonEvent::ObjectEnd
+        } else {
+            JsonEvent::ArrayEnd
+        }
+    }
+
+    /// A key in an object, a value in an array.
+    fn entry(&mut self) -> Result<JsonEvent, JsonError> {
+        let options = &self.input.state.options;
+        if let Some(frame) = self.stack.last_mut() {
+            let limit = match frame.object {
+                true => options.max_object_members,
+                false => options.max_array_len,
+            };
+            if let Some(limit) = limit.filter(|&limit| frame.entries == limit) {
+                let at = self.source.len() - frame.left;
+                return Err(error::too_many_entries(at, limit, frame.object));
+            }
+            frame.entries += 1;
+        }
+        self.wrap(cancelled)?;
+        let object = self.in_object();
+        self.wrap(|i: &mut Input<'a>| allocate_here(i, size::entry_size(object)))?;
+        if object {
+            let key = self.wrap(cut_err((parse_key, sep_with_ws(':'))))?.0;
+            return Ok(JsonEvent::Key(key));
+        }
+        self.wrap(cut_err(value))
+    }
+
+    fn after(&mut self, event: &JsonEvent) {
+        self.state = match event {
+            JsonEvent::ObjectStart | JsonEvent::ArrayStart => {
+                self.stack.push(Frame {
+                    object: *event == JsonEvent::ObjectStart,
+                    entries: 0,
+                    left: self.input.state.opened,
+                });
+                State::FirstEntry
+            }
+            JsonEvent::Key(_) => State::MemberValue,
+            JsonEvent::ObjectEnd | JsonEvent::ArrayEnd => {
+                self.stack.pop();
+                self.input.state.depth -= 1;
+                let rule = if *event == JsonEvent::ObjectEnd {
+                    "object"
+                } else {
+                    "array"
+                };
+                trace::exit(&self.input, rule, true);
+                trace::exit(&self.input, "value", true);
+                self.settled()
+            }
+            _ => self.settled(),
+        };
+    }
+
+    /// The state after a complete value.
+    fn settled(&self) -> State {
+        if self.stack.is_empty() {
+            State::AfterRoot
+        } else {
+            State::AfterEntry
+        }
+    }
+
+    /// Build the tree of the whole document.
+    pub(super) fn into_value(mut self) -> Result<JsonValue, JsonError> {
+        let mut tree = TreeBuilder::default();
+        while let Some(event) = self.next_event()? {
+            tree.push(event);
+        }
+        Ok(tree
+            .into_value()
+            .expect("the reader ends after the root value"))
+    }
+}
+
+/// A scalar, or the opening bracket of a container.
+fn value(input: &mut Input<'_>) -> PResult<JsonEvent> {
+    trace::enter(input, "value");
+    let event = alt((
+        parse_null.value(JsonEvent::Null),
+        parse_bool.map(JsonEvent::Bool),
+        parse_nonfinite.map(|f| JsonEvent::Number(Num::Float(f))),
+        parse_num.map(JsonEvent::Number),
+        parse_string.map(JsonEvent::String),
+        container('[', "array").value(JsonEvent::ArrayStart),
+        container('{', "object").value(JsonEvent::ObjectStart),
+    ))
+    .parse_next(input);
+    // a container's rules end with it, in `after`
+    if !matches!(event, Ok(JsonEvent::ArrayStart | JsonEvent::ObjectStart)) {
+        trace::exit(input, "value", event.is_ok());
+    }
+    event
+}
+
+/// The opening bracket `c`, starting the rule `name` of its container.
+fn container<'a>(c: char, name: &'static str) -> impl Parser<Input<'a>, (), ContextError> {
+    move |input: &mut Input<'a>| {
+        ws(input)?;
+        peek(c).parse_next(input)?;
+        trace::enter(input, name);
+        open(c).parse_next(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use crate::json::{ErrorCode, JsonObject};
+
+    fn events(input: &str) -> Result<Vec<JsonEvent>, JsonError> {
+        let mut reader = JsonReader::new(input);
+        let mut out = Vec::new();
+        while let Some(event) = reader.next_event()? {
+            out.push(event);
+        }
+        Ok(out)
+    }
+
+    #[test]
+    fn json_reader_should_emit_events_in_document_order() {
+        use JsonEvent::*;
+        let input = r#"{"name": "John Doe", "age": 30, "marks": [90.0, -80.5],
+            "address": {"city": "New York", "zip": null}, "ok": true, "tags": [[], {}]}"#;
+        let key = |k: &str| Key(k.into());
+        assert_eq!(
+            events(input).unwrap(),
+            vec![
+                ObjectStart,
+                key("name"),
+                String("John Doe".into()),
+                key("age"),
+                Number(Num::Int(30)),
+                key("marks"),
+                ArrayStart,
+                Number(Num::Float(90.0)),
+                Number(Num::Float(-80.5)),
+                ArrayEnd,
+                key("address"),
+                ObjectStart,
+                key("city"),
+                String("New York".into()),
+                key("zip"),
+                Null,
+                ObjectEnd,
+                key("ok"),
+                Bool(true),
+                key("tags"),
+                ArrayStart,
+                ArrayStart,
+                ArrayEnd,
+                ObjectStart,
+                ObjectEnd,
+                ArrayEnd,
+                ObjectEnd,
+            ]
+        );
+        assert_eq!(events(" 1 ").unwrap(), vec![Number(Num::Int(1))]);
+    }
+
+    #[test]
+    fn json_reader_should_rebuild_the_tree() {
+        let input = r#"{"a": [1, {"b": [null, "x"]}, []], "c": {"d": {}}, "e": 1.5}"#;
+        let tree = JsonReader::new(input).into_value().unwrap();
+        assert_eq!(
+            tree,
+            JsonValue::Object(JsonObject::from_iter([
+                (
+                    "a".into(),
+                    JsonValue::Array(vec![
+                        JsonValue::from(1),
+                        JsonValue::Object(JsonObject::from_iter([(
+                            "b".into(),
+                            JsonValue::Array(vec![JsonValue::Null, JsonValue::from("x")])
+                        )])),
+                        JsonValue::Array(vec![]),
+                    ])
+                ),
+                (
+                    "c".into(),
+                    JsonValue::Object(JsonObject::from_iter([(
+                        "d".into(),
+                        JsonValue::Object(JsonObject::default())
+                    )]))
+                ),
+                ("e".into(), JsonValue::from(1.5)),
+            ]))
+        );
+        assert_eq!(parse_json(input).unwrap(), tree);
+    }
+
+    #[test]
+    fn json_reader_should_validate_nesting() {
+        let mut reader = JsonReader::new("[1, {\"a\": 2]");
+        for _ in 0..5 {
+            reader.next_event().unwrap();
+        }
+        assert_eq!(
+            reader.next_event(),
+            Err(JsonError::Parse {
+                offset: 11,
+                message: "unexpected character ']'".to_string(),
+                code: ErrorCode::UnexpectedToken,
+            })
+        );
+        assert_eq!(reader.next_event(), Ok(None));
+        assert!(events("[1] 2").is_err());
+        assert!(events("[1,]").is_err());
+        assert!(events("{\"a\"}").is_err());
+        assert!(events("").is_err());
+    }
+}
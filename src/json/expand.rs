@@ -0,0 +1,169 @@
+use super::{error::escape_pointer_token, json_string, JsonValue};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ExpandError {
+    /// `${name}` has no value and no `:-default`.
+    #[error("undefined variable {name:?} in string at \"{pointer}\"")]
+    Undefined { name: String, pointer: String },
+    /// A `${` without its closing `}`.
+    #[error("unterminated variable reference in string at \"{pointer}\"")]
+    Unterminated { pointer: String },
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` in every string value of `value`,
+/// using `lookup` to resolve names. The default is used when the variable
+/// is undefined or empty. `$$` stands for a literal `$`, and any other `$`
+/// is kept as it is. Object keys are not expanded.
+///
+/// Pass `|name| std::env::var(name).ok()` to read the process environment.
+pub fn expand_vars(
+    value: &mut JsonValue,
+    mut lookup: impl FnMut(&str) -> Option<String>,
+) -> Result<(), ExpandError> {
+    expand_value(value, &mut lookup, &mut String::new())
+}
+
+fn expand_value(
+    value: &mut JsonValue,
+    lookup: &mut dyn FnMut(&str) -> Option<String>,
+    pointer: &mut String,
+) -> Result<(), ExpandError> {
+    let len = pointer.len();
+    let mut child = |segment: &str, value: &mut JsonValue, pointer: &mut String| {
+        pointer.push('/');
+        pointer.push_str(&escape_pointer_token(segment));
+        expand_value(value, lookup, pointer)?;
+        pointer.truncate(len);
+        Ok(())
+    };
+    match value {
+        JsonValue::String(s) if s.contains('$') => {
+            *s = json_string(expand_str(s, lookup, pointer)?)
+        }
+        JsonValue::Array(arr) => {
+            for (i, v) in arr.iter_mut().enumerate() {
+                child(&i.to_string(), v, pointer)?;
+            }
+        }
+        JsonValue::Object(obj) => {
+            // sorted so the error for a document is always the same one
+            let mut members: Vec<_> = obj.iter_mut().collect();
+            members.sort_unstable_by_key(|(k, _)| *k);
+            for (k, v) in members {
+                child(k, v, pointer)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand_str(
+    s: &str,
+    lookup: &mut dyn FnMut(&str) -> Option<String>,
+    pointer: &str,
+) -> Result<String, ExpandError> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}').ok_or_else(|| ExpandError::Unterminated {
+                pointer: pointer.to_string(),
+            })?;
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            match (lookup(name), default) {
+                (Some(v), Some(default)) if v.is_empty() => out.push_str(default),
+                (Some(v), _) => out.push_str(&v),
+                (None, Some(default)) => out.push_str(default),
+                (None, None) => {
+                    return Err(ExpandError::Undefined {
+                        name: name.to_string(),
+                        pointer: pointer.to_string(),
+                    })
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use std::collections::HashMap;
+
+    fn expand(input: &str, vars: &[(&str, &str)]) -> Result<JsonValue, ExpandError> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        let mut v = parse_json(input).unwrap();
+        expand_vars(&mut v, |name| vars.get(name).map(|v| v.to_string()))?;
+        Ok(v)
+    }
+
+    #[test]
+    fn expand_vars_should_substitute_and_use_defaults() {
+        let v = expand(
+            r#"{"url": "http://${HOST}:${PORT:-8080}/", "port": 1, "list": ["${EMPTY:-x}", "${HOST}"]}"#,
+            &[("HOST", "example.com"), ("EMPTY", "")],
+        )
+        .unwrap();
+        let expected = parse_json(
+            r#"{"url": "http://example.com:8080/", "port": 1, "list": ["x", "example.com"]}"#,
+        )
+        .unwrap();
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn expand_vars_should_report_undefined_variables_with_pointer() {
+        let err = expand(
+            r#"{"servers": [{"host": "ok"}, {"a/b": "${MISSING}"}]}"#,
+            &[],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ExpandError::Undefined {
+                name: "MISSING".to_string(),
+                pointer: "/servers/1/a~1b".to_string()
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "undefined variable \"MISSING\" in string at \"/servers/1/a~1b\""
+        );
+        assert_eq!(
+            expand(r#"["${OPEN"]"#, &[]).unwrap_err(),
+            ExpandError::Unterminated {
+                pointer: "/0".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn expand_vars_should_handle_escapes_and_whole_values() {
+        let v = expand(
+            r#"["$${HOME}", "cost: $5", "${A}", "$$$$"]"#,
+            &[("A", "${B}")],
+        )
+        .unwrap();
+        assert_eq!(
+            v,
+            parse_json(r#"["${HOME}", "cost: $5", "${B}", "$$"]"#).unwrap()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use super::{parse_value, strict, JsonValue};
+use std::ops::Range;
+
+/// The first JSON value in `input`, which may be any text around it, such
+/// as the object in `INFO payload={"user":"x"} elapsed=3ms`, with its byte
+/// span. The value is the first that parses from a `{`, `[`, `"`, `-`,
+/// digit or literal, as [`iter_values`](super::iter_values) parses each
+/// value; where one does not, as in `{oops}`, the search goes on from the
+/// next character. A number or literal only counts as a word of its own,
+/// so nothing is found in `2024-08-12`, `17:05`, `3ms` or `nullable`.
+pub fn extract_json(input: &str) -> Option<(JsonValue, Range<usize>)> {
+    extract_from(input, 0)
+}
+
+/// Every value [`extract_json`] would find in `input`, in order, the
+/// search for each starting after the one before, so none overlap.
+pub fn extract_all_json(input: &str) -> Vec<(JsonValue, Range<usize>)> {
+    let mut found = Vec::new();
+    let mut at = 0;
+    while let Some((value, span)) = extract_from(input, at) {
+        at = span.end;
+        found.push((value, span));
+    }
+    found
+}
+
+fn extract_from(input: &str, from: usize) -> Option<(JsonValue, Range<usize>)> {
+    input[from..].char_indices().find_map(|(i, c)| {
+        let start = from + i;
+        if !matches!(c, '{' | '[' | '"' | '-' | '0'..='9' | 't' | 'f' | 'n') {
+            return None;
+        }
+        let mut rest = strict(&input[start..]);
+        let value = parse_value(&mut rest).ok()?;
+        // closing brackets swallow the whitespace after them
+        let text = &input[start..input.len() - rest.input.len()];
+        let end = start + text.trim_end_matches([' ', '\t', '\n', '\r']).len();
+        let word = !matches!(c, '{' | '[' | '"');
+        if word && (glued(input[..start].chars().next_back()) || glued(input[end..].chars().next()))
+        {
+            return None;
+        }
+        Some((value, start..end))
+    })
+}
+
+/// Whether `c` would run on from a number or literal next to it.
+fn glued(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+' | ':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const LOG: &str = r#"2024-08-12 17:05:44 INFO payload={"user":"x","ok":true} elapsed=3ms"#;
+
+    #[test]
+    fn extract_json_should_find_the_payload_of_a_log_line() {
+        let (value, span) = extract_json(LOG).unwrap();
+        assert_eq!(value, parse_json(r#"{"user": "x", "ok": true}"#).unwrap());
+        assert_eq!(&LOG[span], r#"{"user":"x","ok":true}"#);
+        assert_eq!(extract_all_json(LOG).len(), 1);
+    }
+
+    #[test]
+    fn extract_all_json_should_find_every_value_in_order() {
+        let text = r#"got [1, 2] then {"a": {"b": null}}, "quoted" and 42; done true"#;
+        let found: Vec<_> = extract_all_json(text)
+            .into_iter()
+            .map(|(value, span)| {
+                assert_eq!(parse_json(&text[span.clone()]).unwrap(), value);
+                &text[span]
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                "[1, 2]",
+                r#"{"a": {"b": null}}"#,
+                r#""quoted""#,
+                "42",
+                "true"
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_json_should_find_nothing_in_plain_text() {
+        for text in [
+            "",
+            "no json here",
+            "2024-08-12 17:05:44 took 3ms, nullable v1.2",
+            "[unclosed {",
+        ] {
+            assert_eq!(extract_json(text), None, "{:?}", text);
+            assert!(extract_all_json(text).is_empty(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn extract_json_should_skip_false_starts() {
+        let text = r#"set {oops} and [,] then {"real": [1, 2]} end"#;
+        let (value, span) = extract_json(text).unwrap();
+        assert_eq!(&text[span.clone()], r#"{"real": [1, 2]}"#);
+        assert_eq!(value, parse_json(&text[span]).unwrap());
+        // a minus that starts no number, and one that does
+        assert_eq!(
+            extract_json("a - b = -3").map(|(v, _)| v),
+            Some(JsonValue::from(-3i64))
+        );
+    }
+}
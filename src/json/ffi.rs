@@ -0,0 +1,394 @@
+//! A C API for hosts that are not Rust, behind the `ffi` feature:
+//!
+//! ```c
+//! typedef struct JsonDoc JsonDoc;
+//! typedef struct {
+//!     bool failed;
+//!     size_t offset, line, column; /* 1-based line and column; 0 if not a parse error */
+//!     const char *message;         /* NUL-terminated, NULL unless failed */
+//! } JsonErrorInfo;
+//!
+//! JsonDoc *json_parse(const char *input, size_t len);
+//! void json_free(JsonDoc *doc);
+//! char *json_get_pointer(JsonDoc *doc, const char *pointer);
+//! JsonErrorInfo json_last_error(const JsonDoc *doc);
+//! char *json_format(const char *input, size_t len, size_t indent, size_t *out_len);
+//! void json_string_free(char *s);
+//! ```
+//!
+//! Input is `len` bytes of UTF-8, with no terminating NUL needed; pointers
+//! are NUL-terminated. Every string handed out is the caller's to release
+//! with `json_string_free`, and every document with `json_free`. A failure
+//! returns `NULL` and records an error: on the document for
+//! `json_get_pointer`, and for the calling thread otherwise, read with
+//! `json_last_error(NULL)`. The message stays valid until the next call
+//! that records an error in the same place. No function unwinds into the
+//! caller, and `NULL` is accepted wherever a pointer is.
+//!
+//! The crate builds as an rlib alone, so that other builds do not pay for
+//! the C library. Ask for a shared or static one when building it:
+//!
+//! ```sh
+//! cargo rustc --release --lib --features ffi --crate-type cdylib
+//! cargo rustc --release --lib --features ffi --crate-type staticlib
+//! ```
+
+use super::{
+    error::invalid_utf8, offset_to_line_col, parse_json, FormatOptions, JsonError, JsonValue,
+};
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+/// A parsed document, opaque to C.
+pub struct JsonDoc {
+    value: JsonValue,
+    error: Option<Failure>,
+}
+
+/// What `json_last_error` reports.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct JsonErrorInfo {
+    pub failed: bool,
+    /// Byte offset of a parse error into the input.
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub message: *const c_char,
+}
+
+/// A recorded error, owning its message.
+struct Failure {
+    offset: usize,
+    line: usize,
+    column: usize,
+    message: CString,
+}
+
+impl Failure {
+    fn new(message: impl Into<String>) -> Self {
+        Failure {
+            offset: 0,
+            line: 0,
+            column: 0,
+            message: c_string(message.into()),
+        }
+    }
+
+    /// A failure from parsing `text`, with the position of a parse error.
+    fn parse(text: &str, err: JsonError) -> Self {
+        match err {
+            JsonError::Parse {
+                offset, message, ..
+            } => {
+                let (line, column) = offset_to_line_col(text, offset);
+                Failure {
+                    offset,
+                    line,
+                    column,
+                    message: c_string(message),
+                }
+            }
+            err => Failure::new(err.to_string()),
+        }
+    }
+
+    fn info(&self) -> JsonErrorInfo {
+        JsonErrorInfo {
+            failed: true,
+            offset: self.offset,
+            line: self.line,
+            column: self.column,
+            message: self.message.as_ptr(),
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<Failure>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(failure: Option<Failure>) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = failure);
+}
+
+/// `s` up to its first NUL, which JSON text never contains unescaped.
+fn c_string(mut s: String) -> CString {
+    if let Some(nul) = s.find('\0') {
+        s.truncate(nul);
+    }
+    CString::new(s).expect("NULs were cut off")
+}
+
+/// Run `f`, turning a panic into `fallback` and an error for the thread.
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        set_last_error(Some(Failure::new("internal error: the parser panicked")));
+        fallback
+    })
+}
+
+/// The `len` bytes at `input` as text.
+///
+/// # Safety
+///
+/// `input` must be null or valid for reads of `len` bytes.
+unsafe fn input_text<'a>(input: *const c_char, len: usize) -> Result<&'a str, Failure> {
+    if input.is_null() {
+        return Err(Failure::new("input is null"));
+    }
+    let bytes = slice::from_raw_parts(input.cast::<u8>(), len);
+    std::str::from_utf8(bytes).map_err(|e| {
+        let text = std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default();
+        Failure::parse(text, invalid_utf8(e.valid_up_to()))
+    })
+}
+
+/// Parse `len` bytes of strict JSON at `input`. Returns null on failure.
+///
+/// # Safety
+///
+/// `input` must be null or valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn json_parse(input: *const c_char, len: usize) -> *mut JsonDoc {
+    guard(ptr::null_mut(), || {
+        let parsed = input_text(input, len)
+            .and_then(|text| parse_json(text).map_err(|e| Failure::parse(text, e)));
+        match parsed {
+            Ok(value) => {
+                set_last_error(None);
+                Box::into_raw(Box::new(JsonDoc { value, error: None }))
+            }
+            Err(failure) => {
+                set_last_error(Some(failure));
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Release a document from [`json_parse`].
+///
+/// # Safety
+///
+/// `doc` must be null or a document from [`json_parse`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn json_free(doc: *mut JsonDoc) {
+    guard((), || {
+        if !doc.is_null() {
+            drop(Box::from_raw(doc));
+        }
+    })
+}
+
+/// The value at the JSON Pointer `pointer` as compact JSON, or null if
+/// there is none.
+///
+/// # Safety
+///
+/// `doc` must be null or a live document from [`json_parse`], and
+/// `pointer` null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn json_get_pointer(
+    doc: *mut JsonDoc,
+    pointer: *const c_char,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let Some(doc) = doc.as_mut() else {
+            set_last_error(Some(Failure::new("document is null")));
+            return ptr::null_mut();
+        };
+        let found = if pointer.is_null() {
+            Err(Failure::new("pointer is null"))
+        } else {
+            match CStr::from_ptr(pointer).to_str() {
+                Ok(p) => doc
+                    .value
+                    .pointer(p)
+                    .ok_or_else(|| Failure::new(format!("no value at \"{}\"", p))),
+                Err(_) => Err(Failure::new("pointer is not UTF-8")),
+            }
+        };
+        match found {
+            Ok(value) => {
+                doc.error = None;
+                c_string(value.to_json_string()).into_raw()
+            }
+            Err(failure) => {
+                doc.error = Some(failure);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// The last error recorded on `doc`, or for the calling thread when `doc`
+/// is null. `failed` is false if the last call there succeeded.
+///
+/// # Safety
+///
+/// `doc` must be null or a live document from [`json_parse`].
+#[no_mangle]
+pub unsafe extern "C" fn json_last_error(doc: *const JsonDoc) -> JsonErrorInfo {
+    let none = JsonErrorInfo {
+        failed: false,
+        offset: 0,
+        line: 0,
+        column: 0,
+        message: ptr::null(),
+    };
+    guard(none, || match doc.as_ref() {
+        Some(doc) => doc.error.as_ref().map_or(none, Failure::info),
+        None => LAST_ERROR.with(|last| last.borrow().as_ref().map_or(none, Failure::info)),
+    })
+}
+
+/// Reformat `len` bytes of JSON at `input`, pretty printed with `indent`
+/// spaces per level, or compact when it is 0. The length of the result,
+/// without its NUL, goes to `out_len` unless that is null. Returns null on
+/// failure.
+///
+/// # Safety
+///
+/// `input` must be null or valid for reads of `len` bytes, and `out_len`
+/// null or valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn json_format(
+    input: *const c_char,
+    len: usize,
+    indent: usize,
+    out_len: *mut usize,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let options = match indent {
+            0 => FormatOptions::compact(),
+            n => FormatOptions::pretty().spaces(n),
+        };
+        let formatted = input_text(input, len).and_then(|text| {
+            let value = parse_json(text).map_err(|e| Failure::parse(text, e))?;
+            value
+                .try_to_string_with(&options)
+                .map_err(|e| Failure::new(e.to_string()))
+        });
+        match formatted {
+            Ok(text) => {
+                set_last_error(None);
+                if let Some(out_len) = out_len.as_mut() {
+                    *out_len = text.len();
+                }
+                c_string(text).into_raw()
+            }
+            Err(failure) => {
+                set_last_error(Some(failure));
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Release a string from [`json_get_pointer`] or [`json_format`].
+///
+/// # Safety
+///
+/// `s` must be null or a string from this module not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn json_string_free(s: *mut c_char) {
+    guard((), || {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Take ownership of a string the API returned.
+    unsafe fn take(s: *mut c_char) -> String {
+        assert!(!s.is_null());
+        let text = CStr::from_ptr(s).to_str().unwrap().to_string();
+        json_string_free(s);
+        text
+    }
+
+    unsafe fn message(info: JsonErrorInfo) -> String {
+        assert!(info.failed);
+        CStr::from_ptr(info.message).to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn documents_should_answer_pointers_until_freed() {
+        let input = r#"{"a": [1, {"b": "x\u0000y"}], "c": null}"#;
+        unsafe {
+            let doc = json_parse(input.as_ptr().cast(), input.len());
+            assert!(!doc.is_null());
+            assert!(!json_last_error(ptr::null()).failed);
+
+            let found = json_get_pointer(doc, c"/a/1".as_ptr());
+            assert_eq!(take(found), r#"{"b":"x\u0000y"}"#);
+            assert_eq!(take(json_get_pointer(doc, c"".as_ptr())).len(), 35);
+            assert!(!json_last_error(doc).failed);
+
+            assert!(json_get_pointer(doc, c"/a/9".as_ptr()).is_null());
+            assert_eq!(message(json_last_error(doc)), "no value at \"/a/9\"");
+            assert!(json_get_pointer(doc, ptr::null()).is_null());
+            assert_eq!(message(json_last_error(doc)), "pointer is null");
+            // the thread's error is separate from the document's
+            assert!(!json_last_error(ptr::null()).failed);
+            assert_eq!(take(json_get_pointer(doc, c"/c".as_ptr())), "null");
+            assert!(!json_last_error(doc).failed);
+
+            json_free(doc);
+            json_free(ptr::null_mut());
+            json_string_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn failures_should_report_where_and_why() {
+        unsafe {
+            let input = "{\"a\": 1,\n  \"b\": tru}";
+            assert!(json_parse(input.as_ptr().cast(), input.len()).is_null());
+            let info = json_last_error(ptr::null());
+            assert_eq!((info.offset, info.line, info.column), (16, 2, 8));
+            assert_eq!(message(info), "unexpected character 't'");
+
+            let bytes = b"[\"\xff\"]";
+            assert!(json_parse(bytes.as_ptr().cast(), bytes.len()).is_null());
+            let info = json_last_error(ptr::null());
+            assert_eq!((info.offset, info.line, info.column), (2, 1, 3));
+            assert_eq!(message(info), "invalid UTF-8");
+
+            assert!(json_parse(ptr::null(), 4).is_null());
+            assert_eq!(message(json_last_error(ptr::null())), "input is null");
+            assert!(json_get_pointer(ptr::null_mut(), c"".as_ptr()).is_null());
+            assert_eq!(message(json_last_error(ptr::null())), "document is null");
+
+            let mut len = 99;
+            assert!(json_format(b"[1,".as_ptr().cast(), 3, 2, &mut len).is_null());
+            assert_eq!(len, 99);
+            assert!(json_last_error(ptr::null()).failed);
+        }
+    }
+
+    #[test]
+    fn json_format_should_reindent_or_compact() {
+        let input = r#"{"a": [1, 2]}"#;
+        unsafe {
+            let mut len = 0;
+            let out = json_format(input.as_ptr().cast(), input.len(), 4, &mut len);
+            let pretty = take(out);
+            assert_eq!(pretty, "{\n    \"a\": [\n        1,\n        2\n    ]\n}");
+            assert_eq!(len, pretty.len());
+            assert!(!json_last_error(ptr::null()).failed);
+
+            let out = json_format(input.as_ptr().cast(), input.len(), 0, ptr::null_mut());
+            assert_eq!(take(out), r#"{"a":[1,2]}"#);
+        }
+    }
+}
@@ -0,0 +1,413 @@
+use super::{error::escape_pointer_token, json_string, JsonError, JsonObject, JsonValue};
+use std::collections::{BTreeMap, HashMap};
+
+/// How [`flatten`] names leaves and [`unflatten`] reads the names back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlattenOptions {
+    /// Put between the keys of a path; `.` by default.
+    pub separator: String,
+    /// Name array elements `tags[0]` rather than `tags.0`, so
+    /// [`unflatten`] can tell them from object members named by digits.
+    pub bracket_arrays: bool,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            separator: ".".to_string(),
+            bracket_arrays: false,
+        }
+    }
+}
+
+impl FlattenOptions {
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    pub fn bracket_arrays(mut self, yes: bool) -> Self {
+        self.bracket_arrays = yes;
+        self
+    }
+}
+
+/// One object with a member for each scalar in `value`, named by the path
+/// to it: `{"a": {"b": [1, 2]}}` becomes `{"a.b.0": 1, "a.b.1": 2}`. Empty
+/// objects and arrays stay as values, so [`unflatten`] gets them back, and
+/// a scalar document becomes the member `""`, as `{"": 5}` does. Keys that hold the separator
+/// or brackets come back split, and without `bracket_arrays` an object
+/// whose keys are `0`, `1` and so on comes back as an array.
+pub fn flatten(value: &JsonValue, options: &FlattenOptions) -> JsonValue {
+    let mut flat = JsonObject::default();
+    flatten_into(&mut flat, &mut String::new(), true, value, options);
+    JsonValue::Object(flat)
+}
+
+/// Add the leaves of `value` to `flat`, `path` naming `value` unless it is
+/// the document itself.
+fn flatten_into(
+    flat: &mut JsonObject,
+    path: &mut String,
+    root: bool,
+    value: &JsonValue,
+    options: &FlattenOptions,
+) {
+    let len = path.len();
+    match value {
+        JsonValue::Object(obj) if !obj.is_empty() => {
+            for (k, v) in obj.iter() {
+                if !root {
+                    path.push_str(&options.separator);
+                }
+                path.push_str(k);
+                flatten_into(flat, path, false, v, options);
+                path.truncate(len);
+            }
+        }
+        JsonValue::Array(arr) if !arr.is_empty() => {
+            for (i, v) in arr.iter().enumerate() {
+                if options.bracket_arrays {
+                    path.push_str(&format!("[{}]", i));
+                } else {
+                    if !root {
+                        path.push_str(&options.separator);
+                    }
+                    path.push_str(&i.to_string());
+                }
+                flatten_into(flat, path, false, v, options);
+                path.truncate(len);
+            }
+        }
+        leaf => {
+            flat.insert(json_string(path.as_str()), leaf.clone());
+        }
+    }
+}
+
+/// The document [`flatten`] made `value` from. `value` must be an object;
+/// its members go back in where their names say. A name that runs through
+/// another's value, or asks one place to be both an array and an object,
+/// is a [`JsonError::Path`] at the place in question, as is an array left
+/// with a gap between its indices.
+pub fn unflatten(value: &JsonValue, options: &FlattenOptions) -> Result<JsonValue, JsonError> {
+    let JsonValue::Object(flat) = value else {
+        return Err(conflict("", "a flattened document is an object"));
+    };
+    // sorted so the conflict reported is the same whatever the map order
+    let mut members: Vec<_> = flat.iter().collect();
+    members.sort_unstable_by_key(|(k, _)| *k);
+    let mut root = Node::Empty;
+    for (key, leaf) in members {
+        let steps = steps(key, options);
+        root.insert(&steps, leaf.clone(), key, &mut String::new())?;
+    }
+    root.into_value(options, &mut String::new())
+}
+
+/// Where a flattened name leads, one container at a time.
+#[derive(Debug, PartialEq)]
+enum Step<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+fn steps<'a>(key: &'a str, options: &FlattenOptions) -> Vec<Step<'a>> {
+    if key.is_empty() {
+        return Vec::new();
+    }
+    let mut steps = Vec::new();
+    let parts: Vec<&str> = if options.separator.is_empty() {
+        vec![key]
+    } else {
+        key.split(options.separator.as_str()).collect()
+    };
+    for part in parts {
+        match options.bracket_arrays.then(|| indices(part)).flatten() {
+            Some((name, found)) => {
+                // a name before the brackets, unless they start the document
+                if !name.is_empty() || !steps.is_empty() {
+                    steps.push(Step::Key(name));
+                }
+                steps.extend(found.into_iter().map(Step::Index));
+            }
+            None => steps.push(Step::Key(part)),
+        }
+    }
+    steps
+}
+
+/// `name[1][2]` as `name` and its indices, or `None` if `part` does not
+/// end in indices like that.
+fn indices(part: &str) -> Option<(&str, Vec<usize>)> {
+    let start = part.find('[')?;
+    let (name, mut rest) = part.split_at(start);
+    let mut found = Vec::new();
+    while let Some(inner) = rest.strip_prefix('[') {
+        let end = inner.find(']')?;
+        let digits = &inner[..end];
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        found.push(digits.parse().ok()?);
+        rest = &inner[end + 1..];
+    }
+    rest.is_empty().then_some((name, found))
+}
+
+/// The document being rebuilt: places made by a longer name are containers,
+/// places a name ends at are leaves.
+enum Node {
+    Empty,
+    Leaf(JsonValue),
+    Members(Vec<(String, Node)>, HashMap<String, usize>),
+    Elements(BTreeMap<usize, Node>),
+}
+
+impl Node {
+    /// Put `leaf` at the end of `steps`, `key` being the flattened name it
+    /// came from and `at` the pointer of this node.
+    fn insert(
+        &mut self,
+        steps: &[Step<'_>],
+        leaf: JsonValue,
+        key: &str,
+        at: &mut String,
+    ) -> Result<(), JsonError> {
+        let Some((step, rest)) = steps.split_first() else {
+            return match self {
+                Node::Empty => {
+                    *self = Node::Leaf(leaf);
+                    Ok(())
+                }
+                _ => Err(conflict(
+                    at,
+                    format!("{:?} names a place that holds more", key),
+                )),
+            };
+        };
+        if let Node::Empty = self {
+            *self = match step {
+                Step::Key(_) => Node::Members(Vec::new(), HashMap::new()),
+                Step::Index(_) => Node::Elements(BTreeMap::new()),
+            };
+        }
+        let len = at.len();
+        let child = match (self, step) {
+            (Node::Members(members, index), Step::Key(name)) => {
+                at.push('/');
+                at.push_str(&escape_pointer_token(name));
+                let i = *index.entry(name.to_string()).or_insert_with(|| {
+                    members.push((name.to_string(), Node::Empty));
+                    members.len() - 1
+                });
+                &mut members[i].1
+            }
+            (Node::Elements(elements), Step::Index(i)) => {
+                at.push('/');
+                at.push_str(&i.to_string());
+                elements.entry(*i).or_insert(Node::Empty)
+            }
+            (Node::Leaf(_), _) => {
+                return Err(conflict(at, format!("{:?} runs through a value", key)))
+            }
+            (Node::Members(..), Step::Index(_)) => {
+                return Err(conflict(at, format!("{:?} indexes an object", key)))
+            }
+            (Node::Elements(_), Step::Key(_)) => {
+                return Err(conflict(
+                    at,
+                    format!("{:?} names a member of an array", key),
+                ))
+            }
+            (Node::Empty, _) => unreachable!("filled in above"),
+        };
+        child.insert(rest, leaf, key, at)?;
+        at.truncate(len);
+        Ok(())
+    }
+
+    fn into_value(self, options: &FlattenOptions, at: &mut String) -> Result<JsonValue, JsonError> {
+        let len = at.len();
+        match self {
+            Node::Empty => Ok(JsonValue::Object(JsonObject::default())),
+            Node::Leaf(value) => Ok(value),
+            Node::Members(members, _) => {
+                // digits only, from 0 up: an array flattened without brackets
+                let dense = !options.bracket_arrays
+                    && members
+                        .iter()
+                        .all(|(k, _)| k.parse::<usize>().is_ok_and(|i| i.to_string() == *k))
+                    && (0..members.len()).all(|i| members.iter().any(|(k, _)| *k == i.to_string()));
+                if dense {
+                    let mut members = members;
+                    members.sort_by_key(|(k, _)| k.parse::<usize>().expect("checked above"));
+                    let elements = members.into_iter().map(|(k, node)| {
+                        at.truncate(len);
+                        at.push('/');
+                        at.push_str(&k);
+                        node.into_value(options, at)
+                    });
+                    return elements.collect::<Result<_, _>>().map(JsonValue::Array);
+                }
+                let mut obj = JsonObject::default();
+                for (k, node) in members {
+                    at.truncate(len);
+                    at.push('/');
+                    at.push_str(&escape_pointer_token(&k));
+                    obj.insert(json_string(k), node.into_value(options, at)?);
+                }
+                Ok(JsonValue::Object(obj))
+            }
+            Node::Elements(elements) => {
+                let mut arr = Vec::with_capacity(elements.len());
+                for (i, node) in elements {
+                    at.truncate(len);
+                    if i != arr.len() {
+                        return Err(conflict(
+                            at,
+                            format!("an array has index {} but no index {}", i, arr.len()),
+                        ));
+                    }
+                    at.push('/');
+                    at.push_str(&i.to_string());
+                    arr.push(node.into_value(options, at)?);
+                }
+                Ok(JsonValue::Array(arr))
+            }
+        }
+    }
+}
+
+fn conflict(at: &str, message: impl Into<String>) -> JsonError {
+    JsonError::Path {
+        pointer: at.to_string(),
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const NESTED: &str = r#"{"db": {"host": "localhost", "ports": [5432, 5433]},
+        "features": [{"name": "a", "on": true}, {"name": "b", "on": null}],
+        "empty": {}, "none": [], "matrix": [[1, 2], [3]], "": {"x": 1}}"#;
+
+    fn flat(input: &str, options: &FlattenOptions) -> JsonValue {
+        flatten(&parse_json(input).unwrap(), options)
+    }
+
+    #[test]
+    fn flatten_should_name_leaves_by_their_path() {
+        let expected = parse_json(
+            r#"{"db.host": "localhost", "db.ports.0": 5432, "db.ports.1": 5433,
+            "features.0.name": "a", "features.0.on": true, "features.1.name": "b",
+            "features.1.on": null, "empty": {}, "none": [], "matrix.0.0": 1,
+            "matrix.0.1": 2, "matrix.1.0": 3, ".x": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(flat(NESTED, &FlattenOptions::default()), expected);
+        let brackets = FlattenOptions::default()
+            .separator("__")
+            .bracket_arrays(true);
+        let expected = parse_json(
+            r#"{"db__host": "localhost", "db__ports[0]": 5432, "db__ports[1]": 5433,
+            "features[0]__name": "a", "features[0]__on": true, "features[1]__name": "b",
+            "features[1]__on": null, "empty": {}, "none": [], "matrix[0][0]": 1,
+            "matrix[0][1]": 2, "matrix[1][0]": 3, "__x": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(flat(NESTED, &brackets), expected);
+        assert_eq!(flat("5", &brackets), parse_json(r#"{"": 5}"#).unwrap());
+        assert_eq!(
+            flat("[[true]]", &brackets),
+            parse_json(r#"{"[0][0]": true}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn unflatten_should_undo_flatten() {
+        let original = parse_json(NESTED).unwrap();
+        for options in [
+            FlattenOptions::default(),
+            FlattenOptions::default().separator("__"),
+            FlattenOptions::default().bracket_arrays(true),
+            FlattenOptions::default()
+                .separator("/")
+                .bracket_arrays(true),
+        ] {
+            let flat = flatten(&original, &options);
+            assert_eq!(
+                unflatten(&flat, &options).unwrap(),
+                original,
+                "{:?}",
+                options
+            );
+        }
+        for doc in ["5", "[]", "{}", "[[1], {}]", r#"{"a": [{"b": []}]}"#] {
+            let value = parse_json(doc).unwrap();
+            let options = FlattenOptions::default().bracket_arrays(true);
+            assert_eq!(
+                unflatten(&flatten(&value, &options), &options).unwrap(),
+                value,
+                "{}",
+                doc
+            );
+        }
+        // without brackets, digit keys from 0 up read as an array
+        let digits = parse_json(r#"{"a.0": 1, "a.1": 2, "b.1": 3, "c.00": 4}"#).unwrap();
+        assert_eq!(
+            unflatten(&digits, &FlattenOptions::default()).unwrap(),
+            parse_json(r#"{"a": [1, 2], "b": {"1": 3}, "c": {"00": 4}}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn unflatten_conflicts_should_name_the_place() {
+        let error = |input: &str, options: &FlattenOptions| match unflatten(
+            &parse_json(input).unwrap(),
+            options,
+        )
+        .unwrap_err()
+        {
+            JsonError::Path { pointer, message } => (pointer, message),
+            e => panic!("{:?}", e),
+        };
+        let options = FlattenOptions::default();
+        assert_eq!(
+            error(r#"{"a.b": 1, "a.b.c": 2}"#, &options),
+            (
+                "/a/b".to_string(),
+                r#""a.b.c" runs through a value"#.to_string()
+            )
+        );
+        assert_eq!(error(r#"{"a": 1, "a.b": 2}"#, &options).0, "/a");
+        let brackets = FlattenOptions::default().bracket_arrays(true);
+        assert_eq!(
+            error(r#"{"a[0]": 1, "a.b": 2}"#, &brackets),
+            ("/a".to_string(), r#""a[0]" indexes an object"#.to_string())
+        );
+        assert_eq!(
+            error(r#"{"a[0]": 1, "a[1].b": 2, "a[1][0]": 3}"#, &brackets),
+            (
+                "/a/1".to_string(),
+                r#""a[1][0]" indexes an object"#.to_string()
+            )
+        );
+        assert_eq!(
+            error(r#"{"a[0]": 1, "a[2]": 2}"#, &brackets),
+            (
+                "/a".to_string(),
+                "an array has index 2 but no index 1".to_string()
+            )
+        );
+        assert_eq!(error("[1]", &options).0, "");
+        let e = unflatten(&parse_json(r#"{"x.y": 1, "x": 2}"#).unwrap(), &options).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            r#"cannot make a path through "/x": "x.y" runs through a value"#
+        );
+    }
+}
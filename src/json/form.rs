@@ -0,0 +1,330 @@
+use super::{csv::typed, json_string, JsonError, JsonObject, JsonValue};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormOptions {
+    /// Write arrays of scalars as `key[]=a&key[]=b` instead of repeating the
+    /// bare key. Only the bracket form keeps a one-element array an array.
+    pub array_brackets: bool,
+    /// Decode values that spell a number or `true`/`false` as such instead
+    /// of leaving every value a string.
+    pub parse_scalars: bool,
+}
+
+/// Encode an object as `application/x-www-form-urlencoded`. See
+/// [`to_form_urlencoded_with`].
+pub fn to_form_urlencoded(value: &JsonValue) -> Result<String, JsonError> {
+    to_form_urlencoded_with(value, &FormOptions::default())
+}
+
+/// Nested objects use bracket paths (`a[b]=1`), arrays of scalars repeat
+/// their key, and arrays holding containers index their elements
+/// (`a[0][b]=1`). Keys are sorted; null becomes an empty value and empty
+/// containers are left out. Keys and values are percent-encoded as UTF-8,
+/// with spaces as `+`. The value must be an object.
+pub fn to_form_urlencoded_with(
+    value: &JsonValue,
+    options: &FormOptions,
+) -> Result<String, JsonError> {
+    let JsonValue::Object(obj) = value else {
+        return Err(JsonError::serialize("form data needs an object"));
+    };
+    let mut pairs = Vec::new();
+    let mut members: Vec<_> = obj.iter().collect();
+    members.sort_unstable_by_key(|(k, _)| *k);
+    for (k, v) in members {
+        encode_into(&mut pairs, k.to_string(), v, options);
+    }
+    Ok(pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+        .collect::<Vec<_>>()
+        .join("&"))
+}
+
+fn encode_into(
+    pairs: &mut Vec<(String, String)>,
+    key: String,
+    value: &JsonValue,
+    options: &FormOptions,
+) {
+    match value {
+        JsonValue::Object(obj) => {
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_unstable_by_key(|(k, _)| *k);
+            for (k, v) in members {
+                encode_into(pairs, format!("{}[{}]", key, k), v, options);
+            }
+        }
+        JsonValue::Array(arr) if arr.iter().any(is_container) => {
+            for (i, v) in arr.iter().enumerate() {
+                encode_into(pairs, format!("{}[{}]", key, i), v, options);
+            }
+        }
+        JsonValue::Array(arr) => {
+            let key = if options.array_brackets {
+                format!("{}[]", key)
+            } else {
+                key
+            };
+            for v in arr {
+                encode_into(pairs, key.clone(), v, options);
+            }
+        }
+        JsonValue::Null => pairs.push((key, String::new())),
+        JsonValue::String(s) => pairs.push((key, s.to_string())),
+        JsonValue::Raw(raw) => match raw.parse() {
+            Ok(v) => encode_into(pairs, key, &v, options),
+            Err(_) => pairs.push((key, raw.get().to_string())),
+        },
+        scalar => pairs.push((key, scalar.to_json_string())),
+    }
+}
+
+fn is_container(value: &JsonValue) -> bool {
+    matches!(value, JsonValue::Array(_) | JsonValue::Object(_))
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                out.push(char::from(b))
+            }
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Decode `+` and `%XX` escapes. Malformed escapes are kept as they are and
+/// invalid UTF-8 is replaced.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match s
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                Some(b) => {
+                    out.push(b);
+                    i += 2;
+                }
+                None => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Decode form data into an object. See [`from_form_urlencoded_with`].
+pub fn from_form_urlencoded(input: &str) -> JsonValue {
+    from_form_urlencoded_with(input, &FormOptions::default())
+}
+
+/// Repeated keys and `key[]` collect into arrays, and bracket paths build
+/// nested objects. Objects whose keys are exactly the indices `0..n` become
+/// arrays, so the output of [`to_form_urlencoded`] reads back. A later pair
+/// that conflicts with the shape built so far replaces it. `array_brackets`
+/// has no effect here.
+pub fn from_form_urlencoded_with(input: &str, options: &FormOptions) -> JsonValue {
+    let mut root = Node::Map(Vec::new());
+    for pair in input.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let key = percent_decode(key);
+        let segments = key_path(&key);
+        root.insert(&segments, percent_decode(value));
+    }
+    root.into_value(options)
+}
+
+/// Split `a[b][]` into `["a", "b", ""]`. A key whose brackets do not close
+/// is taken literally.
+fn key_path(key: &str) -> Vec<&str> {
+    let Some(open) = key.find('[').filter(|i| *i > 0) else {
+        return vec![key];
+    };
+    let mut segments = vec![&key[..open]];
+    let mut rest = &key[open..];
+    while let Some(inner) = rest.strip_prefix('[') {
+        let Some(close) = inner.find(']') else {
+            return vec![key];
+        };
+        segments.push(&inner[..close]);
+        rest = &inner[close + 1..];
+    }
+    if !rest.is_empty() {
+        return vec![key];
+    }
+    segments
+}
+
+enum Node {
+    Values(Vec<String>),
+    Map(Vec<(String, Node)>),
+    List(Vec<Node>),
+}
+
+impl Node {
+    fn insert(&mut self, segments: &[&str], value: String) {
+        let Some((first, rest)) = segments.split_first() else {
+            match self {
+                Node::Values(values) => values.push(value),
+                node => *node = Node::Values(vec![value]),
+            }
+            return;
+        };
+        let mut child = Node::Values(Vec::new());
+        if first.is_empty() {
+            if let Node::Values(values) = self {
+                let list = values.drain(..).map(|v| Node::Values(vec![v])).collect();
+                *self = Node::List(list);
+            }
+            let Node::List(list) = self else {
+                *self = Node::List(Vec::new());
+                return self.insert(segments, value);
+            };
+            child.insert(rest, value);
+            list.push(child);
+            return;
+        }
+        if !matches!(self, Node::Map(_)) {
+            *self = Node::Map(Vec::new());
+        }
+        let Node::Map(members) = self else {
+            unreachable!()
+        };
+        match members.iter_mut().find(|(k, _)| k == first) {
+            Some((_, node)) => node.insert(rest, value),
+            None => {
+                child.insert(rest, value);
+                members.push((first.to_string(), child));
+            }
+        }
+    }
+
+    fn into_value(self, options: &FormOptions) -> JsonValue {
+        let scalar = |v: String| {
+            if options.parse_scalars {
+                typed(v)
+            } else {
+                JsonValue::String(json_string(v))
+            }
+        };
+        match self {
+            Node::Values(mut values) if values.len() == 1 => scalar(values.remove(0)),
+            Node::Values(values) => JsonValue::Array(values.into_iter().map(scalar).collect()),
+            Node::List(list) => {
+                JsonValue::Array(list.into_iter().map(|n| n.into_value(options)).collect())
+            }
+            Node::Map(mut members) => {
+                let indices: Option<Vec<usize>> = members
+                    .iter()
+                    .map(|(k, _)| k.parse().ok().filter(|i: &usize| i.to_string() == *k))
+                    .collect();
+                if let Some(mut indices) = indices.filter(|i| !i.is_empty()) {
+                    indices.sort_unstable();
+                    if indices.iter().enumerate().all(|(i, n)| i == *n) {
+                        members.sort_unstable_by_key(|(k, _)| k.parse::<usize>().unwrap());
+                        return JsonValue::Array(
+                            members
+                                .into_iter()
+                                .map(|(_, n)| n.into_value(options))
+                                .collect(),
+                        );
+                    }
+                }
+                JsonValue::Object(
+                    members
+                        .into_iter()
+                        .map(|(k, n)| (json_string(k), n.into_value(options)))
+                        .collect::<JsonObject>(),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const SCALARS: FormOptions = FormOptions {
+        array_brackets: false,
+        parse_scalars: true,
+    };
+
+    #[test]
+    fn form_should_round_trip_flat_objects() {
+        let v = parse_json(r#"{"name": "John Doe", "age": "30", "ok": "true"}"#).unwrap();
+        let form = to_form_urlencoded(&v).unwrap();
+        assert_eq!(form, "age=30&name=John+Doe&ok=true");
+        assert_eq!(from_form_urlencoded(&form), v);
+        assert_eq!(
+            from_form_urlencoded_with(&form, &SCALARS),
+            parse_json(r#"{"name": "John Doe", "age": 30, "ok": true}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn form_should_round_trip_nested_values() {
+        let v = parse_json(
+            r#"{"user": {"name": "a", "address": {"zip": "010001"}}, "items": [{"id": 1}, {"id": 2, "tags": ["x", "y"]}]}"#,
+        )
+        .unwrap();
+        let form = to_form_urlencoded(&v).unwrap();
+        assert_eq!(
+            form,
+            "items%5B0%5D%5Bid%5D=1&items%5B1%5D%5Bid%5D=2&items%5B1%5D%5Btags%5D=x&items%5B1%5D%5Btags%5D=y&user%5Baddress%5D%5Bzip%5D=010001&user%5Bname%5D=a"
+        );
+        assert_eq!(from_form_urlencoded_with(&form, &SCALARS), v);
+        assert_eq!(
+            from_form_urlencoded("a[b][c]=1&a[d]=2"),
+            parse_json(r#"{"a": {"b": {"c": "1"}, "d": "2"}}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn form_should_percent_encode_reserved_and_utf8() {
+        let v = parse_json(r#"{"q": "a&b=c+d %/?#", "city": "Zürich 😀", "k=&": "~"}"#).unwrap();
+        let form = to_form_urlencoded(&v).unwrap();
+        assert_eq!(
+            form,
+            "city=Z%C3%BCrich+%F0%9F%98%80&k%3D%26=%7E&q=a%26b%3Dc%2Bd+%25%2F%3F%23"
+        );
+        assert_eq!(from_form_urlencoded(&form), v);
+        assert_eq!(
+            from_form_urlencoded("a=%zz%4&b=%e2%82%ac&c"),
+            parse_json(r#"{"a": "%zz%4", "b": "€", "c": ""}"#).unwrap()
+        );
+        assert!(to_form_urlencoded(&JsonValue::from(1)).is_err());
+    }
+
+    #[test]
+    fn form_should_group_repeated_keys_into_arrays() {
+        let v = parse_json(r#"{"tag": ["a", "b"], "one": ["x"], "n": null, "e": []}"#).unwrap();
+        assert_eq!(to_form_urlencoded(&v).unwrap(), "n=&one=x&tag=a&tag=b");
+        let brackets = FormOptions {
+            array_brackets: true,
+            ..FormOptions::default()
+        };
+        let form = to_form_urlencoded_with(&v, &brackets).unwrap();
+        assert_eq!(form, "n=&one%5B%5D=x&tag%5B%5D=a&tag%5B%5D=b");
+        assert_eq!(
+            from_form_urlencoded(&form),
+            parse_json(r#"{"tag": ["a", "b"], "one": ["x"], "n": ""}"#).unwrap()
+        );
+        assert_eq!(
+            from_form_urlencoded("a=1&a=2&b[]=3&c[x]=4&c=5&d[=6"),
+            parse_json(r#"{"a": ["1", "2"], "b": ["3"], "c": "5", "d[": "6"}"#).unwrap()
+        );
+    }
+}
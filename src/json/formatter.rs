@@ -0,0 +1,754 @@
+use std::io;
+
+/// An escape sequence the serializer wants written inside a string literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharEscape {
+    Quote,
+    ReverseSolidus,
+    /// `\/`, only under [`EscapeOptions::escape_solidus`](super::EscapeOptions::escape_solidus).
+    Solidus,
+    Backspace,
+    FormFeed,
+    LineFeed,
+    CarriageReturn,
+    Tab,
+    /// A `\uXXXX` escape of one UTF-16 code unit.
+    Unicode(u16),
+}
+
+/// Controls every byte the serializer writes. The serializer walks the value
+/// and calls these hooks in document order; the default methods produce
+/// compact JSON, so an implementation only overrides what it wants to change.
+pub trait Formatter {
+    fn write_null<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"null")
+    }
+
+    fn write_bool<W: io::Write + ?Sized>(&mut self, w: &mut W, value: bool) -> io::Result<()> {
+        w.write_all(if value { b"true" } else { b"false" })
+    }
+
+    fn write_i64<W: io::Write + ?Sized>(&mut self, w: &mut W, value: i64) -> io::Result<()> {
+        write!(w, "{}", value)
+    }
+
+    /// Floats use the shortest digits that parse back to the identical f64.
+    /// Integral floats keep a fractional part (`1.0`) so the Int/Float
+    /// distinction survives a round trip; very large or small magnitudes
+    /// switch to exponent notation (`1e300`, `5e-324`).
+    fn write_f64<W: io::Write + ?Sized>(&mut self, w: &mut W, value: f64) -> io::Result<()> {
+        // Debug is the shortest round-trip form and never drops the `.0`
+        write!(w, "{:?}", value)
+    }
+
+    /// A [`Num::Raw`](super::Num::Raw) token, written as is.
+    fn write_raw_number<W: io::Write + ?Sized>(&mut self, w: &mut W, raw: &str) -> io::Result<()> {
+        w.write_all(raw.as_bytes())
+    }
+
+    /// The text of a [`RawValue`](super::RawValue), written as is.
+    fn write_raw_value<W: io::Write + ?Sized>(&mut self, w: &mut W, raw: &str) -> io::Result<()> {
+        w.write_all(raw.as_bytes())
+    }
+
+    /// NaN and the infinities, only called under
+    /// [`NonFiniteBehavior::Literal`](super::NonFiniteBehavior::Literal).
+    fn write_non_finite<W: io::Write + ?Sized>(&mut self, w: &mut W, value: f64) -> io::Result<()> {
+        let s: &[u8] = if value.is_nan() {
+            b"NaN"
+        } else if value > 0.0 {
+            b"Infinity"
+        } else {
+            b"-Infinity"
+        };
+        w.write_all(s)
+    }
+
+    fn begin_string<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"\"")
+    }
+
+    fn end_string<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"\"")
+    }
+
+    /// A run of string content that needs no escaping.
+    fn write_string_fragment<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        fragment: &str,
+    ) -> io::Result<()> {
+        w.write_all(fragment.as_bytes())
+    }
+
+    fn write_char_escape<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        escape: CharEscape,
+    ) -> io::Result<()> {
+        let s: &[u8] = match escape {
+            CharEscape::Quote => b"\\\"",
+            CharEscape::ReverseSolidus => b"\\\\",
+            CharEscape::Solidus => b"\\/",
+            CharEscape::Backspace => b"\\b",
+            CharEscape::FormFeed => b"\\f",
+            CharEscape::LineFeed => b"\\n",
+            CharEscape::CarriageReturn => b"\\r",
+            CharEscape::Tab => b"\\t",
+            CharEscape::Unicode(unit) => return write!(w, "\\u{:04x}", unit),
+        };
+        w.write_all(s)
+    }
+
+    fn begin_array<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"[")
+    }
+
+    fn end_array<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"]")
+    }
+
+    fn begin_array_value<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if first {
+            Ok(())
+        } else {
+            w.write_all(b",")
+        }
+    }
+
+    fn end_array_value<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn begin_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"{")
+    }
+
+    fn end_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"}")
+    }
+
+    fn begin_object_key<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if first {
+            Ok(())
+        } else {
+            w.write_all(b",")
+        }
+    }
+
+    fn end_object_key<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn begin_object_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b":")
+    }
+
+    fn end_object_value<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes JSON without any insignificant whitespace.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompactFormatter;
+
+impl Formatter for CompactFormatter {}
+
+/// Writes every array element and object member on its own line.
+#[derive(Debug, Clone)]
+pub struct PrettyFormatter<'a> {
+    indent: &'a [u8],
+    newline: &'a [u8],
+    level: usize,
+    has_value: bool,
+    diff_friendly: bool,
+    // SGR parameters wrapped around brackets, commas and colons
+    punctuation: &'a str,
+}
+
+impl<'a> PrettyFormatter<'a> {
+    pub fn with_indent(indent: &'a [u8]) -> Self {
+        Self {
+            indent,
+            newline: b"\n",
+            level: 0,
+            has_value: false,
+            diff_friendly: false,
+            punctuation: "",
+        }
+    }
+
+    /// Use `newline` (e.g. `b"\r\n"`) to break lines instead of `\n`.
+    pub fn with_newline(mut self, newline: &'a [u8]) -> Self {
+        self.newline = newline;
+        self
+    }
+
+    /// Lay out for line diffs, see [`FormatOptions::diff_friendly`]: commas
+    /// lead the entries after the first, at the indent of the container,
+    /// and empty containers are broken across lines too.
+    ///
+    /// [`FormatOptions::diff_friendly`]: super::FormatOptions::diff_friendly
+    pub fn with_diff_layout(mut self, yes: bool) -> Self {
+        self.diff_friendly = yes;
+        self
+    }
+
+    /// Start `level` containers deep, for a value written inside containers
+    /// something else has opened.
+    pub(super) fn at_level(mut self, level: usize) -> Self {
+        self.level = level;
+        self
+    }
+
+    fn write_indent<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        for _ in 0..self.level {
+            w.write_all(self.indent)?;
+        }
+        Ok(())
+    }
+
+    fn write_punctuation<W: io::Write + ?Sized>(&self, w: &mut W, s: &[u8]) -> io::Result<()> {
+        paint(w, self.punctuation, s)
+    }
+
+    fn begin_container<W: io::Write + ?Sized>(&mut self, w: &mut W, open: &[u8]) -> io::Result<()> {
+        self.level += 1;
+        self.has_value = false;
+        self.write_punctuation(w, open)
+    }
+
+    fn end_container<W: io::Write + ?Sized>(&mut self, w: &mut W, close: &[u8]) -> io::Result<()> {
+        self.level -= 1;
+        // empty containers stay on one line, except in the diff layout
+        if self.has_value || self.diff_friendly {
+            w.write_all(self.newline)?;
+            self.write_indent(w)?;
+        }
+        self.has_value = true;
+        self.write_punctuation(w, close)
+    }
+
+    fn begin_entry<W: io::Write + ?Sized>(&mut self, w: &mut W, first: bool) -> io::Result<()> {
+        if self.diff_friendly {
+            w.write_all(self.newline)?;
+            return self.write_leading_comma(w, first);
+        }
+        if !first {
+            self.write_punctuation(w, b",")?;
+        }
+        w.write_all(self.newline)?;
+        self.write_indent(w)
+    }
+
+    /// The indent of an entry with its comma, if any, in place of the first
+    /// space of its last level, so `, ` lines up with the entries above.
+    fn write_leading_comma<W: io::Write + ?Sized>(&self, w: &mut W, first: bool) -> io::Result<()> {
+        for _ in 1..self.level {
+            w.write_all(self.indent)?;
+        }
+        if first {
+            return w.write_all(self.indent);
+        }
+        self.write_punctuation(w, b",")?;
+        match self.indent.strip_prefix(b" ") {
+            Some(rest) => w.write_all(rest),
+            None => w.write_all(self.indent),
+        }
+    }
+}
+
+impl Default for PrettyFormatter<'_> {
+    fn default() -> Self {
+        Self::with_indent(b"  ")
+    }
+}
+
+impl Formatter for PrettyFormatter<'_> {
+    fn begin_array<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.begin_container(w, b"[")
+    }
+
+    fn end_array<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.end_container(w, b"]")
+    }
+
+    fn begin_array_value<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.begin_entry(w, first)
+    }
+
+    fn end_array_value<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+
+    fn begin_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.begin_container(w, b"{")
+    }
+
+    fn end_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.end_container(w, b"}")
+    }
+
+    fn begin_object_key<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.begin_entry(w, first)
+    }
+
+    fn begin_object_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.write_punctuation(w, b":")?;
+        w.write_all(b" ")
+    }
+
+    fn end_object_value<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+}
+
+/// ANSI SGR parameters (`"1;34"` is bold blue) for each kind of token. An
+/// empty string leaves that kind uncolored, so [`ColorScheme::plain`] gives
+/// output byte-identical to [`PrettyFormatter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorScheme {
+    pub key: String,
+    pub string: String,
+    pub number: String,
+    /// `true`, `false` and `null`.
+    pub literal: String,
+    /// Brackets, braces, commas and colons.
+    pub punctuation: String,
+}
+
+impl ColorScheme {
+    pub fn plain() -> Self {
+        Self {
+            key: String::new(),
+            string: String::new(),
+            number: String::new(),
+            literal: String::new(),
+            punctuation: String::new(),
+        }
+    }
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        Self {
+            key: "1;34".to_string(),
+            string: "32".to_string(),
+            number: "36".to_string(),
+            literal: "35".to_string(),
+            punctuation: "90".to_string(),
+        }
+    }
+}
+
+/// Write `token` wrapped in an SGR sequence and a reset, or bare when
+/// `color` is empty.
+fn paint<W: io::Write + ?Sized>(w: &mut W, color: &str, token: &[u8]) -> io::Result<()> {
+    if color.is_empty() {
+        return w.write_all(token);
+    }
+    write!(w, "\x1b[{}m", color)?;
+    w.write_all(token)?;
+    w.write_all(b"\x1b[0m")
+}
+
+/// Pretty printer for terminals that colors each token according to a
+/// [`ColorScheme`]. Escape sequences go around whole tokens, never inside a
+/// string literal, so stripping them gives back the plain rendering.
+#[derive(Debug, Clone)]
+pub struct ColoredFormatter<'a> {
+    pretty: PrettyFormatter<'a>,
+    scheme: &'a ColorScheme,
+    in_key: bool,
+}
+
+impl<'a> ColoredFormatter<'a> {
+    pub fn new(indent: &'a [u8], scheme: &'a ColorScheme) -> Self {
+        let mut pretty = PrettyFormatter::with_indent(indent);
+        pretty.punctuation = &scheme.punctuation;
+        Self {
+            pretty,
+            scheme,
+            in_key: false,
+        }
+    }
+
+    pub fn with_newline(mut self, newline: &'a [u8]) -> Self {
+        self.pretty = self.pretty.with_newline(newline);
+        self
+    }
+
+    fn string_color(&self) -> &'a str {
+        if self.in_key {
+            &self.scheme.key
+        } else {
+            &self.scheme.string
+        }
+    }
+}
+
+impl Formatter for ColoredFormatter<'_> {
+    fn write_null<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        paint(w, &self.scheme.literal, b"null")
+    }
+
+    fn write_bool<W: io::Write + ?Sized>(&mut self, w: &mut W, value: bool) -> io::Result<()> {
+        paint(
+            w,
+            &self.scheme.literal,
+            if value { b"true" } else { b"false" },
+        )
+    }
+
+    fn write_i64<W: io::Write + ?Sized>(&mut self, w: &mut W, value: i64) -> io::Result<()> {
+        paint(w, &self.scheme.number, value.to_string().as_bytes())
+    }
+
+    fn write_f64<W: io::Write + ?Sized>(&mut self, w: &mut W, value: f64) -> io::Result<()> {
+        let mut token = Vec::new();
+        CompactFormatter.write_f64(&mut token, value)?;
+        paint(w, &self.scheme.number, &token)
+    }
+
+    fn write_raw_number<W: io::Write + ?Sized>(&mut self, w: &mut W, raw: &str) -> io::Result<()> {
+        paint(w, &self.scheme.number, raw.as_bytes())
+    }
+
+    fn write_non_finite<W: io::Write + ?Sized>(&mut self, w: &mut W, value: f64) -> io::Result<()> {
+        let mut token = Vec::new();
+        CompactFormatter.write_non_finite(&mut token, value)?;
+        paint(w, &self.scheme.number, &token)
+    }
+
+    fn begin_string<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        let color = self.string_color();
+        if !color.is_empty() {
+            write!(w, "\x1b[{}m", color)?;
+        }
+        w.write_all(b"\"")
+    }
+
+    fn end_string<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"\"")?;
+        if !self.string_color().is_empty() {
+            w.write_all(b"\x1b[0m")?;
+        }
+        Ok(())
+    }
+
+    fn begin_array<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.begin_array(w)
+    }
+
+    fn end_array<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.end_array(w)
+    }
+
+    fn begin_array_value<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.pretty.begin_array_value(w, first)
+    }
+
+    fn end_array_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.end_array_value(w)
+    }
+
+    fn begin_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.begin_object(w)
+    }
+
+    fn end_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.end_object(w)
+    }
+
+    fn begin_object_key<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.in_key = true;
+        self.pretty.begin_object_key(w, first)
+    }
+
+    fn end_object_key<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        self.in_key = false;
+        Ok(())
+    }
+
+    fn begin_object_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.begin_object_value(w)
+    }
+
+    fn end_object_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.end_object_value(w)
+    }
+}
+
+/// Pretty prints JSON5 for hand-maintained files: keys that are identifiers
+/// go unquoted, strings containing `"` but no `'` are single quoted, and
+/// multi-line containers get a trailing comma. Pair it with
+/// [`NonFiniteBehavior::Literal`](super::NonFiniteBehavior::Literal) to
+/// write `NaN` and `Infinity`.
+#[derive(Debug, Clone, Default)]
+pub struct Json5Formatter<'a> {
+    pretty: PrettyFormatter<'a>,
+    in_key: bool,
+    // a string is buffered until its end so the quoting can be chosen
+    string: Vec<Piece>,
+}
+
+#[derive(Debug, Clone)]
+enum Piece {
+    Text(String),
+    Escape(CharEscape),
+}
+
+impl<'a> Json5Formatter<'a> {
+    pub fn with_indent(indent: &'a [u8]) -> Self {
+        Self {
+            pretty: PrettyFormatter::with_indent(indent),
+            in_key: false,
+            string: Vec::new(),
+        }
+    }
+
+    pub fn with_newline(mut self, newline: &'a [u8]) -> Self {
+        self.pretty = self.pretty.with_newline(newline);
+        self
+    }
+
+    fn unquoted_key(&self) -> Option<&str> {
+        match self.string.as_slice() {
+            [Piece::Text(key)] if self.in_key && is_identifier(key) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+/// `$`, `_` and ASCII letters, plus digits after the first character.
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+impl Formatter for Json5Formatter<'_> {
+    fn begin_string<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        self.string.clear();
+        Ok(())
+    }
+
+    fn end_string<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        if let Some(key) = self.unquoted_key() {
+            return w.write_all(key.as_bytes());
+        }
+        let has = |f: fn(&Piece) -> bool| self.string.iter().any(f);
+        let single = has(|p| matches!(p, Piece::Escape(CharEscape::Quote)))
+            && !has(|p| matches!(p, Piece::Text(t) if t.contains('\'')));
+        let quote: &[u8] = if single { b"'" } else { b"\"" };
+        w.write_all(quote)?;
+        for piece in &self.string {
+            match piece {
+                Piece::Text(t) => w.write_all(t.as_bytes())?,
+                Piece::Escape(CharEscape::Quote) if single => w.write_all(b"\"")?,
+                Piece::Escape(e) => CompactFormatter.write_char_escape(w, *e)?,
+            }
+        }
+        w.write_all(quote)
+    }
+
+    fn write_string_fragment<W: io::Write + ?Sized>(
+        &mut self,
+        _w: &mut W,
+        fragment: &str,
+    ) -> io::Result<()> {
+        self.string.push(Piece::Text(fragment.to_string()));
+        Ok(())
+    }
+
+    fn write_char_escape<W: io::Write + ?Sized>(
+        &mut self,
+        _w: &mut W,
+        escape: CharEscape,
+    ) -> io::Result<()> {
+        self.string.push(Piece::Escape(escape));
+        Ok(())
+    }
+
+    fn begin_array<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.begin_array(w)
+    }
+
+    fn end_array<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        if self.pretty.has_value {
+            w.write_all(b",")?;
+        }
+        self.pretty.end_array(w)
+    }
+
+    fn begin_array_value<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.pretty.begin_array_value(w, first)
+    }
+
+    fn end_array_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.end_array_value(w)
+    }
+
+    fn begin_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.begin_object(w)
+    }
+
+    fn end_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        if self.pretty.has_value {
+            w.write_all(b",")?;
+        }
+        self.pretty.end_object(w)
+    }
+
+    fn begin_object_key<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.in_key = true;
+        self.pretty.begin_object_key(w, first)
+    }
+
+    fn end_object_key<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        self.in_key = false;
+        Ok(())
+    }
+
+    fn begin_object_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.begin_object_value(w)
+    }
+
+    fn end_object_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        self.pretty.end_object_value(w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{
+        parse_json, parse_json_with, FormatOptions, JsonValue, NonFiniteBehavior, ParseOptions,
+    };
+
+    fn json5(value: &JsonValue) -> String {
+        let options = FormatOptions::default()
+            .sort_keys(true)
+            .non_finite(NonFiniteBehavior::Literal);
+        let mut out = Vec::new();
+        value
+            .to_writer_with_formatter(&mut out, &mut Json5Formatter::default(), &options)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn json5_should_match_golden_output() {
+        let mut value = parse_json(
+            r#"{
+                "name": "say \"hi\"",
+                "it's": "it's \"quoted\"",
+                "$id_2": [1, 2.5, [], {}],
+                "null": null,
+                "2x": {"nested": true}
+            }"#,
+        )
+        .unwrap();
+        if let JsonValue::Object(obj) = &mut value {
+            obj.insert("inf".into(), JsonValue::from(f64::NEG_INFINITY));
+        }
+        let expected = r#"{
+  $id_2: [
+    1,
+    2.5,
+    [],
+    {},
+  ],
+  "2x": {
+    nested: true,
+  },
+  inf: -Infinity,
+  "it's": "it's \"quoted\"",
+  name: 'say "hi"',
+  null: null,
+}"#;
+        assert_eq!(json5(&value), expected);
+        assert_eq!(
+            parse_json_with(expected, &ParseOptions::json5()).unwrap(),
+            value
+        );
+        assert_eq!(
+            parse_json_with(&value.to_json5_string(), &ParseOptions::json5()).unwrap(),
+            value
+        );
+    }
+
+    fn strip_ansi(s: &str) -> String {
+        let mut out = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn colored_should_strip_to_plain_pretty_output() {
+        let value =
+            parse_json(r#"{"a": [1, -2.5, true, null, "x\u001by"], "b": {"c": "\"q\""}, "d": []}"#)
+                .unwrap();
+        let options = FormatOptions::pretty().sort_keys(true);
+        let plain = value.to_string_with(&options);
+
+        let colored = value.to_string_pretty_colored(&ColorScheme::default());
+        assert_ne!(colored, plain);
+        assert!(colored.contains("\x1b[1;34m\"a\"\x1b[0m"));
+        assert!(colored.contains("\x1b[36m-2.5\x1b[0m"));
+        assert_eq!(strip_ansi(&colored), plain);
+
+        assert_eq!(value.to_string_pretty_colored(&ColorScheme::plain()), plain);
+    }
+
+    #[test]
+    fn json5_should_keep_escapes_inside_strings() {
+        let value = JsonValue::from("tab\there \"q\"\u{1}");
+        assert_eq!(json5(&value), r#"'tab\there "q"\u0001'"#);
+        assert_eq!(JsonValue::from(1.5).to_json5_string(), "1.5");
+    }
+}
@@ -0,0 +1,202 @@
+use super::{
+    parse_cst, parse_json, parse_json_bytes, parse_json_bytes_lossy, parse_json_with,
+    validate_with, FormatOptions, ParseOptions,
+};
+
+/// Check on `input` the invariants a fuzz target is after, panicking when
+/// one fails; errors from the parsers themselves are fine. The bytes the
+/// parser accepts are exactly the UTF-8 text [`parse_json`] accepts, with
+/// the same value, and lossy decoding agrees with [`String::from_utf8_lossy`].
+/// The validators and the syntax tree accept what the parser accepts, strict
+/// or JSON5. Whatever parses serializes, compact and pretty, to text that
+/// parses back to the same value.
+///
+/// ```no_run
+/// // fuzz/fuzz_targets/parse.rs
+/// # macro_rules! fuzz_target { (|$d:ident: &[u8]| $body:block) => {} }
+/// fuzz_target!(|data: &[u8]| {
+///     grammar::json::fuzz_one(data);
+/// });
+/// ```
+pub fn fuzz_one(input: &[u8]) {
+    let bytes = parse_json_bytes(input);
+    let lossy = String::from_utf8_lossy(input);
+    assert_eq!(
+        parse_json_bytes_lossy(input).ok(),
+        parse_json(&lossy).ok(),
+        "lossy bytes and lossy text disagree"
+    );
+    let Ok(text) = std::str::from_utf8(input) else {
+        assert!(bytes.is_err(), "invalid UTF-8 parsed");
+        return;
+    };
+    let value = parse_json(text).ok();
+    assert_eq!(bytes.ok(), value, "bytes and text disagree");
+
+    for options in [ParseOptions::strict(), ParseOptions::json5()] {
+        let parsed = parse_json_with(text, &options).is_ok();
+        let validated = validate_with(text, &options).is_ok();
+        let tree = parse_cst(text, &options).is_ok();
+        assert_eq!(
+            (validated, tree),
+            (parsed, parsed),
+            "validate_with and parse_cst disagree with parse_json_with under {:?}",
+            options
+        );
+    }
+
+    if let Some(value) = value {
+        for format in [FormatOptions::compact(), FormatOptions::pretty()] {
+            let written = value.to_string_with(&format);
+            let reparsed = parse_json(&written).unwrap_or_else(|e| panic!("{}: {}", written, e));
+            assert_eq!(reparsed, value, "{} does not parse back", written);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{ErrorCode, JsonError, JsonValue, Num};
+
+    #[test]
+    fn fuzz_one_should_hold_on_tricky_inputs() {
+        let deep = |n: usize| format!("{}{}", "[".repeat(n), "]".repeat(n));
+        let limit = ParseOptions::DEFAULT_MAX_DEPTH;
+        let mut inputs: Vec<Vec<u8>> = [
+            "1e999999999999999999999",
+            "-1e-999999999999999999999",
+            "0.0000000000000000000000000000000000000000000000000000e99999999",
+            "123456789012345678901234567890",
+            "[1e400, -0, -0.0, 5e-324]",
+            "\"\\ud800\"",
+            "\"\\udc00\\ud800\"",
+            "{\"a\": 1, \"a\": 2}",
+            "/* x */ [1,] // y",
+            "{a: 'b', c: 0x7FFFFFFFFFFFFFFF, d: -0x8000000000000000, e: 0xFFFFFFFFFFFFFFFFF}",
+            "'\\\n'",
+            "NaN",
+            "",
+            " ",
+        ]
+        .iter()
+        .map(|s| s.as_bytes().to_vec())
+        .collect();
+        inputs.push(deep(limit).into_bytes());
+        inputs.push(deep(limit + 1).into_bytes());
+        inputs.push(deep(100_000).into_bytes());
+        // "é" and "😀" cut after each of their bytes
+        for text in ["\"é\"", "\"😀\"", "[\"😀\", 1]"] {
+            let bytes = text.as_bytes();
+            for cut in 1..bytes.len() {
+                inputs.push(bytes[..cut].to_vec());
+                let mut gap = bytes.to_vec();
+                gap.remove(cut);
+                inputs.push(gap);
+            }
+        }
+        for input in &inputs {
+            fuzz_one(input);
+        }
+    }
+
+    #[test]
+    fn gigantic_exponents_should_be_out_of_range_not_infinite() {
+        let out_of_range = |offset| JsonError::Parse {
+            offset,
+            message: "number out of range".to_string(),
+            code: ErrorCode::InvalidNumber,
+        };
+        for (text, offset) in [
+            ("1e999999999999999999999", 0),
+            ("-1e400", 0),
+            ("[0, 1.5e309]", 4),
+        ] {
+            assert_eq!(parse_json(text).unwrap_err(), out_of_range(offset));
+            assert_eq!(
+                parse_json_bytes(text.as_bytes()).unwrap_err(),
+                out_of_range(offset)
+            );
+            assert!(validate_with(text, &ParseOptions::strict()).is_err());
+        }
+        // past i64, an integer is a float unless asked to be exact
+        assert_eq!(
+            parse_json("-99999999999999999999").unwrap(),
+            JsonValue::from(-1e20)
+        );
+        assert_eq!(
+            parse_json(&format!("[{}]", "9".repeat(400))).unwrap_err(),
+            out_of_range(1)
+        );
+        let json5 = ParseOptions::json5();
+        assert_eq!(
+            parse_json_with("[0x10000000000000000]", &json5).unwrap_err(),
+            out_of_range(1)
+        );
+        // tiny ones underflow to zero, which is still a number
+        assert_eq!(
+            parse_json("[1e-999999999999999999999, -5e-325]").unwrap(),
+            parse_json("[0.0, -0.0]").unwrap()
+        );
+        let raw = ParseOptions::strict().preserve_number_text(true);
+        assert_eq!(
+            parse_json_with("1e400", &raw).unwrap(),
+            JsonValue::Number(Num::Raw("1e400".into()))
+        );
+    }
+
+    #[test]
+    fn nesting_should_stop_at_the_depth_limit() {
+        let limit = ParseOptions::DEFAULT_MAX_DEPTH;
+        let at_limit = format!(
+            "{}0{}",
+            "[{\"k\":".repeat(limit / 2),
+            "}]".repeat(limit / 2)
+        );
+        assert!(parse_json(&at_limit).is_ok());
+        assert!(parse_json_bytes(at_limit.as_bytes()).is_ok());
+
+        let over = format!("[{}", at_limit);
+        let err = JsonError::Parse {
+            offset: over.rfind('{').unwrap(),
+            message: "nesting is too deep; raise max_depth".to_string(),
+            code: ErrorCode::DepthExceeded,
+        };
+        assert_eq!(parse_json(&over).unwrap_err(), err);
+        assert_eq!(parse_json_bytes(over.as_bytes()).unwrap_err(), err);
+        assert_eq!(
+            validate_with(&over, &ParseOptions::json5()).unwrap_err(),
+            err
+        );
+
+        let unlimited = ParseOptions {
+            max_depth: None,
+            ..ParseOptions::strict()
+        };
+        assert!(parse_json_with(&format!("{}]", over), &unlimited).is_ok());
+    }
+
+    #[test]
+    fn truncated_multibyte_sequences_should_fail_through_the_bytes_api() {
+        let emoji = "\"😀\"".as_bytes();
+        for cut in 2..emoji.len() - 1 {
+            let mut truncated = emoji[..cut].to_vec();
+            truncated.push(b'"');
+            assert_eq!(
+                parse_json_bytes(&truncated).unwrap_err(),
+                JsonError::Parse {
+                    offset: 1,
+                    message: "invalid UTF-8".to_string(),
+                    code: ErrorCode::InvalidUtf8,
+                },
+                "{:?}",
+                truncated
+            );
+            assert_eq!(
+                parse_json_bytes_lossy(&truncated).unwrap(),
+                "\u{FFFD}".into()
+            );
+            assert!(parse_json_bytes(&emoji[..cut]).is_err());
+        }
+    }
+}
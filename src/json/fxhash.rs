@@ -0,0 +1,86 @@
+use std::hash::Hasher;
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// The Fx hash used by rustc: a rotate, xor and multiply per word. Much
+/// cheaper than SipHash on short keys, but predictable, so only for input
+/// that cannot be crafted to collide. [`JsonObject`](super::JsonObject)
+/// uses it under the `fast-hash` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let mut rest = [0; 8];
+        let tail = chunks.remainder();
+        if !tail.is_empty() {
+            rest[..tail.len()].copy_from_slice(tail);
+            self.add(u64::from_le_bytes(rest));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add(i.into());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, JsonObject, JsonValue};
+    use std::{
+        collections::{hash_map::RandomState, HashMap},
+        hash::{BuildHasherDefault, Hash},
+    };
+
+    fn fx(value: impl Hash) -> u64 {
+        let mut h = FxHasher::default();
+        value.hash(&mut h);
+        h.finish()
+    }
+
+    #[test]
+    fn fx_hasher_should_be_deterministic() {
+        assert_eq!(fx("name"), fx("name".to_string()));
+        assert_ne!(fx("name"), fx("nam"));
+        assert_ne!(fx("abcdefgh1"), fx("abcdefgh2"));
+        assert_ne!(fx(""), fx(" "));
+    }
+
+    #[test]
+    fn objects_should_behave_the_same_under_either_hasher() {
+        let JsonValue::Object(obj) =
+            parse_json(r#"{"a": 1, "bb": [2], "": null, "a": 3}"#).unwrap()
+        else {
+            panic!("not an object")
+        };
+        let sip: HashMap<_, _, RandomState> = obj.clone().into_iter().collect();
+        let fast: HashMap<_, _, BuildHasherDefault<FxHasher>> = obj.clone().into_iter().collect();
+        for key in ["a", "bb", "", "missing"] {
+            assert_eq!(sip.get(key), obj.get(key), "{}", key);
+            assert_eq!(fast.get(key), obj.get(key), "{}", key);
+        }
+        assert_eq!(obj.len(), 3);
+        assert_eq!(fast.into_iter().collect::<JsonObject>(), obj);
+    }
+}
@@ -0,0 +1,574 @@
+use std::io::{self, Read};
+
+/// How far back a DEFLATE match may reach.
+const WINDOW: usize = 32 * 1024;
+
+/// How much output to decode before handing some of it out.
+const CHUNK: usize = 16 * 1024;
+
+/// Decompress a gzip stream (RFC 1952) as it is read, holding only the last
+/// 32 KiB of output that later matches may copy. Members written one after
+/// another are read as one stream, as `gunzip` does. A stream that is cut
+/// short or does not decode is an [`io::ErrorKind::InvalidData`] error, as
+/// is a member whose CRC-32 or length does not match its trailer.
+#[derive(Debug)]
+pub struct GzDecoder<R> {
+    input: Bits<R>,
+    state: State,
+    /// Output already read, back to [`WINDOW`] bytes, then output not yet read.
+    window: Vec<u8>,
+    /// Where in `window` the unread output starts.
+    pos: usize,
+    /// The CRC-32 and length of the current member's output so far.
+    crc: u32,
+    size: usize,
+}
+
+#[derive(Debug)]
+enum State {
+    Header,
+    Block,
+    Stored {
+        left: usize,
+        last: bool,
+    },
+    Codes {
+        codes: Box<(Huffman, Huffman)>,
+        last: bool,
+    },
+    Trailer,
+    Done,
+}
+
+impl<R: Read> GzDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            input: Bits::new(reader),
+            state: State::Header,
+            window: Vec::new(),
+            pos: 0,
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    /// The reader this decodes, which has been read past the end of the
+    /// gzip stream by up to a buffer's worth.
+    pub fn into_inner(self) -> R {
+        self.input.inner
+    }
+
+    /// Decode until there is output to hand out or the stream ends.
+    fn step(&mut self) -> io::Result<()> {
+        let start = self.window.len();
+        match std::mem::replace(&mut self.state, State::Done) {
+            State::Header => {
+                self.header()?;
+                self.crc = 0;
+                self.size = 0;
+                self.state = State::Block;
+            }
+            State::Block => {
+                let last = self.input.take(1)? == 1;
+                self.state = match self.input.take(2)? {
+                    0 => {
+                        self.input.align();
+                        let len = self.input.take(16)?;
+                        if self.input.take(16)? != !len & 0xffff {
+                            return Err(corrupt("a stored block's length is garbled"));
+                        }
+                        State::Stored {
+                            left: len as usize,
+                            last,
+                        }
+                    }
+                    1 => State::Codes {
+                        codes: Box::new(fixed_codes()),
+                        last,
+                    },
+                    2 => State::Codes {
+                        codes: Box::new(self.dynamic_codes()?),
+                        last,
+                    },
+                    _ => return Err(corrupt("a block has the reserved type 3")),
+                };
+            }
+            State::Stored { mut left, last } => {
+                while left > 0 && self.window.len() - start < CHUNK {
+                    let byte = self.input.take(8)? as u8;
+                    self.window.push(byte);
+                    left -= 1;
+                }
+                self.state = match left {
+                    0 => after_block(last),
+                    _ => State::Stored { left, last },
+                };
+            }
+            State::Codes { codes, last } => {
+                let done = self.inflate(&codes.0, &codes.1, start)?;
+                self.state = match done {
+                    true => after_block(last),
+                    false => State::Codes { codes, last },
+                };
+            }
+            State::Trailer => {
+                self.input.align();
+                let crc = self.input.take_le(4)?;
+                let size = self.input.take_le(4)?;
+                if crc != self.crc {
+                    return Err(corrupt("the CRC-32 does not match the data"));
+                }
+                if size != self.size as u32 {
+                    return Err(corrupt("the length does not match the data"));
+                }
+                self.state = match self.input.at_end()? {
+                    true => State::Done,
+                    false => State::Header,
+                };
+            }
+            State::Done => {}
+        }
+        self.size += self.window.len() - start;
+        self.crc = crc32(self.crc, &self.window[start..]);
+        Ok(())
+    }
+
+    fn header(&mut self) -> io::Result<()> {
+        let mut fixed = [0; 10];
+        for byte in &mut fixed {
+            *byte = self.input.take(8)? as u8;
+        }
+        if fixed[..2] != [0x1f, 0x8b] {
+            return Err(corrupt("this is not a gzip stream"));
+        }
+        if fixed[2] != 8 {
+            return Err(corrupt("the compression method is not DEFLATE"));
+        }
+        let flags = fixed[3];
+        if flags & 0xe0 != 0 {
+            return Err(corrupt("the header sets reserved flags"));
+        }
+        if flags & 0x04 != 0 {
+            let extra = self.input.take_le(2)?;
+            for _ in 0..extra {
+                self.input.take(8)?;
+            }
+        }
+        // the file name, then the comment, each ended by a zero byte
+        for flag in [0x08, 0x10] {
+            if flags & flag != 0 {
+                while self.input.take(8)? != 0 {}
+            }
+        }
+        if flags & 0x02 != 0 {
+            self.input.take(16)?;
+        }
+        Ok(())
+    }
+
+    /// The literal/length and distance codes a dynamic block starts with.
+    fn dynamic_codes(&mut self) -> io::Result<(Huffman, Huffman)> {
+        const ORDER: [usize; 19] = [
+            16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+        ];
+        let literals = self.input.take(5)? as usize + 257;
+        let distances = self.input.take(5)? as usize + 1;
+        let count = self.input.take(4)? as usize + 4;
+        let mut lengths = [0; 19];
+        for &i in &ORDER[..count] {
+            lengths[i] = self.input.take(3)? as u8;
+        }
+        let code = Huffman::new(&lengths)?;
+        let mut lengths = vec![0; literals + distances];
+        let mut i = 0;
+        while i < lengths.len() {
+            let (len, repeat) = match code.decode(&mut self.input)? {
+                len @ 0..=15 => (len as u8, 1),
+                16 if i > 0 => (lengths[i - 1], 3 + self.input.take(2)?),
+                16 => return Err(corrupt("a code length repeats nothing")),
+                17 => (0, 3 + self.input.take(3)?),
+                _ => (0, 11 + self.input.take(7)?),
+            };
+            let end = i + repeat as usize;
+            if end > lengths.len() {
+                return Err(corrupt("code lengths run past the codes"));
+            }
+            lengths[i..end].fill(len);
+            i = end;
+        }
+        if lengths[256] == 0 {
+            return Err(corrupt("a block has no end-of-block code"));
+        }
+        let (literal, distance) = lengths.split_at(literals);
+        Ok((Huffman::new(literal)?, Huffman::new(distance)?))
+    }
+
+    /// Decode symbols into the window until the block ends, returning true,
+    /// or a chunk of output has been made since `start`.
+    fn inflate(&mut self, literal: &Huffman, distance: &Huffman, start: usize) -> io::Result<bool> {
+        const LENGTH: [u16; 29] = [
+            3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99,
+            115, 131, 163, 195, 227, 258,
+        ];
+        const LENGTH_BITS: [u8; 29] = [
+            0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+        ];
+        const DISTANCE: [u16; 30] = [
+            1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025,
+            1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+        ];
+        const DISTANCE_BITS: [u8; 30] = [
+            0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12,
+            12, 13, 13,
+        ];
+        while self.window.len() - start < CHUNK {
+            let symbol = literal.decode(&mut self.input)? as usize;
+            if symbol < 256 {
+                self.window.push(symbol as u8);
+                continue;
+            }
+            if symbol == 256 {
+                return Ok(true);
+            }
+            let i = symbol - 257;
+            if i >= LENGTH.len() {
+                return Err(corrupt("a match has an invalid length code"));
+            }
+            let len = LENGTH[i] as usize + self.input.take(LENGTH_BITS[i].into())? as usize;
+            let i = distance.decode(&mut self.input)? as usize;
+            if i >= DISTANCE.len() {
+                return Err(corrupt("a match has an invalid distance code"));
+            }
+            let back = DISTANCE[i] as usize + self.input.take(DISTANCE_BITS[i].into())? as usize;
+            // the window keeps the last WINDOW bytes, of this member or not
+            let made = self.size + (self.window.len() - start);
+            if back > made.min(self.window.len()) {
+                return Err(corrupt("a match reaches back before the data"));
+            }
+            let from = self.window.len() - back;
+            for k in 0..len {
+                let byte = self.window[from + k];
+                self.window.push(byte);
+            }
+        }
+        Ok(false)
+    }
+}
+
+fn after_block(last: bool) -> State {
+    match last {
+        true => State::Trailer,
+        false => State::Block,
+    }
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.window.len() {
+            if let State::Done = self.state {
+                return Ok(0);
+            }
+            if self.window.len() > 2 * WINDOW {
+                let drop = self.window.len() - WINDOW;
+                self.window.drain(..drop);
+                self.pos -= drop;
+            }
+            self.step()?;
+        }
+        let n = buf.len().min(self.window.len() - self.pos);
+        buf[..n].copy_from_slice(&self.window[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Bits from the input, least significant first, as DEFLATE packs them.
+#[derive(Debug)]
+struct Bits<R> {
+    inner: R,
+    buf: Box<[u8]>,
+    start: usize,
+    end: usize,
+    bits: u64,
+    count: u32,
+}
+
+impl<R: Read> Bits<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: vec![0; 8 * 1024].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    /// Whether the input has nothing more, not even a partial byte.
+    fn at_end(&mut self) -> io::Result<bool> {
+        Ok(self.count == 0 && self.fill()? == 0)
+    }
+
+    /// The number of buffered bytes, reading more if there are none.
+    fn fill(&mut self) -> io::Result<usize> {
+        if self.start == self.end {
+            self.start = 0;
+            self.end = loop {
+                match self.inner.read(&mut self.buf) {
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    read => break read?,
+                }
+            };
+        }
+        Ok(self.end - self.start)
+    }
+
+    /// The next `n` bits, up to 16, as a number.
+    fn take(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            if self.fill()? == 0 {
+                return Err(corrupt("the stream ends early"));
+            }
+            self.bits |= u64::from(self.buf[self.start]) << self.count;
+            self.start += 1;
+            self.count += 8;
+        }
+        let value = (self.bits & ((1 << n) - 1)) as u32;
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// A little-endian number of `bytes` whole bytes.
+    fn take_le(&mut self, bytes: u32) -> io::Result<u32> {
+        let mut value = 0;
+        for i in 0..bytes {
+            value |= self.take(8)? << (8 * i);
+        }
+        Ok(value)
+    }
+
+    /// Skip to the next byte boundary.
+    fn align(&mut self) {
+        let skip = self.count % 8;
+        self.bits >>= skip;
+        self.count -= skip;
+    }
+}
+
+/// A canonical Huffman code, as the number of codes of each length and the
+/// symbols in code order.
+#[derive(Debug)]
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// The code giving symbol `i` a code of `lengths[i]` bits, none if 0.
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = 2 * left - i32::from(count);
+            if left < 0 {
+                return Err(corrupt("a Huffman code has too many codes"));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode<R: Read>(&self, input: &mut Bits<R>) -> io::Result<u16> {
+        // the first code of each length, and the index of its symbol
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= input.take(1)? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt("a code is not in the Huffman table"))
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    let literal = Huffman::new(&lengths).expect("the fixed code is complete");
+    let distance = Huffman::new(&[5; 30]).expect("the fixed code is complete");
+    (literal, distance)
+}
+
+/// `crc` carried on over `data`, as gzip's CRC-32 (the IEEE polynomial).
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut c = i as u32;
+            let mut k = 0;
+            while k < 8 {
+                c = if c & 1 != 0 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+                k += 1;
+            }
+            table[i] = c;
+            i += 1;
+        }
+        table
+    };
+    let mut crc = !crc;
+    for &byte in data {
+        crc = TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt gzip stream: {}", message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `{"a": [1, 2, 3], "b": "text"}` and a newline in one stored block.
+    const STORED: [u8; 53] = [
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x01, 0x1e, 0x00, 0xe1, 0xff,
+        0x7b, 0x22, 0x61, 0x22, 0x3a, 0x20, 0x5b, 0x31, 0x2c, 0x20, 0x32, 0x2c, 0x20, 0x33, 0x5d,
+        0x2c, 0x20, 0x22, 0x62, 0x22, 0x3a, 0x20, 0x22, 0x74, 0x65, 0x78, 0x74, 0x22, 0x7d, 0x0a,
+        0xf2, 0x52, 0x06, 0x19, 0x1e, 0x00, 0x00, 0x00,
+    ];
+
+    /// The same text in a block with the fixed codes, and a file name.
+    const FIXED: [u8; 55] = [
+        0x1f, 0x8b, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x78, 0x2e, 0x6a, 0x73, 0x6f,
+        0x6e, 0x00, 0xab, 0x56, 0x4a, 0x54, 0xb2, 0x52, 0x88, 0x36, 0xd4, 0x51, 0x30, 0xd2, 0x51,
+        0x30, 0x8e, 0xd5, 0x51, 0x50, 0x4a, 0x02, 0xf2, 0x95, 0x4a, 0x52, 0x2b, 0x4a, 0x94, 0x6a,
+        0xb9, 0x00, 0xf2, 0x52, 0x06, 0x19, 0x1e, 0x00, 0x00, 0x00,
+    ];
+
+    const TEXT: &[u8] = b"{\"a\": [1, 2, 3], \"b\": \"text\"}\n";
+
+    /// Hands out one byte per read, to split every field and code.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    fn gunzip(input: impl Read) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        GzDecoder::new(input).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn gz_decoder_should_inflate_each_kind_of_block() {
+        for stream in [&STORED[..], &FIXED[..]] {
+            assert_eq!(gunzip(stream).unwrap(), TEXT);
+            assert_eq!(gunzip(Trickle(stream)).unwrap(), TEXT);
+        }
+        // dynamic codes, and matches reaching back across the kept window
+        let expected = include_bytes!("../../tests/golden/items.json");
+        let stream = include_bytes!("../../tests/golden/items.json.gz");
+        assert!(expected.len() > 2 * WINDOW);
+        assert_eq!(gunzip(&stream[..]).unwrap(), expected);
+        assert_eq!(gunzip(Trickle(stream)).unwrap(), expected);
+    }
+
+    #[test]
+    fn gz_decoder_should_read_members_one_after_another() {
+        let stream = [&STORED[..], &FIXED[..], &STORED[..]].concat();
+        assert_eq!(gunzip(&stream[..]).unwrap(), TEXT.repeat(3));
+        let mut decoder = GzDecoder::new(&stream[..]);
+        let mut first = [0; 4];
+        decoder.read_exact(&mut first).unwrap();
+        assert_eq!(&first, b"{\"a\"");
+    }
+
+    #[test]
+    fn gz_decoder_should_reject_corrupt_streams() {
+        let error = |stream: &[u8]| {
+            let e = gunzip(stream).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData, "{}", e);
+            e.to_string()
+        };
+        let mut crc = STORED;
+        crc[46] ^= 1;
+        assert_eq!(
+            error(&crc),
+            "corrupt gzip stream: the CRC-32 does not match the data"
+        );
+        let mut size = FIXED;
+        size[51] = 0x1f;
+        assert_eq!(
+            error(&size),
+            "corrupt gzip stream: the length does not match the data"
+        );
+        assert_eq!(
+            error(&FIXED[..30]),
+            "corrupt gzip stream: the stream ends early"
+        );
+        assert_eq!(error(b"{}"), "corrupt gzip stream: the stream ends early");
+        assert_eq!(
+            error(b"not gzip at all"),
+            "corrupt gzip stream: this is not a gzip stream"
+        );
+        let mut method = STORED;
+        method[2] = 7;
+        assert!(error(&method).ends_with("the compression method is not DEFLATE"));
+        let mut stored = STORED;
+        stored[13] = 0;
+        assert!(error(&stored).ends_with("a stored block's length is garbled"));
+        let mut reserved = STORED;
+        reserved[10] = 0x07;
+        assert!(error(&reserved).ends_with("a block has the reserved type 3"));
+        // a second member that is not one
+        let trailing = [&STORED[..], b"xx"].concat();
+        assert!(error(&trailing).ends_with("the stream ends early"));
+    }
+
+    #[test]
+    fn crc32_should_match_the_check_value() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf4_3926);
+        assert_eq!(crc32(0, b""), 0);
+    }
+}
@@ -0,0 +1,638 @@
+use super::{JsonError, JsonEvent, JsonObject, JsonReader, JsonString, JsonValue, Num};
+use std::ops::ControlFlow;
+use thiserror::Error;
+
+/// Callbacks for [`parse_with_handler`], one per [`JsonEvent`]. Each returns
+/// [`ControlFlow::Break`] to stop parsing on the spot. All of them continue
+/// by default, so a handler only implements what it cares about.
+pub trait JsonHandler {
+    fn on_object_start(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_object_end(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_key(&mut self, _key: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_array_start(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_array_end(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_string(&mut self, _value: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_number(&mut self, _value: &Num) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_bool(&mut self, _value: bool) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_null(&mut self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// How [`parse_with_handler`] ended when the input was not malformed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseOutcome {
+    /// The whole document was parsed.
+    Completed,
+    /// The handler stopped parsing; the rest of the input was not read, so
+    /// errors there go unreported.
+    Cancelled,
+}
+
+/// Parse strict JSON, calling `handler` for each event in document order.
+/// An error in the input is reported only once parsing reaches it, so the
+/// handler may already have seen the events before it.
+pub fn parse_with_handler(
+    input: &str,
+    handler: &mut impl JsonHandler,
+) -> Result<ParseOutcome, JsonError> {
+    let mut reader = JsonReader::new(input);
+    while let Some(event) = reader.next_event()? {
+        let flow = match &event {
+            JsonEvent::ObjectStart => handler.on_object_start(),
+            JsonEvent::ObjectEnd => handler.on_object_end(),
+            JsonEvent::Key(k) => handler.on_key(k),
+            JsonEvent::ArrayStart => handler.on_array_start(),
+            JsonEvent::ArrayEnd => handler.on_array_end(),
+            JsonEvent::String(s) => handler.on_string(s),
+            JsonEvent::Number(n) => handler.on_number(n),
+            JsonEvent::Bool(b) => handler.on_bool(*b),
+            JsonEvent::Null => handler.on_null(),
+        };
+        if flow.is_break() {
+            return Ok(ParseOutcome::Cancelled);
+        }
+    }
+    Ok(ParseOutcome::Completed)
+}
+
+/// A handler that builds the [`JsonValue`] of the document, as
+/// [`parse_json`](super::parse_json) does.
+#[derive(Debug, Default)]
+pub struct TreeBuilder {
+    /// Open containers with the key each one sits under.
+    open: Vec<(JsonValue, Option<JsonString>)>,
+    key: Option<JsonString>,
+    root: Option<JsonValue>,
+}
+
+impl TreeBuilder {
+    /// The finished tree, or `None` if no complete document was seen.
+    pub fn into_value(self) -> Option<JsonValue> {
+        self.root
+    }
+
+    pub(super) fn push(&mut self, event: JsonEvent) {
+        let value = match event {
+            JsonEvent::ObjectStart => return self.open(JsonValue::Object(JsonObject::default())),
+            JsonEvent::ArrayStart => return self.open(JsonValue::Array(Vec::new())),
+            JsonEvent::Key(k) => {
+                self.key = Some(k);
+                return;
+            }
+            JsonEvent::ObjectEnd | JsonEvent::ArrayEnd => {
+                let Some((container, key)) = self.open.pop() else {
+                    return;
+                };
+                self.key = key;
+                container
+            }
+            JsonEvent::String(s) => JsonValue::String(s),
+            JsonEvent::Number(n) => JsonValue::Number(n),
+            JsonEvent::Bool(b) => JsonValue::Bool(b),
+            JsonEvent::Null => JsonValue::Null,
+        };
+        match self.open.last_mut() {
+            Some((JsonValue::Object(obj), _)) => {
+                obj.insert(self.key.take().unwrap_or_default(), value);
+            }
+            Some((JsonValue::Array(arr), _)) => arr.push(value),
+            _ => self.root = Some(value),
+        }
+    }
+
+    fn open(&mut self, container: JsonValue) {
+        self.open.push((container, self.key.take()));
+    }
+}
+
+impl JsonHandler for TreeBuilder {
+    fn on_object_start(&mut self) -> ControlFlow<()> {
+        self.push(JsonEvent::ObjectStart);
+        ControlFlow::Continue(())
+    }
+
+    fn on_object_end(&mut self) -> ControlFlow<()> {
+        self.push(JsonEvent::ObjectEnd);
+        ControlFlow::Continue(())
+    }
+
+    fn on_key(&mut self, key: &str) -> ControlFlow<()> {
+        self.push(JsonEvent::Key(key.into()));
+        ControlFlow::Continue(())
+    }
+
+    fn on_array_start(&mut self) -> ControlFlow<()> {
+        self.push(JsonEvent::ArrayStart);
+        ControlFlow::Continue(())
+    }
+
+    fn on_array_end(&mut self) -> ControlFlow<()> {
+        self.push(JsonEvent::ArrayEnd);
+        ControlFlow::Continue(())
+    }
+
+    fn on_string(&mut self, value: &str) -> ControlFlow<()> {
+        self.push(JsonEvent::String(value.into()));
+        ControlFlow::Continue(())
+    }
+
+    fn on_number(&mut self, value: &Num) -> ControlFlow<()> {
+        self.push(JsonEvent::Number(value.clone()));
+        ControlFlow::Continue(())
+    }
+
+    fn on_bool(&mut self, value: bool) -> ControlFlow<()> {
+        self.push(JsonEvent::Bool(value));
+        ControlFlow::Continue(())
+    }
+
+    fn on_null(&mut self) -> ControlFlow<()> {
+        self.push(JsonEvent::Null);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Why [`ValueBuilder`] refused an event. `event` counts the events fed to
+/// the builder, from 0.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum BuildError {
+    /// A key outside an object, or right after another key.
+    #[error("event {event}: a key belongs in an object, before a value")]
+    UnexpectedKey { event: usize },
+    /// A value inside an object without a key before it.
+    #[error("event {event}: a member value needs a key first")]
+    MissingKey { event: usize },
+    /// An object closed right after a key.
+    #[error("event {event}: the last key has no value")]
+    MissingValue { event: usize },
+    /// An end event that does not close the innermost open container.
+    #[error("event {event}: {found} does not close {open}")]
+    MismatchedEnd {
+        event: usize,
+        found: &'static str,
+        open: &'static str,
+    },
+    /// A value after the document was complete.
+    #[error("event {event}: the document is already complete")]
+    AfterRoot { event: usize },
+    /// [`ValueBuilder::finish`] with containers still open.
+    #[error("{open} containers are still open")]
+    Unclosed { open: usize },
+    /// [`ValueBuilder::finish`] before any value.
+    #[error("no value was built")]
+    Empty,
+}
+
+/// Assembles a [`JsonValue`] from events, as [`TreeBuilder`] does, but
+/// checks that they make a document: keys only directly inside objects and
+/// each followed by a value, ends matching starts, and one root value. The
+/// first misuse is an error; after it the builder refuses everything. It
+/// is also a [`JsonHandler`], which stops at the first misuse and leaves
+/// [`finish`](Self::finish) to report it.
+#[derive(Debug, Default)]
+pub struct ValueBuilder {
+    tree: TreeBuilder,
+    /// Open containers, `true` for objects, innermost last.
+    open: Vec<bool>,
+    /// A key was given and its value has not.
+    key: bool,
+    done: bool,
+    events: usize,
+    failed: Option<BuildError>,
+}
+
+impl ValueBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn object_start(&mut self) -> Result<(), BuildError> {
+        self.event(JsonEvent::ObjectStart)
+    }
+
+    pub fn object_end(&mut self) -> Result<(), BuildError> {
+        self.event(JsonEvent::ObjectEnd)
+    }
+
+    pub fn key(&mut self, key: impl Into<JsonString>) -> Result<(), BuildError> {
+        self.event(JsonEvent::Key(key.into()))
+    }
+
+    pub fn array_start(&mut self) -> Result<(), BuildError> {
+        self.event(JsonEvent::ArrayStart)
+    }
+
+    pub fn array_end(&mut self) -> Result<(), BuildError> {
+        self.event(JsonEvent::ArrayEnd)
+    }
+
+    pub fn string(&mut self, value: impl Into<JsonString>) -> Result<(), BuildError> {
+        self.event(JsonEvent::String(value.into()))
+    }
+
+    pub fn number(&mut self, value: impl Into<Num>) -> Result<(), BuildError> {
+        self.event(JsonEvent::Number(value.into()))
+    }
+
+    pub fn bool(&mut self, value: bool) -> Result<(), BuildError> {
+        self.event(JsonEvent::Bool(value))
+    }
+
+    pub fn null(&mut self) -> Result<(), BuildError> {
+        self.event(JsonEvent::Null)
+    }
+
+    /// Take one event, such as one of [`JsonReader::next_event`].
+    pub fn event(&mut self, event: JsonEvent) -> Result<(), BuildError> {
+        if let Some(failed) = &self.failed {
+            return Err(failed.clone());
+        }
+        let checked = self.check(&event);
+        self.events += 1;
+        match checked {
+            Ok(()) => {
+                self.tree.push(event);
+                Ok(())
+            }
+            Err(e) => {
+                self.failed = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    /// The finished value, or why there is none.
+    pub fn finish(self) -> Result<JsonValue, BuildError> {
+        if let Some(failed) = self.failed {
+            return Err(failed);
+        }
+        if !self.done {
+            return Err(match self.open.len() {
+                0 => BuildError::Empty,
+                open => BuildError::Unclosed { open },
+            });
+        }
+        Ok(self.tree.into_value().expect("the root value was complete"))
+    }
+
+    fn check(&mut self, event: &JsonEvent) -> Result<(), BuildError> {
+        let at = self.events;
+        let in_object = self.open.last() == Some(&true);
+        match event {
+            JsonEvent::Key(_) => {
+                if !in_object || self.key {
+                    return Err(BuildError::UnexpectedKey { event: at });
+                }
+                self.key = true;
+            }
+            JsonEvent::ObjectEnd | JsonEvent::ArrayEnd => {
+                let object = matches!(event, JsonEvent::ObjectEnd);
+                if self.key {
+                    return Err(BuildError::MissingValue { event: at });
+                }
+                if self.open.last() != Some(&object) {
+                    return Err(BuildError::MismatchedEnd {
+                        event: at,
+                        found: if object { "object_end" } else { "array_end" },
+                        open: match self.open.last() {
+                            Some(true) => "an object",
+                            Some(false) => "an array",
+                            None => "anything",
+                        },
+                    });
+                }
+                self.open.pop();
+                self.done = self.open.is_empty();
+            }
+            _ => {
+                if self.done {
+                    return Err(BuildError::AfterRoot { event: at });
+                }
+                if in_object && !self.key {
+                    return Err(BuildError::MissingKey { event: at });
+                }
+                self.key = false;
+                match event {
+                    JsonEvent::ObjectStart => self.open.push(true),
+                    JsonEvent::ArrayStart => self.open.push(false),
+                    _ => self.done = self.open.is_empty(),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flow(&mut self, event: JsonEvent) -> ControlFlow<()> {
+        match self.event(event) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    }
+}
+
+impl JsonHandler for ValueBuilder {
+    fn on_object_start(&mut self) -> ControlFlow<()> {
+        self.flow(JsonEvent::ObjectStart)
+    }
+
+    fn on_object_end(&mut self) -> ControlFlow<()> {
+        self.flow(JsonEvent::ObjectEnd)
+    }
+
+    fn on_key(&mut self, key: &str) -> ControlFlow<()> {
+        self.flow(JsonEvent::Key(key.into()))
+    }
+
+    fn on_array_start(&mut self) -> ControlFlow<()> {
+        self.flow(JsonEvent::ArrayStart)
+    }
+
+    fn on_array_end(&mut self) -> ControlFlow<()> {
+        self.flow(JsonEvent::ArrayEnd)
+    }
+
+    fn on_string(&mut self, value: &str) -> ControlFlow<()> {
+        self.flow(JsonEvent::String(value.into()))
+    }
+
+    fn on_number(&mut self, value: &Num) -> ControlFlow<()> {
+        self.flow(JsonEvent::Number(value.clone()))
+    }
+
+    fn on_bool(&mut self, value: bool) -> ControlFlow<()> {
+        self.flow(JsonEvent::Bool(value))
+    }
+
+    fn on_null(&mut self) -> ControlFlow<()> {
+        self.flow(JsonEvent::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const SAMPLE: &str = r#"{
+        "name": "John Doe",
+        "age": 30,
+        "is_student": false,
+        "marks": [90.0, -80.0, 85.1],
+        "address": {"city": "New York", "zip": 10001, "note": null}
+    }"#;
+
+    #[derive(Default)]
+    struct Counter {
+        keys: usize,
+        scalars: usize,
+        containers: usize,
+        depth: usize,
+        max_depth: usize,
+    }
+
+    impl JsonHandler for Counter {
+        fn on_object_start(&mut self) -> ControlFlow<()> {
+            self.on_array_start()
+        }
+
+        fn on_object_end(&mut self) -> ControlFlow<()> {
+            self.on_array_end()
+        }
+
+        fn on_array_start(&mut self) -> ControlFlow<()> {
+            self.containers += 1;
+            self.depth += 1;
+            self.max_depth = self.max_depth.max(self.depth);
+            ControlFlow::Continue(())
+        }
+
+        fn on_array_end(&mut self) -> ControlFlow<()> {
+            self.depth -= 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_key(&mut self, _key: &str) -> ControlFlow<()> {
+            self.keys += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_string(&mut self, _value: &str) -> ControlFlow<()> {
+            self.scalars += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_number(&mut self, _value: &Num) -> ControlFlow<()> {
+            self.scalars += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_bool(&mut self, _value: bool) -> ControlFlow<()> {
+            self.scalars += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn on_null(&mut self) -> ControlFlow<()> {
+            self.scalars += 1;
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn counting_handler_should_see_every_event() {
+        let mut counter = Counter::default();
+        assert_eq!(
+            parse_with_handler(SAMPLE, &mut counter),
+            Ok(ParseOutcome::Completed)
+        );
+        assert_eq!(counter.keys, 8);
+        assert_eq!(counter.scalars, 9);
+        assert_eq!(counter.containers, 3);
+        assert_eq!((counter.depth, counter.max_depth), (0, 2));
+    }
+
+    #[test]
+    fn handler_should_cancel_without_reading_further() {
+        struct FirstKey(Option<String>);
+        impl JsonHandler for FirstKey {
+            fn on_key(&mut self, key: &str) -> ControlFlow<()> {
+                self.0 = Some(key.to_string());
+                ControlFlow::Break(())
+            }
+        }
+        let mut handler = FirstKey(None);
+        // the error after the first key is never reached
+        assert_eq!(
+            parse_with_handler(r#"{"id": 1, oops"#, &mut handler),
+            Ok(ParseOutcome::Cancelled)
+        );
+        assert_eq!(handler.0.as_deref(), Some("id"));
+        assert!(parse_with_handler(r#"[1, oops"#, &mut FirstKey(None)).is_err());
+    }
+
+    #[test]
+    fn tree_builder_should_match_parse_json() {
+        for input in [SAMPLE, "[]", "\"x\"", r#"[{"a": [[], {}]}, 1e3, null]"#] {
+            let mut tree = TreeBuilder::default();
+            assert_eq!(
+                parse_with_handler(input, &mut tree),
+                Ok(ParseOutcome::Completed)
+            );
+            assert_eq!(
+                tree.into_value(),
+                Some(parse_json(input).unwrap()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn value_builder_should_rebuild_the_events_of_the_reader() {
+        for input in [
+            SAMPLE,
+            "[]",
+            "\"x\"",
+            "7",
+            r#"[{"a": [[], {}]}, 1e3, null, {"": {}}]"#,
+        ] {
+            let mut reader = JsonReader::new(input);
+            let mut builder = ValueBuilder::new();
+            while let Some(event) = reader.next_event().unwrap() {
+                builder.event(event).unwrap();
+            }
+            assert_eq!(
+                builder.finish(),
+                Ok(parse_json(input).unwrap()),
+                "{}",
+                input
+            );
+
+            let mut builder = ValueBuilder::new();
+            assert_eq!(
+                parse_with_handler(input, &mut builder),
+                Ok(ParseOutcome::Completed)
+            );
+            assert_eq!(
+                builder.finish(),
+                Ok(parse_json(input).unwrap()),
+                "{}",
+                input
+            );
+        }
+
+        let mut builder = ValueBuilder::new();
+        builder.object_start().unwrap();
+        builder.key("id").unwrap();
+        builder.number(7).unwrap();
+        builder.key("tags").unwrap();
+        builder.array_start().unwrap();
+        builder.string("a").unwrap();
+        builder.bool(true).unwrap();
+        builder.null().unwrap();
+        builder.array_end().unwrap();
+        builder.object_end().unwrap();
+        assert_eq!(
+            builder.finish(),
+            Ok(parse_json(r#"{"id": 7, "tags": ["a", true, null]}"#).unwrap())
+        );
+    }
+
+    #[test]
+    fn value_builder_should_reject_misuse() {
+        use JsonEvent::*;
+        let cases = [
+            (
+                vec![Key("a".into())],
+                BuildError::UnexpectedKey { event: 0 },
+            ),
+            (
+                vec![ArrayStart, Key("a".into())],
+                BuildError::UnexpectedKey { event: 1 },
+            ),
+            (
+                vec![ObjectStart, Key("a".into()), Key("b".into())],
+                BuildError::UnexpectedKey { event: 2 },
+            ),
+            (vec![ObjectStart, Null], BuildError::MissingKey { event: 1 }),
+            (
+                vec![ObjectStart, Key("a".into()), ObjectEnd],
+                BuildError::MissingValue { event: 2 },
+            ),
+            (
+                vec![ArrayStart, ObjectEnd],
+                BuildError::MismatchedEnd {
+                    event: 1,
+                    found: "object_end",
+                    open: "an array",
+                },
+            ),
+            (
+                vec![ArrayEnd],
+                BuildError::MismatchedEnd {
+                    event: 0,
+                    found: "array_end",
+                    open: "anything",
+                },
+            ),
+            (vec![Null, Null], BuildError::AfterRoot { event: 1 }),
+            (
+                vec![ArrayStart, ArrayEnd, ArrayStart],
+                BuildError::AfterRoot { event: 2 },
+            ),
+        ];
+        for (events, expected) in cases {
+            let mut builder = ValueBuilder::new();
+            let mut got = None;
+            for event in events.clone() {
+                if let Err(e) = builder.event(event) {
+                    got = Some(e);
+                    break;
+                }
+            }
+            assert_eq!(got.as_ref(), Some(&expected), "{:?}", events);
+            // the builder stays failed
+            assert_eq!(builder.null(), Err(expected.clone()));
+            assert_eq!(builder.finish(), Err(expected));
+        }
+
+        let mut builder = ValueBuilder::new();
+        builder.array_start().unwrap();
+        builder.object_start().unwrap();
+        assert_eq!(builder.finish(), Err(BuildError::Unclosed { open: 2 }));
+        assert_eq!(ValueBuilder::new().finish(), Err(BuildError::Empty));
+        assert_eq!(
+            BuildError::MismatchedEnd {
+                event: 3,
+                found: "array_end",
+                open: "an object"
+            }
+            .to_string(),
+            "event 3: array_end does not close an object"
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use super::{error::invalid_utf8, parse_json, JsonError, JsonValue, ParseOptions};
+use winnow::{
+    ascii::digit1,
+    combinator::{alt, dispatch, fail, opt, peek, repeat},
+    error::ErrMode,
+    prelude::*,
+    stream::{Partial, StreamIsPartial},
+    token::{any, one_of, take_till, take_while},
+};
+
+type Bytes<'a> = Partial<&'a [u8]>;
+
+/// Recognize the bytes of one strict JSON value at the start of `input`,
+/// after any whitespace. On a partial stream this returns
+/// [`ErrMode::Incomplete`](winnow::error::ErrMode::Incomplete) whenever more
+/// input could still change the answer, which includes a number or literal
+/// that reaches the end of the buffer; mark the stream complete at EOF to
+/// accept those. Only the shape is checked: escapes, UTF-8 and number range
+/// are left to [`parse_json`] on the recognized bytes.
+pub fn partial_value<'a>(input: &mut Bytes<'a>) -> PResult<&'a [u8]> {
+    ws.parse_next(input)?;
+    (|input: &mut Bytes<'a>| value(input, 0))
+        .recognize()
+        .parse_next(input)
+}
+
+fn ws(input: &mut Bytes<'_>) -> PResult<()> {
+    take_while(0.., (b' ', b'\t', b'\r', b'\n'))
+        .void()
+        .parse_next(input)
+}
+
+/// A value inside `depth` containers. Containers nested past
+/// [`ParseOptions::DEFAULT_MAX_DEPTH`] do not match.
+fn value(input: &mut Bytes<'_>, depth: usize) -> PResult<()> {
+    if depth == ParseOptions::DEFAULT_MAX_DEPTH && matches!(input.first(), Some(b'{' | b'[')) {
+        return fail(input);
+    }
+    dispatch! {peek(any);
+        b'{' => |i: &mut Bytes<'_>| object(i, depth + 1),
+        b'[' => |i: &mut Bytes<'_>| array(i, depth + 1),
+        b'"' => string,
+        b't' => b"true".void(),
+        b'f' => b"false".void(),
+        b'n' => b"null".void(),
+        b'-' | b'0'..=b'9' => number,
+        _ => fail,
+    }
+    .parse_next(input)
+}
+
+fn number(input: &mut Bytes<'_>) -> PResult<()> {
+    (
+        opt(b'-'),
+        digit1,
+        opt((b'.', digit1)),
+        opt((one_of([b'e', b'E']), opt(one_of([b'+', b'-'])), digit1)),
+    )
+        .void()
+        .parse_next(input)
+}
+
+fn string(input: &mut Bytes<'_>) -> PResult<()> {
+    let chunk = alt((take_till(1.., [b'"', b'\\']).void(), (b'\\', any).void()));
+    (b'"', repeat::<_, _, (), _, _>(0.., chunk), b'"')
+        .void()
+        .parse_next(input)
+}
+
+fn array(input: &mut Bytes<'_>, depth: usize) -> PResult<()> {
+    (b'[', ws).parse_next(input)?;
+    if opt(b']').parse_next(input)?.is_some() {
+        return Ok(());
+    }
+    loop {
+        value(input, depth)?;
+        ws(input)?;
+        if one_of([b',', b']']).parse_next(input)? == b']' {
+            return Ok(());
+        }
+        ws(input)?;
+    }
+}
+
+fn object(input: &mut Bytes<'_>, depth: usize) -> PResult<()> {
+    (b'{', ws).parse_next(input)?;
+    if opt(b'}').parse_next(input)?.is_some() {
+        return Ok(());
+    }
+    loop {
+        (string, ws, b':', ws).parse_next(input)?;
+        value(input, depth)?;
+        ws(input)?;
+        if one_of([b',', b'}']).parse_next(input)? == b'}' {
+            return Ok(());
+        }
+        ws(input)?;
+    }
+}
+
+/// Parses JSON documents out of bytes that arrive in pieces, such as reads
+/// from a socket. [`push`](StreamingParser::push) appends a chunk and
+/// [`next_value`](StreamingParser::next_value) yields each document as soon
+/// as its last byte is in; documents may follow each other separated by
+/// whitespace. Call [`finish`](StreamingParser::finish) at EOF so that a
+/// trailing top-level number can complete.
+///
+/// Only bytes of the document in progress are buffered, and a cheap bracket
+/// count keeps the parser from retrying a document before it can be
+/// complete. Values and errors are those of [`parse_json`], with offsets
+/// counted from the start of the stream.
+#[derive(Debug, Default)]
+pub struct StreamingParser {
+    buf: Vec<u8>,
+    /// Bytes of the stream before `buf[0]`.
+    consumed: usize,
+    eof: bool,
+    scan: Scan,
+}
+
+/// Bracket nesting over the buffered bytes, ignoring strings, to tell when
+/// the first document may have ended.
+#[derive(Debug, Default)]
+struct Scan {
+    pos: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    started: bool,
+    /// Once set, stays set until the document is taken.
+    ready: bool,
+}
+
+impl Scan {
+    fn advance(&mut self, bytes: &[u8]) {
+        for &b in &bytes[self.pos..] {
+            if self.in_string {
+                match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                self.ready |= !self.in_string && self.depth == 0;
+                continue;
+            }
+            match b {
+                b' ' | b'\t' | b'\r' | b'\n' => continue,
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+            self.started = true;
+            self.ready |= self.depth == 0 && !self.in_string;
+        }
+        self.pos = bytes.len();
+    }
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+        self.scan.advance(&self.buf);
+    }
+
+    /// Mark the end of the stream; no more chunks may follow.
+    pub fn finish(&mut self) {
+        self.eof = true;
+    }
+
+    /// The next complete document, or `None` until more bytes arrive (or,
+    /// after [`finish`](StreamingParser::finish), when the stream is done).
+    pub fn next_value(&mut self) -> Result<Option<JsonValue>, JsonError> {
+        if !self.eof && !self.scan.ready {
+            return Ok(None);
+        }
+        let mut input = Partial::new(&self.buf[..]);
+        if self.eof {
+            let _ = input.complete();
+            let _ = ws.parse_next(&mut input);
+            if input.is_empty() {
+                return Ok(None);
+            }
+        }
+        let slice = match partial_value.parse_next(&mut input) {
+            Ok(slice) => slice,
+            Err(ErrMode::Incomplete(_)) if !self.eof => return Ok(None),
+            Err(_) => return Err(self.syntax_error()),
+        };
+        let start = slice.as_ptr() as usize - self.buf.as_ptr() as usize;
+        let end = start + slice.len();
+        let base = self.consumed + start;
+        let text = std::str::from_utf8(slice).map_err(|e| invalid_utf8(base + e.valid_up_to()))?;
+        let value = parse_json(text).map_err(|e| e.shifted(base))?;
+        self.buf.drain(..end);
+        self.consumed += end;
+        self.scan = Scan::default();
+        self.scan.advance(&self.buf);
+        Ok(Some(value))
+    }
+
+    /// The bytes not yet taken by a document and their offset into the
+    /// stream.
+    #[cfg(feature = "async")]
+    pub(super) fn buffered(&self) -> (usize, &[u8]) {
+        (self.consumed, &self.buf)
+    }
+
+    /// The error [`parse_json`] reports for the buffered bytes, which the
+    /// recognizer has found to be malformed.
+    fn syntax_error(&self) -> JsonError {
+        let (text, invalid) = match std::str::from_utf8(&self.buf) {
+            Ok(text) => (text, None),
+            Err(e) => (
+                std::str::from_utf8(&self.buf[..e.valid_up_to()]).unwrap(),
+                Some(e.valid_up_to()),
+            ),
+        };
+        match parse_json(text) {
+            Err(e @ JsonError::Parse { offset, .. }) if invalid.is_none_or(|at| offset < at) => {
+                e.shifted(self.consumed)
+            }
+            _ => invalid_utf8(self.consumed + invalid.unwrap_or(text.len())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::ErrorCode;
+
+    const DOC: &str = r#"{"name": "Zoë 😀", "esc": "\"\\\u00e9", "marks": [90.0, -80, 1e3, 0],
+        "nested": {"a": [true, false, null, {}], "b": []}}"#;
+
+    fn byte_at_a_time(input: &str) -> Result<Vec<JsonValue>, JsonError> {
+        let mut parser = StreamingParser::new();
+        let mut values = Vec::new();
+        for b in input.as_bytes() {
+            parser.push(std::slice::from_ref(b));
+            while let Some(v) = parser.next_value()? {
+                values.push(v);
+            }
+        }
+        parser.finish();
+        while let Some(v) = parser.next_value()? {
+            values.push(v);
+        }
+        Ok(values)
+    }
+
+    #[test]
+    fn streaming_parser_should_match_one_shot_parsing() {
+        assert_eq!(byte_at_a_time(DOC), Ok(vec![parse_json(DOC).unwrap()]));
+        for doc in ["0", "-12.5e3", "\"a\\\"b\"", "true", "null", " [ ] "] {
+            assert_eq!(
+                byte_at_a_time(doc),
+                Ok(vec![parse_json(doc).unwrap()]),
+                "{}",
+                doc
+            );
+        }
+        let values = byte_at_a_time("1 [2] {\"a\": 3}\n\"x\"4").unwrap();
+        assert_eq!(
+            values,
+            ["1", "[2]", "{\"a\": 3}", "\"x\"", "4"]
+                .map(|s| parse_json(s).unwrap())
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn streaming_parser_should_wait_for_terminators() {
+        let mut parser = StreamingParser::new();
+        parser.push(b"12");
+        assert_eq!(parser.next_value(), Ok(None));
+        parser.push(b"3 tr");
+        assert_eq!(parser.next_value(), Ok(Some(JsonValue::from(123))));
+        assert_eq!(parser.next_value(), Ok(None));
+        parser.push(b"ue [\"\xc3");
+        assert_eq!(parser.next_value(), Ok(Some(JsonValue::Bool(true))));
+        parser.push(b"\xa9\"]");
+        assert_eq!(
+            parser.next_value(),
+            Ok(Some(parse_json("[\"é\"]").unwrap()))
+        );
+        parser.finish();
+        assert_eq!(parser.next_value(), Ok(None));
+    }
+
+    #[test]
+    fn streaming_parser_should_report_errors_like_parse_json() {
+        for doc in [
+            "[1,]",
+            "{\"a\" 1}",
+            "[1 2]",
+            "[\"\\q\"]",
+            "[tru]",
+            "[1, 2",
+            "\"open",
+        ] {
+            let expected = parse_json(doc).unwrap_err();
+            assert_eq!(byte_at_a_time(doc), Err(expected), "{}", doc);
+        }
+        assert_eq!(
+            byte_at_a_time("[1] [2,]"),
+            Err(JsonError::Parse {
+                offset: 6,
+                message: "trailing commas are not allowed; enable allow_trailing_commas"
+                    .to_string(),
+                code: ErrorCode::TrailingComma,
+            })
+        );
+    }
+}
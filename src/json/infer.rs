@@ -0,0 +1,215 @@
+use super::{JsonObject, JsonString, JsonValue, Num};
+
+/// Describe `example` as a JSON Schema, in the subset [`Schema`](super::Schema)
+/// understands. Every observed key is required.
+pub fn infer_schema(example: &JsonValue) -> JsonValue {
+    infer_schema_many(std::slice::from_ref(example))
+}
+
+/// Describe all of `examples` with one schema. Keys missing from some objects
+/// become optional, and values whose types differ get a `type` union. Array
+/// `items` describe the elements of every array seen at that position.
+pub fn infer_schema_many(examples: &[JsonValue]) -> JsonValue {
+    let mut shape = Shape::default();
+    for example in examples {
+        shape.add(example);
+    }
+    shape.to_schema()
+}
+
+/// Everything seen at one position in the examples.
+#[derive(Debug, Default)]
+pub(super) struct Shape {
+    pub(super) null: bool,
+    pub(super) boolean: bool,
+    pub(super) integer: bool,
+    pub(super) number: bool,
+    pub(super) string: bool,
+    pub(super) array: bool,
+    pub(super) items: Option<Box<Shape>>,
+    /// How many objects were seen, to tell required keys from optional ones.
+    pub(super) objects: usize,
+    /// Properties in first-seen order, with the number of objects having them.
+    pub(super) properties: Vec<(JsonString, Shape, usize)>,
+}
+
+impl Shape {
+    pub(super) fn add(&mut self, value: &JsonValue) {
+        match value {
+            JsonValue::Null => self.null = true,
+            JsonValue::Bool(_) => self.boolean = true,
+            JsonValue::Number(Num::Int(_)) => self.integer = true,
+            JsonValue::Number(Num::Float(_)) => self.number = true,
+            JsonValue::Number(Num::Raw(s)) => {
+                if s.contains(['.', 'e', 'E']) {
+                    self.number = true
+                } else {
+                    self.integer = true
+                }
+            }
+            #[cfg(feature = "decimal")]
+            JsonValue::Number(Num::Decimal(_)) => self.number = true,
+            JsonValue::String(_) => self.string = true,
+            JsonValue::Array(arr) => {
+                self.array = true;
+                for item in arr {
+                    self.items.get_or_insert_with(Default::default).add(item);
+                }
+            }
+            JsonValue::Object(obj) => {
+                self.objects += 1;
+                let mut keys: Vec<_> = obj.iter().collect();
+                keys.sort_unstable_by_key(|(k, _)| *k);
+                for (k, v) in keys {
+                    let i = match self.properties.iter().position(|(name, ..)| name == k) {
+                        Some(i) => i,
+                        None => {
+                            self.properties.push((k.clone(), Shape::default(), 0));
+                            self.properties.len() - 1
+                        }
+                    };
+                    let (_, shape, seen) = &mut self.properties[i];
+                    shape.add(v);
+                    *seen += 1;
+                }
+            }
+            JsonValue::Raw(raw) => {
+                if let Ok(v) = raw.parse() {
+                    self.add(&v);
+                }
+            }
+        }
+    }
+
+    fn to_schema(&self) -> JsonValue {
+        let mut schema = JsonObject::default();
+        let mut types = Vec::new();
+        for (seen, name) in [
+            (self.null, "null"),
+            (self.boolean, "boolean"),
+            // integers are numbers, so a mix widens to number
+            (self.integer && !self.number, "integer"),
+            (self.number, "number"),
+            (self.string, "string"),
+            (self.array, "array"),
+            (self.objects > 0, "object"),
+        ] {
+            if seen {
+                types.push(JsonValue::from(name));
+            }
+        }
+        match types.len() {
+            0 => {}
+            1 => {
+                schema.insert("type".into(), types.remove(0));
+            }
+            _ => {
+                schema.insert("type".into(), JsonValue::Array(types));
+            }
+        }
+        if let Some(items) = &self.items {
+            schema.insert("items".into(), items.to_schema());
+        }
+        if self.objects > 0 {
+            let properties = self
+                .properties
+                .iter()
+                .map(|(k, shape, _)| (k.clone(), shape.to_schema()))
+                .collect();
+            schema.insert("properties".into(), JsonValue::Object(properties));
+            let required = self
+                .properties
+                .iter()
+                .filter(|(_, _, seen)| *seen == self.objects)
+                .map(|(k, ..)| JsonValue::from(k.as_str()))
+                .collect();
+            schema.insert("required".into(), JsonValue::Array(required));
+        }
+        JsonValue::Object(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, Schema};
+
+    #[test]
+    fn infer_schema_should_describe_one_example() {
+        let example = parse_json(
+            r#"{
+                "name": "John Doe",
+                "age": 30,
+                "is_student": false,
+                "marks": [90.0, -80.0, 85.1],
+                "address": {
+                    "city": "New York",
+                    "zip": 10001
+                }
+            }"#,
+        )
+        .unwrap();
+        let schema = infer_schema(&example);
+        let expected = parse_json(
+            r#"{
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "age": {"type": "integer"},
+                    "is_student": {"type": "boolean"},
+                    "marks": {"type": "array", "items": {"type": "number"}},
+                    "address": {
+                        "type": "object",
+                        "properties": {
+                            "city": {"type": "string"},
+                            "zip": {"type": "integer"}
+                        },
+                        "required": ["city", "zip"]
+                    }
+                },
+                "required": ["address", "age", "is_student", "marks", "name"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(schema, expected);
+        assert_eq!(
+            Schema::from_value(&schema).unwrap().validate(&example),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn infer_schema_many_should_widen() {
+        let examples = [
+            parse_json(r#"{"id": 1, "tags": [], "score": 1, "note": null}"#).unwrap(),
+            parse_json(r#"{"id": 2, "tags": ["a", 3], "score": 2.5, "extra": true}"#).unwrap(),
+        ];
+        let schema = infer_schema_many(&examples);
+        let expected = parse_json(
+            r#"{
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "tags": {"type": "array", "items": {"type": ["integer", "string"]}},
+                    "score": {"type": "number"},
+                    "note": {"type": "null"},
+                    "extra": {"type": "boolean"}
+                },
+                "required": ["id", "score", "tags"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(schema, expected);
+        let schema = Schema::from_value(&schema).unwrap();
+        for example in &examples {
+            assert_eq!(schema.validate(example), Ok(()));
+        }
+        assert!(schema
+            .validate(&parse_json(r#"{"id": 1, "tags": [null], "score": 1}"#).unwrap())
+            .is_err());
+        assert_eq!(
+            infer_schema_many(&[]),
+            JsonValue::Object(JsonObject::default())
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use super::{
+    borrowed::string, entries, json_string, open, parse_bool, parse_nonfinite, parse_null,
+    parse_num, sep_with_ws, strict, ws, Input, JsonError, JsonValue, Num,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use winnow::{combinator::alt, error::ErrMode, prelude::*, stream::Stream};
+
+/// A [`JsonValue`] whose object keys are shared: every occurrence of the
+/// same key in a document points at one allocation. Arrays of records with
+/// the same shape then spend memory on their keys once instead of once per
+/// record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InternedValue {
+    Null,
+    Bool(bool),
+    Number(Num),
+    String(String),
+    Array(Vec<InternedValue>),
+    Object(HashMap<Arc<str>, InternedValue>),
+}
+
+impl InternedValue {
+    /// Convert to a [`JsonValue`], copying each key out.
+    pub fn into_value(self) -> JsonValue {
+        match self {
+            InternedValue::Null => JsonValue::Null,
+            InternedValue::Bool(b) => JsonValue::Bool(b),
+            InternedValue::Number(n) => JsonValue::Number(n),
+            InternedValue::String(s) => JsonValue::String(json_string(s)),
+            InternedValue::Array(arr) => {
+                JsonValue::Array(arr.into_iter().map(InternedValue::into_value).collect())
+            }
+            InternedValue::Object(obj) => JsonValue::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (json_string(&*k), v.into_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Parse strict JSON like [`parse_json`](super::parse_json), allocating
+/// each distinct object key only once per document.
+pub fn parse_json_interned(input: &str) -> Result<InternedValue, JsonError> {
+    let mut keys = HashSet::new();
+    let mut rest = strict(input);
+    let value = (ws, |i: &mut Input<'_>| value(i, &mut keys), ws)
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?
+        .1;
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    Ok(value)
+}
+
+/// [`parse_value`](super::parse_value) threading the set of keys seen so
+/// far. The alternatives are tried by hand because two of them need `keys`.
+fn value(input: &mut Input<'_>, keys: &mut HashSet<Arc<str>>) -> PResult<InternedValue> {
+    let start = input.checkpoint();
+    let scalar = alt((
+        parse_null.value(InternedValue::Null),
+        parse_bool.map(InternedValue::Bool),
+        parse_nonfinite.map(|f| InternedValue::Number(Num::Float(f))),
+        parse_num.map(InternedValue::Number),
+        string.map(|s| InternedValue::String(s.into_owned())),
+    ))
+    .parse_next(input);
+    match scalar {
+        Err(ErrMode::Backtrack(_)) => input.reset(&start),
+        scalar => return scalar,
+    }
+    match array(input, keys) {
+        Err(ErrMode::Backtrack(_)) => input.reset(&start),
+        arr => return arr.map(InternedValue::Array),
+    }
+    object(input, keys).map(InternedValue::Object)
+}
+
+fn array(input: &mut Input<'_>, keys: &mut HashSet<Arc<str>>) -> PResult<Vec<InternedValue>> {
+    open('[').parse_next(input)?;
+    entries(input, ']', |i: &mut Input<'_>| value(i, keys))
+}
+
+fn object(
+    input: &mut Input<'_>,
+    keys: &mut HashSet<Arc<str>>,
+) -> PResult<HashMap<Arc<str>, InternedValue>> {
+    open('{').parse_next(input)?;
+    let member = |i: &mut Input<'_>| {
+        let key = string.parse_next(i)?;
+        let key = match keys.get(&*key) {
+            Some(shared) => Arc::clone(shared),
+            None => {
+                let shared: Arc<str> = Arc::from(key);
+                keys.insert(Arc::clone(&shared));
+                shared
+            }
+        };
+        sep_with_ws(':').parse_next(i)?;
+        Ok((key, value(i, keys)?))
+    };
+    Ok(entries(input, '}', member)?.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn parse_json_interned_should_share_keys() {
+        let records: Vec<String> = (0..1000)
+            .map(|i| {
+                format!(
+                    r#"{{"id": {}, "name": "n{}", "tags": {{"id": true}}}}"#,
+                    i, i
+                )
+            })
+            .collect();
+        let input = format!("[{}]", records.join(", "));
+        let InternedValue::Array(arr) = parse_json_interned(&input).unwrap() else {
+            panic!("not an array")
+        };
+        let InternedValue::Object(first) = &arr[0] else {
+            panic!("not an object")
+        };
+        for key in ["id", "name", "tags"] {
+            let (shared, _) = first.get_key_value(key).unwrap();
+            let uses = if key == "id" { 2000 } else { 1000 };
+            assert_eq!(Arc::strong_count(shared), uses, "{}", key);
+        }
+        assert_eq!(
+            InternedValue::Array(arr).into_value(),
+            parse_json(&input).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_json_interned_should_match_parse_json() {
+        for input in [
+            r#"{"a": [1, -2.5, {"a": null}], "bA": "c\n", "d": true, "d": false}"#,
+            " [] ",
+            "\"\"",
+        ] {
+            assert_eq!(
+                parse_json_interned(input).unwrap().into_value(),
+                parse_json(input).unwrap(),
+                "{}",
+                input
+            );
+        }
+        for input in ["[1,]", "{\"a\" 1}", "{\"a\": }", "[1] 2", ""] {
+            assert_eq!(
+                parse_json_interned(input),
+                Err(parse_json(input).unwrap_err()),
+                "{}",
+                input
+            );
+        }
+    }
+}
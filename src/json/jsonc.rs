@@ -0,0 +1,98 @@
+use super::{error, ErrorCode, JsonError};
+
+/// Turn JSONC text into strict JSON text by blanking out `//` and `/* */`
+/// comments. Every byte of a comment becomes a space except line breaks,
+/// which are kept, so both line numbers and byte offsets in later errors
+/// still match the original. String literals are copied untouched.
+///
+/// A `*/` outside any comment is left alone for the JSON parser to reject.
+pub fn strip_comments(input: &str) -> Result<String, JsonError> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'"', _) => {
+                let end = string_end(bytes, i);
+                out.extend_from_slice(&bytes[i..end]);
+                i = end;
+            }
+            (b'/', Some(b'/')) => {
+                let end = input[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                blank(&mut out, &bytes[i..end]);
+                i = end;
+            }
+            (b'/', Some(b'*')) => {
+                let end = input[i + 2..]
+                    .find("*/")
+                    .map(|n| i + 2 + n + 2)
+                    .ok_or_else(|| {
+                        JsonError::parse(ErrorCode::UnexpectedEof, i, error::UNTERMINATED_COMMENT)
+                    })?;
+                blank(&mut out, &bytes[i..end]);
+                i = end;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    Ok(String::from_utf8(out).expect("only ASCII bytes are replaced"))
+}
+
+/// The index just past the string literal opening at `start`, or the end of
+/// input when it is unterminated.
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn blank(out: &mut Vec<u8>, comment: &[u8]) {
+    out.extend(comment.iter().map(|&b| match b {
+        b'\n' | b'\r' => b,
+        _ => b' ',
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, parse_json_with, ParseOptions};
+
+    #[test]
+    fn strip_comments_should_preserve_layout() {
+        let input = "// header\n{\n  \"a\": 1, /* one\n  two */ \"b\": \"// not /* a comment\",\n  \"c\": \"\\\" // still a string\" // é\n}\n";
+        let expected = "         \n{\n  \"a\": 1,       \n         \"b\": \"// not /* a comment\",\n  \"c\": \"\\\" // still a string\"      \n}\n";
+        let stripped = strip_comments(input).unwrap();
+        assert_eq!(stripped, expected);
+        assert_eq!(stripped.len(), input.len());
+        assert_eq!(
+            parse_json(&stripped).unwrap(),
+            parse_json_with(input, &ParseOptions::strict().allow_comments(true)).unwrap()
+        );
+    }
+
+    #[test]
+    fn strip_comments_edge_cases() {
+        assert_eq!(
+            strip_comments("[1, /* 2").unwrap_err(),
+            JsonError::Parse {
+                offset: 4,
+                message: "unterminated block comment".to_string(),
+                code: ErrorCode::UnexpectedEof,
+            }
+        );
+        assert_eq!(strip_comments("[1 */]").unwrap(), "[1 */]");
+        assert_eq!(strip_comments("/**/1/*/ */").unwrap(), "    1      ");
+        assert_eq!(strip_comments("\"open // x").unwrap(), "\"open // x");
+        assert_eq!(strip_comments("1 // crlf\r\n2").unwrap(), "1        \r\n2");
+    }
+}
@@ -0,0 +1,196 @@
+use super::{
+    entries, into_std_string, open, parse_key, sep_with_ws, skip::skip, strict, ws, Input,
+    JsonError, JsonObject, JsonString, JsonValue,
+};
+use std::mem;
+use winnow::{combinator::alt, prelude::*};
+
+/// The keys of the object `input` holds, in the order they first appear,
+/// each once. Member values are checked as [`validate`](super::validate)
+/// checks them but never built, so this is as quick as validating. A valid
+/// document that is not an object has no keys.
+pub fn top_level_keys(input: &str) -> Result<Vec<String>, JsonError> {
+    scan(input, |i: &mut Input<'_>| {
+        if !i.input.starts_with('{') {
+            return skip(i).map(|_| Vec::new());
+        }
+        open('{').parse_next(i)?;
+        let mut keys: Vec<String> = Vec::new();
+        entries(i, '}', |i: &mut Input<'_>| {
+            let key = parse_key(i)?;
+            sep_with_ws(':').parse_next(i)?;
+            skip(i)?;
+            if !keys.iter().any(|k| **k == *key) {
+                keys.push(into_std_string(key));
+            }
+            Ok(())
+        })?;
+        Ok(keys)
+    })
+}
+
+/// The shape of the document in `input`: each object becomes an object
+/// with the same keys mapping to the shapes of their values, each array an
+/// array holding one element that merges the shapes of all of its own, and
+/// each scalar `null`. So `{"id": 1, "tags": [{"a": 1}, {"b": 2}]}` has the
+/// shape `{"id": null, "tags": [{"a": null, "b": null}]}`, and an empty
+/// array stays empty. Scalars are checked but never built.
+pub fn key_tree(input: &str) -> Result<JsonValue, JsonError> {
+    scan(input, shape)
+}
+
+/// Run `scan` over the whole of `input`, with the whitespace around the
+/// document, as [`validate`](super::validate) does.
+fn scan<T>(
+    input: &str,
+    scan: impl for<'a> FnMut(&mut Input<'a>) -> PResult<T>,
+) -> Result<T, JsonError> {
+    let mut rest = strict(input);
+    let found = (ws, scan, ws)
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?
+        .1;
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    Ok(found)
+}
+
+fn shape(input: &mut Input<'_>) -> PResult<JsonValue> {
+    alt((shape_array, shape_object, skip.value(JsonValue::Null))).parse_next(input)
+}
+
+fn shape_array(input: &mut Input<'_>) -> PResult<JsonValue> {
+    open('[').parse_next(input)?;
+    let mut element = None;
+    entries(input, ']', |i: &mut Input<'_>| {
+        let next = shape(i)?;
+        element = Some(match element.take() {
+            Some(seen) => merge(seen, next),
+            None => next,
+        });
+        Ok(())
+    })?;
+    Ok(JsonValue::Array(element.into_iter().collect()))
+}
+
+fn shape_object(input: &mut Input<'_>) -> PResult<JsonValue> {
+    open('{').parse_next(input)?;
+    let mut members = JsonObject::default();
+    entries(input, '}', |i: &mut Input<'_>| {
+        let key = parse_key(i)?;
+        sep_with_ws(':').parse_next(i)?;
+        let next = shape(i)?;
+        merge_member(&mut members, key, next);
+        Ok(())
+    })?;
+    Ok(JsonValue::Object(members))
+}
+
+/// One shape covering both `a` and `b`: objects take the keys of both,
+/// arrays merge their elements, and a container wins over a scalar.
+fn merge(a: JsonValue, b: JsonValue) -> JsonValue {
+    match (a, b) {
+        (JsonValue::Object(mut a), JsonValue::Object(b)) => {
+            for (key, next) in b {
+                merge_member(&mut a, key, next);
+            }
+            JsonValue::Object(a)
+        }
+        (JsonValue::Array(mut a), JsonValue::Array(b)) => {
+            JsonValue::Array(match (a.pop(), b.into_iter().next()) {
+                (Some(x), Some(y)) => vec![merge(x, y)],
+                (x, y) => x.or(y).into_iter().collect(),
+            })
+        }
+        (JsonValue::Null, b) => b,
+        (a, _) => a,
+    }
+}
+
+/// Merge `next` into the member `key` of `members`, keeping its place.
+fn merge_member(members: &mut JsonObject, key: JsonString, next: JsonValue) {
+    match members.get_mut(&key) {
+        Some(seen) => *seen = merge(mem::replace(seen, JsonValue::Null), next),
+        None => {
+            members.insert(key, next);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const SAMPLE: &str = r#"{
+        "id": 42,
+        "name": "widget",
+        "tags": ["a", "b"],
+        "parts": [
+            {"sku": "x-1", "qty": 2},
+            {"sku": "y-2", "dims": {"w": 1.5, "h": [3, 4]}}
+        ],
+        "meta": {"created": "2024-01-01", "empty": [], "nested": [[{"k": true}], [{"j": null}]]},
+        "id": 43
+    }"#;
+
+    #[test]
+    fn top_level_keys_should_list_keys_in_order() {
+        let keys = top_level_keys(SAMPLE).unwrap();
+        assert_eq!(keys, ["id", "name", "tags", "parts", "meta"]);
+        assert_eq!(top_level_keys("[1, 2]").unwrap(), Vec::<String>::new());
+        assert_eq!(top_level_keys(" {} ").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            top_level_keys(r#"{"a": [1,]}"#).unwrap_err(),
+            parse_json(r#"{"a": [1,]}"#).unwrap_err()
+        );
+        assert!(top_level_keys(r#"{"a": 1} x"#).is_err());
+    }
+
+    #[test]
+    fn key_tree_should_match_golden_shape() {
+        let expected = parse_json(
+            r#"{
+                "id": null,
+                "name": null,
+                "tags": [null],
+                "parts": [{"sku": null, "qty": null, "dims": {"w": null, "h": [null]}}],
+                "meta": {"created": null, "empty": [], "nested": [[{"k": null, "j": null}]]}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(key_tree(SAMPLE).unwrap(), expected);
+        assert_eq!(key_tree("7").unwrap(), JsonValue::Null);
+        assert_eq!(
+            key_tree(r#"[1, {"a": 1}, [], {"b": [2]}]"#).unwrap(),
+            parse_json(r#"[{"a": null, "b": [null]}]"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn top_level_keys_should_agree_with_full_parse() {
+        let docs = [
+            SAMPLE,
+            r#"{"z": 1, "a": {"q": 2}, "m": [3]}"#,
+            r#"{"escé": 1, "tab\t": 2}"#,
+            "{}",
+        ];
+        for doc in docs {
+            let JsonValue::Object(parsed) = parse_json(doc).unwrap() else {
+                unreachable!()
+            };
+            let mut expected: Vec<String> = parsed.keys().map(|k| k.to_string()).collect();
+            let mut keys = top_level_keys(doc).unwrap();
+            expected.sort();
+            keys.sort();
+            assert_eq!(keys, expected, "{}", doc);
+            let JsonValue::Object(tree) = key_tree(doc).unwrap() else {
+                unreachable!()
+            };
+            let mut tree: Vec<String> = tree.keys().map(|k| k.to_string()).collect();
+            tree.sort();
+            assert_eq!(tree, expected, "{}", doc);
+        }
+    }
+}
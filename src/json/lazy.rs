@@ -0,0 +1,209 @@
+use super::{
+    entries, open, parse_json_with, parse_key, sep_with_ws, skip::skip, stateful, validate_with,
+    ws, Input, JsonError, JsonString, JsonValue, ParseOptions,
+};
+use std::cell::OnceCell;
+use winnow::prelude::*;
+
+/// A value from [`parse_json_lazy`]: its validated text, split into members
+/// or elements only when one is asked for and parsed only when its value
+/// is. Both steps are done once and kept, so repeated access is free.
+#[derive(Debug, Clone)]
+pub struct LazyValue<'a> {
+    raw: &'a str,
+    /// What the value was validated under, to split and parse it the same.
+    options: ParseOptions,
+    children: OnceCell<Children<'a>>,
+    value: OnceCell<JsonValue>,
+}
+
+#[derive(Debug, Clone)]
+enum Children<'a> {
+    Scalar,
+    Array(Vec<LazyValue<'a>>),
+    Object(Vec<(JsonString, LazyValue<'a>)>),
+}
+
+/// Check `input` as strict JSON up front, with the errors of
+/// [`parse_json`](super::parse_json), but build nothing until a part of it
+/// is read. Suits reading a few fields out of large documents.
+///
+/// ```
+/// use grammar::json::{parse_json_lazy, JsonValue};
+///
+/// let doc = parse_json_lazy(r#"{"id": 7, "items": [1, 2, 3]}"#).unwrap();
+/// assert_eq!(doc.get("id"), Some(&JsonValue::from(7)));
+/// assert!(!doc.member("items").unwrap().is_parsed());
+/// ```
+pub fn parse_json_lazy(input: &str) -> Result<LazyValue<'_>, JsonError> {
+    parse_json_lazy_with(input, &ParseOptions::default())
+}
+
+/// [`parse_json_lazy`] with the switches of
+/// [`parse_json_with`]. Limits such as `max_array_len` and
+/// `max_total_allocated` are checked over the whole document up front, like
+/// its syntax.
+pub fn parse_json_lazy_with<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<LazyValue<'a>, JsonError> {
+    validate_with(input, options)?;
+    Ok(LazyValue::new(input.trim_matches(WS), options))
+}
+
+const WS: [char; 4] = [' ', '\t', '\n', '\r'];
+
+impl<'a> LazyValue<'a> {
+    fn new(raw: &'a str, options: &ParseOptions) -> Self {
+        LazyValue {
+            raw,
+            options: options.clone(),
+            children: OnceCell::new(),
+            value: OnceCell::new(),
+        }
+    }
+
+    /// The text of the value, without surrounding whitespace.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// The member named `key` of an object, the last one if it is repeated,
+    /// as in [`parse_json`](super::parse_json). `None` for a missing key or a non-object.
+    pub fn member(&self, key: &str) -> Option<&LazyValue<'a>> {
+        match self.children() {
+            Children::Object(members) => members.iter().rev().find(|(k, _)| k == key),
+            _ => None,
+        }
+        .map(|(_, v)| v)
+    }
+
+    /// The element at `index` of an array.
+    pub fn element(&self, index: usize) -> Option<&LazyValue<'a>> {
+        match self.children() {
+            Children::Array(elements) => elements.get(index),
+            _ => None,
+        }
+    }
+
+    /// The value of the member named `key`, parsing only that member.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.member(key).map(LazyValue::value)
+    }
+
+    /// The parsed value, built on first use.
+    pub fn value(&self) -> &JsonValue {
+        self.value
+            .get_or_init(|| parse_json_with(self.raw, &self.options).expect("validated up front"))
+    }
+
+    /// Whether [`value`](Self::value) has been built yet.
+    pub fn is_parsed(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// The whole value, equal to what [`parse_json_with`] returns.
+    pub fn to_value(&self) -> JsonValue {
+        self.value().clone()
+    }
+
+    fn children(&self) -> &Children<'a> {
+        self.children
+            .get_or_init(|| split(self.raw, &self.options).expect("validated up front"))
+    }
+}
+
+/// Find the spans of the members or elements of `raw`, skipping over them.
+fn split<'a>(raw: &'a str, options: &ParseOptions) -> PResult<Children<'a>> {
+    let mut input = stateful(raw, options);
+    let input = &mut input;
+    let span = |input: &mut Input<'a>| -> PResult<LazyValue<'a>> {
+        let start = input.input;
+        skip(input)?;
+        let text = &start[..start.len() - input.input.len()];
+        Ok(LazyValue::new(
+            text.trim_end_matches(WS),
+            &input.state.options,
+        ))
+    };
+    // comments may lead the root under `allow_comments`
+    ws(input)?;
+    if input.input.starts_with('[') {
+        open('[').parse_next(input)?;
+        return entries(input, ']', span).map(Children::Array);
+    }
+    if input.input.starts_with('{') {
+        open('{').parse_next(input)?;
+        let member = (parse_key, sep_with_ws(':'), span).map(|(k, _, v)| (k, v));
+        return entries(input, '}', member).map(Children::Object);
+    }
+    Ok(Children::Scalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    fn large_object(members: usize) -> String {
+        let members: Vec<_> = (0..members)
+            .map(|i| {
+                format!(
+                    r#""k{}": {{"n": {}, "tags": ["a\n", {}, null]}}"#,
+                    i,
+                    i,
+                    i * 2
+                )
+            })
+            .collect();
+        format!(" {{ {} }} ", members.join(",\n  "))
+    }
+
+    #[test]
+    fn lazy_value_should_parse_only_what_is_read() {
+        let input = large_object(10_000);
+        let doc = parse_json_lazy(&input).unwrap();
+        let member = doc.member("k4321").unwrap();
+        assert_eq!(member.raw(), r#"{"n": 4321, "tags": ["a\n", 8642, null]}"#);
+        assert_eq!(
+            member.member("tags").unwrap().element(1).unwrap().value(),
+            &JsonValue::from(8642)
+        );
+        assert!(!member.is_parsed() && !doc.is_parsed());
+        assert!(!doc.member("k0").unwrap().is_parsed());
+
+        let first = doc.get("k4321").unwrap();
+        assert!(std::ptr::eq(first, doc.get("k4321").unwrap()));
+        assert_eq!(Some(first), parse_json(&input).unwrap().pointer("/k4321"));
+        assert_eq!(doc.get("missing"), None);
+        assert_eq!(doc.element(0).map(LazyValue::raw), None);
+    }
+
+    #[test]
+    fn lazy_value_should_materialize_like_parse_json() {
+        let large = large_object(200);
+        for input in [
+            large.as_str(),
+            r#"[1, "two", [3, {"four": 4.5}], {}, [], true, null]"#,
+            r#"{"dup": 1, "dup": {"x": [2]}, "esc\"aped": "\u00e9"}"#,
+            " 12 ",
+            "\"s\"",
+        ] {
+            let doc = parse_json_lazy(input).unwrap();
+            assert_eq!(doc.to_value(), parse_json(input).unwrap(), "{}", input);
+        }
+        let doc = parse_json_lazy(r#"{"dup": 1, "dup": {"x": [2]}, "esc\"aped": "é"}"#).unwrap();
+        assert_eq!(doc.member("dup").unwrap().raw(), r#"{"x": [2]}"#);
+        assert_eq!(doc.get("esc\"aped"), Some(&JsonValue::from("é")));
+
+        // errors are found before anything is read
+        for input in ["[1, 2,]", "{\"a\": [1 2]}", "[\"\\q\"]", "{} x", ""] {
+            assert_eq!(
+                parse_json_lazy(input).unwrap_err(),
+                parse_json(input).unwrap_err(),
+                "{}",
+                input
+            );
+        }
+    }
+}
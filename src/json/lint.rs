@@ -0,0 +1,318 @@
+use super::{into_std_string, parse_cst, JsonError, Node, ParseOptions, Token, TokenKind};
+use std::{collections::HashMap, ops::Range};
+
+/// Something legal but suspicious that [`lint`] can report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintRule {
+    /// A key appears twice in one object; only the last value survives.
+    DuplicateKey,
+    /// Two keys of one object differ only by letter case.
+    CaseInsensitiveKey,
+    /// A number whose digits do not survive conversion to f64.
+    PrecisionLoss,
+    /// Containers nested deeper than [`LintOptions::max_depth`].
+    DeepNesting,
+    /// A number spelled with `E`, `e+` or a zero-padded exponent.
+    NonCanonicalNumber,
+    /// A string holding an unescaped bidirectional control character, which
+    /// can make text display differently from how it parses.
+    BidiControl,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 6] = [
+        LintRule::DuplicateKey,
+        LintRule::CaseInsensitiveKey,
+        LintRule::PrecisionLoss,
+        LintRule::DeepNesting,
+        LintRule::NonCanonicalNumber,
+        LintRule::BidiControl,
+    ];
+
+    /// A stable identifier for configuration files and editor integrations.
+    pub fn code(self) -> &'static str {
+        match self {
+            LintRule::DuplicateKey => "duplicate-key",
+            LintRule::CaseInsensitiveKey => "case-insensitive-key",
+            LintRule::PrecisionLoss => "precision-loss",
+            LintRule::DeepNesting => "deep-nesting",
+            LintRule::NonCanonicalNumber => "non-canonical-number",
+            LintRule::BidiControl => "bidi-control",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    pub rule: LintRule,
+    pub message: String,
+    /// Byte range of the offending token or container.
+    pub span: Range<usize>,
+    /// Another location involved, such as the first of two duplicate keys.
+    pub related: Option<Range<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintOptions {
+    /// Rules that are not reported.
+    pub allow: Vec<LintRule>,
+    pub max_depth: usize,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            max_depth: 64,
+        }
+    }
+}
+
+/// Check strict JSON for the [`LintRule`]s, with default options. Warnings are
+/// ordered by position. Input that does not parse is an error.
+pub fn lint(input: &str) -> Result<Vec<LintWarning>, JsonError> {
+    lint_with(input, &LintOptions::default())
+}
+
+pub fn lint_with(input: &str, options: &LintOptions) -> Result<Vec<LintWarning>, JsonError> {
+    let parse_options = ParseOptions::strict();
+    let cst = parse_cst(input, &parse_options)?;
+    let mut linter = Linter {
+        options,
+        parse_options,
+        warnings: Vec::new(),
+    };
+    linter.node(&cst.root, 0);
+    let mut warnings = linter.warnings;
+    warnings.retain(|w| !options.allow.contains(&w.rule));
+    warnings.sort_by_key(|w| w.span.start);
+    Ok(warnings)
+}
+
+struct Linter<'o> {
+    options: &'o LintOptions,
+    parse_options: ParseOptions,
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter<'_> {
+    fn warn(&mut self, rule: LintRule, message: String, span: Range<usize>) {
+        self.warnings.push(LintWarning {
+            rule,
+            message,
+            span,
+            related: None,
+        });
+    }
+
+    fn node(&mut self, node: &Node<'_>, depth: usize) {
+        let c = match node {
+            Node::Scalar(token) => return self.scalar(token),
+            Node::Array(c) | Node::Object(c) => c,
+        };
+        if depth == self.options.max_depth {
+            self.warn(
+                LintRule::DeepNesting,
+                format!("nesting deeper than {} levels", self.options.max_depth),
+                node.span(),
+            );
+        }
+        let mut seen: HashMap<String, Range<usize>> = HashMap::new();
+        let mut folded: HashMap<String, (String, Range<usize>)> = HashMap::new();
+        for entry in &c.entries {
+            if let Some(key) = &entry.key {
+                self.scalar(&key.token);
+                let name = into_std_string(key.to_string(&self.parse_options));
+                let span = key.token.span.clone();
+                if let Some(first) = seen.get(&name) {
+                    self.warnings.push(LintWarning {
+                        rule: LintRule::DuplicateKey,
+                        message: format!("duplicate key {:?}", name),
+                        span: span.clone(),
+                        related: Some(first.clone()),
+                    });
+                } else if let Some((other, first)) = folded.get(&name.to_lowercase()) {
+                    self.warnings.push(LintWarning {
+                        rule: LintRule::CaseInsensitiveKey,
+                        message: format!("key {:?} differs from {:?} only by case", name, other),
+                        span: span.clone(),
+                        related: Some(first.clone()),
+                    });
+                }
+                folded
+                    .entry(name.to_lowercase())
+                    .or_insert_with(|| (name.clone(), span.clone()));
+                seen.entry(name).or_insert(span);
+            }
+            self.node(&entry.value, depth + 1);
+        }
+    }
+
+    fn scalar(&mut self, token: &Token<'_>) {
+        match token.kind {
+            TokenKind::String => {
+                if let Some(c) = token.text.chars().find(|c| is_bidi_control(*c)) {
+                    self.warn(
+                        LintRule::BidiControl,
+                        format!("string contains unescaped U+{:04X}", c as u32),
+                        token.span.clone(),
+                    );
+                }
+            }
+            TokenKind::Number => {
+                if let Some(why) = non_canonical(token.text) {
+                    self.warn(
+                        LintRule::NonCanonicalNumber,
+                        format!("number {} {}", token.text, why),
+                        token.span.clone(),
+                    );
+                }
+                if !survives_f64(token.text) {
+                    self.warn(
+                        LintRule::PrecisionLoss,
+                        format!("number {} cannot be represented exactly as f64", token.text),
+                        token.span.clone(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+fn non_canonical(token: &str) -> Option<&'static str> {
+    let (_, exponent) = token.split_once(['e', 'E'])?;
+    if token.contains('E') {
+        Some("uses an uppercase exponent marker")
+    } else if exponent.starts_with('+') {
+        Some("has an explicit '+' in the exponent")
+    } else if exponent.trim_start_matches('-').starts_with('0') {
+        Some("has a zero-padded exponent")
+    } else {
+        None
+    }
+}
+
+/// Whether the decimal value of the JSON number `token` is what the nearest
+/// f64 prints as, so no written digit is lost or invented.
+pub(super) fn survives_f64(token: &str) -> bool {
+    let f: f64 = match token.parse() {
+        Ok(f) if f64::is_finite(f) => f,
+        _ => return false,
+    };
+    decimal(token) == decimal(&format!("{:e}", f))
+}
+
+/// A number as (negative, significant digits, exponent of the last digit),
+/// with leading and trailing zeros removed.
+fn decimal(token: &str) -> (bool, String, i64) {
+    let (negative, token) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let (mantissa, exponent) = token.split_once(['e', 'E']).unwrap_or((token, "0"));
+    let mut exponent: i64 = exponent.parse().unwrap_or(0);
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    exponent -= frac.len() as i64;
+    let digits = format!("{}{}", int, frac);
+    let digits = digits.trim_start_matches('0');
+    let trimmed = digits.trim_end_matches('0');
+    exponent += (digits.len() - trimmed.len()) as i64;
+    if trimmed.is_empty() {
+        return (false, String::new(), 0);
+    }
+    (negative, trimmed.to_string(), exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(input: &str) -> Vec<LintRule> {
+        lint(input).unwrap().into_iter().map(|w| w.rule).collect()
+    }
+
+    #[test]
+    fn lint_should_accept_clean_documents() {
+        assert!(rules(r#"{"a": [1, 2.5, -0.125, 1e5, 9007199254740992], "b": "text"}"#).is_empty());
+        assert!(lint("[1,").is_err());
+    }
+
+    #[test]
+    fn lint_should_report_duplicate_keys_with_both_spans() {
+        let input = r#"{"a": 1, "b": 2, "a": 3}"#;
+        let warnings = lint(input).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule, LintRule::DuplicateKey);
+        assert_eq!(&input[warnings[0].span.clone()], "\"a\"");
+        assert_eq!(warnings[0].span.start, 17);
+        assert_eq!(warnings[0].related, Some(1..4));
+        assert_eq!(warnings[0].rule.code(), "duplicate-key");
+    }
+
+    #[test]
+    fn lint_should_report_case_only_differences() {
+        let warnings = lint(r#"{"Name": 1, "name": 2}"#).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule, LintRule::CaseInsensitiveKey);
+        assert_eq!(warnings[0].related, Some(1..7));
+    }
+
+    #[test]
+    fn lint_should_report_precision_loss() {
+        let input = "[9007199254740993, 0.1, 3.14159265358979323846, 1e23]";
+        let warnings = lint(input).unwrap();
+        let spans: Vec<_> = warnings.iter().map(|w| &input[w.span.clone()]).collect();
+        assert_eq!(spans, ["9007199254740993", "3.14159265358979323846"]);
+        assert!(warnings.iter().all(|w| w.rule == LintRule::PrecisionLoss));
+    }
+
+    #[test]
+    fn lint_should_report_deep_nesting_once() {
+        let deep = format!("{}{}", "[".repeat(70), "]".repeat(70));
+        let warnings = lint(&deep).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule, LintRule::DeepNesting);
+        assert_eq!(warnings[0].span, 64..76);
+        let options = LintOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert_eq!(lint_with("[[1]]", &options).unwrap(), []);
+        assert_eq!(lint_with("[[[1]]]", &options).unwrap()[0].span, 2..5);
+    }
+
+    #[test]
+    fn lint_should_report_non_canonical_numbers() {
+        assert_eq!(
+            rules("[1e+05, 1E5, 1e-07, 1e5, 1e-7]"),
+            [LintRule::NonCanonicalNumber; 3]
+        );
+    }
+
+    #[test]
+    fn lint_should_report_bidi_controls() {
+        let warnings = lint("{\"k\u{202E}\": \"admin\u{2066}\", \"ok\": \"\\u202e\"}").unwrap();
+        assert_eq!(
+            warnings.iter().map(|w| w.rule).collect::<Vec<_>>(),
+            [LintRule::BidiControl; 2]
+        );
+        assert_eq!(warnings[0].message, "string contains unescaped U+202E");
+    }
+
+    #[test]
+    fn lint_should_skip_allowed_rules() {
+        let options = LintOptions {
+            allow: vec![LintRule::DuplicateKey],
+            ..Default::default()
+        };
+        let warnings = lint_with(r#"{"a": 1e+5, "a": 2}"#, &options).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule, LintRule::NonCanonicalNumber);
+        assert_eq!(LintRule::ALL.len(), 6);
+    }
+}
@@ -0,0 +1,243 @@
+use super::{JsonValue, Pointer};
+
+/// What [`JsonValue::deep_merge`] does when both sides hold an array.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayStrategy {
+    /// The incoming array replaces the existing one.
+    #[default]
+    Replace,
+    /// The incoming elements are appended to the existing ones.
+    Concat,
+}
+
+/// How [`JsonValue::merge_with`] settles a conflict.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeDecision {
+    /// Leave the existing value as it is.
+    KeepExisting,
+    /// Put the incoming value in its place.
+    TakeIncoming,
+    /// Put this value in its place.
+    Replace(JsonValue),
+}
+
+impl JsonValue {
+    /// Layer `other` over `self`. Where both are objects the members merge
+    /// one by one, recursively, keeping members `other` lacks; arrays
+    /// follow `arrays`; anything else, `null` included, is replaced by the
+    /// value from `other`.
+    pub fn deep_merge(&mut self, other: JsonValue, arrays: ArrayStrategy) {
+        match (self, other) {
+            (JsonValue::Object(obj), JsonValue::Object(other)) => {
+                for (key, value) in other {
+                    match obj.get_mut(&key) {
+                        Some(existing) => existing.deep_merge(value, arrays),
+                        None => {
+                            obj.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (JsonValue::Array(arr), JsonValue::Array(other)) if arrays == ArrayStrategy::Concat => {
+                arr.extend(other);
+            }
+            (this, other) => *this = other,
+        }
+    }
+
+    /// Layer `other` over `self` as [`deep_merge`](Self::deep_merge) does
+    /// for objects, asking `resolve` wherever both sides have a value and
+    /// they are not both objects. It is given the location, the existing
+    /// value and the incoming one, and its [`MergeDecision`] is what ends
+    /// up there. Members only `other` has are added without asking.
+    pub fn merge_with(
+        &mut self,
+        other: JsonValue,
+        mut resolve: impl FnMut(&Pointer, &JsonValue, JsonValue) -> MergeDecision,
+    ) {
+        merge_at(self, other, &mut Pointer::default(), &mut resolve);
+    }
+
+    /// Apply an RFC 7386 JSON Merge Patch: an object patch merges member by
+    /// member, `null` members removing what is there, and any other patch
+    /// replaces the value whole.
+    pub fn merge_patch(&mut self, patch: JsonValue) {
+        let JsonValue::Object(patch) = patch else {
+            *self = patch;
+            return;
+        };
+        if !matches!(self, JsonValue::Object(_)) {
+            *self = JsonValue::Object(Default::default());
+        }
+        let JsonValue::Object(obj) = self else {
+            unreachable!("made an object above")
+        };
+        for (key, value) in patch {
+            if value == JsonValue::Null {
+                obj.remove(&key);
+                continue;
+            }
+            match obj.get_mut(&key) {
+                Some(existing) => existing.merge_patch(value),
+                None => {
+                    let mut new = JsonValue::Null;
+                    new.merge_patch(value);
+                    obj.insert(key, new);
+                }
+            }
+        }
+    }
+}
+
+fn merge_at(
+    value: &mut JsonValue,
+    other: JsonValue,
+    at: &mut Pointer,
+    resolve: &mut dyn FnMut(&Pointer, &JsonValue, JsonValue) -> MergeDecision,
+) {
+    match (value, other) {
+        (JsonValue::Object(obj), JsonValue::Object(other)) => {
+            for (key, incoming) in other {
+                match obj.get_mut(&key) {
+                    Some(existing) => {
+                        at.push(key.to_string());
+                        merge_at(existing, incoming, at, resolve);
+                        at.pop();
+                    }
+                    None => {
+                        obj.insert(key, incoming);
+                    }
+                }
+            }
+        }
+        // `resolve` takes `incoming`, so choosing it needs a copy
+        (value, incoming) => match resolve(at, value, incoming.clone()) {
+            MergeDecision::KeepExisting => {}
+            MergeDecision::TakeIncoming => *value = incoming,
+            MergeDecision::Replace(new) => *value = new,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, Num};
+
+    #[test]
+    fn deep_merge_should_layer_objects_and_follow_the_array_strategy() {
+        let base = parse_json(r#"{"a": {"x": 1, "y": [1]}, "b": [1, 2], "c": "keep"}"#).unwrap();
+        let over = parse_json(r#"{"a": {"y": [2], "z": null}, "b": [3], "d": {"e": 1}}"#).unwrap();
+
+        let mut merged = base.clone();
+        merged.deep_merge(over.clone(), ArrayStrategy::Replace);
+        let expected =
+            r#"{"a": {"x": 1, "y": [2], "z": null}, "b": [3], "c": "keep", "d": {"e": 1}}"#;
+        assert_eq!(merged, parse_json(expected).unwrap());
+
+        let mut merged = base.clone();
+        merged.deep_merge(over, ArrayStrategy::Concat);
+        let expected = r#"{"a": {"x": 1, "y": [1, 2], "z": null}, "b": [1, 2, 3], "c": "keep", "d": {"e": 1}}"#;
+        assert_eq!(merged, parse_json(expected).unwrap());
+
+        let mut merged = base;
+        merged.deep_merge(JsonValue::from(5), ArrayStrategy::Concat);
+        assert_eq!(merged, JsonValue::from(5));
+    }
+
+    #[test]
+    fn merge_with_should_let_the_callback_sum_numbers() {
+        let mut totals =
+            parse_json(r#"{"hits": 3, "by_host": {"a": 1, "b": 2}, "tags": ["x"]}"#).unwrap();
+        let incoming = parse_json(
+            r#"{"hits": 4, "by_host": {"b": 5, "c": 1}, "tags": ["y"], "since": "today"}"#,
+        )
+        .unwrap();
+        let mut seen = Vec::new();
+        totals.merge_with(incoming, |at, existing, incoming| {
+            seen.push(at.to_string());
+            match (existing, &incoming) {
+                (JsonValue::Number(Num::Int(a)), JsonValue::Number(Num::Int(b))) => {
+                    MergeDecision::Replace(JsonValue::from(a + b))
+                }
+                _ => MergeDecision::TakeIncoming,
+            }
+        });
+        assert_eq!(
+            totals,
+            parse_json(
+                r#"{"hits": 7, "by_host": {"a": 1, "b": 7, "c": 1}, "tags": ["y"], "since": "today"}"#
+            )
+            .unwrap()
+        );
+        seen.sort();
+        assert_eq!(seen, ["/by_host/b", "/hits", "/tags"]);
+    }
+
+    #[test]
+    fn merge_with_should_keep_existing_values_when_asked() {
+        let mut base = parse_json(r#"{"a": {"x/y": 1, "n": {"deep": 1}}, "b": {"o": 1}}"#).unwrap();
+        let over =
+            parse_json(r#"{"a": {"x/y": 2, "n": 5, "new": true}, "b": 0, "c": null}"#).unwrap();
+        let mut seen = Vec::new();
+        base.merge_with(over, |at, existing, incoming| {
+            seen.push((at.clone(), existing.clone(), incoming));
+            MergeDecision::KeepExisting
+        });
+        assert_eq!(
+            base,
+            parse_json(
+                r#"{"a": {"x/y": 1, "n": {"deep": 1}, "new": true}, "b": {"o": 1}, "c": null}"#
+            )
+            .unwrap()
+        );
+        seen.sort_by_key(|(at, ..)| at.to_string());
+        let paths: Vec<_> = seen.iter().map(|(at, ..)| at.to_string()).collect();
+        assert_eq!(paths, ["/a/n", "/a/x~1y", "/b"]);
+        assert_eq!(seen[1].0.tokens(), ["a", "x/y"]);
+        assert_eq!(seen[1].1, JsonValue::from(1));
+        assert_eq!(seen[1].2, JsonValue::from(2));
+
+        // at the root there is nothing to merge into
+        let mut scalar = JsonValue::from(1);
+        scalar.merge_with(JsonValue::from(2), |at, _, _| {
+            assert!(at.is_root());
+            MergeDecision::Replace(JsonValue::Null)
+        });
+        assert_eq!(scalar, JsonValue::Null);
+    }
+
+    #[test]
+    fn merge_patch_should_follow_rfc7386() {
+        // RFC 7386 Appendix A
+        for (target, patch, result) in [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (
+                r#"{"a":{"b":"c"}}"#,
+                r#"{"a":{"b":"d","c":null}}"#,
+                r#"{"a":{"b":"d"}}"#,
+            ),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, "null", "null"),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (
+                r#"{}"#,
+                r#"{"a":{"bb":{"ccc":null}}}"#,
+                r#"{"a":{"bb":{}}}"#,
+            ),
+        ] {
+            let mut value = parse_json(target).unwrap();
+            value.merge_patch(parse_json(patch).unwrap());
+            assert_eq!(value, parse_json(result).unwrap(), "{} + {}", target, patch);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use super::{descend, parse_bool, parse_null, parse_num, parse_string, strict, Input, JsonError};
+use winnow::{ascii::multispace0, combinator::alt, prelude::*, token::any};
+
+/// Strip insignificant whitespace from `input` without building a value tree.
+/// Tokens are validated with the same sub-parsers `parse_json` uses and then
+/// copied through verbatim, so key order, number spelling (`1e+2`) and string
+/// escapes (`\u00e9`) are preserved byte-for-byte.
+pub fn minify(input: &str) -> Result<String, JsonError> {
+    let mut rest = strict(input);
+    let mut out = String::with_capacity(input.len());
+    multispace0
+        .void()
+        .parse_next(&mut rest)
+        .and_then(|_| minify_value(&mut rest, &mut out))
+        .and_then(|_| multispace0.void().parse_next(&mut rest))
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?;
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    Ok(out)
+}
+
+fn minify_value(input: &mut Input<'_>, out: &mut String) -> PResult<()> {
+    match input.input.chars().next() {
+        Some('[') => minify_container(input, out, ']', minify_value),
+        Some('{') => minify_container(input, out, '}', |input, out| {
+            out.push_str(parse_string.recognize().parse_next(input)?);
+            multispace0(input)?;
+            out.push(':'.parse_next(input)?);
+            multispace0(input)?;
+            minify_value(input, out)
+        }),
+        _ => {
+            let token = alt((
+                parse_null.recognize(),
+                parse_bool.recognize(),
+                parse_num.recognize(),
+                parse_string.recognize(),
+            ))
+            .parse_next(input)?;
+            out.push_str(token);
+            Ok(())
+        }
+    }
+}
+
+/// Copy a container whose opening bracket is next in `input`, using `entry`
+/// to copy each element or member.
+fn minify_container(
+    input: &mut Input<'_>,
+    out: &mut String,
+    close: char,
+    mut entry: impl FnMut(&mut Input<'_>, &mut String) -> PResult<()>,
+) -> PResult<()> {
+    descend(input)?;
+    out.push(any.parse_next(input)?);
+    multispace0(input)?;
+    if input.input.starts_with(close) {
+        any.parse_next(input)?;
+    } else {
+        loop {
+            entry(input, out)?;
+            multispace0(input)?;
+            if alt((',', close)).parse_next(input)? == close {
+                break;
+            }
+            out.push(',');
+            multispace0(input)?;
+        }
+    }
+    out.push(close);
+    input.state.depth -= 1;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, ErrorCode};
+
+    #[test]
+    fn minify_should_match_compact_serializer() {
+        let input = "{\n  \"person\": [\n    1,\n    {\n      \"city\": \"New York\"\n    },\n    [ ],\n    { },\n    null\n  ]\n}\n";
+        let minified = minify(input).unwrap();
+        assert_eq!(minified, r#"{"person":[1,{"city":"New York"},[],{},null]}"#);
+        let v = parse_json(input).unwrap();
+        assert_eq!(minified, v.to_json_string());
+        assert_eq!(parse_json(&minified).unwrap(), v);
+    }
+
+    #[test]
+    fn minify_should_preserve_token_spelling() {
+        let input = r#" { "b" : [ 1e+2 , -0.50, 1E5 ] , "a" : "caf\u00e9 \"x\"" , "c" : true } "#;
+        assert_eq!(
+            minify(input).unwrap(),
+            r#"{"b":[1e+2,-0.50,1E5],"a":"caf\u00e9 \"x\"","c":true}"#
+        );
+    }
+
+    #[test]
+    fn minify_should_reject_what_the_parser_rejects() {
+        for input in ["[1, 2", "[1,,2]", r#"{"a" 1}"#, "nul", "1 2", r#""\x""#, ""] {
+            assert!(minify(input).is_err(), "{:?}", input);
+            assert!(parse_json(input).is_err(), "{:?}", input);
+        }
+        assert_eq!(
+            minify("[1, x]").unwrap_err(),
+            JsonError::Parse {
+                offset: 4,
+                message: "unexpected character 'x'".to_string(),
+                code: ErrorCode::UnexpectedToken,
+            }
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use super::{
+    json_string, ErrorCode, JsonError, JsonObject, JsonString, JsonValue, Num, ParseOptions,
+};
+
+/// Encode `value` as MessagePack, using the smallest integer encoding for
+/// each number and float 64 for floats. Map keys are written in sorted order
+/// so equal values encode to equal bytes. Raw numbers that fit neither i64
+/// nor u64 are encoded as floats.
+pub fn to_msgpack(value: &JsonValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode(&mut out, value);
+    out
+}
+
+fn encode(out: &mut Vec<u8>, value: &JsonValue) {
+    match value {
+        JsonValue::Null => out.push(0xc0),
+        JsonValue::Bool(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+        JsonValue::Number(Num::Int(i)) => encode_int(out, *i),
+        JsonValue::Number(Num::Float(f)) => encode_f64(out, *f),
+        JsonValue::Number(Num::Raw(s)) => {
+            if let Ok(i) = s.parse::<i64>() {
+                encode_int(out, i)
+            } else if let Ok(u) = s.parse::<u64>() {
+                out.push(0xcf);
+                out.extend_from_slice(&u.to_be_bytes());
+            } else {
+                encode_f64(out, s.parse().unwrap_or(f64::NAN))
+            }
+        }
+        #[cfg(feature = "decimal")]
+        JsonValue::Number(Num::Decimal(d)) => encode_f64(out, d.to_f64()),
+        JsonValue::String(s) => encode_str(out, s),
+        JsonValue::Array(arr) => {
+            encode_len(out, arr.len(), 0x90, 0xdc);
+            for v in arr {
+                encode(out, v);
+            }
+        }
+        JsonValue::Object(obj) => {
+            encode_len(out, obj.len(), 0x80, 0xde);
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_unstable_by_key(|(k, _)| *k);
+            for (k, v) in members {
+                encode_str(out, k);
+                encode(out, v);
+            }
+        }
+        JsonValue::Raw(raw) => encode(out, &raw.parse().expect("raw values are valid JSON")),
+    }
+}
+
+fn encode_int(out: &mut Vec<u8>, i: i64) {
+    match i {
+        0..=0x7f => out.push(i as u8),
+        -32..=-1 => out.push(i as i8 as u8),
+        0x80..=0xff => out.extend_from_slice(&[0xcc, i as u8]),
+        0x100..=0xffff => {
+            out.push(0xcd);
+            out.extend_from_slice(&(i as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xce);
+            out.extend_from_slice(&(i as u32).to_be_bytes());
+        }
+        0x1_0000_0000.. => {
+            out.push(0xcf);
+            out.extend_from_slice(&(i as u64).to_be_bytes());
+        }
+        -0x80..=-33 => out.extend_from_slice(&[0xd0, i as i8 as u8]),
+        -0x8000..=-0x81 => {
+            out.push(0xd1);
+            out.extend_from_slice(&(i as i16).to_be_bytes());
+        }
+        -0x8000_0000..=-0x8001 => {
+            out.push(0xd2);
+            out.extend_from_slice(&(i as i32).to_be_bytes());
+        }
+        _ => {
+            out.push(0xd3);
+            out.extend_from_slice(&i.to_be_bytes());
+        }
+    }
+}
+
+fn encode_f64(out: &mut Vec<u8>, f: f64) {
+    out.push(0xcb);
+    out.extend_from_slice(&f.to_be_bytes());
+}
+
+fn encode_str(out: &mut Vec<u8>, s: &str) {
+    match s.len() {
+        len @ 0..=31 => out.push(0xa0 | len as u8),
+        len @ 32..=0xff => out.extend_from_slice(&[0xd9, len as u8]),
+        len @ 0x100..=0xffff => {
+            out.push(0xda);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            out.push(0xdb);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Write an array or map length: the fix marker `fix` for sizes below 16,
+/// else the 16-bit marker `wide` or the 32-bit one after it.
+fn encode_len(out: &mut Vec<u8>, len: usize, fix: u8, wide: u8) {
+    if len < 16 {
+        out.push(fix | len as u8);
+    } else if len <= 0xffff {
+        out.push(wide);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(wide + 1);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+/// Decode one MessagePack value that makes up all of `bytes`. Integers that
+/// fit i64 become [`Num::Int`], larger unsigned ones [`Num::Raw`] with their
+/// exact digits, and float 32 and 64 become [`Num::Float`]. Bin and ext
+/// values have no JSON counterpart and are rejected, as are map keys that
+/// are not strings, and arrays and maps nested deeper than
+/// [`ParseOptions::DEFAULT_MAX_DEPTH`], which the text parser would reject
+/// too. Error offsets are byte offsets into `bytes`.
+pub fn from_msgpack(bytes: &[u8]) -> Result<JsonValue, JsonError> {
+    let mut decoder = Decoder {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+    let value = decoder.value()?;
+    if decoder.pos != bytes.len() {
+        return Err(decoder.error(ErrorCode::TrailingData, "trailing bytes after value"));
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Arrays and maps open around the value being decoded.
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, code: ErrorCode, message: &str) -> JsonError {
+        JsonError::parse(code, self.pos, message)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], JsonError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error(ErrorCode::UnexpectedEof, "unexpected end of input"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, JsonError> {
+        Ok(self
+            .take(n)?
+            .iter()
+            .fold(0, |acc, b| (acc << 8) | u64::from(*b)))
+    }
+
+    fn str(&mut self, len: usize) -> Result<JsonString, JsonError> {
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        std::str::from_utf8(bytes).map(json_string).map_err(|e| {
+            JsonError::parse(
+                ErrorCode::InvalidUtf8,
+                start + e.valid_up_to(),
+                "invalid UTF-8 in string",
+            )
+        })
+    }
+
+    fn value(&mut self) -> Result<JsonValue, JsonError> {
+        let marker = self.take(1)?[0];
+        let container = matches!(marker, 0x80..=0x9f | 0xdc..=0xdf);
+        if container && self.depth == ParseOptions::DEFAULT_MAX_DEPTH {
+            return Err(self.back(ErrorCode::DepthExceeded, "nesting is too deep"));
+        }
+        let int = |i: i64| Ok(JsonValue::Number(Num::Int(i)));
+        match marker {
+            0x00..=0x7f => int(i64::from(marker)),
+            0xe0..=0xff => int(i64::from(marker as i8)),
+            0x80..=0x8f => self.map(usize::from(marker & 0x0f)),
+            0x90..=0x9f => self.array(usize::from(marker & 0x0f)),
+            0xa0..=0xbf => Ok(JsonValue::String(self.str(usize::from(marker & 0x1f))?)),
+            0xc0 => Ok(JsonValue::Null),
+            0xc2 => Ok(JsonValue::Bool(false)),
+            0xc3 => Ok(JsonValue::Bool(true)),
+            0xca => Ok(JsonValue::Number(Num::Float(f64::from(f32::from_bits(
+                self.uint(4)? as u32,
+            ))))),
+            0xcb => Ok(JsonValue::Number(Num::Float(f64::from_bits(self.uint(8)?)))),
+            0xcc => int(self.uint(1)? as i64),
+            0xcd => int(self.uint(2)? as i64),
+            0xce => int(self.uint(4)? as i64),
+            0xcf => {
+                let u = self.uint(8)?;
+                match i64::try_from(u) {
+                    Ok(i) => int(i),
+                    Err(_) => Ok(JsonValue::Number(Num::Raw(u.to_string()))),
+                }
+            }
+            0xd0 => int(i64::from(self.uint(1)? as u8 as i8)),
+            0xd1 => int(i64::from(self.uint(2)? as u16 as i16)),
+            0xd2 => int(i64::from(self.uint(4)? as u32 as i32)),
+            0xd3 => int(self.uint(8)? as i64),
+            0xd9 => {
+                let len = self.uint(1)? as usize;
+                Ok(JsonValue::String(self.str(len)?))
+            }
+            0xda => {
+                let len = self.uint(2)? as usize;
+                Ok(JsonValue::String(self.str(len)?))
+            }
+            0xdb => {
+                let len = self.uint(4)? as usize;
+                Ok(JsonValue::String(self.str(len)?))
+            }
+            0xdc => {
+                let len = self.uint(2)? as usize;
+                self.array(len)
+            }
+            0xdd => {
+                let len = self.uint(4)? as usize;
+                self.array(len)
+            }
+            0xde => {
+                let len = self.uint(2)? as usize;
+                self.map(len)
+            }
+            0xdf => {
+                let len = self.uint(4)? as usize;
+                self.map(len)
+            }
+            0xc4..=0xc6 => {
+                Err(self.back(ErrorCode::Unsupported, "bin values have no JSON equivalent"))
+            }
+            0xc7..=0xc9 | 0xd4..=0xd8 => {
+                Err(self.back(ErrorCode::Unsupported, "ext values have no JSON equivalent"))
+            }
+            0xc1 => Err(self.back(ErrorCode::UnexpectedToken, "reserved marker 0xc1")),
+        }
+    }
+
+    /// An error at the marker byte just read.
+    fn back(&mut self, code: ErrorCode, message: &str) -> JsonError {
+        self.pos -= 1;
+        self.error(code, message)
+    }
+
+    fn array(&mut self, len: usize) -> Result<JsonValue, JsonError> {
+        // every element takes at least a byte, which bounds a bogus length
+        let mut arr = Vec::with_capacity(len.min(self.bytes.len() - self.pos));
+        // left raised on an error, which ends the decoding anyway
+        self.depth += 1;
+        for _ in 0..len {
+            arr.push(self.value()?);
+        }
+        self.depth -= 1;
+        Ok(JsonValue::Array(arr))
+    }
+
+    fn map(&mut self, len: usize) -> Result<JsonValue, JsonError> {
+        let mut obj = JsonObject::default();
+        self.depth += 1;
+        for _ in 0..len {
+            let at = self.pos;
+            let key = match self.value()? {
+                JsonValue::String(k) => k,
+                _ => {
+                    self.pos = at;
+                    return Err(self.error(ErrorCode::Unsupported, "map keys must be strings"));
+                }
+            };
+            obj.insert(key, self.value()?);
+        }
+        self.depth -= 1;
+        Ok(JsonValue::Object(obj))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const CORPUS: [&str; 8] = [
+        "null",
+        "[true, false, 0, -1, -32, -33, 127, 128, 255, 256, 65535, 65536]",
+        "[4294967295, 4294967296, -128, -129, -32768, -32769, -2147483648, -2147483649]",
+        "[9223372036854775807, -9223372036854775808, 1.5, -0.0, 1e300]",
+        r#"{"name": "John Doe", "marks": [90.0, -80.0, 85.1], "address": {"zip": 10001}}"#,
+        r#"["", "café 😀", {}, [], [[]]]"#,
+        r#"{"a": {"b": {"c": [1, {"d": null}]}}}"#,
+        r#""0123456789012345678901234567890123456789""#,
+    ];
+
+    #[test]
+    fn msgpack_should_round_trip() {
+        for text in CORPUS {
+            let v = parse_json(text).unwrap();
+            assert_eq!(from_msgpack(&to_msgpack(&v)).unwrap(), v, "{}", text);
+        }
+        let long = "x".repeat(70_000);
+        let big = JsonValue::Array(vec![JsonValue::from(long.as_str()); 17]);
+        assert_eq!(from_msgpack(&to_msgpack(&big)).unwrap(), big);
+        let wide = JsonValue::Object(
+            (0..70_000)
+                .map(|i| (i.to_string().into(), JsonValue::from(i as i64)))
+                .collect(),
+        );
+        assert_eq!(from_msgpack(&to_msgpack(&wide)).unwrap(), wide);
+    }
+
+    #[test]
+    fn msgpack_should_use_smallest_encodings() {
+        let cases: [(&str, &[u8]); 12] = [
+            ("null", &[0xc0]),
+            ("true", &[0xc3]),
+            ("5", &[0x05]),
+            ("-5", &[0xfb]),
+            ("200", &[0xcc, 0xc8]),
+            ("-100", &[0xd0, 0x9c]),
+            ("1000", &[0xcd, 0x03, 0xe8]),
+            ("100000", &[0xce, 0x00, 0x01, 0x86, 0xa0]),
+            ("1.5", &[0xcb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0]),
+            (r#""abc""#, &[0xa3, b'a', b'b', b'c']),
+            ("[1, [2]]", &[0x92, 0x01, 0x91, 0x02]),
+            (
+                r#"{"b": 1, "a": 2}"#,
+                &[0x82, 0xa1, b'a', 0x02, 0xa1, b'b', 0x01],
+            ),
+        ];
+        for (text, bytes) in cases {
+            let v = parse_json(text).unwrap();
+            assert_eq!(to_msgpack(&v), bytes, "{}", text);
+            assert_eq!(from_msgpack(bytes).unwrap(), v, "{}", text);
+        }
+    }
+
+    #[test]
+    fn msgpack_should_decode_other_encodings() {
+        // float 32, str 8, uint 64 beyond i64 and a non-minimal int 16
+        assert_eq!(
+            from_msgpack(&[0xca, 0x3f, 0xc0, 0, 0]).unwrap(),
+            JsonValue::from(1.5)
+        );
+        assert_eq!(
+            from_msgpack(&[0xd9, 0x02, b'h', b'i']).unwrap(),
+            JsonValue::from("hi")
+        );
+        assert_eq!(
+            from_msgpack(&[0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap(),
+            JsonValue::Number(Num::Raw("18446744073709551615".to_string()))
+        );
+        assert_eq!(
+            from_msgpack(&[0xd1, 0x00, 0x01]).unwrap(),
+            JsonValue::from(1)
+        );
+    }
+
+    #[test]
+    fn msgpack_should_reject_what_json_cannot_hold() {
+        let err = |bytes: &[u8]| match from_msgpack(bytes).unwrap_err() {
+            JsonError::Parse {
+                offset, message, ..
+            } => (offset, message),
+            e => panic!("{:?}", e),
+        };
+        assert_eq!(
+            err(&[0x91, 0xc4, 0x01, 0x00]),
+            (1, "bin values have no JSON equivalent".to_string())
+        );
+        assert_eq!(err(&[0xd4, 0x01, 0x00]).0, 0);
+        assert_eq!(
+            err(&[0x81, 0x01, 0x02]),
+            (1, "map keys must be strings".to_string())
+        );
+        assert_eq!(
+            err(&[0x92, 0x01]),
+            (2, "unexpected end of input".to_string())
+        );
+        assert_eq!(
+            err(&[0x01, 0x02]),
+            (1, "trailing bytes after value".to_string())
+        );
+        assert_eq!(err(&[0xa2, 0xff, 0xfe]).1, "invalid UTF-8 in string");
+        assert_eq!(
+            err(&[0xdd, 0xff, 0xff, 0xff, 0xff]).1,
+            "unexpected end of input"
+        );
+        let code = |bytes: &[u8]| from_msgpack(bytes).unwrap_err().code();
+        assert_eq!(code(&[0x92, 0x01]), ErrorCode::UnexpectedEof);
+        assert_eq!(code(&[0x01, 0x02]), ErrorCode::TrailingData);
+        assert_eq!(code(&[0xc4, 0x01, 0x00]), ErrorCode::Unsupported);
+        assert_eq!(code(&[0xc1]), ErrorCode::UnexpectedToken);
+    }
+
+    #[test]
+    fn msgpack_should_reject_nesting_deeper_than_the_text_parser_takes() {
+        let limit = ParseOptions::DEFAULT_MAX_DEPTH;
+        // unclosed arrays of one element, and maps of one member keyed "a"
+        for (bytes, at) in [
+            (vec![0x91; 200_000], limit),
+            ([0x81, 0xa1, b'a'].repeat(70_000), 3 * limit),
+        ] {
+            let err = from_msgpack(&bytes).unwrap_err();
+            assert_eq!(err.code(), ErrorCode::DepthExceeded);
+            assert!(matches!(err, JsonError::Parse { offset, .. } if offset == at));
+        }
+        let mut deepest = vec![0x91; limit];
+        deepest.push(0x00);
+        let value = from_msgpack(&deepest).unwrap();
+        assert_eq!(to_msgpack(&value), deepest);
+        deepest.insert(0, 0x91);
+        assert_eq!(
+            from_msgpack(&deepest).unwrap_err().code(),
+            ErrorCode::DepthExceeded
+        );
+    }
+}
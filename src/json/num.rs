@@ -0,0 +1,235 @@
+use super::{parse_num, strict, JsonError, Num};
+use std::fmt;
+use winnow::prelude::*;
+
+impl Num {
+    /// Parse `s`, all of it, as one strict JSON number, with the grammar
+    /// and [`Int`](Num::Int)/[`Float`](Num::Float) choice of
+    /// [`parse_json`](super::parse_json): no `+1`, `.5`, `1.`, `01`, `inf`
+    /// or surrounding whitespace, all of which `f64::from_str` takes some
+    /// of.
+    ///
+    /// ```
+    /// use grammar::json::Num;
+    ///
+    /// assert_eq!(Num::from_json_str("1e5").unwrap(), Num::Float(1e5));
+    /// assert!(Num::from_json_str("+1").is_err());
+    /// ```
+    pub fn from_json_str(s: &str) -> Result<Num, JsonError> {
+        let mut rest = strict(s);
+        let num = parse_num
+            .parse_next(&mut rest)
+            .map_err(|e| JsonError::from_parser(s, rest.input, e))?;
+        if !rest.input.is_empty() {
+            return Err(JsonError::parse_at(s, rest.input).trailing());
+        }
+        Ok(num)
+    }
+}
+
+/// The number as the serializer writes it, so the text is a valid JSON
+/// number: `42`, `1.0`, `1e300`, raw text verbatim. NaN and the infinities,
+/// which JSON has no spelling for, come out as `NaN`, `Infinity` and
+/// `-Infinity`, what [`NonFiniteBehavior::Literal`](super::NonFiniteBehavior::Literal)
+/// writes.
+impl fmt::Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Num::Int(i) => write!(f, "{}", i),
+            // Debug is the shortest round-trip form, as in the formatter
+            Num::Float(x) if x.is_finite() => write!(f, "{:?}", x),
+            Num::Float(x) if x.is_nan() => f.write_str("NaN"),
+            Num::Float(x) if *x > 0.0 => f.write_str("Infinity"),
+            Num::Float(_) => f.write_str("-Infinity"),
+            Num::Raw(text) => f.write_str(text),
+            #[cfg(feature = "decimal")]
+            Num::Decimal(d) => write!(f, "{}", d),
+        }
+    }
+}
+
+macro_rules! from_integers {
+    ($($t:ty)*) => {$(
+        impl From<$t> for Num {
+            fn from(i: $t) -> Self {
+                Num::Int(i.into())
+            }
+        }
+    )*};
+}
+
+from_integers!(i8 i16 i32 i64 u8 u16 u32);
+
+/// NaN and the infinities are kept as they are; serializing one fails
+/// unless [`FormatOptions::non_finite`](super::FormatOptions::non_finite)
+/// says otherwise.
+impl From<f64> for Num {
+    fn from(f: f64) -> Self {
+        Num::Float(f)
+    }
+}
+
+/// Widened exactly, so `0.1f32` becomes `0.10000000149011612`. NaN and the
+/// infinities are kept, as for `f64`.
+impl From<f32> for Num {
+    fn from(f: f32) -> Self {
+        Num::Float(f.into())
+    }
+}
+
+macro_rules! eq_integers {
+    ($($t:ty)*) => {$(
+        /// Only an [`Num::Int`] of the same value is equal; a float or raw
+        /// text never is, as `Num` compares by variant.
+        impl PartialEq<$t> for Num {
+            fn eq(&self, other: &$t) -> bool {
+                matches!(self, Num::Int(i) if i64::try_from(*other).is_ok_and(|o| o == *i))
+            }
+        }
+
+        impl PartialEq<Num> for $t {
+            fn eq(&self, other: &Num) -> bool {
+                other == self
+            }
+        }
+    )*};
+}
+
+eq_integers!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+macro_rules! eq_floats {
+    ($($t:ty)*) => {$(
+        /// Only a [`Num::Float`] of the same value is equal, so NaN never is.
+        impl PartialEq<$t> for Num {
+            fn eq(&self, other: &$t) -> bool {
+                matches!(self, Num::Float(f) if *f == f64::from(*other))
+            }
+        }
+
+        impl PartialEq<Num> for $t {
+            fn eq(&self, other: &Num) -> bool {
+                other == self
+            }
+        }
+    )*};
+}
+
+eq_floats!(f32 f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, FormatOptions, JsonValue, NonFiniteBehavior};
+
+    #[test]
+    fn from_json_str_should_take_exactly_one_json_number() {
+        for text in [
+            "0",
+            "-0",
+            "42",
+            "-7",
+            "0.5",
+            "-0.0",
+            "1e5",
+            "1E-5",
+            "2.5e+3",
+            "9223372036854775807",
+            "99999999999999999999",
+        ] {
+            let num = Num::from_json_str(text).unwrap();
+            assert_eq!(
+                JsonValue::Number(num),
+                parse_json(text).unwrap(),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(Num::from_json_str("-0").unwrap(), Num::Int(0));
+        assert_eq!(Num::from_json_str("1e5").unwrap(), Num::Float(1e5));
+        assert_eq!(Num::from_json_str("12").unwrap(), Num::Int(12));
+        for (text, offset) in [
+            ("", 0),
+            ("+1", 0),
+            (".5", 0),
+            ("1.", 1),
+            ("1e", 1),
+            ("-", 1),
+            ("1 ", 1),
+            (" 1", 0),
+            ("1x", 1),
+            ("1,2", 1),
+            ("0x10", 1),
+            ("01", 1),
+            ("-007", 2),
+            ("inf", 0),
+            ("NaN", 0),
+            ("Infinity", 0),
+            ("1e400", 0),
+        ] {
+            match Num::from_json_str(text) {
+                Err(JsonError::Parse { offset: at, .. }) => assert_eq!(at, offset, "{}", text),
+                other => panic!("{:?} gave {:?}", text, other),
+            }
+        }
+    }
+
+    #[test]
+    fn from_should_build_ints_and_floats() {
+        assert_eq!(Num::from(-8i8), Num::Int(-8));
+        assert_eq!(Num::from(-16i16), Num::Int(-16));
+        assert_eq!(Num::from(-32i32), Num::Int(-32));
+        assert_eq!(Num::from(i64::MIN), Num::Int(i64::MIN));
+        assert_eq!(Num::from(u8::MAX), Num::Int(255));
+        assert_eq!(Num::from(u16::MAX), Num::Int(65535));
+        assert_eq!(Num::from(u32::MAX), Num::Int(4294967295));
+        assert_eq!(Num::from(0.5f64), Num::Float(0.5));
+        assert_eq!(Num::from(0.5f32), Num::Float(0.5));
+        assert_eq!(Num::from(0.1f32), Num::Float(f64::from(0.1f32)));
+        assert!(matches!(Num::from(f64::NAN), Num::Float(f) if f.is_nan()));
+        assert_eq!(Num::from(f32::NEG_INFINITY), Num::Float(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn display_should_match_the_serializer() {
+        let nums = [
+            Num::Int(0),
+            Num::Int(-42),
+            Num::Int(i64::MAX),
+            Num::Float(1.0),
+            Num::Float(-0.0),
+            Num::Float(0.1),
+            Num::Float(1e300),
+            Num::Float(5e-324),
+            Num::Float(1.5e-7),
+            Num::Float(123456789012345680000.0),
+            Num::Raw("1.000e+5".into()),
+        ];
+        for n in nums {
+            let written = JsonValue::Number(n.clone()).to_json_string();
+            assert_eq!(n.to_string(), written);
+            assert!(crate::json::parse_json(&written).is_ok(), "{}", written);
+        }
+        let literal = FormatOptions::default().non_finite(NonFiniteBehavior::Literal);
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let written = JsonValue::from(f).to_string_with(&literal);
+            assert_eq!(Num::Float(f).to_string(), written);
+        }
+    }
+
+    #[test]
+    fn nums_should_compare_with_primitives() {
+        assert_eq!(Num::Int(42), 42);
+        assert_eq!(42u8, Num::Int(42));
+        assert_eq!(Num::Int(-1), -1i8);
+        assert_eq!(Num::Int(7), 7usize);
+        assert_ne!(Num::Int(-1), u64::MAX);
+        assert_ne!(Num::Int(i64::MAX), u64::MAX);
+        assert_eq!(Num::Int(i64::MAX), i64::MAX as u64);
+        assert_ne!(Num::Float(42.0), 42);
+        assert_ne!(Num::Raw("42".into()), 42);
+        assert_eq!(Num::Float(0.5), 0.5);
+        assert_eq!(0.5f32, Num::Float(0.5));
+        assert_ne!(Num::Int(1), 1.0);
+        assert_ne!(Num::Float(f64::NAN), f64::NAN);
+    }
+}
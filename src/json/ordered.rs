@@ -0,0 +1,291 @@
+use super::{JsonString, ObjectHasher};
+use std::{borrow::Borrow, collections::HashMap, fmt, hash::Hash, ops::Index, slice, vec};
+
+/// A map from strings that iterates in insertion order, backing
+/// [`JsonObject`](super::JsonObject) under the `preserve-order` feature. It
+/// offers the parts of the `HashMap` API the crate uses, with the same
+/// signatures. Inserting an existing key replaces its value in place, so the
+/// last of duplicate keys wins but keeps the first one's position. Equality
+/// ignores order, as it does for `HashMap`.
+#[derive(Clone)]
+pub struct OrderedMap<V> {
+    entries: Vec<(JsonString, V)>,
+    /// Position of each key in `entries`.
+    index: HashMap<JsonString, usize, ObjectHasher>,
+}
+
+impl<V> Default for OrderedMap<V> {
+    fn default() -> Self {
+        OrderedMap {
+            entries: Vec::new(),
+            index: HashMap::default(),
+        }
+    }
+}
+
+impl<V> OrderedMap<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        JsonString: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&JsonString, &V)>
+    where
+        JsonString: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (k, v) = &self.entries[*self.index.get(key)?];
+        Some((k, v))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        JsonString: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let i = *self.index.get(key)?;
+        Some(&mut self.entries[i].1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        JsonString: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.contains_key(key)
+    }
+
+    /// Insert at the end, or replace the value of an existing key where it
+    /// stands, returning the old value.
+    pub fn insert(&mut self, key: JsonString, value: V) -> Option<V> {
+        if let Some(&i) = self.index.get(&key) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        None
+    }
+
+    /// Remove `key`, keeping the order of the others. Takes time linear in
+    /// the number of later entries.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        JsonString: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// [`remove`](OrderedMap::remove), returning the stored key too.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(JsonString, V)>
+    where
+        JsonString: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let i = self.index.remove(key)?;
+        let entry = self.entries.remove(i);
+        for (k, _) in &self.entries[i..] {
+            *self
+                .index
+                .get_mut::<JsonString>(k)
+                .expect("every entry is indexed") -= 1;
+        }
+        Some(entry)
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&JsonString, &mut V) -> bool) {
+        self.entries.retain_mut(|(k, v)| keep(k, v));
+        self.reindex();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, V> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &JsonString> + '_ {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> + '_ {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, (k, _)) in self.entries.iter().enumerate() {
+            self.index.insert(k.clone(), i);
+        }
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for OrderedMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V: PartialEq> PartialEq for OrderedMap<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<Q, V> Index<&Q> for OrderedMap<V>
+where
+    JsonString: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not in map")
+    }
+}
+
+impl<V> FromIterator<(JsonString, V)> for OrderedMap<V> {
+    fn from_iter<I: IntoIterator<Item = (JsonString, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V> Extend<(JsonString, V)> for OrderedMap<V> {
+    fn extend<I: IntoIterator<Item = (JsonString, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<V> IntoIterator for OrderedMap<V> {
+    type Item = (JsonString, V);
+    type IntoIter = vec::IntoIter<(JsonString, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a OrderedMap<V> {
+    type Item = (&'a JsonString, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a mut OrderedMap<V> {
+    type Item = (&'a JsonString, &'a mut V);
+    type IntoIter = IterMut<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// The entries of an [`OrderedMap`] in order.
+pub struct Iter<'a, V>(slice::Iter<'a, (JsonString, V)>);
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a JsonString, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for Iter<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<V> ExactSizeIterator for Iter<'_, V> {}
+
+/// The entries of an [`OrderedMap`] in order, values mutable.
+pub struct IterMut<'a, V>(slice::IterMut<'a, (JsonString, V)>);
+
+impl<'a, V> Iterator for IterMut<'a, V> {
+    type Item = (&'a JsonString, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for IterMut<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<V> ExactSizeIterator for IterMut<'_, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_map_should_keep_insertion_order() {
+        let mut map: OrderedMap<i32> = [("b", 1), ("a", 2), ("c", 3)]
+            .into_iter()
+            .map(|(k, v)| (JsonString::from(k), v))
+            .collect();
+        assert_eq!(map.insert("a".into(), 4), Some(2));
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(map.remove("b"), Some(1));
+        map.insert("d".into(), 5);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [
+                (&JsonString::from("a"), &4),
+                (&JsonString::from("c"), &3),
+                (&JsonString::from("d"), &5)
+            ]
+        );
+        assert_eq!((map["c"], map.get("b"), map.len()), (3, None, 3));
+        map.retain(|k, _| k != "c");
+        assert_eq!(map.get("d"), Some(&5));
+        let reversed: OrderedMap<i32> = map.clone().into_iter().rev().collect();
+        assert_eq!(reversed, map);
+        assert_eq!(format!("{:?}", reversed), r#"{"d": 5, "a": 4}"#);
+    }
+}
@@ -0,0 +1,187 @@
+use super::{parse_json, sep_with_ws, strict, ws, JsonError, JsonValue};
+use std::{num::NonZeroUsize, thread};
+use winnow::prelude::*;
+
+/// Inputs shorter than this are parsed on the calling thread.
+const PARALLEL_THRESHOLD: usize = 1 << 20;
+
+/// Parse a top-level array like [`parse_json`], splitting the elements
+/// across threads. A quick scan that only tracks strings and nesting finds
+/// where each element starts and ends, then each thread parses a run of
+/// elements. Any input the scan or a thread cannot handle is parsed again
+/// sequentially, so errors are exactly those of [`parse_json`], offsets
+/// included.
+pub fn parse_large_array_parallel(input: &str) -> Result<Vec<JsonValue>, JsonError> {
+    if input.len() >= PARALLEL_THRESHOLD {
+        if let Some(values) = split_elements(input).and_then(|spans| parse_spans(input, &spans)) {
+            return Ok(values);
+        }
+    }
+    parse_sequential(input)
+}
+
+fn parse_sequential(input: &str) -> Result<Vec<JsonValue>, JsonError> {
+    let mut rest = strict(input);
+    (ws, sep_with_ws('['))
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?;
+    match parse_json(input)? {
+        JsonValue::Array(values) => Ok(values),
+        _ => unreachable!("the input opens an array"),
+    }
+}
+
+/// Parse each span on one of the available threads, keeping their order.
+/// `None` if any of them fails.
+fn parse_spans(input: &str, spans: &[(usize, usize)]) -> Option<Vec<JsonValue>> {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let per_thread = spans.len().div_ceil(threads).max(1);
+    let parts: Vec<Option<Vec<JsonValue>>> = thread::scope(|scope| {
+        let handles: Vec<_> = spans
+            .chunks(per_thread)
+            .map(|part| {
+                scope.spawn(move || {
+                    part.iter()
+                        .map(|&(start, end)| parse_json(&input[start..end]).ok())
+                        .collect()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("parsing does not panic"))
+            .collect()
+    });
+    let mut values = Vec::with_capacity(spans.len());
+    for part in parts {
+        values.extend(part?);
+    }
+    Some(values)
+}
+
+/// The byte ranges of the elements of a top-level array, found without
+/// parsing them: commas and brackets only count outside strings, and only
+/// commas at depth one separate elements. `None` when the input does not
+/// have that shape; the elements themselves are not checked.
+fn split_elements(input: &str) -> Option<Vec<(usize, usize)>> {
+    let bytes = input.as_bytes();
+    let is_ws = |b: &u8| matches!(b, b' ' | b'\t' | b'\n' | b'\r');
+    let mut i = bytes.iter().position(|b| !is_ws(b))?;
+    if bytes[i] != b'[' {
+        return None;
+    }
+    i += 1;
+    let mut spans = Vec::new();
+    let mut start = i;
+    let mut depth = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => loop {
+                // no byte of a multibyte character is a quote or backslash
+                i += 1 + memchr::memchr2(b'"', b'\\', &bytes[i + 1..])?;
+                if bytes[i] == b'"' {
+                    break;
+                }
+                i += 1;
+            },
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' if depth > 0 => depth -= 1,
+            b',' if depth == 0 => {
+                spans.push((start, i));
+                start = i + 1;
+            }
+            b']' => {
+                if !spans.is_empty() || !bytes[start..i].iter().all(is_ws) {
+                    spans.push((start, i));
+                }
+                return bytes[i + 1..].iter().all(is_ws).then_some(spans);
+            }
+            b'}' => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_array() -> String {
+        let records: Vec<String> = (0..20_000)
+            .map(|i| {
+                format!(
+                    r#"{{"id": {}, "name": "n,]{}\"}}", "tags": [[], {{"x": [{}.5]}}], "ok": null}}"#,
+                    i, i, i
+                )
+            })
+            .collect();
+        format!(" [\n{}\n] ", records.join(",\n"))
+    }
+
+    #[test]
+    fn parse_large_array_parallel_should_match_parse_json() {
+        let input = large_array();
+        assert!(input.len() >= PARALLEL_THRESHOLD);
+        let JsonValue::Array(expected) = parse_json(&input).unwrap() else {
+            panic!("not an array")
+        };
+        assert_eq!(parse_large_array_parallel(&input).unwrap(), expected);
+        assert_eq!(
+            split_elements(&input).map(|spans| spans.len()),
+            Some(20_000)
+        );
+        for input in ["[]", " [ ] ", "[1, \"a\"]", "[[1, 2], {\"a\": []}]"] {
+            let JsonValue::Array(expected) = parse_json(input).unwrap() else {
+                panic!("not an array")
+            };
+            assert_eq!(parse_large_array_parallel(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn split_elements_should_not_split_inside_strings() {
+        let input = r#"["a,]b", "c\"],[", {"d": "}"}, [","]]"#;
+        let elements: Vec<&str> = split_elements(input)
+            .unwrap()
+            .into_iter()
+            .map(|(start, end)| input[start..end].trim())
+            .collect();
+        assert_eq!(
+            elements,
+            [r#""a,]b""#, r#""c\"],[""#, r#"{"d": "}"}"#, r#"[","]"#]
+        );
+        for input in ["{}", "[1", "[1]]", "[\"]", "[1} ", "[1] x"] {
+            assert_eq!(split_elements(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_large_array_parallel_should_report_true_offsets() {
+        let mut input = large_array();
+        let at = input.find("\"id\": 15000").unwrap();
+        // drop the colon; the parser stops at the number after it
+        input.replace_range(at..at + 5, "\"id\" ");
+        let err = parse_large_array_parallel(&input).unwrap_err();
+        assert_eq!(err, parse_json(&input).unwrap_err());
+        assert!(
+            matches!(err, JsonError::Parse { offset, .. } if offset == at + 6),
+            "{:?}",
+            err
+        );
+
+        for input in ["{}", "[1,]", "[1] 2", ""] {
+            let expected = match parse_json(input) {
+                Ok(_) => parse_sequential(input).unwrap_err(),
+                Err(e) => e,
+            };
+            assert_eq!(
+                parse_large_array_parallel(input),
+                Err(expected),
+                "{}",
+                input
+            );
+        }
+    }
+}
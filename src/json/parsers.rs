@@ -0,0 +1,198 @@
+//! The JSON grammar as winnow parsers, to embed in a larger grammar.
+//!
+//! Each one is a plain `fn(&mut &str) -> PResult<T, E>` for any error type
+//! `E` the surrounding grammar uses, so it works as a [`Parser`] directly:
+//!
+//! ```
+//! use grammar::json::{parsers, JsonValue};
+//! use winnow::{error::ContextError, prelude::*};
+//!
+//! let mut input = r#"[1, "two"] rest"#;
+//! let value = parsers::value::<ContextError>.parse_next(&mut input).unwrap();
+//! assert_eq!(value, JsonValue::Array(vec![1i64.into(), "two".into()]));
+//! assert_eq!(input, " rest");
+//! ```
+//!
+//! They accept strict JSON and consume exactly one token or value, with no
+//! whitespace before or after it; [`ws`] skips whitespace between them.
+//! Errors carry the same labels as [`parse_json`](super::parse_json)'s, as
+//! [`StrContext::Label`] contexts.
+
+use super::{
+    parse_array, parse_bool, parse_null, parse_num, parse_object, parse_string, parse_value,
+    strict, Input, JsonObject, JsonString, JsonValue, Num,
+};
+use winnow::{
+    ascii::multispace0,
+    error::{AddContext, ContextError, ErrorKind, ParserError, StrContext},
+    prelude::*,
+    stream::Stream,
+};
+
+/// Any JSON value.
+pub fn value<'i, E>(input: &mut &'i str) -> PResult<JsonValue, E>
+where
+    E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+{
+    run(input, parse_value)
+}
+
+/// A string literal, unescaped.
+pub fn string<'i, E>(input: &mut &'i str) -> PResult<JsonString, E>
+where
+    E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+{
+    run(input, parse_string)
+}
+
+/// A number, as an `Int` when it has no fraction or exponent.
+pub fn number<'i, E>(input: &mut &'i str) -> PResult<Num, E>
+where
+    E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+{
+    run(input, parse_num)
+}
+
+/// `true` or `false`.
+pub fn boolean<'i, E>(input: &mut &'i str) -> PResult<bool, E>
+where
+    E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+{
+    run(input, parse_bool)
+}
+
+/// `null`.
+pub fn null<'i, E>(input: &mut &'i str) -> PResult<(), E>
+where
+    E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+{
+    run(input, parse_null)
+}
+
+/// An array, from `[` to `]`.
+pub fn array<'i, E>(input: &mut &'i str) -> PResult<Vec<JsonValue>, E>
+where
+    E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+{
+    run(input, parse_array)
+}
+
+/// An object, from `{` to `}`.
+pub fn object<'i, E>(input: &mut &'i str) -> PResult<JsonObject, E>
+where
+    E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+{
+    run(input, parse_object)
+}
+
+/// Whitespace as JSON defines it, possibly none.
+pub fn ws<'i, E>(input: &mut &'i str) -> PResult<(), E>
+where
+    E: ParserError<&'i str>,
+{
+    multispace0.void().parse_next(input)
+}
+
+/// Run one of the crate's own parsers on `input`, translating its errors
+/// to `E`. Containers take the whitespace after their closing bracket
+/// along, which is handed back, since no value ends in whitespace.
+fn run<'i, O, E>(
+    input: &mut &'i str,
+    mut parser: impl Parser<Input<'i>, O, ContextError>,
+) -> PResult<O, E>
+where
+    E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+{
+    let start = input.checkpoint();
+    let original = *input;
+    let mut stateful = strict(original);
+    let result = parser.parse_next(&mut stateful);
+    *input = stateful.input;
+    match result {
+        Ok(output) => {
+            let consumed = &original[..original.len() - input.len()];
+            let value = consumed.trim_end_matches([' ', '\t', '\n', '\r']);
+            *input = &original[value.len()..];
+            Ok(output)
+        }
+        Err(e) => Err(e.map(|e: ContextError| {
+            let mut err = E::from_error_kind(input, ErrorKind::Fail);
+            for context in e.context() {
+                err = err.add_context(input, &start, context.clone());
+            }
+            err
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use winnow::{
+        ascii::{line_ending, space0},
+        combinator::{delimited, separated, separated_pair},
+        error::{ErrMode, InputError},
+        token::take_while,
+    };
+
+    /// `key = value` lines, the values being JSON.
+    fn settings<'i, E>(input: &mut &'i str) -> PResult<Vec<(&'i str, JsonValue)>, E>
+    where
+        E: ParserError<&'i str> + AddContext<&'i str, StrContext>,
+    {
+        let key = take_while(1.., |c: char| c.is_ascii_alphanumeric() || c == '_');
+        let line = separated_pair(key, delimited(space0, '=', space0), value);
+        separated(0.., line, line_ending).parse_next(input)
+    }
+
+    #[test]
+    fn value_should_embed_in_another_grammar() {
+        let input = "name = \"demo\"\nports=[80, 443]\nmeta = {\"a\": [null, true]}\nratio=0.5";
+        let expected = [
+            ("name", "\"demo\""),
+            ("ports", "[80, 443]"),
+            ("meta", r#"{"a": [null, true]}"#),
+            ("ratio", "0.5"),
+        ]
+        .map(|(k, v)| (k, parse_json(v).unwrap()));
+
+        let lines = settings::<ContextError>.parse(input).unwrap();
+        assert_eq!(lines, expected);
+        // the same parsers under an error type that records the input
+        let lines = settings::<InputError<&str>>.parse(input).unwrap();
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn parsers_should_report_errors_in_the_callers_error_type() {
+        let mut input = "x=[1,]";
+        let err = settings::<ContextError>.parse_next(&mut input).unwrap_err();
+        let ErrMode::Cut(err) = err else {
+            panic!("{:?}", err)
+        };
+        assert_eq!(
+            err.context().collect::<Vec<_>>(),
+            [&StrContext::Label(
+                "trailing commas are not allowed; enable allow_trailing_commas"
+            )]
+        );
+
+        let mut input = "tru";
+        assert!(matches!(
+            boolean::<InputError<&str>>.parse_next(&mut input),
+            Err(ErrMode::Backtrack(_))
+        ));
+        let mut input = "-12.5e1,";
+        assert_eq!(number::<ContextError>(&mut input), Ok(Num::Float(-125.0)));
+        assert_eq!(input, ",");
+        let mut input = "\"a\\nb\"";
+        assert_eq!(string::<ContextError>(&mut input).unwrap(), "a\nb");
+        let mut input = "{} \n";
+        assert_eq!(
+            object::<ContextError>(&mut input),
+            Ok(JsonObject::default())
+        );
+        assert_eq!(input, " \n");
+    }
+}
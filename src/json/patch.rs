@@ -0,0 +1,804 @@
+use super::{
+    compare::{CompareOptions, Comparer},
+    error::escape_pointer_token,
+    json_string,
+    pointer::{array_index, tokens},
+    redact::States,
+    JsonObject, JsonValue, Num,
+};
+use thiserror::Error;
+
+/// One operation of an RFC 6902 JSON Patch. Paths are JSON Pointers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOp {
+    Add { path: String, value: JsonValue },
+    Remove { path: String },
+    Replace { path: String, value: JsonValue },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: JsonValue },
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PatchError {
+    /// The patch document is not an array of well-formed operations.
+    #[error("operation {index}: {message}")]
+    Invalid { index: usize, message: String },
+    /// An operation addresses a location that does not exist.
+    #[error("operation {index}: nothing at \"{path}\"")]
+    NotFound { index: usize, path: String },
+    /// A `test` operation found a different value.
+    #[error("operation {index}: test failed at \"{path}\"")]
+    TestFailed { index: usize, path: String },
+}
+
+/// The patch that turns `a` into `b`, using `add`, `remove` and `replace`.
+/// Values are compared semantically: `1` and `1.0` are the same number and
+/// key order is ignored. Members are visited in key order, so the patch is
+/// the same whatever map backs [`JsonObject`]. Arrays are compared index by
+/// index, with elements added or removed at the end.
+pub fn diff(a: &JsonValue, b: &JsonValue) -> Vec<PatchOp> {
+    changes(a, b, &Comparer::new(&CompareOptions::default()))
+}
+
+fn changes(a: &JsonValue, b: &JsonValue, compare: &Comparer<'_>) -> Vec<PatchOp> {
+    let mut patch = Vec::new();
+    diff_into(
+        &mut patch,
+        &mut String::new(),
+        a,
+        b,
+        compare,
+        &compare.start(),
+    );
+    patch
+}
+
+fn diff_into(
+    patch: &mut Vec<PatchOp>,
+    path: &mut String,
+    a: &JsonValue,
+    b: &JsonValue,
+    compare: &Comparer<'_>,
+    states: &States,
+) {
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            let mut keys: Vec<_> = a
+                .keys()
+                .chain(b.keys().filter(|k| !a.contains_key(*k)))
+                .collect();
+            keys.sort();
+            for key in keys {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&escape_pointer_token(key));
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => {
+                        diff_into(patch, path, x, y, compare, &compare.member(states, key))
+                    }
+                    (Some(_), None) => patch.push(PatchOp::Remove { path: path.clone() }),
+                    (None, Some(y)) => patch.push(PatchOp::Add {
+                        path: path.clone(),
+                        value: y.clone(),
+                    }),
+                    (None, None) => unreachable!("key comes from one side"),
+                }
+                path.truncate(len);
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b))
+            if compare.is_unordered(states) && compare.same_elements(a, b, states) => {}
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            let len = path.len();
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                path.push_str(&format!("/{}", i));
+                diff_into(patch, path, x, y, compare, &compare.element(states, i));
+                path.truncate(len);
+            }
+            for (i, y) in b.iter().enumerate().skip(a.len()) {
+                patch.push(PatchOp::Add {
+                    path: format!("{}/{}", path, i),
+                    value: y.clone(),
+                });
+            }
+            // from the end, so each index is still the original one
+            for i in (b.len()..a.len()).rev() {
+                patch.push(PatchOp::Remove {
+                    path: format!("{}/{}", path, i),
+                });
+            }
+        }
+        _ if same_value(a, b) => {}
+        _ => patch.push(PatchOp::Replace {
+            path: path.clone(),
+            value: b.clone(),
+        }),
+    }
+}
+
+/// Options for [`diff_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Turn a subtree removed in one place and added in another into a
+    /// `move`, and the addition of a subtree that is already in the
+    /// document into a `copy`. Each one is kept only if the patch still
+    /// turns `a` into `b`, which takes applying it, so this is slower.
+    pub detect_moves: bool,
+    /// The fewest values, nested ones included, a subtree needs to be
+    /// moved or copied; smaller ones are clearer spelled out in an `add`.
+    pub min_move_size: usize,
+    /// Leave out arrays that hold the same elements in another order, as
+    /// [`CompareOptions::unordered_arrays`] compares them. Arrays that
+    /// differ otherwise are still diffed index by index, so applying the
+    /// patch gives a document equal to `b` when compared the same way.
+    pub unordered_arrays: bool,
+    /// [`unordered_arrays`](Self::unordered_arrays) for just the arrays at
+    /// these patterns, as [`CompareOptions::unordered_at`] takes them.
+    pub unordered_at: Vec<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            detect_moves: false,
+            min_move_size: 2,
+            unordered_arrays: false,
+            unordered_at: Vec::new(),
+        }
+    }
+}
+
+/// [`diff`] with `options`.
+pub fn diff_with(a: &JsonValue, b: &JsonValue, options: &DiffOptions) -> Vec<PatchOp> {
+    let unordered = CompareOptions {
+        unordered_arrays: options.unordered_arrays,
+        unordered_at: options.unordered_at.clone(),
+        ..CompareOptions::default()
+    };
+    let compare = Comparer::new(&unordered);
+    let mut patch = changes(a, b, &compare);
+    if options.detect_moves {
+        find_moves(&mut patch, a, b, &compare, options.min_move_size);
+    }
+    patch
+}
+
+/// Replace the `add`s of `patch` that can be a `move` or a `copy` instead,
+/// trying every candidate on `a` so that only patches giving `b`, or what
+/// `compare` takes for it, are kept.
+fn find_moves(
+    patch: &mut Vec<PatchOp>,
+    a: &JsonValue,
+    b: &JsonValue,
+    compare: &Comparer<'_>,
+    min_size: usize,
+) {
+    let applies = |patch: &[PatchOp]| {
+        let mut patched = a.clone();
+        apply_patch(&mut patched, patch).is_ok()
+            && (same_value(&patched, b) || compare.same(&patched, b, &compare.start()))
+    };
+    // subtrees the patch leaves alone, and those it adds
+    let mut unchanged = Vec::new();
+    unchanged_subtrees(&mut unchanged, &mut String::new(), a, b, min_size);
+    let mut added: Vec<(String, JsonValue)> = Vec::new();
+    let mut i = 0;
+    while i < patch.len() {
+        let PatchOp::Add { path, value } = &patch[i] else {
+            i += 1;
+            continue;
+        };
+        let (path, value) = (path.clone(), value.clone());
+        if size(&value) < min_size {
+            i += 1;
+            continue;
+        }
+        let mut candidates = Vec::new();
+        for (j, op) in patch.iter().enumerate() {
+            let PatchOp::Remove { path: from } = op else {
+                continue;
+            };
+            if a.pointer(from) != Some(&value) {
+                continue;
+            }
+            let op = PatchOp::Move {
+                from: from.clone(),
+                path: path.clone(),
+            };
+            // in the place of the add, or else of the remove
+            let mut at_add = patch.clone();
+            at_add[i] = op.clone();
+            at_add.remove(j);
+            candidates.push((at_add, if j < i { i } else { i + 1 }));
+            let mut at_remove = patch.clone();
+            at_remove[j] = op;
+            at_remove.remove(i);
+            candidates.push((at_remove, i));
+        }
+        let sources = unchanged
+            .iter()
+            .map(|(from, v)| (from, *v))
+            .chain(added.iter().map(|(from, v)| (from, v)));
+        for (from, _) in sources.filter(|(_, v)| **v == value) {
+            let mut copied = patch.clone();
+            copied[i] = PatchOp::Copy {
+                from: from.clone(),
+                path: path.clone(),
+            };
+            candidates.push((copied, i + 1));
+        }
+        match candidates.into_iter().find(|(p, _)| applies(p)) {
+            Some((found, next)) => {
+                *patch = found;
+                i = next;
+            }
+            None => i += 1,
+        }
+        added.push((path, value));
+    }
+}
+
+/// The subtrees of `a` that `b` has at the same place, with at least
+/// `min_size` values.
+fn unchanged_subtrees<'a>(
+    found: &mut Vec<(String, &'a JsonValue)>,
+    path: &mut String,
+    a: &'a JsonValue,
+    b: &JsonValue,
+    min_size: usize,
+) {
+    if a == b && size(a) >= min_size {
+        found.push((path.clone(), a));
+    }
+    let len = path.len();
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            for (key, x) in a {
+                if let Some(y) = b.get(key) {
+                    path.push('/');
+                    path.push_str(&escape_pointer_token(key));
+                    unchanged_subtrees(found, path, x, y, min_size);
+                    path.truncate(len);
+                }
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                path.push_str(&format!("/{}", i));
+                unchanged_subtrees(found, path, x, y, min_size);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// How many values `value` is made of, counting itself.
+fn size(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::Array(arr) => 1 + arr.iter().map(size).sum::<usize>(),
+        JsonValue::Object(obj) => 1 + obj.values().map(size).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+/// Deep equality with numbers compared by value, so `1`, `1.0` and raw
+/// `1e0` are all the same.
+pub(crate) fn same_value(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Number(x), JsonValue::Number(y)) => same_number(x, y),
+        (JsonValue::Array(x), JsonValue::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| same_value(x, y))
+        }
+        (JsonValue::Object(x), JsonValue::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, v)| y.get(k).is_some_and(|w| same_value(v, w)))
+        }
+        (JsonValue::Raw(x), _) => x.parse().is_ok_and(|x| same_value(&x, b)),
+        (_, JsonValue::Raw(y)) => y.parse().is_ok_and(|y| same_value(a, &y)),
+        _ => a == b,
+    }
+}
+
+pub(crate) fn same_number(a: &Num, b: &Num) -> bool {
+    let float = |n: &Num| match n {
+        Num::Int(i) => Some(*i as f64),
+        Num::Float(f) => Some(*f),
+        Num::Raw(s) => s.parse().ok(),
+        #[cfg(feature = "decimal")]
+        Num::Decimal(d) => Some(d.to_f64()),
+    };
+    match (a, b) {
+        // exact, since large integers do not survive the trip through f64
+        (Num::Int(x), Num::Int(y)) => x == y,
+        #[cfg(feature = "decimal")]
+        (Num::Decimal(_), _) | (_, Num::Decimal(_)) => match (a.as_decimal(), b.as_decimal()) {
+            (Some(x), Some(y)) => x.same_value(&y),
+            _ => float(a).is_some_and(|x| float(b) == Some(x)),
+        },
+        _ => float(a).is_some_and(|x| float(b) == Some(x)),
+    }
+}
+
+/// Apply `patch` to `value`. Either every operation succeeds or `value` is
+/// left as it was.
+pub fn apply_patch(value: &mut JsonValue, patch: &[PatchOp]) -> Result<(), PatchError> {
+    let mut patched = value.clone();
+    for (index, op) in patch.iter().enumerate() {
+        apply_op(&mut patched, op, index)?;
+    }
+    *value = patched;
+    Ok(())
+}
+
+fn apply_op(value: &mut JsonValue, op: &PatchOp, index: usize) -> Result<(), PatchError> {
+    let not_found = |path: &str| PatchError::NotFound {
+        index,
+        path: path.to_string(),
+    };
+    match op {
+        PatchOp::Add { path, value: new } => {
+            add(value, path, new.clone()).ok_or_else(|| not_found(path))
+        }
+        PatchOp::Remove { path } => remove(value, path).map(drop).ok_or_else(|| not_found(path)),
+        PatchOp::Replace { path, value: new } => {
+            *value.pointer_mut(path).ok_or_else(|| not_found(path))? = new.clone();
+            Ok(())
+        }
+        PatchOp::Move { from, path } => {
+            if path
+                .strip_prefix(from.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+            {
+                return Err(PatchError::Invalid {
+                    index,
+                    message: format!("cannot move \"{}\" into itself", from),
+                });
+            }
+            let moved = remove(value, from).ok_or_else(|| not_found(from))?;
+            add(value, path, moved).ok_or_else(|| not_found(path))
+        }
+        PatchOp::Copy { from, path } => {
+            let copied = value.pointer(from).ok_or_else(|| not_found(from))?.clone();
+            add(value, path, copied).ok_or_else(|| not_found(path))
+        }
+        PatchOp::Test {
+            path,
+            value: expected,
+        } => {
+            let found = value.pointer(path).ok_or_else(|| not_found(path))?;
+            if !same_value(found, expected) {
+                return Err(PatchError::TestFailed {
+                    index,
+                    path: path.clone(),
+                });
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The container holding the value at `path`, and the last token of it.
+/// `None` for the empty path and for a missing container.
+fn parent<'v>(value: &'v mut JsonValue, path: &str) -> Option<(&'v mut JsonValue, String)> {
+    let split = path.rfind('/')?;
+    let last = tokens(&path[split..])?.next()?.into_owned();
+    Some((value.pointer_mut(&path[..split])?, last))
+}
+
+/// Add `new` at `path`: into an object, replacing any member of that name,
+/// or into an array, before the element at that index or at the end for `-`.
+fn add(value: &mut JsonValue, path: &str, new: JsonValue) -> Option<()> {
+    if path.is_empty() {
+        *value = new;
+        return Some(());
+    }
+    match parent(value, path)? {
+        (JsonValue::Object(obj), key) => {
+            obj.insert(json_string(key), new);
+        }
+        (JsonValue::Array(arr), token) => {
+            let i = if token == "-" {
+                arr.len()
+            } else {
+                array_index(&token)?
+            };
+            if i > arr.len() {
+                return None;
+            }
+            arr.insert(i, new);
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+fn remove(value: &mut JsonValue, path: &str) -> Option<JsonValue> {
+    match parent(value, path)? {
+        (JsonValue::Object(obj), key) => obj.remove(key.as_str()),
+        (JsonValue::Array(arr), token) => {
+            let i = array_index(&token)?;
+            (i < arr.len()).then(|| arr.remove(i))
+        }
+        _ => None,
+    }
+}
+
+impl PatchOp {
+    /// The name of the operation, as in the `op` member.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatchOp::Add { .. } => "add",
+            PatchOp::Remove { .. } => "remove",
+            PatchOp::Replace { .. } => "replace",
+            PatchOp::Move { .. } => "move",
+            PatchOp::Copy { .. } => "copy",
+            PatchOp::Test { .. } => "test",
+        }
+    }
+
+    /// The location the operation acts on.
+    pub fn path(&self) -> &str {
+        match self {
+            PatchOp::Add { path, .. }
+            | PatchOp::Remove { path }
+            | PatchOp::Replace { path, .. }
+            | PatchOp::Move { path, .. }
+            | PatchOp::Copy { path, .. }
+            | PatchOp::Test { path, .. } => path,
+        }
+    }
+
+    /// The operation as a JSON Patch object, such as
+    /// `{"op": "add", "path": "/a", "value": 1}`.
+    pub fn to_json(&self) -> JsonValue {
+        let mut obj = JsonObject::default();
+        obj.insert("op".into(), self.name().into());
+        obj.insert("path".into(), self.path().into());
+        match self {
+            PatchOp::Add { value, .. }
+            | PatchOp::Replace { value, .. }
+            | PatchOp::Test { value, .. } => {
+                obj.insert("value".into(), value.clone());
+            }
+            PatchOp::Move { from, .. } | PatchOp::Copy { from, .. } => {
+                obj.insert("from".into(), from.as_str().into());
+            }
+            PatchOp::Remove { .. } => {}
+        }
+        JsonValue::Object(obj)
+    }
+}
+
+/// `patch` as a JSON Patch document, an array of operation objects.
+pub fn patch_to_json(patch: &[PatchOp]) -> JsonValue {
+    JsonValue::Array(patch.iter().map(PatchOp::to_json).collect())
+}
+
+/// Read a JSON Patch document. Members other than those of the operation
+/// are ignored, as RFC 6902 requires.
+pub fn patch_from_json(doc: &JsonValue) -> Result<Vec<PatchOp>, PatchError> {
+    let JsonValue::Array(ops) = doc else {
+        return Err(PatchError::Invalid {
+            index: 0,
+            message: "a patch is an array of operations".to_string(),
+        });
+    };
+    ops.iter()
+        .enumerate()
+        .map(|(index, op)| {
+            let invalid = |message: String| PatchError::Invalid { index, message };
+            let JsonValue::Object(op) = op else {
+                return Err(invalid("an operation is an object".to_string()));
+            };
+            let text = |name: &str| match op.get(name) {
+                Some(JsonValue::String(s)) => Ok(s.to_string()),
+                _ => Err(invalid(format!("missing string member {:?}", name))),
+            };
+            let value = || {
+                op.get("value")
+                    .cloned()
+                    .ok_or_else(|| invalid("missing member \"value\"".to_string()))
+            };
+            let path = text("path")?;
+            Ok(match text("op")?.as_str() {
+                "add" => PatchOp::Add {
+                    path,
+                    value: value()?,
+                },
+                "remove" => PatchOp::Remove { path },
+                "replace" => PatchOp::Replace {
+                    path,
+                    value: value()?,
+                },
+                "move" => PatchOp::Move {
+                    from: text("from")?,
+                    path,
+                },
+                "copy" => PatchOp::Copy {
+                    from: text("from")?,
+                    path,
+                },
+                "test" => PatchOp::Test {
+                    path,
+                    value: value()?,
+                },
+                other => return Err(invalid(format!("unknown op {:?}", other))),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn diff_should_produce_a_patch_that_applies() {
+        let pairs = [
+            (
+                r#"{"a": 1, "b": {"c": [1, 2, 3], "d": "x"}, "e/f": true}"#,
+                r#"{"a": 1.0, "b": {"c": [1, 5], "d": "x", "g": null}, "h": []}"#,
+            ),
+            (r#"[1, [2], {"k": 3}]"#, r#"[1, [2, 3], {"k": 4}, "new"]"#),
+            (r#"{"a": 1}"#, "[1]"),
+            ("1e2", "100"),
+        ];
+        for (a, b) in pairs {
+            let (a, b) = (parse_json(a).unwrap(), parse_json(b).unwrap());
+            let patch = diff(&a, &b);
+            let mut patched = a.clone();
+            apply_patch(&mut patched, &patch).unwrap();
+            assert!(same_value(&patched, &b), "{:?}", patch);
+            assert_eq!(patch_from_json(&patch_to_json(&patch)).unwrap(), patch);
+        }
+
+        let a = parse_json(r#"{"a": 1, "b": [1, 2, 3], "m~n": "x", "same": {"k": [1]}}"#).unwrap();
+        let b = parse_json(r#"{"a": 1.0, "b": [1], "m~n": "y", "new": 2, "same": {"k": [1]}}"#)
+            .unwrap();
+        assert_eq!(
+            diff(&a, &b),
+            [
+                PatchOp::Remove {
+                    path: "/b/2".into()
+                },
+                PatchOp::Remove {
+                    path: "/b/1".into()
+                },
+                PatchOp::Replace {
+                    path: "/m~0n".into(),
+                    value: "y".into()
+                },
+                PatchOp::Add {
+                    path: "/new".into(),
+                    value: 2.into()
+                },
+            ]
+        );
+        assert_eq!(diff(&a, &a), []);
+    }
+
+    #[test]
+    fn diff_with_should_turn_relocated_subtrees_into_moves() {
+        let moves = DiffOptions {
+            detect_moves: true,
+            ..DiffOptions::default()
+        };
+        let a = parse_json(r#"{"old": {"name": "x", "tags": [1, 2]}, "keep": 1}"#).unwrap();
+        let b = parse_json(r#"{"new": {"name": "x", "tags": [1, 2]}, "keep": 1}"#).unwrap();
+        assert_eq!(
+            diff_with(&a, &b, &moves),
+            [PatchOp::Move {
+                from: "/old".into(),
+                path: "/new".into()
+            }]
+        );
+        assert_eq!(diff_with(&a, &b, &DiffOptions::default()), diff(&a, &b));
+
+        let a = parse_json(r#"{"a": {"big": [1, 2, 3]}}"#).unwrap();
+        let b = parse_json(r#"{"a": {"big": [1, 2, 3]}, "b": [1, 2, 3], "c": [1, 2, 3]}"#).unwrap();
+        assert_eq!(
+            diff_with(&a, &b, &moves),
+            [
+                PatchOp::Copy {
+                    from: "/a/big".into(),
+                    path: "/b".into()
+                },
+                PatchOp::Copy {
+                    from: "/a/big".into(),
+                    path: "/c".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_with_should_produce_a_patch_that_applies() {
+        let moves = DiffOptions {
+            detect_moves: true,
+            ..DiffOptions::default()
+        };
+        let pairs = [
+            (
+                r#"{"a": {"b": {"deep": [1, 2]}}, "z": 1}"#,
+                r#"{"a": {}, "z": {"deep": [1, 2]}}"#,
+            ),
+            (
+                r#"{"list": [{"id": 1}, {"id": 2}, {"id": 3}]}"#,
+                r#"{"list": [{"id": 1}], "moved": [{"id": 3}, {"id": 2}]}"#,
+            ),
+            (
+                r#"[[1, 2], [3, 4], {"k": [5, 6]}]"#,
+                r#"[{"k": [5, 6]}, [3, 4], [1, 2], [1, 2]]"#,
+            ),
+            (
+                r#"{"x": {"y": [1, 2]}}"#,
+                r#"{"x": {"y": [1, 2], "z": {"y": [1, 2]}}}"#,
+            ),
+            (r#"{"a": [1, 2]}"#, r#"{"a": {"inner": [1, 2]}}"#),
+        ];
+        for (a, b) in pairs {
+            let (a, b) = (parse_json(a).unwrap(), parse_json(b).unwrap());
+            let patch = diff_with(&a, &b, &moves);
+            let mut patched = a.clone();
+            apply_patch(&mut patched, &patch).unwrap();
+            assert_eq!(patched, b, "{:?}", patch);
+            assert!(patch.len() <= diff(&a, &b).len(), "{:?}", patch);
+        }
+    }
+
+    #[test]
+    fn diff_with_should_leave_small_subtrees_as_adds() {
+        let a = parse_json(r#"{"from": "text", "obj": {"k": [1, 2]}}"#).unwrap();
+        let b = parse_json(r#"{"to": "text", "other": {"k": [1, 2]}}"#).unwrap();
+        let patch = diff_with(
+            &a,
+            &b,
+            &DiffOptions {
+                detect_moves: true,
+                ..DiffOptions::default()
+            },
+        );
+        let names: Vec<_> = patch.iter().map(PatchOp::name).collect();
+        assert_eq!(names, ["remove", "move", "add"], "{:?}", patch);
+        // {"k": [1, 2]} is 4 values
+        for (min_move_size, moves) in [(4, 1), (5, 0)] {
+            let patch = diff_with(
+                &a,
+                &b,
+                &DiffOptions {
+                    detect_moves: true,
+                    min_move_size,
+                    ..DiffOptions::default()
+                },
+            );
+            let count = patch.iter().filter(|op| op.name() == "move").count();
+            assert_eq!(count, moves, "{:?}", patch);
+        }
+    }
+
+    #[test]
+    fn diff_with_should_leave_unordered_arrays_alone() {
+        let a = parse_json(r#"{"tags": ["x", "y"], "list": [1, 2], "n": 1}"#).unwrap();
+        let b = parse_json(r#"{"tags": ["y", "x"], "list": [2, 1], "n": 2}"#).unwrap();
+        let tags = DiffOptions {
+            unordered_at: vec!["/tags".into()],
+            ..DiffOptions::default()
+        };
+        let patch = diff_with(&a, &b, &tags);
+        let replaced: Vec<_> = patch
+            .iter()
+            .map(|op| match op {
+                PatchOp::Replace { path, .. } => path.as_str(),
+                op => op.name(),
+            })
+            .collect();
+        assert_eq!(replaced, ["/list/0", "/list/1", "/n"]);
+        let all = DiffOptions {
+            unordered_arrays: true,
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            diff_with(&a, &b, &all),
+            [PatchOp::Replace {
+                path: "/n".into(),
+                value: 2i64.into()
+            }]
+        );
+        // arrays that differ as multisets are still diffed by index
+        let c = parse_json(r#"{"tags": ["y", "z"], "list": [2, 1], "n": 2}"#).unwrap();
+        let patch = diff_with(&a, &c, &all);
+        let mut patched = a.clone();
+        apply_patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched.pointer("/tags"), c.pointer("/tags"), "{:?}", patch);
+        let sets = CompareOptions::default().unordered_arrays(true);
+        assert!(crate::json::semantic_eq_with(&patched, &c, &sets));
+        assert_eq!(diff_with(&a, &b, &DiffOptions::default()), diff(&a, &b));
+    }
+
+    #[test]
+    fn apply_patch_should_follow_rfc6902() {
+        let mut doc = parse_json(r#"{"foo": ["bar", "baz"], "x": {"y": 1}}"#).unwrap();
+        let patch = patch_from_json(
+            &parse_json(
+                r#"[
+                    {"op": "add", "path": "/foo/1", "value": "qux"},
+                    {"op": "add", "path": "/foo/-", "value": "end"},
+                    {"op": "test", "path": "/foo/0", "value": "bar"},
+                    {"op": "move", "from": "/x/y", "path": "/z"},
+                    {"op": "copy", "from": "/foo/0", "path": "/x/w"},
+                    {"op": "remove", "path": "/foo/2"},
+                    {"op": "replace", "path": "/z", "value": 1.0}
+                ]"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        apply_patch(&mut doc, &patch).unwrap();
+        let expected =
+            parse_json(r#"{"foo": ["bar", "qux", "end"], "x": {"w": "bar"}, "z": 1.0}"#).unwrap();
+        assert_eq!(doc, expected);
+
+        // a failing operation leaves the document untouched
+        for (op, err) in [
+            (
+                PatchOp::Remove {
+                    path: "/missing".into(),
+                },
+                PatchError::NotFound {
+                    index: 1,
+                    path: "/missing".into(),
+                },
+            ),
+            (
+                PatchOp::Add {
+                    path: "/foo/9".into(),
+                    value: JsonValue::Null,
+                },
+                PatchError::NotFound {
+                    index: 1,
+                    path: "/foo/9".into(),
+                },
+            ),
+            (
+                PatchOp::Test {
+                    path: "/z".into(),
+                    value: 2.into(),
+                },
+                PatchError::TestFailed {
+                    index: 1,
+                    path: "/z".into(),
+                },
+            ),
+            (
+                PatchOp::Move {
+                    from: "/x".into(),
+                    path: "/x/w/v".into(),
+                },
+                PatchError::Invalid {
+                    index: 1,
+                    message: "cannot move \"/x\" into itself".into(),
+                },
+            ),
+        ] {
+            let patch = [
+                PatchOp::Replace {
+                    path: "/z".into(),
+                    value: 3.into(),
+                },
+                op,
+            ];
+            let mut patched = doc.clone();
+            assert_eq!(apply_patch(&mut patched, &patch), Err(err));
+            assert_eq!(patched, expected);
+        }
+        let bad = parse_json(r#"[{"op": "jump", "path": ""}]"#).unwrap();
+        assert!(matches!(
+            patch_from_json(&bad),
+            Err(PatchError::Invalid { index: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,881 @@
+use super::{
+    borrowed::string, entry_end, error::escape_pointer_token, json_string, parse_value,
+    schema::type_name, sep_with_ws, skip::skip, strict, ws, Input, JsonError, JsonObject,
+    JsonValue,
+};
+use std::{borrow::Cow, fmt, slice, str::FromStr};
+use thiserror::Error;
+use winnow::{
+    combinator::{cut_err, opt},
+    prelude::*,
+};
+
+impl JsonValue {
+    /// The value at `ptr`, an RFC 6901 JSON Pointer such as `/a/0/b~1c`,
+    /// given as text or as a parsed [`Pointer`]. The empty pointer is the
+    /// value itself; a malformed one addresses nothing.
+    pub fn pointer<P: AsPointer + ?Sized>(&self, ptr: &P) -> Option<&JsonValue> {
+        ptr.tokens()?.try_fold(self, |value, token| match value {
+            JsonValue::Object(obj) => obj.get(token.as_ref()),
+            JsonValue::Array(arr) => arr.get(array_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Like [`pointer`](Self::pointer), for changing the value in place.
+    pub fn pointer_mut<P: AsPointer + ?Sized>(&mut self, ptr: &P) -> Option<&mut JsonValue> {
+        ptr.tokens()?.try_fold(self, |value, token| match value {
+            JsonValue::Object(obj) => obj.get_mut(token.as_ref()),
+            JsonValue::Array(arr) => arr.get_mut(array_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// The value at the end of `path`, each segment a key taken as it is,
+    /// with no `~` or `/` escaping, or for an array a decimal index as in a
+    /// pointer. The empty path is the value itself. This walks the same
+    /// way as [`pointer`](Self::pointer) without building pointer text.
+    pub fn get_nested(&self, path: &[&str]) -> Option<&JsonValue> {
+        path.iter().try_fold(self, |value, token| match value {
+            JsonValue::Object(obj) => obj.get(*token),
+            JsonValue::Array(arr) => arr.get(array_index(token)?),
+            _ => None,
+        })
+    }
+
+    /// Like [`get_nested`](Self::get_nested), for changing the value in
+    /// place.
+    pub fn get_nested_mut(&mut self, path: &[&str]) -> Option<&mut JsonValue> {
+        path.iter().try_fold(self, |value, token| match value {
+            JsonValue::Object(obj) => obj.get_mut(*token),
+            JsonValue::Array(arr) => arr.get_mut(array_index(token)?),
+            _ => None,
+        })
+    }
+
+    /// The value at the end of `path`, where a [`PathSeg::Key`] only
+    /// steps into an object and a [`PathSeg::Index`] only into an array.
+    pub fn get_segments(&self, path: &[PathSeg<'_>]) -> Option<&JsonValue> {
+        path.iter().try_fold(self, |value, seg| match (value, seg) {
+            (JsonValue::Object(obj), PathSeg::Key(key)) => obj.get(*key),
+            (JsonValue::Array(arr), PathSeg::Index(i)) => arr.get(*i),
+            _ => None,
+        })
+    }
+
+    /// Like [`get_segments`](Self::get_segments), for changing the value in
+    /// place.
+    pub fn get_segments_mut(&mut self, path: &[PathSeg<'_>]) -> Option<&mut JsonValue> {
+        path.iter().try_fold(self, |value, seg| match (value, seg) {
+            (JsonValue::Object(obj), PathSeg::Key(key)) => obj.get_mut(*key),
+            (JsonValue::Array(arr), PathSeg::Index(i)) => arr.get_mut(*i),
+            _ => None,
+        })
+    }
+
+    /// Put `new` at `ptr`: as a member of an object, replacing any of that
+    /// name, or as an element of an array, replacing the one at that index
+    /// or appended for `-` or the index one past the end. The empty pointer
+    /// replaces the value itself. Returns whether there was such a place;
+    /// missing parents are not created.
+    pub fn set_pointer<P: AsPointer + ?Sized>(&mut self, ptr: &P, new: JsonValue) -> bool {
+        let Some(mut tokens) = ptr.tokens() else {
+            return false;
+        };
+        let Some(last) = tokens.next_back() else {
+            *self = new;
+            return true;
+        };
+        let parent = tokens.try_fold(self, |value, token| match value {
+            JsonValue::Object(obj) => obj.get_mut(token.as_ref()),
+            JsonValue::Array(arr) => arr.get_mut(array_index(&token)?),
+            _ => None,
+        });
+        match parent {
+            Some(JsonValue::Object(obj)) => {
+                obj.insert(json_string(last.as_ref()), new);
+                true
+            }
+            Some(JsonValue::Array(arr)) => {
+                let i = if last == "-" {
+                    Some(arr.len())
+                } else {
+                    array_index(&last)
+                };
+                match i {
+                    Some(i) if i < arr.len() => arr[i] = new,
+                    Some(i) if i == arr.len() => arr.push(new),
+                    _ => return false,
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The value at `ptr`, making it first as an empty object if it is
+    /// missing, along with any missing objects on the way. Elements of
+    /// arrays are never made: an array on the path must already hold the
+    /// element. A scalar on the path is an error; see
+    /// [`ensure_path_with`](Self::ensure_path_with).
+    pub fn ensure_path<P: AsPointer + ?Sized>(
+        &mut self,
+        ptr: &P,
+    ) -> Result<&mut JsonValue, JsonError> {
+        self.ensure_path_with(ptr, false)
+    }
+
+    /// [`ensure_path`](Self::ensure_path), replacing a scalar on the path
+    /// with an empty object when `replace_scalars` is set.
+    pub fn ensure_path_with<P: AsPointer + ?Sized>(
+        &mut self,
+        ptr: &P,
+        replace_scalars: bool,
+    ) -> Result<&mut JsonValue, JsonError> {
+        let tokens = ptr
+            .tokens()
+            .ok_or_else(|| path_error("", "malformed JSON Pointer"))?;
+        let mut at = String::new();
+        let mut value = self;
+        for token in tokens {
+            value = step_or_make(value, &token, replace_scalars, &at)?;
+            at.push('/');
+            at.push_str(&escape_pointer_token(&token));
+        }
+        Ok(value)
+    }
+
+    /// The value at `ptr`, putting `make()` there first if it is missing.
+    /// The path to it is made as by [`ensure_path`](Self::ensure_path), so
+    /// the last token may add a member to an object but not an element to
+    /// an array.
+    pub fn get_or_insert_with<P: AsPointer + ?Sized>(
+        &mut self,
+        ptr: &P,
+        make: impl FnOnce() -> JsonValue,
+    ) -> Result<&mut JsonValue, JsonError> {
+        let mut tokens = ptr
+            .tokens()
+            .ok_or_else(|| path_error("", "malformed JSON Pointer"))?;
+        let Some(last) = tokens.next_back() else {
+            return Ok(self);
+        };
+        let mut at = String::new();
+        let mut value = self;
+        for token in tokens {
+            value = step_or_make(value, &token, false, &at)?;
+            at.push('/');
+            at.push_str(&escape_pointer_token(&token));
+        }
+        if let JsonValue::Object(obj) = value {
+            if !obj.contains_key(last.as_ref()) {
+                obj.insert(json_string(last.as_ref()), make());
+            }
+        }
+        step_or_make(value, &last, false, &at)
+    }
+}
+
+/// The member or element `token` of `value`, which is at `at`, making a
+/// missing member as an empty object.
+fn step_or_make<'v>(
+    value: &'v mut JsonValue,
+    token: &str,
+    replace_scalars: bool,
+    at: &str,
+) -> Result<&'v mut JsonValue, JsonError> {
+    if replace_scalars && !matches!(value, JsonValue::Object(_) | JsonValue::Array(_)) {
+        *value = JsonValue::Object(JsonObject::default());
+    }
+    match value {
+        JsonValue::Object(obj) => {
+            if !obj.contains_key(token) {
+                obj.insert(json_string(token), JsonValue::Object(JsonObject::default()));
+            }
+            Ok(obj.get_mut(token).expect("inserted above"))
+        }
+        JsonValue::Array(arr) => {
+            let len = arr.len();
+            match array_index(token).filter(|&i| i < len) {
+                Some(i) => Ok(&mut arr[i]),
+                None => Err(path_error(
+                    at,
+                    format!("no element {:?} in an array of {}", token, len),
+                )),
+            }
+        }
+        other => Err(path_error(
+            at,
+            format!("a {} has no member {:?}", type_name(other), token),
+        )),
+    }
+}
+
+fn path_error(pointer: &str, message: impl Into<String>) -> JsonError {
+    JsonError::Path {
+        pointer: pointer.to_string(),
+        message: message.into(),
+    }
+}
+
+/// One step of a path for [`JsonValue::get_segments`]: a member of an
+/// object by its key, unescaped, or an element of an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSeg<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+impl<'a> From<&'a str> for PathSeg<'a> {
+    fn from(key: &'a str) -> Self {
+        PathSeg::Key(key)
+    }
+}
+
+impl From<usize> for PathSeg<'_> {
+    fn from(i: usize) -> Self {
+        PathSeg::Index(i)
+    }
+}
+
+/// A JSON Pointer parsed once into its unescaped reference tokens, for
+/// addressing the same place in many documents. Displays escaped again, so
+/// `Pointer::parse(s)?.to_string() == s`. The default is the empty pointer,
+/// which addresses the whole document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pointer {
+    tokens: Vec<String>,
+}
+
+/// Why text is not a JSON Pointer.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// A pointer that is not empty must start with `/`.
+    #[error("a JSON Pointer must be empty or start with '/'")]
+    NoLeadingSlash,
+    /// A `~` in reference token `token` (counting from 0), at byte
+    /// `offset`, is not followed by `0` or `1`.
+    #[error("invalid escape in reference token {token} at byte {offset}: '~' must be followed by '0' or '1'")]
+    BadEscape { token: usize, offset: usize },
+}
+
+impl Pointer {
+    /// Parse `ptr`, such as `/a/~0b/0`, decoding `~1` to `/` and `~0` to `~`.
+    pub fn parse(ptr: &str) -> Result<Self, PointerError> {
+        check(ptr)?;
+        Ok(Pointer {
+            tokens: unescaped(ptr).map(Cow::into_owned).collect(),
+        })
+    }
+
+    /// The reference tokens, unescaped.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// Whether this is the empty pointer, addressing the whole document.
+    pub fn is_root(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Go one level deeper, into the member or element `token`, given
+    /// unescaped.
+    pub fn push(&mut self, token: impl Into<String>) {
+        self.tokens.push(token.into());
+    }
+
+    /// Put `token` in front, for a path built from the inside out.
+    pub(crate) fn prepend(&mut self, token: impl Into<String>) {
+        self.tokens.insert(0, token.into());
+    }
+
+    /// Go one level up, returning the token removed; `None` at the root.
+    pub fn pop(&mut self) -> Option<String> {
+        self.tokens.pop()
+    }
+
+    /// The pointer to the container of what this points at; `None` for the
+    /// root.
+    pub fn parent(&self) -> Option<Pointer> {
+        let (_, parent) = self.tokens.split_last()?;
+        Some(Pointer {
+            tokens: parent.to_vec(),
+        })
+    }
+
+    /// This pointer with `token` pushed on, leaving this one as it is.
+    pub fn join(&self, token: impl Into<String>) -> Pointer {
+        let mut joined = self.clone();
+        joined.push(token);
+        joined
+    }
+}
+
+impl fmt::Display for Pointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens
+            .iter()
+            .try_for_each(|token| write!(f, "/{}", escape_pointer_token(token)))
+    }
+}
+
+impl FromStr for Pointer {
+    type Err = PointerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pointer::parse(s)
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for Pointer {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Pointer {
+            tokens: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// What [`JsonValue::pointer`] and friends accept as a pointer: its text,
+/// parsed on every use, or a [`Pointer`] parsed before.
+pub trait AsPointer {
+    /// The unescaped reference tokens, or `None` if malformed.
+    fn tokens(&self) -> Option<PointerTokens<'_>>;
+}
+
+impl AsPointer for str {
+    fn tokens(&self) -> Option<PointerTokens<'_>> {
+        tokens(self)
+    }
+}
+
+impl AsPointer for String {
+    fn tokens(&self) -> Option<PointerTokens<'_>> {
+        tokens(self)
+    }
+}
+
+impl AsPointer for Pointer {
+    fn tokens(&self) -> Option<PointerTokens<'_>> {
+        Some(PointerTokens(Tokens::Parsed(self.tokens.iter())))
+    }
+}
+
+/// The reference tokens of an [`AsPointer`], unescaped.
+#[derive(Debug, Clone)]
+pub struct PointerTokens<'a>(Tokens<'a>);
+
+#[derive(Debug, Clone)]
+enum Tokens<'a> {
+    Text(std::str::Split<'a, char>),
+    Parsed(slice::Iter<'a, String>),
+}
+
+impl<'a> Iterator for PointerTokens<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            Tokens::Text(split) => split.next().map(unescape),
+            Tokens::Parsed(iter) => iter.next().map(|token| Cow::Borrowed(token.as_str())),
+        }
+    }
+}
+
+impl DoubleEndedIterator for PointerTokens<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            Tokens::Text(split) => split.next_back().map(unescape),
+            Tokens::Parsed(iter) => iter.next_back().map(|token| Cow::Borrowed(token.as_str())),
+        }
+    }
+}
+
+/// `parse_json(input)?.pointer(ptr).cloned()` without building anything but
+/// the addressed value: elements and members off the path are stepped over
+/// as [`skip_value`](super::skip_value) does. The whole document is still
+/// read, so errors are those of [`parse_json`](super::parse_json) and, as
+/// there, the last of duplicate keys wins.
+pub fn get_pointer_streaming(input: &str, ptr: &str) -> Result<Option<JsonValue>, JsonError> {
+    let tokens = tokens(ptr);
+    let mut rest = strict(input);
+    let walk = |i: &mut Input<'_>| match tokens.clone() {
+        Some(tokens) => find(i, tokens),
+        None => skip(i).map(|()| None),
+    };
+    let found = (ws, walk, ws)
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?
+        .1;
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    Ok(found)
+}
+
+/// The unescaped reference tokens of `ptr`, or `None` if it is malformed.
+pub(crate) fn tokens(ptr: &str) -> Option<PointerTokens<'_>> {
+    check(ptr).ok()?;
+    Some(PointerTokens(match ptr.strip_prefix('/') {
+        Some(rest) => Tokens::Text(rest.split('/')),
+        None => Tokens::Parsed([].iter()),
+    }))
+}
+
+/// Whether `ptr` is well-formed: empty or starting with `/`, and with every
+/// `~` escaping `0` or `1`.
+fn check(ptr: &str) -> Result<(), PointerError> {
+    if !ptr.is_empty() && !ptr.starts_with('/') {
+        return Err(PointerError::NoLeadingSlash);
+    }
+    let bytes = ptr.as_bytes();
+    let mut token = 0;
+    for (offset, &b) in bytes.iter().enumerate().skip(1) {
+        match b {
+            b'/' => token += 1,
+            b'~' if !matches!(bytes.get(offset + 1), Some(b'0' | b'1')) => {
+                return Err(PointerError::BadEscape { token, offset });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn unescaped(ptr: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    ptr.split('/').skip(1).map(unescape)
+}
+
+fn unescape(token: &str) -> Cow<'_, str> {
+    if token.contains('~') {
+        Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/// An array index token: digits without a leading zero.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    let digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+    if !digits || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+fn find<'t>(
+    input: &mut Input<'_>,
+    mut tokens: impl Iterator<Item = Cow<'t, str>> + Clone,
+) -> PResult<Option<JsonValue>> {
+    let Some(token) = tokens.next() else {
+        return parse_value.map(Some).parse_next(input);
+    };
+    if opt(sep_with_ws('[')).parse_next(input)?.is_some() {
+        if opt(sep_with_ws(']')).parse_next(input)?.is_some() {
+            return Ok(None);
+        }
+        let index = array_index(&token);
+        let mut found = None;
+        let mut i = 0;
+        loop {
+            if index == Some(i) {
+                found = cut_err(|i: &mut Input<'_>| find(i, tokens.clone())).parse_next(input)?;
+            } else {
+                cut_err(skip).parse_next(input)?;
+            }
+            if entry_end(input, ']')? {
+                return Ok(found);
+            }
+            i += 1;
+        }
+    }
+    if opt(sep_with_ws('{')).parse_next(input)?.is_some() {
+        let mut found = None;
+        if opt(sep_with_ws('}')).parse_next(input)?.is_some() {
+            return Ok(found);
+        }
+        loop {
+            let matched = cut_err(|i: &mut Input<'_>| key_is(i, &token)).parse_next(input)?;
+            cut_err(sep_with_ws(':')).parse_next(input)?;
+            if matched {
+                found = cut_err(|i: &mut Input<'_>| find(i, tokens.clone())).parse_next(input)?;
+            } else {
+                cut_err(skip).parse_next(input)?;
+            }
+            if entry_end(input, '}')? {
+                return Ok(found);
+            }
+        }
+    }
+    skip(input)?;
+    Ok(None)
+}
+
+/// Whether the next key equals `token`.
+fn key_is(input: &mut Input<'_>, token: &str) -> PResult<bool> {
+    string.map(|key| key == token).parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const DOC: &str = r#" {
+        "data": {"items": [{"id": 1}, {"id": 2, "tags": ["a", "b"]}, [], {}]},
+        "a/b": 1, "m~n": 2, "": 3, "esc\u00e9": 4, "é\"": 5,
+        "dup": 1, "dup": {"x": [true]}, "n": null, "s": "text"
+    } "#;
+
+    #[test]
+    fn pointer_should_round_trip_escapes() {
+        for text in [
+            "",
+            "/",
+            "/a",
+            "/a~1b/~0c",
+            "/~01",
+            "/~10/",
+            "/data/items/0",
+            "//x",
+        ] {
+            let ptr = Pointer::parse(text).unwrap();
+            assert_eq!(ptr.to_string(), text);
+            assert_eq!(text.parse::<Pointer>(), Ok(ptr.clone()));
+        }
+        let ptr = Pointer::parse("/a~1b/~0c/~01/").unwrap();
+        assert_eq!(ptr.tokens(), ["a/b", "~c", "~1", ""]);
+        let built: Pointer = ["a/b", "~c"].into_iter().collect();
+        assert_eq!(built.to_string(), "/a~1b/~0c");
+        assert_eq!(Pointer::parse("a/b"), Err(PointerError::NoLeadingSlash));
+        assert_eq!(
+            Pointer::parse("/ok/b~2/c"),
+            Err(PointerError::BadEscape {
+                token: 1,
+                offset: 5
+            })
+        );
+        assert_eq!(
+            Pointer::parse("/~"),
+            Err(PointerError::BadEscape {
+                token: 0,
+                offset: 1
+            })
+        );
+        // the same pointers as text address nothing
+        let doc = parse_json(r#"{"b~2": 1, "~": 2}"#).unwrap();
+        assert_eq!(doc.pointer("/b~2"), None);
+        assert_eq!(doc.pointer("/~"), None);
+        assert_eq!(doc.pointer("/~0"), Some(&JsonValue::from(2)));
+    }
+
+    #[test]
+    fn pointer_should_build_and_walk_up() {
+        let mut ptr = Pointer::default();
+        assert!(ptr.is_root());
+        assert_eq!(ptr.parent(), None);
+        assert_eq!(ptr.pop(), None);
+        ptr.push("data");
+        ptr.push("a/b");
+        assert_eq!(ptr.to_string(), "/data/a~1b");
+        let child = ptr.join("0");
+        assert_eq!(child.to_string(), "/data/a~1b/0");
+        assert_eq!(child.parent(), Some(ptr.clone()));
+        assert_eq!(ptr.to_string(), "/data/a~1b");
+        assert_eq!(ptr.pop(), Some("a/b".to_string()));
+        assert_eq!(ptr, Pointer::parse("/data").unwrap());
+        assert_eq!(ptr.parent(), Some(Pointer::default()));
+    }
+
+    #[test]
+    fn prebuilt_pointer_should_match_text() {
+        let docs: Vec<JsonValue> = (0..50)
+            .map(|i| {
+                parse_json(&format!(
+                    r#"{{"data": {{"items": [{{"id": {}}}, {{"id": {}}}]}}, "a/b": {{"~": {}}}}}"#,
+                    i,
+                    i * 2,
+                    i * 3
+                ))
+                .unwrap()
+            })
+            .collect();
+        let tree = parse_json(DOC).unwrap();
+        for text in [
+            "",
+            "/data/items/1/id",
+            "/a~1b/~0",
+            "/data/items/2",
+            "/data/x/0",
+        ] {
+            let ptr = Pointer::parse(text).unwrap();
+            for doc in docs.iter().chain([&tree]) {
+                assert_eq!(doc.pointer(&ptr), doc.pointer(text), "{}", text);
+            }
+        }
+        let ptr = Pointer::parse("/data/items/1/id").unwrap();
+        let ids: Vec<_> = docs.iter().filter_map(|doc| doc.pointer(&ptr)).collect();
+        assert_eq!(ids.len(), 50);
+        assert_eq!(ids[7], &JsonValue::from(14));
+        let mut doc = docs[1].clone();
+        *doc.pointer_mut(&ptr).unwrap() = JsonValue::Null;
+        assert_eq!(doc.pointer("/data/items/1/id"), Some(&JsonValue::Null));
+    }
+
+    #[test]
+    fn get_nested_should_take_keys_literally() {
+        let doc = parse_json(DOC).unwrap();
+        assert_eq!(doc.get_nested(&["a/b"]), Some(&JsonValue::from(1i64)));
+        assert_eq!(doc.get_nested(&["m~n"]), doc.pointer("/m~0n"));
+        assert_eq!(doc.get_nested(&[""]), Some(&JsonValue::from(3i64)));
+        assert_eq!(doc.get_nested(&[]), Some(&doc));
+        // escapes are not read: this is the key `m~n` spelled as in a pointer
+        assert_eq!(doc.get_nested(&["m~0n"]), None);
+        assert_eq!(doc.get_nested(&["a", "b"]), None);
+        assert_eq!(
+            doc.get_nested(&["data", "items", "1", "tags", "0"]),
+            doc.pointer("/data/items/1/tags/0")
+        );
+        assert_eq!(doc.get_nested(&["data", "items", "01"]), None);
+        assert_eq!(doc.get_nested(&["data", "items", "9"]), None);
+        assert_eq!(doc.get_nested(&["s", "x"]), None);
+
+        let mut doc = doc;
+        *doc.get_nested_mut(&["dup", "x", "0"]).unwrap() = JsonValue::Null;
+        assert_eq!(doc.pointer("/dup/x/0"), Some(&JsonValue::Null));
+        assert!(doc.get_nested_mut(&["nowhere"]).is_none());
+    }
+
+    #[test]
+    fn get_segments_should_walk_mixed_paths() {
+        let doc = parse_json(DOC).unwrap();
+        let path = [
+            PathSeg::Key("data"),
+            PathSeg::Key("items"),
+            PathSeg::Index(1),
+            PathSeg::Key("tags"),
+            PathSeg::Index(1),
+        ];
+        assert_eq!(doc.get_segments(&path), Some(&JsonValue::from("b")));
+        assert_eq!(
+            doc.get_segments(&["a/b".into(),]),
+            Some(&JsonValue::from(1i64))
+        );
+        // a key does not index an array, nor an index look up a member
+        assert_eq!(
+            doc.get_segments(&["data".into(), "items".into(), "0".into()]),
+            None
+        );
+        assert_eq!(doc.get_segments(&["data".into(), 0.into()]), None);
+        assert_eq!(
+            doc.get_segments(&["data".into(), "items".into(), 4.into()]),
+            None
+        );
+        assert_eq!(doc.get_segments(&[]), Some(&doc));
+
+        let mut doc = doc;
+        let path = ["data".into(), "items".into(), 0.into(), "id".into()];
+        *doc.get_segments_mut(&path).unwrap() = JsonValue::from(10i64);
+        assert_eq!(
+            doc.pointer("/data/items/0/id"),
+            Some(&JsonValue::from(10i64))
+        );
+        assert!(doc.get_segments_mut(&["n".into(), 0.into()]).is_none());
+    }
+
+    #[test]
+    fn set_pointer_should_insert_replace_and_append() {
+        let mut doc = parse_json(r#"{"a": {"b": [1, 2]}}"#).unwrap();
+        assert!(doc.set_pointer("/a/c", JsonValue::from("new")));
+        assert!(doc.set_pointer(&Pointer::parse("/a/b/0").unwrap(), JsonValue::from(10)));
+        assert!(doc.set_pointer("/a/b/-", JsonValue::from(3)));
+        assert!(doc.set_pointer(&String::from("/a/b/3"), JsonValue::from(4)));
+        assert_eq!(
+            doc,
+            parse_json(r#"{"a": {"b": [10, 2, 3, 4], "c": "new"}}"#).unwrap()
+        );
+        assert!(!doc.set_pointer("/a/b/9", JsonValue::Null));
+        assert!(!doc.set_pointer("/a/b/01", JsonValue::Null));
+        assert!(!doc.set_pointer("/x/y", JsonValue::Null));
+        assert!(!doc.set_pointer("/a/c/d", JsonValue::Null));
+        assert!(!doc.set_pointer("a", JsonValue::Null));
+        assert!(doc.set_pointer("", JsonValue::from(true)));
+        assert_eq!(doc, JsonValue::Bool(true));
+    }
+
+    #[test]
+    fn ensure_path_should_make_missing_objects() {
+        let mut doc = parse_json(r#"{"a": {"x": 1}}"#).unwrap();
+        let c = doc.ensure_path("/a/b/c").unwrap();
+        assert_eq!(c, &JsonValue::Object(JsonObject::default()));
+        if let JsonValue::Object(obj) = c {
+            obj.insert("d".into(), JsonValue::from(2));
+        }
+        assert_eq!(
+            doc,
+            parse_json(r#"{"a": {"x": 1, "b": {"c": {"d": 2}}}}"#).unwrap()
+        );
+        // what is there already is kept
+        assert_eq!(doc.ensure_path("/a/x"), Ok(&mut JsonValue::from(1)));
+        assert_eq!(
+            doc.ensure_path(&Pointer::parse("/a/b/c/d").unwrap()),
+            Ok(&mut JsonValue::from(2))
+        );
+        let mut root = JsonValue::Null;
+        assert_eq!(root.ensure_path(""), Ok(&mut JsonValue::Null));
+    }
+
+    #[test]
+    fn ensure_path_should_not_clobber_scalars_unless_asked() {
+        let mut doc = parse_json(r#"{"a": {"b": 1, "arr": [{"k": 1}]}}"#).unwrap();
+        let before = doc.clone();
+        assert_eq!(
+            doc.ensure_path("/a/b/c"),
+            Err(JsonError::Path {
+                pointer: "/a/b".to_string(),
+                message: "a number has no member \"c\"".to_string(),
+            })
+        );
+        assert_eq!(doc, before);
+        assert!(doc.ensure_path_with("/a/b/c/d", true).is_ok());
+        assert_eq!(
+            doc.pointer("/a/b"),
+            Some(&parse_json(r#"{"c": {"d": {}}}"#).unwrap())
+        );
+    }
+
+    #[test]
+    fn ensure_path_should_only_traverse_existing_elements() {
+        let mut doc = parse_json(r#"{"arr": [{"k": 1}]}"#).unwrap();
+        assert!(doc.ensure_path("/arr/0/new").is_ok());
+        assert_eq!(
+            doc.pointer("/arr/0"),
+            Some(&parse_json(r#"{"k": 1, "new": {}}"#).unwrap())
+        );
+        for ptr in ["/arr/1/x", "/arr/-", "/arr/01"] {
+            let err = doc.ensure_path_with(ptr, true).unwrap_err();
+            assert!(
+                matches!(&err, JsonError::Path { pointer, .. } if pointer == "/arr"),
+                "{}: {}",
+                ptr,
+                err
+            );
+        }
+        assert!(doc.ensure_path("no-slash").is_err());
+    }
+
+    #[test]
+    fn get_or_insert_with_should_keep_what_is_there() {
+        let mut doc = parse_json(r#"{"a": {"n": 1}, "arr": [5]}"#).unwrap();
+        *doc.get_or_insert_with("/a/count", || JsonValue::from(0))
+            .unwrap() = JsonValue::from(3);
+        assert_eq!(
+            doc.get_or_insert_with("/a/count", || unreachable!()),
+            Ok(&mut JsonValue::from(3))
+        );
+        assert_eq!(
+            doc.get_or_insert_with("/new/deep/list", || JsonValue::Array(vec![])),
+            Ok(&mut JsonValue::Array(vec![]))
+        );
+        assert_eq!(
+            doc.get_or_insert_with("/arr/0", || unreachable!()),
+            Ok(&mut JsonValue::from(5))
+        );
+        assert!(doc
+            .get_or_insert_with("/arr/1", || JsonValue::from(6))
+            .is_err());
+        assert!(doc
+            .get_or_insert_with("/a/n/x", || JsonValue::Null)
+            .is_err());
+        assert_eq!(
+            doc,
+            parse_json(r#"{"a": {"n": 1, "count": 3}, "arr": [5], "new": {"deep": {"list": []}}}"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn get_pointer_streaming_should_match_pointer() {
+        let tree = parse_json(DOC).unwrap();
+        for ptr in [
+            "",
+            "/data",
+            "/data/items",
+            "/data/items/0/id",
+            "/data/items/1/tags/1",
+            "/data/items/1/tags/2",
+            "/data/items/2",
+            "/data/items/3",
+            "/data/items/4",
+            "/data/items/01",
+            "/data/items/-",
+            "/data/items/x",
+            "/data/missing",
+            "/a~1b",
+            "/m~0n",
+            "/",
+            "/escé",
+            "/é\"",
+            "/dup",
+            "/dup/x/0",
+            "/n",
+            "/n/x",
+            "/s/0",
+            "no-slash",
+        ] {
+            assert_eq!(
+                get_pointer_streaming(DOC, ptr).unwrap().as_ref(),
+                tree.pointer(ptr),
+                "{:?}",
+                ptr
+            );
+        }
+    }
+
+    #[test]
+    fn get_pointer_streaming_should_report_errors_like_parse_json() {
+        for doc in [
+            "[1, 2, oops",
+            "[1, oops, 3]",
+            "{\"a\": 1, \"a\": x}",
+            "{\"a\": [1,]}",
+            "{\"b\": 1} 2",
+            "{\"a\" 1}",
+            "",
+        ] {
+            for ptr in ["", "/a", "/1", "/a/0", "x"] {
+                assert_eq!(
+                    get_pointer_streaming(doc, ptr),
+                    Err(parse_json(doc).unwrap_err()),
+                    "{} {}",
+                    doc,
+                    ptr
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn get_pointer_streaming_should_handle_large_documents() {
+        let items: Vec<String> = (0..20_000)
+            .map(|i| {
+                format!(
+                    r#"{{"id": {}, "name": "item {}", "tags": ["x", "y"]}}"#,
+                    i, i
+                )
+            })
+            .collect();
+        let doc = format!(
+            r#"{{"meta": {{}}, "data": {{"items": [{}]}}}}"#,
+            items.join(",")
+        );
+        assert_eq!(
+            get_pointer_streaming(&doc, "/data/items/15000/id"),
+            Ok(Some(JsonValue::from(15000)))
+        );
+        assert_eq!(
+            get_pointer_streaming(&doc, "/data/items/15000/tags/1"),
+            Ok(Some(JsonValue::from("y")))
+        );
+        assert_eq!(get_pointer_streaming(&doc, "/data/items/20000"), Ok(None));
+    }
+}
@@ -0,0 +1,186 @@
+use super::{escape_str, EscapeOptions, JsonValue};
+
+/// How much of a value [`JsonValue::preview`] shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewLimits {
+    /// Characters of a string, or of the text of a [`JsonValue::Raw`],
+    /// shown before it is cut with `…`; 40 by default.
+    pub max_string_chars: usize,
+    /// Elements of an array, or members of an object, shown before the
+    /// rest are counted as `… (+n more)`; 8 by default.
+    pub max_items: usize,
+    /// Containers nested deeper than this show as `[…]` or `{…}`; 3 by
+    /// default, so the value itself is depth 0.
+    pub max_depth: usize,
+}
+
+impl Default for PreviewLimits {
+    fn default() -> Self {
+        PreviewLimits {
+            max_string_chars: 40,
+            max_items: 8,
+            max_depth: 3,
+        }
+    }
+}
+
+impl PreviewLimits {
+    pub fn max_string_chars(mut self, n: usize) -> Self {
+        self.max_string_chars = n;
+        self
+    }
+
+    pub fn max_items(mut self, n: usize) -> Self {
+        self.max_items = n;
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+}
+
+impl JsonValue {
+    /// A short view of `self` for logs and error messages, cut down to
+    /// `limits`: `{"name": "a very long st…", "ids": [1, 2, … (+998 more)],
+    /// "deep": {…}}`. Members are shown in key order. What is cut is marked
+    /// with `…`, which JSON never has outside a string, so a preview of a
+    /// large value is never mistaken for the value; one that fits within
+    /// `limits` reads like compact JSON with a space after each `:` and
+    /// `,`. `{:?}` still prints everything.
+    pub fn preview(&self, limits: &PreviewLimits) -> String {
+        let mut out = String::new();
+        preview_into(&mut out, self, limits, 0);
+        out
+    }
+}
+
+fn preview_into(out: &mut String, value: &JsonValue, limits: &PreviewLimits, depth: usize) {
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonValue::Number(n) => out.push_str(&n.to_string()),
+        JsonValue::String(s) => {
+            out.push('"');
+            push_cut(out, s, limits, true);
+            out.push('"');
+        }
+        JsonValue::Raw(raw) => push_cut(out, raw.get(), limits, false),
+        JsonValue::Array(arr) if arr.is_empty() => out.push_str("[]"),
+        JsonValue::Array(_) if depth >= limits.max_depth => out.push_str("[…]"),
+        JsonValue::Array(arr) => {
+            out.push('[');
+            for (i, v) in arr.iter().take(limits.max_items).enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                preview_into(out, v, limits, depth + 1);
+            }
+            push_more(out, arr.len(), limits);
+            out.push(']');
+        }
+        JsonValue::Object(obj) if obj.is_empty() => out.push_str("{}"),
+        JsonValue::Object(_) if depth >= limits.max_depth => out.push_str("{…}"),
+        JsonValue::Object(obj) => {
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (k, v)) in members.into_iter().take(limits.max_items).enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push('"');
+                push_cut(out, k, limits, true);
+                out.push_str("\": ");
+                preview_into(out, v, limits, depth + 1);
+            }
+            push_more(out, obj.len(), limits);
+            out.push('}');
+        }
+    }
+}
+
+/// `text` up to `limits.max_string_chars`, then `…` if there was more,
+/// escaped as in a JSON string when `escape` is set.
+fn push_cut(out: &mut String, text: &str, limits: &PreviewLimits, escape: bool) {
+    let (shown, cut) = match text.char_indices().nth(limits.max_string_chars) {
+        Some((at, _)) => (&text[..at], true),
+        None => (text, false),
+    };
+    if escape {
+        escape_str(shown, out, &EscapeOptions::default());
+    } else {
+        out.push_str(shown);
+    }
+    if cut {
+        out.push('…');
+    }
+}
+
+/// The count of the entries of a container of `len` that were left out.
+fn push_more(out: &mut String, len: usize, limits: &PreviewLimits) {
+    if len > limits.max_items {
+        if limits.max_items > 0 {
+            out.push_str(", ");
+        }
+        out.push_str(&format!("… (+{} more)", len - limits.max_items));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    fn preview(input: &str, limits: &PreviewLimits) -> String {
+        parse_json(input).unwrap().preview(limits)
+    }
+
+    #[test]
+    fn preview_should_render_small_values_fully() {
+        let input =
+            r#"{"name": "ann", "tags": ["a", "b\n"], "n": 1.5, "ok": true, "none": null, "e": {}}"#;
+        assert_eq!(
+            preview(input, &PreviewLimits::default()),
+            r#"{"e": {}, "n": 1.5, "name": "ann", "none": null, "ok": true, "tags": ["a", "b\n"]}"#
+        );
+        assert_eq!(preview("[]", &PreviewLimits::default()), "[]");
+    }
+
+    #[test]
+    fn preview_should_cut_long_strings() {
+        let limits = PreviewLimits::default().max_string_chars(5);
+        assert_eq!(preview(r#""abcdefgh""#, &limits), r#""abcde…""#);
+        assert_eq!(preview(r#""abcde""#, &limits), r#""abcde""#);
+        // characters, not bytes, and escapes after the cut
+        assert_eq!(preview(r#""ééééé\"é""#, &limits), r#""ééééé…""#);
+        assert_eq!(preview(r#"{"keykeykey": 1}"#, &limits), r#"{"keyke…": 1}"#);
+    }
+
+    #[test]
+    fn preview_should_count_what_it_leaves_out() {
+        let long: Vec<String> = (0..1237).map(|i| i.to_string()).collect();
+        let input = format!("[{}]", long.join(","));
+        let limits = PreviewLimits::default().max_items(3);
+        assert_eq!(preview(&input, &limits), "[0, 1, 2, … (+1234 more)]");
+        assert_eq!(
+            preview(r#"{"d": 4, "a": 1, "c": 3, "b": 2, "e": 5}"#, &limits),
+            r#"{"a": 1, "b": 2, "c": 3, … (+2 more)}"#
+        );
+        let none = PreviewLimits::default().max_items(0);
+        assert_eq!(preview("[1, 2]", &none), "[… (+2 more)]");
+    }
+
+    #[test]
+    fn preview_should_cap_depth() {
+        let limits = PreviewLimits::default().max_depth(2);
+        assert_eq!(
+            preview(r#"{"a": {"b": {"c": 1}, "l": [[1], []]}}"#, &limits),
+            r#"{"a": {"b": {…}, "l": […]}}"#
+        );
+        let limits = PreviewLimits::default().max_depth(0);
+        assert_eq!(preview("[[1]]", &limits), "[…]");
+        assert_eq!(preview("7", &limits), "7");
+    }
+}
@@ -0,0 +1,136 @@
+use super::{
+    error::escape_pointer_token, parse_cst, parse_json, JsonError, JsonValue, Node, ParseOptions,
+};
+use std::fmt;
+
+/// The exact text of one JSON value, checked for well-formedness but not
+/// decomposed. Stored in a tree as [`JsonValue::Raw`], it is written back
+/// byte for byte, which keeps opaque payloads and signed subdocuments intact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawValue(Box<str>);
+
+impl RawValue {
+    /// Validate `text` as strict JSON and keep it. Whitespace around the
+    /// value is trimmed; everything inside it is kept.
+    pub fn from_string(text: impl Into<String>) -> Result<Self, JsonError> {
+        let text = text.into();
+        parse_cst(&text, &ParseOptions::strict())?;
+        let trimmed = text.trim_matches([' ', '\t', '\n', '\r']);
+        if trimmed.len() == text.len() {
+            Ok(RawValue(text.into_boxed_str()))
+        } else {
+            Ok(RawValue(trimmed.into()))
+        }
+    }
+
+    pub fn get(&self) -> &str {
+        &self.0
+    }
+
+    /// Parse the text into a full value.
+    pub fn parse(&self) -> Result<JsonValue, JsonError> {
+        parse_json(&self.0)
+    }
+}
+
+impl fmt::Display for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Parse strict JSON, keeping the values at the given JSON Pointers as
+/// [`RawValue`]s instead of parsing them. Pointers that match nothing are
+/// ignored.
+pub fn parse_json_with_raw(input: &str, pointers: &[&str]) -> Result<JsonValue, JsonError> {
+    let options = ParseOptions::strict();
+    let cst = parse_cst(input, &options)?;
+    let mut pointer = String::new();
+    Ok(lower(input, &cst.root, &options, pointers, &mut pointer))
+}
+
+fn lower(
+    input: &str,
+    node: &Node<'_>,
+    options: &ParseOptions,
+    pointers: &[&str],
+    pointer: &mut String,
+) -> JsonValue {
+    if pointers.contains(&pointer.as_str()) {
+        return JsonValue::Raw(RawValue(input[node.span()].into()));
+    }
+    let len = pointer.len();
+    let mut child = |segment: &str, node: &Node<'_>| {
+        pointer.push('/');
+        pointer.push_str(&escape_pointer_token(segment));
+        let value = lower(input, node, options, pointers, pointer);
+        pointer.truncate(len);
+        value
+    };
+    match node {
+        Node::Scalar(_) => node.to_value(options),
+        Node::Array(c) => JsonValue::Array(
+            c.entries
+                .iter()
+                .enumerate()
+                .map(|(i, e)| child(&i.to_string(), &e.value))
+                .collect(),
+        ),
+        Node::Object(c) => JsonValue::Object(
+            c.entries
+                .iter()
+                .map(|e| {
+                    let key = e.key.as_ref().expect("object entries have keys");
+                    let key = key.to_string(options);
+                    let value = child(&key, &e.value);
+                    (key, value)
+                })
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_fields_should_be_written_back_verbatim() {
+        let payload = "{ \"z\" : 1.50,\n    \"a\":[ true ] }";
+        let input = format!(
+            "{{\"id\": 7, \"payload\": {}, \"list\": [1, \"x\"]}}",
+            payload
+        );
+        let v = parse_json_with_raw(&input, &["/payload", "/list/1"]).unwrap();
+        let JsonValue::Object(obj) = &v else {
+            panic!("expected an object")
+        };
+        let JsonValue::Raw(raw) = &obj["payload"] else {
+            panic!("expected a raw payload")
+        };
+        assert_eq!(raw.get(), payload);
+        assert_eq!(raw.parse().unwrap(), parse_json(payload).unwrap());
+        assert_eq!(obj["id"], JsonValue::from(7));
+
+        let out = v.to_json_string_pretty();
+        assert!(out.contains(payload), "{}", out);
+        assert!(
+            out.contains("  \"list\": [\n    1,\n    \"x\"\n  ]"),
+            "{}",
+            out
+        );
+        assert_eq!(out.matches(payload).count(), 1);
+        assert!(v
+            .to_json_string()
+            .contains(&format!("\"payload\":{}", payload)));
+    }
+
+    #[test]
+    fn raw_value_should_validate_and_trim() {
+        assert_eq!(RawValue::from_string(" [1,2] \n").unwrap().get(), "[1,2]");
+        assert!(RawValue::from_string("[1,").is_err());
+        assert!(RawValue::from_string("1 2").is_err());
+        let raw = RawValue::from_string("\"caf\\u00e9\"").unwrap();
+        assert_eq!(JsonValue::Raw(raw).to_json_string(), "\"caf\\u00e9\"");
+    }
+}
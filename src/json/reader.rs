@@ -0,0 +1,873 @@
+#[cfg(feature = "gzip")]
+use super::gzip::GzDecoder;
+use super::{
+    error::{self, invalid_utf8},
+    parse_json_with, size, ErrorCode, JsonError, JsonEvent, JsonValue, Num, ParseOptions,
+    TreeBuilder,
+};
+use std::{borrow::Cow, fs::File, io, mem, ops::ControlFlow, path::Path};
+
+/// The bytes every gzip stream starts with.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Parse strict JSON from `reader` without first reading it into a string.
+/// Input is pulled through a fixed buffer, so only the tree being built and
+/// the current token are held in memory. Tokens are decoded whole, so UTF-8
+/// sequences split across reads are fine. Offsets in parse errors count bytes
+/// from the start of the stream; read failures are [`JsonError::Io`].
+pub fn from_reader<R: io::Read>(reader: R) -> Result<JsonValue, JsonError> {
+    read_tree(reader, &ReadOptions::default())
+}
+
+/// [`from_reader`] under the limits of `options`: `max_depth`,
+/// `max_array_len`, `max_object_members` and `max_total_allocated`, each
+/// failing as soon as it is passed, `error_on_precision_loss` and `cancel`. The input is read as strict JSON
+/// whatever the other switches say, except `preserve_number_text`, which is
+/// kept.
+pub fn from_reader_with<R: io::Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<JsonValue, JsonError> {
+    let options = ReadOptions {
+        raw_numbers: options.preserve_number_text,
+        limits: Some(options.clone()),
+        ..ReadOptions::default()
+    };
+    read_tree(reader, &options)
+}
+
+/// [`from_reader`] that also takes gzip-compressed JSON, told apart by its
+/// first bytes and decompressed as it is read, so memory stays bounded as
+/// it does for plain input. A stream that does not decompress is a
+/// [`JsonError::Io`]; without the `gzip` feature, so is any gzip stream.
+pub fn from_reader_auto<R: io::Read>(reader: R) -> Result<JsonValue, JsonError> {
+    from_reader(MaybeGzip::new(reader)?)
+}
+
+/// A reader of `R` that decompresses it if it is a gzip stream and reads
+/// it as it is otherwise.
+#[derive(Debug)]
+pub struct MaybeGzip<R>(Decoded<R>);
+
+/// The bytes looked at to tell, put back in front of the rest.
+type Peeked<R> = io::Chain<io::Take<io::Cursor<[u8; 2]>>, R>;
+
+#[derive(Debug)]
+enum Decoded<R> {
+    Plain(Peeked<R>),
+    #[cfg(feature = "gzip")]
+    Gzip(GzDecoder<Peeked<R>>),
+}
+
+impl<R: io::Read> MaybeGzip<R> {
+    /// Look at the first bytes of `reader` to tell how to read it.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 2];
+        let mut len = 0;
+        while len < magic.len() {
+            match reader.read(&mut magic[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let gzip = magic == GZIP_MAGIC;
+        let peeked = io::Read::chain(io::Read::take(io::Cursor::new(magic), len as u64), reader);
+        match gzip {
+            #[cfg(feature = "gzip")]
+            true => Ok(Self(Decoded::Gzip(GzDecoder::new(peeked)))),
+            #[cfg(not(feature = "gzip"))]
+            true => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "gzip-compressed input needs the gzip feature",
+            )),
+            false => Ok(Self(Decoded::Plain(peeked))),
+        }
+    }
+
+    /// Whether the input is being decompressed.
+    pub fn is_gzip(&self) -> bool {
+        !matches!(self.0, Decoded::Plain(_))
+    }
+}
+
+impl<R: io::Read> io::Read for MaybeGzip<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            Decoded::Plain(r) => r.read(buf),
+            #[cfg(feature = "gzip")]
+            Decoded::Gzip(r) => r.read(buf),
+        }
+    }
+}
+
+/// Parse strict JSON from bytes, such as an HTTP body, without validating
+/// them as UTF-8 up front. Bytes are only decoded inside string tokens;
+/// invalid UTF-8 there or anywhere else is a [`JsonError::Parse`] with the
+/// offset of the first bad byte.
+pub fn parse_json_bytes(input: &[u8]) -> Result<JsonValue, JsonError> {
+    read_tree(input, &ReadOptions::default())
+}
+
+/// [`parse_json_bytes`] that replaces invalid UTF-8 inside strings with
+/// U+FFFD, as [`String::from_utf8_lossy`] does. Invalid bytes outside
+/// strings are still an error.
+pub fn parse_json_bytes_lossy(input: &[u8]) -> Result<JsonValue, JsonError> {
+    let options = ReadOptions {
+        lossy: true,
+        ..ReadOptions::default()
+    };
+    read_tree(input, &options)
+}
+
+fn read_tree<R: io::Read>(reader: R, options: &ReadOptions) -> Result<JsonValue, JsonError> {
+    let mut tree = TreeBuilder::default();
+    read_events(reader, options, |event| {
+        tree.push(event);
+        Ok(())
+    })?;
+    Ok(tree.into_value().expect("a complete document was read"))
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct ReadOptions {
+    /// Hand out every number as [`Num::Raw`](super::Num::Raw).
+    pub(super) raw_numbers: bool,
+    /// Replace invalid UTF-8 in strings with U+FFFD.
+    pub(super) lossy: bool,
+    /// Where to take the depth and breadth limits, `error_on_precision_loss`
+    /// and `cancel` from, instead of the defaults.
+    pub(super) limits: Option<ParseOptions>,
+}
+
+/// Read one strict JSON document from `reader`, handing each event to `sink`
+/// as soon as its token is complete; an error from `sink` ends reading.
+pub(super) fn read_events<R: io::Read>(
+    reader: R,
+    options: &ReadOptions,
+    mut sink: impl FnMut(JsonEvent) -> Result<(), JsonError>,
+) -> Result<(), JsonError> {
+    let mut r = Reader::new(reader, options);
+    r.skip_ws()?;
+    r.allocate(r.offset(), size::VALUE_SIZE)?;
+    r.value(&mut sink)?;
+    r.end()
+}
+
+/// [`for_each_element`](super::for_each_element) reading the array from
+/// `reader`, so that only the current element is ever in memory. Offsets
+/// in errors count bytes from the start of the stream, and an element that
+/// fails to read is a [`JsonError::Element`] with its index.
+pub fn for_each_element_from_reader<R: io::Read>(
+    reader: R,
+    mut f: impl FnMut(usize, JsonValue) -> ControlFlow<()>,
+) -> Result<(), JsonError> {
+    let mut r = Reader::new(reader, &ReadOptions::default());
+    r.skip_ws()?;
+    if r.peek()? != Some(b'[') {
+        return Err(r.error_here()?);
+    }
+    let mut depth = 0;
+    let mut index = 0;
+    let mut element = TreeBuilder::default();
+    let mut stopped = false;
+    let read = r.nested(|r| {
+        r.array(&mut |event| {
+            match event {
+                JsonEvent::ArrayStart | JsonEvent::ObjectStart => depth += 1,
+                JsonEvent::ArrayEnd | JsonEvent::ObjectEnd => depth -= 1,
+                _ => {}
+            }
+            // the brackets of the array itself
+            if matches!(event, JsonEvent::ArrayStart if depth == 1) || depth == 0 {
+                return Ok(());
+            }
+            element.push(event);
+            if depth > 1 {
+                return Ok(());
+            }
+            let value = mem::take(&mut element).into_value();
+            let flow = f(index, value.expect("a complete element was read"));
+            index += 1;
+            if flow.is_break() {
+                stopped = true;
+                // any error stops the reader; this one is never seen
+                return Err(error::eof(0));
+            }
+            Ok(())
+        })
+    });
+    match read {
+        _ if stopped => Ok(()),
+        Ok(()) => r.end(),
+        Err(e) => Err(JsonError::Element {
+            index,
+            source: Box::new(e),
+        }),
+    }
+}
+
+/// [`from_reader`] for the file at `path`. Errors are wrapped in
+/// [`JsonError::File`] so that their message names the file.
+pub fn from_file(path: impl AsRef<Path>) -> Result<JsonValue, JsonError> {
+    let path = path.as_ref();
+    File::open(path)
+        .map_err(JsonError::from)
+        .and_then(from_reader)
+        .map_err(|e| JsonError::File {
+            path: path.to_path_buf(),
+            source: Box::new(e),
+        })
+}
+
+struct Reader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+    /// Bytes of the stream before `buf[0]`.
+    consumed: usize,
+    options: ParseOptions,
+    lossy: bool,
+    /// Arrays and objects open around the current position.
+    depth: usize,
+    /// The offset `cancel` was last asked at.
+    asked: Option<usize>,
+    /// `max_total_allocated`, counted here rather than by each token's parse.
+    budget: Option<usize>,
+    /// Bytes counted against `budget` so far.
+    allocated: usize,
+}
+
+type Sink<'s> = dyn FnMut(JsonEvent) -> Result<(), JsonError> + 's;
+
+impl<R: io::Read> Reader<R> {
+    fn new(inner: R, options: &ReadOptions) -> Self {
+        Reader {
+            inner,
+            buf: vec![0; 8192],
+            pos: 0,
+            len: 0,
+            consumed: 0,
+            options: match &options.limits {
+                Some(limits) => ParseOptions {
+                    max_depth: limits.max_depth,
+                    max_array_len: limits.max_array_len,
+                    max_object_members: limits.max_object_members,
+                    error_on_precision_loss: limits.error_on_precision_loss,
+                    cancel: limits.cancel.clone(),
+                    ..ParseOptions::strict()
+                },
+                None => ParseOptions::strict(),
+            }
+            .preserve_number_text(options.raw_numbers),
+            lossy: options.lossy,
+            depth: 0,
+            asked: None,
+            budget: options
+                .limits
+                .as_ref()
+                .and_then(|limits| limits.max_total_allocated),
+            allocated: 0,
+        }
+    }
+
+    /// Only whitespace may follow the document.
+    fn end(&mut self) -> Result<(), JsonError> {
+        self.skip_ws()?;
+        match self.peek()? {
+            None => Ok(()),
+            Some(_) => Err(self.error_here()?.trailing()),
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.consumed + self.pos
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, JsonError> {
+        if self.pos == self.len {
+            self.consumed += self.len;
+            self.pos = 0;
+            self.len = loop {
+                match self.inner.read(&mut self.buf) {
+                    Ok(n) => break n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e.into()),
+                }
+            };
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+        Ok(Some(self.buf[self.pos]))
+    }
+
+    fn bump(&mut self) {
+        self.pos += 1;
+    }
+
+    fn skip_ws(&mut self) -> Result<(), JsonError> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.bump();
+        }
+        Ok(())
+    }
+
+    /// An error for the character at the current position, or for the end of
+    /// input.
+    fn error_here(&mut self) -> Result<JsonError, JsonError> {
+        let offset = self.offset();
+        let mut bytes = Vec::new();
+        while let Some(b) = self.peek()? {
+            bytes.push(b);
+            self.bump();
+            match std::str::from_utf8(&bytes) {
+                Ok(text) => return Ok(error::unexpected(offset, text.chars().next().unwrap())),
+                Err(e) if e.error_len().is_none() && bytes.len() < 4 => {}
+                Err(_) => return Ok(invalid_utf8(offset)),
+            }
+        }
+        if !bytes.is_empty() {
+            // a sequence cut short by the end of input
+            return Ok(invalid_utf8(offset));
+        }
+        Ok(error::eof(offset))
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        self.skip_ws()?;
+        if self.peek()? == Some(byte) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error_here()?)
+        }
+    }
+
+    fn value(&mut self, sink: &mut Sink<'_>) -> Result<(), JsonError> {
+        self.skip_ws()?;
+        let value = match self.peek()? {
+            Some(b'{') => return self.nested(|r| r.object(sink)),
+            Some(b'[') => return self.nested(|r| r.array(sink)),
+            Some(b'"') => self.scalar(|r, token| r.string_token(token))?,
+            Some(b'-' | b'0'..=b'9') => self.scalar(|r, token| {
+                r.take_while(token, |b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+            })?,
+            Some(b'a'..=b'z') => {
+                self.scalar(|r, token| r.take_while(token, |b| b.is_ascii_alphanumeric()))?
+            }
+            _ => return Err(self.error_here()?),
+        };
+        sink(match value {
+            JsonValue::String(s) => JsonEvent::String(s),
+            JsonValue::Number(n) => JsonEvent::Number(n),
+            JsonValue::Bool(b) => JsonEvent::Bool(b),
+            _ => JsonEvent::Null,
+        })
+    }
+
+    /// Read the container whose bracket is next with `read`, one level
+    /// deeper. Going past `max_depth` fails at the bracket.
+    fn nested(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<(), JsonError>,
+    ) -> Result<(), JsonError> {
+        if self.options.max_depth.is_some_and(|max| self.depth >= max) {
+            return Err(JsonError::parse(
+                ErrorCode::DepthExceeded,
+                self.offset(),
+                error::DEPTH_EXCEEDED,
+            ));
+        }
+        self.depth += 1;
+        let read = read(self);
+        self.depth -= 1;
+        read
+    }
+
+    /// Collect one scalar token with `scan` and hand it to the string parser,
+    /// so that the values and errors match [`parse_json`](super::parse_json).
+    fn scalar(
+        &mut self,
+        scan: impl FnOnce(&mut Self, &mut Vec<u8>) -> Result<(), JsonError>,
+    ) -> Result<JsonValue, JsonError> {
+        let start = self.offset();
+        let mut token = Vec::new();
+        scan(self, &mut token)?;
+        let (text, shifts) = match std::str::from_utf8(&token) {
+            Ok(text) => (Cow::Borrowed(text), Vec::new()),
+            // only string tokens can hold non-ASCII bytes
+            Err(_) if self.lossy => lossy(&token),
+            Err(e) => return Err(invalid_utf8(start + e.valid_up_to())),
+        };
+        let at_end = self.peek()?.is_none();
+        let value = parse_json_with(&text, &self.options).map_err(|e| match e {
+            // the token ends where the input does, or else before what
+            // follows it, which the parse on its own cannot tell apart
+            JsonError::Parse {
+                code: ErrorCode::UnexpectedEof,
+                ..
+            } if !at_end => JsonError::parse_at(&text, &text).shifted(start),
+            JsonError::Parse {
+                offset,
+                code: ErrorCode::UnexpectedToken | ErrorCode::TrailingData,
+                ..
+            } if at_end && error::truncated(&text[offset..]) => error::eof(self.offset()),
+            JsonError::Parse {
+                offset,
+                message,
+                code,
+            } => JsonError::Parse {
+                offset: start + token_offset(&shifts, offset),
+                message,
+                // all of the token is one value
+                code: match code {
+                    ErrorCode::TrailingData => ErrorCode::UnexpectedToken,
+                    code => code,
+                },
+            },
+            e => e.shifted(start),
+        })?;
+        let text_size = match &value {
+            JsonValue::String(s) => s.len(),
+            JsonValue::Number(Num::Raw(s)) => s.len(),
+            _ => 0,
+        };
+        self.allocate(start, text_size)?;
+        Ok(value)
+    }
+
+    fn take_while(&mut self, token: &mut Vec<u8>, f: impl Fn(u8) -> bool) -> Result<(), JsonError> {
+        while let Some(b) = self.peek()? {
+            if !f(b) {
+                break;
+            }
+            token.push(b);
+            self.bump();
+        }
+        Ok(())
+    }
+
+    /// A quoted string through its closing quote, escapes left in place.
+    fn string_token(&mut self, token: &mut Vec<u8>) -> Result<(), JsonError> {
+        let start = self.offset();
+        token.push(b'"');
+        self.bump();
+        let mut escaped = false;
+        // the text is counted once decoded; an escape decodes to no less
+        // than a sixth of its bytes, so a token longer than this cannot fit
+        let most = self.budget.map(|max| {
+            max.saturating_sub(self.allocated)
+                .saturating_mul(6)
+                .saturating_add(2)
+        });
+        while let Some(b) = self.peek()? {
+            if most.is_some_and(|most| token.len() > most) {
+                return self.allocate(start, usize::MAX);
+            }
+            token.push(b);
+            self.bump();
+            match b {
+                b'"' if !escaped => return Ok(()),
+                b'\\' => escaped = !escaped,
+                _ => escaped = false,
+            }
+        }
+        Ok(())
+    }
+
+    /// Step over a comma, rejecting it when `close` follows.
+    fn comma(&mut self, close: u8) -> Result<(), JsonError> {
+        let offset = self.offset();
+        self.bump();
+        self.skip_ws()?;
+        if self.peek()? == Some(close) {
+            return Err(JsonError::parse(
+                ErrorCode::TrailingComma,
+                offset,
+                error::TRAILING_COMMA,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fail if a container opened at `start` already has `entries`, as many
+    /// as `max_array_len` or `max_object_members` allow.
+    fn count(&self, start: usize, entries: usize, object: bool) -> Result<(), JsonError> {
+        let limit = match object {
+            true => self.options.max_object_members,
+            false => self.options.max_array_len,
+        };
+        match limit {
+            Some(limit) if entries == limit => Err(error::too_many_entries(start, limit, object)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count `bytes` more against `max_total_allocated`, failing at `at`,
+    /// where the value they are for began, if that is too many.
+    fn allocate(&mut self, at: usize, bytes: usize) -> Result<(), JsonError> {
+        let Some(max) = self.budget else {
+            return Ok(());
+        };
+        let total = self.allocated.saturating_add(bytes);
+        if total > max {
+            return Err(JsonError::parse(
+                ErrorCode::LimitExceeded,
+                at,
+                error::ALLOCATION_BUDGET,
+            ));
+        }
+        self.allocated = total;
+        Ok(())
+    }
+
+    /// Fail if `cancel` says to, asking it once every
+    /// [`ParseOptions::CANCEL_INTERVAL`] bytes.
+    fn cancelled(&mut self) -> Result<(), JsonError> {
+        let Some(probe) = &self.options.cancel else {
+            return Ok(());
+        };
+        let offset = self.offset();
+        if self
+            .asked
+            .is_some_and(|at| offset - at < ParseOptions::CANCEL_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.asked = Some(offset);
+        match probe.cancelled() {
+            true => Err(JsonError::Cancelled { offset }),
+            false => Ok(()),
+        }
+    }
+
+    fn array(&mut self, sink: &mut Sink<'_>) -> Result<(), JsonError> {
+        let start = self.offset();
+        self.bump();
+        sink(JsonEvent::ArrayStart)?;
+        self.skip_ws()?;
+        if self.peek()? == Some(b']') {
+            self.bump();
+            return sink(JsonEvent::ArrayEnd);
+        }
+        let mut entries = 0;
+        loop {
+            self.count(start, entries, false)?;
+            self.cancelled()?;
+            self.allocate(self.offset(), size::entry_size(false))?;
+            entries += 1;
+            self.value(sink)?;
+            self.skip_ws()?;
+            match self.peek()? {
+                Some(b',') => self.comma(b']')?,
+                Some(b']') => {
+                    self.bump();
+                    return sink(JsonEvent::ArrayEnd);
+                }
+                _ => return Err(self.error_here()?),
+            }
+        }
+    }
+
+    fn object(&mut self, sink: &mut Sink<'_>) -> Result<(), JsonError> {
+        let start = self.offset();
+        self.bump();
+        sink(JsonEvent::ObjectStart)?;
+        self.skip_ws()?;
+        if self.peek()? == Some(b'}') {
+            self.bump();
+            return sink(JsonEvent::ObjectEnd);
+        }
+        let mut entries = 0;
+        loop {
+            self.skip_ws()?;
+            self.count(start, entries, true)?;
+            self.cancelled()?;
+            self.allocate(self.offset(), size::entry_size(true))?;
+            entries += 1;
+            if self.peek()? != Some(b'"') {
+                return Err(self.error_here()?);
+            }
+            let JsonValue::String(key) = self.scalar(|r, token| r.string_token(token))? else {
+                unreachable!("a quoted token parses to a string")
+            };
+            self.expect(b':')?;
+            sink(JsonEvent::Key(key))?;
+            self.value(sink)?;
+            self.skip_ws()?;
+            match self.peek()? {
+                Some(b',') => self.comma(b'}')?,
+                Some(b'}') => {
+                    self.bump();
+                    return sink(JsonEvent::ObjectEnd);
+                }
+                _ => return Err(self.error_here()?),
+            }
+        }
+    }
+}
+
+/// `token` with each invalid UTF-8 sequence replaced by U+FFFD, and for
+/// each replacement the offsets just after it in the text and the token.
+fn lossy(token: &[u8]) -> (Cow<'_, str>, Vec<(usize, usize)>) {
+    let mut text = String::with_capacity(token.len());
+    let mut shifts = Vec::new();
+    let mut read = 0;
+    for chunk in token.utf8_chunks() {
+        text.push_str(chunk.valid());
+        read += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            read += chunk.invalid().len();
+            shifts.push((text.len(), read));
+        }
+    }
+    (Cow::Owned(text), shifts)
+}
+
+/// Map an offset into the text made by [`lossy`] back to the token.
+fn token_offset(shifts: &[(usize, usize)], offset: usize) -> usize {
+    match shifts.iter().rev().find(|(text, _)| *text <= offset) {
+        Some((text, token)) => token + (offset - text),
+        None => offset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{for_each_element, parse_json};
+
+    /// Hands out at most `chunk` bytes per read, then fails if `fail_at` is
+    /// reached.
+    struct Chunked<'a> {
+        data: &'a [u8],
+        chunk: usize,
+        fail_at: Option<usize>,
+        pos: usize,
+    }
+
+    impl io::Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.fail_at.is_some_and(|at| self.pos >= at) {
+                return Err(io::Error::new(io::ErrorKind::ConnectionReset, "gone"));
+            }
+            let n = self.chunk.min(buf.len()).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    fn chunked(data: &str, fail_at: Option<usize>) -> Chunked<'_> {
+        Chunked {
+            data: data.as_bytes(),
+            chunk: 3,
+            fail_at,
+            pos: 0,
+        }
+    }
+
+    const DOC: &str = r#" {"name": "Zoë 😀 水", "escaped": "\"\u00e9\\", "marks": [90.0, -80, 1e3],
+        "nested": {"a": [true, false, null, {}], "b": []}, "t": "\t"} "#;
+
+    #[test]
+    fn from_reader_should_match_parse_json_across_chunk_boundaries() {
+        let expected = parse_json(DOC).unwrap();
+        assert_eq!(from_reader(chunked(DOC, None)).unwrap(), expected);
+        assert_eq!(from_reader(DOC.as_bytes()).unwrap(), expected);
+        for doc in ["0", "-1.5", "\"\"", "  null\n"] {
+            assert_eq!(from_reader(chunked(doc, None)), parse_json(doc), "{}", doc);
+        }
+    }
+
+    #[test]
+    fn from_reader_should_report_offsets_like_parse_json() {
+        for doc in [
+            "[1, 2",
+            "[1 2]",
+            "{\"a\" 1}",
+            "{\"a\": 1,}",
+            "[\"é\" x]",
+            "[truth]",
+            "[\"\\q\"]",
+            "[01x]",
+            "[\"open",
+            "{1: 2}",
+            "[] ]",
+            "[é]",
+            "",
+        ] {
+            assert_eq!(from_reader(chunked(doc, None)), parse_json(doc), "{}", doc);
+        }
+        let bytes = b"[\"a\xff\"]";
+        assert_eq!(
+            from_reader(&bytes[..]),
+            Err(JsonError::Parse {
+                offset: 3,
+                message: "invalid UTF-8".to_string(),
+                code: ErrorCode::InvalidUtf8,
+            })
+        );
+    }
+
+    #[test]
+    fn from_reader_should_surface_io_errors() {
+        let err = from_reader(chunked(DOC, Some(30))).unwrap_err();
+        match err {
+            JsonError::Io(e) => assert_eq!(e.kind(), io::ErrorKind::ConnectionReset),
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn parse_json_bytes_should_match_parse_json() {
+        for doc in [DOC, "[1, 2", "{\"a\" 1}", "[\"\\q\"]", "[] ]", ""] {
+            assert_eq!(parse_json_bytes(doc.as_bytes()), parse_json(doc), "{}", doc);
+            assert_eq!(
+                parse_json_bytes_lossy(doc.as_bytes()),
+                parse_json(doc),
+                "{}",
+                doc
+            );
+        }
+    }
+
+    #[test]
+    fn parse_json_bytes_should_locate_invalid_utf8() {
+        let invalid = |offset| {
+            Err(JsonError::Parse {
+                offset,
+                message: "invalid UTF-8".to_string(),
+                code: ErrorCode::InvalidUtf8,
+            })
+        };
+        let in_string = b"{\"k\": \"a\xffb\xe2\x82\"}";
+        assert_eq!(parse_json_bytes(in_string), invalid(8));
+        assert_eq!(
+            parse_json_bytes_lossy(in_string),
+            parse_json("{\"k\": \"a\u{fffd}b\u{fffd}\"}")
+        );
+        assert_eq!(parse_json_bytes(b"[1, \xff]"), invalid(4));
+        assert_eq!(parse_json_bytes_lossy(b"[1, \xff]"), invalid(4));
+        assert_eq!(parse_json_bytes_lossy(b"{\"\xc3\": 1} \xe9"), invalid(9));
+        // offsets after a replacement still point into the original bytes
+        let Err(JsonError::Parse {
+            offset, message, ..
+        }) = parse_json("[\"\\q\"]")
+        else {
+            panic!("bad escape accepted")
+        };
+        assert_eq!(
+            parse_json_bytes_lossy(b"[\"\xff\xff\xff\xff\\q\"]"),
+            Err(JsonError::Parse {
+                offset: offset + 4,
+                message,
+                code: ErrorCode::InvalidEscape,
+            })
+        );
+    }
+
+    #[test]
+    fn from_file_should_name_the_path() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("from-file-{}.json", std::process::id()));
+        std::fs::write(&path, DOC).unwrap();
+        assert_eq!(from_file(&path).unwrap(), parse_json(DOC).unwrap());
+        std::fs::write(&path, "[1,").unwrap();
+        let message = from_file(&path).unwrap_err().to_string();
+        assert_eq!(
+            message,
+            format!("{}: unexpected end of input at offset 3", path.display())
+        );
+        std::fs::remove_file(&path).unwrap();
+        let err = from_file(&path).unwrap_err();
+        assert!(
+            matches!(&err, JsonError::File { source, .. } if matches!(**source, JsonError::Io(_)))
+        );
+        assert!(err.to_string().starts_with(&path.display().to_string()));
+    }
+
+    #[test]
+    fn for_each_element_from_reader_should_match_the_in_memory_version() {
+        let records: Vec<String> = (0..2_000)
+            .map(|i| format!(r#"{{"id": {}, "name": "n{}", "tags": [[], {{}}]}}"#, i, i))
+            .collect();
+        let input = format!(" [\n{}\n] ", records.join(",\n"));
+        for stop_at in [None, Some(1), Some(500)] {
+            let flow = |i: usize| match stop_at {
+                Some(n) if i + 1 == n => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            };
+            let (mut expected, mut actual) = (Vec::new(), Vec::new());
+            let from_memory = for_each_element(&input, |i, v| {
+                expected.push((i, v));
+                flow(i)
+            });
+            let from_reader = for_each_element_from_reader(chunked(&input, None), |i, v| {
+                actual.push((i, v));
+                flow(i)
+            });
+            assert_eq!(from_reader, from_memory);
+            assert_eq!(actual, expected);
+            assert_eq!(actual.len(), stop_at.unwrap_or(2_000));
+        }
+        let keep_going = |_, _| ControlFlow::Continue(());
+        for input in [
+            "[0, 1, {\"a\": }, 3]",
+            "[1 2]",
+            "[1,]",
+            "[1] x",
+            "{}",
+            "",
+            "[\"\\q\"]",
+        ] {
+            assert_eq!(
+                for_each_element_from_reader(chunked(input, None), keep_going),
+                for_each_element(input, keep_going),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn from_reader_auto_should_read_plain_and_gzip_input() {
+        let expected = parse_json(DOC).unwrap();
+        assert_eq!(from_reader_auto(chunked(DOC, None)).unwrap(), expected);
+        for doc in ["", "0", "[1 2]"] {
+            assert_eq!(
+                from_reader_auto(chunked(doc, None)),
+                parse_json(doc),
+                "{}",
+                doc
+            );
+        }
+        let plain = MaybeGzip::new(DOC.as_bytes()).unwrap();
+        assert!(!plain.is_gzip());
+
+        let gzip = &include_bytes!("../../tests/golden/items.json.gz")[..];
+        let text = include_str!("../../tests/golden/items.json");
+        #[cfg(feature = "gzip")]
+        {
+            assert!(MaybeGzip::new(gzip).unwrap().is_gzip());
+            assert_eq!(from_reader_auto(gzip).unwrap(), parse_json(text).unwrap());
+            let cut = &gzip[..gzip.len() / 2];
+            match from_reader_auto(cut).unwrap_err() {
+                JsonError::Io(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+                e => panic!("{:?}", e),
+            }
+        }
+        #[cfg(not(feature = "gzip"))]
+        {
+            let _ = text;
+            match from_reader_auto(gzip).unwrap_err() {
+                JsonError::Io(e) => assert_eq!(e.kind(), io::ErrorKind::Unsupported),
+                e => panic!("{:?}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use super::{
+    error::escape_pointer_token,
+    pointer::{array_index, tokens},
+    JsonValue,
+};
+use std::borrow::Cow;
+
+/// What [`JsonValue::redact_default`] puts in place of a redacted value.
+pub const REDACTED: &str = "[REDACTED]";
+
+impl JsonValue {
+    /// Replace every value a pattern matches with `replacement`, and say
+    /// which, as JSON Pointers in document order with members in key
+    /// order. A pattern is a JSON Pointer whose reference token `*` stands
+    /// for any one member or element and `**` for any number of levels,
+    /// none included: `/users/*/password`, `/**/token`. A value that is
+    /// replaced is not looked inside, and a malformed pattern matches
+    /// nothing. Patterns are matched while walking, so no pointer is built
+    /// for a value that no pattern can reach.
+    pub fn redact(&mut self, patterns: &[&str], replacement: JsonValue) -> Vec<String> {
+        let patterns = Patterns::new(patterns.iter().copied());
+        let mut redacted = Vec::new();
+        let mut walk = Walk {
+            patterns: &patterns,
+            replacement: &replacement,
+            path: String::new(),
+            redacted: &mut redacted,
+        };
+        walk.visit(self, patterns.start());
+        redacted
+    }
+
+    /// [`redact`](Self::redact), with the string [`REDACTED`].
+    pub fn redact_default(&mut self, patterns: &[&str]) -> Vec<String> {
+        self.redact(patterns, REDACTED.into())
+    }
+}
+
+/// A reference token of a pattern.
+#[derive(PartialEq)]
+enum Segment<'p> {
+    Token(Cow<'p, str>),
+    One,
+    Any,
+}
+
+/// Patterns as [`JsonValue::redact`] takes them, matched a level at a
+/// time. A state pairs each pattern that can still match with how many of
+/// its segments the path so far has used up.
+pub(crate) struct Patterns<'p>(Vec<Vec<Segment<'p>>>);
+
+pub(crate) type States = Vec<(usize, usize)>;
+
+impl<'p> Patterns<'p> {
+    /// Malformed patterns are left out, so they match nothing.
+    pub(crate) fn new(patterns: impl IntoIterator<Item = &'p str>) -> Self {
+        let compile = |pattern| {
+            let segments = tokens(pattern)?.map(|token| match &*token {
+                "*" => Segment::One,
+                "**" => Segment::Any,
+                _ => Segment::Token(token),
+            });
+            Some(segments.collect())
+        };
+        Patterns(patterns.into_iter().filter_map(compile).collect())
+    }
+
+    /// The states of the document itself.
+    pub(crate) fn start(&self) -> States {
+        self.close((0..self.0.len()).map(|p| (p, 0)).collect())
+    }
+
+    /// Whether some pattern matches the whole path.
+    pub(crate) fn matched(&self, states: &States) -> bool {
+        states.iter().any(|&(p, at)| at == self.0[p].len())
+    }
+
+    /// The states after going down one level, to an entry whose reference
+    /// token `is` recognizes.
+    pub(crate) fn step(&self, states: &States, is: impl Fn(&str) -> bool) -> States {
+        let mut next = Vec::new();
+        for &(p, at) in states {
+            let moved = match self.0[p].get(at) {
+                Some(Segment::Token(token)) if is(token) => (p, at + 1),
+                Some(Segment::One) => (p, at + 1),
+                Some(Segment::Any) => (p, at),
+                _ => continue,
+            };
+            if !next.contains(&moved) {
+                next.push(moved);
+            }
+        }
+        self.close(next)
+    }
+
+    /// `states` with those where a `**` matches no level at all.
+    fn close(&self, mut states: States) -> States {
+        let mut i = 0;
+        while i < states.len() {
+            let (p, at) = states[i];
+            if self.0[p].get(at) == Some(&Segment::Any) && !states.contains(&(p, at + 1)) {
+                states.push((p, at + 1));
+            }
+            i += 1;
+        }
+        states
+    }
+}
+
+struct Walk<'w, 'p> {
+    patterns: &'w Patterns<'p>,
+    replacement: &'w JsonValue,
+    path: String,
+    redacted: &'w mut Vec<String>,
+}
+
+impl Walk<'_, '_> {
+    fn visit(&mut self, value: &mut JsonValue, states: States) {
+        if self.patterns.matched(&states) {
+            *value = self.replacement.clone();
+            self.redacted.push(self.path.clone());
+            return;
+        }
+        match value {
+            JsonValue::Object(obj) => {
+                let mut members: Vec<_> = obj.iter_mut().collect();
+                members.sort_by(|a, b| a.0.cmp(b.0));
+                for (key, v) in members {
+                    let next = self.patterns.step(&states, |token| token == &**key);
+                    if !next.is_empty() {
+                        let len = self.path.len();
+                        self.path.push('/');
+                        self.path.push_str(&escape_pointer_token(key));
+                        self.visit(v, next);
+                        self.path.truncate(len);
+                    }
+                }
+            }
+            JsonValue::Array(arr) => {
+                for (i, v) in arr.iter_mut().enumerate() {
+                    let next = self
+                        .patterns
+                        .step(&states, |token| array_index(token) == Some(i));
+                    if !next.is_empty() {
+                        let len = self.path.len();
+                        self.path.push_str(&format!("/{}", i));
+                        self.visit(v, next);
+                        self.path.truncate(len);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const BODY: &str = r#"{
+        "users": [
+            {"name": "ann", "password": "hunter2", "auth": {"token": "t1"}},
+            {"name": "bob", "password": "swordfish"}
+        ],
+        "token": "t0",
+        "config": {"db": {"token": "t2", "a/b": 1}, "list": [{"token": "t3"}, 5]}
+    }"#;
+
+    #[test]
+    fn redact_should_match_one_level_wildcards() {
+        let mut doc = parse_json(BODY).unwrap();
+        let found = doc.redact_default(&["/users/*/password"]);
+        assert_eq!(found, ["/users/0/password", "/users/1/password"]);
+        assert_eq!(doc.pointer("/users/0/password"), Some(&REDACTED.into()));
+        assert_eq!(doc.pointer("/users/1/name"), Some(&"bob".into()));
+        assert_eq!(doc.pointer("/token"), Some(&"t0".into()));
+
+        let mut doc = parse_json(BODY).unwrap();
+        let found = doc.redact(&["/users/1", "/config/db/a~1b"], JsonValue::Null);
+        assert_eq!(found, ["/config/db/a~1b", "/users/1"]);
+        assert_eq!(doc.pointer("/users/1"), Some(&JsonValue::Null));
+    }
+
+    #[test]
+    fn redact_should_match_any_depth() {
+        let mut doc = parse_json(BODY).unwrap();
+        let found = doc.redact_default(&["/**/token"]);
+        assert_eq!(
+            found,
+            [
+                "/config/db/token",
+                "/config/list/0/token",
+                "/token",
+                "/users/0/auth/token"
+            ]
+        );
+        assert_eq!(doc.pointer("/users/1/password"), Some(&"swordfish".into()));
+        assert_eq!(doc.pointer("/config/list/1"), Some(&5i64.into()));
+        let mut expected = parse_json(BODY).unwrap();
+        for ptr in &found {
+            assert!(expected.set_pointer(ptr.as_str(), REDACTED.into()));
+        }
+        assert_eq!(doc, expected);
+    }
+
+    #[test]
+    fn redact_should_report_nothing_when_nothing_matches() {
+        let mut doc = parse_json(BODY).unwrap();
+        let before = doc.clone();
+        let found = doc.redact_default(&["/users/*/ssn", "/users/7", "/nope/**", "bad", "/~2"]);
+        assert!(found.is_empty());
+        assert_eq!(doc, before);
+        assert!(doc.redact_default(&[]).is_empty());
+    }
+
+    #[test]
+    fn redact_should_reach_into_arrays_and_stop_at_what_it_replaces() {
+        let mut doc = parse_json(r#"{"a": [[1, 2], [3, {"k": 4}]], "b": {"k": 5}}"#).unwrap();
+        let found = doc.redact(&["/a/*/1", "/**/k"], JsonValue::from(0i64));
+        assert_eq!(found, ["/a/0/1", "/a/1/1", "/b/k"]);
+        assert_eq!(
+            doc,
+            parse_json(r#"{"a": [[1, 0], [3, 0]], "b": {"k": 0}}"#).unwrap()
+        );
+        let mut doc = parse_json("[1, [2]]").unwrap();
+        assert_eq!(doc.redact_default(&["/**"]), [""]);
+        assert_eq!(doc, JsonValue::from(REDACTED));
+    }
+}
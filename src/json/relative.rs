@@ -0,0 +1,154 @@
+use super::{pointer::array_index, JsonValue, Pointer};
+
+/// What a relative JSON Pointer evaluates to: a value, or with a trailing
+/// `#` the name or index the location has in its container.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelResult<'a> {
+    Value(&'a JsonValue),
+    Key(String),
+    Index(usize),
+}
+
+impl JsonValue {
+    /// Evaluate `rel`, a Relative JSON Pointer as used by JSON Schema, from
+    /// the location `base`: `1/sibling` goes up one level and then down to
+    /// `sibling`, `0-1` moves to the previous element of the surrounding
+    /// array, and `0#` is the key or index of the location itself. `None`
+    /// when `base` or the result is not in the document, when it goes above
+    /// the root, and for malformed `rel`.
+    pub fn pointer_relative(&self, base: &Pointer, rel: &str) -> Option<RelResult<'_>> {
+        self.pointer(base)?;
+        let (up, rest) = non_negative(rel)?;
+        let (shift, rest) = match rest.strip_prefix(['+', '-']) {
+            Some(digits) => {
+                let (n, after) = non_negative(digits)?;
+                let n = isize::try_from(n).ok()?;
+                (Some(if rest.starts_with('-') { -n } else { n }), after)
+            }
+            None => (None, rest),
+        };
+        let kept = base.tokens().len().checked_sub(up)?;
+        let mut location = base.tokens()[..kept].to_vec();
+        let in_array = match location.split_last() {
+            Some((_, parent)) => matches!(walk(self, parent), Some(JsonValue::Array(_))),
+            None => false,
+        };
+        if let Some(shift) = shift {
+            // only an element can move, to another one of the same array
+            let last = location.last_mut().filter(|_| in_array)?;
+            *last = array_index(last)?.checked_add_signed(shift)?.to_string();
+        }
+        let value = walk(self, &location)?;
+        if rest == "#" {
+            let last = location.pop()?;
+            return Some(if in_array {
+                RelResult::Index(array_index(&last)?)
+            } else {
+                RelResult::Key(last)
+            });
+        }
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+        value.pointer(rest).map(RelResult::Value)
+    }
+}
+
+/// The value at the unescaped `tokens` from `value`.
+fn walk<'v>(value: &'v JsonValue, tokens: &[String]) -> Option<&'v JsonValue> {
+    tokens.iter().try_fold(value, |value, token| match value {
+        JsonValue::Object(obj) => obj.get(token.as_str()),
+        JsonValue::Array(arr) => arr.get(array_index(token)?),
+        _ => None,
+    })
+}
+
+/// The non-negative integer `rel` starts with, without leading zeros, and
+/// what follows it.
+fn non_negative(rel: &str) -> Option<(usize, &str)> {
+    let end = rel.find(|c: char| !c.is_ascii_digit()).unwrap_or(rel.len());
+    Some((array_index(&rel[..end])?, &rel[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const DOC: &str = r#"{
+        "foo": ["bar", "baz"],
+        "highly": {"nested": {"objects": true}}
+    }"#;
+
+    #[test]
+    fn pointer_relative_should_follow_the_draft_examples() {
+        let doc = parse_json(DOC).unwrap();
+        let eval = |base, rel| doc.pointer_relative(&Pointer::parse(base).unwrap(), rel);
+        // starting from "baz"
+        assert_eq!(
+            eval("/foo/1", "0"),
+            Some(RelResult::Value(&JsonValue::from("baz")))
+        );
+        assert_eq!(
+            eval("/foo/1", "1/0"),
+            Some(RelResult::Value(&JsonValue::from("bar")))
+        );
+        assert_eq!(
+            eval("/foo/1", "0-1"),
+            Some(RelResult::Value(&JsonValue::from("bar")))
+        );
+        assert_eq!(
+            eval("/foo/1", "2/highly/nested/objects"),
+            Some(RelResult::Value(&JsonValue::Bool(true)))
+        );
+        assert_eq!(eval("/foo/1", "0#"), Some(RelResult::Index(1)));
+        assert_eq!(eval("/foo/1", "0-1#"), Some(RelResult::Index(0)));
+        assert_eq!(eval("/foo/1", "1#"), Some(RelResult::Key("foo".into())));
+        // starting from {"objects": true}
+        assert_eq!(
+            eval("/highly/nested", "0/objects"),
+            Some(RelResult::Value(&JsonValue::Bool(true)))
+        );
+        assert_eq!(
+            eval("/highly/nested", "1/nested/objects"),
+            Some(RelResult::Value(&JsonValue::Bool(true)))
+        );
+        assert_eq!(
+            eval("/highly/nested", "2/foo/0"),
+            Some(RelResult::Value(&JsonValue::from("bar")))
+        );
+        assert_eq!(
+            eval("/highly/nested", "0#"),
+            Some(RelResult::Key("nested".into()))
+        );
+        assert_eq!(
+            eval("/highly/nested", "1#"),
+            Some(RelResult::Key("highly".into()))
+        );
+    }
+
+    #[test]
+    fn pointer_relative_should_reject_what_is_not_there() {
+        let doc = parse_json(DOC).unwrap();
+        let eval = |base, rel| doc.pointer_relative(&Pointer::parse(base).unwrap(), rel);
+        // above the root, and the root has no name
+        assert_eq!(eval("/foo/1", "3"), None);
+        assert_eq!(eval("/foo/1", "2#"), None);
+        assert_eq!(eval("", "0#"), None);
+        assert_eq!(eval("", "0"), Some(RelResult::Value(&doc)));
+        // moving out of the array, or where there is no array
+        assert_eq!(eval("/foo/1", "0+1"), None);
+        assert_eq!(eval("/foo/0", "0-1"), None);
+        assert_eq!(
+            eval("/foo/0", "0+1"),
+            Some(RelResult::Value(&JsonValue::from("baz")))
+        );
+        assert_eq!(eval("/highly/nested", "0+1"), None);
+        // a base that is not in the document
+        assert_eq!(eval("/foo/2", "1"), None);
+        // malformed
+        for rel in ["", "01", "-1", "0+", "0+01", "0x", "0#/a", "0##", "1foo"] {
+            assert_eq!(eval("/foo/1", rel), None, "{}", rel);
+        }
+    }
+}
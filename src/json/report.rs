@@ -0,0 +1,259 @@
+use super::{error::escape_pointer_token, patch::same_value, JsonObject, JsonValue};
+use std::fmt;
+
+/// What changed between two documents, for people and UIs rather than for
+/// [`apply_patch`](super::apply_patch): each entry carries its JSON
+/// Pointer, and a change carries both values. Members are visited in key
+/// order and arrays index by index, as [`diff`](super::diff) does, so the
+/// same pair always gives the same report.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffReport {
+    /// Members and elements only the new document has.
+    pub added: Vec<ReportEntry>,
+    /// Members and elements only the old document has.
+    pub removed: Vec<ReportEntry>,
+    /// Places where both documents hold a value, but not the same one.
+    pub changed: Vec<ReportChange>,
+    /// Arrays whose length changed, besides the elements added or removed
+    /// at their end.
+    pub resized: Vec<ReportResize>,
+}
+
+/// A value one side of a [`DiffReport`] has and the other lacks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportEntry {
+    pub pointer: String,
+    pub value: JsonValue,
+}
+
+/// A value of a [`DiffReport`] that was replaced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportChange {
+    pub pointer: String,
+    pub old: JsonValue,
+    pub new: JsonValue,
+}
+
+/// An array of a [`DiffReport`] that grew or shrank.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportResize {
+    pub pointer: String,
+    pub old: usize,
+    pub new: usize,
+}
+
+/// The report of what turns `a` into `b`. Values are compared as
+/// [`diff`](super::diff) compares them, so `1` and `1.0` are the same.
+pub fn diff_report(a: &JsonValue, b: &JsonValue) -> DiffReport {
+    let mut report = DiffReport::default();
+    report_into(&mut report, &mut String::new(), a, b);
+    report
+}
+
+fn report_into(report: &mut DiffReport, path: &mut String, a: &JsonValue, b: &JsonValue) {
+    let entry = |path: &str, value: &JsonValue| ReportEntry {
+        pointer: path.to_string(),
+        value: value.clone(),
+    };
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            let mut keys: Vec<_> = a
+                .keys()
+                .chain(b.keys().filter(|k| !a.contains_key(*k)))
+                .collect();
+            keys.sort();
+            for key in keys {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&escape_pointer_token(key));
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => report_into(report, path, x, y),
+                    (Some(x), None) => report.removed.push(entry(path, x)),
+                    (None, Some(y)) => report.added.push(entry(path, y)),
+                    (None, None) => unreachable!("key comes from one side"),
+                }
+                path.truncate(len);
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            if a.len() != b.len() {
+                report.resized.push(ReportResize {
+                    pointer: path.clone(),
+                    old: a.len(),
+                    new: b.len(),
+                });
+            }
+            let len = path.len();
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                path.push_str(&format!("/{}", i));
+                report_into(report, path, x, y);
+                path.truncate(len);
+            }
+            for (i, y) in b.iter().enumerate().skip(a.len()) {
+                report.added.push(entry(&format!("{}/{}", path, i), y));
+            }
+            for (i, x) in a.iter().enumerate().skip(b.len()) {
+                report.removed.push(entry(&format!("{}/{}", path, i), x));
+            }
+        }
+        _ if same_value(a, b) => {}
+        _ => report.changed.push(ReportChange {
+            pointer: path.clone(),
+            old: a.clone(),
+            new: b.clone(),
+        }),
+    }
+}
+
+impl DiffReport {
+    /// Whether the documents are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.resized.is_empty()
+    }
+
+    /// The report as an object with the arrays `added`, `removed`,
+    /// `changed` and `resized`, each entry an object with a `pointer`
+    /// member and `value`, or `old` and `new`.
+    pub fn to_json(&self) -> JsonValue {
+        self.clone().into()
+    }
+}
+
+impl From<DiffReport> for JsonValue {
+    fn from(report: DiffReport) -> Self {
+        fn entry<const N: usize>(pointer: String, members: [(&str, JsonValue); N]) -> JsonValue {
+            let mut obj = JsonObject::default();
+            obj.insert("pointer".into(), pointer.as_str().into());
+            for (key, value) in members {
+                obj.insert(key.into(), value);
+            }
+            JsonValue::Object(obj)
+        }
+        let list = |entries: Vec<ReportEntry>| {
+            let entries = entries.into_iter();
+            JsonValue::Array(
+                entries
+                    .map(|e| entry(e.pointer, [("value", e.value)]))
+                    .collect(),
+            )
+        };
+        let mut obj = JsonObject::default();
+        obj.insert("added".into(), list(report.added));
+        obj.insert("removed".into(), list(report.removed));
+        let changed = report
+            .changed
+            .into_iter()
+            .map(|c| entry(c.pointer, [("old", c.old), ("new", c.new)]));
+        obj.insert("changed".into(), JsonValue::Array(changed.collect()));
+        let resized = report.resized.into_iter().map(|r| {
+            let len = |n: usize| JsonValue::from(n as i64);
+            entry(r.pointer, [("old", len(r.old)), ("new", len(r.new))])
+        });
+        obj.insert("resized".into(), JsonValue::Array(resized.collect()));
+        JsonValue::Object(obj)
+    }
+}
+
+/// One line per entry: `- "/a": 1` for a removal, `+ "/b": 2` for an
+/// addition, `~ "/c": 1 -> 2` for a change and `# "/d": length 1 -> 3` for
+/// a resized array, values written compactly, in that order. The pointer
+/// `""` is the whole document.
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for e in &self.removed {
+            writeln!(f, "- \"{}\": {}", e.pointer, e.value.to_json_string())?;
+        }
+        for e in &self.added {
+            writeln!(f, "+ \"{}\": {}", e.pointer, e.value.to_json_string())?;
+        }
+        for c in &self.changed {
+            writeln!(
+                f,
+                "~ \"{}\": {} -> {}",
+                c.pointer,
+                c.old.to_json_string(),
+                c.new.to_json_string()
+            )?;
+        }
+        for r in &self.resized {
+            writeln!(f, "# \"{}\": length {} -> {}", r.pointer, r.old, r.new)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    fn report(a: &str, b: &str) -> DiffReport {
+        diff_report(&parse_json(a).unwrap(), &parse_json(b).unwrap())
+    }
+
+    #[test]
+    fn diff_report_should_categorize_changes() {
+        let r = report(
+            r#"{"keep": 1, "gone": true, "n": 1, "list": [1, 2, 3], "deep": {"x": "a"}}"#,
+            r#"{"keep": 1.0, "new": null, "n": 2, "list": [1, 5], "deep": {"x": "b", "y": []}}"#,
+        );
+        let entry = |pointer: &str, value: &str| ReportEntry {
+            pointer: pointer.to_string(),
+            value: parse_json(value).unwrap(),
+        };
+        assert_eq!(r.added, [entry("/deep/y", "[]"), entry("/new", "null")]);
+        assert_eq!(r.removed, [entry("/gone", "true"), entry("/list/2", "3")]);
+        let pointers: Vec<_> = r.changed.iter().map(|c| c.pointer.as_str()).collect();
+        assert_eq!(pointers, ["/deep/x", "/list/1", "/n"]);
+        assert_eq!(r.changed[2].old, JsonValue::from(1i64));
+        assert_eq!(r.changed[2].new, JsonValue::from(2i64));
+        assert_eq!(
+            r.resized,
+            [ReportResize {
+                pointer: "/list".to_string(),
+                old: 3,
+                new: 2
+            }]
+        );
+        assert!(report(r#"{"a": [1, {"b": 2}]}"#, r#"{"a": [1.0, {"b": 2}]}"#).is_empty());
+    }
+
+    #[test]
+    fn diff_report_should_escape_pointers_and_render() {
+        let r = report(
+            r#"{"a/b": 1, "m~n": [0], "s": "x"}"#,
+            r#"{"a/b": 2, "m~n": [0, {"k": "v"}], "t": "x"}"#,
+        );
+        assert_eq!(
+            r.to_string(),
+            concat!(
+                "- \"/s\": \"x\"\n",
+                "+ \"/m~0n/1\": {\"k\":\"v\"}\n",
+                "+ \"/t\": \"x\"\n",
+                "~ \"/a~1b\": 1 -> 2\n",
+                "# \"/m~0n\": length 1 -> 2\n",
+            )
+        );
+        assert_eq!(report("1", "[1]").to_string(), "~ \"\": 1 -> [1]\n");
+        assert_eq!(report("[]", "[]").to_string(), "");
+    }
+
+    #[test]
+    fn diff_report_should_convert_to_json() {
+        let r = report(r#"{"a": [1], "b": 1}"#, r#"{"a": [], "b": 2, "c": 3}"#);
+        let expected = parse_json(
+            r#"{
+                "added": [{"pointer": "/c", "value": 3}],
+                "removed": [{"pointer": "/a/0", "value": 1}],
+                "changed": [{"pointer": "/b", "old": 1, "new": 2}],
+                "resized": [{"pointer": "/a", "old": 1, "new": 0}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(r.to_json(), expected);
+        assert_eq!(JsonValue::from(r), expected);
+    }
+}
@@ -0,0 +1,225 @@
+#[cfg(feature = "compact-str")]
+use super::parse_string;
+use super::{
+    entries, json_string, open, parse_bool, parse_nonfinite, parse_null, parse_num,
+    parse_string_into, sep_with_ws, strict, ws, Input, JsonError, JsonObject, JsonValue, Num,
+};
+use std::mem;
+use winnow::{combinator::alt, error::ErrMode, prelude::*, stream::Stream};
+
+/// Buffers that [`parse_json_into`] needs only while it runs, such as the
+/// unescaped key being looked up. Parsing with a kept `Scratch` reuses
+/// those too.
+#[derive(Debug, Default)]
+pub struct Scratch {
+    key: String,
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// [`parse_json_into`] borrowing these buffers.
+    pub fn parse_into(&mut self, input: &str, out: &mut JsonValue) -> Result<(), JsonError> {
+        let mut rest = strict(input);
+        (ws, |i: &mut Input<'_>| value(i, out, self), ws)
+            .parse_next(&mut rest)
+            .map_err(|e| JsonError::from_parser(input, rest.input, e))?;
+        if !rest.input.is_empty() {
+            return Err(JsonError::parse_at(input, rest.input).trailing());
+        }
+        Ok(())
+    }
+}
+
+/// Parse strict JSON like [`parse_json`](super::parse_json) into `out`,
+/// reusing the allocations of the value already there. Where the new
+/// document has the same shape, strings and arrays are overwritten in
+/// place and object members are moved over by key, so parsing a stream of
+/// similar documents allocates little after the first. Parts that differ
+/// are replaced. On success `out` equals a fresh parse; on error its
+/// contents are unspecified.
+pub fn parse_json_into(input: &str, out: &mut JsonValue) -> Result<(), JsonError> {
+    Scratch::new().parse_into(input, out)
+}
+
+/// [`parse_value`](super::parse_value) into `slot`. The alternatives are
+/// tried by hand because the reusing ones need `slot`.
+fn value(input: &mut Input<'_>, slot: &mut JsonValue, scratch: &mut Scratch) -> PResult<()> {
+    let start = input.checkpoint();
+    let scalar = alt((
+        parse_null.value(JsonValue::Null),
+        parse_bool.map(JsonValue::Bool),
+        parse_nonfinite.map(|f| JsonValue::Number(Num::Float(f))),
+        parse_num.map(JsonValue::Number),
+    ))
+    .parse_next(input);
+    match scalar {
+        Err(ErrMode::Backtrack(_)) => input.reset(&start),
+        scalar => return scalar.map(|v| *slot = v),
+    }
+    match string(input, slot) {
+        Err(ErrMode::Backtrack(_)) => input.reset(&start),
+        s => return s,
+    }
+    match array(input, slot, scratch) {
+        Err(ErrMode::Backtrack(_)) => input.reset(&start),
+        arr => return arr,
+    }
+    object(input, slot, scratch)
+}
+
+#[cfg(not(feature = "compact-str"))]
+fn string(input: &mut Input<'_>, slot: &mut JsonValue) -> PResult<()> {
+    let mut s = match slot {
+        JsonValue::String(s) => mem::take(s),
+        _ => String::new(),
+    };
+    s.clear();
+    let parsed = parse_string_into(input, &mut s);
+    match (&parsed, slot) {
+        (Ok(()), slot) => *slot = JsonValue::String(s),
+        // not a string after all: keep the buffer for whatever comes next
+        (Err(_), JsonValue::String(old)) => *old = s,
+        _ => {}
+    }
+    parsed
+}
+
+/// A [`SmallString`](super::SmallString) cannot be written into, and the
+/// short ones hold no allocation to keep, so strings are parsed afresh.
+#[cfg(feature = "compact-str")]
+fn string(input: &mut Input<'_>, slot: &mut JsonValue) -> PResult<()> {
+    *slot = JsonValue::String(parse_string(input)?);
+    Ok(())
+}
+
+fn array(input: &mut Input<'_>, slot: &mut JsonValue, scratch: &mut Scratch) -> PResult<()> {
+    open('[').parse_next(input)?;
+    if !matches!(slot, JsonValue::Array(_)) {
+        *slot = JsonValue::Array(Vec::new());
+    }
+    let JsonValue::Array(arr) = slot else {
+        unreachable!("just made an array")
+    };
+    let mut len = 0;
+    let parsed = entries(input, ']', |i: &mut Input<'_>| {
+        if len == arr.len() {
+            arr.push(JsonValue::Null);
+        }
+        value(i, &mut arr[len], scratch)?;
+        len += 1;
+        Ok(())
+    });
+    arr.truncate(len);
+    parsed.map(|_| ())
+}
+
+fn object(input: &mut Input<'_>, slot: &mut JsonValue, scratch: &mut Scratch) -> PResult<()> {
+    open('{').parse_next(input)?;
+    // members of the old object are taken out by key as the keys come up
+    let mut old = match slot {
+        JsonValue::Object(obj) => mem::take(obj),
+        _ => JsonObject::default(),
+    };
+    let mut obj = JsonObject::default();
+    let parsed = entries(input, '}', |i: &mut Input<'_>| {
+        scratch.key.clear();
+        parse_string_into(i, &mut scratch.key)?;
+        sep_with_ws(':').parse_next(i)?;
+        // a repeated key overwrites its earlier value where it stands
+        if let Some(slot) = obj.get_mut(scratch.key.as_str()) {
+            return value(i, slot, scratch);
+        }
+        let (key, mut member) = old
+            .remove_entry(scratch.key.as_str())
+            .unwrap_or_else(|| (json_string(scratch.key.as_str()), JsonValue::Null));
+        value(i, &mut member, scratch)?;
+        obj.insert(key, member);
+        Ok(())
+    });
+    *slot = JsonValue::Object(obj);
+    parsed.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    fn record(i: usize) -> String {
+        format!(
+            r#"{{"id": {}, "name": "name {:04}", "tags": ["t{}", "u\n{}"], "owner": {{"login": "l{}", "dup": 1, "dup": "x{}"}}}}"#,
+            i,
+            i,
+            i % 10,
+            i % 10,
+            i % 10,
+            i % 10
+        )
+    }
+
+    #[test]
+    fn parse_json_into_should_equal_a_fresh_parse() {
+        let mut scratch = Scratch::new();
+        let mut out = JsonValue::Null;
+        for i in 0..50 {
+            let input = format!("[{}, {}]", record(i), record(i + 1));
+            scratch.parse_into(&input, &mut out).unwrap();
+            assert_eq!(out, parse_json(&input).unwrap(), "{}", input);
+        }
+        // shapes that change between parses
+        for input in [
+            "{\"a\": [1, 2, 3], \"b\": \"s\"}",
+            "{\"a\": \"s\", \"c\": {\"d\": null}}",
+            "[{\"a\": [1]}, 2, \"three\", [4, [5]]]",
+            "[true]",
+            "\"esc\\u00e9\"",
+            "{\"esc\\\"aped\": {}, \"\": []}",
+            "12.5",
+        ] {
+            parse_json_into(input, &mut out).unwrap();
+            assert_eq!(out, parse_json(input).unwrap(), "{}", input);
+        }
+        for input in ["[1,]", "{\"a\" 1}", "{\"a\": }", "[\"x\\q\"]", "[1] 2", ""] {
+            assert_eq!(
+                parse_json_into(input, &mut out),
+                Err(parse_json(input).unwrap_err()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn parse_json_into_should_reuse_allocations() {
+        fn buffers(value: &JsonValue) -> (*const JsonValue, *const u8, *const u8) {
+            let ptr = |p| match value.pointer(p) {
+                Some(JsonValue::Array(arr)) => arr.as_ptr() as *const u8,
+                // strings reuse no buffer when they are small strings
+                Some(JsonValue::String(_)) if cfg!(feature = "compact-str") => std::ptr::null(),
+                Some(JsonValue::String(s)) => s.as_ptr(),
+                v => panic!("{} is {:?}", p, v),
+            };
+            (
+                ptr("/0/tags") as *const JsonValue,
+                ptr("/0/name"),
+                ptr("/0/owner/login"),
+            )
+        }
+
+        let mut scratch = Scratch::new();
+        let mut out = JsonValue::Null;
+        scratch
+            .parse_into(&format!("[{}]", record(0)), &mut out)
+            .unwrap();
+        let before = buffers(&out);
+        for i in 1..20 {
+            let input = format!("[{}]", record(i));
+            scratch.parse_into(&input, &mut out).unwrap();
+            assert_eq!(out, parse_json(&input).unwrap());
+            assert_eq!(buffers(&out), before, "{}", i);
+        }
+    }
+}
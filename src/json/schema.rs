@@ -0,0 +1,1217 @@
+use super::{
+    error::escape_pointer_token, JsonError, JsonEvent, JsonObject, JsonReader, JsonString,
+    JsonValue, Num, ValueBuilder,
+};
+use thiserror::Error;
+
+/// The JSON Schema `type` names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaType {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    /// A number with no fractional part, including floats such as `1.0`.
+    Integer,
+    String,
+}
+
+impl SchemaType {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "null" => SchemaType::Null,
+            "boolean" => SchemaType::Boolean,
+            "object" => SchemaType::Object,
+            "array" => SchemaType::Array,
+            "number" => SchemaType::Number,
+            "integer" => SchemaType::Integer,
+            "string" => SchemaType::String,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SchemaType::Null => "null",
+            SchemaType::Boolean => "boolean",
+            SchemaType::Object => "object",
+            SchemaType::Array => "array",
+            SchemaType::Number => "number",
+            SchemaType::Integer => "integer",
+            SchemaType::String => "string",
+        }
+    }
+
+    fn matches(self, value: &JsonValue) -> bool {
+        match (self, value) {
+            (SchemaType::Null, JsonValue::Null)
+            | (SchemaType::Boolean, JsonValue::Bool(_))
+            | (SchemaType::Object, JsonValue::Object(_))
+            | (SchemaType::Array, JsonValue::Array(_))
+            | (SchemaType::Number, JsonValue::Number(_))
+            | (SchemaType::String, JsonValue::String(_)) => true,
+            (SchemaType::Integer, JsonValue::Number(n)) => as_f64(n).fract() == 0.0,
+            _ => false,
+        }
+    }
+}
+
+/// A schema document is malformed, for example `"minimum": "ten"`.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("invalid schema at \"{pointer}\": {message}")]
+pub struct SchemaError {
+    /// JSON Pointer into the schema document.
+    pub pointer: String,
+    pub message: String,
+}
+
+/// One way an instance fails a schema.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("\"{pointer}\" fails {keyword} at \"{schema_pointer}\": {message}")]
+pub struct ValidationError {
+    /// JSON Pointer of the offending value in the instance.
+    pub pointer: String,
+    /// JSON Pointer of the failing keyword in the schema document; for a
+    /// keyword reached through `$ref`, where the reference leads.
+    pub schema_pointer: String,
+    pub keyword: &'static str,
+    pub message: String,
+}
+
+/// A subset of JSON Schema covering the core validation keywords: `type`,
+/// `enum`, `const`, `minimum`, `maximum`, `minLength`, `maxLength`,
+/// `properties`, `required`, `additionalProperties`, `items`, `minItems`
+/// and `maxItems`, the composition keywords `allOf`, `anyOf`, `oneOf` and
+/// `not`, and `$ref` to a schema in the same document, such as
+/// `#/definitions/node` or `#/$defs/node`; nothing is fetched. Other
+/// keywords are ignored, as the specification asks.
+///
+/// A reference may lead back to a schema it is in, so recursive shapes
+/// such as trees can be described. Following references that come back
+/// round without moving into the instance constrains nothing more, so the
+/// cycle is cut there rather than followed forever.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    /// JSON Pointer of this schema in the schema document.
+    location: String,
+    /// Set for the `false` schema, which nothing satisfies.
+    reject: bool,
+    types: Option<Vec<SchemaType>>,
+    enum_values: Option<Vec<JsonValue>>,
+    const_value: Option<JsonValue>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    properties: Vec<(String, Schema)>,
+    required: Vec<String>,
+    additional_properties: Option<Box<Schema>>,
+    items: Option<Box<Schema>>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    all_of: Vec<Schema>,
+    any_of: Vec<Schema>,
+    one_of: Vec<Schema>,
+    not: Option<Box<Schema>>,
+    /// The index in the root's `refs` of the schema `$ref` names.
+    reference: Option<usize>,
+    /// Set on the root only: each schema a `$ref` names, parsed once.
+    refs: Vec<Schema>,
+}
+
+/// The references met while parsing a schema document.
+struct Refs<'v> {
+    root: &'v JsonValue,
+    /// Each target with the pointer after the `#` that names it.
+    targets: Vec<(String, Schema)>,
+}
+
+impl Refs<'_> {
+    /// The index of the schema `reference` names, parsing it the first time
+    /// it is named. A target is entered before it is parsed, so a schema
+    /// that refers to itself is parsed once. `from` is the schema holding
+    /// the `$ref`.
+    fn resolve(&mut self, reference: &str, from: &str) -> Result<usize, SchemaError> {
+        let bad = |message: &str| invalid(from, "$ref", message);
+        let Some(ptr) = reference.strip_prefix('#') else {
+            return Err(bad("only references within the document are supported"));
+        };
+        if let Some(i) = self.targets.iter().position(|(p, _)| p == ptr) {
+            return Ok(i);
+        }
+        let root = self.root;
+        let Some(target) = root.pointer(ptr) else {
+            return Err(bad(&format!("unresolved reference {:?}", reference)));
+        };
+        let i = self.targets.len();
+        self.targets.push((ptr.to_string(), Schema::default()));
+        self.targets[i].1 = Schema::parse(target, &mut ptr.to_string(), self)?;
+        Ok(i)
+    }
+}
+
+impl Schema {
+    pub fn from_value(value: &JsonValue) -> Result<Self, SchemaError> {
+        let mut refs = Refs {
+            root: value,
+            targets: Vec::new(),
+        };
+        let mut schema = Self::parse(value, &mut String::new(), &mut refs)?;
+        schema.refs = refs.targets.into_iter().map(|(_, s)| s).collect();
+        Ok(schema)
+    }
+
+    fn parse(
+        value: &JsonValue,
+        pointer: &mut String,
+        refs: &mut Refs,
+    ) -> Result<Self, SchemaError> {
+        let obj = match value {
+            JsonValue::Bool(b) => {
+                return Ok(Schema {
+                    location: pointer.clone(),
+                    reject: !b,
+                    ..Default::default()
+                })
+            }
+            JsonValue::Object(obj) => obj,
+            _ => {
+                return Err(invalid(
+                    pointer,
+                    "",
+                    "a schema must be an object or a boolean",
+                ))
+            }
+        };
+        let mut schema = Schema {
+            location: pointer.clone(),
+            ..Default::default()
+        };
+        for (keyword, v) in obj {
+            let keyword = keyword.as_str();
+            let bad = |message: &str| invalid(pointer, keyword, message);
+            match keyword {
+                "type" => {
+                    let names = match v {
+                        JsonValue::String(s) => vec![s],
+                        JsonValue::Array(arr) => arr
+                            .iter()
+                            .map(|v| match v {
+                                JsonValue::String(s) => Ok(s),
+                                _ => Err(bad("type names must be strings")),
+                            })
+                            .collect::<Result<_, _>>()?,
+                        _ => return Err(bad("must be a string or an array of strings")),
+                    };
+                    let types = names
+                        .into_iter()
+                        .map(|n| {
+                            SchemaType::from_name(n)
+                                .ok_or_else(|| bad(&format!("unknown type {:?}", n)))
+                        })
+                        .collect::<Result<_, _>>()?;
+                    schema.types = Some(types);
+                }
+                "enum" => match v {
+                    JsonValue::Array(arr) => schema.enum_values = Some(arr.clone()),
+                    _ => return Err(bad("must be an array")),
+                },
+                "const" => schema.const_value = Some(v.clone()),
+                "minimum" | "maximum" => {
+                    let n = match v {
+                        JsonValue::Number(n) => as_f64(n),
+                        _ => return Err(bad("must be a number")),
+                    };
+                    if keyword == "minimum" {
+                        schema.minimum = Some(n);
+                    } else {
+                        schema.maximum = Some(n);
+                    }
+                }
+                "minLength" | "maxLength" | "minItems" | "maxItems" => {
+                    let n = match v {
+                        JsonValue::Number(Num::Int(i)) if *i >= 0 => *i as usize,
+                        _ => return Err(bad("must be a non-negative integer")),
+                    };
+                    *match keyword {
+                        "minLength" => &mut schema.min_length,
+                        "maxLength" => &mut schema.max_length,
+                        "minItems" => &mut schema.min_items,
+                        _ => &mut schema.max_items,
+                    } = Some(n);
+                }
+                "properties" => {
+                    let JsonValue::Object(props) = v else {
+                        return Err(bad("must be an object"));
+                    };
+                    let mut props: Vec<_> = props.iter().collect();
+                    props.sort_unstable_by_key(|(k, _)| *k);
+                    for (name, sub) in props {
+                        let sub = within(pointer, &["properties", name], |p| {
+                            Self::parse(sub, p, refs)
+                        })?;
+                        schema.properties.push((name.to_string(), sub));
+                    }
+                }
+                "required" => {
+                    let JsonValue::Array(names) = v else {
+                        return Err(bad("must be an array of strings"));
+                    };
+                    for name in names {
+                        match name {
+                            JsonValue::String(s) => schema.required.push(s.to_string()),
+                            _ => return Err(bad("must be an array of strings")),
+                        }
+                    }
+                }
+                "additionalProperties" | "items" => {
+                    let sub = within(pointer, &[keyword], |p| Self::parse(v, p, refs))?;
+                    if keyword == "items" {
+                        schema.items = Some(Box::new(sub));
+                    } else {
+                        schema.additional_properties = Some(Box::new(sub));
+                    }
+                }
+                "allOf" | "anyOf" | "oneOf" => {
+                    let subs = match v {
+                        JsonValue::Array(subs) if !subs.is_empty() => subs,
+                        _ => return Err(bad("must be a non-empty array of schemas")),
+                    };
+                    let subs = subs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, sub)| {
+                            within(pointer, &[keyword, &i.to_string()], |p| {
+                                Self::parse(sub, p, refs)
+                            })
+                        })
+                        .collect::<Result<_, _>>()?;
+                    *match keyword {
+                        "allOf" => &mut schema.all_of,
+                        "anyOf" => &mut schema.any_of,
+                        _ => &mut schema.one_of,
+                    } = subs;
+                }
+                "not" => {
+                    let sub = within(pointer, &[keyword], |p| Self::parse(v, p, refs))?;
+                    schema.not = Some(Box::new(sub));
+                }
+                "$ref" => {
+                    let JsonValue::String(reference) = v else {
+                        return Err(bad("must be a string"));
+                    };
+                    schema.reference = Some(refs.resolve(reference, pointer)?);
+                }
+                _ => {}
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Check `value` against the schema, collecting every violation.
+    pub fn validate(&self, value: &JsonValue) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let mut checker = Checker {
+            refs: &self.refs,
+            following: Vec::new(),
+        };
+        checker.check(self, value, &mut String::new(), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// [`validate`](Self::validate) for the JSON text `input`, checking it
+    /// as [`JsonReader`] reads it instead of parsing it into a tree first.
+    /// Only a value that a keyword must see whole is built: one under
+    /// `enum`, `const`, `anyOf`, `oneOf` or `not`, or the `false` schema,
+    /// and it is checked as `validate` checks it. Otherwise memory grows
+    /// with the depth of the document, not its size. The violations are
+    /// those `validate` finds in `parse_json(input)`, though not always in
+    /// the same order, except that where an object repeats a key each of
+    /// its values is checked, not just the last. The outer error is
+    /// `input` not being JSON; violations found before it are dropped.
+    pub fn validate_stream(
+        &self,
+        input: &str,
+    ) -> Result<Result<(), Vec<ValidationError>>, JsonError> {
+        let mut stream = Stream {
+            refs: &self.refs,
+            frames: Vec::new(),
+            pointer: String::new(),
+            errors: Vec::new(),
+        };
+        let mut reader = JsonReader::new(input);
+        while let Some(event) = reader.next_event()? {
+            stream.event(self, event);
+        }
+        Ok(if stream.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(stream.errors)
+        })
+    }
+}
+
+/// Checks the events of a document against a schema as they come.
+struct Stream<'s> {
+    refs: &'s [Schema],
+    /// Open containers, innermost last.
+    frames: Vec<Frame<'s>>,
+    /// JSON Pointer of the value the next event belongs to.
+    pointer: String,
+    errors: Vec<ValidationError>,
+}
+
+enum Frame<'s> {
+    /// A value being built, for `schemas` to check once it is whole.
+    Whole {
+        schemas: Vec<&'s Schema>,
+        builder: ValueBuilder,
+        depth: usize,
+    },
+    /// A container checked entry by entry. `applied` holds the schemas
+    /// for it, with those their `allOf` and `$ref` lead to.
+    Open {
+        applied: Vec<&'s Schema>,
+        object: bool,
+        entries: usize,
+        /// The required keys seen so far.
+        seen: Vec<JsonString>,
+        /// The schemas for the value of the member whose key came last.
+        member: Vec<&'s Schema>,
+        /// The length of `pointer` at the container.
+        at: usize,
+    },
+}
+
+impl<'s> Stream<'s> {
+    fn event(&mut self, root: &'s Schema, event: JsonEvent) {
+        if let Some(Frame::Whole { builder, depth, .. }) = self.frames.last_mut() {
+            match event {
+                JsonEvent::ObjectStart | JsonEvent::ArrayStart => *depth += 1,
+                JsonEvent::ObjectEnd | JsonEvent::ArrayEnd => *depth -= 1,
+                _ => {}
+            }
+            let done = *depth == 0;
+            builder.event(event).expect("the reader pairs containers");
+            if done {
+                let Some(Frame::Whole {
+                    schemas, builder, ..
+                }) = self.frames.pop()
+                else {
+                    unreachable!("the top frame is whole")
+                };
+                let value = builder.finish().expect("the value is closed");
+                self.check_whole(&schemas, &value);
+                self.entry_done();
+            }
+            return;
+        }
+        match event {
+            JsonEvent::Key(key) => self.key(key),
+            JsonEvent::ObjectEnd | JsonEvent::ArrayEnd => {
+                let Some(Frame::Open {
+                    applied,
+                    object,
+                    entries,
+                    seen,
+                    ..
+                }) = self.frames.pop()
+                else {
+                    unreachable!("the reader pairs containers")
+                };
+                for schema in applied {
+                    if object {
+                        let has = |name: &str| seen.iter().any(|k| &**k == name);
+                        check_required(schema, has, &self.pointer, &mut self.errors);
+                    } else {
+                        check_items(schema, entries, &self.pointer, &mut self.errors);
+                    }
+                }
+                self.entry_done();
+            }
+            JsonEvent::ObjectStart | JsonEvent::ArrayStart => {
+                let object = event == JsonEvent::ObjectStart;
+                let schemas = self.entry_schemas(root);
+                let mut applied = Vec::new();
+                for schema in &schemas {
+                    self.expand(schema, &mut Vec::new(), &mut applied);
+                }
+                if applied.iter().any(|s| needs_whole(s)) {
+                    let mut builder = ValueBuilder::new();
+                    builder.event(event).expect("a value may start here");
+                    self.frames.push(Frame::Whole {
+                        schemas,
+                        builder,
+                        depth: 1,
+                    });
+                    return;
+                }
+                let empty = match object {
+                    true => JsonValue::Object(JsonObject::default()),
+                    false => JsonValue::Array(Vec::new()),
+                };
+                for schema in &applied {
+                    check_type(schema, &empty, &self.pointer, &mut self.errors);
+                }
+                self.frames.push(Frame::Open {
+                    applied,
+                    object,
+                    entries: 0,
+                    seen: Vec::new(),
+                    member: Vec::new(),
+                    at: self.pointer.len(),
+                });
+            }
+            scalar => {
+                let schemas = self.entry_schemas(root);
+                let value = match scalar {
+                    JsonEvent::String(s) => JsonValue::String(s),
+                    JsonEvent::Number(n) => JsonValue::Number(n),
+                    JsonEvent::Bool(b) => JsonValue::Bool(b),
+                    _ => JsonValue::Null,
+                };
+                self.check_whole(&schemas, &value);
+                self.entry_done();
+            }
+        }
+    }
+
+    /// Step into the member `key` of the innermost object.
+    fn key(&mut self, key: JsonString) {
+        let Some(Frame::Open {
+            applied,
+            seen,
+            member,
+            ..
+        }) = self.frames.last_mut()
+        else {
+            unreachable!("keys are in objects")
+        };
+        member.clear();
+        for schema in applied.iter() {
+            if let Some((_, sub)) = schema.properties.iter().find(|(name, _)| **name == *key) {
+                member.push(sub);
+            } else if let Some(additional) = &schema.additional_properties {
+                if additional.reject {
+                    let message = format!("property {:?} is not allowed", key);
+                    let error = failure(schema, &self.pointer, "additionalProperties", message);
+                    self.errors.push(error);
+                } else {
+                    member.push(additional);
+                }
+            }
+            if schema.required.iter().any(|name| **name == *key) && !seen.contains(&key) {
+                seen.push(key.clone());
+            }
+        }
+        self.pointer.push('/');
+        self.pointer.push_str(&escape_pointer_token(&key));
+    }
+
+    /// The schemas for the value that starts now, with `pointer` moved to
+    /// it.
+    fn entry_schemas(&mut self, root: &'s Schema) -> Vec<&'s Schema> {
+        match self.frames.last_mut() {
+            None => vec![root],
+            Some(Frame::Open {
+                object: true,
+                member,
+                ..
+            }) => std::mem::take(member),
+            Some(Frame::Open {
+                applied, entries, ..
+            }) => {
+                self.pointer.push_str(&format!("/{}", entries));
+                applied.iter().filter_map(|s| s.items.as_deref()).collect()
+            }
+            Some(Frame::Whole { .. }) => unreachable!("whole values take every event"),
+        }
+    }
+
+    /// Step out of the entry that has just ended.
+    fn entry_done(&mut self) {
+        if let Some(Frame::Open { entries, at, .. }) = self.frames.last_mut() {
+            *entries += 1;
+            self.pointer.truncate(*at);
+        }
+    }
+
+    /// `schema` and the schemas its `allOf` and `$ref` lead to, in the
+    /// order [`Checker::check`] visits them, cutting cycles of references
+    /// as it does. `following` holds the references on the way here.
+    fn expand(&self, schema: &'s Schema, following: &mut Vec<usize>, out: &mut Vec<&'s Schema>) {
+        out.push(schema);
+        for sub in &schema.all_of {
+            self.expand(sub, following, out);
+        }
+        if let Some(i) = schema.reference {
+            if !following.contains(&i) {
+                following.push(i);
+                self.expand(&self.refs[i], following, out);
+                following.pop();
+            }
+        }
+    }
+
+    fn check_whole(&mut self, schemas: &[&'s Schema], value: &JsonValue) {
+        for schema in schemas {
+            let mut checker = Checker {
+                refs: self.refs,
+                following: Vec::new(),
+            };
+            checker.check(schema, value, &mut self.pointer, &mut self.errors);
+        }
+    }
+}
+
+/// Whether `schema` has a keyword that must see a value whole, besides
+/// those of its subschemas.
+fn needs_whole(schema: &Schema) -> bool {
+    schema.reject
+        || schema.enum_values.is_some()
+        || schema.const_value.is_some()
+        || !schema.any_of.is_empty()
+        || !schema.one_of.is_empty()
+        || schema.not.is_some()
+}
+
+/// Checks an instance against the schemas of one document.
+struct Checker<'s> {
+    refs: &'s [Schema],
+    /// Each `$ref` being followed with the pointer of the value it is
+    /// applied to, to cut cycles of references.
+    following: Vec<(usize, String)>,
+}
+
+impl Checker<'_> {
+    fn check(
+        &mut self,
+        schema: &Schema,
+        value: &JsonValue,
+        pointer: &mut String,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let JsonValue::Raw(raw) = value {
+            if let Ok(parsed) = raw.parse() {
+                self.check(schema, &parsed, pointer, errors);
+            }
+            return;
+        }
+        if schema.reject {
+            let message = "no value is allowed here".to_string();
+            errors.push(failure(schema, pointer, "false", message));
+            return;
+        }
+        check_type(schema, value, pointer, errors);
+        let mut fail = |keyword: &'static str, message: String| {
+            errors.push(failure(schema, pointer, keyword, message))
+        };
+        if let Some(values) = &schema.enum_values {
+            if !values.iter().any(|v| json_eq(v, value)) {
+                fail("enum", "value is not one of the allowed values".to_string());
+            }
+        }
+        if let Some(expected) = &schema.const_value {
+            if !json_eq(expected, value) {
+                fail("const", format!("expected {}", expected.to_json_string()));
+            }
+        }
+        match value {
+            JsonValue::Number(n) => {
+                let n = as_f64(n);
+                if let Some(min) = schema.minimum.filter(|min| n < *min) {
+                    fail("minimum", format!("{} is less than {}", n, min));
+                }
+                if let Some(max) = schema.maximum.filter(|max| n > *max) {
+                    fail("maximum", format!("{} is greater than {}", n, max));
+                }
+            }
+            JsonValue::String(s) => {
+                let len = s.chars().count();
+                if let Some(min) = schema.min_length.filter(|min| len < *min) {
+                    fail("minLength", format!("length {} is less than {}", len, min));
+                }
+                if let Some(max) = schema.max_length.filter(|max| len > *max) {
+                    fail(
+                        "maxLength",
+                        format!("length {} is greater than {}", len, max),
+                    );
+                }
+            }
+            JsonValue::Array(arr) => {
+                check_items(schema, arr.len(), pointer, errors);
+                if let Some(items) = &schema.items {
+                    for (i, item) in arr.iter().enumerate() {
+                        within(pointer, &[&i.to_string()], |p| {
+                            self.check(items, item, p, errors)
+                        });
+                    }
+                }
+            }
+            JsonValue::Object(obj) => self.check_object(schema, obj, pointer, errors),
+            _ => {}
+        }
+        self.check_composition(schema, value, pointer, errors);
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &Schema,
+        obj: &JsonObject,
+        pointer: &mut String,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        check_required(schema, |name| obj.contains_key(name), pointer, errors);
+        for (name, sub) in &schema.properties {
+            if let Some(v) = obj.get(name.as_str()) {
+                within(pointer, &[name], |p| self.check(sub, v, p, errors));
+            }
+        }
+        if let Some(additional) = &schema.additional_properties {
+            let mut extra: Vec<_> = obj
+                .iter()
+                .filter(|(k, _)| !schema.properties.iter().any(|(name, _)| name == *k))
+                .collect();
+            extra.sort_unstable_by_key(|(k, _)| *k);
+            for (name, v) in extra {
+                if additional.reject {
+                    let message = format!("property {:?} is not allowed", name);
+                    errors.push(failure(schema, pointer, "additionalProperties", message));
+                } else {
+                    within(pointer, &[name], |p| self.check(additional, v, p, errors));
+                }
+            }
+        }
+    }
+
+    /// `allOf`, `anyOf`, `oneOf`, `not` and `$ref`.
+    fn check_composition(
+        &mut self,
+        schema: &Schema,
+        value: &JsonValue,
+        pointer: &mut String,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for sub in &schema.all_of {
+            self.check(sub, value, pointer, errors);
+        }
+        if !schema.any_of.is_empty()
+            && !schema
+                .any_of
+                .iter()
+                .any(|sub| self.passes(sub, value, pointer))
+        {
+            let message = "value matches none of the schemas".to_string();
+            errors.push(failure(schema, pointer, "anyOf", message));
+        }
+        if !schema.one_of.is_empty() {
+            let matched = schema
+                .one_of
+                .iter()
+                .filter(|sub| self.passes(sub, value, pointer))
+                .count();
+            if matched != 1 {
+                let message = format!("value matches {} of the schemas, not exactly one", matched);
+                errors.push(failure(schema, pointer, "oneOf", message));
+            }
+        }
+        if let Some(not) = &schema.not {
+            if self.passes(not, value, pointer) {
+                let message = "value matches a schema it must not".to_string();
+                errors.push(failure(schema, pointer, "not", message));
+            }
+        }
+        if let Some(i) = schema.reference {
+            let entry = (i, pointer.clone());
+            if !self.following.contains(&entry) {
+                self.following.push(entry);
+                let refs = self.refs;
+                self.check(&refs[i], value, pointer, errors);
+                self.following.pop();
+            }
+        }
+    }
+
+    /// Whether `value` satisfies `schema`, its violations dropped.
+    fn passes(&mut self, schema: &Schema, value: &JsonValue, pointer: &mut String) -> bool {
+        let mut errors = Vec::new();
+        self.check(schema, value, pointer, &mut errors);
+        errors.is_empty()
+    }
+}
+
+fn check_type(
+    schema: &Schema,
+    value: &JsonValue,
+    pointer: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if let Some(types) = &schema.types {
+        if !types.iter().any(|t| t.matches(value)) {
+            let names: Vec<_> = types.iter().map(|t| t.name()).collect();
+            let message = format!("expected {}, got {}", names.join(" or "), type_name(value));
+            errors.push(failure(schema, pointer, "type", message));
+        }
+    }
+}
+
+/// `minItems` and `maxItems` for an array of `len` elements.
+fn check_items(schema: &Schema, len: usize, pointer: &str, errors: &mut Vec<ValidationError>) {
+    if let Some(min) = schema.min_items.filter(|min| len < *min) {
+        let message = format!("{} items is fewer than {}", len, min);
+        errors.push(failure(schema, pointer, "minItems", message));
+    }
+    if let Some(max) = schema.max_items.filter(|max| len > *max) {
+        let message = format!("{} items is more than {}", len, max);
+        errors.push(failure(schema, pointer, "maxItems", message));
+    }
+}
+
+/// `required` for an object that has the members `has` says it has.
+fn check_required(
+    schema: &Schema,
+    has: impl Fn(&str) -> bool,
+    pointer: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    for name in &schema.required {
+        if !has(name) {
+            let message = format!("missing property {:?}", name);
+            errors.push(failure(schema, pointer, "required", message));
+        }
+    }
+}
+
+/// `keyword` of `schema` failed for the value at `pointer`.
+fn failure(
+    schema: &Schema,
+    pointer: &str,
+    keyword: &'static str,
+    message: String,
+) -> ValidationError {
+    // the `false` schema is a keyword of its own
+    let schema_pointer = match keyword {
+        "false" => schema.location.clone(),
+        _ => keyword_pointer(&schema.location, keyword),
+    };
+    ValidationError {
+        pointer: pointer.to_string(),
+        schema_pointer,
+        keyword,
+        message,
+    }
+}
+
+/// Run `f` with `segments` appended to `pointer`.
+fn within<T>(pointer: &mut String, segments: &[&str], f: impl FnOnce(&mut String) -> T) -> T {
+    let len = pointer.len();
+    for segment in segments {
+        pointer.push('/');
+        pointer.push_str(&escape_pointer_token(segment));
+    }
+    let out = f(pointer);
+    pointer.truncate(len);
+    out
+}
+
+fn invalid(pointer: &str, keyword: &str, message: &str) -> SchemaError {
+    let pointer = if keyword.is_empty() {
+        pointer.to_string()
+    } else {
+        keyword_pointer(pointer, keyword)
+    };
+    SchemaError {
+        pointer,
+        message: message.to_string(),
+    }
+}
+
+fn keyword_pointer(schema: &str, keyword: &str) -> String {
+    format!("{}/{}", schema, escape_pointer_token(keyword))
+}
+
+fn as_f64(n: &Num) -> f64 {
+    match n {
+        Num::Int(i) => *i as f64,
+        Num::Float(f) => *f,
+        Num::Raw(s) => s.parse().unwrap_or(f64::NAN),
+        #[cfg(feature = "decimal")]
+        Num::Decimal(d) => d.to_f64(),
+    }
+}
+
+pub(crate) fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+        JsonValue::Raw(_) => "raw value",
+    }
+}
+
+/// Equality as JSON Schema defines it: numbers compare by value, so `1`
+/// equals `1.0`.
+fn json_eq(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => match (a, b) {
+            (Num::Int(a), Num::Int(b)) => a == b,
+            _ => as_f64(a) == as_f64(b),
+        },
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_eq(a, b))
+        }
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, a)| b.get(k).is_some_and(|b| json_eq(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    fn schema(text: &str) -> Schema {
+        Schema::from_value(&parse_json(text).unwrap()).unwrap()
+    }
+
+    /// The (pointer, keyword) pairs of every violation.
+    fn failures(schema: &Schema, instance: &str) -> Vec<(String, &'static str)> {
+        match schema.validate(&parse_json(instance).unwrap()) {
+            Ok(()) => vec![],
+            Err(errors) => errors.into_iter().map(|e| (e.pointer, e.keyword)).collect(),
+        }
+    }
+
+    fn at(pointer: &str, keyword: &'static str) -> (String, &'static str) {
+        (pointer.to_string(), keyword)
+    }
+
+    #[test]
+    fn schema_should_check_type() {
+        let s = schema(r#"{"type": "integer"}"#);
+        assert_eq!(failures(&s, "3"), []);
+        assert_eq!(failures(&s, "3.0"), []);
+        assert_eq!(failures(&s, "3.5"), [at("", "type")]);
+        let s = schema(r#"{"type": ["string", "null"]}"#);
+        assert_eq!(failures(&s, "null"), []);
+        let errors = s.validate(&JsonValue::from(true)).unwrap_err();
+        assert_eq!(errors[0].message, "expected string or null, got boolean");
+    }
+
+    #[test]
+    fn schema_should_check_enum_and_const() {
+        let s = schema(r#"{"enum": ["red", 1, [true]]}"#);
+        assert_eq!(failures(&s, "1.0"), []);
+        assert_eq!(failures(&s, "[true]"), []);
+        assert_eq!(failures(&s, r#""blue""#), [at("", "enum")]);
+        let s = schema(r#"{"const": {"a": 1}}"#);
+        assert_eq!(failures(&s, r#"{"a": 1}"#), []);
+        assert_eq!(failures(&s, r#"{"a": 2}"#), [at("", "const")]);
+    }
+
+    #[test]
+    fn schema_should_check_numeric_and_length_bounds() {
+        let s = schema(r#"{"minimum": 1, "maximum": 10.5, "minLength": 2, "maxLength": 3}"#);
+        assert_eq!(failures(&s, "10.5"), []);
+        assert_eq!(failures(&s, "0"), [at("", "minimum")]);
+        assert_eq!(failures(&s, "11"), [at("", "maximum")]);
+        assert_eq!(failures(&s, r#""éé""#), []);
+        assert_eq!(failures(&s, r#""a""#), [at("", "minLength")]);
+        assert_eq!(failures(&s, r#""abcd""#), [at("", "maxLength")]);
+    }
+
+    #[test]
+    fn schema_should_check_arrays() {
+        let s = schema(r#"{"items": {"type": "number"}, "minItems": 1, "maxItems": 2}"#);
+        assert_eq!(failures(&s, "[1, 2]"), []);
+        assert_eq!(failures(&s, "[]"), [at("", "minItems")]);
+        assert_eq!(
+            failures(&s, r#"[1, "x", null]"#),
+            [at("", "maxItems"), at("/1", "type"), at("/2", "type")]
+        );
+    }
+
+    #[test]
+    fn schema_should_check_objects() {
+        let s = schema(
+            r#"{"properties": {"a/b": {"type": "string"}}, "required": ["a/b", "c"],
+                "additionalProperties": false}"#,
+        );
+        assert_eq!(
+            failures(&s, r#"{"a/b": 1, "d": 0}"#),
+            [
+                at("", "required"),
+                at("/a~1b", "type"),
+                at("", "additionalProperties")
+            ]
+        );
+        let s = schema(r#"{"additionalProperties": {"type": "boolean"}}"#);
+        assert_eq!(failures(&s, r#"{"x": true, "y": 1}"#), [at("/y", "type")]);
+        assert_eq!(failures(&schema("false"), "1"), [at("", "false")]);
+        assert_eq!(failures(&schema("true"), "1"), []);
+    }
+
+    #[test]
+    fn schema_should_validate_realistic_payloads() {
+        let s = schema(
+            r#"{
+                "type": "object",
+                "required": ["id", "name", "servers"],
+                "properties": {
+                    "id": {"type": "integer", "minimum": 1},
+                    "name": {"type": "string", "minLength": 1},
+                    "tags": {"type": "array", "items": {"enum": ["prod", "dev"]}},
+                    "servers": {
+                        "type": "array",
+                        "minItems": 1,
+                        "items": {
+                            "type": "object",
+                            "required": ["host", "port"],
+                            "properties": {
+                                "host": {"type": "string"},
+                                "port": {"type": "integer", "minimum": 1, "maximum": 65535}
+                            },
+                            "additionalProperties": false
+                        }
+                    }
+                }
+            }"#,
+        );
+        let good = r#"{"id": 7, "name": "api", "tags": ["prod"],
+                       "servers": [{"host": "a", "port": 80}, {"host": "b", "port": 8080}]}"#;
+        assert_eq!(failures(&s, good), []);
+        let bad = r#"{"id": 0, "name": "", "tags": ["qa"],
+                      "servers": [{"host": "a", "port": 80}, {"host": "b", "port": "80", "tls": true}, {"port": 70000}]}"#;
+        let mut found = failures(&s, bad);
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                at("/id", "minimum"),
+                at("/name", "minLength"),
+                at("/servers/1", "additionalProperties"),
+                at("/servers/1/port", "type"),
+                at("/servers/2", "required"),
+                at("/servers/2/port", "maximum"),
+                at("/tags/0", "enum"),
+            ]
+        );
+    }
+
+    const TREE: &str = r##"{
+        "$ref": "#/definitions/node",
+        "definitions": {
+            "node": {
+                "type": "object",
+                "required": ["value"],
+                "properties": {
+                    "value": {"type": "number"},
+                    "children": {"type": "array", "items": {"$ref": "#/definitions/node"}}
+                }
+            }
+        }
+    }"##;
+
+    #[test]
+    fn schema_should_follow_recursive_refs() {
+        let s = schema(TREE);
+        let good =
+            r#"{"value": 1, "children": [{"value": 2}, {"value": 3, "children": [{"value": 4}]}]}"#;
+        assert_eq!(failures(&s, good), []);
+        let bad = r#"{"value": 1, "children": [{"value": "2"}, {"children": [{"value": null}]}]}"#;
+        let errors = s.validate(&parse_json(bad).unwrap()).unwrap_err();
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.pointer.as_str(), e.schema_pointer.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "/children/0/value",
+                    "/definitions/node/properties/value/type"
+                ),
+                ("/children/1", "/definitions/node/required"),
+                (
+                    "/children/1/children/0/value",
+                    "/definitions/node/properties/value/type"
+                ),
+            ]
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            r#""/children/1" fails required at "/definitions/node/required": missing property "value""#
+        );
+        let s =
+            schema(r##"{"$defs": {"id": {"type": "integer"}}, "items": {"$ref": "#/$defs/id"}}"##);
+        assert_eq!(failures(&s, "[1, 2.5]"), [at("/1", "type")]);
+    }
+
+    #[test]
+    fn schema_should_check_composition() {
+        let s = schema(r#"{"allOf": [{"type": "integer"}, {"minimum": 2}]}"#);
+        assert_eq!(failures(&s, "3"), []);
+        assert_eq!(failures(&s, "1.5"), [at("", "type"), at("", "minimum")]);
+        let errors = s.validate(&JsonValue::from(1i64)).unwrap_err();
+        assert_eq!(errors[0].schema_pointer, "/allOf/1/minimum");
+
+        let s = schema(r#"{"anyOf": [{"type": "string"}, {"minimum": 10}]}"#);
+        assert_eq!(failures(&s, r#""x""#), []);
+        assert_eq!(failures(&s, "12"), []);
+        assert_eq!(failures(&s, "3"), [at("", "anyOf")]);
+
+        let s = schema(r#"{"oneOf": [{"type": "integer"}, {"minimum": 10}]}"#);
+        assert_eq!(failures(&s, "3"), []);
+        assert_eq!(failures(&s, "10.5"), []);
+        assert_eq!(failures(&s, "12"), [at("", "oneOf")]);
+        assert_eq!(failures(&s, "1.5"), [at("", "oneOf")]);
+
+        let s = schema(r#"{"properties": {"a": {"not": {"type": "null"}}}}"#);
+        assert_eq!(failures(&s, r#"{"a": 0}"#), []);
+        let errors = s
+            .validate(&parse_json(r#"{"a": null}"#).unwrap())
+            .unwrap_err();
+        assert_eq!(
+            (
+                errors[0].pointer.as_str(),
+                errors[0].schema_pointer.as_str()
+            ),
+            ("/a", "/properties/a/not")
+        );
+        assert!(Schema::from_value(&parse_json(r#"{"anyOf": []}"#).unwrap()).is_err());
+    }
+
+    #[test]
+    fn schema_should_reject_unresolved_refs() {
+        let err = Schema::from_value(
+            &parse_json(r##"{"properties": {"a": {"$ref": "#/definitions/missing"}}}"##).unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(err.pointer, "/properties/a/$ref");
+        assert_eq!(
+            err.message,
+            r##"unresolved reference "#/definitions/missing""##
+        );
+        let err =
+            Schema::from_value(&parse_json(r#"{"$ref": "other.json#/a"}"#).unwrap()).unwrap_err();
+        assert_eq!(err.pointer, "/$ref");
+    }
+
+    #[test]
+    fn schema_should_cut_reference_cycles() {
+        let s = schema(
+            r##"{
+                "definitions": {
+                    "a": {"$ref": "#/definitions/b"},
+                    "b": {"allOf": [{"$ref": "#/definitions/a"}, {"type": "string"}]}
+                },
+                "$ref": "#/definitions/a"
+            }"##,
+        );
+        assert_eq!(failures(&s, r#""x""#), []);
+        assert_eq!(failures(&s, "1"), [at("", "type")]);
+        let s = schema(r##"{"$ref": "#"}"##);
+        assert_eq!(failures(&s, "[1]"), []);
+    }
+
+    #[test]
+    fn validate_stream_should_match_tree_validation() {
+        let schemas = [
+            TREE,
+            r#"{"type": "object", "required": ["a", "b"], "additionalProperties": false,
+                "properties": {"a": {"type": "integer", "maximum": 3}, "b": {"items": {"type": "string", "maxLength": 2}, "maxItems": 2}}}"#,
+            r#"{"items": {"anyOf": [{"type": "string"}, {"type": "object", "required": ["k"]}]}, "minItems": 2}"#,
+            r#"{"allOf": [{"type": "array"}, {"items": {"oneOf": [{"minimum": 0}, {"type": "array", "items": false}]}}]}"#,
+            r#"{"additionalProperties": {"not": {"enum": [null, {"x": [1]}]}}, "properties": {"c": {"const": [1, {"d": true}]}}}"#,
+            r##"{"$defs": {"list": {"type": ["array", "null"], "items": {"$ref": "#/$defs/list"}, "maxItems": 1}}, "$ref": "#/$defs/list"}"##,
+            "true",
+            "false",
+        ];
+        let instances = [
+            "null",
+            "0",
+            "-4.5",
+            r#""abc""#,
+            "[]",
+            "{}",
+            r#"{"value": 1, "children": [{"value": 2}, {"value": "x", "children": [{}]}]}"#,
+            r#"{"a": 4, "b": ["x", "yyy", 3], "z": null}"#,
+            r#"{"a": 1.5, "b": []}"#,
+            r#"["s", {"k": 1}, {"j": 2}, 7]"#,
+            r#"[1, -1, [], [2], [[null]]]"#,
+            r#"{"c": [1, {"d": true}], "e": {"x": [1.0]}, "f": null, "g": 2}"#,
+            r#"{"c": [1, {"d": false}]}"#,
+            "[[[null]]]",
+            "[[null, null]]",
+        ];
+        let key = |e: &ValidationError| {
+            (
+                e.pointer.clone(),
+                e.schema_pointer.clone(),
+                e.message.clone(),
+            )
+        };
+        for schema_text in schemas {
+            let s = schema(schema_text);
+            for instance in instances {
+                let mut tree = s
+                    .validate(&parse_json(instance).unwrap())
+                    .err()
+                    .unwrap_or_default();
+                let mut stream = s
+                    .validate_stream(instance)
+                    .unwrap()
+                    .err()
+                    .unwrap_or_default();
+                tree.sort_by_key(key);
+                stream.sort_by_key(key);
+                assert_eq!(stream, tree, "{} against {}", instance, schema_text);
+            }
+        }
+    }
+
+    #[test]
+    fn validate_stream_should_report_pointers_and_syntax_errors() {
+        let s = schema(TREE);
+        let errors = s
+            .validate_stream(r#"{"value": 1, "children": [{"value": "2"}, {}]}"#)
+            .unwrap()
+            .unwrap_err();
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.pointer.as_str(), e.keyword))
+            .collect();
+        assert_eq!(
+            found,
+            [("/children/0/value", "type"), ("/children/1", "required")]
+        );
+        assert_eq!(s.validate_stream(r#"{"value": 0}"#), Ok(Ok(())));
+        assert_eq!(
+            s.validate_stream(r#"{"value": "x",}"#),
+            Err(parse_json(r#"{"value": "x",}"#).unwrap_err())
+        );
+    }
+
+    #[test]
+    fn schema_should_reject_malformed_schemas() {
+        let err = Schema::from_value(
+            &parse_json(r#"{"properties": {"a": {"minimum": "ten"}}}"#).unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(err.pointer, "/properties/a/minimum");
+        assert!(Schema::from_value(&parse_json(r#"{"type": "float"}"#).unwrap()).is_err());
+        assert!(Schema::from_value(&JsonValue::from(1)).is_err());
+    }
+}
@@ -0,0 +1,1607 @@
+use super::{
+    CharEscape, ColorScheme, ColoredFormatter, CompactFormatter, Formatter, Json5Formatter,
+    JsonError, JsonObject, JsonString, JsonValue, Num, PrettyFormatter,
+};
+use std::{cmp::Ordering, fmt, io, sync::Arc};
+
+/// Indentation used for each nesting level by the pretty printer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Indent {
+    /// A number of spaces per level.
+    Spaces(usize),
+    /// An arbitrary string per level, e.g. `"\t"`.
+    Str(String),
+}
+
+impl Indent {
+    pub fn tab() -> Self {
+        Indent::Str("\t".to_string())
+    }
+
+    /// The text of one level.
+    pub fn unit(&self) -> String {
+        match self {
+            Indent::Spaces(n) => " ".repeat(*n),
+            Indent::Str(s) => s.clone(),
+        }
+    }
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Indent::Spaces(2)
+    }
+}
+
+/// Line terminator used by the pretty printer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Newline {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl Newline {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Options controlling how a [`JsonValue`] is written out. Build one with
+/// [`FormatOptions::compact`] or [`FormatOptions::pretty`] and chain setters:
+///
+/// ```
+/// use grammar::json::{FormatOptions, Newline};
+///
+/// let options = FormatOptions::pretty().tabs().newline(Newline::CrLf);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Pretty print with this indent, or emit compact output when `None`.
+    pub indent: Option<Indent>,
+    /// Compact output only: put a space after each `:`.
+    pub space_after_colon: bool,
+    /// Compact output only: put a space after each `,`.
+    pub space_after_comma: bool,
+    pub newline: Newline,
+    /// End the document with a newline.
+    pub trailing_newline: bool,
+    pub escape: EscapeOptions,
+    /// Emit object members sorted by key (Unicode code point order) at every
+    /// level. Without it members come out in the map's iteration order, which
+    /// for the HashMap backing can differ between runs.
+    pub sort_keys: bool,
+    /// Emit object members in this order at every level, in place of
+    /// `sort_keys` or the map's order; it wins over both when set.
+    pub key_order: Option<KeyOrder>,
+    /// When pretty printing, keep a container on one line (`[1, 2]`) if it
+    /// fits within this many columns including its indentation and key, and
+    /// only break larger ones. `Some(0)` always breaks, `Some(usize::MAX)`
+    /// always inlines. Indent strings count one column per byte.
+    pub max_width: Option<usize>,
+    /// What to write for NaN and infinite floats.
+    pub non_finite: NonFiniteBehavior,
+    /// When pretty printing, lay out for documents kept under version
+    /// control: every entry on its own line whatever `max_width` says,
+    /// commas leading the entries after the first, and empty containers
+    /// broken across lines as well. Adding, removing or changing one scalar
+    /// entry then changes one line, except for the first entry of a
+    /// container, which has no comma. Members keep the map's order, or
+    /// `sort_keys` or `key_order` if set, so with sorted keys an entry
+    /// lands where it sorts and its diff stays one line. The output is
+    /// still plain JSON.
+    pub diff_friendly: bool,
+}
+
+/// An order for object keys, for [`FormatOptions::key_order`]: a
+/// comparator, shared so options stay cheap to clone. Two orders are equal
+/// only if they are the same one.
+///
+/// ```
+/// use grammar::json::{parse_json, FormatOptions, KeyOrder};
+///
+/// let value = parse_json(r#"{"b": 1, "type": 2, "id": 3, "a": 4}"#).unwrap();
+/// let options = FormatOptions::compact().key_order(KeyOrder::priority(["id", "name", "type"]));
+/// assert_eq!(value.to_string_with(&options), r#"{"id":3,"type":2,"a":4,"b":1}"#);
+/// ```
+#[derive(Clone)]
+pub struct KeyOrder(Arc<KeyCompare>);
+
+type KeyCompare = dyn Fn(&str, &str) -> Ordering + Send + Sync;
+
+impl KeyOrder {
+    pub fn new(compare: impl Fn(&str, &str) -> Ordering + Send + Sync + 'static) -> Self {
+        KeyOrder(Arc::new(compare))
+    }
+
+    /// The keys in `first` ahead of all others, in the order given, and the
+    /// rest after them in code point order, as `sort_keys` puts them.
+    pub fn priority<I: IntoIterator<Item = S>, S: Into<String>>(first: I) -> Self {
+        let first: Vec<String> = first.into_iter().map(Into::into).collect();
+        let rank = move |key: &str| first.iter().position(|k| k == key).unwrap_or(usize::MAX);
+        KeyOrder::new(move |a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)))
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        (self.0)(a, b)
+    }
+}
+
+impl fmt::Debug for KeyOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyOrder(..)")
+    }
+}
+
+impl PartialEq for KeyOrder {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for KeyOrder {}
+
+/// What to do with NaN and infinite floats, which JSON cannot represent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFiniteBehavior {
+    /// Fail with [`JsonError::Serialize`] carrying the pointer of the
+    /// offending value. Only the serializers that return a `Result`, such
+    /// as [`JsonValue::try_to_string_with`] and the writers, can fail; the
+    /// others, such as [`JsonValue::to_json_string`], write `null` instead,
+    /// so use one of the former when a non-finite float must not go
+    /// unnoticed.
+    #[default]
+    Error,
+    /// Write `null` in their place.
+    Null,
+    /// Write `NaN`, `Infinity` and `-Infinity`, as JSON5 and JavaScript
+    /// accept them.
+    Literal,
+}
+
+/// Options controlling which characters get escaped in string literals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeOptions {
+    /// Escape every character above U+007F as `\uXXXX`, using surrogate pairs
+    /// outside the BMP, so the output is pure ASCII.
+    pub ascii_only: bool,
+    /// Escape `<`, `>` and `&` so the output can be embedded in an HTML
+    /// `<script>` block.
+    pub escape_html: bool,
+    /// Escape U+2028 and U+2029, which are legal in JSON strings but are line
+    /// terminators in JavaScript source. On by default.
+    pub escape_line_separators: bool,
+    /// Escape `/` as `\/`, so `</` cannot appear in the output.
+    pub escape_solidus: bool,
+}
+
+impl Default for EscapeOptions {
+    fn default() -> Self {
+        Self {
+            ascii_only: false,
+            escape_html: false,
+            escape_line_separators: true,
+            escape_solidus: false,
+        }
+    }
+}
+
+impl FormatOptions {
+    pub fn compact() -> Self {
+        Self::default()
+    }
+
+    pub fn pretty() -> Self {
+        Self {
+            indent: Some(Indent::default()),
+            ..Default::default()
+        }
+    }
+
+    /// Pretty print with `indent`.
+    pub fn indent(mut self, indent: Indent) -> Self {
+        self.indent = Some(indent);
+        self
+    }
+
+    /// Pretty print with `n` spaces per level.
+    pub fn spaces(self, n: usize) -> Self {
+        self.indent(Indent::Spaces(n))
+    }
+
+    /// Pretty print with one tab per level.
+    pub fn tabs(self) -> Self {
+        self.indent(Indent::tab())
+    }
+
+    pub fn space_after_colon(mut self, yes: bool) -> Self {
+        self.space_after_colon = yes;
+        self
+    }
+
+    pub fn space_after_comma(mut self, yes: bool) -> Self {
+        self.space_after_comma = yes;
+        self
+    }
+
+    pub fn newline(mut self, newline: Newline) -> Self {
+        self.newline = newline;
+        self
+    }
+
+    pub fn trailing_newline(mut self, yes: bool) -> Self {
+        self.trailing_newline = yes;
+        self
+    }
+
+    pub fn escape(mut self, escape: EscapeOptions) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    pub fn sort_keys(mut self, yes: bool) -> Self {
+        self.sort_keys = yes;
+        self
+    }
+
+    pub fn key_order(mut self, order: KeyOrder) -> Self {
+        self.key_order = Some(order);
+        self
+    }
+
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    pub fn non_finite(mut self, behavior: NonFiniteBehavior) -> Self {
+        self.non_finite = behavior;
+        self
+    }
+
+    /// Turn on [`diff_friendly`](Self::diff_friendly) layout, also pretty
+    /// printing with the default indent if no indent is set.
+    pub fn diff_friendly(mut self, yes: bool) -> Self {
+        self.diff_friendly = yes;
+        if yes && self.indent.is_none() {
+            self.indent = Some(Indent::default());
+        }
+        self
+    }
+}
+
+impl JsonValue {
+    /// Serialize to compact JSON without any insignificant whitespace.
+    ///
+    /// NaN and infinite floats are written as `null`, since
+    /// [`NonFiniteBehavior::Error`] is only honoured by
+    /// [`JsonValue::try_to_string_with`]; use that to get the error instead.
+    pub fn to_json_string(&self) -> String {
+        self.to_string_with(&FormatOptions::default())
+    }
+
+    /// Serialize to pretty JSON indented with two spaces.
+    pub fn to_json_string_pretty(&self) -> String {
+        self.to_string_with(&FormatOptions::pretty())
+    }
+
+    /// Serialize to pretty JSON with the given indentation. The output has no
+    /// trailing newline.
+    pub fn to_json_string_indent(&self, indent: &Indent) -> String {
+        self.to_string_with(&FormatOptions {
+            indent: Some(indent.clone()),
+            ..Default::default()
+        })
+    }
+
+    /// Like [`JsonValue::try_to_string_with`] but never fails: under
+    /// [`NonFiniteBehavior::Error`], which only the `try_` serializers
+    /// honour, a non-finite float is written as `null`.
+    pub fn to_string_with(&self, options: &FormatOptions) -> String {
+        String::from_utf8(self.to_vec_with(options)).expect("serializer only emits UTF-8")
+    }
+
+    pub fn try_to_string_with(&self, options: &FormatOptions) -> Result<String, JsonError> {
+        let out = self.try_to_vec_with(options)?;
+        Ok(String::from_utf8(out).expect("serializer only emits UTF-8"))
+    }
+
+    /// Serialize to compact JSON as UTF-8 bytes.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.to_vec_with(&FormatOptions::default())
+    }
+
+    /// Serialize to pretty JSON indented with two spaces as UTF-8 bytes.
+    pub fn to_vec_pretty(&self) -> Vec<u8> {
+        self.to_vec_with(&FormatOptions::pretty())
+    }
+
+    /// Like [`JsonValue::to_string_with`] but returns the bytes.
+    pub fn to_vec_with(&self, options: &FormatOptions) -> Vec<u8> {
+        let nulls;
+        let options = match options.non_finite {
+            NonFiniteBehavior::Error => {
+                nulls = options.clone().non_finite(NonFiniteBehavior::Null);
+                &nulls
+            }
+            _ => options,
+        };
+        self.try_to_vec_with(options)
+            .expect("only non-finite floats fail to serialize")
+    }
+
+    pub fn try_to_vec_with(&self, options: &FormatOptions) -> Result<Vec<u8>, JsonError> {
+        let mut out = Vec::new();
+        if let Err(e) = self.to_writer_with(&mut out, options) {
+            let e = e
+                .into_inner()
+                .and_then(|e| e.downcast::<JsonError>().ok())
+                .expect("writing to a Vec only fails on serialization errors");
+            return Err(*e);
+        }
+        Ok(out)
+    }
+
+    /// Stream compact JSON into `w`. Flushing is left to the caller.
+    pub fn to_writer(&self, w: &mut impl io::Write) -> io::Result<()> {
+        self.to_writer_with(w, &FormatOptions::default())
+    }
+
+    /// Stream pretty JSON indented with two spaces into `w`.
+    pub fn to_writer_pretty(&self, w: &mut impl io::Write) -> io::Result<()> {
+        self.to_writer_with(w, &FormatOptions::pretty())
+    }
+
+    /// Stream pretty JSON with the given indentation into `w`.
+    pub fn to_writer_indent(&self, w: &mut impl io::Write, indent: &Indent) -> io::Result<()> {
+        self.to_writer_with(
+            w,
+            &FormatOptions {
+                indent: Some(indent.clone()),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Serialization errors come back as [`io::ErrorKind::InvalidData`]
+    /// wrapping a [`JsonError`].
+    pub fn to_writer_with(
+        &self,
+        w: &mut impl io::Write,
+        options: &FormatOptions,
+    ) -> io::Result<()> {
+        let newline = options.newline.as_str().as_bytes();
+        match &options.indent {
+            Some(indent) if options.max_width.is_some() && !options.diff_friendly => {
+                let unit = indent.unit();
+                let mut ser = WidthSerializer {
+                    w,
+                    unit: unit.as_bytes(),
+                    newline,
+                    width: options.max_width.unwrap_or(usize::MAX),
+                    escape: &options.escape,
+                    sort_keys: options.sort_keys,
+                    key_order: options.key_order.as_ref(),
+                    non_finite: options.non_finite,
+                };
+                ser.write_value(self, 0, 0, 0)?;
+            }
+            Some(indent) => {
+                let unit = indent.unit();
+                let mut f = PrettyFormatter::with_indent(unit.as_bytes())
+                    .with_newline(newline)
+                    .with_diff_layout(options.diff_friendly);
+                self.to_writer_with_formatter(w, &mut f, options)?;
+            }
+            None => {
+                let mut f = SpacedFormatter {
+                    colon: options.space_after_colon,
+                    comma: options.space_after_comma,
+                };
+                self.to_writer_with_formatter(w, &mut f, options)?;
+            }
+        }
+        if options.trailing_newline {
+            w.write_all(newline)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize to JSON5 indented with two spaces, see [`Json5Formatter`].
+    /// NaN and infinities are written as `NaN` and `Infinity`.
+    pub fn to_json5_string(&self) -> String {
+        let mut out = Vec::new();
+        let options = FormatOptions::default().non_finite(NonFiniteBehavior::Literal);
+        self.to_writer_with_formatter(&mut out, &mut Json5Formatter::default(), &options)
+            .expect("writing to a Vec never fails");
+        String::from_utf8(out).expect("serializer only emits UTF-8")
+    }
+
+    /// Serialize to pretty JSON with ANSI colors for a terminal. Keys come
+    /// out sorted so the output is stable between runs.
+    pub fn to_string_pretty_colored(&self, scheme: &ColorScheme) -> String {
+        let mut out = Vec::new();
+        let options = FormatOptions::pretty().sort_keys(true);
+        let mut f = ColoredFormatter::new(b"  ", scheme);
+        self.to_writer_with_formatter(&mut out, &mut f, &options)
+            .expect("writing to a Vec never fails");
+        String::from_utf8(out).expect("serializer only emits UTF-8")
+    }
+
+    /// Drive a custom [`Formatter`]. `options.indent` is ignored since layout
+    /// is entirely up to the formatter; escaping and key order still apply.
+    pub fn to_writer_with_formatter<F: Formatter>(
+        &self,
+        w: &mut impl io::Write,
+        formatter: &mut F,
+        options: &FormatOptions,
+    ) -> io::Result<()> {
+        serializer(w, formatter, options).write_value(self)
+    }
+}
+
+/// Write `value` laid out as [`JsonValue::to_writer_with`] would if it sat
+/// `level` containers deep, without the trailing newline. `max_width` is
+/// ignored.
+pub(super) fn write_nested<W: io::Write + ?Sized>(
+    value: &JsonValue,
+    w: &mut W,
+    options: &FormatOptions,
+    level: usize,
+) -> io::Result<()> {
+    match &options.indent {
+        Some(indent) => {
+            let unit = indent.unit();
+            let newline = options.newline.as_str().as_bytes();
+            let mut f = PrettyFormatter::with_indent(unit.as_bytes())
+                .with_newline(newline)
+                .with_diff_layout(options.diff_friendly)
+                .at_level(level);
+            serializer(w, &mut f, options).write_value(value)
+        }
+        None => {
+            let mut f = SpacedFormatter {
+                colon: options.space_after_colon,
+                comma: options.space_after_comma,
+            };
+            serializer(w, &mut f, options).write_value(value)
+        }
+    }
+}
+
+fn serializer<'a, W: ?Sized, F>(
+    w: &'a mut W,
+    formatter: &'a mut F,
+    options: &'a FormatOptions,
+) -> Serializer<'a, W, F> {
+    Serializer {
+        w,
+        formatter,
+        escape: &options.escape,
+        sort_keys: options.sort_keys,
+        key_order: options.key_order.as_ref(),
+        non_finite: options.non_finite,
+    }
+}
+
+struct Serializer<'a, W: ?Sized, F> {
+    w: &'a mut W,
+    formatter: &'a mut F,
+    escape: &'a EscapeOptions,
+    sort_keys: bool,
+    key_order: Option<&'a KeyOrder>,
+    non_finite: NonFiniteBehavior,
+}
+
+impl<W: io::Write + ?Sized, F: Formatter> Serializer<'_, W, F> {
+    fn write_value(&mut self, value: &JsonValue) -> io::Result<()> {
+        match value {
+            JsonValue::Null => self.formatter.write_null(self.w),
+            JsonValue::Bool(b) => self.formatter.write_bool(self.w, *b),
+            JsonValue::Number(Num::Int(i)) => self.formatter.write_i64(self.w, *i),
+            JsonValue::Number(Num::Float(f)) => {
+                write_f64(self.w, self.formatter, *f, self.non_finite)
+            }
+            JsonValue::Number(Num::Raw(s)) => self.formatter.write_raw_number(self.w, s),
+            #[cfg(feature = "decimal")]
+            JsonValue::Number(Num::Decimal(d)) => {
+                self.formatter.write_raw_number(self.w, &d.to_string())
+            }
+            JsonValue::String(s) => write_string(self.w, self.formatter, s, self.escape),
+            JsonValue::Raw(raw) => self.formatter.write_raw_value(self.w, raw.get()),
+            JsonValue::Array(arr) => {
+                self.formatter.begin_array(self.w)?;
+                for (i, v) in arr.iter().enumerate() {
+                    self.formatter.begin_array_value(self.w, i == 0)?;
+                    self.write_value(v).map_err(|e| within(e, &i.to_string()))?;
+                    self.formatter.end_array_value(self.w)?;
+                }
+                self.formatter.end_array(self.w)
+            }
+            JsonValue::Object(obj) => {
+                self.formatter.begin_object(self.w)?;
+                for (i, (k, v)) in members(obj, self.sort_keys, self.key_order)
+                    .into_iter()
+                    .enumerate()
+                {
+                    self.formatter.begin_object_key(self.w, i == 0)?;
+                    write_string(self.w, self.formatter, k, self.escape)?;
+                    self.formatter.end_object_key(self.w)?;
+                    self.formatter.begin_object_value(self.w)?;
+                    self.write_value(v).map_err(|e| within(e, k))?;
+                    self.formatter.end_object_value(self.w)?;
+                }
+                self.formatter.end_object(self.w)
+            }
+        }
+    }
+}
+
+pub(super) fn write_f64<W: io::Write + ?Sized, F: Formatter>(
+    w: &mut W,
+    formatter: &mut F,
+    f: f64,
+    non_finite: NonFiniteBehavior,
+) -> io::Result<()> {
+    if f.is_finite() {
+        return formatter.write_f64(w, f);
+    }
+    match non_finite {
+        NonFiniteBehavior::Error => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            JsonError::serialize(format!("{} is not a valid JSON number", f)),
+        )),
+        NonFiniteBehavior::Null => formatter.write_null(w),
+        NonFiniteBehavior::Literal => formatter.write_non_finite(w, f),
+    }
+}
+
+/// Extend the pointer of a serialization error on its way out of a
+/// container. Plain I/O errors pass through untouched.
+fn within(e: io::Error, segment: &str) -> io::Error {
+    if !e.get_ref().is_some_and(|inner| inner.is::<JsonError>()) {
+        return e;
+    }
+    let inner = e
+        .into_inner()
+        .and_then(|e| e.downcast::<JsonError>().ok())
+        .expect("checked above");
+    io::Error::new(io::ErrorKind::InvalidData, inner.within(segment))
+}
+
+fn members<'v>(
+    obj: &'v JsonObject,
+    sort_keys: bool,
+    key_order: Option<&KeyOrder>,
+) -> Vec<(&'v JsonString, &'v JsonValue)> {
+    let mut members: Vec<_> = obj.iter().collect();
+    match key_order {
+        Some(order) => members.sort_by(|(a, _), (b, _)| order.compare(a, b)),
+        None if sort_keys => members.sort_unstable_by_key(|(k, _)| *k),
+        None => {}
+    }
+    members
+}
+
+/// Compact output with optional spaces after `,` and `:`. The width-aware
+/// printer uses it with both on for containers it keeps on one line.
+pub(super) struct SpacedFormatter {
+    pub(super) colon: bool,
+    pub(super) comma: bool,
+}
+
+impl SpacedFormatter {
+    fn inline() -> Self {
+        Self {
+            colon: true,
+            comma: true,
+        }
+    }
+
+    fn separator<W: io::Write + ?Sized>(&self, w: &mut W, first: bool) -> io::Result<()> {
+        match (first, self.comma) {
+            (true, _) => Ok(()),
+            (false, true) => w.write_all(b", "),
+            (false, false) => w.write_all(b","),
+        }
+    }
+}
+
+impl Formatter for SpacedFormatter {
+    fn begin_array_value<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.separator(w, first)
+    }
+
+    fn begin_object_key<W: io::Write + ?Sized>(
+        &mut self,
+        w: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.separator(w, first)
+    }
+
+    fn begin_object_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(if self.colon { b": " } else { b":" })
+    }
+}
+
+/// Counts bytes and fails as soon as more than `budget` are written, so
+/// measuring a huge container stops after `budget` bytes.
+struct Budget {
+    budget: usize,
+}
+
+impl io::Write for Budget {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.budget = self
+            .budget
+            .checked_sub(buf.len())
+            .ok_or_else(|| io::Error::other("over budget"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Pretty printer that inlines containers fitting in `width` columns. Only
+/// containers that end up broken across lines get measured, and measuring
+/// is capped by the remaining budget, so the cost stays linear in practice.
+struct WidthSerializer<'a, W: ?Sized> {
+    w: &'a mut W,
+    unit: &'a [u8],
+    newline: &'a [u8],
+    width: usize,
+    escape: &'a EscapeOptions,
+    sort_keys: bool,
+    key_order: Option<&'a KeyOrder>,
+    non_finite: NonFiniteBehavior,
+}
+
+impl<W: io::Write + ?Sized> WidthSerializer<'_, W> {
+    /// `prefix` is the width already taken on this line by the member key and
+    /// `suffix` the width of what follows the value (a trailing comma).
+    fn write_value(
+        &mut self,
+        value: &JsonValue,
+        level: usize,
+        prefix: usize,
+        suffix: usize,
+    ) -> io::Result<()> {
+        let is_empty = match value {
+            JsonValue::Array(arr) => arr.is_empty(),
+            JsonValue::Object(obj) => obj.is_empty(),
+            _ => true,
+        };
+        let used = (level * self.unit.len())
+            .saturating_add(prefix)
+            .saturating_add(suffix);
+        if is_empty || self.fits(value, self.width.saturating_sub(used)) {
+            return self.write_inline(value);
+        }
+
+        match value {
+            JsonValue::Array(arr) => {
+                self.w.write_all(b"[")?;
+                for (i, v) in arr.iter().enumerate() {
+                    self.begin_entry(i, level + 1)?;
+                    let suffix = usize::from(i + 1 < arr.len());
+                    self.write_value(v, level + 1, 0, suffix)
+                        .map_err(|e| within(e, &i.to_string()))?;
+                }
+                self.w.write_all(self.newline)?;
+                self.write_indent(level)?;
+                self.w.write_all(b"]")
+            }
+            JsonValue::Object(obj) => {
+                self.w.write_all(b"{")?;
+                let members = members(obj, self.sort_keys, self.key_order);
+                let len = members.len();
+                for (i, (k, v)) in members.into_iter().enumerate() {
+                    self.begin_entry(i, level + 1)?;
+                    let mut key = Vec::new();
+                    write_string(&mut key, &mut CompactFormatter, k, self.escape)?;
+                    key.extend_from_slice(b": ");
+                    self.w.write_all(&key)?;
+                    let suffix = usize::from(i + 1 < len);
+                    self.write_value(v, level + 1, key.len(), suffix)
+                        .map_err(|e| within(e, k))?;
+                }
+                self.w.write_all(self.newline)?;
+                self.write_indent(level)?;
+                self.w.write_all(b"}")
+            }
+            _ => unreachable!("scalars are always written inline"),
+        }
+    }
+
+    fn fits(&self, value: &JsonValue, budget: usize) -> bool {
+        let mut counter = Budget { budget };
+        let mut ser = Serializer {
+            w: &mut counter,
+            formatter: &mut SpacedFormatter::inline(),
+            escape: self.escape,
+            sort_keys: false,
+            key_order: None,
+            non_finite: self.non_finite,
+        };
+        ser.write_value(value).is_ok()
+    }
+
+    fn write_inline(&mut self, value: &JsonValue) -> io::Result<()> {
+        let mut ser = Serializer {
+            w: self.w,
+            formatter: &mut SpacedFormatter::inline(),
+            escape: self.escape,
+            sort_keys: self.sort_keys,
+            key_order: self.key_order,
+            non_finite: self.non_finite,
+        };
+        ser.write_value(value)
+    }
+
+    fn begin_entry(&mut self, i: usize, level: usize) -> io::Result<()> {
+        if i > 0 {
+            self.w.write_all(b",")?;
+        }
+        self.w.write_all(self.newline)?;
+        self.write_indent(level)
+    }
+
+    fn write_indent(&mut self, level: usize) -> io::Result<()> {
+        for _ in 0..level {
+            self.w.write_all(self.unit)?;
+        }
+        Ok(())
+    }
+}
+
+/// Append `s` to `out` escaped as the serializer escapes string contents,
+/// without the quotes around them, for splicing text into JSON written by
+/// hand.
+///
+/// ```
+/// use grammar::json::{escape_str, EscapeOptions};
+///
+/// let mut out = String::from("{\"note\": \"");
+/// escape_str("say \"hi\"\n", &mut out, &EscapeOptions::default());
+/// out.push_str("\"}");
+/// assert_eq!(out, r#"{"note": "say \"hi\"\n"}"#);
+/// ```
+pub fn escape_str(s: &str, out: &mut String, options: &EscapeOptions) {
+    write_string(&mut StrSink(out), &mut Unquoted, s, options)
+        .expect("appending to a String never fails");
+}
+
+/// Appends to a `String`; the string writer only ever hands it whole
+/// characters.
+struct StrSink<'a>(&'a mut String);
+
+impl io::Write for StrSink<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s = std::str::from_utf8(buf).expect("string fragments are whole characters");
+        self.0.push_str(s);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Compact output minus the quotes around strings.
+struct Unquoted;
+
+impl Formatter for Unquoted {
+    fn begin_string<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn end_string<W: io::Write + ?Sized>(&mut self, _w: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub(super) fn write_string<W: io::Write + ?Sized, F: Formatter>(
+    w: &mut W,
+    formatter: &mut F,
+    s: &str,
+    escape: &EscapeOptions,
+) -> io::Result<()> {
+    formatter.begin_string(w)?;
+    // hand over unescaped runs in one go and only stop at characters needing escapes
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let short = match c {
+            '"' => Some(CharEscape::Quote),
+            '\\' => Some(CharEscape::ReverseSolidus),
+            '/' if escape.escape_solidus => Some(CharEscape::Solidus),
+            '\n' => Some(CharEscape::LineFeed),
+            '\r' => Some(CharEscape::CarriageReturn),
+            '\t' => Some(CharEscape::Tab),
+            '\u{08}' => Some(CharEscape::Backspace),
+            '\u{0c}' => Some(CharEscape::FormFeed),
+            '<' | '>' | '&' if escape.escape_html => None,
+            '\u{2028}' | '\u{2029}' if escape.escape_line_separators => None,
+            c if c < '\u{20}' || (escape.ascii_only && !c.is_ascii()) => None,
+            _ => continue,
+        };
+        if start < i {
+            formatter.write_string_fragment(w, &s[start..i])?;
+        }
+        match short {
+            Some(e) => formatter.write_char_escape(w, e)?,
+            None => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    formatter.write_char_escape(w, CharEscape::Unicode(*unit))?;
+                }
+            }
+        }
+        start = i + c.len_utf8();
+    }
+    if start < s.len() {
+        formatter.write_string_fragment(w, &s[start..])?;
+    }
+    formatter.end_string(w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> JsonValue {
+        let mut address = JsonObject::default();
+        address.insert("city".into(), JsonValue::String("New York".into()));
+        let mut root = JsonObject::default();
+        root.insert(
+            "person".into(),
+            JsonValue::Array(vec![
+                JsonValue::Number(Num::Int(1)),
+                JsonValue::Object(address),
+                JsonValue::Array(vec![]),
+                JsonValue::Object(Default::default()),
+                JsonValue::Null,
+            ]),
+        );
+        JsonValue::Object(root)
+    }
+
+    #[test]
+    fn compact_should_work() {
+        assert_eq!(
+            sample().to_json_string(),
+            r#"{"person":[1,{"city":"New York"},[],{},null]}"#
+        );
+    }
+
+    #[test]
+    fn pretty_two_spaces_should_work() {
+        let expected = "{\n  \"person\": [\n    1,\n    {\n      \"city\": \"New York\"\n    },\n    [],\n    {},\n    null\n  ]\n}";
+        assert_eq!(sample().to_json_string_pretty(), expected);
+    }
+
+    #[test]
+    fn pretty_four_spaces_should_work() {
+        let expected = "{\n    \"person\": [\n        1,\n        {\n            \"city\": \"New York\"\n        },\n        [],\n        {},\n        null\n    ]\n}";
+        assert_eq!(sample().to_json_string_indent(&Indent::Spaces(4)), expected);
+    }
+
+    #[test]
+    fn pretty_tab_should_work() {
+        let expected =
+            "{\n\t\"person\": [\n\t\t1,\n\t\t{\n\t\t\t\"city\": \"New York\"\n\t\t},\n\t\t[],\n\t\t{},\n\t\tnull\n\t]\n}";
+        assert_eq!(sample().to_json_string_indent(&Indent::tab()), expected);
+    }
+
+    #[test]
+    fn scalars_should_not_break_lines() {
+        let v = JsonValue::String("a\"b\\c\n".into());
+        assert_eq!(v.to_json_string_pretty(), r#""a\"b\\c\n""#);
+    }
+
+    #[test]
+    fn to_writer_should_match_string_serializer() -> io::Result<()> {
+        let v = sample();
+        let mut buf = Vec::new();
+        v.to_writer(&mut buf)?;
+        assert_eq!(buf, v.to_json_string().into_bytes());
+
+        let mut buf = Vec::new();
+        v.to_writer_pretty(&mut buf)?;
+        assert_eq!(buf, v.to_json_string_pretty().into_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn ascii_only_should_escape_non_ascii() {
+        let v = JsonValue::String("café 😀".into());
+        let options = FormatOptions {
+            escape: EscapeOptions {
+                ascii_only: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let s = v.to_string_with(&options);
+        assert_eq!(s, r#""caf\u00e9 \ud83d\ude00""#);
+        assert!(s.is_ascii());
+        assert_eq!(crate::json::parse_json(&s).unwrap(), v);
+
+        assert_eq!(v.to_json_string(), "\"café 😀\"");
+    }
+
+    #[test]
+    fn escape_html_should_escape_markup() {
+        let mut obj = JsonObject::default();
+        obj.insert("<b>".into(), JsonValue::String("</script>&".into()));
+        let v = JsonValue::Object(obj);
+        let options = FormatOptions {
+            escape: EscapeOptions {
+                escape_html: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let s = v.to_string_with(&options);
+        assert_eq!(s, r#"{"\u003cb\u003e":"\u003c/script\u003e\u0026"}"#);
+        assert_eq!(crate::json::parse_json(&s).unwrap(), v);
+    }
+
+    #[test]
+    fn line_separators_should_be_escaped_by_default() {
+        let v = JsonValue::String("a\u{2028}b\u{2029}c".into());
+        let s = v.to_json_string();
+        assert_eq!(s, r#""a\u2028b\u2029c""#);
+        assert_eq!(crate::json::parse_json(&s).unwrap(), v);
+
+        let options = FormatOptions {
+            escape: EscapeOptions {
+                escape_line_separators: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(v.to_string_with(&options), "\"a\u{2028}b\u{2029}c\"");
+    }
+
+    fn escaped(s: &str, escape: EscapeOptions) -> String {
+        let mut out = String::from(">");
+        escape_str(s, &mut out, &escape);
+        out
+    }
+
+    #[test]
+    fn escape_str_should_match_the_serializer() {
+        let text = "q\"b\\ \u{1}\t\n</a>&é😀\u{2028}";
+        let all = EscapeOptions {
+            ascii_only: true,
+            escape_html: true,
+            escape_line_separators: true,
+            escape_solidus: true,
+        };
+        for escape in [EscapeOptions::default(), all] {
+            let options = FormatOptions::compact().escape(escape);
+            let quoted = JsonValue::from(text).to_string_with(&options);
+            let body = escaped(text, escape);
+            assert_eq!(body[1..], quoted[1..quoted.len() - 1]);
+            assert_eq!(crate::json::unescape_str(&body[1..]).unwrap(), text);
+        }
+        assert_eq!(
+            escaped(text, all),
+            r#">q\"b\\ \u0001\t\n\u003c\/a\u003e\u0026\u00e9\ud83d\ude00\u2028"#
+        );
+        // appends, leaving what is there alone
+        assert_eq!(escaped("", EscapeOptions::default()), ">");
+    }
+
+    #[test]
+    fn escape_options_should_each_escape_their_characters() {
+        let only = |set: fn(&mut EscapeOptions)| {
+            let mut escape = EscapeOptions {
+                escape_line_separators: false,
+                ..Default::default()
+            };
+            set(&mut escape);
+            escaped("</é>&\u{2029}", escape)
+        };
+        assert_eq!(only(|_| {}), "></é>&\u{2029}");
+        assert_eq!(only(|e| e.ascii_only = true), r"></\u00e9>&\u2029");
+        assert_eq!(
+            only(|e| e.escape_html = true),
+            ">\\u003c/é\\u003e\\u0026\u{2029}"
+        );
+        assert_eq!(only(|e| e.escape_line_separators = true), r"></é>&\u2029");
+        assert_eq!(only(|e| e.escape_solidus = true), "><\\/é>&\u{2029}");
+    }
+
+    #[test]
+    fn sort_keys_should_be_stable_across_insertion_order() {
+        let keys = ["b", "a", "é", "Z", "aa"];
+        let build = |keys: &mut dyn Iterator<Item = &&str>| {
+            let inner: JsonObject = keys.map(|k| ((*k).into(), JsonValue::Null)).collect();
+            let mut obj = inner.clone();
+            obj.insert("nested".into(), JsonValue::Object(inner));
+            JsonValue::Object(obj)
+        };
+        let a = build(&mut keys.iter());
+        let b = build(&mut keys.iter().rev());
+
+        let options = FormatOptions {
+            sort_keys: true,
+            ..Default::default()
+        };
+        let expected = r#"{"Z":null,"a":null,"aa":null,"b":null,"nested":{"Z":null,"a":null,"aa":null,"b":null,"é":null},"é":null}"#;
+        assert_eq!(a.to_string_with(&options), expected);
+        assert_eq!(b.to_string_with(&options), expected);
+
+        let options = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::pretty()
+        };
+        assert_eq!(a.to_string_with(&options), b.to_string_with(&options));
+    }
+
+    #[test]
+    fn key_order_should_put_priority_keys_first_at_every_level() {
+        let v = crate::json::parse_json(
+            r#"{"zeta": 1, "type": "t", "items": [{"b": 2, "name": "n", "id": 1}], "id": 0}"#,
+        )
+        .unwrap();
+        let order = KeyOrder::priority(["id", "name", "type"]);
+        let compact = FormatOptions::compact().key_order(order.clone());
+        assert_eq!(
+            v.to_string_with(&compact),
+            r#"{"id":0,"type":"t","items":[{"id":1,"name":"n","b":2}],"zeta":1}"#
+        );
+        // the comparator wins over sort_keys and applies to every layout
+        let pretty = FormatOptions::pretty()
+            .sort_keys(true)
+            .key_order(order.clone());
+        assert_eq!(
+            v.to_string_with(&pretty),
+            "{\n  \"id\": 0,\n  \"type\": \"t\",\n  \"items\": [\n    {\n      \"id\": 1,\n      \"name\": \"n\",\n      \"b\": 2\n    }\n  ],\n  \"zeta\": 1\n}"
+        );
+        let narrow = FormatOptions::pretty().max_width(40).key_order(order);
+        assert_eq!(
+            v.to_string_with(&narrow),
+            "{\n  \"id\": 0,\n  \"type\": \"t\",\n  \"items\": [\n    {\"id\": 1, \"name\": \"n\", \"b\": 2}\n  ],\n  \"zeta\": 1\n}"
+        );
+        let reversed = FormatOptions::compact().key_order(KeyOrder::new(|a, b| b.cmp(a)));
+        assert_eq!(
+            v.to_string_with(&reversed),
+            r#"{"zeta":1,"type":"t","items":[{"name":"n","id":1,"b":2}],"id":0}"#
+        );
+        // without one, output is as before
+        assert_eq!(
+            v.to_string_with(&FormatOptions::compact().sort_keys(true)),
+            r#"{"id":0,"items":[{"b":2,"id":1,"name":"n"}],"type":"t","zeta":1}"#
+        );
+        assert_eq!(FormatOptions::compact().key_order, None);
+        assert_eq!(compact.clone(), compact);
+        assert_ne!(
+            compact,
+            FormatOptions::compact().key_order(KeyOrder::priority(["id"]))
+        );
+    }
+
+    #[test]
+    fn floats_should_use_shortest_round_trip_form() {
+        let cases = [
+            (0.1 + 0.2, "0.30000000000000004"),
+            (0.3, "0.3"),
+            (1.0, "1.0"),
+            (-0.0, "-0.0"),
+            (1e300, "1e300"),
+            (5e-324, "5e-324"),
+            (123456.789, "123456.789"),
+        ];
+        for (f, expected) in cases {
+            assert_eq!(JsonValue::Number(Num::Float(f)).to_json_string(), expected);
+        }
+    }
+
+    #[test]
+    fn floats_should_round_trip_bit_identically() {
+        // xorshift64*, deterministic so failures are reproducible
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut checked = 0;
+        while checked < 20_000 {
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            let f = f64::from_bits(state.wrapping_mul(0x2545_f491_4f6c_dd1d));
+            if !f.is_finite() {
+                continue;
+            }
+            let s = JsonValue::Number(Num::Float(f)).to_json_string();
+            match crate::json::parse_json(&s).unwrap() {
+                JsonValue::Number(Num::Float(g)) => assert_eq!(f.to_bits(), g.to_bits(), "{}", s),
+                v => panic!("{} parsed as {:?}", s, v),
+            }
+            checked += 1;
+        }
+    }
+
+    const DIFF_FIXTURE: &str = r#"{"name": "demo", "tags": ["a", "b"], "empty": [],
+        "items": [{"id": 1}, {"id": 2, "on": true}], "meta": {}}"#;
+
+    fn diff_options() -> FormatOptions {
+        FormatOptions::compact().diff_friendly(true).sort_keys(true)
+    }
+
+    #[test]
+    fn diff_friendly_should_put_commas_first() {
+        let value = crate::json::parse_json(DIFF_FIXTURE).unwrap();
+        let expected = r#"{
+  "empty": [
+  ]
+, "items": [
+    {
+      "id": 1
+    }
+  , {
+      "id": 2
+    , "on": true
+    }
+  ]
+, "meta": {
+  }
+, "name": "demo"
+, "tags": [
+    "a"
+  , "b"
+  ]
+}"#;
+        assert_eq!(value.to_string_with(&diff_options()), expected);
+        // max_width does not inline anything
+        let narrow = diff_options().max_width(usize::MAX);
+        assert_eq!(value.to_string_with(&narrow), expected);
+        let tabs = FormatOptions::pretty().tabs().diff_friendly(true);
+        let tabbed = JsonValue::Array(vec![1.into(), 2.into()]).to_string_with(&tabs);
+        assert_eq!(tabbed, "[\n\t1\n,\t2\n]");
+        assert_eq!(JsonValue::from(1).to_string_with(&diff_options()), "1");
+    }
+
+    /// The lines that differ, as `-` and `+` lines, assuming one contiguous
+    /// change.
+    fn line_diff(before: &str, after: &str) -> Vec<String> {
+        let (a, b): (Vec<_>, Vec<_>) = (before.lines().collect(), after.lines().collect());
+        let head = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+        let tail = a[head..]
+            .iter()
+            .rev()
+            .zip(b[head..].iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count();
+        let removed = a[head..a.len() - tail].iter().map(|l| format!("-{}", l));
+        let added = b[head..b.len() - tail].iter().map(|l| format!("+{}", l));
+        removed.chain(added).collect()
+    }
+
+    #[test]
+    fn diff_friendly_edits_should_change_one_line() {
+        let before = crate::json::parse_json(DIFF_FIXTURE).unwrap();
+        let edit = |f: fn(&mut JsonObject)| {
+            let mut value = before.clone();
+            let JsonValue::Object(obj) = &mut value else {
+                unreachable!()
+            };
+            f(obj);
+            line_diff(
+                &before.to_string_with(&diff_options()),
+                &value.to_string_with(&diff_options()),
+            )
+        };
+        fn push(obj: &mut JsonObject, key: &str, item: JsonValue) {
+            let Some(JsonValue::Array(items)) = obj.get_mut(key) else {
+                unreachable!()
+            };
+            items.push(item);
+        }
+        let appended = edit(|obj| push(obj, "tags", "c".into()));
+        assert_eq!(appended, [r#"+  , "c""#]);
+        let first = edit(|obj| push(obj, "empty", JsonValue::Null));
+        assert_eq!(first, ["+    null"]);
+        let sorted_in = edit(|obj| {
+            obj.insert("lang".into(), "en".into());
+        });
+        assert_eq!(sorted_in, [r#"+, "lang": "en""#]);
+        let changed = edit(|obj| {
+            obj.insert("name".into(), "renamed".into());
+        });
+        assert_eq!(changed, [r#"-, "name": "demo""#, r#"+, "name": "renamed""#]);
+        // the plain pretty printer touches the line before as well
+        let mut value = before.clone();
+        let JsonValue::Object(obj) = &mut value else {
+            unreachable!()
+        };
+        push(obj, "tags", "c".into());
+        let pretty = FormatOptions::pretty().sort_keys(true);
+        let diff = line_diff(
+            &before.to_string_with(&pretty),
+            &value.to_string_with(&pretty),
+        );
+        assert_eq!(diff, [r#"-    "b""#, r#"+    "b","#, r#"+    "c""#]);
+    }
+
+    fn width_options(width: usize) -> FormatOptions {
+        FormatOptions {
+            sort_keys: true,
+            max_width: Some(width),
+            ..FormatOptions::pretty()
+        }
+    }
+
+    #[test]
+    fn width_aware_should_inline_short_containers() {
+        let v = crate::json::parse_json(
+            r#"{"point": [1, 2], "points": [{"x": 1, "y": 2}, {"x": 3, "y": 4}, {"x": 5, "y": 6}], "empty": []}"#,
+        )
+        .unwrap();
+
+        let expected = r#"{
+  "empty": [],
+  "point": [1, 2],
+  "points": [{"x": 1, "y": 2}, {"x": 3, "y": 4}, {"x": 5, "y": 6}]
+}"#;
+        assert_eq!(v.to_string_with(&width_options(80)), expected);
+
+        let expected = r#"{
+  "empty": [],
+  "point": [1, 2],
+  "points": [
+    {"x": 1, "y": 2},
+    {"x": 3, "y": 4},
+    {"x": 5, "y": 6}
+  ]
+}"#;
+        assert_eq!(v.to_string_with(&width_options(40)), expected);
+
+        // width 0 never inlines and matches the plain pretty printer
+        let plain = FormatOptions {
+            sort_keys: true,
+            ..FormatOptions::pretty()
+        };
+        assert_eq!(
+            v.to_string_with(&width_options(0)),
+            v.to_string_with(&plain)
+        );
+
+        let expected = r#"{"empty": [], "point": [1, 2], "points": [{"x": 1, "y": 2}, {"x": 3, "y": 4}, {"x": 5, "y": 6}]}"#;
+        assert_eq!(v.to_string_with(&width_options(usize::MAX)), expected);
+    }
+
+    #[test]
+    fn width_aware_should_count_indent_and_key() {
+        // `  "ab": [1, 2],` is exactly 15 columns
+        let v = crate::json::parse_json(r#"{"ab": [1, 2], "c": 0}"#).unwrap();
+        let s = v.to_string_with(&width_options(15));
+        assert!(s.contains("\"ab\": [1, 2],\n"), "{}", s);
+        let s = v.to_string_with(&width_options(14));
+        assert!(s.contains("\"ab\": [\n    1,"), "{}", s);
+    }
+
+    #[test]
+    fn width_aware_should_handle_deep_nesting() {
+        let mut v = JsonValue::Null;
+        for _ in 0..200 {
+            v = JsonValue::Array(vec![v, JsonValue::Number(Num::Int(1))]);
+        }
+        let s = v.to_string_with(&width_options(80));
+        let unlimited = crate::json::ParseOptions {
+            max_depth: None,
+            ..Default::default()
+        };
+        assert_eq!(crate::json::parse_json_with(&s, &unlimited).unwrap(), v);
+    }
+
+    #[test]
+    fn format_options_compact_spacing_should_work() {
+        let v = crate::json::parse_json(r#"{"a": [1, 2], "b": []}"#).unwrap();
+        let base = FormatOptions::compact().sort_keys(true);
+        assert_eq!(v.to_string_with(&base), r#"{"a":[1,2],"b":[]}"#);
+        assert_eq!(
+            v.to_string_with(&base.clone().space_after_colon(true)),
+            r#"{"a": [1,2],"b": []}"#
+        );
+        assert_eq!(
+            v.to_string_with(&base.clone().space_after_comma(true)),
+            r#"{"a":[1, 2], "b":[]}"#
+        );
+        assert_eq!(
+            v.to_string_with(&base.trailing_newline(true)),
+            "{\"a\":[1,2],\"b\":[]}\n"
+        );
+    }
+
+    #[test]
+    fn format_options_pretty_layout_should_work() {
+        let v = crate::json::parse_json(r#"{"a": [1, []], "b": {"c": null}}"#).unwrap();
+        let base = FormatOptions::pretty().sort_keys(true);
+
+        let expected =
+            "{\n   \"a\": [\n      1,\n      []\n   ],\n   \"b\": {\n      \"c\": null\n   }\n}";
+        assert_eq!(v.to_string_with(&base.clone().spaces(3)), expected);
+
+        let expected = "{\r\n  \"a\": [\r\n    1,\r\n    []\r\n  ],\r\n  \"b\": {\r\n    \"c\": null\r\n  }\r\n}\r\n";
+        let options = base.clone().newline(Newline::CrLf).trailing_newline(true);
+        assert_eq!(v.to_string_with(&options), expected);
+
+        let expected = "{\r\n\t\"a\": [\r\n\t\t1,\r\n\t\t[]\r\n\t],\r\n\t\"b\": {\r\n\t\t\"c\": null\r\n\t}\r\n}";
+        let options = base.clone().tabs().newline(Newline::CrLf);
+        assert_eq!(v.to_string_with(&options), expected);
+
+        let expected = "{\r\n\t\"a\": [1, []],\r\n\t\"b\": {\"c\": null}\r\n}\r\n";
+        let options = base
+            .tabs()
+            .newline(Newline::CrLf)
+            .max_width(20)
+            .trailing_newline(true);
+        assert_eq!(v.to_string_with(&options), expected);
+    }
+
+    #[test]
+    fn format_options_should_apply_to_writer() -> io::Result<()> {
+        let v = sample();
+        let options = FormatOptions::pretty().tabs().trailing_newline(true);
+        let mut out = Vec::new();
+        v.to_writer_with(&mut out, &options)?;
+        assert_eq!(out, v.to_string_with(&options).into_bytes());
+        assert!(out.ends_with(b"}\n"));
+        Ok(())
+    }
+
+    /// Shouts `NULL` and uses `=` between keys and values, recording the
+    /// order in which the hooks fire.
+    #[derive(Default)]
+    struct ShoutingFormatter {
+        calls: Vec<&'static str>,
+    }
+
+    impl Formatter for ShoutingFormatter {
+        fn write_null<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+            self.calls.push("null");
+            w.write_all(b"NULL")
+        }
+
+        fn begin_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+            self.calls.push("begin_object");
+            w.write_all(b"{")
+        }
+
+        fn begin_object_key<W: io::Write + ?Sized>(
+            &mut self,
+            w: &mut W,
+            first: bool,
+        ) -> io::Result<()> {
+            self.calls.push(if first { "first_key" } else { "key" });
+            if !first {
+                w.write_all(b",")?;
+            }
+            Ok(())
+        }
+
+        fn begin_object_value<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+            self.calls.push("value");
+            w.write_all(b"=")
+        }
+
+        fn end_object<W: io::Write + ?Sized>(&mut self, w: &mut W) -> io::Result<()> {
+            self.calls.push("end_object");
+            w.write_all(b"}")
+        }
+    }
+
+    #[test]
+    fn custom_formatter_hooks_should_fire_in_order() -> io::Result<()> {
+        let v = crate::json::parse_json(r#"{"a": null, "b": [null]}"#).unwrap();
+        let options = FormatOptions {
+            sort_keys: true,
+            ..Default::default()
+        };
+        let mut f = ShoutingFormatter::default();
+        let mut out = Vec::new();
+        v.to_writer_with_formatter(&mut out, &mut f, &options)?;
+        assert_eq!(String::from_utf8(out).unwrap(), r#"{"a"=NULL,"b"=[NULL]}"#);
+        assert_eq!(
+            f.calls,
+            [
+                "begin_object",
+                "first_key",
+                "value",
+                "null",
+                "key",
+                "value",
+                "null",
+                "end_object"
+            ]
+        );
+        Ok(())
+    }
+
+    struct FailingWriter {
+        remaining: usize,
+        written: Vec<u8>,
+    }
+
+    impl io::Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(io::Error::other("disk full"));
+            }
+            let n = buf.len().min(self.remaining);
+            self.remaining -= n;
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn to_writer_should_propagate_errors() {
+        let mut w = FailingWriter {
+            remaining: 10,
+            written: Vec::new(),
+        };
+        let err = sample().to_writer_pretty(&mut w).unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+        assert_eq!(w.written.len(), 10);
+    }
+
+    fn with_non_finite(f: f64) -> JsonValue {
+        let mut inner = JsonObject::default();
+        inner.insert("b".into(), JsonValue::from(f));
+        let mut root = JsonObject::default();
+        root.insert(
+            "a".into(),
+            JsonValue::Array(vec![JsonValue::from(1), JsonValue::Object(inner)]),
+        );
+        JsonValue::Object(root)
+    }
+
+    #[test]
+    fn non_finite_should_error_with_pointer_by_default() {
+        let expected = JsonError::Serialize {
+            pointer: "/a/1/b".to_string(),
+            message: "NaN is not a valid JSON number".to_string(),
+        };
+        let v = with_non_finite(f64::NAN);
+        for options in [
+            FormatOptions::compact(),
+            FormatOptions::pretty(),
+            FormatOptions::pretty().max_width(80),
+        ] {
+            assert_eq!(v.try_to_string_with(&options).unwrap_err(), expected);
+        }
+
+        let err = v.to_writer(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "cannot serialize value at \"/a/1/b\": NaN is not a valid JSON number"
+        );
+    }
+
+    #[test]
+    fn non_finite_null_should_write_null() {
+        let options = FormatOptions::compact().non_finite(NonFiniteBehavior::Null);
+        assert_eq!(
+            with_non_finite(f64::INFINITY).to_string_with(&options),
+            r#"{"a":[1,{"b":null}]}"#
+        );
+    }
+
+    #[test]
+    fn non_finite_should_be_null_where_no_error_can_be_returned() {
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let v = with_non_finite(f);
+            assert_eq!(v.to_json_string(), r#"{"a":[1,{"b":null}]}"#);
+            assert_eq!(v.to_vec(), br#"{"a":[1,{"b":null}]}"#);
+            let pretty = "{\n  \"a\": [\n    1,\n    {\n      \"b\": null\n    }\n  ]\n}";
+            assert_eq!(v.to_json_string_pretty(), pretty);
+            assert_eq!(v.to_vec_pretty(), pretty.as_bytes());
+            assert_eq!(
+                v.to_json_string_indent(&Indent::Str("\t".into())),
+                pretty.replace("  ", "\t")
+            );
+            assert!(v.try_to_vec_with(&FormatOptions::default()).is_err());
+        }
+    }
+
+    #[test]
+    fn non_finite_literal_should_write_js_names() {
+        let options = FormatOptions::pretty()
+            .max_width(80)
+            .non_finite(NonFiniteBehavior::Literal);
+        for (f, literal) in [
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ] {
+            assert_eq!(
+                with_non_finite(f).to_string_with(&options),
+                format!(r#"{{"a": [1, {{"b": {}}}]}}"#, literal)
+            );
+        }
+    }
+
+    const ODD_NUMBERS: [&str; 10] = [
+        "1.000",
+        "1e+5",
+        "1E5",
+        "-0",
+        "-0.0e-0",
+        "0.10",
+        "12345678901234567890123",
+        "1.7976931348623157e309",
+        "5e-325",
+        "100",
+    ];
+
+    fn raw_options() -> crate::json::ParseOptions {
+        crate::json::ParseOptions {
+            preserve_number_text: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn raw_numbers_should_round_trip_verbatim() {
+        for text in ODD_NUMBERS {
+            let v = crate::json::parse_json_with(text, &raw_options()).unwrap();
+            assert_eq!(v, JsonValue::Number(Num::Raw(text.to_string())));
+            assert_eq!(v.to_json_string(), text);
+        }
+    }
+
+    #[test]
+    fn raw_numbers_should_survive_pretty_printing() {
+        let input = format!("[{}]", ODD_NUMBERS.join(","));
+        let v = crate::json::parse_json_with(&input, &raw_options()).unwrap();
+        let pretty = v.to_json_string_pretty();
+        assert_eq!(pretty, format!("[\n  {}\n]", ODD_NUMBERS.join(",\n  ")));
+        assert_eq!(
+            v.to_string_with(&FormatOptions::pretty().max_width(200)),
+            format!("[{}]", ODD_NUMBERS.join(", "))
+        );
+        assert_eq!(
+            crate::json::parse_json_with(&pretty, &raw_options()).unwrap(),
+            v
+        );
+
+        let normalized = crate::json::parse_json(r#"{"a": 1.000, "b": 1e+5}"#).unwrap();
+        assert_eq!(
+            normalized.to_string_with(&FormatOptions::compact().sort_keys(true)),
+            r#"{"a":1.0,"b":100000.0}"#
+        );
+    }
+
+    #[test]
+    fn to_vec_should_match_string_serializer() {
+        let corpus = [
+            sample(),
+            JsonValue::Null,
+            JsonValue::from("caf\u{e9} \u{1F600} \"q\""),
+            crate::json::parse_json(r#"[1, -2.5, 1e300, true, [[]], {"k": "v"}]"#).unwrap(),
+        ];
+        for v in corpus {
+            assert_eq!(v.to_vec(), v.to_json_string().into_bytes());
+            assert_eq!(v.to_vec_pretty(), v.to_json_string_pretty().into_bytes());
+        }
+        assert_eq!(
+            JsonValue::from(f64::NAN).try_to_vec_with(&FormatOptions::compact()),
+            Err(JsonError::serialize("NaN is not a valid JSON number"))
+        );
+    }
+}
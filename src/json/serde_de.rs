@@ -0,0 +1,526 @@
+use super::{into_std_string, parse_json, JsonError, JsonObject, JsonValue, Num, Pointer};
+use serde::de::{
+    self, value::StringDeserializer, DeserializeOwned, DeserializeSeed, EnumAccess,
+    IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use std::fmt::Display;
+
+/// Parse `input` and decode it into a `T`. Malformed JSON fails with
+/// [`JsonError::Parse`] and its offset; a document of the wrong shape with
+/// [`JsonError::Decode`] and the path to the value at fault, as in
+/// `at /servers/2/port: invalid type: string "80x", expected u16`.
+///
+/// ```
+/// use grammar::json::from_str;
+///
+/// let pairs: Vec<(String, Option<u8>)> = from_str(r#"[["a", 1], ["b", null]]"#).unwrap();
+/// assert_eq!(pairs, [("a".to_string(), Some(1)), ("b".to_string(), None)]);
+/// ```
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, JsonError> {
+    from_value(parse_json(input)?)
+}
+
+/// Decode an already parsed value into a `T`.
+pub fn from_value<T: DeserializeOwned>(value: JsonValue) -> Result<T, JsonError> {
+    T::deserialize(Deserializer::new(value))
+}
+
+/// A serde `Deserializer` over a [`JsonValue`].
+///
+/// Enums are externally tagged: a unit variant is a string and any other
+/// variant an object whose one member is named after it. Integers decode
+/// into any integer type they fit, and into floats; floats with no
+/// fractional part decode into integers too. Map keys are strings.
+pub struct Deserializer {
+    value: JsonValue,
+}
+
+impl Deserializer {
+    pub fn new(value: JsonValue) -> Self {
+        Deserializer { value }
+    }
+
+    /// Parse `input` to deserialize from.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &str) -> Result<Self, JsonError> {
+        parse_json(input).map(Deserializer::new)
+    }
+}
+
+impl de::Error for JsonError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JsonError::Decode {
+            path: Pointer::default(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// How `value` looks to serde, for type errors.
+fn unexpected(value: &JsonValue) -> de::Unexpected<'_> {
+    match value {
+        JsonValue::Null => de::Unexpected::Unit,
+        JsonValue::Bool(b) => de::Unexpected::Bool(*b),
+        JsonValue::Number(Num::Int(i)) => de::Unexpected::Signed(*i),
+        JsonValue::Number(Num::Float(f)) => de::Unexpected::Float(*f),
+        JsonValue::Number(Num::Raw(_)) => de::Unexpected::Other("number"),
+        #[cfg(feature = "decimal")]
+        JsonValue::Number(Num::Decimal(_)) => de::Unexpected::Other("number"),
+        JsonValue::String(s) => de::Unexpected::Str(s),
+        JsonValue::Array(_) => de::Unexpected::Seq,
+        JsonValue::Object(_) => de::Unexpected::Map,
+        JsonValue::Raw(_) => de::Unexpected::Other("raw value"),
+    }
+}
+
+impl Deserializer {
+    /// Integers as they are, and integral floats as integers.
+    fn deserialize_integer<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        match self.value {
+            JsonValue::Number(Num::Float(f))
+                if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 =>
+            {
+                visitor.visit_i64(f as i64)
+            }
+            JsonValue::Number(Num::Float(f))
+                if f.fract() == 0.0 && f >= 0.0 && f < u64::MAX as f64 =>
+            {
+                visitor.visit_u64(f as u64)
+            }
+            _ => de::Deserializer::deserialize_any(self, visitor),
+        }
+    }
+}
+
+macro_rules! deserialize_integers {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+            self.deserialize_integer(visitor)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = JsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        match self.value {
+            JsonValue::Null => visitor.visit_unit(),
+            JsonValue::Bool(b) => visitor.visit_bool(b),
+            JsonValue::Number(Num::Int(i)) => visitor.visit_i64(i),
+            JsonValue::Number(Num::Float(f)) => visitor.visit_f64(f),
+            JsonValue::Number(Num::Raw(text)) => {
+                if let Ok(i) = text.parse::<i64>() {
+                    visitor.visit_i64(i)
+                } else if let Ok(u) = text.parse::<u64>() {
+                    visitor.visit_u64(u)
+                } else {
+                    visitor.visit_f64(text.parse().unwrap_or(f64::NAN))
+                }
+            }
+            #[cfg(feature = "decimal")]
+            JsonValue::Number(Num::Decimal(d)) => visitor.visit_f64(d.to_f64()),
+            JsonValue::String(s) => visitor.visit_string(into_std_string(s)),
+            JsonValue::Array(arr) => visitor.visit_seq(Elements {
+                elements: arr.into_iter().enumerate(),
+            }),
+            JsonValue::Object(obj) => visitor.visit_map(Members {
+                members: obj.into_iter(),
+                value: None,
+            }),
+            JsonValue::Raw(raw) => Deserializer::new(raw.parse()?).deserialize_any(visitor),
+        }
+    }
+
+    deserialize_integers! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        match self.value {
+            JsonValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        match self.value {
+            JsonValue::String(s) => {
+                let variant: StringDeserializer<JsonError> = into_std_string(s).into_deserializer();
+                visitor.visit_enum(variant)
+            }
+            JsonValue::Object(obj) if obj.len() == 1 => {
+                let (variant, value) = obj.into_iter().next().expect("one member");
+                visitor.visit_enum(Variant {
+                    variant: into_std_string(variant),
+                    value,
+                })
+            }
+            other => Err(de::Error::invalid_type(
+                unexpected(&other),
+                &"a string or an object with one member",
+            )),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier
+    }
+}
+
+impl<'de> IntoDeserializer<'de, JsonError> for JsonValue {
+    type Deserializer = Deserializer;
+
+    fn into_deserializer(self) -> Deserializer {
+        Deserializer::new(self)
+    }
+}
+
+/// The elements of an array, numbered for error pointers.
+struct Elements {
+    elements: std::iter::Enumerate<std::vec::IntoIter<JsonValue>>,
+}
+
+impl<'de> SeqAccess<'de> for Elements {
+    type Error = JsonError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, JsonError> {
+        let Some((index, value)) = self.elements.next() else {
+            return Ok(None);
+        };
+        seed.deserialize(Deserializer::new(value))
+            .map(Some)
+            .map_err(|e| e.within(&index.to_string()))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+/// The members of an object, keeping the key of the one whose value is next
+/// for error pointers.
+struct Members {
+    members: <JsonObject as IntoIterator>::IntoIter,
+    value: Option<(String, JsonValue)>,
+}
+
+impl<'de> MapAccess<'de> for Members {
+    type Error = JsonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, JsonError> {
+        let Some((key, value)) = self.members.next() else {
+            return Ok(None);
+        };
+        let key = into_std_string(key);
+        let decoded = seed
+            .deserialize(key.as_str().into_deserializer())
+            .map_err(|e: JsonError| e.within(&key))?;
+        self.value = Some((key, value));
+        Ok(Some(decoded))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, JsonError> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| <JsonError as de::Error>::custom("value asked for before its key"))?;
+        seed.deserialize(Deserializer::new(value))
+            .map_err(|e| e.within(&key))
+    }
+}
+
+/// An enum variant written as `{"Variant": value}`. Errors in the value are
+/// under the variant's name, the member holding it.
+struct Variant {
+    variant: String,
+    value: JsonValue,
+}
+
+impl<'de> EnumAccess<'de> for Variant {
+    type Error = JsonError;
+    type Variant = Variant;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Variant), JsonError> {
+        let variant: StringDeserializer<JsonError> = self.variant.clone().into_deserializer();
+        Ok((seed.deserialize(variant)?, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant {
+    type Error = JsonError;
+
+    fn unit_variant(self) -> Result<(), JsonError> {
+        match self.value {
+            JsonValue::Null => Ok(()),
+            other => Err(
+                <JsonError as de::Error>::invalid_type(unexpected(&other), &"null")
+                    .within(&self.variant),
+            ),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, JsonError> {
+        seed.deserialize(Deserializer::new(self.value))
+            .map_err(|e| e.within(&self.variant))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        de::Deserializer::deserialize_seq(Deserializer::new(self.value), visitor)
+            .map_err(|e| e.within(&self.variant))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        de::Deserializer::deserialize_map(Deserializer::new(self.value), visitor)
+            .map_err(|e| e.within(&self.variant))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::{collections::BTreeMap, fmt, time::Duration};
+
+    /// What `#[derive(Deserialize)]` would write for
+    /// `struct Server { host: String, port: u16, weight: Option<f64>, backup: bool }`
+    /// with `#[serde(default)]` on `backup`.
+    #[derive(Debug, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+        weight: Option<f64>,
+        backup: bool,
+    }
+
+    impl<'de> Deserialize<'de> for Server {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct ServerVisitor;
+
+            impl<'de> Visitor<'de> for ServerVisitor {
+                type Value = Server;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("struct Server")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Server, A::Error> {
+                    let (mut host, mut port, mut weight, mut backup) = (None, None, None, None);
+                    while let Some(key) = map.next_key::<String>()? {
+                        match key.as_str() {
+                            "host" => host = Some(map.next_value()?),
+                            "port" => port = Some(map.next_value()?),
+                            "weight" => weight = Some(map.next_value()?),
+                            "backup" => backup = Some(map.next_value()?),
+                            _ => {
+                                map.next_value::<de::IgnoredAny>()?;
+                            }
+                        }
+                    }
+                    Ok(Server {
+                        host: host.ok_or_else(|| de::Error::missing_field("host"))?,
+                        port: port.ok_or_else(|| de::Error::missing_field("port"))?,
+                        weight: weight.unwrap_or_default(),
+                        backup: backup.unwrap_or_default(),
+                    })
+                }
+            }
+
+            deserializer.deserialize_struct(
+                "Server",
+                &["host", "port", "weight", "backup"],
+                ServerVisitor,
+            )
+        }
+    }
+
+    /// `enum Mode { Off, Fixed(u32), Window { size: u8, step: u8 } }`,
+    /// written out by hand, the struct variant's fields kept as a map.
+    #[derive(Debug, PartialEq)]
+    enum Mode {
+        Off,
+        Fixed(u32),
+        Window(BTreeMap<String, u8>),
+    }
+
+    impl<'de> Deserialize<'de> for Mode {
+        fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct ModeVisitor;
+
+            impl<'de> Visitor<'de> for ModeVisitor {
+                type Value = Mode;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("enum Mode")
+                }
+
+                fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Mode, A::Error> {
+                    let (name, variant) = data.variant::<String>()?;
+                    match name.as_str() {
+                        "Off" => variant.unit_variant().map(|()| Mode::Off),
+                        "Fixed" => variant.newtype_variant().map(Mode::Fixed),
+                        "Window" => variant
+                            .struct_variant(&["size", "step"], MapVisitor)
+                            .map(Mode::Window),
+                        other => Err(de::Error::unknown_variant(other, VARIANTS)),
+                    }
+                }
+            }
+
+            struct MapVisitor;
+
+            impl<'de> Visitor<'de> for MapVisitor {
+                type Value = BTreeMap<String, u8>;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("struct variant Window")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                    let mut fields = BTreeMap::new();
+                    while let Some((k, v)) = map.next_entry()? {
+                        fields.insert(k, v);
+                    }
+                    Ok(fields)
+                }
+            }
+
+            const VARIANTS: &[&str] = &["Off", "Fixed", "Window"];
+            deserializer.deserialize_enum("Mode", VARIANTS, ModeVisitor)
+        }
+    }
+
+    #[test]
+    fn from_str_should_decode_nested_structs_enums_and_options() {
+        let input = r#"{
+            "servers": [
+                {"host": "a", "port": 80, "weight": 0.5},
+                {"host": "b", "port": 8080.0, "weight": null, "backup": true, "note": [1]}
+            ],
+            "modes": ["Off", {"Fixed": 3}, {"Window": {"size": 4, "step": 1}}],
+            "retry": {"Ok": {"secs": 2, "nanos": 500}},
+            "limits": {"cpu": [1, null]}
+        }"#;
+        let value = parse_json(input).unwrap();
+        let part = |key: &str| value.pointer(&format!("/{}", key)).unwrap().clone();
+        let servers: Vec<Server> = from_value(part("servers")).unwrap();
+        let modes: Vec<Mode> = from_value(part("modes")).unwrap();
+        let retry: Result<Duration, String> = from_value(part("retry")).unwrap();
+        let limits: BTreeMap<String, (i8, Option<u64>)> = from_value(part("limits")).unwrap();
+        assert_eq!(
+            servers,
+            [
+                Server {
+                    host: "a".into(),
+                    port: 80,
+                    weight: Some(0.5),
+                    backup: false,
+                },
+                Server {
+                    host: "b".into(),
+                    port: 8080,
+                    weight: None,
+                    backup: true,
+                },
+            ]
+        );
+        let window = BTreeMap::from([("size".to_string(), 4), ("step".to_string(), 1)]);
+        assert_eq!(modes, [Mode::Off, Mode::Fixed(3), Mode::Window(window)]);
+        assert_eq!(retry, Ok(Duration::new(2, 500)));
+        assert_eq!(limits, BTreeMap::from([("cpu".to_string(), (1, None))]));
+    }
+
+    #[test]
+    fn from_str_should_report_where_the_shape_is_wrong() {
+        let path = |p: &str| Pointer::parse(p).unwrap();
+        let err =
+            from_str::<Vec<Server>>(r#"[{"host": "a", "port": 1}, {"host": "b", "port": "80x"}]"#)
+                .unwrap_err();
+        assert_eq!(
+            err,
+            JsonError::Decode {
+                path: path("/1/port"),
+                message: "invalid type: string \"80x\", expected u16".into(),
+            }
+        );
+        assert_eq!(err.path(), Some(&path("/1/port")));
+
+        let fleet = r#"{"servers": [
+            {"host": "a", "port": 1},
+            {"host": "b", "port": 2},
+            {"host": "c", "port": "80x"}
+        ]}"#;
+        let err = from_str::<BTreeMap<String, Vec<Server>>>(fleet).unwrap_err();
+        assert_eq!(err.path().unwrap().tokens(), ["servers", "2", "port"]);
+        assert_eq!(
+            err.to_string(),
+            "at /servers/2/port: invalid type: string \"80x\", expected u16"
+        );
+
+        let err =
+            from_str::<BTreeMap<String, Server>>(r#"{"primary": {"host": "a"}}"#).unwrap_err();
+        assert_eq!(
+            err,
+            JsonError::Decode {
+                path: path("/primary"),
+                message: "missing field `port`".into(),
+            }
+        );
+        // keys are escaped in the text, not in the tokens
+        let err = from_str::<BTreeMap<String, u8>>(r#"{"a/b": 1, "c~d": -1}"#).unwrap_err();
+        assert_eq!(err.path().unwrap().tokens(), ["c~d"]);
+        assert!(err.to_string().starts_with("at /c~0d: "));
+        let window = r#"["Off", {"Window": {"size": 4, "step": "1"}}]"#;
+        let err = from_str::<Vec<Mode>>(window).unwrap_err();
+        assert_eq!(err.path(), Some(&path("/1/Window/step")));
+
+        let err = from_str::<u8>("256").unwrap_err();
+        assert_eq!(err.path(), Some(&Pointer::default()));
+        assert_eq!(err.to_string(), "invalid value: integer `256`, expected u8");
+        assert!(matches!(
+            from_str::<Vec<Mode>>(r#"["Off", {"Fixed": 1, "Off": null}]"#).unwrap_err(),
+            JsonError::Decode { path, .. } if path.to_string() == "/1"
+        ));
+        assert_eq!(from_str::<u8>("[1,").unwrap_err().path(), None);
+        assert!(matches!(
+            from_str::<u8>("[1,").unwrap_err(),
+            JsonError::Parse { offset: 3, .. }
+        ));
+    }
+}
@@ -0,0 +1,160 @@
+use super::{into_std_string, json_string, JsonError, JsonValue, Num};
+
+/// Lossless: integers keep their type, and `u64`s past `i64::MAX` become
+/// [`Num::Raw`] with their digits.
+impl From<serde_json::Value> for JsonValue {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => JsonValue::Null,
+            serde_json::Value::Bool(b) => JsonValue::Bool(b),
+            serde_json::Value::Number(n) => JsonValue::Number(if let Some(i) = n.as_i64() {
+                Num::Int(i)
+            } else if let Some(u) = n.as_u64() {
+                Num::Raw(u.to_string())
+            } else {
+                Num::Float(n.as_f64().expect("a number is an i64, a u64 or an f64"))
+            }),
+            serde_json::Value::String(s) => JsonValue::String(json_string(s)),
+            serde_json::Value::Array(arr) => {
+                JsonValue::Array(arr.into_iter().map(JsonValue::from).collect())
+            }
+            serde_json::Value::Object(obj) => JsonValue::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (json_string(k), JsonValue::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Fails where `serde_json` has no equivalent: NaN and infinite floats, raw
+/// integers wider than 64 bits and raw numbers out of `f64` range, with the
+/// pointer of the first one in [`JsonError::Serialize`]. There is no `From`,
+/// which would rule this out.
+impl TryFrom<JsonValue> for serde_json::Value {
+    type Error = JsonError;
+
+    fn try_from(value: JsonValue) -> Result<Self, JsonError> {
+        Ok(match value {
+            JsonValue::Null => serde_json::Value::Null,
+            JsonValue::Bool(b) => serde_json::Value::Bool(b),
+            JsonValue::Number(n) => serde_json::Value::Number(number(n)?),
+            JsonValue::String(s) => serde_json::Value::String(into_std_string(s)),
+            JsonValue::Array(arr) => serde_json::Value::Array(
+                arr.into_iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        serde_json::Value::try_from(v).map_err(|e| e.within(&i.to_string()))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            JsonValue::Object(obj) => serde_json::Value::Object(
+                obj.into_iter()
+                    .map(|(k, v)| {
+                        let k = into_std_string(k);
+                        match serde_json::Value::try_from(v) {
+                            Ok(v) => Ok((k, v)),
+                            Err(e) => Err(e.within(&k)),
+                        }
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            JsonValue::Raw(raw) => serde_json::Value::try_from(raw.parse()?)?,
+        })
+    }
+}
+
+fn number(n: Num) -> Result<serde_json::Number, JsonError> {
+    let float = |f: f64| {
+        serde_json::Number::from_f64(f)
+            .ok_or_else(|| JsonError::serialize(format!("{} is not a valid JSON number", f)))
+    };
+    match n {
+        Num::Int(i) => Ok(i.into()),
+        Num::Float(f) => float(f),
+        Num::Raw(text) => {
+            if let Ok(i) = text.parse::<i64>() {
+                Ok(i.into())
+            } else if let Ok(u) = text.parse::<u64>() {
+                Ok(u.into())
+            } else if !text.contains(['.', 'e', 'E']) {
+                Err(JsonError::serialize(format!(
+                    "{} does not fit in 64 bits",
+                    text
+                )))
+            } else {
+                match text.parse::<f64>() {
+                    Ok(f) if f.is_finite() => float(f),
+                    _ => Err(JsonError::serialize(format!(
+                        "{} is out of range for f64",
+                        text
+                    ))),
+                }
+            }
+        }
+        #[cfg(feature = "decimal")]
+        Num::Decimal(d) => float(d.to_f64()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, patch::same_value};
+    use serde_json::json;
+
+    #[test]
+    fn values_should_convert_both_ways_without_loss() {
+        let corpus = [
+            json!(null),
+            json!([true, false, 0, -1, i64::MIN, i64::MAX, 0.5, -1e300]),
+            json!({"a": {"b": ["", "Zoë\n", {"c": null}]}, "d": []}),
+        ];
+        for value in corpus {
+            let ours = JsonValue::from(value.clone());
+            assert_eq!(serde_json::Value::try_from(ours.clone()).unwrap(), value);
+            assert!(same_value(&ours, &parse_json(&value.to_string()).unwrap()));
+        }
+        let big = JsonValue::from(json!(u64::MAX));
+        assert_eq!(
+            big,
+            JsonValue::Number(Num::Raw("18446744073709551615".into()))
+        );
+        assert_eq!(
+            serde_json::Value::try_from(big).unwrap().as_u64(),
+            Some(u64::MAX)
+        );
+
+        let mut deep = json!(1);
+        for i in 0..200 {
+            deep = if i % 2 == 0 {
+                json!([deep])
+            } else {
+                json!({"k": deep})
+            };
+        }
+        let ours = JsonValue::from(deep.clone());
+        assert_eq!(serde_json::Value::try_from(ours).unwrap(), deep);
+    }
+
+    #[test]
+    fn try_from_should_reject_what_serde_json_cannot_hold() {
+        let value = parse_json(r#"{"a": [1, 2]}"#).unwrap();
+        let mut nan = value.clone();
+        *nan.pointer_mut("/a/1").unwrap() = JsonValue::from(f64::NAN);
+        assert_eq!(
+            serde_json::Value::try_from(nan).unwrap_err(),
+            JsonError::Serialize {
+                pointer: "/a/1".into(),
+                message: "NaN is not a valid JSON number".into(),
+            }
+        );
+        let wide = JsonValue::Number(Num::Raw("123456789012345678901234567890".into()));
+        assert_eq!(
+            serde_json::Value::try_from(wide).unwrap_err().to_string(),
+            "cannot serialize value at \"\": 123456789012345678901234567890 does not fit in 64 bits"
+        );
+        let raw = JsonValue::Number(Num::Raw("1.50".into()));
+        assert_eq!(serde_json::Value::try_from(raw).unwrap(), json!(1.5));
+    }
+}
@@ -0,0 +1,804 @@
+use super::{
+    base64::{self, STD_ALPHABET},
+    json_string, FormatOptions, JsonError, JsonObject, JsonValue, NonFiniteBehavior, Num,
+};
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt::Display;
+
+/// What [`to_value_with`] does with what JSON has no place for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToValueOptions {
+    pub bytes: BytesFormat,
+    /// NaN and infinite floats: an error, `null`, or kept as floats under
+    /// [`NonFiniteBehavior::Literal`] for the writer to spell.
+    pub non_finite: NonFiniteBehavior,
+}
+
+/// How byte strings become JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BytesFormat {
+    /// An array of numbers from 0 to 255.
+    #[default]
+    Array,
+    /// A string in padded standard base64.
+    Base64,
+}
+
+impl ToValueOptions {
+    pub fn bytes(mut self, format: BytesFormat) -> Self {
+        self.bytes = format;
+        self
+    }
+
+    pub fn non_finite(mut self, behavior: NonFiniteBehavior) -> Self {
+        self.non_finite = behavior;
+        self
+    }
+}
+
+/// Turn anything serde can serialize into a [`JsonValue`], with the default
+/// [`ToValueOptions`].
+///
+/// Enums come out externally tagged, as `"Unit"` or `{"Variant": ...}`, and
+/// integers beyond `i64` as [`Num::Raw`]. Map keys must be strings; chars,
+/// booleans, integers and unit variants are accepted too and written as the
+/// string they display as, and any other key fails with
+/// [`JsonError::Serialize`].
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<JsonValue, JsonError> {
+    to_value_with(value, &ToValueOptions::default())
+}
+
+pub fn to_value_with<T: Serialize + ?Sized>(
+    value: &T,
+    options: &ToValueOptions,
+) -> Result<JsonValue, JsonError> {
+    value.serialize(Serializer { options: *options })
+}
+
+/// Serialize `value` to compact JSON text by way of [`to_value`].
+pub fn to_string_via_serde<T: Serialize + ?Sized>(value: &T) -> Result<String, JsonError> {
+    to_value(value)?.try_to_string_with(&FormatOptions::default())
+}
+
+impl ser::Error for JsonError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JsonError::serialize(msg.to_string())
+    }
+}
+
+/// The serde `Serializer` behind [`to_value_with`].
+#[derive(Clone, Copy)]
+struct Serializer {
+    options: ToValueOptions,
+}
+
+fn int(i: i128) -> JsonValue {
+    match i64::try_from(i) {
+        Ok(i) => JsonValue::Number(Num::Int(i)),
+        Err(_) => JsonValue::Number(Num::Raw(i.to_string())),
+    }
+}
+
+/// `{"variant": value}`, the external tagging of a non-unit variant.
+fn tagged(variant: &str, value: JsonValue) -> JsonValue {
+    let mut obj = JsonObject::default();
+    obj.insert(json_string(variant), value);
+    JsonValue::Object(obj)
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = JsonValue;
+    type Error = JsonError;
+    type SerializeSeq = Elements;
+    type SerializeTuple = Elements;
+    type SerializeTupleStruct = Elements;
+    type SerializeTupleVariant = Elements;
+    type SerializeMap = Members;
+    type SerializeStruct = Members;
+    type SerializeStructVariant = Members;
+
+    fn serialize_bool(self, v: bool) -> Result<JsonValue, JsonError> {
+        Ok(JsonValue::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<JsonValue, JsonError> {
+        Ok(int(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<JsonValue, JsonError> {
+        Ok(int(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<JsonValue, JsonError> {
+        Ok(int(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<JsonValue, JsonError> {
+        Ok(int(v.into()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<JsonValue, JsonError> {
+        Ok(int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<JsonValue, JsonError> {
+        Ok(int(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<JsonValue, JsonError> {
+        Ok(int(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<JsonValue, JsonError> {
+        Ok(int(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<JsonValue, JsonError> {
+        Ok(int(v.into()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<JsonValue, JsonError> {
+        Ok(match i128::try_from(v) {
+            Ok(i) => int(i),
+            Err(_) => JsonValue::Number(Num::Raw(v.to_string())),
+        })
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<JsonValue, JsonError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<JsonValue, JsonError> {
+        if v.is_finite() {
+            return Ok(JsonValue::Number(Num::Float(v)));
+        }
+        match self.options.non_finite {
+            NonFiniteBehavior::Error => Err(JsonError::serialize(format!(
+                "{} is not a valid JSON number",
+                v
+            ))),
+            NonFiniteBehavior::Null => Ok(JsonValue::Null),
+            NonFiniteBehavior::Literal => Ok(JsonValue::Number(Num::Float(v))),
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<JsonValue, JsonError> {
+        Ok(JsonValue::String(json_string(v)))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<JsonValue, JsonError> {
+        Ok(JsonValue::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<JsonValue, JsonError> {
+        Ok(match self.options.bytes {
+            BytesFormat::Array => JsonValue::Array(v.iter().map(|&b| int(b.into())).collect()),
+            BytesFormat::Base64 => JsonValue::from(base64::encode(v, STD_ALPHABET, true)),
+        })
+    }
+
+    fn serialize_none(self) -> Result<JsonValue, JsonError> {
+        Ok(JsonValue::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<JsonValue, JsonError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<JsonValue, JsonError> {
+        Ok(JsonValue::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<JsonValue, JsonError> {
+        Ok(JsonValue::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<JsonValue, JsonError> {
+        Ok(JsonValue::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<JsonValue, JsonError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<JsonValue, JsonError> {
+        let value = value.serialize(self).map_err(|e| e.within(variant))?;
+        Ok(tagged(variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Elements, JsonError> {
+        Ok(Elements {
+            serializer: self,
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Elements, JsonError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Elements, JsonError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Elements, JsonError> {
+        let mut elements = self.serialize_seq(Some(len))?;
+        elements.variant = Some(variant);
+        Ok(elements)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Members, JsonError> {
+        Ok(Members {
+            serializer: self,
+            members: JsonObject::default(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Members, JsonError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Members, JsonError> {
+        let mut members = self.serialize_map(Some(len))?;
+        members.variant = Some(variant);
+        Ok(members)
+    }
+}
+
+/// Point an error below a variant's tag as well, if there is one.
+fn within_variant(e: JsonError, variant: Option<&str>) -> JsonError {
+    match variant {
+        Some(variant) => e.within(variant),
+        None => e,
+    }
+}
+
+/// A sequence, tuple or tuple variant being built.
+struct Elements {
+    serializer: Serializer,
+    elements: Vec<JsonValue>,
+    /// Set for a tuple variant, which is wrapped in its tag at the end.
+    variant: Option<&'static str>,
+}
+
+impl Elements {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        let value = value.serialize(self.serializer).map_err(|e| {
+            within_variant(e.within(&self.elements.len().to_string()), self.variant)
+        })?;
+        self.elements.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<JsonValue, JsonError> {
+        let array = JsonValue::Array(self.elements);
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, array),
+            None => array,
+        })
+    }
+}
+
+impl ser::SerializeSeq for Elements {
+    type Ok = JsonValue;
+    type Error = JsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Elements {
+    type Ok = JsonValue;
+    type Error = JsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Elements {
+    type Ok = JsonValue;
+    type Error = JsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Elements {
+    type Ok = JsonValue;
+    type Error = JsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonError> {
+        self.finish()
+    }
+}
+
+/// A map, struct or struct variant being built.
+struct Members {
+    serializer: Serializer,
+    members: JsonObject,
+    /// The key given to `serialize_key`, waiting for its value.
+    key: Option<String>,
+    /// Set for a struct variant, which is wrapped in its tag at the end.
+    variant: Option<&'static str>,
+}
+
+impl Members {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), JsonError> {
+        let value = value
+            .serialize(self.serializer)
+            .map_err(|e| within_variant(e.within(&key), self.variant))?;
+        self.members.insert(json_string(key), value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<JsonValue, JsonError> {
+        let object = JsonValue::Object(self.members);
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, object),
+            None => object,
+        })
+    }
+}
+
+impl ser::SerializeMap for Members {
+    type Ok = JsonValue;
+    type Error = JsonError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), JsonError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| JsonError::serialize("map value serialized before its key"))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Members {
+    type Ok = JsonValue;
+    type Error = JsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Members {
+    type Ok = JsonValue;
+    type Error = JsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<JsonValue, JsonError> {
+        self.finish()
+    }
+}
+
+/// Turns a map key into the string it is stored under.
+struct KeySerializer;
+
+fn bad_key(what: &str) -> JsonError {
+    JsonError::serialize(format!("map keys must be strings, not {}", what))
+}
+
+macro_rules! display_keys {
+    ($($method:ident: $t:ty)*) => {$(
+        fn $method(self, v: $t) -> Result<String, JsonError> {
+            Ok(v.to_string())
+        }
+    )*};
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = JsonError;
+    type SerializeSeq = Impossible<String, JsonError>;
+    type SerializeTuple = Impossible<String, JsonError>;
+    type SerializeTupleStruct = Impossible<String, JsonError>;
+    type SerializeTupleVariant = Impossible<String, JsonError>;
+    type SerializeMap = Impossible<String, JsonError>;
+    type SerializeStruct = Impossible<String, JsonError>;
+    type SerializeStructVariant = Impossible<String, JsonError>;
+
+    display_keys! {
+        serialize_bool: bool serialize_char: char serialize_str: &str
+        serialize_i8: i8 serialize_i16: i16 serialize_i32: i32 serialize_i64: i64
+        serialize_i128: i128 serialize_u8: u8 serialize_u16: u16 serialize_u32: u32
+        serialize_u64: u64 serialize_u128: u128
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String, JsonError> {
+        Err(bad_key("a float"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String, JsonError> {
+        Err(bad_key("a float"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, JsonError> {
+        Err(bad_key("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<String, JsonError> {
+        Err(bad_key("None"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, JsonError> {
+        Err(bad_key("an option"))
+    }
+
+    fn serialize_unit(self) -> Result<String, JsonError> {
+        Err(bad_key("a unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, JsonError> {
+        Err(bad_key("a unit struct"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, JsonError> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, JsonError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, JsonError> {
+        Err(bad_key("an enum variant with data"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, JsonError> {
+        Err(bad_key("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, JsonError> {
+        Err(bad_key("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, JsonError> {
+        Err(bad_key("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, JsonError> {
+        Err(bad_key("an enum variant with data"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, JsonError> {
+        Err(bad_key("a map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, JsonError> {
+        Err(bad_key("a struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, JsonError> {
+        Err(bad_key("an enum variant with data"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use serde::ser::{SerializeMap, SerializeStruct, SerializeStructVariant};
+    use std::collections::BTreeMap;
+
+    /// `struct Server { host: String, port: u16, tags: Vec<&str> }`, as
+    /// `#[derive(Serialize)]` writes it.
+    struct Server {
+        host: &'static str,
+        port: u16,
+        tags: Vec<&'static str>,
+    }
+
+    impl Serialize for Server {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut s = serializer.serialize_struct("Server", 3)?;
+            s.serialize_field("host", self.host)?;
+            s.serialize_field("port", &self.port)?;
+            s.serialize_field("tags", &self.tags)?;
+            s.end()
+        }
+    }
+
+    /// `enum Shape { Dot, Circle { r: f64 } }` in the four tagging styles,
+    /// each written the way the derive does it.
+    #[derive(Clone, Copy)]
+    enum Tagging {
+        External,
+        Internal,
+        Adjacent,
+        Untagged,
+    }
+
+    struct Shape(Tagging, Option<f64>);
+
+    impl Serialize for Shape {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match (&self.0, self.1) {
+                (Tagging::External, None) => serializer.serialize_unit_variant("Shape", 0, "Dot"),
+                (Tagging::External, Some(r)) => {
+                    let mut s = serializer.serialize_struct_variant("Shape", 1, "Circle", 1)?;
+                    s.serialize_field("r", &r)?;
+                    s.end()
+                }
+                // #[serde(tag = "type")]
+                (Tagging::Internal, r) => {
+                    let mut s = serializer.serialize_struct("Shape", 2)?;
+                    s.serialize_field("type", if r.is_some() { "Circle" } else { "Dot" })?;
+                    if let Some(r) = r {
+                        s.serialize_field("r", &r)?;
+                    }
+                    s.end()
+                }
+                // #[serde(tag = "t", content = "c")]
+                (Tagging::Adjacent, r) => {
+                    let mut s = serializer.serialize_struct("Shape", 2)?;
+                    s.serialize_field("t", if r.is_some() { "Circle" } else { "Dot" })?;
+                    if let Some(r) = r {
+                        s.serialize_field("c", &BTreeMap::from([("r", r)]))?;
+                    }
+                    s.end()
+                }
+                // #[serde(untagged)]
+                (Tagging::Untagged, None) => serializer.serialize_unit(),
+                (Tagging::Untagged, Some(r)) => {
+                    let mut s = serializer.serialize_struct("Circle", 1)?;
+                    s.serialize_field("r", &r)?;
+                    s.end()
+                }
+            }
+        }
+    }
+
+    fn json(text: &str) -> JsonValue {
+        parse_json(text).unwrap()
+    }
+
+    #[test]
+    fn to_value_should_build_structs_enums_and_collections() {
+        let server = Server {
+            host: "a",
+            port: 80,
+            tags: vec!["x", "y"],
+        };
+        assert_eq!(
+            to_value(&server).unwrap(),
+            json(r#"{"host": "a", "port": 80, "tags": ["x", "y"]}"#)
+        );
+
+        for (tagging, dot, circle) in [
+            (Tagging::External, r#""Dot""#, r#"{"Circle": {"r": 1.5}}"#),
+            (
+                Tagging::Internal,
+                r#"{"type": "Dot"}"#,
+                r#"{"type": "Circle", "r": 1.5}"#,
+            ),
+            (
+                Tagging::Adjacent,
+                r#"{"t": "Dot"}"#,
+                r#"{"t": "Circle", "c": {"r": 1.5}}"#,
+            ),
+            (Tagging::Untagged, "null", r#"{"r": 1.5}"#),
+        ] {
+            assert_eq!(to_value(&Shape(tagging, None)).unwrap(), json(dot));
+            assert_eq!(to_value(&Shape(tagging, Some(1.5))).unwrap(), json(circle));
+        }
+
+        let nested: BTreeMap<&str, Vec<Option<(u8, char)>>> =
+            BTreeMap::from([("a", vec![Some((1, 'x')), None]), ("b", vec![])]);
+        assert_eq!(
+            to_value(&nested).unwrap(),
+            json(r#"{"a": [[1, "x"], null], "b": []}"#)
+        );
+        assert_eq!(
+            to_value(&Ok::<(i32, u64), ()>((-1, u64::MAX))).unwrap(),
+            tagged(
+                "Ok",
+                JsonValue::Array(vec![
+                    JsonValue::from(-1),
+                    JsonValue::Number(Num::Raw("18446744073709551615".into())),
+                ])
+            )
+        );
+        assert_eq!(to_string_via_serde(&[(1, "a")]).unwrap(), r#"[[1,"a"]]"#);
+    }
+
+    #[test]
+    fn to_value_should_stringify_simple_keys_and_reject_others() {
+        struct Keys(bool);
+        impl Serialize for Keys {
+            fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry(&'k', &1)?;
+                map.serialize_entry(&false, &2)?;
+                map.serialize_entry(&-3i8, &3)?;
+                map.serialize_entry(&u128::MAX, &4)?;
+                if self.0 {
+                    map.serialize_entry(&(), &5)?;
+                }
+                map.end()
+            }
+        }
+        let expected = r#"{"k": 1, "false": 2, "-3": 3,
+            "340282366920938463463374607431768211455": 4}"#;
+        assert_eq!(to_value(&Keys(false)).unwrap(), json(expected));
+        assert_eq!(
+            to_value(&Keys(true)).unwrap_err(),
+            JsonError::serialize("map keys must be strings, not a unit")
+        );
+
+        let floats = BTreeMap::from([("a", vec![BTreeMap::from([(FloatKey(1.5), 1)])])]);
+        assert_eq!(
+            to_value(&floats).unwrap_err().to_string(),
+            "cannot serialize value at \"/a/0\": map keys must be strings, not a float"
+        );
+    }
+
+    #[derive(PartialEq)]
+    struct FloatKey(f64);
+
+    impl PartialOrd for FloatKey {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Eq for FloatKey {}
+
+    impl Ord for FloatKey {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.0.total_cmp(&other.0)
+        }
+    }
+
+    impl Serialize for FloatKey {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_f64(self.0)
+        }
+    }
+
+    #[test]
+    fn to_value_with_should_follow_the_bytes_and_non_finite_options() {
+        struct Bytes(&'static [u8]);
+        impl Serialize for Bytes {
+            fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+        assert_eq!(to_value(&Bytes(b"fo")).unwrap(), json("[102, 111]"));
+        let base64 = ToValueOptions::default().bytes(BytesFormat::Base64);
+        assert_eq!(
+            to_value_with(&Bytes(b"fo"), &base64).unwrap(),
+            JsonValue::from("Zm8=")
+        );
+
+        let floats = vec![1.0, f64::NAN];
+        assert_eq!(
+            to_value(&floats).unwrap_err(),
+            JsonError::Serialize {
+                pointer: "/1".into(),
+                message: "NaN is not a valid JSON number".into(),
+            }
+        );
+        assert!(matches!(
+            to_value(&Shape(Tagging::External, Some(f64::NAN))).unwrap_err(),
+            JsonError::Serialize { pointer, .. } if pointer == "/Circle/r"
+        ));
+        let null = ToValueOptions::default().non_finite(NonFiniteBehavior::Null);
+        assert_eq!(to_value_with(&floats, &null).unwrap(), json("[1.0, null]"));
+        let literal = ToValueOptions::default().non_finite(NonFiniteBehavior::Literal);
+        let kept = to_value_with(&f64::INFINITY, &literal).unwrap();
+        assert_eq!(kept, JsonValue::from(f64::INFINITY));
+    }
+}
@@ -0,0 +1,230 @@
+use super::{json_string, JsonObject, JsonValue, Num};
+use serde::{
+    de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor},
+    ser::{Serialize, SerializeMap, SerializeSeq, Serializer},
+};
+use std::fmt;
+
+/// Variants map onto the serde data model one to one: `Int` as `i64`,
+/// `Float` as `f64` and objects as maps. Raw numbers go out as `i64`, `u64`
+/// or `f64`, the first that holds their text, and raw values as the value
+/// they spell.
+impl Serialize for JsonValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Bool(b) => serializer.serialize_bool(*b),
+            JsonValue::Number(Num::Int(i)) => serializer.serialize_i64(*i),
+            JsonValue::Number(Num::Float(f)) => serializer.serialize_f64(*f),
+            JsonValue::Number(Num::Raw(text)) => {
+                if let Ok(i) = text.parse::<i64>() {
+                    serializer.serialize_i64(i)
+                } else if let Ok(u) = text.parse::<u64>() {
+                    serializer.serialize_u64(u)
+                } else {
+                    // valid number tokens always parse as f64
+                    serializer.serialize_f64(text.parse().unwrap_or(f64::NAN))
+                }
+            }
+            #[cfg(feature = "decimal")]
+            JsonValue::Number(Num::Decimal(d)) => serializer.serialize_f64(d.to_f64()),
+            JsonValue::String(s) => serializer.serialize_str(s),
+            JsonValue::Array(arr) => {
+                let mut seq = serializer.serialize_seq(Some(arr.len()))?;
+                for v in arr {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            JsonValue::Object(obj) => {
+                let mut map = serializer.serialize_map(Some(obj.len()))?;
+                for (k, v) in obj {
+                    map.serialize_entry(k.as_str(), v)?;
+                }
+                map.end()
+            }
+            JsonValue::Raw(raw) => raw
+                .parse()
+                .map_err(serde::ser::Error::custom)?
+                .serialize(serializer),
+        }
+    }
+}
+
+/// Accepts anything a self-describing format produces. Integers that fit
+/// `i64` become [`Num::Int`]; wider ones keep their digits as [`Num::Raw`],
+/// so `u64::MAX` survives. Units and `None` are `null`, bytes an array of
+/// numbers, and map keys must be strings.
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Bool(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(Num::Int(i)))
+    }
+
+    fn visit_u64<E>(self, u: u64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(match i64::try_from(u) {
+            Ok(i) => Num::Int(i),
+            Err(_) => Num::Raw(u.to_string()),
+        }))
+    }
+
+    fn visit_i128<E>(self, i: i128) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(match i64::try_from(i) {
+            Ok(i) => Num::Int(i),
+            Err(_) => Num::Raw(i.to_string()),
+        }))
+    }
+
+    fn visit_u128<E>(self, u: u128) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(match i64::try_from(u) {
+            Ok(i) => Num::Int(i),
+            Err(_) => Num::Raw(u.to_string()),
+        }))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(Num::Float(f)))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(json_string(s)))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(json_string(s)))
+    }
+
+    fn visit_bytes<E>(self, bytes: &[u8]) -> Result<JsonValue, E> {
+        Ok(JsonValue::Array(
+            bytes
+                .iter()
+                .map(|&b| JsonValue::from(i64::from(b)))
+                .collect(),
+        ))
+    }
+
+    fn visit_unit<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        JsonValue::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<JsonValue, D::Error> {
+        JsonValue::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut arr = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(v) = seq.next_element()? {
+            arr.push(v);
+        }
+        Ok(JsonValue::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut obj = JsonObject::default();
+        while let Some(key) = map.next_key::<KeyString>()? {
+            obj.insert(json_string(key.0), map.next_value()?);
+        }
+        Ok(JsonValue::Object(obj))
+    }
+}
+
+/// A map key, which has to be a string.
+struct KeyString(String);
+
+impl<'de> Deserialize<'de> for KeyString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = KeyString;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string key")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<KeyString, E> {
+                Ok(KeyString(s.to_string()))
+            }
+
+            fn visit_string<E: de::Error>(self, s: String) -> Result<KeyString, E> {
+                Ok(KeyString(s))
+            }
+        }
+
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, patch::same_value};
+
+    const CORPUS: &str = r#"{
+        "null": null, "bools": [true, false], "int": -42, "big": 0,
+        "float": 2.5, "exp": 1e300, "text": "Zoë \"quoted\"\n",
+        "nested": {"a": [[], {}, [1, {"b": null}]]}
+    }"#;
+
+    /// The corpus plus a `u64` past `i64::MAX`, which the parser rejects.
+    fn corpus() -> JsonValue {
+        let mut value = parse_json(CORPUS).unwrap();
+        *value.pointer_mut("/big").unwrap() = JsonValue::Number(Num::Raw(u64::MAX.to_string()));
+        value
+    }
+
+    #[test]
+    fn values_should_round_trip_through_serde_json_text() {
+        let value = corpus();
+        let text = serde_json::to_string(&value).unwrap();
+        let back: JsonValue = serde_json::from_str(&text).unwrap();
+        assert!(same_value(&value, &back), "{:?}", back);
+        assert_eq!(
+            back.pointer("/big"),
+            Some(&JsonValue::Number(Num::Raw("18446744073709551615".into())))
+        );
+    }
+
+    #[test]
+    fn values_should_round_trip_through_the_serde_data_model() {
+        // serde_json::Value goes through Serialize and Deserialize without
+        // any text in between, as another self-describing format would
+        let value = corpus();
+        let other = serde_json::to_value(&value).unwrap();
+        assert_eq!(other["big"], serde_json::json!(u64::MAX));
+        assert_eq!(other["nested"]["a"][2][0], serde_json::json!(1));
+        let back: JsonValue = serde_json::from_value(other).unwrap();
+        assert!(same_value(&value, &back), "{:?}", back);
+
+        let err = serde_json::from_str::<JsonValue>("[1, ").unwrap_err();
+        assert!(err.is_eof(), "{}", err);
+    }
+}
@@ -0,0 +1,187 @@
+use super::{pointer::array_index, AsPointer, JsonString, JsonValue, Num, RawValue};
+use std::sync::Arc;
+
+/// A document whose arrays and objects are shared by its clones, so a
+/// clone costs a reference count however big the document is. Changing
+/// one, through [`pointer_make_mut`](Self::pointer_make_mut), copies only
+/// the containers on the path to the change, each a shallow copy whose
+/// other children stay shared, the way `Arc::make_mut` does for one value;
+/// a container no other clone holds is changed in place.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedJson {
+    Null,
+    Bool(bool),
+    Number(Num),
+    String(JsonString),
+    Array(Arc<Vec<SharedJson>>),
+    Object(Arc<SharedObject>),
+    Raw(RawValue),
+}
+
+/// The map behind [`SharedJson::Object`], keeping members as
+/// [`JsonObject`](super::JsonObject) does in this build.
+#[cfg(not(any(feature = "preserve-order", feature = "sorted-keys")))]
+pub type SharedObject = std::collections::HashMap<JsonString, SharedJson, super::ObjectHasher>;
+
+#[cfg(feature = "preserve-order")]
+pub type SharedObject = super::OrderedMap<SharedJson>;
+
+#[cfg(all(feature = "sorted-keys", not(feature = "preserve-order")))]
+pub type SharedObject = std::collections::BTreeMap<JsonString, SharedJson>;
+
+impl SharedJson {
+    /// The value at the JSON Pointer `ptr`, as [`JsonValue::pointer`]
+    /// finds it.
+    pub fn pointer<P: AsPointer + ?Sized>(&self, ptr: &P) -> Option<&SharedJson> {
+        ptr.tokens()?.try_fold(self, |value, token| match value {
+            SharedJson::Object(obj) => obj.get(token.as_ref()),
+            SharedJson::Array(arr) => arr.get(array_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// The value at `ptr`, to change in place: each container on the way
+    /// that is shared is copied first, so other clones keep seeing the
+    /// document as it was, while the siblings of the path stay shared by
+    /// both. Nothing is copied when `ptr` leads nowhere.
+    pub fn pointer_make_mut<P: AsPointer + ?Sized>(&mut self, ptr: &P) -> Option<&mut SharedJson> {
+        self.pointer(ptr)?;
+        ptr.tokens()?.try_fold(self, |value, token| match value {
+            SharedJson::Object(obj) => Arc::make_mut(obj).get_mut(token.as_ref()),
+            SharedJson::Array(arr) => Arc::make_mut(arr).get_mut(array_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// The elements of an array, to change, copied first if shared.
+    pub fn make_array_mut(&mut self) -> Option<&mut Vec<SharedJson>> {
+        match self {
+            SharedJson::Array(arr) => Some(Arc::make_mut(arr)),
+            _ => None,
+        }
+    }
+
+    /// The members of an object, to change, copied first if shared.
+    pub fn make_object_mut(&mut self) -> Option<&mut SharedObject> {
+        match self {
+            SharedJson::Object(obj) => Some(Arc::make_mut(obj)),
+            _ => None,
+        }
+    }
+
+    /// The document as a [`JsonValue`] of its own, sharing nothing.
+    pub fn to_value(&self) -> JsonValue {
+        match self {
+            SharedJson::Null => JsonValue::Null,
+            SharedJson::Bool(b) => JsonValue::Bool(*b),
+            SharedJson::Number(n) => JsonValue::Number(n.clone()),
+            SharedJson::String(s) => JsonValue::String(s.clone()),
+            SharedJson::Array(arr) => JsonValue::Array(arr.iter().map(Self::to_value).collect()),
+            SharedJson::Object(obj) => JsonValue::Object(
+                obj.iter()
+                    .map(|(key, value)| (key.clone(), value.to_value()))
+                    .collect(),
+            ),
+            SharedJson::Raw(raw) => JsonValue::Raw(raw.clone()),
+        }
+    }
+}
+
+impl From<JsonValue> for SharedJson {
+    fn from(value: JsonValue) -> Self {
+        match value {
+            JsonValue::Null => SharedJson::Null,
+            JsonValue::Bool(b) => SharedJson::Bool(b),
+            JsonValue::Number(n) => SharedJson::Number(n),
+            JsonValue::String(s) => SharedJson::String(s),
+            JsonValue::Array(arr) => {
+                SharedJson::Array(Arc::new(arr.into_iter().map(Self::from).collect()))
+            }
+            JsonValue::Object(obj) => SharedJson::Object(Arc::new(
+                obj.into_iter()
+                    .map(|(key, value)| (key, Self::from(value)))
+                    .collect(),
+            )),
+            JsonValue::Raw(raw) => SharedJson::Raw(raw),
+        }
+    }
+}
+
+impl From<&SharedJson> for JsonValue {
+    fn from(value: &SharedJson) -> Self {
+        value.to_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const DOC: &str = r#"{"a": {"b": {"c": 1, "d": [true]}, "x": [1, 2, 3]}, "y": {"z": null}}"#;
+
+    /// The allocation of a container, to tell shared ones apart.
+    fn allocation(value: &SharedJson) -> *const () {
+        match value {
+            SharedJson::Array(arr) => Arc::as_ptr(arr).cast(),
+            SharedJson::Object(obj) => Arc::as_ptr(obj).cast(),
+            _ => panic!("not a container: {:?}", value),
+        }
+    }
+
+    #[test]
+    fn pointer_make_mut_should_copy_only_the_path() {
+        let original = SharedJson::from(parse_json(DOC).unwrap());
+        let mut copy = original.clone();
+        *copy.pointer_make_mut("/a/b/c").unwrap() = SharedJson::from(JsonValue::from(2));
+
+        assert_eq!(original.to_value(), parse_json(DOC).unwrap());
+        assert_eq!(
+            copy.pointer("/a/b/c"),
+            Some(&SharedJson::Number(Num::Int(2)))
+        );
+        let at = |doc: &SharedJson, ptr: &str| allocation(doc.pointer(ptr).unwrap());
+        // the ancestors of the change are copies
+        for ptr in ["", "/a", "/a/b"] {
+            assert_ne!(at(&original, ptr), at(&copy, ptr), "{:?}", ptr);
+        }
+        // everything beside the path is still shared
+        for ptr in ["/a/b/d", "/a/x", "/y"] {
+            assert_eq!(at(&original, ptr), at(&copy, ptr), "{:?}", ptr);
+        }
+        let SharedJson::Object(y) = copy.pointer("/y").unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(Arc::strong_count(y), 2);
+    }
+
+    #[test]
+    fn pointer_make_mut_should_not_copy_what_no_one_shares() {
+        let mut only = SharedJson::from(parse_json(DOC).unwrap());
+        let before = ["", "/a", "/a/b", "/a/x"].map(|ptr| allocation(only.pointer(ptr).unwrap()));
+        only.pointer_make_mut("/a/x/1")
+            .map(|n| *n = SharedJson::Null)
+            .unwrap();
+        only.make_object_mut()
+            .unwrap()
+            .insert("w".into(), SharedJson::Bool(true));
+        let after = ["", "/a", "/a/b", "/a/x"].map(|ptr| allocation(only.pointer(ptr).unwrap()));
+        assert_eq!(before, after);
+        let mut expected = parse_json(DOC).unwrap();
+        *expected.pointer_mut("/a/x/1").unwrap() = JsonValue::Null;
+        expected.set_pointer("/w", JsonValue::Bool(true));
+        assert_eq!(JsonValue::from(&only), expected);
+    }
+
+    #[test]
+    fn pointer_make_mut_should_copy_nothing_for_a_missing_path() {
+        let original = SharedJson::from(parse_json(DOC).unwrap());
+        let mut copy = original.clone();
+        for ptr in ["/a/missing", "/a/x/3", "/a/b/c/deeper", "no slash"] {
+            assert_eq!(copy.pointer_make_mut(ptr), None, "{:?}", ptr);
+        }
+        assert_eq!(allocation(&original), allocation(&copy));
+        assert!(copy.pointer_make_mut("").is_some());
+        assert!(SharedJson::Null.make_array_mut().is_none());
+    }
+}
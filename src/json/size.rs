@@ -0,0 +1,68 @@
+use super::{JsonString, JsonValue, Num};
+use std::mem::size_of;
+
+impl JsonValue {
+    /// An estimate of the bytes `self` takes: the `JsonValue` itself, the
+    /// text of every string, key, raw number and [`JsonValue::Raw`], and for
+    /// each element its `JsonValue` and for each member that plus the
+    /// [`JsonString`] of its key. Spare capacity and the bookkeeping of the
+    /// map behind [`JsonObject`](super::JsonObject) are left out, and text
+    /// is counted in full even when `compact-str` keeps it inline, so it is
+    /// a measure to compare rather than what the allocator reports.
+    /// [`ParseOptions::max_total_allocated`](super::ParseOptions::max_total_allocated)
+    /// counts by the same rules.
+    pub fn deep_size_of(&self) -> usize {
+        VALUE_SIZE + text_size(self)
+    }
+}
+
+/// What [`JsonValue::deep_size_of`] counts for a value besides its text.
+pub(super) const VALUE_SIZE: usize = size_of::<JsonValue>();
+
+/// What [`JsonValue::deep_size_of`] counts for one more element of an
+/// array, or member of an object when `object` is set, besides its text.
+pub(super) fn entry_size(object: bool) -> usize {
+    VALUE_SIZE + if object { size_of::<JsonString>() } else { 0 }
+}
+
+/// The text `value` holds, inside it and down through its containers,
+/// with the slots of their entries.
+fn text_size(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::String(s) => s.len(),
+        JsonValue::Number(Num::Raw(s)) => s.len(),
+        JsonValue::Raw(raw) => raw.get().len(),
+        JsonValue::Array(arr) => arr.iter().map(|v| entry_size(false) + text_size(v)).sum(),
+        JsonValue::Object(obj) => obj
+            .iter()
+            .map(|(k, v)| entry_size(true) + k.len() + text_size(v))
+            .sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, parse_json_with, ParseOptions};
+
+    #[test]
+    fn deep_size_of_should_count_text_and_slots() {
+        let value = size_of::<JsonValue>();
+        let member = entry_size(true);
+        assert_eq!(parse_json("null").unwrap().deep_size_of(), value);
+        assert_eq!(parse_json(r#""abc""#).unwrap().deep_size_of(), value + 3);
+        assert_eq!(parse_json("[1, 2]").unwrap().deep_size_of(), 3 * value);
+        assert_eq!(
+            parse_json(r#"{"ab": "c", "d": [null]}"#)
+                .unwrap()
+                .deep_size_of(),
+            value + (member + 2 + 1) + (member + 1 + value)
+        );
+        let raw = ParseOptions::strict().preserve_number_text(true);
+        assert_eq!(
+            parse_json_with("1.50", &raw).unwrap().deep_size_of(),
+            value + 4
+        );
+    }
+}
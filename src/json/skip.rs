@@ -0,0 +1,215 @@
+use super::{
+    allocate, allocate_here, counted_identifier, entries, error, open, parse_bool, parse_escape,
+    parse_nonfinite, parse_null, parse_num, plain_span, sep_with_ws, single_quote, size, span_end,
+    stateful, strict, ws, Input, JsonError, ParseOptions,
+};
+use winnow::{
+    combinator::{alt, opt, separated_pair},
+    prelude::*,
+    stream::Stream,
+};
+
+/// Consume one strict JSON value from the front of `input` without building
+/// it. The value is checked exactly as [`parse_json`](super::parse_json)
+/// checks it, escapes and number range included, and `input` is left where
+/// parsing would leave it: after the value, plus any whitespace following a
+/// closing bracket. Nothing is allocated. Error offsets are into `input` as
+/// passed in, which is left untouched on error.
+pub fn skip_value(input: &mut &str) -> Result<(), JsonError> {
+    let mut rest = strict(input);
+    skip.parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?;
+    *input = rest.input;
+    Ok(())
+}
+
+/// Check that `input` is strict JSON without building anything. Valid
+/// input and errors, offsets included, are exactly those of
+/// [`parse_json`](super::parse_json).
+pub fn validate(input: &str) -> Result<(), JsonError> {
+    validate_with(input, &ParseOptions::default())
+}
+
+/// [`validate`] with the leniency switches of
+/// [`parse_json_with`](super::parse_json_with).
+pub fn validate_with(input: &str, options: &ParseOptions) -> Result<(), JsonError> {
+    let mut rest = stateful(input, options);
+    let mut start = 0;
+    let mut end = 0;
+    (
+        ws,
+        |i: &mut Input<'_>| {
+            start = input.len() - i.input.len();
+            allocate_here(i, size::VALUE_SIZE)?;
+            skip(i)?;
+            end = input.len() - i.input.len();
+            Ok(())
+        },
+        ws,
+    )
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?;
+    if options.require_container && !input[start..].starts_with(['[', '{']) {
+        return Err(error::scalar_root(input, start..end));
+    }
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    Ok(())
+}
+
+/// Whether [`validate`] accepts `input`.
+pub fn is_valid_json(input: &str) -> bool {
+    validate(input).is_ok()
+}
+
+/// [`parse_value`](super::parse_value) without the value. Containers go
+/// through the same `entries` loop; a `Vec<()>` never allocates.
+pub(super) fn skip(input: &mut Input<'_>) -> PResult<()> {
+    alt((
+        parse_null,
+        parse_bool.void(),
+        parse_nonfinite.void(),
+        parse_num.void(),
+        skip_string,
+        skip_array,
+        skip_object,
+    ))
+    .parse_next(input)
+}
+
+/// [`parse_string`](super::parse_string) without the string.
+/// Its text is counted against `max_total_allocated` all the same.
+pub(super) fn skip_string(input: &mut Input<'_>) -> PResult<()> {
+    let start = input.checkpoint();
+    let quote = alt(('"', single_quote)).parse_next(input)?;
+    loop {
+        let span = plain_span(input, quote);
+        allocate(input, &start, span.len())?;
+        if !span_end(input)? {
+            return Ok(());
+        }
+        let continued = input.state.options.allow_line_continuations
+            && opt(alt(("\r\n", "\n", "\r"))).parse_next(input)?.is_some();
+        if !continued {
+            let c = parse_escape(input, quote)?;
+            allocate(input, &start, c.len_utf8())?;
+        }
+    }
+}
+
+fn skip_key(input: &mut Input<'_>) -> PResult<()> {
+    if input.state.options.allow_unquoted_keys {
+        alt((skip_string, counted_identifier.void())).parse_next(input)
+    } else {
+        skip_string(input)
+    }
+}
+
+fn skip_array(input: &mut Input<'_>) -> PResult<()> {
+    open('[').parse_next(input)?;
+    entries(input, ']', skip).map(|_| ())
+}
+
+fn skip_object(input: &mut Input<'_>) -> PResult<()> {
+    open('{').parse_next(input)?;
+    let entry = separated_pair(skip_key, sep_with_ws(':'), skip);
+    entries(input, '}', entry).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_value, ParseOptions};
+
+    const CORPUS: &[&str] = &[
+        "null",
+        "true,",
+        "false ]",
+        "-12.5e3 ",
+        "0",
+        r#""plain""#,
+        r#""esc \" \\ \/ \n é 😀" tail"#,
+        "[]",
+        "[1, [2, [3]], {}] \n next",
+        r#"{"a": {"b": [null, true]}, "c": "d"}  ,"#,
+        "[1 2]",
+        "[1,]",
+        r#"{"a" 1}"#,
+        r#""\q""#,
+        r#""\ud83d""#,
+        "99999999999999999999",
+        "1e400",
+        "NaN",
+        "'single'",
+        "[\"open",
+        "tru",
+        "",
+    ];
+
+    #[test]
+    fn skip_value_should_stop_where_parsing_stops() {
+        for text in CORPUS {
+            let mut parsed = strict(text);
+            let expected = parse_value
+                .parse_next(&mut parsed)
+                .map(|_| parsed.input)
+                .map_err(|e| JsonError::from_parser(text, parsed.input, e));
+            let mut skipped = *text;
+            let result = skip_value(&mut skipped).map(|()| skipped);
+            assert_eq!(result, expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn validate_should_agree_with_parse_json() {
+        let jsonc = ParseOptions::strict()
+            .allow_comments(true)
+            .allow_trailing_commas(true);
+        let extra = [
+            " [1, 2] ",
+            "[1] 2",
+            "\t{}\n",
+            "{\"a\": [1, {\"b\": null}], \"a\": 2}",
+            "[1, // one\n 2,] /* end */",
+            "/* open",
+            "{a: 1}",
+            "[01]",
+        ];
+        for text in CORPUS.iter().chain(&extra) {
+            assert_eq!(
+                validate(text),
+                crate::json::parse_json(text).map(|_| ()),
+                "{:?}",
+                text
+            );
+            assert_eq!(is_valid_json(text), validate(text).is_ok());
+            for options in [jsonc.clone(), ParseOptions::json5()] {
+                assert_eq!(
+                    validate_with(text, &options),
+                    crate::json::parse_json_with(text, &options).map(|_| ()),
+                    "{:?} with {:?}",
+                    text,
+                    options
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn skip_should_follow_parse_options() {
+        let options = ParseOptions::json5();
+        for text in [
+            "{a: 'x', /* c */ b: [1, 2,],} rest",
+            "'a\\\nb'",
+            "+.5",
+            "0x1F",
+        ] {
+            let mut parsed = stateful(text, &options);
+            let mut skipped = parsed.clone();
+            assert!(parse_value.parse_next(&mut parsed).is_ok(), "{}", text);
+            assert!(skip.parse_next(&mut skipped).is_ok(), "{}", text);
+            assert_eq!(skipped.input, parsed.input, "{}", text);
+        }
+    }
+}
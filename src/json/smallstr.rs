@@ -0,0 +1,252 @@
+use std::{
+    borrow::{Borrow, Cow},
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+/// Longest string kept inline, sized so that a `SmallString` is no bigger
+/// than a `String`.
+const INLINE: usize = 22;
+
+/// An immutable string that keeps up to 22 bytes inline and longer ones in
+/// a single heap allocation. Most object keys and many short values then
+/// cost no allocation at all. It derefs to `str`, and hashes, compares and
+/// borrows as one, so maps keyed by it are looked up with `&str`.
+/// [`JsonString`](super::JsonString) is this type under the `compact-str`
+/// feature.
+#[derive(Clone)]
+pub struct SmallString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, bytes: [u8; INLINE] },
+    Heap(Box<str>),
+}
+
+impl SmallString {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Inline { len, bytes } => {
+                // SAFETY: the inline bytes are always copied from a whole `str`
+                unsafe { std::str::from_utf8_unchecked(&bytes[..*len as usize]) }
+            }
+            Repr::Heap(s) => s,
+        }
+    }
+
+    /// Whether the string is stored inline, holding no allocation.
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+
+    fn inline(s: &str) -> Option<Self> {
+        let len = s.len();
+        if len > INLINE {
+            return None;
+        }
+        let mut bytes = [0; INLINE];
+        bytes[..len].copy_from_slice(s.as_bytes());
+        Some(SmallString(Repr::Inline {
+            len: len as u8,
+            bytes,
+        }))
+    }
+}
+
+impl Default for SmallString {
+    fn default() -> Self {
+        SmallString(Repr::Inline {
+            len: 0,
+            bytes: [0; INLINE],
+        })
+    }
+}
+
+impl Deref for SmallString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallString {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl Borrow<str> for SmallString {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl From<&str> for SmallString {
+    fn from(s: &str) -> Self {
+        Self::inline(s).unwrap_or_else(|| SmallString(Repr::Heap(s.into())))
+    }
+}
+
+impl From<&String> for SmallString {
+    fn from(s: &String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<String> for SmallString {
+    fn from(s: String) -> Self {
+        Self::inline(&s).unwrap_or_else(|| SmallString(Repr::Heap(s.into_boxed_str())))
+    }
+}
+
+impl From<Cow<'_, str>> for SmallString {
+    fn from(s: Cow<'_, str>) -> Self {
+        match s {
+            Cow::Borrowed(s) => s.into(),
+            Cow::Owned(s) => s.into(),
+        }
+    }
+}
+
+impl From<char> for SmallString {
+    fn from(c: char) -> Self {
+        (&*c.encode_utf8(&mut [0; 4])).into()
+    }
+}
+
+impl From<SmallString> for String {
+    fn from(s: SmallString) -> Self {
+        match s.0 {
+            Repr::Heap(s) => s.into(),
+            Repr::Inline { .. } => s.as_str().to_string(),
+        }
+    }
+}
+
+impl FromIterator<char> for SmallString {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        String::from_iter(iter).into()
+    }
+}
+
+impl Hash for SmallString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialEq for SmallString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallString {}
+
+impl PartialOrd for SmallString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialEq<str> for SmallString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for SmallString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for SmallString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<SmallString> for str {
+    fn eq(&self, other: &SmallString) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<SmallString> for &str {
+    fn eq(&self, other: &SmallString) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<SmallString> for String {
+    fn eq(&self, other: &SmallString) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl fmt::Debug for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn small_string_should_store_short_strings_inline() {
+        assert_eq!(
+            std::mem::size_of::<SmallString>(),
+            std::mem::size_of::<String>()
+        );
+        for s in ["", "id", "é水😀", "exactly_22_bytes_long_"] {
+            let small = SmallString::from(s);
+            assert!(small.is_inline(), "{}", s);
+            assert_eq!(small, s);
+            assert_eq!(String::from(small.clone()), s);
+        }
+        let long = "a string too long to be kept inline";
+        let small = SmallString::from(long.to_string());
+        assert!(!small.is_inline());
+        assert_eq!((small.as_str(), small.len()), (long, long.len()));
+        assert_eq!(
+            format!("{:?} {}", SmallString::from("a\n"), small),
+            format!("{:?} {}", "a\n", long)
+        );
+    }
+
+    #[test]
+    fn small_string_should_behave_as_str_in_maps() {
+        let map: HashMap<SmallString, i32> = [("b", 1), ("a longer key than inline", 2)]
+            .into_iter()
+            .map(|(k, v)| (k.into(), v))
+            .collect();
+        assert_eq!(map.get("b"), Some(&1));
+        assert_eq!(map.get("a longer key than inline"), Some(&2));
+        let mut keys: Vec<_> = map.into_keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["a longer key than inline", "b"]);
+    }
+}
@@ -0,0 +1,326 @@
+use super::{error, offset_to_line_col, JsonError};
+use std::{borrow::Cow, path::Path};
+
+/// Columns a tab advances to the next multiple of.
+const TAB_WIDTH: usize = 4;
+/// Columns of a source line shown before it is cut down around the caret.
+const MAX_WIDTH: usize = 80;
+/// Lines shown before the one with the error.
+const CONTEXT_LINES: usize = 2;
+
+impl JsonError {
+    /// The error as a compiler shows one: the message, where it is, and the
+    /// line it is on with a caret under the spot, after a couple of lines
+    /// before it.
+    ///
+    /// ```text
+    /// error: unexpected character '2'
+    ///  --> 2:6
+    ///   |
+    /// 1 | {"a": 1,
+    /// 2 |  "b" 2}
+    ///   |      ^
+    /// ```
+    ///
+    /// `input` is what was parsed; errors for lines and elements of it, and
+    /// for a file holding it, point into it too. Tabs are expanded to stops
+    /// every four columns, CJK and other wide characters take two columns
+    /// and combining marks none, and a line too long to show is cut to the
+    /// part around the caret, with `...` where it was cut. The column in
+    /// the location counts characters, as [`offset_to_line_col`] does.
+    /// Errors with no position are just `error: ` and the message.
+    pub fn display_with_source(&self, input: &str) -> String {
+        match self.source_position(input) {
+            Some((offset, message, path)) => render(input, offset, &message, path),
+            None => format!("error: {}", self),
+        }
+    }
+
+    /// The offset into `input` a parse error is at, its message, and the
+    /// file it is in.
+    fn source_position<'e>(
+        &'e self,
+        input: &str,
+    ) -> Option<(usize, Cow<'e, str>, Option<&'e Path>)> {
+        match self {
+            JsonError::Parse {
+                offset, message, ..
+            } => Some((*offset, Cow::Borrowed(message), None)),
+            JsonError::ScalarRoot { span, found } => Some((
+                span.start,
+                Cow::Owned(format!(
+                    "the document must be an object or array, not {}",
+                    found
+                )),
+                None,
+            )),
+            JsonError::TooManyEntries {
+                span,
+                limit,
+                object,
+            } => Some((
+                span.start,
+                Cow::Owned(error::too_many_message(*object, *limit)),
+                None,
+            )),
+            JsonError::PrecisionLoss { span, token } => Some((
+                span.start,
+                Cow::Owned(format!("{} cannot be held exactly", token)),
+                None,
+            )),
+            JsonError::Cancelled { offset } => {
+                Some((*offset, Cow::Borrowed("parsing was cancelled"), None))
+            }
+            JsonError::Element { source, .. } => source.source_position(input),
+            JsonError::Line { line, source } => {
+                let start = line_start(input, *line)?;
+                let (offset, message, path) = source.source_position(&input[start..])?;
+                Some((start + offset, message, path))
+            }
+            JsonError::File { path, source } => {
+                let (offset, message, _) = source.source_position(input)?;
+                Some((offset, message, Some(path)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The offset of 1-based line `line` of `input`, if it has that many.
+pub(super) fn line_start(input: &str, line: usize) -> Option<usize> {
+    match line.checked_sub(1)? {
+        0 => Some(0),
+        n => Some(input.match_indices('\n').nth(n - 1)?.0 + 1),
+    }
+}
+
+fn render(input: &str, offset: usize, message: &str, path: Option<&Path>) -> String {
+    let mut offset = offset.min(input.len());
+    while !input.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let (line, column) = offset_to_line_col(input, offset);
+    let start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let err_line = cells(source_line(&input[start..]));
+    let caret = err_line
+        .iter()
+        .find(|c| start + c.at >= offset)
+        .map_or_else(|| end_col(&err_line), |c| c.col);
+    // the columns shown, the caret among them
+    let from = if end_col(&err_line) <= MAX_WIDTH {
+        0
+    } else {
+        let from = caret.saturating_sub(MAX_WIDTH / 2);
+        let from = err_line.iter().map(|c| c.col).find(|&col| col >= from);
+        from.unwrap_or(caret)
+    };
+    let to = from + MAX_WIDTH;
+    let prefix = if from > 0 { "..." } else { "" };
+
+    let first = line.saturating_sub(CONTEXT_LINES).max(1);
+    let shown: Vec<(usize, &str)> = input
+        .split('\n')
+        .enumerate()
+        .map(|(i, text)| (i + 1, source_line(text)))
+        .skip(first - 1)
+        .take(line - first + 1)
+        .collect();
+    let gutter = " ".repeat(line.to_string().len());
+    let location = match path {
+        Some(path) => format!("{}:{}:{}", path.display(), line, column),
+        None => format!("{}:{}", line, column),
+    };
+    let mut out = format!(
+        "error: {}\n{}--> {}\n{} |",
+        message, gutter, location, gutter
+    );
+    for (number, text) in shown {
+        let cells = cells(text);
+        // a line over before the window has nothing in it to show
+        let text = match end_col(&cells) > from {
+            true => format!("{}{}", prefix, window(&cells, from, to)),
+            false => String::new(),
+        };
+        out.push_str(format!("\n{:>w$} | {}", number, text, w = gutter.len()).trim_end());
+    }
+    let under = prefix.len() + caret - from;
+    out.push_str(&format!("\n{} | {}^", gutter, " ".repeat(under)));
+    out
+}
+
+/// `text` up to the end of its line, without the line break.
+fn source_line(text: &str) -> &str {
+    let line = text.split('\n').next().unwrap_or_default();
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// A character of a line and where it is drawn.
+struct Cell {
+    /// Byte offset in the line.
+    at: usize,
+    col: usize,
+    width: usize,
+    c: char,
+}
+
+fn cells(line: &str) -> Vec<Cell> {
+    let mut col = 0;
+    line.char_indices()
+        .map(|(at, c)| {
+            let width = match c {
+                '\t' => TAB_WIDTH - col % TAB_WIDTH,
+                c => char_width(c),
+            };
+            let cell = Cell { at, col, width, c };
+            col += width;
+            cell
+        })
+        .collect()
+}
+
+fn end_col(cells: &[Cell]) -> usize {
+    cells.last().map_or(0, |c| c.col + c.width)
+}
+
+/// The characters drawn wholly within columns `from..to`, tabs as spaces,
+/// and `...` if the line goes on past them.
+fn window(cells: &[Cell], from: usize, to: usize) -> String {
+    let mut out = String::new();
+    for cell in cells.iter().filter(|c| c.col >= from) {
+        if cell.col + cell.width > to {
+            out.push_str("...");
+            break;
+        }
+        match cell.c {
+            '\t' => out.push_str(&" ".repeat(cell.width)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Columns a terminal gives `c`, near enough without the Unicode tables:
+/// two for the wide East Asian blocks and emoji, none for combining marks,
+/// zero-width characters and variation selectors, and one for the rest.
+fn char_width(c: char) -> usize {
+    match u32::from(c) {
+        0x0300..=0x036f | 0x200b..=0x200f | 0xfe00..=0xfe0f => 0,
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0x33ff
+        | 0x3400..=0x4dbf
+        | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, parse_ndjson};
+
+    fn shown(input: &str) -> String {
+        parse_json(input).unwrap_err().display_with_source(input)
+    }
+
+    #[test]
+    fn errors_should_point_at_the_start_of_a_line() {
+        assert_eq!(
+            shown("[1\n2]"),
+            "error: unexpected character '2'\n --> 2:1\n  |\n1 | [1\n2 | 2]\n  | ^"
+        );
+    }
+
+    #[test]
+    fn carets_should_line_up_after_multibyte_characters() {
+        assert_eq!(
+            shown("{\"é\": 1 \"x\": 2}"),
+            "error: unexpected character '\"'\n --> 1:9\n  |\n1 | {\"é\": 1 \"x\": 2}\n  |         ^"
+        );
+        // wide characters take two columns, the location counts characters
+        assert_eq!(
+            shown("[\"日本語\" 1]"),
+            "error: unexpected character '1'\n --> 1:8\n  |\n1 | [\"日本語\" 1]\n  |           ^"
+        );
+    }
+
+    #[test]
+    fn errors_on_the_last_line_should_show_the_lines_before() {
+        assert_eq!(
+            shown("[\n  1,\n  2\n  3]"),
+            "error: unexpected character '3'\n --> 4:3\n  |\n2 |   1,\n3 |   2\n4 |   3]\n  |   ^"
+        );
+        assert_eq!(
+            shown("[1,\r\n2"),
+            "error: unexpected end of input\n --> 2:2\n  |\n1 | [1,\n2 | 2\n  |  ^"
+        );
+        let input = format!("{}[", "\n".repeat(9));
+        assert_eq!(
+            parse_json(&input).unwrap_err().display_with_source(&input),
+            "error: unexpected end of input\n  --> 10:2\n   |\n 8 |\n 9 |\n10 | [\n   |  ^"
+        );
+    }
+
+    #[test]
+    fn tabs_should_expand_to_stops() {
+        assert_eq!(
+            shown("[1,\t\t2 3]"),
+            "error: unexpected character '3'\n --> 1:8\n  |\n1 | [1,     2 3]\n  |           ^"
+        );
+    }
+
+    #[test]
+    fn long_lines_should_be_cut_around_the_caret() {
+        let items: Vec<String> = (100..160).map(|i| i.to_string()).collect();
+        let input = format!("{{\"a\":\n[{} x, {}]}}", items.join(","), items.join(","));
+        let lines: Vec<String> = shown(&input).lines().map(String::from).collect();
+        assert_eq!(lines[1], " --> 2:242");
+        // the short line has nothing in the columns shown
+        assert_eq!(lines[3], "1 |");
+        let code = &lines[4];
+        let caret = &lines[5];
+        assert!(
+            code.starts_with("2 | ...") && code.ends_with("..."),
+            "{}",
+            code
+        );
+        assert_eq!(
+            code.chars().count(),
+            "2 | ...".len() + MAX_WIDTH + "...".len()
+        );
+        let under = caret.find('^').unwrap();
+        assert_eq!(&code[under..under + 1], "x");
+        assert!(under > MAX_WIDTH / 2 && under < MAX_WIDTH / 2 + "2 | ...".len() + 1);
+    }
+
+    #[test]
+    fn wrapped_errors_should_point_into_the_whole_input() {
+        let input = "{\"a\": 1}\n[1 2]\n";
+        let err = parse_ndjson(input).find_map(Result::err).unwrap();
+        assert_eq!(
+            err.display_with_source(input),
+            "error: unexpected character '2'\n --> 2:4\n  |\n1 | {\"a\": 1}\n2 | [1 2]\n  |    ^"
+        );
+        let file = JsonError::File {
+            path: "data.json".into(),
+            source: Box::new(parse_json("[1 2]").unwrap_err()),
+        };
+        assert!(file
+            .display_with_source("[1 2]")
+            .starts_with("error: unexpected character '2'\n --> data.json:1:4\n"));
+        let no_position = JsonError::serialize("NaN is not a valid JSON number");
+        assert_eq!(
+            no_position.display_with_source("[1]"),
+            format!("error: {}", no_position)
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use super::{
+    into_std_string, json_string, parse_cst, JsonError, JsonValue, Node, Num, ParseOptions,
+};
+use std::ops::Range;
+
+/// A value together with the byte range of the input it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Range<usize>,
+}
+
+/// A [`JsonValue`] whose children carry their spans. Object members keep the
+/// document order, and duplicates are kept too, so tools can point at each one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedValue {
+    Null,
+    Bool(bool),
+    Number(Num),
+    String(String),
+    Array(Vec<Spanned<SpannedValue>>),
+    Object(Vec<Member>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    /// The span covers the quotes of the key, but not the colon.
+    pub key: Spanned<String>,
+    pub value: Spanned<SpannedValue>,
+}
+
+/// Parse strict JSON like [`parse_json`](super::parse_json), recording the
+/// span of every value. Spans include the quotes of strings and the brackets
+/// of containers, and exclude surrounding whitespace.
+pub fn parse_json_spanned(input: &str) -> Result<Spanned<SpannedValue>, JsonError> {
+    let options = ParseOptions::strict();
+    let cst = parse_cst(input, &options)?;
+    Ok(lower(&cst.root, &options))
+}
+
+fn lower(node: &Node<'_>, options: &ParseOptions) -> Spanned<SpannedValue> {
+    let value = match node {
+        Node::Scalar(_) => match node.to_value(options) {
+            JsonValue::Null => SpannedValue::Null,
+            JsonValue::Bool(b) => SpannedValue::Bool(b),
+            JsonValue::Number(n) => SpannedValue::Number(n),
+            JsonValue::String(s) => SpannedValue::String(into_std_string(s)),
+            _ => unreachable!("scalar tokens lower to scalars"),
+        },
+        Node::Array(c) => {
+            SpannedValue::Array(c.entries.iter().map(|e| lower(&e.value, options)).collect())
+        }
+        Node::Object(c) => SpannedValue::Object(
+            c.entries
+                .iter()
+                .map(|e| {
+                    let key = e.key.as_ref().expect("object entries have keys");
+                    Member {
+                        key: Spanned {
+                            value: into_std_string(key.to_string(options)),
+                            span: key.token.span.clone(),
+                        },
+                        value: lower(&e.value, options),
+                    }
+                })
+                .collect(),
+        ),
+    };
+    Spanned {
+        value,
+        span: node.span(),
+    }
+}
+
+impl Spanned<SpannedValue> {
+    /// Drop the spans. Later duplicate keys win, as in `parse_json`.
+    pub fn into_value(self) -> JsonValue {
+        match self.value {
+            SpannedValue::Null => JsonValue::Null,
+            SpannedValue::Bool(b) => JsonValue::Bool(b),
+            SpannedValue::Number(n) => JsonValue::Number(n),
+            SpannedValue::String(s) => JsonValue::String(json_string(s)),
+            SpannedValue::Array(items) => {
+                JsonValue::Array(items.into_iter().map(Spanned::into_value).collect())
+            }
+            SpannedValue::Object(members) => JsonValue::Object(
+                members
+                    .into_iter()
+                    .map(|m| (json_string(m.key.value), m.value.into_value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// The 1-based line and column of byte `offset` in `input`. Columns count
+/// characters, not bytes. Offsets past the end map to the end of the input.
+pub fn offset_to_line_col(input: &str, offset: usize) -> (usize, usize) {
+    let mut end = offset.min(input.len());
+    while !input.is_char_boundary(end) {
+        end -= 1;
+    }
+    let before = &input[..end];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn spans_should_cover_whole_tokens() {
+        let input = " {\"a\": [1, \"x\"], \"b\" :{}}\n";
+        let root = parse_json_spanned(input).unwrap();
+        assert_eq!(root.span, 1..25);
+        let SpannedValue::Object(members) = &root.value else {
+            panic!("expected an object")
+        };
+        assert_eq!(members[0].key.span, 2..5);
+        assert_eq!(members[0].value.span, 7..15);
+        let SpannedValue::Array(items) = &members[0].value.value else {
+            panic!("expected an array")
+        };
+        assert_eq!(items[0].span, 8..9);
+        assert_eq!(items[1].span, 11..14);
+        assert_eq!(&input[items[1].span.clone()], "\"x\"");
+        assert_eq!(members[1].key.value, "b");
+        assert_eq!(members[1].key.span, 17..20);
+        assert_eq!(members[1].value.span, 22..24);
+        assert_eq!(root.into_value(), parse_json(input).unwrap());
+    }
+
+    #[test]
+    fn spans_should_nest() {
+        fn check(v: &Spanned<SpannedValue>) {
+            let children: Vec<_> = match &v.value {
+                SpannedValue::Array(items) => items.iter().map(|i| (None, i)).collect(),
+                SpannedValue::Object(members) => {
+                    members.iter().map(|m| (Some(&m.key), &m.value)).collect()
+                }
+                _ => return,
+            };
+            let mut last = v.span.start;
+            for (key, child) in children {
+                if let Some(key) = key {
+                    assert!(last < key.span.start && key.span.end < child.span.start);
+                }
+                assert!(last < child.span.start && child.span.end < v.span.end);
+                last = child.span.end;
+                check(child);
+            }
+        }
+        let input =
+            r#"{"servers": [{"host": "a", "port": 80}, {"host": "b", "port": [[]]}], "n": null}"#;
+        check(&parse_json_spanned(input).unwrap());
+    }
+
+    #[test]
+    fn offset_to_line_col_should_count_lines_and_chars() {
+        let input = "{\n  \"é\": 1,\n  \"port\": x\n}";
+        assert_eq!(offset_to_line_col(input, 0), (1, 1));
+        assert_eq!(offset_to_line_col(input, 2), (2, 1));
+        let x = input.find('x').unwrap();
+        assert_eq!(offset_to_line_col(input, x), (3, 11));
+        assert_eq!(
+            offset_to_line_col(input, input.find(": 1").unwrap()),
+            (2, 6)
+        );
+        assert_eq!(offset_to_line_col(input, 1000), (4, 2));
+    }
+}
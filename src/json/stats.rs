@@ -0,0 +1,283 @@
+use super::{
+    borrowed::string,
+    entries, into_std_string, open, parse_bool, parse_nonfinite, parse_null, parse_num,
+    reader::{read_events, ReadOptions},
+    sep_with_ws, strict, ws, Input, JsonError, JsonEvent,
+};
+use std::{collections::HashSet, io};
+use winnow::{combinator::alt, error::ErrMode, prelude::*, stream::Stream};
+
+/// Counts describing a document, from [`stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonStats {
+    pub nulls: usize,
+    pub bools: usize,
+    pub numbers: usize,
+    /// String values; keys are counted separately.
+    pub strings: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    /// Members of all objects, counting a repeated key each time it appears.
+    pub members: usize,
+    /// Elements of all arrays.
+    pub elements: usize,
+    /// Distinct keys across the whole document.
+    pub distinct_keys: usize,
+    /// Containers nested inside each other at the deepest point: 0 for a
+    /// scalar, 1 for `[]`.
+    pub max_depth: usize,
+    /// Bytes of the longest string value, unescaped.
+    pub longest_string: usize,
+    /// Bytes of all string values, unescaped.
+    pub string_bytes: usize,
+}
+
+impl JsonStats {
+    /// All values of every type, at any depth.
+    pub fn values(&self) -> usize {
+        self.nulls + self.bools + self.numbers + self.strings + self.arrays + self.objects
+    }
+}
+
+/// Count what a strict JSON document holds in one pass over it, without
+/// building it. Errors are those of [`parse_json`](super::parse_json).
+pub fn stats(input: &str) -> Result<JsonStats, JsonError> {
+    let mut walk = Walk {
+        stats: JsonStats::default(),
+        keys: HashSet::new(),
+    };
+    let mut rest = strict(input);
+    (ws, |i: &mut Input<'_>| walk.value(i, 0), ws)
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?;
+    if !rest.input.is_empty() {
+        return Err(JsonError::parse_at(input, rest.input).trailing());
+    }
+    walk.stats.distinct_keys = walk.keys.len();
+    Ok(walk.stats)
+}
+
+/// [`stats`] for a document read from `reader` through a fixed buffer, so
+/// that not even its text is held in memory whole. Errors are those of
+/// [`from_reader`](super::from_reader).
+pub fn stats_from_reader<R: io::Read>(reader: R) -> Result<JsonStats, JsonError> {
+    let mut walk = Walk {
+        stats: JsonStats::default(),
+        keys: HashSet::new(),
+    };
+    // the open containers, innermost last; `true` for arrays
+    let mut open = Vec::new();
+    read_events(reader, &ReadOptions::default(), |event| {
+        let value = !matches!(
+            event,
+            JsonEvent::Key(_) | JsonEvent::ArrayEnd | JsonEvent::ObjectEnd
+        );
+        if value && open.last() == Some(&true) {
+            walk.stats.elements += 1;
+        }
+        match event {
+            JsonEvent::ArrayStart | JsonEvent::ObjectStart => {
+                let array = event == JsonEvent::ArrayStart;
+                open.push(array);
+                walk.open(open.len());
+                if array {
+                    walk.stats.arrays += 1;
+                } else {
+                    walk.stats.objects += 1;
+                }
+            }
+            JsonEvent::ArrayEnd | JsonEvent::ObjectEnd => {
+                open.pop();
+            }
+            JsonEvent::Key(key) => {
+                walk.stats.members += 1;
+                if !walk.keys.contains(&*key) {
+                    walk.keys.insert(into_std_string(key));
+                }
+            }
+            JsonEvent::String(s) => walk.count(Scalar::String(s.len())),
+            JsonEvent::Number(_) => walk.count(Scalar::Number),
+            JsonEvent::Bool(_) => walk.count(Scalar::Bool),
+            JsonEvent::Null => walk.count(Scalar::Null),
+        }
+        Ok(())
+    })?;
+    walk.stats.distinct_keys = walk.keys.len();
+    Ok(walk.stats)
+}
+
+struct Walk {
+    stats: JsonStats,
+    keys: HashSet<String>,
+}
+
+/// What a scalar adds to the counts.
+#[derive(Clone, Copy)]
+enum Scalar {
+    Null,
+    Bool,
+    Number,
+    /// The unescaped length.
+    String(usize),
+}
+
+impl Walk {
+    /// Count one value inside `depth` containers. The alternatives are tried
+    /// by hand because the containers need `self`.
+    fn value(&mut self, input: &mut Input<'_>, depth: usize) -> PResult<()> {
+        let start = input.checkpoint();
+        let scalar = alt((
+            parse_null.value(Scalar::Null),
+            parse_bool.value(Scalar::Bool),
+            parse_nonfinite.value(Scalar::Number),
+            parse_num.value(Scalar::Number),
+            string.map(|s| Scalar::String(s.len())),
+        ))
+        .parse_next(input);
+        match scalar {
+            Err(ErrMode::Backtrack(_)) => input.reset(&start),
+            scalar => return scalar.map(|s| self.count(s)),
+        }
+        match self.array(input, depth + 1) {
+            Err(ErrMode::Backtrack(_)) => input.reset(&start),
+            arr => return arr,
+        }
+        self.object(input, depth + 1)
+    }
+
+    fn array(&mut self, input: &mut Input<'_>, depth: usize) -> PResult<()> {
+        open('[').parse_next(input)?;
+        self.open(depth);
+        self.stats.arrays += 1;
+        let elements = entries(input, ']', |i: &mut Input<'_>| self.value(i, depth))?;
+        self.stats.elements += elements.len();
+        Ok(())
+    }
+
+    fn object(&mut self, input: &mut Input<'_>, depth: usize) -> PResult<()> {
+        open('{').parse_next(input)?;
+        self.open(depth);
+        self.stats.objects += 1;
+        let members = entries(input, '}', |i: &mut Input<'_>| {
+            let key = string.parse_next(i)?;
+            if !self.keys.contains(&*key) {
+                self.keys.insert(key.into_owned());
+            }
+            sep_with_ws(':').parse_next(i)?;
+            self.value(i, depth)
+        })?;
+        self.stats.members += members.len();
+        Ok(())
+    }
+
+    fn count(&mut self, scalar: Scalar) {
+        let stats = &mut self.stats;
+        match scalar {
+            Scalar::Null => stats.nulls += 1,
+            Scalar::Bool => stats.bools += 1,
+            Scalar::Number => stats.numbers += 1,
+            Scalar::String(len) => {
+                stats.strings += 1;
+                stats.string_bytes += len;
+                stats.longest_string = stats.longest_string.max(len);
+            }
+        }
+    }
+
+    fn open(&mut self, depth: usize) {
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, JsonString, JsonValue};
+
+    /// The same counts taken from a parsed tree.
+    fn tree_stats(
+        value: &JsonValue,
+        depth: usize,
+        stats: &mut JsonStats,
+        keys: &mut Vec<JsonString>,
+    ) {
+        match value {
+            JsonValue::Null => stats.nulls += 1,
+            JsonValue::Bool(_) => stats.bools += 1,
+            JsonValue::Number(_) => stats.numbers += 1,
+            JsonValue::Raw(_) => unreachable!("parse_json keeps no raw text"),
+            JsonValue::String(s) => {
+                stats.strings += 1;
+                stats.string_bytes += s.len();
+                stats.longest_string = stats.longest_string.max(s.len());
+            }
+            JsonValue::Array(arr) => {
+                stats.arrays += 1;
+                stats.elements += arr.len();
+                stats.max_depth = stats.max_depth.max(depth + 1);
+                for v in arr {
+                    tree_stats(v, depth + 1, stats, keys);
+                }
+            }
+            JsonValue::Object(obj) => {
+                stats.objects += 1;
+                stats.members += obj.len();
+                stats.max_depth = stats.max_depth.max(depth + 1);
+                for (k, v) in obj {
+                    if !keys.contains(k) {
+                        keys.push(k.clone());
+                    }
+                    tree_stats(v, depth + 1, stats, keys);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stats_should_match_the_parsed_tree() {
+        let nested = format!("{}\"deep\"{}", "[{\"k\": ".repeat(60), "}]".repeat(60));
+        for input in [
+            r#"{"name": "Zoë", "age": 30, "marks": [90.5, -80, 1e3], "address": {"city": "New\nYork", "zip": null}, "ok": true}"#,
+            r#"[[], {}, [[]], [{"a": [""]}], "\u00e9\"", false]"#,
+            "\"only\"",
+            "0",
+            &nested,
+        ] {
+            let mut expected = JsonStats::default();
+            let mut keys = Vec::new();
+            tree_stats(&parse_json(input).unwrap(), 0, &mut expected, &mut keys);
+            expected.distinct_keys = keys.len();
+            assert_eq!(stats(input).unwrap(), expected, "{}", input);
+            assert_eq!(stats_from_reader(input.as_bytes()).unwrap(), expected);
+        }
+        let deep = stats(&nested).unwrap();
+        assert_eq!(
+            (deep.max_depth, deep.distinct_keys, deep.values()),
+            (120, 1, 121)
+        );
+    }
+
+    #[test]
+    fn stats_should_count_repeated_keys_and_fail_like_parse_json() {
+        let counted = stats(r#"{"a": "xy", "a": "xyz", "b": {"a": 1}}"#).unwrap();
+        assert_eq!(
+            (counted.members, counted.distinct_keys, counted.string_bytes),
+            (4, 2, 5)
+        );
+        assert_eq!(counted.longest_string, 3);
+        for input in ["[1,]", "{\"a\" 1}", "[\"\\q\"]", "[1] 2", ""] {
+            assert_eq!(
+                stats(input),
+                Err(parse_json(input).unwrap_err()),
+                "{}",
+                input
+            );
+            assert_eq!(
+                stats_from_reader(input.as_bytes()),
+                Err(crate::json::from_reader(input.as_bytes()).unwrap_err()),
+                "{}",
+                input
+            );
+        }
+    }
+}
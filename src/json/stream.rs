@@ -0,0 +1,495 @@
+use super::{
+    entry_end, parse_json, parse_value, sep_with_ws, strict, ws, ErrorCode, Input, JsonError,
+    JsonValue,
+};
+use std::{
+    io, iter,
+    ops::{ControlFlow, Range},
+};
+use winnow::{
+    combinator::{cut_err, opt},
+    PResult, Parser,
+};
+
+/// Parse newline-delimited JSON (NDJSON, JSON Lines), yielding one result
+/// per non-blank line. A broken line yields an error carrying its line
+/// number and iteration carries on with the next one.
+pub fn parse_ndjson(input: &str) -> impl Iterator<Item = Result<JsonValue, JsonError>> + '_ {
+    input
+        .split('\n')
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            parse_json(line).map_err(|e| JsonError::Line {
+                line: i + 1,
+                source: Box::new(e),
+            })
+        })
+}
+
+/// The RFC 7464 record separator.
+const RS: char = '\u{1e}';
+
+/// Parse an RFC 7464 JSON text sequence (`application/json-seq`) of
+/// `RS <json> LF` records. A record that fails to parse yields an error and
+/// decoding resumes at the next RS. Error offsets are into `input`.
+///
+/// A top-level number, `true`, `false` or `null` not followed by whitespace
+/// may have been cut short, so such a record is reported as truncated, as
+/// the RFC recommends.
+pub fn parse_json_seq(input: &str) -> impl Iterator<Item = Result<JsonValue, JsonError>> + '_ {
+    let mut offset = 0;
+    input.split(RS).filter_map(move |record| {
+        let start = offset;
+        offset += record.len() + RS.len_utf8();
+        // consecutive separators are allowed, and the text before the first
+        // one must be empty
+        if record.trim().is_empty() {
+            return None;
+        }
+        if start == 0 {
+            return Some(Err(JsonError::parse(
+                ErrorCode::UnexpectedToken,
+                0,
+                "expected record separator",
+            )));
+        }
+        let value = match parse_json(record) {
+            Ok(value) => value,
+            Err(e) => return Some(Err(e.shifted(start))),
+        };
+        let scalar = matches!(
+            value,
+            JsonValue::Number(_) | JsonValue::Bool(_) | JsonValue::Null
+        );
+        if scalar && !record.ends_with(char::is_whitespace) {
+            return Some(Err(JsonError::parse(
+                ErrorCode::UnexpectedEof,
+                start + record.len(),
+                "truncated record",
+            )));
+        }
+        Some(Ok(value))
+    })
+}
+
+/// Write `values` as an RFC 7464 JSON text sequence of compact records.
+pub fn write_json_seq<'a>(
+    w: &mut impl io::Write,
+    values: impl IntoIterator<Item = &'a JsonValue>,
+) -> io::Result<()> {
+    for value in values {
+        write!(w, "{}", RS)?;
+        value.to_writer(w)?;
+        w.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Parse JSON values written back to back, as `jq -c` and many websocket
+/// APIs produce (`{"a":1}{"b":2} 3 "x"`), yielding each with its byte span.
+/// Whitespace between values is optional except between two numbers, since
+/// `1 2` and `12` could otherwise not be told apart.
+///
+/// Iteration stops after the first error, whose offset is where parsing
+/// stopped, so a caller can skip ahead and restart on the rest.
+pub fn iter_values(
+    input: &str,
+) -> impl Iterator<Item = Result<(JsonValue, Range<usize>), JsonError>> + '_ {
+    let mut rest = strict(input);
+    let mut failed = false;
+    iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let skipped = ws.parse_next(&mut rest);
+        let start = input.len() - rest.input.len();
+        if skipped.is_ok() && rest.input.is_empty() {
+            return None;
+        }
+        match skipped.and_then(|_| parse_value(&mut rest)) {
+            Ok(value) => {
+                // closing brackets swallow the whitespace after them
+                let text = &input[start..input.len() - rest.input.len()];
+                let end = start + text.trim_end_matches([' ', '\t', '\n', '\r']).len();
+                Some(Ok((value, start..end)))
+            }
+            Err(e) => {
+                failed = true;
+                Some(Err(JsonError::from_parser(input, rest.input, e)))
+            }
+        }
+    })
+}
+
+/// Iterate the elements of a top-level array one at a time, so that only
+/// the current element is ever built. The opening `[` is checked up front;
+/// the closing `]` and trailing whitespace are checked after the last
+/// element. An element that fails to parse yields [`JsonError::Element`]
+/// with its index, and iteration ends after any error.
+pub fn iter_array(input: &str) -> Result<ArrayIter<'_>, JsonError> {
+    let mut rest = strict(input);
+    (ws, sep_with_ws('['))
+        .parse_next(&mut rest)
+        .map_err(|e| JsonError::from_parser(input, rest.input, e))?;
+    Ok(ArrayIter {
+        source: input,
+        rest,
+        index: 0,
+        state: ArrayState::First,
+    })
+}
+
+/// Call `f` with the index and value of each element of a top-level array
+/// in turn, dropping each value before parsing the next, until `f` returns
+/// [`ControlFlow::Break`]. Errors are those of [`iter_array`]; for input
+/// that is not in memory see
+/// [`for_each_element_from_reader`](super::for_each_element_from_reader).
+pub fn for_each_element(
+    input: &str,
+    mut f: impl FnMut(usize, JsonValue) -> ControlFlow<()>,
+) -> Result<(), JsonError> {
+    for (index, element) in iter_array(input)?.enumerate() {
+        if f(index, element?).is_break() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The elements of a top-level array; see [`iter_array`].
+pub struct ArrayIter<'a> {
+    source: &'a str,
+    rest: Input<'a>,
+    index: usize,
+    state: ArrayState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArrayState {
+    First,
+    Next,
+    Done,
+}
+
+impl ArrayIter<'_> {
+    /// The next element, or `None` if the array closes instead.
+    fn element(&mut self) -> PResult<Option<JsonValue>> {
+        let rest = &mut self.rest;
+        let closed = match self.state {
+            ArrayState::First => opt(sep_with_ws(']')).parse_next(rest)?.is_some(),
+            // a broken separator is blamed on the element it should introduce
+            _ => entry_end(rest, ']')?,
+        };
+        if closed {
+            return Ok(None);
+        }
+        let value = cut_err(parse_value).parse_next(rest)?;
+        self.index += 1;
+        self.state = ArrayState::Next;
+        Ok(Some(value))
+    }
+
+    fn finish(&mut self) -> Result<(), JsonError> {
+        self.state = ArrayState::Done;
+        if self.rest.input.is_empty() {
+            Ok(())
+        } else {
+            Err(JsonError::parse_at(self.source, self.rest.input).trailing())
+        }
+    }
+}
+
+impl Iterator for ArrayIter<'_> {
+    type Item = Result<JsonValue, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.state {
+            ArrayState::Done => None,
+            ArrayState::First | ArrayState::Next => {
+                let index = self.index;
+                match self.element() {
+                    Ok(Some(value)) => Some(Ok(value)),
+                    Ok(None) => self.finish().err().map(Err),
+                    Err(e) => {
+                        self.state = ArrayState::Done;
+                        let e = JsonError::from_parser(self.source, self.rest.input, e);
+                        Some(Err(JsonError::Element {
+                            index,
+                            source: Box::new(e),
+                        }))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Num;
+
+    #[test]
+    fn ndjson_should_skip_blank_lines_and_continue_after_errors() {
+        let input = "{\"a\": 1}\n\n[1, 2]\r\n{\"broken\": }\n  \n\"last\"";
+        let results: Vec<_> = parse_ndjson(input).collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0], parse_json(r#"{"a": 1}"#));
+        assert_eq!(results[1], parse_json("[1, 2]"));
+        assert_eq!(
+            results[2],
+            Err(JsonError::Line {
+                line: 4,
+                source: Box::new(JsonError::Parse {
+                    offset: 11,
+                    message: "unexpected character '}'".to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                })
+            })
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "line 4: unexpected character '}' at offset 11"
+        );
+        assert_eq!(results[3], Ok(JsonValue::from("last")));
+    }
+
+    #[test]
+    fn ndjson_should_handle_empty_input() {
+        assert_eq!(parse_ndjson("").count(), 0);
+        assert_eq!(parse_ndjson("\n\n").count(), 0);
+    }
+
+    #[test]
+    fn json_seq_should_parse_valid_records() {
+        let input = "\u{1e}{\"a\": 1}\n\u{1e}[1, 2]\n\u{1e}\u{1e}3\n";
+        let values: Vec<_> = parse_json_seq(input).collect();
+        assert_eq!(
+            values,
+            vec![
+                parse_json(r#"{"a": 1}"#),
+                parse_json("[1, 2]"),
+                Ok(JsonValue::Number(Num::Int(3)))
+            ]
+        );
+    }
+
+    #[test]
+    fn json_seq_should_resync_after_a_corrupted_record() {
+        let input = "\u{1e}\"one\"\n\u{1e}{\"bad\": tru\u{1e}[\"three\"]\n";
+        let values: Vec<_> = parse_json_seq(input).collect();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0], Ok(JsonValue::from("one")));
+        assert_eq!(
+            values[1],
+            Err(JsonError::Parse {
+                offset: 19,
+                message: "unexpected end of input".to_string(),
+                code: ErrorCode::UnexpectedEof,
+            })
+        );
+        assert_eq!(values[2], parse_json(r#"["three"]"#));
+    }
+
+    #[test]
+    fn json_seq_should_report_truncated_final_record() {
+        let input = "\u{1e}true\n\u{1e}12";
+        let values: Vec<_> = parse_json_seq(input).collect();
+        assert_eq!(values[0], Ok(JsonValue::Bool(true)));
+        assert_eq!(
+            values[1],
+            Err(JsonError::Parse {
+                offset: 9,
+                message: "truncated record".to_string(),
+                code: ErrorCode::UnexpectedEof,
+            })
+        );
+        assert!(parse_json_seq("\u{1e}{\"a\": [1").next().unwrap().is_err());
+        assert!(parse_json_seq("1\n").next().unwrap().is_err());
+    }
+
+    #[test]
+    fn json_seq_writer_should_round_trip() -> io::Result<()> {
+        let values = vec![
+            parse_json(r#"{"a": [1, "x"]}"#).unwrap(),
+            JsonValue::Null,
+            JsonValue::from(2.5),
+        ];
+        let mut out = Vec::new();
+        write_json_seq(&mut out, &values)?;
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text, "\u{1e}{\"a\":[1,\"x\"]}\n\u{1e}null\n\u{1e}2.5\n");
+        let parsed: Result<Vec<_>, _> = parse_json_seq(&text).collect();
+        assert_eq!(parsed.unwrap(), values);
+        Ok(())
+    }
+
+    fn spans(input: &str) -> Vec<Result<(JsonValue, Range<usize>), JsonError>> {
+        iter_values(input).collect()
+    }
+
+    #[test]
+    fn iter_values_should_split_objects_back_to_back() {
+        let input = r#"{"a":1}{"b":2} 3 "x""#;
+        let values = spans(input);
+        assert_eq!(
+            values,
+            vec![
+                parse_json(r#"{"a":1}"#).map(|v| (v, 0..7)),
+                parse_json(r#"{"b":2}"#).map(|v| (v, 7..14)),
+                Ok((JsonValue::Number(Num::Int(3)), 15..16)),
+                Ok((JsonValue::from("x"), 17..20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_values_should_handle_scalar_separators() {
+        let values: Vec<_> = iter_values("truenull\"s\"[]1 2\n\t-3.5 ")
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                JsonValue::Bool(true),
+                JsonValue::Null,
+                JsonValue::from("s"),
+                JsonValue::Array(vec![]),
+                JsonValue::Number(Num::Int(1)),
+                JsonValue::Number(Num::Int(2)),
+                JsonValue::from(-3.5),
+            ]
+        );
+        // without whitespace adjacent digits are one number
+        assert_eq!(spans("12").len(), 1);
+        assert_eq!(spans("").len(), 0);
+        assert_eq!(spans("  \n").len(), 0);
+    }
+
+    #[test]
+    fn iter_values_should_stop_at_a_trailing_partial_value() {
+        let values = spans(r#"[1] {"a": [2, "#);
+        assert_eq!(values.len(), 2);
+        assert_eq!(
+            values[0],
+            Ok((JsonValue::Array(vec![JsonValue::from(1)]), 0..3))
+        );
+        assert_eq!(
+            values[1],
+            Err(JsonError::Parse {
+                offset: 14,
+                message: "unexpected end of input".to_string(),
+                code: ErrorCode::UnexpectedEof,
+            })
+        );
+    }
+
+    #[test]
+    fn iter_array_should_yield_elements_lazily() {
+        let n = 20_000;
+        let mut input = String::from(" [");
+        for i in 0..n {
+            if i > 0 {
+                input.push_str(",\n");
+            }
+            input.push_str(&format!(r#"{{"id": {}, "tags": ["a", "b"]}}"#, i));
+        }
+        input.push_str("]\n");
+        let mut count = 0;
+        for (i, element) in iter_array(&input).unwrap().enumerate() {
+            let JsonValue::Object(element) = element.unwrap() else {
+                panic!("element {} is not an object", i)
+            };
+            assert_eq!(element["id"], JsonValue::from(i as i64));
+            count += 1;
+        }
+        assert_eq!(count, n);
+        assert_eq!(iter_array(" [ ] ").unwrap().count(), 0);
+    }
+
+    #[test]
+    fn iter_array_should_report_the_failing_element() {
+        let results: Vec<_> = iter_array("[1, 2, {\"a\": }, 4]").unwrap().collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[2],
+            Err(JsonError::Element {
+                index: 2,
+                source: Box::new(JsonError::Parse {
+                    offset: 13,
+                    message: "unexpected character '}'".to_string(),
+                    code: ErrorCode::UnexpectedToken,
+                })
+            })
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "element 2: unexpected character '}' at offset 13"
+        );
+        let missing_comma: Vec<_> = iter_array("[1 2]").unwrap().collect();
+        assert!(matches!(
+            missing_comma[1],
+            Err(JsonError::Element { index: 1, .. })
+        ));
+        let trailing: Vec<_> = iter_array("[1] x").unwrap().collect();
+        assert_eq!(
+            trailing[1],
+            Err(JsonError::parse_at("[1] x", "x").trailing())
+        );
+        assert_eq!(
+            iter_array("{}").err(),
+            Some(JsonError::parse_at("{}", "{}"))
+        );
+    }
+
+    fn records(n: usize) -> String {
+        let records: Vec<String> = (0..n)
+            .map(|i| format!(r#"{{"id": {}, "amount": {}, "tags": ["a"]}}"#, i, i % 7))
+            .collect();
+        format!(" [\n{}\n] ", records.join(",\n"))
+    }
+
+    #[test]
+    fn for_each_element_should_visit_every_element() {
+        let input = records(100_000);
+        let mut total = 0;
+        let mut next = 0;
+        let result = for_each_element(&input, |i, element| {
+            assert_eq!(i, next);
+            next += 1;
+            match element.pointer("/amount") {
+                Some(JsonValue::Number(Num::Int(n))) => total += n,
+                other => panic!("element {} has amount {:?}", i, other),
+            }
+            ControlFlow::Continue(())
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!((next, total), (100_000, (0..100_000).map(|i| i % 7).sum()));
+    }
+
+    #[test]
+    fn for_each_element_should_stop_on_break_and_report_the_element() {
+        let mut seen = Vec::new();
+        let result = for_each_element("[0, 1, 2, 3, {\"broken\"}]", |i, _| {
+            seen.push(i);
+            if i == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!((result, seen), (Ok(()), vec![0, 1, 2]));
+
+        let mut seen = 0;
+        let result = for_each_element("[0, 1, {\"a\": }, 3]", |_, _| {
+            seen += 1;
+            ControlFlow::Continue(())
+        });
+        assert_eq!(seen, 2);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "element 2: unexpected character '}' at offset 13"
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use super::{
+    comment, identifier, parse_bool, parse_key, parse_nonfinite, parse_null, parse_num,
+    parse_string, stateful, Input, JsonError, JsonString, Num, ParseOptions, Token, TokenKind,
+};
+use winnow::{
+    ascii::multispace0,
+    combinator::{alt, cut_err, fail, opt, peek},
+    error::ContextError,
+    prelude::*,
+    stream::Stream,
+};
+
+/// The tokens of a JSON text, whitespace left out, each with its byte span.
+/// Literals, numbers, strings and comments are lexed by the same code the
+/// parser uses, under the same `options`, so a token is only ever handed
+/// out when the parser would accept its text. Nothing checks that tokens
+/// come in an order that makes a document: `] 1 :` tokenizes fine. After
+/// an error the tokenizer is exhausted.
+pub struct Tokenizer<'a> {
+    source: &'a str,
+    input: Input<'a>,
+    done: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// A tokenizer for strict JSON.
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, &ParseOptions::default())
+    }
+
+    /// A tokenizer with the leniency switches in `options`. Comments become
+    /// [`TokenKind::LineComment`] and [`TokenKind::BlockComment`] tokens
+    /// under `allow_comments`, and unquoted keys [`TokenKind::Identifier`]
+    /// under `allow_unquoted_keys`.
+    pub fn with_options(input: &'a str, options: &ParseOptions) -> Self {
+        Tokenizer {
+            source: input,
+            input: stateful(input, options),
+            done: false,
+        }
+    }
+
+    /// The next token, or `None` at the end of the input.
+    pub fn next_token(&mut self) -> Result<Option<Token<'a>>, JsonError> {
+        if self.done {
+            return Ok(None);
+        }
+        let token = self.step();
+        if !matches!(token, Ok(Some(_))) {
+            self.done = true;
+        }
+        token
+    }
+
+    fn step(&mut self) -> Result<Option<Token<'a>>, JsonError> {
+        let found = (|i: &mut Input<'a>| {
+            multispace0.parse_next(i)?;
+            if i.input.is_empty() {
+                return Ok(None);
+            }
+            if let Some(text) = opt(comment).parse_next(i)? {
+                let kind = if text.starts_with("//") {
+                    TokenKind::LineComment
+                } else {
+                    TokenKind::BlockComment
+                };
+                return Ok(Some((kind, text)));
+            }
+            cut_err(next).map(Some).parse_next(i)
+        })(&mut self.input)
+        .map_err(|e| JsonError::from_parser(self.source, self.input.input, e))?;
+        Ok(found.map(|(kind, text)| {
+            let end = self.source.len() - self.input.input.len();
+            Token {
+                kind,
+                text,
+                span: end - text.len()..end,
+            }
+        }))
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token<'a>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+/// A punctuation mark, an unquoted key or a scalar, with its text.
+fn next<'a>(input: &mut Input<'a>) -> PResult<(TokenKind, &'a str)> {
+    let kind = match input.input.as_bytes()[0] {
+        b'[' => TokenKind::LBracket,
+        b']' => TokenKind::RBracket,
+        b'{' => TokenKind::LBrace,
+        b'}' => TokenKind::RBrace,
+        b':' => TokenKind::Colon,
+        b',' => TokenKind::Comma,
+        _ => {
+            if input.state.options.allow_unquoted_keys {
+                // the longest word wins, so `nullable` is one key
+                let word = opt(peek(identifier)).parse_next(input)?;
+                if word.is_some_and(|w| !["null", "true", "false", "NaN", "Infinity"].contains(&w))
+                {
+                    return identifier
+                        .map(|w| (TokenKind::Identifier, w))
+                        .parse_next(input);
+                }
+            }
+            let (kind, text) = scalar(input)?;
+            // `01` and `truex` are no more two tokens than they are one
+            let glued = input
+                .input
+                .starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+            if kind != TokenKind::String && glued {
+                return fail(input);
+            }
+            return Ok((kind, text));
+        }
+    };
+    Ok((kind, input.next_slice(1)))
+}
+
+/// A null, boolean, number or string, as the parser reads it.
+pub(super) fn scalar<'a>(input: &mut Input<'a>) -> PResult<(TokenKind, &'a str)> {
+    let text = alt((
+        parse_null.recognize(),
+        parse_bool.recognize(),
+        parse_nonfinite.recognize(),
+        parse_num.recognize(),
+        parse_string.recognize(),
+    ))
+    .parse_next(input)?;
+    let kind = match text {
+        "null" => TokenKind::Null,
+        "true" => TokenKind::True,
+        "false" => TokenKind::False,
+        _ if text.starts_with(['"', '\'']) => TokenKind::String,
+        _ => TokenKind::Number,
+    };
+    Ok((kind, text))
+}
+
+impl Token<'_> {
+    /// The content of a [`TokenKind::String`] or [`TokenKind::Identifier`]
+    /// token, quotes and escapes resolved; `None` for any other kind. It is
+    /// decoded on each call.
+    pub fn decode_string(&self) -> Option<JsonString> {
+        match self.kind {
+            TokenKind::String | TokenKind::Identifier => Some(decode(self.text, parse_key)),
+            _ => None,
+        }
+    }
+
+    /// The value of a [`TokenKind::Number`] token, as [`parse_json`] reads
+    /// it; `None` for any other kind. It is decoded on each call.
+    ///
+    /// [`parse_json`]: super::parse_json
+    pub fn decode_number(&self) -> Option<Num> {
+        match self.kind {
+            TokenKind::Number => Some(decode(
+                self.text,
+                alt((parse_nonfinite.map(Num::Float), parse_num)),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Run `parser` over the text of a token. Every option that changes what
+/// is accepted is on, so text validated under any options decodes.
+fn decode<'t, O>(text: &'t str, mut parser: impl Parser<Input<'t>, O, ContextError>) -> O {
+    parser
+        .parse(stateful(text, &ParseOptions::json5()))
+        .expect("tokens were validated while lexing")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::ErrorCode;
+
+    fn kinds<'a>(input: &'a str, options: &ParseOptions) -> Vec<(TokenKind, &'a str)> {
+        Tokenizer::with_options(input, options)
+            .map(|t| t.map(|t| (t.kind, t.text)))
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn tokenizer_should_produce_the_expected_tokens() {
+        use TokenKind::*;
+        let strict = ParseOptions::strict();
+        assert_eq!(
+            kinds(
+                r#" {"a": [1, -2.5e3, true, false, null], "b\n": "x"} "#,
+                &strict
+            ),
+            [
+                (LBrace, "{"),
+                (String, r#""a""#),
+                (Colon, ":"),
+                (LBracket, "["),
+                (Number, "1"),
+                (Comma, ","),
+                (Number, "-2.5e3"),
+                (Comma, ","),
+                (True, "true"),
+                (Comma, ","),
+                (False, "false"),
+                (Comma, ","),
+                (Null, "null"),
+                (RBracket, "]"),
+                (Comma, ","),
+                (String, r#""b\n""#),
+                (Colon, ":"),
+                (String, r#""x""#),
+                (RBrace, "}"),
+            ]
+        );
+        assert_eq!(kinds("", &strict), []);
+        // only tokens are checked, not the grammar
+        assert_eq!(
+            kinds("] 1 :", &strict),
+            [(RBracket, "]"), (Number, "1"), (Colon, ":")]
+        );
+        assert_eq!(
+            kinds(
+                "// c\n{nullable: 'x', n: NaN, /* b */ h: 0x1F,}",
+                &ParseOptions::json5()
+            ),
+            [
+                (LineComment, "// c"),
+                (LBrace, "{"),
+                (Identifier, "nullable"),
+                (Colon, ":"),
+                (String, "'x'"),
+                (Comma, ","),
+                (Identifier, "n"),
+                (Colon, ":"),
+                (Number, "NaN"),
+                (Comma, ","),
+                (BlockComment, "/* b */"),
+                (Identifier, "h"),
+                (Colon, ":"),
+                (Number, "0x1F"),
+                (Comma, ","),
+                (RBrace, "}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizer_spans_should_tile_the_input() {
+        let corpus = [
+            "null",
+            "  [ 1 ,2,\t3.50e+1 ]\n",
+            "{\"a\" : {\"b\":[true,false , \"x\\u00e9\\n\"]},\r\n \"c\":-0}",
+            "\n\n{ \"deep\": [[[ [] ], {}]] }  ",
+        ];
+        for input in corpus {
+            let mut at = 0;
+            for token in Tokenizer::new(input) {
+                let token = token.unwrap();
+                assert!(input[at..token.span.start].trim().is_empty(), "{:?}", input);
+                assert_eq!(&input[token.span.clone()], token.text);
+                at = token.span.end;
+            }
+            assert!(input[at..].trim().is_empty(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn tokenizer_should_decode_lazily_and_reject_what_the_parser_rejects() {
+        let tokens: Vec<_> = Tokenizer::new(r#"["aé\n", 1e2, 7]"#)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(tokens[1].decode_string(), Some("a\u{e9}\n".into()));
+        assert_eq!(tokens[3].decode_number(), Some(Num::Float(100.0)));
+        assert_eq!(tokens[5].decode_number(), Some(Num::Int(7)));
+        assert_eq!(tokens[0].decode_string(), None);
+        assert_eq!(tokens[1].decode_number(), None);
+
+        for (input, offset) in [
+            ("[01]", 2),
+            ("[1, x]", 4),
+            ("\"a\tb\"", 2),
+            ("// c", 0),
+            ("'s'", 0),
+        ] {
+            let mut tokens = Tokenizer::new(input);
+            match tokens.by_ref().find_map(Result::err) {
+                Some(JsonError::Parse { offset: at, .. }) => assert_eq!(at, offset, "{:?}", input),
+                other => panic!("{:?}: {:?}", input, other),
+            }
+            assert!(tokens.next().is_none());
+        }
+        let err = Tokenizer::new("[NaN]").find_map(Result::err).unwrap();
+        assert_eq!(err.code(), ErrorCode::NotAllowed);
+    }
+}
@@ -0,0 +1,256 @@
+use super::{events::JsonReader, Input, JsonError, JsonValue, ParseOptions};
+use std::{cell::RefCell, fmt, mem};
+use winnow::{combinator::trace, error::ContextError, prelude::*};
+
+/// What a parse went through, rule by rule: each rule as it starts and as
+/// it ends, in order and nested. Displays as an indented outline.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseTrace {
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// `rule` started at byte `offset`.
+    Enter { rule: &'static str, offset: usize },
+    /// `rule` ended at byte `offset`, with or without a match. After a
+    /// failure that ends the parse, the rules around it get no exit.
+    Exit {
+        rule: &'static str,
+        offset: usize,
+        matched: bool,
+    },
+}
+
+impl ParseTrace {
+    /// The rules in the order they were entered.
+    pub fn rules(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.events.iter().filter_map(|e| match e {
+            TraceEvent::Enter { rule, .. } => Some(*rule),
+            TraceEvent::Exit { .. } => None,
+        })
+    }
+
+    /// The rules that matched, in the order they ended.
+    pub fn matched(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.events.iter().filter_map(|e| match e {
+            TraceEvent::Exit {
+                rule,
+                matched: true,
+                ..
+            } => Some(*rule),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for ParseTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut depth = 0;
+        for event in &self.events {
+            match *event {
+                TraceEvent::Enter { rule, offset } => {
+                    writeln!(
+                        f,
+                        "{:indent$}> {} @{}",
+                        "",
+                        rule,
+                        offset,
+                        indent = depth * 2
+                    )?;
+                    depth += 1;
+                }
+                TraceEvent::Exit {
+                    rule,
+                    offset,
+                    matched,
+                } => {
+                    depth -= 1;
+                    let outcome = if matched { "ok" } else { "failed" };
+                    let indent = depth * 2;
+                    writeln!(f, "{:indent$}< {} {} @{}", "", rule, outcome, offset)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`parse_json`](super::parse_json), recording a [`ParseTrace`] of the
+/// rules it went through, up to the error if there is one. The result is
+/// the same as without the trace.
+pub fn parse_json_traced(input: &str) -> (Result<JsonValue, JsonError>, ParseTrace) {
+    parse_json_traced_with(input, &ParseOptions::default())
+}
+
+/// [`parse_json_traced`] with the leniency switches in `options`.
+pub fn parse_json_traced_with(
+    input: &str,
+    options: &ParseOptions,
+) -> (Result<JsonValue, JsonError>, ParseTrace) {
+    EVENTS.with(|events| events.borrow_mut().clear());
+    let mut reader = JsonReader::with_options(input, options);
+    reader.trace();
+    let result = reader.into_value();
+    let events = EVENTS.with(|events| mem::take(&mut *events.borrow_mut()));
+    // events hold how much input was left; turn that into offsets
+    let offset = |left: usize| input.len() - left;
+    let events = events
+        .into_iter()
+        .map(|event| match event {
+            TraceEvent::Enter { rule, offset: left } => TraceEvent::Enter {
+                rule,
+                offset: offset(left),
+            },
+            TraceEvent::Exit {
+                rule,
+                offset: left,
+                matched,
+            } => TraceEvent::Exit {
+                rule,
+                offset: offset(left),
+                matched,
+            },
+        })
+        .collect();
+    (result, ParseTrace { events })
+}
+
+thread_local! {
+    static EVENTS: RefCell<Vec<TraceEvent>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Record that `rule` starts now, when `input` is being traced.
+pub(super) fn enter(input: &Input<'_>, rule: &'static str) {
+    if input.state.tracing {
+        let offset = input.input.len();
+        EVENTS.with(|events| events.borrow_mut().push(TraceEvent::Enter { rule, offset }));
+    }
+}
+
+/// Record that `rule` ends now, when `input` is being traced.
+pub(super) fn exit(input: &Input<'_>, rule: &'static str, matched: bool) {
+    if input.state.tracing {
+        let offset = input.input.len();
+        EVENTS.with(|events| {
+            events.borrow_mut().push(TraceEvent::Exit {
+                rule,
+                offset,
+                matched,
+            })
+        });
+    }
+}
+
+/// `parser` as the grammar rule `name`: traced by winnow's `debug` feature,
+/// and recorded into the [`ParseTrace`] when the input is being traced.
+pub(super) fn rule<'a, O>(
+    name: &'static str,
+    mut parser: impl Parser<Input<'a>, O, ContextError>,
+) -> impl Parser<Input<'a>, O, ContextError> {
+    trace(name, move |input: &mut Input<'a>| {
+        if !input.state.tracing {
+            return parser.parse_next(input);
+        }
+        enter(input, name);
+        let result = parser.parse_next(input);
+        exit(input, name, result.is_ok());
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, parse_json_with};
+
+    #[test]
+    fn trace_should_follow_the_grammar() {
+        let (value, trace) = parse_json_traced(r#"{"a": [1]}"#);
+        assert_eq!(value.unwrap(), parse_json(r#"{"a": [1]}"#).unwrap());
+        assert_eq!(
+            trace.matched().collect::<Vec<_>>(),
+            ["string", "key", "number", "value", "array", "value", "object", "value"]
+        );
+        assert_eq!(
+            trace.rules().collect::<Vec<_>>(),
+            [
+                "value",
+                "null",
+                "bool",
+                "nonfinite",
+                "number",
+                "string",
+                "object",
+                "key",
+                "string",
+                "value",
+                "null",
+                "bool",
+                "nonfinite",
+                "number",
+                "string",
+                "array",
+                "value",
+                "null",
+                "bool",
+                "nonfinite",
+                "number",
+            ]
+        );
+        let outline = trace.to_string();
+        assert!(
+            outline.starts_with("> value @0\n  > null @0\n  < null failed @0\n"),
+            "{}",
+            outline
+        );
+        assert!(
+            outline.contains("\n          > number @7\n          < number ok @8\n"),
+            "{}",
+            outline
+        );
+        assert!(
+            outline.ends_with("    < value ok @9\n  < object ok @10\n< value ok @10\n"),
+            "{}",
+            outline
+        );
+    }
+
+    #[test]
+    fn trace_should_change_no_results() {
+        let json5 = ParseOptions::json5();
+        for (input, options) in [
+            (
+                r#"{"a": [1, 2.5e3, "xé"], "b": {"c": null}, "d": [true, false]}"#,
+                ParseOptions::strict(),
+            ),
+            ("[1, 2,]", ParseOptions::strict()),
+            (r#"{"a" 1}"#, ParseOptions::strict()),
+            ("[1e400]", ParseOptions::strict()),
+            ("{a: 'b', /* c */ d: [0x1F, +.5, Infinity,],}", json5),
+            ("[[[1]]]", ParseOptions::strict().max_depth(2)),
+            ("", ParseOptions::strict()),
+        ] {
+            let (traced, trace) = parse_json_traced_with(input, &options);
+            assert_eq!(
+                traced.is_ok(),
+                parse_json_with(input, &options).is_ok(),
+                "{}",
+                input
+            );
+            match (traced, parse_json_with(input, &options)) {
+                (Ok(a), Ok(b)) => assert_eq!(a, b),
+                (Err(a), Err(b)) => assert_eq!(a, b),
+                (a, b) => panic!("{}: {:?} vs {:?}", input, a, b),
+            }
+            assert!(trace.rules().next().is_some(), "{}", input);
+        }
+        // the failing rule is there to see
+        let (_, trace) = parse_json_traced("[1, tru]");
+        assert!(trace.events.contains(&TraceEvent::Exit {
+            rule: "bool",
+            offset: 4,
+            matched: false
+        }));
+    }
+}
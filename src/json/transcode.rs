@@ -0,0 +1,254 @@
+use super::{
+    reader::{read_events, ReadOptions},
+    ser::{write_f64, write_string, SpacedFormatter},
+    EscapeOptions, FormatOptions, Formatter, JsonError, JsonEvent, Num, PrettyFormatter,
+};
+use std::io;
+
+/// Reformat the JSON document read from `reader` into `writer` without
+/// building it: each token is written out as soon as it is read, so memory
+/// holds one token and the nesting stack however large the document. A
+/// string can be transcoded through its bytes (`text.as_bytes()`).
+///
+/// Layout, escaping and the trailing newline follow `options` as for
+/// [`JsonValue::to_writer_with`](super::JsonValue::to_writer_with). Members
+/// keep their input order and numbers their original spelling, so
+/// `sort_keys` and `key_order` are ignored, as is `max_width`, which needs to
+/// see a whole container before writing it. Input errors are those of
+/// [`from_reader`](super::from_reader); some output may already have been
+/// written when one is found.
+pub fn transcode<R: io::Read, W: io::Write>(
+    reader: R,
+    writer: &mut W,
+    options: &FormatOptions,
+) -> Result<(), JsonError> {
+    let newline = options.newline.as_str().as_bytes();
+    match &options.indent {
+        Some(indent) => {
+            let unit = indent.unit();
+            let f = PrettyFormatter::with_indent(unit.as_bytes())
+                .with_newline(newline)
+                .with_diff_layout(options.diff_friendly);
+            Transcoder::new(writer, f, &options.escape).run(reader)?;
+        }
+        None => {
+            let f = SpacedFormatter {
+                colon: options.space_after_colon,
+                comma: options.space_after_comma,
+            };
+            Transcoder::new(writer, f, &options.escape).run(reader)?;
+        }
+    }
+    if options.trailing_newline {
+        writer.write_all(newline)?;
+    }
+    Ok(())
+}
+
+/// Turns events into [`Formatter`] calls, tracking only whether each open
+/// container is an object and whether it has had an entry yet.
+struct Transcoder<'a, W, F> {
+    w: &'a mut W,
+    formatter: F,
+    escape: &'a EscapeOptions,
+    /// `(is_object, first)` for each open container, innermost last.
+    stack: Vec<(bool, bool)>,
+}
+
+impl<'a, W: io::Write, F: Formatter> Transcoder<'a, W, F> {
+    fn new(w: &'a mut W, formatter: F, escape: &'a EscapeOptions) -> Self {
+        Transcoder {
+            w,
+            formatter,
+            escape,
+            stack: Vec::new(),
+        }
+    }
+
+    fn run(mut self, reader: impl io::Read) -> Result<(), JsonError> {
+        let options = ReadOptions {
+            raw_numbers: true,
+            ..ReadOptions::default()
+        };
+        read_events(reader, &options, |event| Ok(self.event(event)?))
+    }
+
+    fn event(&mut self, event: JsonEvent) -> io::Result<()> {
+        let (w, f) = (&mut *self.w, &mut self.formatter);
+        match event {
+            JsonEvent::Key(key) => {
+                if let Some((_, first)) = self.stack.last_mut() {
+                    f.begin_object_key(w, *first)?;
+                    *first = false;
+                }
+                write_string(w, f, &key, self.escape)?;
+                f.end_object_key(w)?;
+                return f.begin_object_value(w);
+            }
+            JsonEvent::ObjectEnd => {
+                self.stack.pop();
+                f.end_object(w)?;
+            }
+            JsonEvent::ArrayEnd => {
+                self.stack.pop();
+                f.end_array(w)?;
+            }
+            event => {
+                if let Some((false, first)) = self.stack.last_mut() {
+                    f.begin_array_value(w, *first)?;
+                    *first = false;
+                }
+                match event {
+                    JsonEvent::ObjectStart => {
+                        self.stack.push((true, true));
+                        return f.begin_object(w);
+                    }
+                    JsonEvent::ArrayStart => {
+                        self.stack.push((false, true));
+                        return f.begin_array(w);
+                    }
+                    JsonEvent::String(s) => write_string(w, f, &s, self.escape)?,
+                    JsonEvent::Number(Num::Raw(s)) => f.write_raw_number(w, &s)?,
+                    JsonEvent::Number(Num::Int(i)) => f.write_i64(w, i)?,
+                    JsonEvent::Number(Num::Float(x)) => write_f64(w, f, x, Default::default())?,
+                    JsonEvent::Bool(b) => f.write_bool(w, b)?,
+                    _ => f.write_null(w)?,
+                }
+            }
+        }
+        // a value just ended
+        match self.stack.last() {
+            Some((true, _)) => self.formatter.end_object_value(self.w),
+            Some((false, _)) => self.formatter.end_array_value(self.w),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, parse_json_with, JsonValue, ParseOptions};
+
+    fn transcoded(input: &str, options: &FormatOptions) -> String {
+        let mut out = Vec::new();
+        transcode(input.as_bytes(), &mut out, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    const DOC: &str = r#" {"a": [1, -2.50, 1E+3, 12345678901234567890], "b": {"c": "é\né <&>",
+        "d": [], "e": {}}, "f": [[true, false], [null, {"g": "h"}]], "i": " "} "#;
+
+    #[test]
+    fn transcode_should_round_trip() {
+        let original = parse_json_with(DOC, &ParseOptions::strict().preserve_number_text(true));
+        for options in [
+            FormatOptions::compact(),
+            FormatOptions::pretty(),
+            FormatOptions::pretty().tabs().trailing_newline(true),
+            FormatOptions::compact()
+                .space_after_colon(true)
+                .space_after_comma(true),
+        ] {
+            let out = transcoded(DOC, &options);
+            let reparsed =
+                parse_json_with(&out, &ParseOptions::strict().preserve_number_text(true));
+            assert_eq!(reparsed, original, "{}", out);
+        }
+        assert_eq!(
+            transcoded(DOC, &FormatOptions::compact()),
+            r#"{"a":[1,-2.50,1E+3,12345678901234567890],"b":{"c":"é\né <&>","d":[],"e":{}},"f":[[true,false],[null,{"g":"h"}]],"i":"\u2028"}"#
+        );
+        for bad in ["[1, x]", "{\"a\" 1}", "[1,]", "[] 2", ""] {
+            let err = transcode(bad.as_bytes(), &mut Vec::new(), &FormatOptions::pretty());
+            assert_eq!(err, Err(parse_json(bad).unwrap_err()), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn transcode_should_match_the_tree_printer() {
+        // sorted keys and canonical number spellings, so the tree prints the same
+        let input = r#"{"a": [1, -2.5, {"b": [], "c": {}}], "d": {"e": "x\t<y>", "f": [null, true]}, "g": "é"}"#;
+        let tree = parse_json(input).unwrap();
+        let escape = EscapeOptions {
+            ascii_only: true,
+            escape_html: true,
+            ..Default::default()
+        };
+        for options in [
+            FormatOptions::pretty(),
+            FormatOptions::pretty().spaces(4).escape(escape),
+            FormatOptions::compact().space_after_colon(true),
+            FormatOptions::compact().diff_friendly(true),
+        ] {
+            assert_eq!(
+                transcoded(input, &options),
+                tree.to_string_with(&options.clone().sort_keys(true))
+            );
+        }
+    }
+
+    /// Generates `[0,1,...]` on the fly, so the input is never held whole.
+    struct Elements {
+        next: usize,
+        count: usize,
+        pending: Vec<u8>,
+    }
+
+    impl io::Read for Elements {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pending.is_empty() && self.next <= self.count {
+                self.pending = match self.next {
+                    0 => b"[".to_vec(),
+                    n if n == self.count => format!("{}]", n - 1).into_bytes(),
+                    n => format!("{},", n - 1).into_bytes(),
+                };
+                self.next += 1;
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+
+    /// Keeps nothing but counts.
+    #[derive(Default)]
+    struct Counting {
+        bytes: usize,
+        lines: usize,
+    }
+
+    impl io::Write for Counting {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.bytes += buf.len();
+            self.lines += buf.iter().filter(|&&b| b == b'\n').count();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn transcode_should_stream_large_arrays() {
+        let count = 100_000;
+        let elements = || Elements {
+            next: 0,
+            count,
+            pending: Vec::new(),
+        };
+        let mut out = Counting::default();
+        transcode(elements(), &mut out, &FormatOptions::pretty()).unwrap();
+        assert_eq!(out.lines, count + 1);
+        let digits: usize = (0..count).map(|i| i.to_string().len()).sum();
+        // brackets, plus per element a comma (but one), a newline and two spaces
+        assert_eq!(out.bytes, 2 + digits + (count - 1) + count * 3 + 1);
+        let mut out = Counting::default();
+        transcode(elements(), &mut out, &FormatOptions::compact()).unwrap();
+        assert_eq!((out.bytes, out.lines), (2 + digits + count - 1, 0));
+        let value = JsonValue::Array((0..count as i64).map(JsonValue::from).collect());
+        assert_eq!(out.bytes, value.to_json_string().len());
+    }
+}
@@ -0,0 +1,173 @@
+use super::JsonValue;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// A UUID (RFC 9562) as its 16 bytes, for IDs carried in JSON strings. It
+/// does not check the version or variant bits, so any 128-bit value is one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid([u8; 16]);
+
+/// Text that is not a UUID in the accepted form.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("invalid UUID {0:?}")]
+pub struct UuidError(String);
+
+impl Uuid {
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Uuid(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Read `s` as the hyphenated form, `8-4-4-4-12` hex digits in either
+    /// case; also, if `lenient`, the 32 digits without hyphens and either
+    /// form in braces.
+    pub fn parse(s: &str, lenient: bool) -> Result<Self, UuidError> {
+        let invalid = || UuidError(s.to_string());
+        let inner = match s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(inner) if lenient => inner,
+            Some(_) => return Err(invalid()),
+            None => s,
+        };
+        let digits: Vec<u8> = match inner.len() {
+            36 => {
+                let b = inner.as_bytes();
+                if [8, 13, 18, 23].iter().any(|&i| b[i] != b'-') {
+                    return Err(invalid());
+                }
+                b.iter().copied().filter(|&c| c != b'-').collect()
+            }
+            32 if lenient => inner.bytes().collect(),
+            _ => return Err(invalid()),
+        };
+        if digits.len() != 32 {
+            return Err(invalid());
+        }
+        let mut bytes = [0; 16];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            let nibble = |c: u8| char::from(c).to_digit(16).ok_or_else(invalid);
+            *byte = (nibble(pair[0])? << 4 | nibble(pair[1])?) as u8;
+        }
+        Ok(Uuid(bytes))
+    }
+}
+
+/// Only the hyphenated form; see [`Uuid::parse`] for the others.
+impl FromStr for Uuid {
+    type Err = UuidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse(s, false)
+    }
+}
+
+/// Lowercase and hyphenated: `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Uuid> for JsonValue {
+    fn from(uuid: Uuid) -> Self {
+        JsonValue::from(uuid.to_string())
+    }
+}
+
+impl JsonValue {
+    /// The UUID in a hyphenated string, in either case. `None` for other
+    /// values and other spellings; see [`as_uuid_with`](Self::as_uuid_with).
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        self.as_uuid_with(false)
+    }
+
+    /// [`as_uuid`](Self::as_uuid), also taking the braced and hyphen-less
+    /// forms if `lenient`.
+    pub fn as_uuid_with(&self, lenient: bool) -> Option<Uuid> {
+        match self {
+            JsonValue::String(s) => Uuid::parse(s, lenient).ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a string [`as_uuid`](Self::as_uuid) reads.
+    pub fn is_uuid(&self) -> bool {
+        self.as_uuid().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+    #[test]
+    fn uuids_should_round_trip_lowercase() {
+        let uuid = JsonValue::from(ID).as_uuid().unwrap();
+        assert_eq!(uuid.as_bytes()[..4], [0x67, 0xe5, 0x50, 0x44]);
+        assert_eq!(JsonValue::from(uuid), JsonValue::from(ID));
+        let upper = JsonValue::from(ID.to_uppercase());
+        assert!(upper.is_uuid());
+        assert_eq!(
+            JsonValue::from(upper.as_uuid().unwrap()),
+            JsonValue::from(ID)
+        );
+        assert_eq!(ID.parse::<Uuid>().unwrap(), uuid);
+        assert_eq!(
+            Uuid::from_bytes([0; 16]).to_string(),
+            "00000000-0000-0000-0000-000000000000"
+        );
+    }
+
+    #[test]
+    fn other_forms_should_need_lenient() {
+        let uuid = ID.parse::<Uuid>().unwrap();
+        for text in [
+            "67e5504410b1426f9247bb680e5fe0c8",
+            "{67e55044-10b1-426f-9247-bb680e5fe0c8}",
+            "{67E5504410B1426F9247BB680E5FE0C8}",
+        ] {
+            let value = JsonValue::from(text);
+            assert_eq!(value.as_uuid(), None, "{}", text);
+            assert!(!value.is_uuid());
+            assert_eq!(value.as_uuid_with(true), Some(uuid), "{}", text);
+        }
+    }
+
+    #[test]
+    fn non_uuids_should_be_none() {
+        for text in [
+            "",
+            "67e55044-10b1-426f-9247-bb680e5fe0c",
+            "67e55044-10b1-426f-9247-bb680e5fe0c8a",
+            "67e55044-10b1-426f-9247_bb680e5fe0c8",
+            "67e5504-410b1-426f-9247-bb680e5fe0c8",
+            "67e55044-10b1-426f-9247-bb680e5fe0cg",
+            "+7e55044-10b1-426f-9247-bb680e5fe0c8",
+            "67e55044-10b1-426f-9247-bb680e5fé0",
+            "{67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "urn:uuid:67e55044-10b1-426f-9247-bb680e5fe0c8",
+            " 67e55044-10b1-426f-9247-bb680e5fe0c8",
+        ] {
+            assert_eq!(JsonValue::from(text).as_uuid_with(true), None, "{}", text);
+            assert!(text.parse::<Uuid>().is_err());
+        }
+        for value in [
+            JsonValue::Null,
+            JsonValue::from(1),
+            JsonValue::Array(vec![]),
+        ] {
+            assert_eq!(value.as_uuid(), None);
+            assert!(!value.is_uuid());
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use super::{
+    lint::survives_f64, parse_cst, JsonError, JsonObject, JsonValue, Node, Num, ParseOptions, Token,
+};
+use std::{fmt, ops::Range};
+
+/// What kind of thing a [`JsonWarning`] is about. As with
+/// [`ErrorCode`](super::ErrorCode), codes and their names stay put while
+/// messages may change, and more codes may be added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WarningCode {
+    /// A key appears again in the same object; the last value wins.
+    DuplicateKey,
+    /// A number whose written digits the f64 it became does not hold.
+    PrecisionLoss,
+    /// An integer too large for [`Num::Int`] that became the nearest float
+    /// under [`big_int_as_float`](ParseOptions::big_int_as_float).
+    IntegerAsFloat,
+    /// Containers nested more than three quarters of the way to
+    /// [`max_depth`](ParseOptions::max_depth), reported once, for the first
+    /// of them.
+    NearDepthLimit,
+}
+
+impl WarningCode {
+    /// The code as `snake_case` text, such as `"duplicate_key"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningCode::DuplicateKey => "duplicate_key",
+            WarningCode::PrecisionLoss => "precision_loss",
+            WarningCode::IntegerAsFloat => "integer_as_float",
+            WarningCode::NearDepthLimit => "near_depth_limit",
+        }
+    }
+}
+
+impl fmt::Display for WarningCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Something [`parse_json_with_warnings`] accepted but the caller may want
+/// to know about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonWarning {
+    pub code: WarningCode,
+    pub message: String,
+    /// Byte range of the token or container it is about.
+    pub span: Range<usize>,
+}
+
+/// [`parse_json_with`](super::parse_json_with), also reporting what parsed
+/// but may not be what the document meant: duplicate keys, numbers the f64
+/// they became cannot hold exactly, integers too large for [`Num::Int`]
+/// that became floats, and nesting close to `max_depth`. Warnings are in
+/// document order. The other entry points look for none of these.
+pub fn parse_json_with_warnings(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(JsonValue, Vec<JsonWarning>), JsonError> {
+    let cst = parse_cst(input, options)?;
+    let mut collector = Collector {
+        options,
+        warnings: Vec::new(),
+        warned_depth: false,
+    };
+    let value = collector.node(&cst.root, 1);
+    Ok((value, collector.warnings))
+}
+
+struct Collector<'o> {
+    options: &'o ParseOptions,
+    warnings: Vec<JsonWarning>,
+    warned_depth: bool,
+}
+
+impl Collector<'_> {
+    fn warn(&mut self, code: WarningCode, message: String, span: Range<usize>) {
+        self.warnings.push(JsonWarning {
+            code,
+            message,
+            span,
+        });
+    }
+
+    /// The value of `node`, a container `depth` levels deep.
+    fn node(&mut self, node: &Node<'_>, depth: usize) -> JsonValue {
+        let c = match node {
+            Node::Scalar(token) => return self.scalar(node, token),
+            Node::Array(c) | Node::Object(c) => c,
+        };
+        if let Some(max) = self.options.max_depth {
+            if !self.warned_depth && depth * 4 > max * 3 {
+                self.warned_depth = true;
+                self.warn(
+                    WarningCode::NearDepthLimit,
+                    format!(
+                        "nesting {} levels deep, close to the limit of {}",
+                        depth, max
+                    ),
+                    node.span(),
+                );
+            }
+        }
+        if let Node::Array(_) = node {
+            let items = c.entries.iter().map(|e| self.node(&e.value, depth + 1));
+            return JsonValue::Array(items.collect());
+        }
+        let mut obj = JsonObject::default();
+        for entry in &c.entries {
+            let key = entry.key.as_ref().expect("object entries have keys");
+            let name = key.to_string(self.options);
+            let value = self.node(&entry.value, depth + 1);
+            if obj.insert(name.clone(), value).is_some() {
+                self.warn(
+                    WarningCode::DuplicateKey,
+                    format!("duplicate key {:?}; the last value wins", &*name),
+                    key.token.span.clone(),
+                );
+            }
+        }
+        JsonValue::Object(obj)
+    }
+
+    fn scalar(&mut self, node: &Node<'_>, token: &Token<'_>) -> JsonValue {
+        let value = node.to_value(self.options);
+        if let JsonValue::Number(Num::Float(f)) = value {
+            let integer = token.text.bytes().all(|b| b == b'-' || b.is_ascii_digit());
+            if !f.is_finite() {
+                // NaN and Infinity, spelled as such
+            } else if integer {
+                self.warn(
+                    WarningCode::IntegerAsFloat,
+                    format!("integer {} is too large and became {:?}", token.text, f),
+                    token.span.clone(),
+                );
+            } else if !survives_f64(token.text) {
+                self.warn(
+                    WarningCode::PrecisionLoss,
+                    format!("number {} became {:?}", token.text, f),
+                    token.span.clone(),
+                );
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json_with;
+
+    fn warnings(input: &str, options: &ParseOptions) -> Vec<(WarningCode, Range<usize>)> {
+        let (value, warnings) = parse_json_with_warnings(input, options).unwrap();
+        assert_eq!(value, parse_json_with(input, options).unwrap(), "{}", input);
+        warnings.into_iter().map(|w| (w.code, w.span)).collect()
+    }
+
+    #[test]
+    fn clean_documents_should_have_no_warnings() {
+        let input = r#"{"a": [1, 2.5, -0.125, 1e300, "x"], "b": {"a": null}}"#;
+        assert_eq!(warnings(input, &ParseOptions::strict()), []);
+    }
+
+    #[test]
+    fn duplicate_keys_should_point_at_the_later_key() {
+        let input = r#"{"a": 1, "b": {"a": 2}, "a": 3, "a": 4}"#;
+        let (value, found) = parse_json_with_warnings(input, &ParseOptions::strict()).unwrap();
+        assert_eq!(
+            value,
+            crate::json::parse_json(r#"{"a": 4, "b": {"a": 2}}"#).unwrap()
+        );
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].code, WarningCode::DuplicateKey);
+        assert_eq!(&input[found[0].span.clone()], r#""a""#);
+        assert_eq!(found[0].span, 24..27);
+        assert_eq!(found[1].span, 32..35);
+        assert_eq!(found[0].message, "duplicate key \"a\"; the last value wins");
+        // keys are compared unescaped
+        let escaped = r#"{"é": 1, "\u00e9": 2}"#;
+        assert_eq!(
+            warnings(escaped, &ParseOptions::strict()),
+            [(WarningCode::DuplicateKey, 10..18)]
+        );
+    }
+
+    #[test]
+    fn lossy_numbers_should_be_reported() {
+        let input = "[0.1, 3.14159265358979323846, 1e-400, 123456789012345678901]";
+        let found = warnings(input, &ParseOptions::strict());
+        assert_eq!(
+            found,
+            [
+                (WarningCode::PrecisionLoss, 6..28),
+                (WarningCode::PrecisionLoss, 30..36),
+                (WarningCode::IntegerAsFloat, 38..59),
+            ]
+        );
+        // exact numbers say nothing
+        let exact = ParseOptions::strict().preserve_number_text(true);
+        assert_eq!(warnings(input, &exact), []);
+        let nonfinite = ParseOptions::strict().allow_nonfinite(true);
+        assert_eq!(warnings("[Infinity, -Infinity]", &nonfinite), []);
+        let negative = warnings("-99999999999999999999", &ParseOptions::strict());
+        assert_eq!(negative, [(WarningCode::IntegerAsFloat, 0..21)]);
+    }
+
+    #[test]
+    fn nesting_near_the_limit_should_be_reported_once() {
+        let options = ParseOptions::strict().max_depth(4);
+        assert_eq!(warnings("[[[1]]]", &options), []);
+        assert_eq!(
+            warnings("[[[[1], [2]]]]", &options),
+            [(WarningCode::NearDepthLimit, 3..6)]
+        );
+        assert_eq!(warnings("[[[[1]]]]", &ParseOptions::strict()), []);
+        let unlimited = ParseOptions {
+            max_depth: None,
+            ..ParseOptions::strict()
+        };
+        assert_eq!(warnings("[[[[1]]]]", &unlimited), []);
+        assert!(parse_json_with_warnings("[[[[[1]]]]]", &options).is_err());
+    }
+
+    #[test]
+    fn warning_codes_should_have_snake_case_names() {
+        assert_eq!(WarningCode::DuplicateKey.as_str(), "duplicate_key");
+        assert_eq!(WarningCode::IntegerAsFloat.to_string(), "integer_as_float");
+    }
+}
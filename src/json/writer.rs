@@ -0,0 +1,533 @@
+use super::{
+    ser::{write_nested, write_string},
+    CompactFormatter, FormatOptions, JsonError, JsonValue, RawValue,
+};
+use std::io;
+
+/// Writes one document piece by piece, for output too large to build as a
+/// [`JsonValue`] first. Containers are opened with
+/// [`begin_array`](Self::begin_array) and [`begin_object`](Self::begin_object),
+/// whose builders borrow the writer until their `end()`, so a parent cannot
+/// be written to while a child is open. What the borrows cannot rule out,
+/// such as two keys in a row or a second root value, is an error and writes
+/// nothing.
+///
+/// Layout and escaping follow `options` as for
+/// [`JsonValue::to_writer_with`], so a document written here is byte for
+/// byte what its tree would print, as long as members come in the order the
+/// tree would write them: `sort_keys` and `key_order` only apply inside
+/// values handed over whole. `max_width` is ignored, since it needs to see a
+/// whole container before writing it.
+///
+/// A builder dropped without `end()` is not closed for you, because there
+/// would be nowhere to report a write failure. The writer is left unusable
+/// instead, as it is after any failed write: everything after,
+/// [`finish`](Self::finish) included, fails saying why.
+#[derive(Debug)]
+pub struct JsonWriter<W> {
+    w: W,
+    options: FormatOptions,
+    /// Open containers, innermost last.
+    stack: Vec<Frame>,
+    has_root: bool,
+    /// Why the writer is unusable, once it is.
+    broken: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    object: bool,
+    /// No entry written yet.
+    empty: bool,
+    /// An object key is waiting for its value.
+    after_key: bool,
+}
+
+impl<W: io::Write> JsonWriter<W> {
+    pub fn new(w: W, options: &FormatOptions) -> Self {
+        JsonWriter {
+            w,
+            options: options.clone(),
+            stack: Vec::new(),
+            has_root: false,
+            broken: None,
+        }
+    }
+
+    /// Open the root array.
+    pub fn begin_array(&mut self) -> Result<ArrayWriter<'_, W>, JsonError> {
+        self.root(|w| w.open(false))?;
+        Ok(ArrayWriter { writer: self })
+    }
+
+    /// Open the root object.
+    pub fn begin_object(&mut self) -> Result<ObjectWriter<'_, W>, JsonError> {
+        self.root(|w| w.open(true))?;
+        Ok(ObjectWriter { writer: self })
+    }
+
+    /// Write a whole root value.
+    pub fn value(&mut self, value: impl Into<JsonValue>) -> Result<(), JsonError> {
+        self.value_ref(&value.into())
+    }
+
+    /// [`value`](Self::value) without giving up the tree.
+    pub fn value_ref(&mut self, value: &JsonValue) -> Result<(), JsonError> {
+        self.root(|w| w.write_value(value))
+    }
+
+    /// The trailing newline if `options` asks for one, then the underlying
+    /// writer, flushed. Fails if no root value was written or one was left
+    /// open.
+    pub fn finish(mut self) -> Result<W, JsonError> {
+        self.check()?;
+        if !self.has_root {
+            return Err(JsonError::serialize("no value was written"));
+        }
+        if !self.stack.is_empty() {
+            return Err(JsonError::serialize("the root value was left open"));
+        }
+        if self.options.trailing_newline {
+            self.w.write_all(self.options.newline.as_str().as_bytes())?;
+        }
+        self.w.flush()?;
+        Ok(self.w)
+    }
+
+    fn check(&self) -> Result<(), JsonError> {
+        match &self.broken {
+            Some(why) => Err(JsonError::serialize(why.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Run `write`, leaving the writer unusable if it fails partway.
+    fn emit(
+        &mut self,
+        write: impl FnOnce(&mut Self) -> Result<(), JsonError>,
+    ) -> Result<(), JsonError> {
+        let result = write(self);
+        if let Err(e) = &result {
+            self.broken
+                .get_or_insert_with(|| format!("an earlier write failed: {}", e));
+        }
+        result
+    }
+
+    fn root(
+        &mut self,
+        write: impl FnOnce(&mut Self) -> Result<(), JsonError>,
+    ) -> Result<(), JsonError> {
+        self.check()?;
+        if self.has_root {
+            return Err(JsonError::serialize(
+                "the document already has a root value",
+            ));
+        }
+        self.has_root = true;
+        self.emit(write)
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.stack
+            .last_mut()
+            .expect("builders keep their frame open")
+    }
+
+    fn pretty(&self) -> bool {
+        self.options.indent.is_some()
+    }
+
+    /// Write the next value of the innermost container with `write`, after
+    /// a comma and line break in an array or right after the key in an
+    /// object. Fails in an object with no key waiting.
+    fn entry(
+        &mut self,
+        write: impl FnOnce(&mut Self) -> Result<(), JsonError>,
+    ) -> Result<(), JsonError> {
+        self.check()?;
+        let frame = *self.frame();
+        if frame.object && !frame.after_key {
+            return Err(JsonError::serialize("an object value needs a key first"));
+        }
+        self.emit(|w| {
+            if frame.object {
+                w.frame().after_key = false;
+            } else {
+                w.separator(frame.empty)?;
+            }
+            write(w)
+        })
+    }
+
+    fn key(&mut self, key: &str) -> Result<(), JsonError> {
+        self.check()?;
+        let frame = *self.frame();
+        if frame.after_key {
+            return Err(JsonError::serialize(format!(
+                "key {:?} written while the previous key still needs a value",
+                key
+            )));
+        }
+        self.emit(|w| {
+            w.separator(frame.empty)?;
+            write_string(&mut w.w, &mut CompactFormatter, key, &w.options.escape)?;
+            let spaced = w.pretty() || w.options.space_after_colon;
+            w.w.write_all(if spaced { b": " } else { b":" })?;
+            w.frame().after_key = true;
+            Ok(())
+        })
+    }
+
+    /// What goes before an entry; `first` is whether it is the first one.
+    fn separator(&mut self, first: bool) -> Result<(), JsonError> {
+        self.frame().empty = false;
+        let level = self.stack.len();
+        let Some(indent) = &self.options.indent else {
+            match (first, self.options.space_after_comma) {
+                (true, _) => {}
+                (false, true) => self.w.write_all(b", ")?,
+                (false, false) => self.w.write_all(b",")?,
+            }
+            return Ok(());
+        };
+        let unit = indent.unit();
+        let newline = self.options.newline.as_str().as_bytes();
+        if !self.options.diff_friendly {
+            if !first {
+                self.w.write_all(b",")?;
+            }
+            self.w.write_all(newline)?;
+            return self.indent(&unit, level);
+        }
+        // as PrettyFormatter's diff layout: the comma in the last indent
+        self.w.write_all(newline)?;
+        self.indent(&unit, level - 1)?;
+        if first {
+            self.w.write_all(unit.as_bytes())?;
+        } else {
+            self.w.write_all(b",")?;
+            self.w
+                .write_all(unit.strip_prefix(' ').unwrap_or(&unit).as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn indent(&mut self, unit: &str, level: usize) -> Result<(), JsonError> {
+        for _ in 0..level {
+            self.w.write_all(unit.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_value(&mut self, value: &JsonValue) -> Result<(), JsonError> {
+        let level = self.stack.len();
+        write_nested(value, &mut self.w, &self.options, level).map_err(unwrap_io)
+    }
+
+    fn write_raw(&mut self, raw: &RawValue) -> Result<(), JsonError> {
+        Ok(self.w.write_all(raw.get().as_bytes())?)
+    }
+
+    fn open(&mut self, object: bool) -> Result<(), JsonError> {
+        self.w.write_all(if object { b"{" } else { b"[" })?;
+        self.stack.push(Frame {
+            object,
+            empty: true,
+            after_key: false,
+        });
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), JsonError> {
+        self.check()?;
+        let frame = *self.frame();
+        if frame.after_key {
+            return Err(JsonError::serialize(
+                "object closed while a key still needs a value",
+            ));
+        }
+        self.emit(|w| {
+            w.stack.pop();
+            if let Some(indent) = &w.options.indent {
+                if !frame.empty || w.options.diff_friendly {
+                    w.w.write_all(w.options.newline.as_str().as_bytes())?;
+                    w.indent(&indent.unit(), w.stack.len())?;
+                }
+            }
+            w.w.write_all(if frame.object { b"}" } else { b"]" })?;
+            Ok(())
+        })
+    }
+
+    fn abandon(&mut self, what: &str) {
+        self.broken
+            .get_or_insert_with(|| format!("an {} was dropped without end()", what));
+    }
+}
+
+/// Serialization errors travel through `io` wrapped; take them back out.
+fn unwrap_io(e: io::Error) -> JsonError {
+    if !e.get_ref().is_some_and(|inner| inner.is::<JsonError>()) {
+        return JsonError::Io(e);
+    }
+    let inner = e.into_inner().and_then(|e| e.downcast::<JsonError>().ok());
+    *inner.expect("checked above")
+}
+
+/// An open array of a [`JsonWriter`]; close it with [`end`](Self::end).
+#[derive(Debug)]
+pub struct ArrayWriter<'w, W: io::Write> {
+    writer: &'w mut JsonWriter<W>,
+}
+
+impl<W: io::Write> ArrayWriter<'_, W> {
+    /// Append a whole value.
+    pub fn value(&mut self, value: impl Into<JsonValue>) -> Result<&mut Self, JsonError> {
+        self.value_ref(&value.into())
+    }
+
+    /// [`value`](Self::value) without giving up the tree.
+    pub fn value_ref(&mut self, value: &JsonValue) -> Result<&mut Self, JsonError> {
+        self.writer.entry(|w| w.write_value(value))?;
+        Ok(self)
+    }
+
+    /// Append `raw` verbatim.
+    pub fn raw(&mut self, raw: &RawValue) -> Result<&mut Self, JsonError> {
+        self.writer.entry(|w| w.write_raw(raw))?;
+        Ok(self)
+    }
+
+    /// Open an array as the next element.
+    pub fn begin_array(&mut self) -> Result<ArrayWriter<'_, W>, JsonError> {
+        self.writer.entry(|w| w.open(false))?;
+        Ok(ArrayWriter {
+            writer: self.writer,
+        })
+    }
+
+    /// Open an object as the next element.
+    pub fn begin_object(&mut self) -> Result<ObjectWriter<'_, W>, JsonError> {
+        self.writer.entry(|w| w.open(true))?;
+        Ok(ObjectWriter {
+            writer: self.writer,
+        })
+    }
+
+    /// Close the array.
+    pub fn end(self) -> Result<(), JsonError> {
+        let result = self.writer.close();
+        if result.is_ok() {
+            std::mem::forget(self);
+        }
+        result
+    }
+}
+
+impl<W: io::Write> Drop for ArrayWriter<'_, W> {
+    fn drop(&mut self) {
+        self.writer.abandon("array");
+    }
+}
+
+/// An open object of a [`JsonWriter`]: [`key`](Self::key) and a value in
+/// turn, then [`end`](Self::end).
+#[derive(Debug)]
+pub struct ObjectWriter<'w, W: io::Write> {
+    writer: &'w mut JsonWriter<W>,
+}
+
+impl<W: io::Write> ObjectWriter<'_, W> {
+    /// Start a member; its value is whatever is written next.
+    pub fn key(&mut self, key: &str) -> Result<&mut Self, JsonError> {
+        self.writer.key(key)?;
+        Ok(self)
+    }
+
+    /// The value for the last key.
+    pub fn value(&mut self, value: impl Into<JsonValue>) -> Result<&mut Self, JsonError> {
+        self.value_ref(&value.into())
+    }
+
+    /// [`value`](Self::value) without giving up the tree.
+    pub fn value_ref(&mut self, value: &JsonValue) -> Result<&mut Self, JsonError> {
+        self.writer.entry(|w| w.write_value(value))?;
+        Ok(self)
+    }
+
+    /// `raw`, verbatim, as the value for the last key.
+    pub fn raw(&mut self, raw: &RawValue) -> Result<&mut Self, JsonError> {
+        self.writer.entry(|w| w.write_raw(raw))?;
+        Ok(self)
+    }
+
+    /// Open an array as the value for the last key.
+    pub fn begin_array(&mut self) -> Result<ArrayWriter<'_, W>, JsonError> {
+        self.writer.entry(|w| w.open(false))?;
+        Ok(ArrayWriter {
+            writer: self.writer,
+        })
+    }
+
+    /// Open an object as the value for the last key.
+    pub fn begin_object(&mut self) -> Result<ObjectWriter<'_, W>, JsonError> {
+        self.writer.entry(|w| w.open(true))?;
+        Ok(ObjectWriter {
+            writer: self.writer,
+        })
+    }
+
+    /// Close the object. Fails if the last key has no value.
+    pub fn end(self) -> Result<(), JsonError> {
+        let result = self.writer.close();
+        if result.is_ok() {
+            std::mem::forget(self);
+        }
+        result
+    }
+}
+
+impl<W: io::Write> Drop for ObjectWriter<'_, W> {
+    fn drop(&mut self) {
+        self.writer.abandon("object");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{parse_json, ErrorCode, Indent, Newline};
+
+    const DOC: &str = r#"{"name": "x\t<y>", "tags": ["a", [], {}, [1, 2.5]],
+        "nested": {"deep": {"list": [null, true, {"k": "é"}]}, "empty": {}}, "n": -3}"#;
+
+    /// DOC again, through the builders, with `nested` handed over whole and
+    /// the other keys in sorted order.
+    fn build(options: &FormatOptions) -> Result<Vec<u8>, JsonError> {
+        let tree = parse_json(DOC).unwrap();
+        let JsonValue::Object(members) = &tree else {
+            unreachable!()
+        };
+        let nested = members.get("nested").unwrap();
+        let mut writer = JsonWriter::new(Vec::new(), options);
+        let mut root = writer.begin_object()?;
+        root.key("n")?.value(-3i64)?;
+        root.key("name")?.value("x\t<y>")?;
+        root.key("nested")?.value_ref(nested)?;
+        root.key("tags")?;
+        let mut tags = root.begin_array()?;
+        tags.value("a")?;
+        tags.begin_array()?.end()?;
+        tags.begin_object()?.end()?;
+        let mut numbers = tags.begin_array()?;
+        numbers
+            .value(1i64)?
+            .raw(&RawValue::from_string("2.5").unwrap())?;
+        numbers.end()?;
+        tags.end()?;
+        root.end()?;
+        writer.finish()
+    }
+
+    #[test]
+    fn writer_should_match_the_tree_serializer() {
+        let tree = parse_json(DOC).unwrap();
+        for options in [
+            FormatOptions::compact(),
+            FormatOptions::pretty(),
+            FormatOptions::pretty()
+                .indent(Indent::tab())
+                .newline(Newline::CrLf)
+                .trailing_newline(true),
+            FormatOptions::compact()
+                .space_after_colon(true)
+                .space_after_comma(true),
+            FormatOptions::pretty().spaces(4).diff_friendly(true),
+        ] {
+            let options = options.sort_keys(true);
+            let built = String::from_utf8(build(&options).unwrap()).unwrap();
+            assert_eq!(built, tree.to_string_with(&options), "{:?}", options);
+        }
+        let mut writer = JsonWriter::new(Vec::new(), &FormatOptions::pretty());
+        writer.value(true).unwrap();
+        assert_eq!(writer.finish().unwrap(), b"true");
+    }
+
+    fn misuse(write: impl FnOnce(&mut JsonWriter<Vec<u8>>) -> Result<(), JsonError>) -> String {
+        let mut writer = JsonWriter::new(Vec::new(), &FormatOptions::compact());
+        let err = write(&mut writer).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Serialize);
+        err.to_string()
+    }
+
+    #[test]
+    fn misuse_should_be_an_error() {
+        let two_keys = misuse(|w| {
+            w.begin_object()?.key("a")?.key("b")?;
+            Ok(())
+        });
+        assert!(two_keys.contains("key \"b\""), "{}", two_keys);
+        let no_key = misuse(|w| {
+            let mut obj = w.begin_object()?;
+            obj.value(1i64)?;
+            obj.end()
+        });
+        assert!(no_key.contains("needs a key"), "{}", no_key);
+        let dangling = misuse(|w| {
+            let mut obj = w.begin_object()?;
+            obj.key("a")?;
+            obj.end()
+        });
+        assert!(dangling.contains("still needs a value"), "{}", dangling);
+        let two_roots = misuse(|w| {
+            w.value(1i64)?;
+            w.begin_array()?.end()
+        });
+        assert!(two_roots.contains("already"), "{}", two_roots);
+        let writer = JsonWriter::new(Vec::new(), &FormatOptions::compact());
+        assert!(writer.finish().is_err());
+        // nothing was written by the calls that failed
+        let mut writer = JsonWriter::new(Vec::new(), &FormatOptions::compact());
+        let mut obj = writer.begin_object().unwrap();
+        obj.key("a").unwrap();
+        assert!(obj.key("b").is_err());
+        obj.value(1i64).unwrap();
+        obj.end().unwrap();
+        assert_eq!(writer.finish().unwrap(), br#"{"a":1}"#);
+    }
+
+    #[test]
+    fn dropping_an_open_builder_should_poison_the_writer() {
+        let mut writer = JsonWriter::new(Vec::new(), &FormatOptions::compact());
+        let mut arr = writer.begin_array().unwrap();
+        arr.value(1i64).unwrap();
+        drop(arr.begin_object().unwrap());
+        let err = arr.value(2i64).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with(": an object was dropped without end()"));
+        assert!(arr.end().is_err());
+        let err = writer.finish().unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with(": an object was dropped without end()"));
+    }
+
+    #[test]
+    fn failed_values_should_poison_the_writer() {
+        let mut writer = JsonWriter::new(Vec::new(), &FormatOptions::compact());
+        let mut arr = writer.begin_array().unwrap();
+        arr.value(1i64).unwrap();
+        // the comma is out before the value fails
+        let err = arr.value(f64::NAN).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Serialize);
+        assert!(err.to_string().contains("NaN"), "{}", err);
+        let err = arr.value(2i64).unwrap_err();
+        assert!(
+            err.to_string().contains("an earlier write failed"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use super::{JsonValue, Num};
+
+/// Render `value` as block-style YAML with two-space indentation. Object
+/// keys are sorted. Strings are written plain unless YAML would read them as
+/// something else (`"yes"`, `"1.0"`, `"null"`) or they contain characters
+/// that are special to it, in which case they are double-quoted. Strings
+/// with line breaks become literal block scalars (`|`). The output ends in a
+/// newline.
+pub fn to_yaml_string(value: &JsonValue) -> String {
+    let mut out = String::new();
+    write_node(&mut out, value, 0);
+    out.push('\n');
+    out
+}
+
+/// Write `value` starting at the current position; continuation lines are
+/// indented by `indent` spaces.
+fn write_node(out: &mut String, value: &JsonValue, indent: usize) {
+    match value {
+        JsonValue::Array(arr) if !arr.is_empty() => {
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    newline(out, indent);
+                }
+                out.push('-');
+                write_child(out, item, indent + 2, true);
+            }
+        }
+        JsonValue::Object(obj) if !obj.is_empty() => {
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_unstable_by_key(|(k, _)| *k);
+            for (i, (k, v)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    newline(out, indent);
+                }
+                write_string(out, k);
+                out.push(':');
+                write_child(out, v, indent + 2, false);
+            }
+        }
+        JsonValue::Raw(raw) => match raw.parse() {
+            Ok(v) => write_node(out, &v, indent),
+            Err(_) => out.push_str(raw.get()),
+        },
+        scalar => write_scalar(out, scalar, indent),
+    }
+}
+
+/// Write the value after a `-` or `key:`. Mappings and sequences nested in a
+/// sequence item start on the same line; under a key they start on the next.
+fn write_child(out: &mut String, value: &JsonValue, indent: usize, in_sequence: bool) {
+    let nested = match value {
+        JsonValue::Array(arr) => !arr.is_empty(),
+        JsonValue::Object(obj) => !obj.is_empty(),
+        _ => false,
+    };
+    if nested && !in_sequence {
+        newline(out, indent);
+    } else {
+        out.push(' ');
+    }
+    write_node(out, value, indent);
+}
+
+fn write_scalar(out: &mut String, value: &JsonValue, indent: usize) {
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonValue::Number(Num::Float(f)) if f.is_nan() => out.push_str(".nan"),
+        JsonValue::Number(Num::Float(f)) if f.is_infinite() => {
+            out.push_str(if *f > 0.0 { ".inf" } else { "-.inf" })
+        }
+        JsonValue::Number(n) => out.push_str(&JsonValue::Number(n.clone()).to_json_string()),
+        JsonValue::String(s) if s.contains('\n') && block_safe(s) => write_literal(out, s, indent),
+        JsonValue::String(s) => write_string(out, s),
+        JsonValue::Array(_) => out.push_str("[]"),
+        JsonValue::Object(_) => out.push_str("{}"),
+        JsonValue::Raw(raw) => out.push_str(raw.get()),
+    }
+}
+
+fn newline(out: &mut String, indent: usize) {
+    out.push('\n');
+    out.extend(std::iter::repeat_n(' ', indent));
+}
+
+/// Whether `s` survives a literal block scalar: no other control characters,
+/// and no line made only of spaces, which YAML would fold into the indent.
+fn block_safe(s: &str) -> bool {
+    !s.chars().any(|c| c.is_control() && c != '\n' && c != '\t')
+        && !s
+            .split('\n')
+            .any(|l| !l.is_empty() && l.trim_start_matches(' ').is_empty())
+}
+
+/// A `|` block scalar, with an indentation indicator when the text starts
+/// with a space and a chomping indicator for the trailing newlines.
+fn write_literal(out: &mut String, s: &str, indent: usize) {
+    out.push('|');
+    if s.starts_with(' ') {
+        out.push('2');
+    }
+    let body = s.trim_end_matches('\n');
+    let trailing = s.len() - body.len();
+    match trailing {
+        0 => out.push('-'),
+        1 => {}
+        _ => out.push('+'),
+    }
+    for line in body
+        .split('\n')
+        .chain(std::iter::repeat_n("", trailing.saturating_sub(1)))
+    {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            newline(out, indent);
+            out.push_str(line);
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    if needs_quotes(s) {
+        out.push_str(&JsonValue::from(s).to_json_string());
+    } else {
+        out.push_str(s);
+    }
+}
+
+/// Words YAML 1.1 or 1.2 parsers read as booleans or null.
+const RESERVED: [&str; 12] = [
+    "null", "~", "true", "false", "yes", "no", "on", "off", "y", "n", ".nan", ".inf",
+];
+
+fn needs_quotes(s: &str) -> bool {
+    let lower = s.to_ascii_lowercase();
+    s.is_empty()
+        || RESERVED.contains(&lower.trim_start_matches(['-', '+']))
+        || looks_numeric(s)
+        || s.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%',
+            '@', '`', ' ',
+        ])
+        || s.ends_with([' ', ':'])
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.contains(|c: char| c.is_control())
+}
+
+/// Numbers in any notation a YAML parser might accept: decimal, with
+/// underscores, or hex, octal and binary prefixes.
+fn looks_numeric(s: &str) -> bool {
+    let unsigned = s.trim_start_matches(['-', '+']);
+    let digits = unsigned.replace('_', "");
+    digits.parse::<f64>().is_ok()
+        && !digits
+            .chars()
+            .any(|c| c.is_ascii_alphabetic() && c != 'e' && c != 'E')
+        || ["0x", "0o", "0b"].iter().any(|p| unsigned.starts_with(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn to_yaml_string_should_match_golden_output() {
+        let value = parse_json(
+            r##"{
+                "name": "John Doe",
+                "age": 30,
+                "student": false,
+                "answer": "yes",
+                "version": "1.0",
+                "zip": "010001",
+                "nickname": null,
+                "marks": [90.0, -80.5, 85],
+                "address": {"city": "New York", "note": "ring: twice", "tags": []},
+                "notes": "line one\nline two\n",
+                "script": "echo hi\n\necho bye",
+                "jobs": [
+                    {"name": "build", "steps": ["fetch", "compile"]},
+                    [1, [2, 3]],
+                    {}
+                ],
+                "- dash": "#hash",
+                "": "empty key"
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(
+            to_yaml_string(&value),
+            include_str!("../../tests/golden/document.yaml")
+        );
+    }
+
+    #[test]
+    fn scalars_should_be_quoted_only_when_ambiguous() {
+        for s in [
+            "yes", "No", "null", "~", "1.0", "-5", "1e3", "0x1f", "1_000", ".inf", "",
+        ] {
+            assert!(needs_quotes(s), "{}", s);
+        }
+        for s in ["hello world", "a-b", "1.0.0", "e", "v1", "C:\\tmp", "x#y"] {
+            assert!(!needs_quotes(s), "{}", s);
+        }
+        assert_eq!(
+            to_yaml_string(&JsonValue::from("tab\there")),
+            "\"tab\\there\"\n"
+        );
+        assert_eq!(
+            to_yaml_string(&JsonValue::from(f64::NEG_INFINITY)),
+            "-.inf\n"
+        );
+        assert_eq!(to_yaml_string(&parse_json("[]").unwrap()), "[]\n");
+    }
+
+    #[test]
+    fn multi_line_strings_should_use_block_scalars() {
+        let yaml = |s: &str| to_yaml_string(&parse_json(&format!(r#"{{"k": {:?}}}"#, s)).unwrap());
+        assert_eq!(yaml("a\nb"), "k: |-\n  a\n  b\n");
+        assert_eq!(yaml("a\nb\n"), "k: |\n  a\n  b\n");
+        assert_eq!(yaml("a\n\n"), "k: |+\n  a\n\n");
+        assert_eq!(yaml("  a\nb\n"), "k: |2\n    a\n  b\n");
+        // whitespace-only lines cannot be kept in a block scalar
+        assert_eq!(yaml("a\n  \nb"), "k: \"a\\n  \\nb\"\n");
+    }
+}
@@ -0,0 +1,1221 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+/// Run the `json` binary with `args`, feeding it `stdin`.
+fn json(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_json"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the binary runs");
+    // the binary may exit without reading, closing the pipe early
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+/// A file named `name` holding `contents`, unique to this test binary.
+fn fixture(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("json-cli-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn cli_should_pretty_print_stdin_and_files() {
+    let single = r#"[1, {"name": "Zoë"}, null]"#;
+    let expected = "[\n  1,\n  {\n    \"name\": \"Zoë\"\n  },\n  null\n]\n";
+    for args in [&[][..], &["-"]] {
+        let output = json(args, single);
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(stdout(&output), expected);
+    }
+    let path = fixture("pretty.json", single);
+    let output = json(&[path.to_str().unwrap()], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), expected);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cli_should_report_errors_with_positions() {
+    let output = json(&["missing.json"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr(&output).contains("missing.json"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(output.stdout.is_empty());
+
+    let output = json(&[], "{\n  \"a\": [1 2]\n}");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "json: <stdin>:2:11: unexpected character '2'\n"
+    );
+
+    let path = fixture("bad.json", "[\n\"é\",\n  tru]");
+    let output = json(&[path.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        format!("json: {}:3:3: unexpected character 't'\n", path.display())
+    );
+    fs::remove_file(path).unwrap();
+
+    let output = json(&["--bogus"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("json: unknown option --bogus"));
+}
+
+#[test]
+fn cli_should_format_as_the_flags_say() {
+    let doc = r#"[1, {"a": [true, "é"]}, []]"#;
+    for (args, expected) in [
+        (&[][..], "[\n  1,\n  {\n    \"a\": [\n      true,\n      \"é\"\n    ]\n  },\n  []\n]\n"),
+        (&["--minify"], "[1,{\"a\":[true,\"é\"]},[]]\n"),
+        (
+            &["--indent", "4"],
+            "[\n    1,\n    {\n        \"a\": [\n            true,\n            \"é\"\n        ]\n    },\n    []\n]\n",
+        ),
+        (&["--tab"], "[\n\t1,\n\t{\n\t\t\"a\": [\n\t\t\ttrue,\n\t\t\t\"é\"\n\t\t]\n\t},\n\t[]\n]\n"),
+        (&["--indent", "0"], "[\n1,\n{\n\"a\": [\ntrue,\n\"é\"\n]\n},\n[]\n]\n"),
+    ] {
+        let output = json(args, doc);
+        assert!(output.status.success(), "{:?}: {}", args, stderr(&output));
+        assert_eq!(stdout(&output), expected, "{:?}", args);
+    }
+
+    let path = fixture("out.json", "");
+    let output = json(&["--minify", "-o", path.to_str().unwrap(), "-"], doc);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "[1,{\"a\":[true,\"é\"]},[]]\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cli_should_reject_conflicting_flags_before_parsing() {
+    for (args, message) in [
+        (
+            &["--minify", "--indent", "2"][..],
+            "--minify cannot be combined with --indent or --tab",
+        ),
+        (
+            &["--tab", "--minify"],
+            "--minify cannot be combined with --indent or --tab",
+        ),
+        (
+            &["--indent", "2", "--tab"],
+            "--indent and --tab cannot be combined",
+        ),
+        (&["--indent", "two"], "--indent needs a number of spaces"),
+        (&["-o"], "-o needs a value"),
+    ] {
+        // the input is broken too, but the usage error comes first
+        let output = json(args, "[1,");
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(
+            stderr(&output).starts_with(&format!("json: {}\n", message)),
+            "{:?}: {}",
+            args,
+            stderr(&output)
+        );
+        assert!(output.stdout.is_empty());
+    }
+}
+
+#[test]
+fn cli_should_sort_keys_at_every_level() {
+    let doc = r#"{"zeta": {"b": 1, "a": [{"y": 2, "x": 3}, 0]}, "alpha": [3, 1, 2], "Mid": null}"#;
+    let output = json(&["--sort-keys", "--minify"], doc);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "{\"Mid\":null,\"alpha\":[3,1,2],\"zeta\":{\"a\":[{\"x\":3,\"y\":2},0],\"b\":1}}\n"
+    );
+    let output = json(&["--indent", "1", "--sort-keys"], doc);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "{\n \"Mid\": null,\n \"alpha\": [\n  3,\n  1,\n  2\n ],\n \"zeta\": {\n  \"a\": [\n   {\n    \"x\": 3,\n    \"y\": 2\n   },\n   0\n  ],\n  \"b\": 1\n }\n}\n"
+    );
+}
+
+#[test]
+fn cli_get_should_print_values_at_pointers() {
+    let doc = r#"{"users": [{"name": "Zoë \"Z\"", "tags": ["a", 1]}], "a/b": {"~": true}}"#;
+    let path = fixture("get.json", doc);
+    let file = path.to_str().unwrap();
+    for (args, expected) in [
+        (&["get", "/users/0/name", file][..], "Zoë \"Z\"\n"),
+        (
+            &["get", "--json", "/users/0/name", file],
+            "\"Zoë \\\"Z\\\"\"\n",
+        ),
+        (&["get", "/users/0/tags", file], "[\"a\",1]\n"),
+        (
+            &["get", "/users/0/tags/1", "/a~1b", file],
+            "1\n{\"~\":true}\n",
+        ),
+        (&["get", "--path", "users[0].tags[0]", file], "a\n"),
+        (
+            &["get", "--path", r#"["a/b"].~"#, "/users/0/tags/0", file],
+            "true\na\n",
+        ),
+    ] {
+        let output = json(args, "");
+        assert!(output.status.success(), "{:?}: {}", args, stderr(&output));
+        assert_eq!(stdout(&output), expected, "{:?}", args);
+    }
+    // with one argument it is the pointer, and the input is stdin
+    let output = json(&["get", "/users/0/name"], doc);
+    assert_eq!(stdout(&output), "Zoë \"Z\"\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cli_get_should_fail_when_a_pointer_does_not_resolve() {
+    let doc = r#"{"a": [1, 2]}"#;
+    let output = json(&["get", "/a/5", "/a/0", "--path", "b.c", "-"], doc);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "1\n");
+    assert_eq!(
+        stderr(&output),
+        "json: /a/5: not found\njson: b.c: not found\n"
+    );
+
+    for (args, message) in [
+        (
+            &["get", "a"][..],
+            "a is not a JSON Pointer, which starts with /",
+        ),
+        (&["get", "--path", "a..b"], "invalid path a..b: empty name"),
+        (&["get"], "no pointer or path given"),
+    ] {
+        let output = json(args, doc);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(
+            stderr(&output).starts_with(&format!("json: {}\n", message)),
+            "{:?}: {}",
+            args,
+            stderr(&output)
+        );
+    }
+}
+
+#[test]
+fn cli_validate_should_report_each_invalid_file() {
+    let good = fixture("valid.json", "{\"a\": [1, 2]}");
+    let bad = fixture("invalid.json", "{\n  \"a\": [1, 2,]\n}");
+    let (good, bad) = (good.to_str().unwrap(), bad.to_str().unwrap());
+
+    let output = json(&["validate", good], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+
+    let output = json(&["validate", good, bad], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{}:2:13: trailing commas are not allowed; enable allow_trailing_commas\n",
+            bad
+        )
+    );
+
+    let output = json(&["validate", "--format", "json", bad, good, "-"], "[1");
+    assert_eq!(output.status.code(), Some(1));
+    let lines: Vec<_> = stdout(&output).lines().collect();
+    assert_eq!(lines.len(), 2, "{}", stdout(&output));
+    let expected = format!(
+        r#"{{"column":13,"file":{:?},"line":2,"message":"trailing commas are not allowed; enable allow_trailing_commas","offset":14}}"#,
+        bad
+    );
+    assert_eq!(lines[0], expected);
+    assert!(lines[1].starts_with(r#"{"column":3,"file":"<stdin>","line":1,"#));
+
+    let output = json(&["validate", "--allow-trailing-commas", good, bad], "");
+    assert!(output.status.success(), "{}", stdout(&output));
+    fs::remove_file(good).unwrap();
+    fs::remove_file(bad).unwrap();
+}
+
+#[test]
+fn cli_diff_should_report_or_patch_the_differences() {
+    let a = fixture(
+        "diff-a.json",
+        r#"{"name": "x", "n": 1, "tags": ["a", "b"], "old": true}"#,
+    );
+    let same = fixture(
+        "diff-same.json",
+        "{\"tags\": [\"a\", \"b\"],\n \"old\": true, \"n\": 1.0, \"name\": \"x\"}",
+    );
+    let b = fixture(
+        "diff-b.json",
+        r#"{"name": "y", "n": 1, "tags": ["a", "b", "c"], "new": null}"#,
+    );
+    let (a, same, b) = (
+        a.to_str().unwrap(),
+        same.to_str().unwrap(),
+        b.to_str().unwrap(),
+    );
+
+    for args in [&["diff", a, same][..], &["diff", "--exit-code", a, same]] {
+        let output = json(args, "");
+        assert!(output.status.success(), "{:?}: {}", args, stderr(&output));
+        assert!(output.stdout.is_empty(), "{}", stdout(&output));
+    }
+
+    let output = json(&["diff", a, b], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "~ /name: \"x\" -> \"y\"\n+ /new: null\n- /old: true\n+ /tags/2: \"c\"\n"
+    );
+    let output = json(&["diff", "--exit-code", "-", a], "[1]");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout(&output).starts_with("~ (root): [1] -> {"),
+        "{}",
+        stdout(&output)
+    );
+
+    let output = json(&["diff", "--patch", a, b], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let patch = stdout(&output);
+    let expected = r#"[
+  {
+    "op": "replace",
+    "path": "/name",
+    "value": "y"
+  },
+  {
+    "op": "add",
+    "path": "/new",
+    "value": null
+  },
+  {
+    "op": "remove",
+    "path": "/old"
+  },
+  {
+    "op": "add",
+    "path": "/tags/2",
+    "value": "c"
+  }
+]
+"#;
+    assert_eq!(patch, expected);
+    let output = json(&["diff", "--patch", "--exit-code", a, same], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "[]\n");
+
+    for path in [a, same, b] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn cli_merge_should_layer_documents_left_to_right() {
+    let base = fixture(
+        "merge-base.json",
+        r#"{"name": "app", "ports": [80], "db": {"host": "localhost", "pool": 5}, "debug": true}"#,
+    );
+    let site = fixture(
+        "merge-site.json",
+        r#"{"ports": [443], "db": {"host": "db.internal", "user": null}}"#,
+    );
+    let local = fixture("merge-local.json", r#"{"db": {"pool": 20}, "debug": null}"#);
+    let (base, site, local) = (
+        base.to_str().unwrap(),
+        site.to_str().unwrap(),
+        local.to_str().unwrap(),
+    );
+    for (flags, expected) in [
+        (
+            &[][..],
+            r#"{"db":{"host":"db.internal","pool":20,"user":null},"debug":null,"name":"app","ports":[443]}"#,
+        ),
+        (
+            &["--array-strategy", "concat"],
+            r#"{"db":{"host":"db.internal","pool":20,"user":null},"debug":null,"name":"app","ports":[80,443]}"#,
+        ),
+        (
+            &["--merge-patch"],
+            r#"{"db":{"host":"db.internal","pool":20},"name":"app","ports":[443]}"#,
+        ),
+    ] {
+        let mut args = vec!["merge", "--minify", "--sort-keys"];
+        args.extend(flags);
+        args.extend([base, site, local]);
+        let output = json(&args, "");
+        assert!(output.status.success(), "{:?}: {}", flags, stderr(&output));
+        assert_eq!(stdout(&output), format!("{}\n", expected), "{:?}", flags);
+    }
+
+    // one layer can come from a pipe
+    let output = json(
+        &["merge", "--minify", base, "-", local],
+        r#"{"name": "piped", "ports": [], "db": 1}"#,
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let merged = stdout(&output);
+    assert!(merged.contains(r#""name":"piped""#) && merged.contains(r#""db":{"pool":20}"#));
+
+    let output = json(
+        &["merge", "--merge-patch", "--array-strategy", "concat", base],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(2));
+    for path in [base, site, local] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn cli_should_convert_between_ndjson_and_arrays() {
+    let lines = "{\"id\":[1,\"a\"]}\n\n\"two\"\r\n[3,{\"x\":null}]\n";
+    let output = json(&["seq2array"], lines);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let array = stdout(&output).to_string();
+    assert_eq!(array, "[{\"id\":[1,\"a\"]},\"two\",[3,{\"x\":null}]]\n");
+
+    let path = fixture("array.json", &array);
+    let output = json(&["array2seq", path.to_str().unwrap()], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "{\"id\":[1,\"a\"]}\n\"two\"\n[3,{\"x\":null}]\n"
+    );
+    fs::remove_file(path).unwrap();
+
+    let output = json(&["seq2array", "--indent", "1"], "1\n[2]\n");
+    assert_eq!(stdout(&output), "[\n 1,\n [\n  2\n ]\n]\n");
+
+    let output = json(&["array2seq"], "[1,\n {\"a\": tru}]");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "json: element 1: <stdin>:2:8: unexpected character 't'\n"
+    );
+}
+
+#[test]
+fn cli_seq2array_should_stop_at_or_skip_invalid_lines() {
+    let lines = "1\n{\"a\": [2,}\n3\n";
+    for args in [&["seq2array"][..], &["seq2array", "--indent", "1"]] {
+        let output = json(args, lines);
+        assert_eq!(output.status.code(), Some(1));
+        // not even the `[1` before the invalid line
+        assert!(output.stdout.is_empty(), "{}", stdout(&output));
+        assert_eq!(
+            stderr(&output),
+            "json: <stdin>:2:10: unexpected character '}'\n"
+        );
+    }
+
+    let output = json(&["seq2array", "--skip-invalid"], lines);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "[1,3]\n");
+    assert_eq!(
+        stderr(&output),
+        "json: <stdin>:2:10: unexpected character '}'\n"
+    );
+}
+
+#[test]
+fn cli_fmt_should_check_and_rewrite_files() {
+    let formatted = fixture("fmt-ok.json", "[\n  1,\n  2\n]\n");
+    let messy = fixture("fmt-messy.json", "[1,\n2]");
+    let broken = fixture("fmt-broken.json", "[1,\n2");
+    let names = [&formatted, &messy, &broken].map(|path| path.to_str().unwrap().to_string());
+    let [formatted, messy, broken] = &names;
+
+    let output = json(&["fmt", formatted, messy], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "[\n  1,\n  2\n]\n[\n  1,\n  2\n]\n");
+
+    let output = json(&["fmt", "--check", formatted, messy], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), format!("{}\n", messy));
+
+    for _ in 0..2 {
+        let output = json(&["fmt", "--write", "--indent", "1", messy, broken], "");
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        assert_eq!(
+            stderr(&output),
+            format!("json: {}:2:2: unexpected end of input\n", broken)
+        );
+        // no trailing newline, as the original has none
+        assert_eq!(fs::read_to_string(messy).unwrap(), "[\n 1,\n 2\n]");
+        assert_eq!(fs::read_to_string(broken).unwrap(), "[1,\n2");
+    }
+    let output = json(&["fmt", "--check", "--indent", "1", messy], "");
+    assert!(output.status.success(), "{}", stdout(&output));
+
+    for path in names {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn cli_fmt_should_keep_the_key_order_of_objects() {
+    let path = fixture(
+        "fmt-object.json",
+        r#"{"d":1,"b":{"z":2,"a":3},"c":4,"a":5,"e":1.50}"#,
+    );
+    let name = path.to_str().unwrap();
+
+    let output = json(&["fmt", "--write", name], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let written = fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        written,
+        "{\n  \"d\": 1,\n  \"b\": {\n    \"z\": 2,\n    \"a\": 3\n  },\n  \"c\": 4,\n  \"a\": 5,\n  \"e\": 1.50\n}"
+    );
+    let output = json(&["fmt", "--check", name], "");
+    assert!(output.status.success(), "{}", stdout(&output));
+    // a second rewrite leaves the file as it is
+    let output = json(&["fmt", "--write", name], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(&path).unwrap(), written);
+
+    let output = json(&["fmt", "--sort-keys", "--minify", name], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "{\"a\":5,\"b\":{\"a\":3,\"z\":2},\"c\":4,\"d\":1,\"e\":1.5}\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cli_should_convert_to_and_from_csv() {
+    let path = fixture(
+        "rows.json",
+        r#"[{"name": "Smith, J.", "age": 40, "city": {"name": "NY"}}, {"name": "say \"hi\"", "zip": "007"}]"#,
+    );
+    let file = path.to_str().unwrap();
+    let output = json(&["to-csv", "--flatten", file], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let csv = "age,city.name,name,zip\r\n40,NY,\"Smith, J.\",\r\n,,\"say \"\"hi\"\"\",007\r\n";
+    assert_eq!(stdout(&output), csv);
+
+    let output = json(&["to-csv", "--columns", "zip,name", file], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "zip,name\r\n,\"Smith, J.\"\r\n007,\"say \"\"hi\"\"\"\r\n"
+    );
+
+    let output = json(&["from-csv", "--minify", "--sort-keys"], csv);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "[{\"age\":40,\"city.name\":\"NY\",\"name\":\"Smith, J.\"},{\"name\":\"say \\\"hi\\\"\",\"zip\":\"007\"}]\n"
+    );
+
+    for (input, message) in [
+        (r#"[{"a": 1}, [2]]"#, "json: row 1 is not an object\n"),
+        (
+            r#"[{"a": {"b": 1}}]"#,
+            "json: row 0 has a nested value at \"a\"; enable flatten\n",
+        ),
+        ("{}", "json: CSV conversion needs an array of objects\n"),
+    ] {
+        let output = json(&["to-csv"], input);
+        assert_eq!(output.status.code(), Some(1), "{}", input);
+        assert_eq!(stderr(&output), message);
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cli_stats_should_count_without_building_the_document() {
+    let doc = r#"{"name": "Zoë", "tags": ["a", "bb"], "n": [1, 2.5, null], "ok": true, "nested": {"name": {}}}"#;
+    let path = fixture("stats.json", doc);
+    let output = json(&["stats", "--json", path.to_str().unwrap()], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let expected = format!(
+        r#"{{
+  "arrays": 2,
+  "bools": 1,
+  "distinct_keys": 5,
+  "elements": 5,
+  "longest_string": 4,
+  "max_depth": 3,
+  "members": 6,
+  "nulls": 1,
+  "numbers": 2,
+  "objects": 3,
+  "string_bytes": 7,
+  "strings": 3,
+  "total_bytes": {},
+  "values": 12
+}}
+"#,
+        doc.len()
+    );
+    assert_eq!(stdout(&output), expected);
+    fs::remove_file(path).unwrap();
+
+    let output = json(&["stats"], "[1, [2]]");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("\nmax depth                  2\n"),
+        "{}",
+        stdout(&output)
+    );
+
+    // about 6 MB
+    let large = format!(
+        "[{}]",
+        vec![r#"{"id": 12345, "tags": ["x", "y"], "text": "lorem ipsum"}"#; 100_000].join(",")
+    );
+    let path = fixture("stats-large.json", &large);
+    let output = json(&["stats", "--json", path.to_str().unwrap()], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("\"objects\": 100000,"),
+        "{}",
+        stdout(&output)
+    );
+    fs::remove_file(path).unwrap();
+
+    let output = json(&["stats"], "[1, x]");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "json: <stdin>: unexpected character 'x' at offset 4\n"
+    );
+}
+
+#[test]
+fn cli_strip_comments_should_keep_the_layout() {
+    let tsconfig = "{\n  // compiler settings\n  \"compilerOptions\": {\n    \"target\": \"es2020\", /* was es5 */\n    \"paths\": {\"@/*\": [\"src/*\"]}\n  }\n  /* trailing\n     note */\n}\n";
+    let blank = |n| " ".repeat(n);
+    let golden = format!(
+        "{{\n{}\n  \"compilerOptions\": {{\n    \"target\": \"es2020\",{}\n    \"paths\": {{\"@/*\": [\"src/*\"]}}\n  }}\n{}\n{}\n}}\n",
+        blank(22),
+        blank(14),
+        blank(13),
+        blank(12)
+    );
+    let output = json(&["strip-comments"], tsconfig);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), golden);
+
+    let output = json(&["strip-comments", "--minify"], tsconfig);
+    assert_eq!(
+        stdout(&output),
+        "{\"compilerOptions\":{\"target\":\"es2020\",\"paths\":{\"@/*\":[\"src/*\"]}}}\n"
+    );
+
+    let path = fixture("tsconfig.json", tsconfig);
+    for _ in 0..2 {
+        let output = json(&["strip-comments", "--write", path.to_str().unwrap()], "");
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert!(output.stdout.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), golden);
+    }
+    fs::remove_file(path).unwrap();
+
+    for (input, message) in [
+        (
+            "[1, /* open\n 2]",
+            "json: <stdin>:1:5: unterminated block comment\n",
+        ),
+        (
+            "// one\n[1,]",
+            "json: <stdin>:2:3: trailing commas are not allowed; enable allow_trailing_commas\n",
+        ),
+    ] {
+        let output = json(&["strip-comments"], input);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(stderr(&output), message);
+    }
+}
+
+#[test]
+fn cli_should_color_pretty_output_when_asked() {
+    let doc = r#"{"a": [1, "x", null]}"#;
+    let output = json(&["--color", "always"], doc);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let colored = stdout(&output);
+    assert!(colored.contains("\x1b[1;34m\"a\"\x1b[0m"), "{:?}", colored);
+    assert!(colored.contains("\x1b[36m1\x1b[0m"), "{:?}", colored);
+    assert!(colored.ends_with("\n"));
+
+    // piped, so auto means no color; NO_COLOR does not override always
+    for args in [&["--color", "never"][..], &["--color", "auto"], &[]] {
+        let output = json(args, doc);
+        assert_eq!(
+            stdout(&output),
+            "{\n  \"a\": [\n    1,\n    \"x\",\n    null\n  ]\n}\n"
+        );
+    }
+    let path = fixture("color.json", doc);
+    let output = Command::new(env!("CARGO_BIN_EXE_json"))
+        .args(["--color", "always", path.to_str().unwrap()])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert_eq!(stdout(&output), colored);
+    fs::remove_file(path).unwrap();
+    // compact output has no colors to give
+    let output = json(&["--color", "always", "--minify"], doc);
+    assert_eq!(stdout(&output), "{\"a\":[1,\"x\",null]}\n");
+    let output = json(&["merge", "--color", "always", "-"], doc);
+    assert!(stdout(&output).contains("\x1b["), "{:?}", stdout(&output));
+    let output = json(&["--color", "sometimes"], doc);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_schema_should_infer_and_check() {
+    let a = fixture("schema-a.json", r#"{"name": "a", "tags": ["x"], "n": 1}"#);
+    let b = fixture("schema-b.json", r#"{"name": "b", "n": 2.5, "extra": null}"#);
+    let output = json(
+        &["schema", "infer", a.to_str().unwrap(), b.to_str().unwrap()],
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let expected = r#"{
+  "properties": {
+    "extra": {
+      "type": "null"
+    },
+    "n": {
+      "type": "number"
+    },
+    "name": {
+      "type": "string"
+    },
+    "tags": {
+      "items": {
+        "type": "string"
+      },
+      "type": "array"
+    }
+  },
+  "required": [
+    "n",
+    "name"
+  ],
+  "type": "object"
+}
+"#;
+    assert_eq!(stdout(&output), expected);
+
+    let schema = fixture("schema.json", expected);
+    let output = json(
+        &[
+            "schema",
+            "check",
+            "--schema",
+            schema.to_str().unwrap(),
+            a.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+
+    let output = json(
+        &[
+            "schema",
+            "check",
+            "--schema",
+            schema.to_str().unwrap(),
+            a.to_str().unwrap(),
+            "-",
+        ],
+        r#"{"name": "c", "tags": ["x", 3]}"#,
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "<stdin>: (root): missing property \"n\"\n<stdin>: /tags/1: expected string, got number\n"
+    );
+    for path in [a, b, schema] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn cli_set_should_put_values_at_pointers() {
+    let doc = r#"{"spec": {"replicas": 1, "ports": [80]}, "name": "web"}"#;
+    let set = |args: &[&str]| {
+        let mut all = vec!["set", "--minify", "--sort-keys", "-"];
+        all.extend_from_slice(args);
+        json(&all, doc)
+    };
+    let output = set(&["/spec/replicas", "5"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "{\"name\":\"web\",\"spec\":{\"ports\":[80],\"replicas\":5}}\n"
+    );
+    let output = set(&["/spec/ports/-", r#"{"tls": true}"#]);
+    assert_eq!(
+        stdout(&output),
+        "{\"name\":\"web\",\"spec\":{\"ports\":[80,{\"tls\":true}],\"replicas\":1}}\n"
+    );
+
+    // the value is JSON unless --string says otherwise
+    let output = set(&["/name", "true"]);
+    assert_eq!(
+        stdout(&output),
+        "{\"name\":true,\"spec\":{\"ports\":[80],\"replicas\":1}}\n"
+    );
+    let output = set(&["--string", "/name", "true"]);
+    assert_eq!(
+        stdout(&output),
+        "{\"name\":\"true\",\"spec\":{\"ports\":[80],\"replicas\":1}}\n"
+    );
+    let output = set(&["/name", "api"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("use --string"),
+        "{}",
+        stderr(&output)
+    );
+
+    // missing parents only with --create-parents
+    let output = set(&["/metadata/labels/app", "\"web\""]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "json: cannot set /metadata/labels/app: /metadata/labels does not exist; --create-parents makes it\n"
+    );
+    let output = set(&["--create-parents", "/metadata/labels/app", "\"web\""]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "{\"metadata\":{\"labels\":{\"app\":\"web\"}},\"name\":\"web\",\"spec\":{\"ports\":[80],\"replicas\":1}}\n"
+    );
+
+    // type conflicts name the pointer
+    for (pointer, message) in [
+        (
+            "/name/first",
+            "cannot set /name/first: /name is a string, not a container",
+        ),
+        (
+            "/spec/ports/3",
+            "cannot set /spec/ports/3: /spec/ports is an array of 1, with no index \"3\"",
+        ),
+        (
+            "/spec/replicas/x",
+            "cannot set /spec/replicas/x: /spec/replicas is a number, not a container",
+        ),
+    ] {
+        let output = set(&["--create-parents", pointer, "1"]);
+        assert_eq!(output.status.code(), Some(1), "{}", pointer);
+        assert!(stderr(&output).contains(message), "{}", stderr(&output));
+        assert!(output.stdout.is_empty());
+    }
+    let output = set(&["spec", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("invalid pointer \"spec\""),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn cli_delete_should_remove_members_and_elements() {
+    let doc = r#"{"metadata": {"annotations": {"foo": 1, "bar": 2}}, "list": [1, 2, 3]}"#;
+    let delete = |pointer: &str| json(&["delete", "--minify", "--sort-keys", "-", pointer], doc);
+    let output = delete("/metadata/annotations/foo");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "{\"list\":[1,2,3],\"metadata\":{\"annotations\":{\"bar\":2}}}\n"
+    );
+    let output = delete("/list/1");
+    assert_eq!(
+        stdout(&output),
+        "{\"list\":[1,3],\"metadata\":{\"annotations\":{\"bar\":2,\"foo\":1}}}\n"
+    );
+    let output = delete("/metadata/missing");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        "json: cannot delete /metadata/missing: nothing there\n"
+    );
+    let output = delete("");
+    assert_eq!(output.status.code(), Some(2));
+    let output = json(&["delete", "--string", "-", "/list"], doc);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_set_and_delete_should_rewrite_files_whole() {
+    let path = fixture(
+        "edit.json",
+        r#"{"z": 1, "m": [{"y": 2, "b": 3}, {"x": 4, "c": 5}], "a": 1}"#,
+    );
+    let name = path.to_str().unwrap();
+    let output = json(&["set", "--write", name, "/b", "[true]"], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    let written = fs::read_to_string(&path).unwrap();
+    let reparsed = json(&["get", "/b/0", name], "");
+    assert_eq!(stdout(&reparsed), "true\n", "{}", written);
+    // the members stay where they were, the new one after them
+    let expected = r#"{
+  "z": 1,
+  "m": [
+    {
+      "y": 2,
+      "b": 3
+    },
+    {
+      "x": 4,
+      "c": 5
+    }
+  ],
+  "a": 1,
+  "b": [
+    true
+  ]
+}
+"#;
+    assert_eq!(written, expected);
+
+    let output = json(&["delete", "--write", "--minify", name, "/m/0"], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "{\"z\":1,\"m\":[{\"x\":4,\"c\":5}],\"a\":1,\"b\":[true]}\n"
+    );
+    let output = json(
+        &[
+            "set",
+            "--write",
+            "--minify",
+            name,
+            "/m/0/c",
+            "{\"q\":1,\"p\":2}",
+        ],
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "{\"z\":1,\"m\":[{\"x\":4,\"c\":{\"p\":2,\"q\":1}}],\"a\":1,\"b\":[true]}\n"
+    );
+
+    // a failed edit leaves the file as it was, and no temporary file beside it
+    let before = fs::read(&path).unwrap();
+    let output = json(&["set", "--write", name, "/a/b", "2"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read(&path).unwrap(), before);
+    let output = json(&["delete", "--write", name, "/zzz"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read(&path).unwrap(), before);
+    let dir = path.parent().unwrap();
+    let prefix = format!(".{}", path.file_name().unwrap().to_str().unwrap());
+    let leftovers = fs::read_dir(dir)
+        .unwrap()
+        .filter(|entry| {
+            let entry = entry.as_ref().unwrap().file_name();
+            entry.to_str().is_some_and(|name| name.starts_with(&prefix))
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+
+    let output = json(&["set", "--write", "-", "/a", "1"], "{}");
+    assert_eq!(output.status.code(), Some(2));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cli_flatten_and_unflatten_should_round_trip() {
+    let nested = r#"{"db": {"host": "h", "ports": [1, 2]}, "tags": [], "meta": {"a.b": null}}"#;
+    let path = fixture("nested.json", nested);
+    let name = path.to_str().unwrap();
+    let output = json(&["flatten", "--sort-keys", "--minify", name], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "{\"db.host\":\"h\",\"db.ports.0\":1,\"db.ports.1\":2,\"meta.a.b\":null,\"tags\":[]}\n"
+    );
+
+    // with a separator the keys never hold, the document comes back whole
+    let output = json(&["flatten", "--sep", "/", "--bracket-arrays", name], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let flat = stdout(&output).to_string();
+    assert!(flat.contains("\"db/ports[1]\": 2"), "{}", flat);
+    let output = json(
+        &[
+            "unflatten",
+            "--sep",
+            "/",
+            "--bracket-arrays",
+            "--sort-keys",
+            "--minify",
+        ],
+        &flat,
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    let expected = json(&["--sort-keys", "--minify", name], "");
+    assert_eq!(stdout(&output), stdout(&expected));
+    fs::remove_file(path).unwrap();
+
+    let output = json(
+        &["unflatten", "--minify", "-"],
+        r#"{"a.0": 1, "a.1": {"b": 2}}"#,
+    );
+    assert_eq!(stdout(&output), "{\"a\":[1,{\"b\":2}]}\n");
+    let output = json(&["flatten", "--sep", ""], "{}");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_unflatten_should_report_conflicting_paths() {
+    let output = json(&["unflatten"], r#"{"a.b": 1, "a.b.c": 2}"#);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(
+        stderr(&output).contains(r#""/a/b": "a.b.c" runs through a value"#),
+        "{}",
+        stderr(&output)
+    );
+    let output = json(&["unflatten"], "[1]");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn cli_paths_should_list_every_leaf_in_key_order() {
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let sample = golden.join("paths.json");
+    let sample = sample.to_str().unwrap();
+    let output = json(&["paths", sample], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        fs::read_to_string(golden.join("paths.txt")).unwrap()
+    );
+    let output = json(&["paths", "--types", "--max-depth", "1", sample], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        concat!(
+            "/a~1b\tobject\n",
+            "/address\tobject\n",
+            "/born\tnumber\n",
+            "/extra\tobject\n",
+            "/languages\tarray\n",
+            "/name\tstring\n",
+            "/notes\tarray\n",
+        )
+    );
+    let output = json(
+        &["paths", "--max-depth", "1"],
+        r#"{"a": [1, [2]], "b": {"c": 1}, "d": 0}"#,
+    );
+    assert_eq!(stdout(&output), "/a\t[2 elements]\n/b\t{1 member}\n/d\t0\n");
+    assert_eq!(stdout(&json(&["paths"], r#""s""#)), "\t\"s\"\n");
+    let output = json(&["paths", "--max-depth", "x"], "1");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn cli_should_read_gzip_input_like_plain_input() {
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let plain = golden.join("items.json");
+    let gzip = golden.join("items.json.gz");
+    for args in [
+        &["--sort-keys"][..],
+        &["--sort-keys", "--minify"],
+        &["get", "/599/name"],
+        &["fmt", "--sort-keys"],
+    ] {
+        let expected = json(&[args, &[plain.to_str().unwrap()]].concat(), "");
+        let output = json(&[args, &[gzip.to_str().unwrap()]].concat(), "");
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(output.stdout, expected.stdout, "{:?}", args);
+    }
+    let output = json(&["stats", gzip.to_str().unwrap()], "");
+    assert!(output.status.success(), "{}", stderr(&output));
+    // members come out in whatever order the map keeps them
+    let elements = |path: &PathBuf| {
+        let output = json(&["array2seq", path.to_str().unwrap()], "");
+        assert!(output.status.success(), "{}", stderr(&output));
+        let lines = stdout(&output).lines();
+        lines
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect::<Vec<serde_json::Value>>()
+    };
+    assert_eq!(elements(&gzip), elements(&plain));
+
+    // formatting in place would write plain JSON over the compressed file
+    let bytes = fs::read(&gzip).unwrap();
+    let path = fixture("write.json.gz", "");
+    fs::write(&path, &bytes).unwrap();
+    let name = path.to_str().unwrap();
+    let output = json(&["fmt", "--write", "--minify", name], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "json: {}: cannot rewrite gzip-compressed input in place\n",
+            name
+        )
+    );
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+
+    // a cut-off stream fails naming the file, leaving nothing half printed
+    let path = fixture("cut.json.gz", "");
+    fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+    let name = path.to_str().unwrap();
+    let output = json(&[name], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "json: {}: I/O error: corrupt gzip stream: the stream ends early\n",
+            name
+        )
+    );
+    let mut flipped = bytes.clone();
+    let crc = flipped.len() - 8;
+    flipped[crc] ^= 0xff;
+    fs::write(&path, &flipped).unwrap();
+    let output = json(&["validate", name], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr(&output).contains("the CRC-32 does not match"),
+        "{}",
+        stderr(&output)
+    );
+
+    // editing in place would write the document back uncompressed
+    fs::write(&path, &bytes).unwrap();
+    let output = json(&["set", "--write", name, "/0/id", "1"], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr(&output).contains("gzip-compressed"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cli_ndjson_should_filter_records_line_by_line() {
+    let log = concat!(
+        "{\"level\": \"error\", \"msg\": \"disk full\"}\n",
+        "{\"level\": \"info\", \"msg\": \"started\"}\n",
+        "\n",
+        "{\"level\": \"error\", \"msg\": \"disk\n",
+        "{\"msg\": \"no level\"}\n",
+        "[\"not\", \"a\", \"record\"]\n",
+        "{\"level\": \"error\", \"msg\": \"retrying\", \"attempt\": 2}\r\n",
+    );
+    let path = fixture("log.ndjson", log);
+    let name = path.to_str().unwrap();
+
+    // processing stops at a bad line, after what came before it
+    let output = json(
+        &[
+            "ndjson",
+            "--sort-keys",
+            "--get",
+            "/level",
+            "--match",
+            "error",
+            name,
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "{\"level\":\"error\",\"msg\":\"disk full\"}\n"
+    );
+    assert_eq!(
+        stderr(&output),
+        format!("json: {}:4:32: unexpected end of input\n", name)
+    );
+
+    let output = json(
+        &[
+            "ndjson",
+            "--skip-invalid",
+            "--sort-keys",
+            "--get",
+            "/level",
+            "--match",
+            "error",
+            name,
+        ],
+        "",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        concat!(
+            "{\"level\":\"error\",\"msg\":\"disk full\"}\n",
+            "{\"attempt\":2,\"level\":\"error\",\"msg\":\"retrying\"}\n",
+        )
+    );
+    assert_eq!(
+        stderr(&output),
+        format!("json: {}:4:32: unexpected end of input\n", name)
+    );
+
+    let output = json(
+        &["ndjson", "--skip-invalid", "--get", "/msg", "--values", "-"],
+        log,
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "\"disk full\"\n\"started\"\n\"no level\"\n\"retrying\"\n"
+    );
+    assert!(stderr(&output).starts_with("json: <stdin>:4:"));
+
+    // --match reads JSON first, so numbers match numbers
+    let output = json(
+        &[
+            "ndjson",
+            "--skip-invalid",
+            "--get",
+            "/attempt",
+            "--match",
+            "2",
+            "--values",
+        ],
+        log,
+    );
+    assert_eq!(stdout(&output), "2\n");
+    let output = json(&["ndjson", "--match", "error"], log);
+    assert_eq!(output.status.code(), Some(2));
+    fs::remove_file(path).unwrap();
+}
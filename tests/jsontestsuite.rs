@@ -0,0 +1,84 @@
+use grammar::json::{parse_json, parse_json_bytes, validate, JsonError};
+use std::{collections::HashMap, fs, path::Path};
+
+/// Every document of the suite with its name, sorted.
+fn cases() -> Vec<(String, Vec<u8>)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/jsontestsuite");
+    let mut cases: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "json"))
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+            (name, fs::read(&path).unwrap())
+        })
+        .collect();
+    cases.sort();
+    cases
+}
+
+/// `bytes` through every strict entry point, which must agree.
+fn parse(name: &str, bytes: &[u8]) -> Result<(), JsonError> {
+    let from_bytes = parse_json_bytes(bytes).map(drop);
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        let from_str = parse_json(text).map(drop);
+        assert_eq!(
+            from_str.is_ok(),
+            from_bytes.is_ok(),
+            "{}: {:?}",
+            name,
+            from_str
+        );
+        assert_eq!(validate(text).is_ok(), from_bytes.is_ok(), "{}", name);
+    }
+    from_bytes
+}
+
+#[test]
+fn y_cases_should_parse() {
+    let mut seen = 0;
+    for (name, bytes) in cases().iter().filter(|(n, _)| n.starts_with("y_")) {
+        if let Err(e) = parse(name, bytes) {
+            panic!("{}: {}", name, e);
+        }
+        seen += 1;
+    }
+    assert!(seen > 0, "no y_ cases found");
+}
+
+#[test]
+fn n_cases_should_be_rejected() {
+    let mut seen = 0;
+    for (name, bytes) in cases().iter().filter(|(n, _)| n.starts_with("n_")) {
+        assert!(parse(name, bytes).is_err(), "{} parsed", name);
+        seen += 1;
+    }
+    assert!(seen > 0, "no n_ cases found");
+}
+
+#[test]
+fn i_cases_should_match_the_decision_table() {
+    let table = include_str!("jsontestsuite/i_decisions.tsv");
+    let decisions: HashMap<&str, (&str, &str)> = table
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.is_empty())
+        .map(|line| {
+            let fields: Vec<_> = line.split('\t').collect();
+            assert_eq!(fields.len(), 4, "malformed line {:?}", line);
+            (fields[0], (fields[1], fields[2]))
+        })
+        .collect();
+    let mut seen = 0;
+    for (name, bytes) in cases().iter().filter(|(n, _)| n.starts_with("i_")) {
+        let Some(&(decision, code)) = decisions.get(name.as_str()) else {
+            panic!("{} has no line in i_decisions.tsv", name);
+        };
+        match (decision, parse(name, bytes)) {
+            ("accept", Ok(())) => {}
+            ("reject", Err(e)) => assert_eq!(e.code().as_str(), code, "{}: {}", name, e),
+            (decision, got) => panic!("{} should {}, got {:?}", name, decision, got),
+        }
+        seen += 1;
+    }
+    assert_eq!(seen, decisions.len(), "i_decisions.tsv names missing files");
+}